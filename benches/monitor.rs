@@ -0,0 +1,94 @@
+//! Benchmarks of the per-cycle hot paths. Run with `cargo bench`; the
+//! container counts match a busy host so regressions show up before they
+//! reach small VPSes.
+
+use chrono::{Duration, Utc};
+use std::sync::Arc;
+use criterion::{black_box, criterion_group, criterion_main, BatchSize, Criterion};
+use performance_monitor::alert::Alert;
+use performance_monitor::config::{Config, MemoryLeakConfig, TrendRuleConfig};
+use performance_monitor::docker_monitor::ContainerStats;
+use performance_monitor::email_notifier::EmailNotifier;
+use performance_monitor::leak::LeakDetector;
+use performance_monitor::metrics::RuntimeMetricsSnapshot;
+use performance_monitor::server_monitor::ServerMonitor;
+use performance_monitor::trend::{self, TrendSamples};
+
+const CONTAINERS: usize = 300;
+/// Samples per series, a 4h window at a 2 minute interval.
+const SAMPLES: i64 = 120;
+
+fn containers(count: usize) -> Vec<ContainerStats> {
+    let alert: Alert = serde_json::from_str(include_str!("../tests/fixtures/render/alerts/container_cpu.json")).unwrap();
+    let template = alert.containers[0].clone();
+    (0..count)
+        .map(|i| ContainerStats {
+            id: format!("{:064x}", i),
+            name: format!("app-{}", i),
+            memory_usage: 256 * 1024 * 1024 + i as u64 * 1024 * 1024,
+            cpu_usage: (i % 100) as f64,
+            ..template.clone()
+        })
+        .collect()
+}
+
+fn samples(containers: &[ContainerStats]) -> TrendSamples {
+    let now = Utc::now();
+    let mut samples = TrendSamples::default();
+    for step in (0..SAMPLES).rev() {
+        let at = now - Duration::minutes(step * 2);
+        trend::record_containers(&mut samples, containers, at, |_| true);
+    }
+    samples
+}
+
+fn stats_collection(c: &mut Criterion) {
+    let mut monitor = ServerMonitor::new(Arc::new(Config::default()));
+    c.bench_function("server_stats/full", |b| b.iter(|| black_box(monitor.get_full_stats())));
+}
+
+fn rule_evaluation(c: &mut Criterion) {
+    let containers = containers(CONTAINERS);
+    let samples = samples(&containers);
+    let now = Utc::now();
+    
+    c.bench_function("trend/record", |b| {
+        b.iter_batched(
+            || samples.clone(),
+            |mut samples| trend::record_containers(&mut samples, &containers, now, |_| true),
+            BatchSize::LargeInput,
+        )
+    });
+    
+    let rules = trend::load_rules(&[TrendRuleConfig {
+        name: "memory-growth".to_string(),
+        metric: "container_memory".to_string(),
+        containers: Vec::new(),
+        condition: "rate".to_string(),
+        threshold: 50.0,
+        window: "4h".to_string(),
+        severity: "warning".to_string(),
+    }]);
+    c.bench_function("trend/evaluate", |b| b.iter(|| black_box(trend::evaluate(&rules, &samples, now))));
+    
+    let detector = LeakDetector::new(&MemoryLeakConfig {
+        enabled: true,
+        window: "4h".to_string(),
+        ..MemoryLeakConfig::default()
+    })
+    .unwrap();
+    c.bench_function("leak/detect", |b| b.iter(|| black_box(detector.detect(&samples, &containers, now))));
+}
+
+fn rendering(c: &mut Criterion) {
+    let mut alert: Alert = serde_json::from_str(include_str!("../tests/fixtures/render/alerts/container_cpu.json")).unwrap();
+    alert.containers = containers(CONTAINERS);
+    let email = EmailNotifier::new(&Config::default());
+    c.bench_function("render/email_container_cpu", |b| b.iter(|| black_box(email.render_rule_alert(&alert))));
+    
+    let metrics = RuntimeMetricsSnapshot::default();
+    c.bench_function("render/prometheus", |b| b.iter(|| black_box(metrics.to_prometheus())));
+}
+
+criterion_group!(benches, stats_collection, rule_evaluation, rendering);
+criterion_main!(benches);
\ No newline at end of file
@@ -0,0 +1,14 @@
+fn main() {
+    #[cfg(feature = "grpc")]
+    {
+        // Use the bundled protoc so building needs no system install
+        if std::env::var_os("PROTOC").is_none() {
+            let protoc = protoc_bin_vendored::protoc_bin_path().expect("bundled protoc is available");
+            std::env::set_var("PROTOC", protoc);
+        }
+        tonic_prost_build::compile_protos("proto/monitor.proto").expect("proto/monitor.proto compiles");
+    }
+    println!("cargo:rerun-if-changed=proto/monitor.proto");
+    // Picks the release archive `self-update` downloads
+    println!("cargo:rustc-env=BUILD_TARGET={}", std::env::var("TARGET").unwrap_or_default());
+}
\ No newline at end of file
@@ -82,7 +82,7 @@ create_backup() {
         print_status "Creating backup of current deployment..."
         
         # Backup configuration files
-        cp -r "$DEPLOY_DIR/config.json" "$BACKUP_PATH/" 2>/dev/null || true
+        cp -r "$DEPLOY_DIR/config" "$BACKUP_PATH/" 2>/dev/null || true
         cp -r "$DEPLOY_DIR/docker-compose.yml" "$BACKUP_PATH/" 2>/dev/null || true
         cp -r "$DEPLOY_DIR/logs" "$BACKUP_PATH/" 2>/dev/null || true
         
@@ -118,11 +118,21 @@ download_files() {
     print_status "Downloading docker-compose.yml..."
     curl -o docker-compose.yml "https://raw.githubusercontent.com/$REPO/main/docker-compose.yml"
     
+    # The config lives in config/ so the container can replace it atomically
+    mkdir -p config
+    if [ -f "config.json" ] && [ ! -f "config/config.json" ]; then
+        print_status "Moving config.json to config/config.json..."
+        mv config.json config/config.json
+    fi
+    
     # Download config template if not exists
-    if [ ! -f "config.json" ]; then
+    if [ ! -f "config/config.json" ]; then
         print_status "Downloading config.json template..."
-        curl -o config.json "https://raw.githubusercontent.com/$REPO/main/config.json"
-        print_warning "Please edit config.json with your settings before starting the service"
+        curl -o config/config.json "https://raw.githubusercontent.com/$REPO/main/config.json"
+        print_warning "Please edit config/config.json with your settings before starting the service"
+    fi
+    if ! chown -R 10001 config 2>/dev/null; then
+        print_warning "Run 'sudo chown -R 10001 $DEPLOY_DIR/config' so set-threshold --persist can write the config"
     fi
     
     print_status "✅ Files downloaded"
@@ -226,7 +236,7 @@ show_deployment_info() {
     echo "  Stop services: docker-compose -f $DEPLOY_DIR/docker-compose.yml down"
     echo "  Restart services: docker-compose -f $DEPLOY_DIR/docker-compose.yml restart"
     echo ""
-    echo "Configuration file: $DEPLOY_DIR/config.json"
+    echo "Configuration file: $DEPLOY_DIR/config/config.json"
     echo "Log files: $DEPLOY_DIR/logs/"
 }
 
@@ -243,7 +243,7 @@ BACKUP_NAME="backup-$DATE"
 mkdir -p "$BACKUP_DIR/$BACKUP_NAME"
 
 # Backup configuration
-cp -r "$HOME/performance-monitor/config.json" "$BACKUP_DIR/$BACKUP_NAME/" 2>/dev/null || true
+cp -r "$HOME/performance-monitor/config" "$BACKUP_DIR/$BACKUP_NAME/" 2>/dev/null || true
 cp -r "$HOME/performance-monitor/docker-compose.yml" "$BACKUP_DIR/$BACKUP_NAME/" 2>/dev/null || true
 
 # Backup logs
@@ -303,7 +303,7 @@ show_next_steps() {
     echo "  Create backup: ./deploy.sh backup"
     echo ""
     echo "Configuration files:"
-    echo "  Config: $HOME/performance-monitor/config.json"
+    echo "  Config: $HOME/performance-monitor/config/config.json"
     echo "  Logs: $HOME/performance-monitor/logs/"
     echo "  Backups: $HOME/performance-monitor/backups/"
 }
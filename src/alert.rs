@@ -0,0 +1,323 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use crate::config::AlertRuleConfig;
+use crate::docker_monitor::ContainerStats;
+use crate::escalation::{Acknowledgement, EscalationStep};
+use crate::history::Comparison;
+use crate::incident::IncidentState;
+use crate::process_watchdog::ProcessStatus;
+use crate::server_monitor::ProcessUsage;
+
+pub const RULE_CPU: &str = "cpu";
+pub const RULE_HOT_CORE: &str = "hot_core";
+pub const RULE_MEMORY: &str = "memory";
+pub const RULE_SWAP: &str = "swap";
+pub const RULE_DISK: &str = "disk";
+pub const RULE_BANDWIDTH: &str = "bandwidth";
+pub const RULE_CONTAINER_CPU: &str = "container_cpu";
+pub const RULE_UNLIMITED_CONTAINER: &str = "unlimited_container";
+pub const RULE_NEWCOMER_CONTAINER: &str = "newcomer_container";
+pub const RULE_PROCESS: &str = "process";
+pub const RULE_DISK_LATENCY: &str = "disk_latency";
+pub const RULE_CONNTRACK: &str = "conntrack";
+pub const RULE_PING: &str = "ping";
+pub const RULE_FINGERPRINT: &str = "fingerprint";
+pub const RULE_IMAGE_DRIFT: &str = "image_drift";
+pub const RULE_GROUP_BUDGET: &str = "group_budget";
+pub const RULE_TREND: &str = "trend";
+pub const RULE_MEMORY_LEAK: &str = "memory_leak";
+pub const RULE_CPU_FREQUENCY: &str = "cpu_frequency";
+pub const RULE_UPS: &str = "ups";
+pub const RULE_VM: &str = "vm";
+pub const RULE_LXD_CONTAINER: &str = "lxd_container";
+pub const RULE_ZFS: &str = "zfs";
+pub const RULE_STORAGE_POOL: &str = "storage_pool";
+pub const RULE_COMMAND: &str = "command";
+pub const RULE_CRON: &str = "cron";
+pub const RULE_OUTBOUND: &str = "outbound_connection";
+
+/// Every built-in rule name, e.g. to tell a rule from a container name.
+pub const ALL_RULES: [&str; 27] = [
+    RULE_CPU, RULE_HOT_CORE, RULE_MEMORY, RULE_SWAP, RULE_DISK, RULE_BANDWIDTH, RULE_CONTAINER_CPU, RULE_UNLIMITED_CONTAINER, RULE_NEWCOMER_CONTAINER, RULE_PROCESS,
+    RULE_DISK_LATENCY, RULE_CONNTRACK, RULE_PING, RULE_FINGERPRINT, RULE_IMAGE_DRIFT, RULE_GROUP_BUDGET, RULE_TREND,
+    RULE_MEMORY_LEAK, RULE_CPU_FREQUENCY, RULE_UPS, RULE_VM, RULE_LXD_CONTAINER, RULE_ZFS,
+    RULE_STORAGE_POOL, RULE_COMMAND, RULE_CRON, RULE_OUTBOUND,
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Ok,
+    Warning,
+    Critical,
+}
+
+impl Severity {
+    /// Classifies a value against warning and critical thresholds.
+    pub fn for_value(value: f64, warning: f64, critical: f64) -> Self {
+        if value > critical {
+            Severity::Critical
+        } else if value > warning {
+            Severity::Warning
+        } else {
+            Severity::Ok
+        }
+    }
+    
+    /// Process exit code following the Nagios plugin convention.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Severity::Ok => 0,
+            Severity::Warning => 1,
+            Severity::Critical => 2,
+        }
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Ok => "OK",
+            Severity::Warning => "WARNING",
+            Severity::Critical => "CRITICAL",
+        }
+    }
+}
+
+/// Exit code for internal errors, matching Nagios' UNKNOWN state.
+pub const EXIT_UNKNOWN: i32 = 3;
+
+/// Result of one monitoring cycle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CheckOutcome {
+    pub severity: Severity,
+    pub problems: Vec<String>,
+    pub perfdata: Vec<String>,
+    /// Checks that failed to run, e.g. an unreachable Docker API.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub unknown: Vec<String>,
+}
+
+impl Default for CheckOutcome {
+    fn default() -> Self {
+        Self {
+            severity: Severity::Ok,
+            problems: Vec::new(),
+            perfdata: Vec::new(),
+            unknown: Vec::new(),
+        }
+    }
+}
+
+impl CheckOutcome {
+    pub fn add_problem(&mut self, severity: Severity, message: String) {
+        self.severity = self.severity.max(severity);
+        self.problems.push(message);
+    }
+    
+    pub fn add_unknown(&mut self, message: String) {
+        self.unknown.push(message);
+    }
+    
+    pub fn is_alerting(&self) -> bool {
+        self.severity != Severity::Ok
+    }
+    
+    /// A critical problem is reported as such, otherwise a check that failed
+    /// to run makes the result UNKNOWN.
+    fn is_unknown(&self) -> bool {
+        !self.unknown.is_empty() && self.severity != Severity::Critical
+    }
+    
+    pub fn exit_code(&self) -> i32 {
+        if self.is_unknown() {
+            EXIT_UNKNOWN
+        } else {
+            self.severity.exit_code()
+        }
+    }
+    
+    /// The problems and checks that failed to run, comma separated.
+    pub fn summary(&self) -> String {
+        let messages = [self.problems.as_slice(), self.unknown.as_slice()].concat();
+        if messages.is_empty() {
+            "All systems normal".to_string()
+        } else {
+            messages.join(", ")
+        }
+    }
+    
+    /// Single line plugin output: `PERFMON <STATE> - <summary> | <perfdata>`.
+    pub fn nagios_line(&self) -> String {
+        let state = if self.is_unknown() { "UNKNOWN" } else { self.severity.label() };
+        let summary = self.summary();
+        
+        if self.perfdata.is_empty() {
+            format!("PERFMON {} - {}", state, summary)
+        } else {
+            format!("PERFMON {} - {} | {}", state, summary, self.perfdata.join(" "))
+        }
+    }
+}
+
+/// A structured alert produced by a rule, rendered by every notification channel.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Alert {
+    pub rule: String,
+    pub severity: Severity,
+    pub summary: String,
+    /// Incident state the notification reports, `firing` for regular alerts.
+    #[serde(default)]
+    pub state: IncidentState,
+    pub value: Option<f64>,
+    /// Human readable threshold the rule evaluated, e.g. `80.00% (critical 95.00%)`.
+    pub threshold: String,
+    /// The sampling window the value was evaluated over.
+    pub window: String,
+    pub timestamp: DateTime<Utc>,
+    #[serde(default)]
+    pub containers: Vec<ContainerStats>,
+    #[serde(default)]
+    pub processes: Vec<ProcessStatus>,
+    /// Busiest host processes by CPU, then the largest by memory.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub top_processes: Vec<ProcessUsage>,
+    /// Extra context for the reader, e.g. a likely root cause.
+    #[serde(default)]
+    pub notes: Vec<String>,
+    /// Sampled values rendered as charts in the email, e.g. memory growth.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub charts: Vec<AlertChart>,
+    /// Full details of a truncated alert, attached to the email only.
+    #[serde(skip)]
+    pub attachment: Option<AlertAttachment>,
+    /// Escalation tiers notified so far about the incident.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub escalations: Vec<EscalationStep>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub acknowledged: Option<Acknowledgement>,
+    /// The same metric 1h, 24h and a week earlier.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub history: Vec<Comparison>,
+    /// What the rule means, from `alert_rules`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    /// Remediation docs for the rule, from `alert_rules`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runbook_url: Option<String>,
+}
+
+/// A series drawn as a 0 to `max` line chart.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertChart {
+    pub title: String,
+    pub unit: String,
+    pub max: f64,
+    pub values: Vec<f64>,
+}
+
+/// A file attached to the alert email.
+#[derive(Debug, Clone)]
+pub struct AlertAttachment {
+    pub filename: String,
+    pub content_type: String,
+    pub body: Vec<u8>,
+}
+
+impl Alert {
+    pub fn new(rule: &str, severity: Severity, summary: String, threshold: String, window: String) -> Self {
+        Self {
+            rule: rule.to_string(),
+            severity,
+            summary,
+            state: IncidentState::Firing,
+            value: None,
+            threshold,
+            window,
+            timestamp: Utc::now(),
+            containers: Vec::new(),
+            processes: Vec::new(),
+            top_processes: Vec::new(),
+            notes: Vec::new(),
+            charts: Vec::new(),
+            attachment: None,
+            escalations: Vec::new(),
+            acknowledged: None,
+            history: Vec::new(),
+            description: None,
+            runbook_url: None,
+        }
+    }
+    
+    pub fn with_value(mut self, value: f64) -> Self {
+        self.value = Some(value);
+        self
+    }
+    
+    pub fn with_containers(mut self, containers: Vec<ContainerStats>) -> Self {
+        self.containers = containers;
+        self
+    }
+    
+    pub fn with_processes(mut self, processes: Vec<ProcessStatus>) -> Self {
+        self.processes = processes;
+        self
+    }
+    
+    pub fn with_top_processes(mut self, top_processes: Vec<ProcessUsage>) -> Self {
+        self.top_processes = top_processes;
+        self
+    }
+    
+    pub fn with_notes(mut self, notes: Vec<String>) -> Self {
+        self.notes = notes;
+        self
+    }
+    
+    pub fn with_charts(mut self, charts: Vec<AlertChart>) -> Self {
+        self.charts = charts;
+        self
+    }
+    
+    pub fn with_state(mut self, state: IncidentState) -> Self {
+        self.state = state;
+        self
+    }
+    
+    /// Adds the description and runbook configured for the rule, if any.
+    pub fn with_rule_info(mut self, rule: Option<&AlertRuleConfig>) -> Self {
+        if let Some(rule) = rule {
+            self.description = rule.description.clone();
+            self.runbook_url = rule.runbook_url.clone();
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn failed_checks_are_unknown_unless_something_is_critical() {
+        let mut outcome = CheckOutcome::default();
+        outcome.add_unknown("unable to evaluate container_cpu".to_string());
+        assert_eq!(outcome.exit_code(), EXIT_UNKNOWN);
+        assert_eq!(outcome.nagios_line(), "PERFMON UNKNOWN - unable to evaluate container_cpu");
+        
+        outcome.add_problem(Severity::Warning, "CPU 86.40% (threshold 80%)".to_string());
+        assert_eq!(outcome.exit_code(), EXIT_UNKNOWN);
+        
+        outcome.add_problem(Severity::Critical, "1 watched processes down".to_string());
+        assert_eq!(outcome.exit_code(), 2);
+        assert!(outcome.nagios_line().starts_with("PERFMON CRITICAL - CPU 86.40%"));
+        assert_eq!(CheckOutcome::default().exit_code(), 0);
+    }
+    
+    #[test]
+    fn summary_lists_every_problem() {
+        let mut outcome = CheckOutcome::default();
+        assert_eq!(outcome.summary(), "All systems normal");
+        outcome.add_problem(Severity::Warning, "Disk / 91.00% used".to_string());
+        outcome.add_problem(Severity::Critical, "1 watched processes down".to_string());
+        assert_eq!(outcome.summary(), "Disk / 91.00% used, 1 watched processes down");
+    }
+}
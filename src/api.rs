@@ -0,0 +1,349 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use tokio::sync::broadcast;
+use crate::alert::Alert;
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::config::HttpConfig;
+use crate::docker_monitor::ContainerStats;
+use crate::escalation::Acknowledgement;
+use crate::silence::Silences;
+use crate::thresholds::Thresholds;
+use crate::server_monitor::ServerStats;
+use log::warn;
+
+/// Alerts buffered for slow stream subscribers before they start missing some.
+const ALERT_FEED_CAPACITY: usize = 64;
+
+/// What a token may do. Viewers read the dashboard and API, admins can also
+/// silence alerts and change state.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    Viewer,
+    Admin,
+}
+
+impl Role {
+    pub fn parse(role: &str) -> Option<Self> {
+        match role.to_lowercase().as_str() {
+            "viewer" => Some(Role::Viewer),
+            "admin" => Some(Role::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// A signed command email that was run, kept until its signing time falls
+/// out of `email_commands.max_age` and a copy would be refused anyway.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandledCommand {
+    /// SHA-256 of the signed content and signing time, hex encoded.
+    pub digest: String,
+    pub signed_at: DateTime<Utc>,
+}
+
+/// Who made an authorized call.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Identity {
+    /// The `http.users` name, empty for anonymous reads.
+    pub name: String,
+    pub role: Role,
+}
+
+/// Why a call was refused.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Denied {
+    /// No admin token is configured, so writes are off.
+    Disabled,
+    /// The `Authorization` token is missing or wrong.
+    InvalidToken,
+    /// The token is valid but its role is too low for the call.
+    Forbidden,
+}
+
+impl Denied {
+    pub fn message(&self) -> &'static str {
+        match self {
+            Denied::Disabled => "Write calls are disabled until http.api_token or an admin in http.users is set",
+            Denied::InvalidToken => "Missing or invalid API token",
+            Denied::Forbidden => "This call needs the admin role",
+        }
+    }
+}
+
+struct Account {
+    name: String,
+    token: String,
+    role: Role,
+}
+
+/// Latest cycle results, the live alert feed, silences, threshold changes and
+/// acknowledgements, shared between the monitor loop and the REST and gRPC
+/// APIs.
+pub struct ApiState {
+    server: Mutex<Option<ServerStats>>,
+    containers: Mutex<Vec<ContainerStats>>,
+    firing: Mutex<Vec<String>>,
+    acknowledgements: Mutex<HashMap<String, Acknowledgement>>,
+    alerts: broadcast::Sender<Alert>,
+    accounts: Vec<Account>,
+    /// Reads need a token too once `http.users` is set.
+    protect_reads: bool,
+    /// The leader while this replica stands by, when changes must go there.
+    standby: Mutex<Option<String>>,
+    /// Signing times of the command emails already run, by digest.
+    handled_commands: Mutex<HashMap<String, DateTime<Utc>>>,
+    pub silences: Silences,
+    pub thresholds: Thresholds,
+}
+
+impl ApiState {
+    pub fn new(config: &HttpConfig) -> Self {
+        let mut accounts: Vec<Account> = config.api_token.iter()
+            .filter(|token| !token.is_empty())
+            .map(|token| Account { name: "admin".to_string(), token: token.clone(), role: Role::Admin })
+            .collect();
+        for user in config.users.iter().filter(|user| !user.token.is_empty()) {
+            // An unknown role gets the least privilege rather than admin
+            let role = Role::parse(&user.role).unwrap_or_else(|| {
+                warn!("Unknown role '{}' for HTTP user {}, treating it as viewer", user.role, user.name);
+                Role::Viewer
+            });
+            accounts.push(Account { name: user.name.clone(), token: user.token.clone(), role });
+        }
+        
+        Self {
+            server: Mutex::new(None),
+            containers: Mutex::new(Vec::new()),
+            firing: Mutex::new(Vec::new()),
+            acknowledgements: Mutex::new(HashMap::new()),
+            alerts: broadcast::channel(ALERT_FEED_CAPACITY).0,
+            accounts,
+            protect_reads: !config.users.is_empty(),
+            standby: Mutex::new(None),
+            handled_commands: Mutex::new(HashMap::new()),
+            silences: Silences::default(),
+            thresholds: Thresholds::default(),
+        }
+    }
+    
+    /// Checks the `Authorization` header value of a call needing `required`.
+    /// Accepts `Bearer <token>` and, so browsers can log in to the dashboard,
+    /// `Basic` credentials with the token as password.
+    pub fn authorize(&self, authorization: Option<&str>, required: Role) -> Result<Identity, Denied> {
+        if required == Role::Viewer && !self.protect_reads {
+            return Ok(Identity { name: String::new(), role: Role::Viewer });
+        }
+        if !self.accounts.iter().any(|account| account.role >= required) {
+            return Err(Denied::Disabled);
+        }
+        
+        let given = authorization.and_then(presented_token).ok_or(Denied::InvalidToken)?;
+        let account = self.accounts.iter()
+            .find(|account| account.token.len() == given.len() && openssl::memcmp::eq(account.token.as_bytes(), given.as_bytes()))
+            .ok_or(Denied::InvalidToken)?;
+        if account.role < required {
+            return Err(Denied::Forbidden);
+        }
+        Ok(Identity { name: account.name.clone(), role: account.role })
+    }
+    
+    /// `Some(leader)` while this replica stands by, `None` once it leads.
+    pub fn set_standby(&self, leader: Option<String>) {
+        *self.standby.lock().unwrap() = leader;
+    }
+    
+    /// Why a silence, acknowledgement or threshold change is refused here:
+    /// a standby does not save its state, so the change would be lost.
+    pub fn refuse_changes(&self) -> Option<String> {
+        self.standby.lock().unwrap().as_ref()
+            .map(|leader| format!("This replica is standing by, send changes to the leader {}", leader))
+    }
+    
+    /// Restores the digests of command emails run before, from the saved
+    /// state, so a resent copy is refused after a restart or failover too.
+    pub fn load_handled_commands(&self, commands: Vec<HandledCommand>) {
+        *self.handled_commands.lock().unwrap() = commands.into_iter()
+            .map(|command| (command.digest, command.signed_at))
+            .collect();
+    }
+    
+    /// Records a signed command email, `false` when it was run before.
+    /// Commands signed more than `max_age` ago are forgotten, the caller
+    /// refuses those by their signing time.
+    pub fn mark_command_handled(&self, digest: String, signed_at: DateTime<Utc>, max_age: Duration) -> bool {
+        let mut handled = self.handled_commands.lock().unwrap();
+        let oldest = Utc::now() - max_age;
+        handled.retain(|_, signed_at| *signed_at >= oldest);
+        if handled.contains_key(&digest) {
+            return false;
+        }
+        handled.insert(digest, signed_at);
+        true
+    }
+    
+    pub fn handled_commands(&self) -> Vec<HandledCommand> {
+        let mut commands: Vec<HandledCommand> = self.handled_commands.lock().unwrap().iter()
+            .map(|(digest, signed_at)| HandledCommand { digest: digest.clone(), signed_at: *signed_at })
+            .collect();
+        commands.sort_by(|a, b| a.signed_at.cmp(&b.signed_at).then_with(|| a.digest.cmp(&b.digest)));
+        commands
+    }
+    
+    pub fn publish_server(&self, server: ServerStats) {
+        *self.server.lock().unwrap() = Some(server);
+    }
+    
+    pub fn publish_containers(&self, containers: &[ContainerStats]) {
+        let mut published = self.containers.lock().unwrap();
+        published.clear();
+        published.extend_from_slice(containers);
+    }
+    
+    /// Rules firing or flapping after the last cycle, sorted. Acknowledgements
+    /// of rules that stopped firing are dropped.
+    pub fn publish_firing(&self, mut rules: Vec<String>) {
+        rules.sort();
+        self.acknowledgements.lock().unwrap().retain(|rule, _| rules.contains(rule));
+        *self.firing.lock().unwrap() = rules;
+    }
+    
+    /// Restores acknowledgements of incidents still open from the saved state.
+    pub fn load_acknowledgements(&self, acknowledgements: HashMap<String, Acknowledgement>) {
+        *self.acknowledgements.lock().unwrap() = acknowledgements;
+    }
+    
+    /// Marks a firing rule as taken by `by`, which stops its escalation.
+    /// Returns `None` when the rule is not firing.
+    pub fn acknowledge(&self, rule: &str, by: &str) -> Option<Acknowledgement> {
+        if !self.firing.lock().unwrap().iter().any(|firing| firing == rule) {
+            return None;
+        }
+        let mut acknowledgements = self.acknowledgements.lock().unwrap();
+        let acknowledgement = acknowledgements.entry(rule.to_string())
+            .or_insert_with(|| {
+                audit::record(AuditEntry::new(AuditAction::Acknowledgement, by, rule, "acknowledged, escalation stopped"));
+                Acknowledgement { by: by.to_string(), at: Utc::now() }
+            });
+        Some(acknowledgement.clone())
+    }
+    
+    pub fn acknowledgement(&self, rule: &str) -> Option<Acknowledgement> {
+        self.acknowledgements.lock().unwrap().get(rule).cloned()
+    }
+    
+    /// Forwards a notification to stream subscribers, if there are any.
+    pub fn publish_alert(&self, alert: &Alert) {
+        if self.alerts.receiver_count() > 0 {
+            let _ = self.alerts.send(alert.clone());
+        }
+    }
+    
+    /// Host stats of the last completed cycle, `None` before the first one.
+    pub fn server(&self) -> Option<ServerStats> {
+        self.server.lock().unwrap().clone()
+    }
+    
+    pub fn containers(&self) -> Vec<ContainerStats> {
+        self.containers.lock().unwrap().clone()
+    }
+    
+    pub fn firing(&self) -> Vec<String> {
+        self.firing.lock().unwrap().clone()
+    }
+    
+    pub fn subscribe(&self) -> broadcast::Receiver<Alert> {
+        self.alerts.subscribe()
+    }
+}
+
+fn presented_token(authorization: &str) -> Option<String> {
+    if let Some(token) = authorization.strip_prefix("Bearer ") {
+        return Some(token.trim().to_string());
+    }
+    let credentials = STANDARD.decode(authorization.strip_prefix("Basic ")?.trim()).ok()?;
+    let credentials = String::from_utf8(credentials).ok()?;
+    credentials.split_once(':').map(|(_, password)| password.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::HttpUserConfig;
+    
+    fn user(name: &str, token: &str, role: &str) -> HttpUserConfig {
+        HttpUserConfig { name: name.to_string(), token: token.to_string(), role: role.to_string() }
+    }
+    
+    fn state(api_token: Option<&str>, users: Vec<HttpUserConfig>) -> ApiState {
+        ApiState::new(&HttpConfig { api_token: api_token.map(str::to_string), users, ..HttpConfig::default() })
+    }
+    
+    fn basic(password: &str) -> String {
+        format!("Basic {}", STANDARD.encode(format!("anyone:{}", password)))
+    }
+    
+    #[test]
+    fn reads_are_anonymous_without_users() {
+        let api = state(Some("secret"), Vec::new());
+        assert_eq!(api.authorize(None, Role::Viewer), Ok(Identity { name: String::new(), role: Role::Viewer }));
+        assert_eq!(api.authorize(None, Role::Admin), Err(Denied::InvalidToken));
+    }
+    
+    #[test]
+    fn users_protect_reads() {
+        let api = state(None, vec![user("ops", "view-token", "viewer")]);
+        assert_eq!(api.authorize(None, Role::Viewer), Err(Denied::InvalidToken));
+        assert_eq!(api.authorize(Some("Bearer view-token"), Role::Viewer).unwrap().name, "ops");
+    }
+    
+    #[test]
+    fn viewers_are_refused_on_admin_calls() {
+        let api = state(None, vec![user("ops", "view-token", "viewer"), user("root", "admin-token", "admin")]);
+        assert_eq!(api.authorize(Some("Bearer view-token"), Role::Admin), Err(Denied::Forbidden));
+        assert_eq!(
+            api.authorize(Some("Bearer admin-token"), Role::Admin),
+            Ok(Identity { name: "root".to_string(), role: Role::Admin })
+        );
+    }
+    
+    #[test]
+    fn bearer_and_basic_tokens_resolve() {
+        let api = state(Some("secret"), Vec::new());
+        assert_eq!(api.authorize(Some("Bearer secret"), Role::Admin).unwrap().name, "admin");
+        assert_eq!(api.authorize(Some(&basic("secret")), Role::Admin).unwrap().name, "admin");
+        assert_eq!(presented_token("Bearer  secret "), Some("secret".to_string()));
+        assert_eq!(presented_token(&basic("pass:with:colons")), Some("pass:with:colons".to_string()));
+        assert_eq!(presented_token("Basic not-base64!"), None);
+        assert_eq!(presented_token("Token secret"), None);
+    }
+    
+    #[test]
+    fn unknown_roles_are_viewers() {
+        let api = state(Some("secret"), vec![user("ops", "ops-token", "superuser")]);
+        assert_eq!(
+            api.authorize(Some("Bearer ops-token"), Role::Viewer),
+            Ok(Identity { name: "ops".to_string(), role: Role::Viewer })
+        );
+        assert_eq!(api.authorize(Some("Bearer ops-token"), Role::Admin), Err(Denied::Forbidden));
+    }
+    
+    #[test]
+    fn wrong_tokens_are_denied() {
+        let api = state(Some("secret"), vec![user("ops", "view-token", "viewer")]);
+        for authorization in ["Bearer secreT", "Bearer secret2", "Bearer secre", "Bearer ", "secret"] {
+            assert_eq!(api.authorize(Some(authorization), Role::Viewer), Err(Denied::InvalidToken), "{}", authorization);
+        }
+        assert_eq!(api.authorize(Some(&basic("wrong")), Role::Admin), Err(Denied::InvalidToken));
+    }
+    
+    #[test]
+    fn writes_are_disabled_without_an_admin() {
+        let api = state(Some(""), vec![user("ops", "view-token", "viewer"), user("root", "", "admin")]);
+        assert_eq!(api.authorize(Some("Bearer view-token"), Role::Admin), Err(Denied::Disabled));
+        assert_eq!(api.authorize(None, Role::Admin), Err(Denied::Disabled));
+        assert_eq!(state(None, Vec::new()).authorize(Some("Bearer anything"), Role::Admin), Err(Denied::Disabled));
+    }
+}
@@ -0,0 +1,183 @@
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use crate::alert::{Alert, Severity};
+use crate::config::{AppriseConfig, Config};
+use crate::incident::IncidentState;
+use crate::kubernetes;
+use crate::network;
+use anyhow::{Result, anyhow};
+use log::{info, error, warn};
+
+/// Notifier for an Apprise API server, which fans notifications out to the
+/// services behind apprise URLs (Telegram, Discord, Pushover, ntfy, ...).
+pub struct AppriseNotifier {
+    servers: Vec<AppriseConfig>,
+    client: Client,
+    hostname: String,
+}
+
+impl AppriseNotifier {
+    pub fn new(config: &Config) -> Self {
+        let servers: Vec<AppriseConfig> = config.apprise.iter()
+            .filter(|apprise| {
+                let usable = apprise.key.is_some() || !apprise.urls.is_empty();
+                if !usable {
+                    warn!("Apprise channel {} needs a config key or urls. Skipping.", apprise.name);
+                }
+                usable
+            })
+            .cloned()
+            .collect();
+        
+        if !servers.is_empty() {
+            info!("Apprise notifier initialized with {} channels", servers.len());
+        }
+        
+        Self {
+            servers,
+            client: network::http_client(&config.network),
+            hostname: kubernetes::hostname(),
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.servers.is_empty()
+    }
+    
+    /// Overrides the hostname shown in notifications, for `render`.
+    pub fn set_hostname(&mut self, hostname: &str) {
+        self.hostname = hostname.to_string();
+    }
+    
+    /// Builds the payload of every Apprise channel, whatever rules it is
+    /// subscribed to, named like the channels `send` reports.
+    pub fn preview(&self, alert: &Alert) -> Vec<(String, Value)> {
+        self.servers.iter()
+            .map(|apprise| (format!("apprise:{}", apprise.name), self.payload(apprise, alert)))
+            .collect()
+    }
+    
+    /// Sends the alert to every Apprise channel subscribed to its rule.
+    /// Returns the outcome per channel, named `apprise:<name>`.
+    pub async fn send(&self, alert: &Alert) -> Vec<(String, bool)> {
+        let mut results = Vec::new();
+        
+        for apprise in &self.servers {
+            if !apprise.rules.is_empty() && !apprise.rules.contains(&alert.rule) {
+                continue;
+            }
+            
+            let sent = match self.notify(apprise, alert).await {
+                Ok(_) => {
+                    info!("{} alert sent through apprise {}", alert.rule, apprise.name);
+                    true
+                }
+                Err(e) => {
+                    error!("Failed to send {} alert through apprise {}: {}", alert.rule, apprise.name, e);
+                    false
+                }
+            };
+            results.push((format!("apprise:{}", apprise.name), sent));
+        }
+        
+        results
+    }
+    
+    /// Uses `/notify/<key>` for configurations stored on the server, or the
+    /// stateless `/notify` endpoint with the configured apprise URLs.
+    async fn notify(&self, apprise: &AppriseConfig, alert: &Alert) -> Result<()> {
+        let server = apprise.server.trim_end_matches('/');
+        let payload = self.payload(apprise, alert);
+        let url = match &apprise.key {
+            Some(key) => format!("{}/notify/{}", server, key),
+            None => format!("{}/notify", server),
+        };
+        
+        let response = self.client.post(&url)
+            .timeout(Duration::from_millis(apprise.timeout_ms))
+            .json(&payload)
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("HTTP {}: {}", status, text.chars().take(200).collect::<String>()));
+        }
+        
+        Ok(())
+    }
+    
+    /// `/notify/<key>` takes the tag to pick from the stored configuration,
+    /// the stateless `/notify` endpoint takes the apprise URLs.
+    fn payload(&self, apprise: &AppriseConfig, alert: &Alert) -> Value {
+        let mut payload = json!({
+            "title": format!("[{}] {} on {}", label(alert), alert.summary, self.hostname),
+            "body": body(alert),
+            "type": notification_type(alert),
+            "format": "markdown",
+        });
+        match &apprise.key {
+            Some(_) => {
+                if let Some(tag) = &apprise.tag {
+                    payload["tag"] = tag.clone().into();
+                }
+            }
+            None => payload["urls"] = apprise.urls.join(",").into(),
+        }
+        payload
+    }
+}
+
+fn label(alert: &Alert) -> &'static str {
+    if alert.state == IncidentState::Firing {
+        alert.severity.label()
+    } else {
+        alert.state.label()
+    }
+}
+
+/// Apprise notification type, which downstream services map to icons/colors.
+fn notification_type(alert: &Alert) -> &'static str {
+    match (alert.state, alert.severity) {
+        (IncidentState::Resolved, _) | (IncidentState::Ok, _) => "success",
+        (IncidentState::Firing, Severity::Critical) => "failure",
+        (IncidentState::Firing, Severity::Warning) => "warning",
+        _ => "info",
+    }
+}
+
+fn body(alert: &Alert) -> String {
+    let mut lines = vec![
+        format!("**Rule:** {}", alert.rule),
+        format!("**Severity:** {}", alert.severity.label()),
+        format!("**Threshold:** {}", alert.threshold),
+        format!("**Window:** {}", alert.window),
+    ];
+    if let Some(value) = alert.value {
+        lines.push(format!("**Value:** {:.2}", value));
+    }
+    if !alert.containers.is_empty() {
+        let names: Vec<&str> = alert.containers.iter().map(|container| container.name.as_str()).collect();
+        lines.push(format!("**Containers:** {}", names.join(", ")));
+    }
+    if !alert.processes.is_empty() {
+        let names: Vec<&str> = alert.processes.iter().map(|process| process.name.as_str()).collect();
+        lines.push(format!("**Processes:** {}", names.join(", ")));
+    }
+    if !alert.top_processes.is_empty() {
+        let top: Vec<String> = alert.top_processes.iter()
+            .map(|process| format!("{} ({}) {:.1}%", process.name, process.pid, process.cpu_usage))
+            .collect();
+        lines.push(format!("**Top Processes:** {}", top.join(", ")));
+    }
+    if let Some(url) = &alert.runbook_url {
+        lines.push(format!("**Runbook:** {}", url));
+    }
+    if let Some(description) = &alert.description {
+        lines.push(description.clone());
+    }
+    lines.extend(alert.notes.iter().map(|note| format!("- {}", note)));
+    lines.join("\n")
+}
\ No newline at end of file
@@ -0,0 +1,202 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use comfy_table::{presets, ContentArrangement, Table};
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::{Mutex, OnceLock};
+use crate::config::{AuditConfig, Config};
+use crate::timezone;
+use anyhow::{Result, anyhow};
+use log::{error, warn};
+
+/// What kind of action an audit entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AuditAction {
+    /// An alert sent to a channel.
+    Notification,
+    /// A change made to the host: container restarts, restart commands,
+    /// scaling hooks, pruned resources.
+    Remediation,
+    Silence,
+    Acknowledgement,
+    /// A threshold changed at runtime or in the config file.
+    Config,
+}
+
+impl AuditAction {
+    pub fn parse(name: &str) -> Option<Self> {
+        serde_json::from_value(serde_json::Value::String(name.to_string())).ok()
+    }
+    
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuditAction::Notification => "notification",
+            AuditAction::Remediation => "remediation",
+            AuditAction::Silence => "silence",
+            AuditAction::Acknowledgement => "acknowledgement",
+            AuditAction::Config => "config",
+        }
+    }
+}
+
+/// One line of the audit log. `actor` is `monitor` for what the monitor did
+/// on its own, otherwise the API user, chat user or `cli:<login>`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    pub actor: String,
+    /// What was acted on: a rule, container, process or threshold name.
+    pub target: String,
+    pub detail: String,
+    pub success: bool,
+}
+
+impl AuditEntry {
+    pub fn new(action: AuditAction, actor: &str, target: &str, detail: impl Into<String>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            action,
+            actor: actor.to_string(),
+            target: target.to_string(),
+            detail: detail.into(),
+            success: true,
+        }
+    }
+    
+    pub fn with_success(mut self, success: bool) -> Self {
+        self.success = success;
+        self
+    }
+}
+
+/// Open only for appending; entries are never rewritten.
+struct AuditLog {
+    path: String,
+    lock: Mutex<()>,
+}
+
+static LOG: OnceLock<Option<AuditLog>> = OnceLock::new();
+
+/// Applies the `audit` config. Only the first call has an effect; until
+/// then, and when disabled, nothing is recorded.
+pub fn configure(config: &AuditConfig) {
+    let _ = LOG.set(config.enabled.then(|| AuditLog {
+        path: config.file.clone(),
+        lock: Mutex::new(()),
+    }));
+}
+
+/// Appends the entry to the audit log as a JSON line. A failed write is
+/// logged, never returned: the action already happened.
+pub fn record(entry: AuditEntry) {
+    let Some(Some(log)) = LOG.get() else {
+        return;
+    };
+    let line = match serde_json::to_string(&entry) {
+        Ok(line) => line,
+        Err(e) => {
+            error!("Failed to serialize audit entry: {}", e);
+            return;
+        }
+    };
+    
+    let _guard = log.lock.lock().unwrap();
+    if let Some(parent) = Path::new(&log.path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        let _ = fs::create_dir_all(parent);
+    }
+    let result = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .mode(0o600)
+        .open(&log.path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if let Err(e) = result {
+        error!("Failed to write audit log {}: {}", log.path, e);
+    }
+}
+
+/// The actor for commands run from a shell, the login behind `sudo` if any.
+pub fn cli_actor() -> String {
+    let login = ["SUDO_USER", "USER", "LOGNAME"].iter()
+        .find_map(|name| std::env::var(name).ok().filter(|value| !value.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string());
+    format!("cli:{}", login)
+}
+
+/// Filters of `audit list`.
+#[derive(Debug, Default)]
+pub struct AuditQuery {
+    pub action: Option<AuditAction>,
+    /// Substring of the actor.
+    pub actor: Option<String>,
+    /// Substring of the target.
+    pub target: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    /// Only the most recent entries, 0 for all.
+    pub limit: usize,
+}
+
+impl AuditQuery {
+    fn matches(&self, entry: &AuditEntry) -> bool {
+        self.action.is_none_or(|action| entry.action == action)
+            && self.actor.as_ref().is_none_or(|actor| entry.actor.contains(actor.as_str()))
+            && self.target.as_ref().is_none_or(|target| entry.target.contains(target.as_str()))
+            && self.since.is_none_or(|since| entry.timestamp >= since)
+    }
+}
+
+/// Entries of the audit log matching `query`, oldest first. Lines that do
+/// not parse are skipped with a warning.
+pub fn read(path: &str, query: &AuditQuery) -> Result<Vec<AuditEntry>> {
+    let content = fs::read_to_string(path).map_err(|e| anyhow!("cannot read audit log {}: {}", path, e))?;
+    let mut entries: Vec<AuditEntry> = content.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .filter_map(|(index, line)| match serde_json::from_str(line) {
+            Ok(entry) => Some(entry),
+            Err(e) => {
+                warn!("Skipping line {} of {}: {}", index + 1, path, e);
+                None
+            }
+        })
+        .filter(|entry| query.matches(entry))
+        .collect();
+    if query.limit > 0 && entries.len() > query.limit {
+        entries.drain(..entries.len() - query.limit);
+    }
+    Ok(entries)
+}
+
+/// `audit list`: the matching entries as a table, or as JSON lines.
+pub fn list(config: &Config, query: &AuditQuery, json: bool) -> Result<String> {
+    let entries = read(&config.audit.file, query)?;
+    if json {
+        let lines: Result<Vec<String>, _> = entries.iter().map(serde_json::to_string).collect();
+        return Ok(lines?.join("\n"));
+    }
+    if entries.is_empty() {
+        return Ok("No audit entries match.".to_string());
+    }
+    
+    let timezone = timezone::load(&config.timezone);
+    let mut table = Table::new();
+    table
+        .load_preset(presets::UTF8_FULL)
+        .set_content_arrangement(ContentArrangement::Dynamic)
+        .set_header(vec!["Time", "Action", "Actor", "Target", "Detail", "Result"]);
+    for entry in &entries {
+        table.add_row(vec![
+            timezone.format(entry.timestamp),
+            entry.action.label().to_string(),
+            entry.actor.clone(),
+            entry.target.clone(),
+            entry.detail.clone(),
+            if entry.success { "ok" } else { "failed" }.to_string(),
+        ]);
+    }
+    Ok(table.to_string())
+}
\ No newline at end of file
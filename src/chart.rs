@@ -0,0 +1,169 @@
+use anyhow::Result;
+
+const WIDTH: usize = 640;
+const HEIGHT: usize = 200;
+const PADDING: usize = 10;
+const BACKGROUND: [u8; 3] = [255, 255, 255];
+const GRID: [u8; 3] = [225, 225, 225];
+const AXIS: [u8; 3] = [150, 150, 150];
+
+/// One line of a percent chart.
+pub struct Series<'a> {
+    pub color: [u8; 3],
+    pub values: &'a [f64],
+}
+
+/// Renders a 0-100% line chart as a PNG. Labels are left to the surrounding
+/// HTML so no font rendering is needed; grid lines mark every 25%.
+pub fn percent_chart(series: &[Series]) -> Result<Vec<u8>> {
+    line_chart(series, 100.0)
+}
+
+/// Renders a line chart scaled from 0 to `max`, with grid lines every 25% of
+/// `max`. Values above `max` are clipped.
+pub fn line_chart(series: &[Series], max: f64) -> Result<Vec<u8>> {
+    let mut canvas = Canvas::new(max);
+    
+    for percent in [25.0, 50.0, 75.0] {
+        let y = canvas.y_for(percent);
+        canvas.line((PADDING, y), (WIDTH - PADDING, y), GRID);
+    }
+    canvas.line((PADDING, PADDING), (PADDING, HEIGHT - PADDING), AXIS);
+    canvas.line((PADDING, HEIGHT - PADDING), (WIDTH - PADDING, HEIGHT - PADDING), AXIS);
+    
+    for line in series {
+        let points: Vec<(usize, usize)> = line.values.iter()
+            .enumerate()
+            .map(|(index, value)| (canvas.x_for(index, line.values.len()), canvas.y_for(value / canvas.max * 100.0)))
+            .collect();
+        for pair in points.windows(2) {
+            canvas.thick_line(pair[0], pair[1], line.color);
+        }
+        if let [point] = points.as_slice() {
+            canvas.thick_line(*point, *point, line.color);
+        }
+    }
+    
+    canvas.encode()
+}
+
+/// Renders a stacked area chart scaled from 0 to `max`: each series is drawn
+/// on top of the ones before it, so the outline is the total. All series must
+/// have the same length.
+pub fn stacked_chart(series: &[Series], max: f64) -> Result<Vec<u8>> {
+    let mut canvas = Canvas::new(max);
+    let count = series.iter().map(|line| line.values.len()).max().unwrap_or(0);
+    let mut base = vec![0.0; count];
+    
+    for line in series {
+        let top: Vec<f64> = base.iter()
+            .enumerate()
+            .map(|(index, below)| below + line.values.get(index).copied().unwrap_or(0.0))
+            .collect();
+        for index in 0..count {
+            let next = (index + 1).min(count - 1);
+            let (x0, x1) = (canvas.x_for(index, count), canvas.x_for(next, count));
+            for x in x0..=x1 {
+                let t = if x1 > x0 { (x - x0) as f64 / (x1 - x0) as f64 } else { 0.0 };
+                let lower = base[index] + (base[next] - base[index]) * t;
+                let upper = top[index] + (top[next] - top[index]) * t;
+                let from = canvas.y_for(lower / canvas.max * 100.0);
+                let to = canvas.y_for(upper / canvas.max * 100.0);
+                canvas.line((x, from), (x, to), line.color);
+            }
+        }
+        base = top;
+    }
+    
+    for percent in [25.0, 50.0, 75.0] {
+        let y = canvas.y_for(percent);
+        canvas.dashed_line(y, GRID);
+    }
+    canvas.line((PADDING, PADDING), (PADDING, HEIGHT - PADDING), AXIS);
+    canvas.line((PADDING, HEIGHT - PADDING), (WIDTH - PADDING, HEIGHT - PADDING), AXIS);
+    
+    canvas.encode()
+}
+
+struct Canvas {
+    pixels: Vec<u8>,
+    max: f64,
+}
+
+impl Canvas {
+    fn new(max: f64) -> Self {
+        Self {
+            pixels: BACKGROUND.repeat(WIDTH * HEIGHT),
+            max: if max > 0.0 { max } else { 100.0 },
+        }
+    }
+    
+    fn x_for(&self, index: usize, count: usize) -> usize {
+        let span = WIDTH - 2 * PADDING;
+        PADDING + if count > 1 { index * span / (count - 1) } else { span / 2 }
+    }
+    
+    fn y_for(&self, percent: f64) -> usize {
+        let span = (HEIGHT - 2 * PADDING) as f64;
+        HEIGHT - PADDING - (percent.clamp(0.0, 100.0) / 100.0 * span).round() as usize
+    }
+    
+    fn set(&mut self, x: i64, y: i64, color: [u8; 3]) {
+        if x < 0 || y < 0 || x >= WIDTH as i64 || y >= HEIGHT as i64 {
+            return;
+        }
+        let offset = (y as usize * WIDTH + x as usize) * 3;
+        self.pixels[offset..offset + 3].copy_from_slice(&color);
+    }
+    
+    /// Bresenham line between two points.
+    fn line(&mut self, from: (usize, usize), to: (usize, usize), color: [u8; 3]) {
+        let (mut x, mut y) = (from.0 as i64, from.1 as i64);
+        let (x1, y1) = (to.0 as i64, to.1 as i64);
+        let dx = (x1 - x).abs();
+        let dy = -(y1 - y).abs();
+        let step_x = if x < x1 { 1 } else { -1 };
+        let step_y = if y < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        
+        loop {
+            self.set(x, y, color);
+            if x == x1 && y == y1 {
+                break;
+            }
+            let doubled = 2 * error;
+            if doubled >= dy {
+                error += dy;
+                x += step_x;
+            }
+            if doubled <= dx {
+                error += dx;
+                y += step_y;
+            }
+        }
+    }
+    
+    /// Dashed horizontal grid line, so it stays visible over filled areas.
+    fn dashed_line(&mut self, y: usize, color: [u8; 3]) {
+        for x in (PADDING..WIDTH - PADDING).step_by(4) {
+            self.set(x as i64, y as i64, color);
+            self.set(x as i64 + 1, y as i64, color);
+        }
+    }
+    
+    fn thick_line(&mut self, from: (usize, usize), to: (usize, usize), color: [u8; 3]) {
+        for offset in 0..2 {
+            self.line((from.0, from.1 + offset), (to.0, to.1 + offset), color);
+            self.line((from.0 + offset, from.1), (to.0 + offset, to.1), color);
+        }
+    }
+    
+    fn encode(&self) -> Result<Vec<u8>> {
+        let mut png = Vec::new();
+        let mut encoder = png::Encoder::new(&mut png, WIDTH as u32, HEIGHT as u32);
+        encoder.set_color(png::ColorType::Rgb);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.write_header()?.write_image_data(&self.pixels)?;
+        Ok(png)
+    }
+}
\ No newline at end of file
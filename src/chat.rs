@@ -0,0 +1,215 @@
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::time::Duration;
+use crate::alert::{Alert, Severity};
+use crate::config::{ChatConfig, Config};
+use crate::incident::IncidentState;
+use crate::kubernetes;
+use crate::network;
+use anyhow::{Result, anyhow};
+use log::{info, error, warn};
+
+/// Mattermost and Rocket.Chat incoming webhooks. Both accept Slack style
+/// attachments; they differ in how the sender name and icon are set.
+pub struct ChatNotifier {
+    channels: Vec<ChatConfig>,
+    client: Client,
+    hostname: String,
+}
+
+impl ChatNotifier {
+    pub fn new(config: &Config) -> Self {
+        let channels: Vec<ChatConfig> = config.chat.iter()
+            .filter(|chat| {
+                let known = chat.kind == "mattermost" || chat.kind == "rocketchat";
+                if !known {
+                    warn!("Chat channel {} has unknown kind '{}' (expected mattermost or rocketchat). Skipping.", chat.name, chat.kind);
+                }
+                known
+            })
+            .cloned()
+            .collect();
+        
+        if !channels.is_empty() {
+            info!("Chat notifier initialized with {} channels", channels.len());
+        }
+        
+        Self {
+            channels,
+            client: network::http_client(&config.network),
+            hostname: kubernetes::hostname(),
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.channels.is_empty()
+    }
+    
+    /// Overrides the hostname shown in notifications, for `render`.
+    pub fn set_hostname(&mut self, hostname: &str) {
+        self.hostname = hostname.to_string();
+    }
+    
+    /// Builds the payload of every chat channel, whatever rules it is
+    /// subscribed to, named like the channels `send` reports.
+    pub fn preview(&self, alert: &Alert) -> Vec<(String, Value)> {
+        self.channels.iter()
+            .map(|chat| (format!("{}:{}", chat.kind, chat.name), self.payload(chat, alert)))
+            .collect()
+    }
+    
+    /// Posts the alert to every chat channel subscribed to its rule. Returns
+    /// the outcome per channel, named `<kind>:<name>`.
+    pub async fn send(&self, alert: &Alert) -> Vec<(String, bool)> {
+        let channels = self.channels.iter().filter(|chat| chat.rules.is_empty() || chat.rules.contains(&alert.rule));
+        self.deliver(channels.collect(), alert).await
+    }
+    
+    /// Posts the alert to the channels named in `names` as `<kind>:<name>`,
+    /// whatever rules they are subscribed to. Used by escalation tiers.
+    pub async fn send_to(&self, alert: &Alert, names: &[String]) -> Vec<(String, bool)> {
+        let channels = self.channels.iter().filter(|chat| names.contains(&format!("{}:{}", chat.kind, chat.name)));
+        self.deliver(channels.collect(), alert).await
+    }
+    
+    async fn deliver(&self, channels: Vec<&ChatConfig>, alert: &Alert) -> Vec<(String, bool)> {
+        let mut results = Vec::new();
+        
+        for chat in channels {
+            let sent = match self.post(chat, alert).await {
+                Ok(_) => {
+                    info!("{} alert posted to {} {}", alert.rule, chat.kind, chat.name);
+                    true
+                }
+                Err(e) => {
+                    error!("Failed to post {} alert to {} {}: {}", alert.rule, chat.kind, chat.name, e);
+                    false
+                }
+            };
+            results.push((format!("{}:{}", chat.kind, chat.name), sent));
+        }
+        
+        results
+    }
+    
+    async fn post(&self, chat: &ChatConfig, alert: &Alert) -> Result<()> {
+        let response = self.client.post(&chat.url)
+            .timeout(Duration::from_millis(chat.timeout_ms))
+            .json(&self.payload(chat, alert))
+            .send()
+            .await?;
+        
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("HTTP {}: {}", status, text.chars().take(200).collect::<String>()));
+        }
+        
+        Ok(())
+    }
+    
+    fn payload(&self, chat: &ChatConfig, alert: &Alert) -> Value {
+        let firing = alert.state == IncidentState::Firing;
+        let label = if firing { alert.severity.label() } else { alert.state.label() };
+        let color = if firing { severity_color(alert.severity) } else { state_color(alert.state) };
+        
+        let mut fields = vec![
+            field("Severity", alert.severity.label(), true),
+            field("State", alert.state.label(), true),
+            field("Threshold", &alert.threshold, true),
+            field("Window", &alert.window, true),
+        ];
+        if let Some(value) = alert.value {
+            fields.insert(2, field("Value", &format!("{:.2}", value), true));
+        }
+        if !alert.containers.is_empty() {
+            let names: Vec<&str> = alert.containers.iter().map(|container| container.name.as_str()).collect();
+            fields.push(field("Containers", &names.join(", "), false));
+        }
+        if !alert.processes.is_empty() {
+            let names: Vec<&str> = alert.processes.iter().map(|process| process.name.as_str()).collect();
+            fields.push(field("Processes", &names.join(", "), false));
+        }
+        if !alert.top_processes.is_empty() {
+            let top: Vec<String> = alert.top_processes.iter()
+                .map(|process| format!("{} ({}) {:.1}%", process.name, process.pid, process.cpu_usage))
+                .collect();
+            fields.push(field("Top Processes", &top.join(", "), false));
+        }
+        
+        if let Some(url) = &alert.runbook_url {
+            fields.push(field("Runbook", url, false));
+        }
+        
+        let notes: Vec<String> = alert.description.iter().cloned()
+            .chain(alert.notes.iter().map(|note| format!("- {}", note)))
+            .collect();
+        let mut payload = json!({
+            "text": format!("**[{}] {}**", label, alert.summary),
+            "attachments": [{
+                "fallback": format!("[{}] {} on {}", label, alert.summary, self.hostname),
+                "color": color,
+                "title": format!("{} on {}", alert.rule, self.hostname),
+                "text": notes.join("\n"),
+                "fields": fields,
+                "ts": alert.timestamp.timestamp(),
+            }],
+        });
+        
+        if let Some(url) = &alert.runbook_url {
+            payload["attachments"][0]["title_link"] = url.clone().into();
+        }
+        if let Some(channel) = self.channel_for(chat, alert) {
+            payload["channel"] = channel.into();
+        }
+        match chat.kind.as_str() {
+            "rocketchat" => {
+                payload["alias"] = chat.username.clone().into();
+                if let Some(icon) = &chat.icon {
+                    payload["emoji"] = icon.clone().into();
+                }
+            }
+            _ => {
+                payload["username"] = chat.username.clone().into();
+                if let Some(icon) = &chat.icon {
+                    payload["icon_url"] = icon.clone().into();
+                }
+            }
+        }
+        
+        payload
+    }
+    
+    /// Channel override for the alert's severity (or state when not firing),
+    /// falling back to the channel's default.
+    fn channel_for<'a>(&self, chat: &'a ChatConfig, alert: &Alert) -> Option<&'a str> {
+        let key = if alert.state == IncidentState::Firing {
+            alert.severity.label()
+        } else {
+            alert.state.label()
+        };
+        chat.severity_channels.get(&key.to_lowercase())
+            .or(chat.channel.as_ref())
+            .map(String::as_str)
+    }
+}
+
+fn field(title: &str, value: &str, short: bool) -> Value {
+    json!({ "title": title, "value": value, "short": short })
+}
+
+fn severity_color(severity: Severity) -> &'static str {
+    match severity {
+        Severity::Ok => "#2ecc71",
+        Severity::Warning => "#f39c12",
+        Severity::Critical => "#e74c3c",
+    }
+}
+
+fn state_color(state: IncidentState) -> &'static str {
+    match state {
+        IncidentState::Resolved | IncidentState::Ok => "#2ecc71",
+        IncidentState::Flapping => "#9b59b6",
+        IncidentState::Pending | IncidentState::Firing => "#f39c12",
+    }
+}
\ No newline at end of file
@@ -0,0 +1,430 @@
+use chrono::{DateTime, Duration, Utc};
+use openssl::hash::MessageDigest;
+use openssl::pkey::PKey;
+use openssl::sign::Signer;
+use regex::Regex;
+use serde::Deserialize;
+use crate::alert::ALL_RULES;
+use crate::api::{ApiState, Role};
+use crate::config::{parse_duration, ChatOpsConfig};
+use crate::kubernetes;
+use crate::html::escape;
+use crate::redact::Redactor;
+use crate::units;
+use anyhow::{Result, anyhow};
+
+/// Slack rejects replayed requests older than this, and so do we.
+const SLACK_MAX_AGE: i64 = 300;
+const DEFAULT_TOP: usize = 5;
+
+const HELP: &str = "Commands:
+`status` host usage, firing rules and silences
+`top [n]` busiest containers by CPU
+`silence <rule|container> <duration> [comment]` mute notifications, e.g. `silence web 1h deploy`
+`silence <duration>` mute all notifications
+`silences` active silences
+`ack <rule>` take a firing rule and stop its escalation";
+
+/// A command typed after the slash command, e.g. `/monitor top 10`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Status,
+    Top(usize),
+    Silence { target: String, duration: Duration, comment: String },
+    Silences,
+    Ack(String),
+    Help,
+}
+
+impl Command {
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut words = text.split_whitespace();
+        match words.next().unwrap_or("help").to_lowercase().as_str() {
+            "status" => Ok(Command::Status),
+            "top" => {
+                let count = match words.next() {
+                    Some(count) => count.parse().map_err(|_| anyhow!("`top` takes a number, got `{}`", count))?,
+                    None => DEFAULT_TOP,
+                };
+                Ok(Command::Top(count))
+            }
+            "silence" => {
+                let (target, duration) = match (words.next(), words.next()) {
+                    (Some(target), Some(duration)) => (target, duration),
+                    (Some(duration), None) if parse_duration(duration).is_ok() => ("*", duration),
+                    _ => return Err(anyhow!("Usage: `silence <rule|container> <duration> [comment]`")),
+                };
+                let duration = Duration::from_std(parse_duration(duration)?)?;
+                Ok(Command::Silence {
+                    target: target.to_string(),
+                    duration,
+                    comment: words.collect::<Vec<_>>().join(" "),
+                })
+            }
+            "silences" => Ok(Command::Silences),
+            "ack" => match words.next() {
+                Some(rule) => Ok(Command::Ack(rule.to_string())),
+                None => Err(anyhow!("Usage: `ack <rule>`")),
+            },
+            "help" => Ok(Command::Help),
+            other => Err(anyhow!("Unknown command `{}`.\n{}", other, HELP)),
+        }
+    }
+}
+
+/// Runs a command against the shared API state and returns the reply as
+/// Markdown that renders in both Slack and Telegram. `user` is the display
+/// name recorded on silences and acknowledgements, which only admins may
+/// create. Host data in the reply is masked by `redactor` like in alerts.
+pub fn execute(api: &ApiState, redactor: &Redactor, text: &str, user: &str, role: Role) -> String {
+    match Command::parse(text) {
+        Ok(command) => run(api, redactor, command, user, role),
+        Err(e) => e.to_string(),
+    }
+}
+
+/// Answers a Slack or Telegram command from `user_id`. Like the REST API
+/// without an admin token, silences and acknowledgements are refused for
+/// everyone until `chatops.admins` names someone.
+pub fn reply(config: &ChatOpsConfig, api: &ApiState, redactor: &Redactor, text: &str, user: &str, user_id: &str) -> String {
+    let command = match Command::parse(text) {
+        Ok(command) => command,
+        Err(e) => return e.to_string(),
+    };
+    if matches!(command, Command::Silence { .. } | Command::Ack(_)) && config.admins.is_empty() {
+        return "Silences and acknowledgements are disabled until chatops.admins is set".to_string();
+    }
+    run(api, redactor, command, user, role(config, user_id))
+}
+
+fn run(api: &ApiState, redactor: &Redactor, command: Command, user: &str, role: Role) -> String {
+    if let (Command::Silence { .. } | Command::Ack(_), Some(refused)) = (&command, api.refuse_changes()) {
+        return refused;
+    }
+    
+    let reply = match command {
+        Command::Status => status(api),
+        Command::Top(count) => top(api, redactor, count),
+        Command::Silence { .. } if role < Role::Admin => {
+            "Only admins can create silences, ask one to add your user ID to chatops.admins".to_string()
+        }
+        Command::Silence { target, duration, comment } => {
+            // A rule that has fired before is muted as a rule, anything else as a container
+            let known_rule = target == "*" || ALL_RULES.contains(&target.as_str()) || api.firing().contains(&target);
+            let (rule, container) = if known_rule {
+                (target.clone(), None)
+            } else {
+                ("*".to_string(), Some(target.clone()))
+            };
+            match api.silences.create(&rule, container, duration, comment, user.to_string()) {
+                Ok(silence) if target == "*" => format!(
+                    "Silenced all rules until {} (id `{}`)",
+                    format_time(silence.ends_at), silence.id
+                ),
+                Ok(silence) => format!(
+                    "Silenced `{}` until {} (id `{}`)",
+                    target, format_time(silence.ends_at), silence.id
+                ),
+                Err(e) => format!("Could not create silence: {}", e),
+            }
+        }
+        Command::Silences => silences(api),
+        Command::Ack(_) if role < Role::Admin => {
+            "Only admins can acknowledge alerts, ask one to add your user ID to chatops.admins".to_string()
+        }
+        Command::Ack(rule) => match api.acknowledge(&rule, user) {
+            Some(acknowledgement) => format!(
+                "`{}` acknowledged by {} at {}", rule, acknowledgement.by, format_time(acknowledgement.at)
+            ),
+            None => format!("`{}` is not firing", rule),
+        },
+        Command::Help => HELP.to_string(),
+    };
+    redactor.text(&reply)
+}
+
+fn status(api: &ApiState) -> String {
+    let Some(server) = api.server() else {
+        return "No monitoring cycle has completed yet".to_string();
+    };
+    let firing = api.firing();
+    let firing = if firing.is_empty() { "none".to_string() } else { firing.join(", ") };
+    
+    format!(
+        "*{}* at {}\nCPU {:.1}% · Memory {:.1}% · Disk {:.1}% · Load {:.2}\nContainers: {}\nFiring: {}\nSilences: {}",
+        kubernetes::hostname(),
+        format_time(server.timestamp),
+        server.cpu_usage,
+        server.memory_usage.percent,
+        server.disk_usage.percent,
+        server.load_average.one_min,
+        api.containers().len(),
+        firing,
+        api.silences.active(Utc::now()).len(),
+    )
+}
+
+fn top(api: &ApiState, redactor: &Redactor, count: usize) -> String {
+    let mut containers = api.containers();
+    if containers.is_empty() {
+        return "No containers seen in the last cycle".to_string();
+    }
+    containers.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    // Masked before the columns are sized, so the table stays aligned
+    for container in &mut containers {
+        container.name = redactor.text(&container.name);
+    }
+    
+    let count = count.max(1);
+    let width = containers.iter().take(count).map(|container| container.name.len()).max().unwrap_or(4).max(4);
+    let rows: Vec<String> = containers.iter()
+        .take(count)
+        .map(|container| format!(
+            "{:<width$}  {:>6.1}%  {:>10}",
+            container.name, container.cpu_usage, units::bytes(container.memory_usage as f64), width = width
+        ))
+        .collect();
+    format!("```\n{:<width$}  {:>7}  {:>10}\n{}\n```", "Name", "CPU", "Memory", rows.join("\n"), width = width)
+}
+
+fn silences(api: &ApiState) -> String {
+    let active = api.silences.active(Utc::now());
+    if active.is_empty() {
+        return "No active silences".to_string();
+    }
+    active.iter()
+        .map(|silence| {
+            let rule = if silence.rule == "*" { "all rules" } else { silence.rule.as_str() };
+            let target = match &silence.container {
+                Some(container) => format!("{} on {}", rule, container),
+                None => rule.to_string(),
+            };
+            let by = if silence.created_by.is_empty() { String::new() } else { format!(" by {}", silence.created_by) };
+            format!("`{}` {} until {}{}", silence.id, target, format_time(silence.ends_at), by)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_time(time: DateTime<Utc>) -> String {
+    time.format("%Y-%m-%d %H:%M UTC").to_string()
+}
+
+/// Fields of a Slack slash command request we use. `user_id` is stable,
+/// `user_name` can be changed by the user and is only shown.
+#[derive(Debug, Deserialize)]
+pub struct SlackCommand {
+    #[serde(default)]
+    pub text: String,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub user_name: String,
+}
+
+/// Checks Slack's `X-Slack-Signature` over the raw request body, rejecting
+/// requests signed more than five minutes ago.
+pub fn verify_slack(config: &ChatOpsConfig, timestamp: Option<&str>, signature: Option<&str>, body: &[u8], now: DateTime<Utc>) -> Result<()> {
+    let secret = config.slack_signing_secret.as_deref()
+        .ok_or_else(|| anyhow!("Slack commands are disabled until chatops.slack_signing_secret is set"))?;
+    let (Some(timestamp), Some(signature)) = (timestamp, signature) else {
+        return Err(anyhow!("Missing Slack signature headers"));
+    };
+    let sent: i64 = timestamp.parse().map_err(|_| anyhow!("Invalid Slack timestamp"))?;
+    if (now.timestamp() - sent).abs() > SLACK_MAX_AGE {
+        return Err(anyhow!("Slack request is too old"));
+    }
+    
+    let mut signed = format!("v0:{}:", timestamp).into_bytes();
+    signed.extend_from_slice(body);
+    let expected = format!("v0={}", hex(&hmac_sha256(secret.as_bytes(), &signed)?));
+    if expected.len() != signature.len() || !openssl::memcmp::eq(expected.as_bytes(), signature.as_bytes()) {
+        return Err(anyhow!("Invalid Slack signature"));
+    }
+    Ok(())
+}
+
+/// The parts of a Telegram bot update carrying a message.
+#[derive(Debug, Deserialize)]
+pub struct TelegramUpdate {
+    #[serde(default)]
+    pub message: Option<TelegramMessage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramMessage {
+    pub chat: TelegramChat,
+    #[serde(default)]
+    pub from: Option<TelegramUser>,
+    #[serde(default)]
+    pub text: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramChat {
+    pub id: i64,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct TelegramUser {
+    pub id: i64,
+    #[serde(default)]
+    pub username: Option<String>,
+    #[serde(default)]
+    pub first_name: String,
+}
+
+impl TelegramMessage {
+    /// The text after `/monitor` (or `/monitor@botname`), `None` for any
+    /// other message.
+    pub fn command_text(&self) -> Option<&str> {
+        let text = self.text.as_deref()?.trim();
+        let rest = text.strip_prefix("/monitor")?;
+        let rest = match rest.strip_prefix('@') {
+            Some(mention) => mention.split_once(char::is_whitespace).map_or("", |(_, rest)| rest),
+            None if rest.is_empty() || rest.starts_with(char::is_whitespace) => rest,
+            None => return None,
+        };
+        Some(rest.trim())
+    }
+    
+    /// The sender's numeric id, which unlike the names cannot be changed.
+    pub fn user_id(&self) -> Option<i64> {
+        self.from.as_ref().map(|user| user.id)
+    }
+    
+    /// A name to show and record on silences, never used to authorize.
+    pub fn user(&self) -> String {
+        self.from.as_ref()
+            .map(|user| user.username.clone().unwrap_or_else(|| user.first_name.clone()))
+            .unwrap_or_default()
+    }
+}
+
+/// Checks Telegram's `X-Telegram-Bot-Api-Secret-Token` header.
+pub fn verify_telegram(config: &ChatOpsConfig, secret: Option<&str>) -> Result<()> {
+    let expected = config.telegram_secret_token.as_deref()
+        .ok_or_else(|| anyhow!("Telegram commands are disabled until chatops.telegram_secret_token is set"))?;
+    let given = secret.unwrap_or_default();
+    if expected.len() != given.len() || !openssl::memcmp::eq(expected.as_bytes(), given.as_bytes()) {
+        return Err(anyhow!("Invalid Telegram secret token"));
+    }
+    Ok(())
+}
+
+/// The role of a chat user by their Slack user ID or Telegram user id: admin
+/// only when listed in `chatops.admins`, so nobody is admin while it is empty.
+pub fn role(config: &ChatOpsConfig, user_id: &str) -> Role {
+    if !user_id.is_empty() && config.admins.iter().any(|admin| admin == user_id) {
+        Role::Admin
+    } else {
+        Role::Viewer
+    }
+}
+
+pub fn telegram_chat_allowed(config: &ChatOpsConfig, chat: i64) -> bool {
+    config.telegram_chats.is_empty() || config.telegram_chats.contains(&chat)
+}
+
+/// Converts a reply to Telegram HTML. Telegram's Markdown mode rejects
+/// unbalanced underscores, which rule and container names are full of.
+pub fn telegram_html(reply: &str) -> String {
+    let html = escape(reply);
+    let html = Regex::new(r"(?s)```\n?(.*?)\n?```").unwrap().replace_all(&html, "<pre>$1</pre>");
+    let html = Regex::new(r"`([^`\n]+)`").unwrap().replace_all(&html, "<code>$1</code>");
+    Regex::new(r"\*([^*\n]+)\*").unwrap().replace_all(&html, "<b>$1</b>").into_owned()
+}
+
+/// Converts a reply to plain text for email by dropping the Markdown markers.
+pub fn plain_text(reply: &str) -> String {
+    let text = Regex::new(r"(?m)^```\n?").unwrap().replace_all(reply, "");
+    let text = Regex::new(r"`([^`\n]+)`").unwrap().replace_all(&text, "$1");
+    Regex::new(r"\*([^*\n]+)\*").unwrap().replace_all(&text, "$1").into_owned()
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Example request from Slack's "Verifying requests from Slack" guide
+    const SLACK_SECRET: &str = "8f742231b10e8888abcd99yyyzzz85a5";
+    const SLACK_TIMESTAMP: &str = "1531420618";
+    const SLACK_BODY: &str = "token=xyzz0WbapA4vBCDEFasx0q6G&team_id=T1DC2JH3J&team_domain=testteamnow&channel_id=G8PSS9T3V&channel_name=foobar&user_id=U2CERLKJA&user_name=roadrunner&command=%2Fwebhook-collect&text=&response_url=https%3A%2F%2Fhooks.slack.com%2Fcommands%2FT1DC2JH3J%2F397700885554%2F96rGlfmibIGlgcZRskXaIFfN&trigger_id=398738663015.47445629121.803a0bc887a14d10d2c447fce8b6703c";
+    const SLACK_SIGNATURE: &str = "v0=a2114d57b48eac39b9ad189dd8316235a7b4a8d21a10bd27519666489c69b503";
+    
+    fn slack_config() -> ChatOpsConfig {
+        ChatOpsConfig { slack_signing_secret: Some(SLACK_SECRET.to_string()), ..Default::default() }
+    }
+    
+    fn sent_after(seconds: i64) -> DateTime<Utc> {
+        DateTime::from_timestamp(SLACK_TIMESTAMP.parse::<i64>().unwrap() + seconds, 0).unwrap()
+    }
+    
+    #[test]
+    fn parses_commands() {
+        assert_eq!(Command::parse("").unwrap(), Command::Help);
+        assert_eq!(Command::parse("  STATUS ").unwrap(), Command::Status);
+        assert_eq!(Command::parse("top").unwrap(), Command::Top(DEFAULT_TOP));
+        assert_eq!(Command::parse("top 10").unwrap(), Command::Top(10));
+        assert_eq!(Command::parse("silences").unwrap(), Command::Silences);
+        assert_eq!(Command::parse("ack disk").unwrap(), Command::Ack("disk".to_string()));
+        assert_eq!(
+            Command::parse("silence web 1h deploy of v2").unwrap(),
+            Command::Silence { target: "web".to_string(), duration: Duration::hours(1), comment: "deploy of v2".to_string() },
+        );
+        assert_eq!(
+            Command::parse("silence 30m").unwrap(),
+            Command::Silence { target: "*".to_string(), duration: Duration::minutes(30), comment: String::new() },
+        );
+    }
+    
+    #[test]
+    fn rejects_malformed_commands() {
+        for text in ["top many", "top -1", "silence", "silence web", "silence web soon", "ack", "reboot now"] {
+            assert!(Command::parse(text).is_err(), "{}", text);
+        }
+        assert!(Command::parse("reboot").unwrap_err().to_string().contains("`silences` active silences"));
+    }
+    
+    #[test]
+    fn verifies_slack_signatures() {
+        let config = slack_config();
+        let verify = |signature: &str, body: &str, now| verify_slack(&config, Some(SLACK_TIMESTAMP), Some(signature), body.as_bytes(), now);
+        assert!(verify(SLACK_SIGNATURE, SLACK_BODY, sent_after(0)).is_ok());
+        assert!(verify(SLACK_SIGNATURE, SLACK_BODY, sent_after(-SLACK_MAX_AGE)).is_ok());
+        assert!(verify(SLACK_SIGNATURE, &SLACK_BODY.replace("text=", "text=silence"), sent_after(0)).is_err());
+        assert!(verify(&SLACK_SIGNATURE.replace("v0=a", "v0=b"), SLACK_BODY, sent_after(0)).is_err());
+        assert!(verify(&SLACK_SIGNATURE[..20], SLACK_BODY, sent_after(0)).is_err());
+        assert!(verify(SLACK_SIGNATURE, SLACK_BODY, sent_after(SLACK_MAX_AGE + 1)).is_err());
+    }
+    
+    #[test]
+    fn slack_needs_a_secret_and_headers() {
+        let body = SLACK_BODY.as_bytes();
+        assert!(verify_slack(&ChatOpsConfig::default(), Some(SLACK_TIMESTAMP), Some(SLACK_SIGNATURE), body, sent_after(0)).is_err());
+        assert!(verify_slack(&slack_config(), None, Some(SLACK_SIGNATURE), body, sent_after(0)).is_err());
+        assert!(verify_slack(&slack_config(), Some(SLACK_TIMESTAMP), None, body, sent_after(0)).is_err());
+        assert!(verify_slack(&slack_config(), Some("yesterday"), Some(SLACK_SIGNATURE), body, sent_after(0)).is_err());
+    }
+    
+    #[test]
+    fn only_listed_ids_are_admins() {
+        let config = ChatOpsConfig { admins: vec!["U2CERLKJA".to_string(), "123456789".to_string()], ..Default::default() };
+        assert_eq!(role(&config, "U2CERLKJA"), Role::Admin);
+        assert_eq!(role(&config, "123456789"), Role::Admin);
+        assert_eq!(role(&config, "roadrunner"), Role::Viewer);
+        assert_eq!(role(&config, ""), Role::Viewer);
+        assert_eq!(role(&ChatOpsConfig::default(), ""), Role::Viewer);
+    }
+}
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+use reqwest::Client;
+use tokio::sync::OnceCell;
+use crate::config::CloudConfig;
+use log::debug;
+use anyhow::{Result, anyhow};
+
+const AWS_METADATA_URL: &str = "http://169.254.169.254/latest";
+const GCP_METADATA_URL: &str = "http://metadata.google.internal/computeMetadata/v1/instance";
+const AZURE_METADATA_URL: &str = "http://169.254.169.254/metadata/instance?api-version=2021-02-01";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudMetadata {
+    pub provider: String,
+    pub instance_id: String,
+    pub region: String,
+    pub instance_type: String,
+}
+
+static DETECTED: OnceCell<Option<CloudMetadata>> = OnceCell::const_new();
+
+impl CloudMetadata {
+    /// Prometheus labels identifying the instance, without braces.
+    pub fn prometheus_labels(&self) -> String {
+        format!(
+            "provider=\"{}\",instance_id=\"{}\",region=\"{}\",instance_type=\"{}\"",
+            self.provider, self.instance_id, self.region, self.instance_type
+        )
+    }
+}
+
+/// Probes the AWS, GCP and Azure metadata endpoints concurrently and returns the
+/// first one that answers. Returns `None` on bare metal or when detection is disabled.
+/// The endpoints are probed once per process, later calls return the first result.
+pub async fn detect(config: &CloudConfig) -> Option<CloudMetadata> {
+    DETECTED.get_or_init(|| probe(config)).await.clone()
+}
+
+async fn probe(config: &CloudConfig) -> Option<CloudMetadata> {
+    if !config.detect_metadata {
+        return None;
+    }
+    
+    let client = Client::builder()
+        .timeout(Duration::from_millis(config.metadata_timeout_ms))
+        .no_proxy()
+        .build()
+        .ok()?;
+    
+    let (aws, gcp, azure) = tokio::join!(
+        detect_aws(&client),
+        detect_gcp(&client),
+        detect_azure(&client),
+    );
+    
+    for (provider, result) in [("aws", aws), ("gcp", gcp), ("azure", azure)] {
+        match result {
+            Ok(metadata) => return Some(metadata),
+            Err(e) => debug!("No {} metadata: {}", provider, e),
+        }
+    }
+    
+    None
+}
+
+async fn detect_aws(client: &Client) -> Result<CloudMetadata> {
+    // IMDSv2 requires a session token
+    let token = client.put(format!("{}/api/token", AWS_METADATA_URL))
+        .header("X-aws-ec2-metadata-token-ttl-seconds", "60")
+        .send().await?
+        .error_for_status()?
+        .text().await?;
+    
+    let get = |path: &'static str| {
+        let request = client.get(format!("{}/meta-data/{}", AWS_METADATA_URL, path))
+            .header("X-aws-ec2-metadata-token", token.clone());
+        async move {
+            Ok::<String, anyhow::Error>(request.send().await?.error_for_status()?.text().await?)
+        }
+    };
+    
+    Ok(CloudMetadata {
+        provider: "aws".to_string(),
+        instance_id: get("instance-id").await?,
+        region: get("placement/region").await?,
+        instance_type: get("instance-type").await?,
+    })
+}
+
+async fn detect_gcp(client: &Client) -> Result<CloudMetadata> {
+    let get = |path: &'static str| {
+        let request = client.get(format!("{}/{}", GCP_METADATA_URL, path))
+            .header("Metadata-Flavor", "Google");
+        async move {
+            Ok::<String, anyhow::Error>(request.send().await?.error_for_status()?.text().await?)
+        }
+    };
+    
+    // Zone and machine type come back as full resource paths, e.g. projects/1/zones/us-central1-a
+    let last_segment = |value: String| value.rsplit('/').next().unwrap_or_default().to_string();
+    let zone = last_segment(get("zone").await?);
+    let region = zone.rsplit_once('-').map_or(zone.clone(), |(region, _)| region.to_string());
+    
+    Ok(CloudMetadata {
+        provider: "gcp".to_string(),
+        instance_id: get("id").await?,
+        region,
+        instance_type: last_segment(get("machine-type").await?),
+    })
+}
+
+async fn detect_azure(client: &Client) -> Result<CloudMetadata> {
+    let response: serde_json::Value = client.get(AZURE_METADATA_URL)
+        .header("Metadata", "true")
+        .send().await?
+        .error_for_status()?
+        .json().await?;
+    
+    let compute = response.get("compute").ok_or_else(|| anyhow!("Missing compute section"))?;
+    let field = |name: &str| compute.get(name)
+        .and_then(|value| value.as_str())
+        .unwrap_or("unknown")
+        .to_string();
+    
+    Ok(CloudMetadata {
+        provider: "azure".to_string(),
+        instance_id: field("vmId"),
+        region: field("location"),
+        instance_type: field("vmSize"),
+    })
+}
\ No newline at end of file
@@ -0,0 +1,172 @@
+use chrono::{DateTime, Utc};
+use log::warn;
+use regex::Regex;
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use tokio::time::timeout;
+use crate::alert::Severity;
+use crate::config::{parse_duration, CommandCheckConfig};
+
+/// Output kept in results and alert notes.
+const MAX_OUTPUT_CHARS: usize = 500;
+
+/// Outcome of the last run of a command check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CommandResult {
+    pub name: String,
+    /// `None` when the command was killed by a signal, timed out or could not start.
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    /// Text matched by `alert_pattern`.
+    pub matched: Option<String>,
+    /// Number captured by `value_pattern`.
+    pub value: Option<f64>,
+    pub output: String,
+    pub ran_at: DateTime<Utc>,
+}
+
+impl CommandResult {
+    pub fn is_failure(&self) -> bool {
+        self.timed_out || self.exit_code != Some(0) || self.matched.is_some()
+    }
+    
+    pub fn describe(&self) -> String {
+        let outcome = if self.timed_out {
+            "timed out".to_string()
+        } else if let Some(matched) = &self.matched {
+            format!("output matched '{}'", matched)
+        } else {
+            match self.exit_code {
+                Some(code) => format!("exit code {}", code),
+                None => "killed by signal".to_string(),
+            }
+        };
+        let mut description = format!("{}: {}", self.name, outcome);
+        if let Some(value) = self.value {
+            description.push_str(&format!(", value {}", value));
+        }
+        if !self.output.is_empty() {
+            description.push_str(&format!(" - {}", self.output));
+        }
+        description
+    }
+}
+
+/// A configured shell command with its compiled patterns and schedule.
+pub struct CommandCheck {
+    pub config: CommandCheckConfig,
+    interval: Option<Duration>,
+    timeout: Duration,
+    alert_pattern: Option<Regex>,
+    value_pattern: Option<Regex>,
+    last_run: Option<Instant>,
+    last_result: Option<CommandResult>,
+}
+
+/// Builds the configured checks, skipping those with an invalid interval,
+/// timeout or pattern.
+pub fn load_checks(configs: &[CommandCheckConfig]) -> Vec<CommandCheck> {
+    configs.iter()
+        .filter_map(|config| {
+            let interval = match config.interval.as_deref().map(parse_duration).transpose() {
+                Ok(interval) => interval,
+                Err(e) => {
+                    warn!("Command check {} has invalid interval: {}. Skipping.", config.name, e);
+                    return None;
+                }
+            };
+            let timeout = match parse_duration(&config.timeout) {
+                Ok(timeout) => timeout,
+                Err(e) => {
+                    warn!("Command check {} has invalid timeout: {}. Skipping.", config.name, e);
+                    return None;
+                }
+            };
+            let compile = |pattern: &Option<String>| pattern.as_deref().map(Regex::new).transpose();
+            let (alert_pattern, value_pattern) = match (compile(&config.alert_pattern), compile(&config.value_pattern)) {
+                (Ok(alert_pattern), Ok(value_pattern)) => (alert_pattern, value_pattern),
+                (Err(e), _) | (_, Err(e)) => {
+                    warn!("Command check {} has invalid pattern: {}. Skipping.", config.name, e);
+                    return None;
+                }
+            };
+            
+            Some(CommandCheck {
+                config: config.clone(),
+                interval,
+                timeout,
+                alert_pattern,
+                value_pattern,
+                last_run: None,
+                last_result: None,
+            })
+        })
+        .collect()
+}
+
+impl CommandCheck {
+    pub fn severity(&self) -> Severity {
+        if self.config.severity == "critical" { Severity::Critical } else { Severity::Warning }
+    }
+    
+    /// Runs the command when its interval has passed (every cycle without an
+    /// interval) and returns the latest result, so a failure stays active
+    /// between runs.
+    pub async fn poll(&mut self) -> &CommandResult {
+        let due = match (self.last_run, self.interval) {
+            (Some(last_run), Some(interval)) => last_run.elapsed() >= interval,
+            _ => true,
+        };
+        if due || self.last_result.is_none() {
+            self.last_run = Some(Instant::now());
+            self.last_result = Some(self.run().await);
+        }
+        self.last_result.as_ref().unwrap()
+    }
+    
+    async fn run(&self) -> CommandResult {
+        let mut result = CommandResult {
+            name: self.config.name.clone(),
+            exit_code: None,
+            timed_out: false,
+            matched: None,
+            value: None,
+            output: String::new(),
+            ran_at: Utc::now(),
+        };
+        
+        let child = Command::new("sh")
+            .arg("-c")
+            .arg(&self.config.command)
+            .env("CHECK_NAME", &self.config.name)
+            .kill_on_drop(true)
+            .output();
+        let output = match timeout(self.timeout, child).await {
+            Ok(Ok(output)) => output,
+            Ok(Err(e)) => {
+                result.output = format!("failed to run: {}", e);
+                return result;
+            }
+            Err(_) => {
+                result.timed_out = true;
+                result.output = format!("no result after {:?}", self.timeout);
+                return result;
+            }
+        };
+        
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let text = format!("{}{}", stdout, stderr);
+        result.exit_code = output.status.code();
+        result.matched = self.alert_pattern.as_ref()
+            .and_then(|pattern| pattern.find(&text))
+            .map(|matched| matched.as_str().to_string());
+        result.value = self.value_pattern.as_ref()
+            .and_then(|pattern| pattern.captures(&text))
+            .and_then(|captures| captures.get(1).or_else(|| captures.get(0)))
+            .and_then(|value| value.as_str().trim().parse().ok());
+        result.output = text.trim().chars().take(MAX_OUTPUT_CHARS).collect();
+        result
+    }
+}
\ No newline at end of file
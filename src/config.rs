@@ -1,19 +1,209 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fs;
-use anyhow::Result;
+use std::time::Duration;
+use anyhow::{Result, anyhow};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
     pub monitoring: MonitoringConfig,
     pub email: EmailConfig,
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub otlp: OtlpConfig,
+    /// Emoji in email subjects, email bodies and terminal output. Some
+    /// corporate mail gateways mangle them.
+    #[serde(default = "default_true")]
+    pub emoji: bool,
+    /// Zone for timestamps in alerts and status output: `UTC`, `local`, a
+    /// fixed offset such as `+07:00` or an IANA name such as `Asia/Jakarta`.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    #[serde(default)]
+    pub units: UnitsConfig,
+    #[serde(default)]
+    pub watchdog: WatchdogConfig,
+    #[serde(default)]
+    pub alert_rules: HashMap<String, AlertRuleConfig>,
+    #[serde(default)]
+    pub cloud: CloudConfig,
+    #[serde(default)]
+    pub kubernetes: KubernetesConfig,
+    #[serde(default)]
+    pub docker: DockerConfig,
+    #[serde(default)]
+    pub digest: DigestConfig,
+    #[serde(default)]
+    pub cost: CostConfig,
+    #[serde(default)]
+    pub scaling: ScalingConfig,
+    #[serde(default)]
+    pub resource_audit: ResourceAuditConfig,
+    #[serde(default)]
+    pub security_audit: SecurityAuditConfig,
+    #[serde(default)]
+    pub dispatch: DispatchConfig,
+    #[serde(default)]
+    pub http: HttpConfig,
+    #[serde(default)]
+    pub grpc: GrpcConfig,
+    #[serde(default)]
+    pub chatops: ChatOpsConfig,
+    #[serde(default)]
+    pub email_commands: EmailCommandsConfig,
+    #[serde(default)]
+    pub dependencies: DependencyConfig,
+    #[serde(default)]
+    pub incidents: IncidentConfig,
+    #[serde(default)]
+    pub escalation: EscalationConfig,
+    #[serde(default)]
+    pub oncall: OnCallConfig,
+    #[serde(default)]
+    pub maintenance_calendar: MaintenanceCalendarConfig,
+    #[serde(default)]
+    pub tickets: TicketConfig,
+    #[serde(default)]
+    pub correlation: CorrelationConfig,
+    #[serde(default)]
+    pub inhibit_rules: Vec<InhibitRuleConfig>,
+    #[serde(default)]
+    pub redaction: RedactionConfig,
+    #[serde(default)]
+    pub audit: AuditConfig,
+    #[serde(default)]
+    pub inventory: InventoryConfig,
+    #[serde(default)]
+    pub disk_latency: DiskLatencyConfig,
+    #[serde(default)]
+    pub conntrack: ConntrackConfig,
+    #[serde(default)]
+    pub cpu_frequency: CpuFrequencyConfig,
+    #[serde(default)]
+    pub ups: Vec<UpsConfig>,
+    #[serde(default)]
+    pub libvirt: LibvirtConfig,
+    #[serde(default)]
+    pub lxd: LxdConfig,
+    #[serde(default)]
+    pub zfs: ZfsConfig,
+    #[serde(default)]
+    pub storage_pools: StoragePoolConfig,
+    #[serde(default)]
+    pub command_checks: Vec<CommandCheckConfig>,
+    #[serde(default)]
+    pub cron: CronConfig,
+    #[serde(default)]
+    pub ping: PingConfig,
+    #[serde(default)]
+    pub fingerprints: FingerprintConfig,
+    #[serde(default)]
+    pub outbound: OutboundConfig,
+    #[serde(default)]
+    pub image_pins: ImagePinConfig,
+    #[serde(default)]
+    pub container_groups: Vec<ContainerGroupConfig>,
+    #[serde(default)]
+    pub filesystems: FilesystemConfig,
+    #[serde(default)]
+    pub interfaces: InterfaceConfig,
+    #[serde(default)]
+    pub cpu_cores: CpuCoreConfig,
+    #[serde(default)]
+    pub storage: StorageConfig,
+    #[serde(default)]
+    pub ha: HaConfig,
+    #[serde(default)]
+    pub history: HistoryConfig,
+    #[serde(default)]
+    pub trend_rules: Vec<TrendRuleConfig>,
+    #[serde(default)]
+    pub memory_leak: MemoryLeakConfig,
+    #[serde(default)]
+    pub reports: Vec<ReportConfig>,
+    #[serde(default)]
+    pub statuspage: StatusPageConfig,
+    #[serde(default)]
+    pub webhooks: Vec<WebhookConfig>,
+    #[serde(default)]
+    pub chat: Vec<ChatConfig>,
+    #[serde(default)]
+    pub apprise: Vec<AppriseConfig>,
+    #[serde(default)]
+    pub webpush: WebPushConfig,
+    #[serde(default)]
+    pub push_monitors: Vec<PushMonitorConfig>,
+    #[serde(default)]
+    pub network: NetworkConfig,
+    #[serde(default)]
+    pub self_update: SelfUpdateConfig,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MonitoringConfig {
     pub cpu_threshold: f64,
+    #[serde(default = "default_cpu_critical_threshold")]
+    pub cpu_critical_threshold: f64,
+    /// Host memory usage in percent above which the `memory` rule fires, 0 to disable.
+    #[serde(default = "default_memory_threshold")]
+    pub memory_threshold: f64,
+    #[serde(default = "default_memory_critical_threshold")]
+    pub memory_critical_threshold: f64,
+    /// Swap usage in percent above which the `swap` rule fires, 0 to disable.
+    #[serde(default)]
+    pub swap_threshold: f64,
+    #[serde(default = "default_swap_critical_threshold")]
+    pub swap_critical_threshold: f64,
+    /// Usage in percent of the root disk or any other mount above which the
+    /// `disk` rule fires, 0 to disable.
+    #[serde(default = "default_disk_threshold")]
+    pub disk_threshold: f64,
+    #[serde(default = "default_disk_critical_threshold")]
+    pub disk_critical_threshold: f64,
+    /// Processes listed by CPU and by memory in `cpu`, `hot_core` and `swap` alerts,
+    /// 0 to leave them out.
+    #[serde(default = "default_top_processes")]
+    pub top_processes: usize,
     pub check_interval: u64,
     pub docker_stats_timeout: u64,
+    #[serde(default = "default_deploy_grace_label")]
+    pub deploy_grace_label: String,
+    #[serde(default)]
+    pub deploy_grace_period: u64,
+    #[serde(default = "default_state_file")]
+    pub state_file: String,
+    #[serde(default)]
+    pub unlimited_container_cpu_threshold: f64,
+    #[serde(default)]
+    pub unlimited_container_memory_mb: u64,
+    #[serde(default = "default_newcomer_max_age")]
+    pub newcomer_max_age: u64,
+    #[serde(default)]
+    pub newcomer_cpu_threshold: f64,
+    #[serde(default)]
+    pub newcomer_memory_threshold: f64,
+    #[serde(default)]
+    pub state_dump_file: Option<String>,
+    /// File rewritten with the full snapshot after every cycle.
+    #[serde(default)]
+    pub snapshot_file: Option<String>,
+    #[serde(default)]
+    pub splay: u64,
+    #[serde(default)]
+    pub align_to_interval: bool,
+    #[serde(default)]
+    pub incident_check_interval: u64,
+    /// What the continuous schedule does when a cycle overruns its slot:
+    /// `skip` the missed checks, `delay` the cadence or `burst` to catch up.
+    #[serde(default = "default_missed_tick_behavior")]
+    pub missed_tick_behavior: String,
+    /// Deployment environment, e.g. `prod`, available to subject templates.
+    #[serde(default)]
+    pub environment: Option<String>,
+    /// Resident memory of the monitor itself above which it logs a warning,
+    /// 0 to disable.
+    #[serde(default = "default_memory_budget_mb")]
+    pub memory_budget_mb: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -24,6 +214,39 @@ pub struct EmailConfig {
     pub sender_email: String,
     pub sender_password: String,
     pub recipient_email: String,
+    #[serde(default = "default_queue_file")]
+    pub queue_file: String,
+    #[serde(default = "default_queue_max_size")]
+    pub queue_max_size: usize,
+    #[serde(default)]
+    pub signing: Option<EmailSigningConfig>,
+    /// Tera template for alert subjects, e.g.
+    /// `[{{ environment | upper }}][{{ severity }}] {{ hostname }}: {{ summary }}`.
+    #[serde(default)]
+    pub subject_template: Option<String>,
+    /// Overrides `timezone` for the recipient's emails.
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+/// Signs outgoing emails so recipients can verify they came from this host.
+/// `method` is "smime" (certificate + private_key in PEM) or "pgp" (a key
+/// from the local gpg keyring).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailSigningConfig {
+    pub method: String,
+    #[serde(default)]
+    pub certificate: Option<String>,
+    #[serde(default)]
+    pub private_key: Option<String>,
+    #[serde(default)]
+    pub key_password: Option<String>,
+    #[serde(default)]
+    pub chain: Option<String>,
+    #[serde(default)]
+    pub key_id: Option<String>,
+    #[serde(default)]
+    pub gpg_home: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -32,6 +255,1544 @@ pub struct LoggingConfig {
     pub file: String,
     pub max_size_mb: u32,
     pub backup_count: u32,
+    /// `text` or `json` (one object per line, with the cycle span fields).
+    #[serde(default = "default_log_format")]
+    pub format: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct WatchdogConfig {
+    #[serde(default)]
+    pub processes: Vec<ProcessCheckConfig>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessCheckConfig {
+    pub name: String,
+    #[serde(default)]
+    pub process_name: Option<String>,
+    #[serde(default)]
+    pub cmdline: Option<String>,
+    #[serde(default)]
+    pub pidfile: Option<String>,
+    #[serde(default)]
+    pub restart_command: Option<String>,
+}
+
+/// Per-rule metadata keyed by rule name (`cpu`, `container_cpu`, `process`).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct AlertRuleConfig {
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub runbook_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CloudConfig {
+    #[serde(default = "default_true")]
+    pub detect_metadata: bool,
+    #[serde(default = "default_metadata_timeout_ms")]
+    pub metadata_timeout_ms: u64,
+}
+
+impl Default for CloudConfig {
+    fn default() -> Self {
+        Self {
+            detect_metadata: true,
+            metadata_timeout_ms: default_metadata_timeout_ms(),
+        }
+    }
+}
+
+/// In-cluster mode, detected when the monitor runs in a pod. `node_name_env`
+/// names the downward API variable holding the node name; `host_root` is where
+/// the node's `/` is mounted, whose disk is then reported instead of the pod's.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KubernetesConfig {
+    #[serde(default = "default_true")]
+    pub detect: bool,
+    #[serde(default = "default_node_name_env")]
+    pub node_name_env: String,
+    #[serde(default = "default_host_root")]
+    pub host_root: String,
+}
+
+impl Default for KubernetesConfig {
+    fn default() -> Self {
+        Self {
+            detect: true,
+            node_name_env: default_node_name_env(),
+            host_root: default_host_root(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DigestConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_digest_interval_hours")]
+    pub interval_hours: u64,
+}
+
+impl Default for DigestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_hours: default_digest_interval_hours(),
+        }
+    }
+}
+
+/// Right-sizing report settings. `prices` maps instance types to a monthly price.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CostConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_cost_report_interval_days")]
+    pub report_interval_days: u64,
+    #[serde(default)]
+    pub instance_type: Option<String>,
+    #[serde(default)]
+    pub prices: HashMap<String, f64>,
+    #[serde(default = "default_downsize_p95")]
+    pub downsize_cpu_p95: f64,
+    #[serde(default = "default_downsize_p95")]
+    pub downsize_memory_p95: f64,
+    #[serde(default = "default_upsize_p95")]
+    pub upsize_cpu_p95: f64,
+    #[serde(default = "default_upsize_p95")]
+    pub upsize_memory_p95: f64,
+}
+
+impl Default for CostConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            report_interval_days: default_cost_report_interval_days(),
+            instance_type: None,
+            prices: HashMap::new(),
+            downsize_cpu_p95: default_downsize_p95(),
+            downsize_memory_p95: default_downsize_p95(),
+            upsize_cpu_p95: default_upsize_p95(),
+            upsize_memory_p95: default_upsize_p95(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ScalingConfig {
+    #[serde(default)]
+    pub hooks: Vec<ScalingHookConfig>,
+}
+
+/// A scale-up/scale-down action. `metric` is `cpu` or `memory`, `direction` is
+/// `above` or `below`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScalingHookConfig {
+    pub name: String,
+    pub metric: String,
+    pub direction: String,
+    pub threshold: f64,
+    #[serde(default = "default_sustained_checks")]
+    pub sustained_checks: u32,
+    #[serde(default = "default_scaling_cooldown")]
+    pub cooldown: String,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// Alerts sent as one notification when they fire in the same cycle. The
+/// `merge` rules' alerts are folded into the `primary` rule's alert, whose
+/// summary then names the `top_contributors` containers by CPU.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_correlation_rules")]
+    pub rules: Vec<CorrelationRuleConfig>,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            rules: default_correlation_rules(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CorrelationRuleConfig {
+    pub primary: String,
+    pub merge: Vec<String>,
+    #[serde(default = "default_top_contributors")]
+    pub top_contributors: usize,
+}
+
+/// While a rule matching `source` fires, notifications of rules matching
+/// `target` are dropped by the dispatcher. Both are glob patterns of rule
+/// names, e.g. `container_*`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InhibitRuleConfig {
+    pub source: Vec<String>,
+    pub target: Vec<String>,
+}
+
+/// Scrubbing of alerts before they leave the host. Every match of the
+/// `patterns` regexes in summaries, notes, container names, images, label
+/// values and process names is replaced by `replacement`, only its capture
+/// groups when it has any (`password=(\S+)` keeps `password=`). Container
+/// labels whose key matches a `labels` glob, case-insensitively, have their
+/// value replaced entirely.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RedactionConfig {
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default = "default_redaction_labels")]
+    pub labels: Vec<String>,
+    #[serde(default = "default_redaction_replacement")]
+    pub replacement: String,
+}
+
+impl Default for RedactionConfig {
+    fn default() -> Self {
+        Self {
+            patterns: Vec::new(),
+            labels: default_redaction_labels(),
+            replacement: default_redaction_replacement(),
+        }
+    }
+}
+
+/// Append-only record of notifications, remediation actions, silences,
+/// acknowledgements and threshold changes, one JSON object per line in
+/// `file`, read with `audit list`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_audit_file")]
+    pub file: String,
+}
+
+impl Default for AuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            file: default_audit_file(),
+        }
+    }
+}
+
+/// Docker API access. The monitor only observes unless
+/// `allow_container_actions` is set, which enables restarting, killing and
+/// removing containers (`container` command, `resource_audit.auto_prune`).
+/// `control_host` is the endpoint for those actions (`unix://` or
+/// `tcp://`), defaulting to `DOCKER_HOST`. `read_only` is for a socket
+/// behind a proxy that only forwards GET requests and overrides
+/// `allow_container_actions`; also set by `--docker-socket-ro`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct DockerConfig {
+    #[serde(default)]
+    pub read_only: bool,
+    #[serde(default)]
+    pub allow_container_actions: bool,
+    #[serde(default)]
+    pub control_host: Option<String>,
+}
+
+/// Orphaned Docker resource audit. `exclude` holds glob patterns of resource
+/// names that are never pruned; containers are only pruned once they have
+/// been stopped for `prune_min_age`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResourceAuditConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub auto_prune: bool,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    #[serde(default = "default_prune_min_age")]
+    pub prune_min_age: String,
+}
+
+impl Default for ResourceAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            auto_prune: false,
+            exclude: Vec::new(),
+            prune_min_age: default_prune_min_age(),
+        }
+    }
+}
+
+/// Units for sizes in emails, notes and status output. `system` is `binary`
+/// (KiB, MiB, GiB) or `decimal` (kB, MB, GB); `locale` (e.g. `id_ID`) picks
+/// the decimal separator and defaults to `LC_ALL`/`LC_NUMERIC`/`LANG`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UnitsConfig {
+    #[serde(default = "default_unit_system")]
+    pub system: String,
+    #[serde(default)]
+    pub locale: Option<String>,
+}
+
+impl Default for UnitsConfig {
+    fn default() -> Self {
+        Self {
+            system: default_unit_system(),
+            locale: None,
+        }
+    }
+}
+
+/// Export of the cycle, container stats and notification spans to an
+/// OpenTelemetry collector over OTLP/HTTP. Needs a build with the `otlp`
+/// feature. `sample_ratio` is the share of traces kept (0.0 to 1.0).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OtlpConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_otlp_endpoint")]
+    pub endpoint: String,
+    #[serde(default = "default_otlp_service_name")]
+    pub service_name: String,
+    #[serde(default = "default_otlp_sample_ratio")]
+    pub sample_ratio: f64,
+}
+
+impl Default for OtlpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            endpoint: default_otlp_endpoint(),
+            service_name: default_otlp_service_name(),
+            sample_ratio: default_otlp_sample_ratio(),
+        }
+    }
+}
+
+/// Security audit of running containers, reported in the digest. Bind
+/// mounts of `sensitive_paths` (or their parents) are flagged; `exclude` holds
+/// glob patterns of container names that are skipped.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SecurityAuditConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_sensitive_paths")]
+    pub sensitive_paths: Vec<String>,
+    #[serde(default = "default_true")]
+    pub check_env: bool,
+    #[serde(default)]
+    pub exclude: Vec<String>,
+}
+
+impl Default for SecurityAuditConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            sensitive_paths: default_sensitive_paths(),
+            check_env: true,
+            exclude: Vec::new(),
+        }
+    }
+}
+
+/// Notification dispatch queue between rule evaluation and notifiers.
+/// `overflow_policy` is `drop_oldest` or `drop_newest`; with `merge_duplicates`
+/// a queued alert is replaced by a newer one for the same rule. Alerts are cut
+/// to `max_rows` rows per list for every channel (0 disables the limit), with
+/// the full details attached to the email as `attachment` (`csv`, `json` or `none`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DispatchConfig {
+    #[serde(default = "default_dispatch_queue_size")]
+    pub queue_size: usize,
+    #[serde(default = "default_dispatch_concurrency")]
+    pub concurrency: usize,
+    #[serde(default = "default_overflow_policy")]
+    pub overflow_policy: String,
+    #[serde(default = "default_true")]
+    pub merge_duplicates: bool,
+    #[serde(default = "default_dispatch_drain_timeout")]
+    pub drain_timeout: u64,
+    #[serde(default = "default_dispatch_max_rows")]
+    pub max_rows: usize,
+    #[serde(default = "default_dispatch_attachment")]
+    pub attachment: String,
+}
+
+impl Default for DispatchConfig {
+    fn default() -> Self {
+        Self {
+            queue_size: default_dispatch_queue_size(),
+            concurrency: default_dispatch_concurrency(),
+            overflow_policy: default_overflow_policy(),
+            merge_duplicates: true,
+            drain_timeout: default_dispatch_drain_timeout(),
+            max_rows: default_dispatch_max_rows(),
+            attachment: default_dispatch_attachment(),
+        }
+    }
+}
+
+/// Built-in HTTP endpoint, started in continuous mode. `api_token` is an
+/// admin token the REST and gRPC APIs require for writes such as creating
+/// silences; without any admin token those calls are refused. With `users`
+/// set, the dashboard and read API need a viewer or admin token as well.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_http_listen")]
+    pub listen: String,
+    #[serde(default)]
+    pub api_token: Option<String>,
+    #[serde(default)]
+    pub users: Vec<HttpUserConfig>,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: default_http_listen(),
+            api_token: None,
+            users: Vec::new(),
+        }
+    }
+}
+
+/// A named API token. `role` is `viewer` (read only) or `admin`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HttpUserConfig {
+    pub name: String,
+    pub token: String,
+    #[serde(default = "default_http_role")]
+    pub role: String,
+}
+
+/// Slash commands (`/monitor status`, `/monitor top`, `/monitor silence web 1h`)
+/// served on the HTTP endpoint. Slack requests are checked against
+/// `slack_signing_secret`, Telegram webhooks against `telegram_secret_token`
+/// and, when set, `telegram_chats`; a platform without its secret is refused.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ChatOpsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub slack_signing_secret: Option<String>,
+    #[serde(default)]
+    pub telegram_secret_token: Option<String>,
+    #[serde(default)]
+    pub telegram_chats: Vec<i64>,
+    /// Slack user IDs (`U…`) and numeric Telegram user ids allowed to create
+    /// silences and acknowledge alerts; nobody can while it is empty. Names
+    /// are not used because users can change them.
+    #[serde(default)]
+    pub admins: Vec<String>,
+}
+
+/// Commands sent by email (`STATUS`, `SILENCE 2h`) for admins without chat
+/// access. The inbox is polled over IMAPS; only messages signed with S/MIME
+/// by one of `trusted_certificates`, or with PGP by one of `trusted_keys` in
+/// the `gpg_home` keyring, are executed. Replies go
+/// out through the `email` SMTP settings.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmailCommandsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub imap_server: String,
+    #[serde(default = "default_imap_port")]
+    pub imap_port: u16,
+    #[serde(default)]
+    pub username: String,
+    #[serde(default)]
+    pub password: String,
+    #[serde(default = "default_imap_mailbox")]
+    pub mailbox: String,
+    #[serde(default = "default_email_command_poll")]
+    pub poll_interval: u64,
+    /// `smime` or `pgp`.
+    #[serde(default = "default_email_command_method")]
+    pub method: String,
+    #[serde(default)]
+    pub trusted_certificates: Option<String>,
+    #[serde(default)]
+    pub gpg_home: Option<String>,
+    #[serde(default)]
+    pub trusted_keys: Vec<String>,
+    /// Addresses allowed to issue commands, matched against the addresses in
+    /// the signing certificate or key rather than the unsigned `From:`. Any
+    /// trusted signer when empty.
+    #[serde(default)]
+    pub allowed_senders: Vec<String>,
+    /// Commands signed longer ago than this, or this far in the future, are
+    /// refused as replays.
+    #[serde(default = "default_email_command_max_age")]
+    pub max_age: String,
+}
+
+impl Default for EmailCommandsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            imap_server: String::new(),
+            imap_port: default_imap_port(),
+            username: String::new(),
+            password: String::new(),
+            mailbox: default_imap_mailbox(),
+            poll_interval: default_email_command_poll(),
+            method: default_email_command_method(),
+            trusted_certificates: None,
+            gpg_home: None,
+            trusted_keys: Vec::new(),
+            allowed_senders: Vec::new(),
+            max_age: default_email_command_max_age(),
+        }
+    }
+}
+
+/// gRPC mirror of the REST API, started in continuous mode. Needs a build
+/// with the `grpc` feature.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GrpcConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_grpc_listen")]
+    pub listen: String,
+}
+
+impl Default for GrpcConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: default_grpc_listen(),
+        }
+    }
+}
+
+/// Escalation of rules left firing without acknowledgement. Tier `n` is
+/// notified once the rule has fired for its `after_minutes`, until the rule
+/// is acknowledged or resolves. `rules` limits escalation to some rules, all
+/// when empty.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct EscalationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub tiers: Vec<EscalationTierConfig>,
+}
+
+/// Who a tier notifies: email addresses, and channels by the name the
+/// notifiers report them as, e.g. `webhook:pagerduty` or `mattermost:ops`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EscalationTierConfig {
+    pub name: String,
+    pub after_minutes: u64,
+    #[serde(default)]
+    pub emails: Vec<String>,
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+/// Who receives the emails otherwise sent to `email.recipient_email`. An
+/// event covering the current time in the `ical_url` calendar wins, then the
+/// weekly (`rotation_days`) `rotation` counted from `rotation_start`, then
+/// `recipient_email` itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OnCallConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub rotation: Vec<String>,
+    /// RFC 3339 time of the first handoff, e.g. `2024-01-01T09:00:00+07:00`.
+    #[serde(default = "default_rotation_start")]
+    pub rotation_start: String,
+    #[serde(default = "default_rotation_days")]
+    pub rotation_days: u64,
+    #[serde(default)]
+    pub ical_url: Option<String>,
+    /// Seconds between calendar downloads.
+    #[serde(default = "default_ical_refresh")]
+    pub ical_refresh: u64,
+}
+
+impl Default for OnCallConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            rotation: Vec::new(),
+            rotation_start: default_rotation_start(),
+            rotation_days: default_rotation_days(),
+            ical_url: None,
+            ical_refresh: default_ical_refresh(),
+        }
+    }
+}
+
+/// An iCalendar feed of maintenance windows. Events whose title matches one
+/// of `patterns` (regular expressions, any event when empty) silence `rules`
+/// (every rule when empty), optionally only for containers matching the
+/// `container` glob.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MaintenanceCalendarConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub url: String,
+    /// Seconds between calendar downloads.
+    #[serde(default = "default_ical_refresh")]
+    pub refresh: u64,
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default)]
+    pub container: Option<String>,
+}
+
+impl Default for MaintenanceCalendarConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            url: String::new(),
+            refresh: default_ical_refresh(),
+            patterns: Vec::new(),
+            rules: Vec::new(),
+            container: None,
+        }
+    }
+}
+
+/// Issue tracker tickets for alerts that keep firing. A ticket is opened in
+/// GitHub Issues (`kind` `github`, `repository` as `owner/repo`) or Jira Cloud
+/// (`kind` `jira`, `project` key, `user` email with an API `token`) once a
+/// rule has fired for `after_minutes`, and commented on when it resolves,
+/// closed too with `close_on_resolve` (Jira through `resolve_transition`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TicketConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_ticket_kind")]
+    pub kind: String,
+    #[serde(default = "default_ticket_after_minutes")]
+    pub after_minutes: u64,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    /// Jira site, e.g. `https://example.atlassian.net`, or the GitHub API.
+    #[serde(default = "default_github_api_url")]
+    pub url: String,
+    #[serde(default)]
+    pub repository: String,
+    #[serde(default)]
+    pub project: String,
+    #[serde(default = "default_ticket_issue_type")]
+    pub issue_type: String,
+    #[serde(default)]
+    pub user: String,
+    #[serde(default)]
+    pub token: String,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    #[serde(default = "default_true")]
+    pub close_on_resolve: bool,
+    #[serde(default = "default_ticket_resolve_transition")]
+    pub resolve_transition: String,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for TicketConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            kind: default_ticket_kind(),
+            after_minutes: default_ticket_after_minutes(),
+            rules: Vec::new(),
+            url: default_github_api_url(),
+            repository: String::new(),
+            project: String::new(),
+            issue_type: default_ticket_issue_type(),
+            user: String::new(),
+            token: String::new(),
+            labels: Vec::new(),
+            close_on_resolve: true,
+            resolve_transition: default_ticket_resolve_transition(),
+            timeout_ms: default_webhook_timeout_ms(),
+        }
+    }
+}
+
+/// Container dependencies for alert correlation. `containers` maps a container
+/// name to the containers it depends on; the `label` (comma separated) can
+/// declare the same on the container itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DependencyConfig {
+    #[serde(default = "default_depends_on_label")]
+    pub label: String,
+    #[serde(default)]
+    pub containers: HashMap<String, Vec<String>>,
+    #[serde(default)]
+    pub suppress_downstream: bool,
+}
+
+impl Default for DependencyConfig {
+    fn default() -> Self {
+        Self {
+            label: default_depends_on_label(),
+            containers: HashMap::new(),
+            suppress_downstream: false,
+        }
+    }
+}
+
+/// Per-rule state machine. A rule fires after `pending_checks` consecutive
+/// alerting checks and is considered flapping when its percent state change over
+/// the last `flap_history` checks reaches `flap_high_threshold`, until it drops
+/// below `flap_low_threshold` (set `flap_high_threshold` to 0 to disable).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IncidentConfig {
+    #[serde(default = "default_pending_checks")]
+    pub pending_checks: u32,
+    #[serde(default = "default_flap_history")]
+    pub flap_history: usize,
+    #[serde(default = "default_flap_high_threshold")]
+    pub flap_high_threshold: f64,
+    #[serde(default = "default_flap_low_threshold")]
+    pub flap_low_threshold: f64,
+    #[serde(default = "default_true")]
+    pub notify_resolved: bool,
+}
+
+impl Default for IncidentConfig {
+    fn default() -> Self {
+        Self {
+            pending_checks: default_pending_checks(),
+            flap_history: default_flap_history(),
+            flap_high_threshold: default_flap_high_threshold(),
+            flap_low_threshold: default_flap_low_threshold(),
+            notify_resolved: true,
+        }
+    }
+}
+
+/// Tracking of started, stopped and image-changed containers between cycles.
+/// `max_changes` caps the changes kept until the next digest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryConfig {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    #[serde(default = "default_inventory_max_changes")]
+    pub max_changes: usize,
+}
+
+impl Default for InventoryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            max_changes: default_inventory_max_changes(),
+        }
+    }
+}
+
+/// Per block device I/O latency. `devices` limits the check to the listed
+/// devices (e.g. `sda`, `nvme0n1`); empty means every physical disk.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DiskLatencyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_read_latency_ms")]
+    pub read_threshold_ms: f64,
+    #[serde(default = "default_write_latency_ms")]
+    pub write_threshold_ms: f64,
+    #[serde(default = "default_sustained_checks")]
+    pub sustained_checks: u32,
+    #[serde(default)]
+    pub devices: Vec<String>,
+}
+
+impl Default for DiskLatencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            read_threshold_ms: default_read_latency_ms(),
+            write_threshold_ms: default_write_latency_ms(),
+            sustained_checks: default_sustained_checks(),
+            devices: Vec::new(),
+        }
+    }
+}
+
+/// nf_conntrack table usage, in percent of `nf_conntrack_max`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConntrackConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_conntrack_threshold")]
+    pub threshold: f64,
+    #[serde(default = "default_conntrack_critical_threshold")]
+    pub critical_threshold: f64,
+}
+
+impl Default for ConntrackConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            threshold: default_conntrack_threshold(),
+            critical_threshold: default_conntrack_critical_threshold(),
+        }
+    }
+}
+
+/// CPU frequency from cpufreq. The CPU is considered stuck when the average
+/// frequency stays below `min_frequency_percent` of the hardware maximum while
+/// usage is at least `usage_threshold` percent, for `sustained_checks` checks.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuFrequencyConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_min_frequency_percent")]
+    pub min_frequency_percent: f64,
+    #[serde(default = "default_frequency_usage_threshold")]
+    pub usage_threshold: f64,
+    #[serde(default = "default_sustained_checks")]
+    pub sustained_checks: u32,
+}
+
+impl Default for CpuFrequencyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_frequency_percent: default_min_frequency_percent(),
+            usage_threshold: default_frequency_usage_threshold(),
+            sustained_checks: default_sustained_checks(),
+        }
+    }
+}
+
+/// A UPS read through NUT (`kind: "nut"`, `ups` is the name in upsd) or
+/// apcupsd (`kind: "apcupsd"`). On battery raises a warning; it becomes
+/// critical when the UPS flags low battery or charge or runtime drop to
+/// `low_battery_percent` / `low_runtime_minutes`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpsConfig {
+    pub name: String,
+    #[serde(default = "default_ups_kind")]
+    pub kind: String,
+    #[serde(default = "default_ups_host")]
+    pub host: String,
+    #[serde(default)]
+    pub port: Option<u16>,
+    #[serde(default = "default_ups_name")]
+    pub ups: String,
+    #[serde(default = "default_low_battery_percent")]
+    pub low_battery_percent: f64,
+    #[serde(default = "default_low_runtime_minutes")]
+    pub low_runtime_minutes: f64,
+    #[serde(default = "default_metadata_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// KVM/QEMU guests read through `virsh`, connecting to `uri` (e.g.
+/// `qemu:///system`) or the libvirt default. Running guests above
+/// `cpu_threshold` (percent of their vCPUs) or `memory_threshold` raise a
+/// warning; crashed guests a critical alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LibvirtConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub uri: Option<String>,
+    #[serde(default = "default_vm_cpu_threshold")]
+    pub cpu_threshold: f64,
+    #[serde(default = "default_vm_memory_threshold")]
+    pub memory_threshold: f64,
+}
+
+impl Default for LibvirtConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            uri: None,
+            cpu_threshold: default_vm_cpu_threshold(),
+            memory_threshold: default_vm_memory_threshold(),
+        }
+    }
+}
+
+/// LXD instances read through the REST API on `socket` (the snap or
+/// package socket when unset), limited to `project` when set. They use the
+/// container CPU thresholds from `monitoring`.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct LxdConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub socket: Option<String>,
+    #[serde(default)]
+    pub project: Option<String>,
+}
+
+/// ZFS pool health through `zpool` and ARC statistics. Degraded pools, pools
+/// at `capacity_threshold` percent or more and scrub or data errors raise a
+/// warning; faulted or unavailable pools a critical alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ZfsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_zfs_capacity_threshold")]
+    pub capacity_threshold: f64,
+}
+
+impl Default for ZfsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity_threshold: default_zfs_capacity_threshold(),
+        }
+    }
+}
+
+/// Btrfs data/metadata allocation and LVM thin-pool usage, in percent of
+/// what the pool can still hold rather than what `df` reports.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StoragePoolConfig {
+    #[serde(default)]
+    pub btrfs: bool,
+    #[serde(default)]
+    pub lvm_thin: bool,
+    #[serde(default = "default_storage_pool_threshold")]
+    pub threshold: f64,
+    #[serde(default = "default_storage_pool_critical_threshold")]
+    pub critical_threshold: f64,
+}
+
+impl Default for StoragePoolConfig {
+    fn default() -> Self {
+        Self {
+            btrfs: false,
+            lvm_thin: false,
+            threshold: default_storage_pool_threshold(),
+            critical_threshold: default_storage_pool_critical_threshold(),
+        }
+    }
+}
+
+/// A shell command run every `interval` (every cycle when unset). It alerts
+/// when it exits nonzero, times out, or its output matches `alert_pattern`.
+/// The first capture group of `value_pattern` is exported as a metric.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CommandCheckConfig {
+    pub name: String,
+    pub command: String,
+    #[serde(default)]
+    pub interval: Option<String>,
+    #[serde(default = "default_command_timeout")]
+    pub timeout: String,
+    #[serde(default)]
+    pub alert_pattern: Option<String>,
+    #[serde(default)]
+    pub value_pattern: Option<String>,
+    #[serde(default = "default_trend_severity")]
+    pub severity: String,
+}
+
+/// Jobs run through `performance-monitor wrap`. Every wrapped job is
+/// checked for failures; jobs listed in `jobs` also alert when they have not
+/// started for `every` plus `grace`, or run longer than `max_duration`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CronConfig {
+    #[serde(default = "default_cron_state_dir")]
+    pub state_dir: String,
+    #[serde(default)]
+    pub jobs: Vec<CronJobConfig>,
+}
+
+impl Default for CronConfig {
+    fn default() -> Self {
+        Self {
+            state_dir: default_cron_state_dir(),
+            jobs: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CronJobConfig {
+    pub name: String,
+    pub every: String,
+    #[serde(default = "default_cron_grace")]
+    pub grace: String,
+    #[serde(default)]
+    pub max_duration: Option<String>,
+    #[serde(default = "default_trend_severity")]
+    pub severity: String,
+}
+
+/// Outbound connections. `ip_preference` ("auto", "ipv4" or "ipv6") orders
+/// the resolved addresses; the other family is still tried as a fallback.
+/// `proxy` routes outbound HTTP(S) through a proxy, overriding the
+/// HTTP_PROXY/HTTPS_PROXY environment variables used otherwise.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkConfig {
+    #[serde(default = "default_ip_preference")]
+    pub ip_preference: String,
+    #[serde(default = "default_happy_eyeballs_delay_ms")]
+    pub happy_eyeballs_delay_ms: u64,
+    #[serde(default)]
+    pub proxy: Option<String>,
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+impl Default for NetworkConfig {
+    fn default() -> Self {
+        Self {
+            ip_preference: default_ip_preference(),
+            happy_eyeballs_delay_ms: default_happy_eyeballs_delay_ms(),
+            proxy: None,
+            no_proxy: Vec::new(),
+        }
+    }
+}
+
+/// Where `self-update` looks for releases. The `.sig` of the release archive
+/// is verified with `public_key` (PEM) as well as its `.sha256`; nothing is
+/// installed without a key unless `require_signature` is turned off.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SelfUpdateConfig {
+    #[serde(default = "default_self_update_repository")]
+    pub repository: String,
+    #[serde(default = "default_github_api_url")]
+    pub api_url: String,
+    #[serde(default)]
+    pub public_key: Option<String>,
+    #[serde(default = "default_true")]
+    pub require_signature: bool,
+}
+
+impl Default for SelfUpdateConfig {
+    fn default() -> Self {
+        Self {
+            repository: default_self_update_repository(),
+            api_url: default_github_api_url(),
+            public_key: None,
+            require_signature: true,
+        }
+    }
+}
+
+/// Network path checks. A target is degraded when its packet loss exceeds
+/// `loss_threshold` percent or its average latency exceeds `latency_threshold_ms`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingConfig {
+    #[serde(default)]
+    pub targets: Vec<PingTargetConfig>,
+    #[serde(default = "default_ping_latency_threshold_ms")]
+    pub latency_threshold_ms: f64,
+    #[serde(default = "default_ping_loss_threshold")]
+    pub loss_threshold: f64,
+}
+
+impl Default for PingConfig {
+    fn default() -> Self {
+        Self {
+            targets: Vec::new(),
+            latency_threshold_ms: default_ping_latency_threshold_ms(),
+            loss_threshold: default_ping_loss_threshold(),
+        }
+    }
+}
+
+/// A host probed with ICMP, or TCP connects on `port` when ICMP is unavailable.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PingTargetConfig {
+    pub name: String,
+    pub host: String,
+    #[serde(default = "default_ping_count")]
+    pub count: u32,
+    #[serde(default = "default_metadata_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default)]
+    pub port: Option<u16>,
+}
+
+/// Services whose TLS certificate or SSH host key is recorded and compared
+/// every check. A changed fingerprint raises a critical alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FingerprintConfig {
+    #[serde(default)]
+    pub services: Vec<ServiceFingerprintConfig>,
+    #[serde(default = "default_metadata_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+impl Default for FingerprintConfig {
+    fn default() -> Self {
+        Self {
+            services: Vec::new(),
+            timeout_ms: default_metadata_timeout_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ServiceFingerprintConfig {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    /// `tls` or `ssh`.
+    pub kind: String,
+}
+
+/// Outbound TCP connections of the host and, with `containers`, of every
+/// running container, checked against `watchlists` each cycle. A connection
+/// to a listed address raises a critical alert.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OutboundConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_true")]
+    pub containers: bool,
+    #[serde(default)]
+    pub watchlists: Vec<WatchlistConfig>,
+    /// Hours between reloads of the watchlist files and URLs.
+    #[serde(default = "default_watchlist_refresh_hours")]
+    pub refresh_hours: u64,
+}
+
+impl Default for OutboundConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            containers: true,
+            watchlists: Vec::new(),
+            refresh_hours: default_watchlist_refresh_hours(),
+        }
+    }
+}
+
+/// A list of IP addresses, CIDR ranges or host names, one per line with `#`
+/// comments, read from `path`, downloaded from `url` or given as `entries`.
+/// Host names are resolved on every reload.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WatchlistConfig {
+    pub name: String,
+    #[serde(default)]
+    pub path: Option<String>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub entries: Vec<String>,
+}
+
+/// Expected image per container name or glob pattern: a tag (`nginx:1.25`),
+/// a repo digest (`nginx@sha256:...`) or an image ID (`sha256:...`).
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ImagePinConfig {
+    #[serde(default)]
+    pub containers: HashMap<String, String>,
+}
+
+/// Containers selected by `label` (`key=value`, or `key` for any value)
+/// whose combined usage must stay within `cpu_budget` CPUs and
+/// `memory_budget_mb`, 0 for no budget. The `group_budget` alert also goes to
+/// the team owning the group: `emails` and `channels`, named as in escalation
+/// tiers.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerGroupConfig {
+    pub name: String,
+    pub label: String,
+    #[serde(default)]
+    pub cpu_budget: f64,
+    #[serde(default)]
+    pub memory_budget_mb: u64,
+    #[serde(default)]
+    pub emails: Vec<String>,
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+/// Mount points watched by the `disk` rule and listed by `--status`, as glob
+/// patterns. An empty `include` watches every mount; `exclude` wins over it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FilesystemConfig {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default = "default_filesystem_exclude")]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub thresholds: Vec<MountThresholdConfig>,
+}
+
+impl Default for FilesystemConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: default_filesystem_exclude(),
+            thresholds: Vec::new(),
+        }
+    }
+}
+
+/// Disk thresholds for the mount points matching `mount`, in place of
+/// `disk_threshold` and `disk_critical_threshold`. The first matching entry
+/// applies; a `threshold` of 0 stops alerting on those mounts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MountThresholdConfig {
+    pub mount: String,
+    pub threshold: f64,
+    #[serde(default)]
+    pub critical_threshold: Option<f64>,
+}
+
+/// Network interfaces listed by `--status` and watched by the `bandwidth`
+/// rule, as glob patterns like `filesystems`. The rule fires when received or
+/// transmitted bandwidth of an interface is above `threshold_mbps` Mbit/s, 0
+/// to disable, and is critical above `critical_threshold_mbps`, 0 for never.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InterfaceConfig {
+    #[serde(default)]
+    pub include: Vec<String>,
+    #[serde(default = "default_interface_exclude")]
+    pub exclude: Vec<String>,
+    #[serde(default)]
+    pub threshold_mbps: f64,
+    #[serde(default)]
+    pub critical_threshold_mbps: f64,
+}
+
+impl Default for InterfaceConfig {
+    fn default() -> Self {
+        Self {
+            include: Vec::new(),
+            exclude: default_interface_exclude(),
+            threshold_mbps: 0.0,
+            critical_threshold_mbps: 0.0,
+        }
+    }
+}
+
+/// Per-core CPU usage. The `hot_core` rule fires when a single core stays
+/// above `threshold` percent for `sustained_checks` checks, however low the
+/// host average, 0 to disable, and is critical above `critical_threshold`,
+/// 0 for never.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuCoreConfig {
+    #[serde(default)]
+    pub threshold: f64,
+    #[serde(default)]
+    pub critical_threshold: f64,
+    #[serde(default = "default_sustained_checks")]
+    pub sustained_checks: u32,
+}
+
+impl Default for CpuCoreConfig {
+    fn default() -> Self {
+        Self {
+            threshold: 0.0,
+            critical_threshold: 0.0,
+            sustained_checks: default_sustained_checks(),
+        }
+    }
+}
+
+/// Where the monitor state (incidents, history, silences, runtime metrics)
+/// is kept. `sqlite` uses the database at `path`, by default
+/// `monitoring.state_file` with a `.db` extension; `postgres` the database at
+/// `url`, for replicas sharing one state (feature "postgres"); `file` the JSON
+/// `monitoring.state_file`. Rows are keyed by `namespace`, the hostname when
+/// empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StorageConfig {
+    #[serde(default = "default_storage_backend")]
+    pub backend: String,
+    #[serde(default)]
+    pub path: String,
+    #[serde(default)]
+    pub url: String,
+    #[serde(default)]
+    pub namespace: String,
+}
+
+impl Default for StorageConfig {
+    fn default() -> Self {
+        Self {
+            backend: default_storage_backend(),
+            path: String::new(),
+            url: String::new(),
+            namespace: String::new(),
+        }
+    }
+}
+
+/// Leader election between replicas sharing one `storage.namespace`: only
+/// the leader runs checks, sends notifications and saves the state, the
+/// others stand by and take over once its lease runs out. `backend` is
+/// `postgres` (a lease row in the `storage.url` database) or `file` (a lease
+/// in `lock_file` on storage every replica mounts). `lease_secs` 0 is three
+/// check intervals; `instance` names this replica, the pod name or
+/// `<hostname>-<pid>` when empty.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HaConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_ha_backend")]
+    pub backend: String,
+    #[serde(default = "default_ha_lock_file")]
+    pub lock_file: String,
+    #[serde(default)]
+    pub lease_secs: u64,
+    #[serde(default)]
+    pub instance: String,
+}
+
+impl Default for HaConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backend: default_ha_backend(),
+            lock_file: default_ha_lock_file(),
+            lease_secs: 0,
+            instance: String::new(),
+        }
+    }
+}
+
+/// Hourly usage history kept in the state file for trend reports and the
+/// `/history` dashboard. `top_containers` is how many containers per hour are
+/// kept by CPU and by memory, and how many are stacked on the dashboard.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryConfig {
+    #[serde(default = "default_history_retention_days")]
+    pub retention_days: u64,
+    #[serde(default = "default_history_top_containers")]
+    pub top_containers: usize,
+}
+
+impl Default for HistoryConfig {
+    fn default() -> Self {
+        Self {
+            retention_days: default_history_retention_days(),
+            top_containers: default_history_top_containers(),
+        }
+    }
+}
+
+/// Scheduled trend report email. `schedule` is `daily` or `weekly`; it is sent
+/// at `time` (local `HH:MM`), weekly reports on `weekday`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ReportConfig {
+    pub name: String,
+    #[serde(default = "default_report_schedule")]
+    pub schedule: String,
+    #[serde(default = "default_report_time")]
+    pub time: String,
+    #[serde(default = "default_report_weekday")]
+    pub weekday: String,
+}
+
+/// Rate-of-change rule. `metric` is `cpu`, `memory`, `disk` (percent) or
+/// `container_memory` (MB, limited to `containers` globs when set). A `rate`
+/// rule fires when the least-squares slope over `window` reaches `threshold`
+/// per hour; a `monotonic` rule fires when the metric only grew over `window`,
+/// by at least `threshold` in total.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrendRuleConfig {
+    pub name: String,
+    pub metric: String,
+    #[serde(default)]
+    pub containers: Vec<String>,
+    #[serde(default = "default_trend_condition")]
+    pub condition: String,
+    #[serde(default)]
+    pub threshold: f64,
+    #[serde(default = "default_trend_window")]
+    pub window: String,
+    #[serde(default = "default_trend_severity")]
+    pub severity: String,
+}
+
+/// Built-in memory leak heuristic for containers (all, or those matching
+/// `containers` globs). A container is flagged when its memory never dropped
+/// over `window` and the linear fit grows at least `slope_threshold_mb` per hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MemoryLeakConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_memory_leak_window")]
+    pub window: String,
+    #[serde(default = "default_memory_leak_slope_mb")]
+    pub slope_threshold_mb: f64,
+    #[serde(default)]
+    pub containers: Vec<String>,
+}
+
+impl Default for MemoryLeakConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window: default_memory_leak_window(),
+            slope_threshold_mb: default_memory_leak_slope_mb(),
+            containers: Vec::new(),
+        }
+    }
+}
+
+/// Status page with per-component uptime. Written to `output` every cycle
+/// when set, and served on `/status` by the HTTP endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusPageConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_statuspage_title")]
+    pub title: String,
+    #[serde(default)]
+    pub output: String,
+    #[serde(default = "default_statuspage_history_days")]
+    pub history_days: u64,
+}
+
+/// Generic HTTP webhook notification channel. Without a template the alert is
+/// sent as JSON; a Tera `template` (or `template_file`) renders a custom body.
+/// `rules` limits the hook to those alert rules, empty means all.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    pub name: String,
+    pub url: String,
+    #[serde(default = "default_webhook_method")]
+    pub method: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+    #[serde(default)]
+    pub template: Option<String>,
+    #[serde(default)]
+    pub template_file: Option<String>,
+    #[serde(default = "default_webhook_content_type")]
+    pub content_type: String,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Mattermost (`kind: mattermost`) or Rocket.Chat (`kind: rocketchat`) incoming
+/// webhook. `severity_channels` overrides `channel` per severity (`warning`,
+/// `critical`) or non-firing state (`resolved`, `flapping`).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChatConfig {
+    pub name: String,
+    pub kind: String,
+    pub url: String,
+    #[serde(default)]
+    pub channel: Option<String>,
+    #[serde(default)]
+    pub severity_channels: HashMap<String, String>,
+    #[serde(default = "default_chat_username")]
+    pub username: String,
+    /// Icon URL for Mattermost, emoji such as `:rotating_light:` for Rocket.Chat.
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Uptime Kuma push monitor (`kind: uptime_kuma`, `url` is the push URL) or
+/// Healthchecks.io check (`kind: healthchecks`, `url` is the ping URL) fed
+/// after every cycle. `checks` is a glob over status check names such as
+/// `Process nginx`; the monitor is down when any matching check is unhealthy.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushMonitorConfig {
+    pub name: String,
+    pub kind: String,
+    pub url: String,
+    #[serde(default = "default_push_monitor_checks")]
+    pub checks: String,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Apprise API server channel. With `key` the server's stored configuration
+/// is used (optionally filtered by `tag`); otherwise `urls` are sent along with
+/// each notification to the stateless endpoint.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AppriseConfig {
+    pub name: String,
+    pub server: String,
+    #[serde(default)]
+    pub key: Option<String>,
+    #[serde(default)]
+    pub tag: Option<String>,
+    #[serde(default)]
+    pub urls: Vec<String>,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+}
+
+/// Browser push notifications served by the HTTP endpoint. The VAPID key is
+/// generated into `vapid_key_file` on first start; browsers subscribe on `/push`.
+/// Subscriptions are limited to `max_subscriptions` endpoints on hosts matching
+/// `push_services`, so the monitor never posts alerts to arbitrary URLs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WebPushConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_vapid_key_file")]
+    pub vapid_key_file: String,
+    #[serde(default = "default_push_subscriptions_file")]
+    pub subscriptions_file: String,
+    #[serde(default = "default_vapid_subject")]
+    pub subject: String,
+    #[serde(default)]
+    pub rules: Vec<String>,
+    #[serde(default = "default_push_ttl")]
+    pub ttl: u64,
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+    #[serde(default = "default_max_push_subscriptions")]
+    pub max_subscriptions: usize,
+    /// Glob patterns of push service hosts, the browsers' own by default.
+    #[serde(default = "default_push_services")]
+    pub push_services: Vec<String>,
+}
+
+impl Default for StatusPageConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            title: default_statuspage_title(),
+            output: String::new(),
+            history_days: default_statuspage_history_days(),
+        }
+    }
+}
+
+impl Default for WebPushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            vapid_key_file: default_vapid_key_file(),
+            subscriptions_file: default_push_subscriptions_file(),
+            subject: default_vapid_subject(),
+            rules: Vec::new(),
+            ttl: default_push_ttl(),
+            timeout_ms: default_webhook_timeout_ms(),
+            max_subscriptions: default_max_push_subscriptions(),
+            push_services: default_push_services(),
+        }
+    }
 }
 
 impl Default for Config {
@@ -39,8 +1800,32 @@ impl Default for Config {
         Self {
             monitoring: MonitoringConfig {
                 cpu_threshold: 80.0,
+                cpu_critical_threshold: default_cpu_critical_threshold(),
+                memory_threshold: default_memory_threshold(),
+                memory_critical_threshold: default_memory_critical_threshold(),
+                swap_threshold: 0.0,
+                swap_critical_threshold: default_swap_critical_threshold(),
+                disk_threshold: default_disk_threshold(),
+                disk_critical_threshold: default_disk_critical_threshold(),
+                top_processes: default_top_processes(),
                 check_interval: 300,
                 docker_stats_timeout: 10,
+                deploy_grace_label: default_deploy_grace_label(),
+                deploy_grace_period: 0,
+                state_file: default_state_file(),
+                unlimited_container_cpu_threshold: 0.0,
+                unlimited_container_memory_mb: 0,
+                newcomer_max_age: default_newcomer_max_age(),
+                newcomer_cpu_threshold: 0.0,
+                newcomer_memory_threshold: 0.0,
+                state_dump_file: None,
+                snapshot_file: None,
+                splay: 0,
+                align_to_interval: false,
+                incident_check_interval: 0,
+                missed_tick_behavior: default_missed_tick_behavior(),
+                environment: None,
+                memory_budget_mb: default_memory_budget_mb(),
             },
             email: EmailConfig {
                 enabled: false,
@@ -49,24 +1834,628 @@ impl Default for Config {
                 sender_email: String::new(),
                 sender_password: String::new(),
                 recipient_email: String::new(),
+                queue_file: default_queue_file(),
+                queue_max_size: default_queue_max_size(),
+                signing: None,
+                subject_template: None,
+                timezone: None,
             },
             logging: LoggingConfig {
                 level: "INFO".to_string(),
                 file: "monitoring.log".to_string(),
                 max_size_mb: 10,
                 backup_count: 5,
+                format: default_log_format(),
             },
+            emoji: true,
+            timezone: default_timezone(),
+            units: UnitsConfig::default(),
+            otlp: OtlpConfig::default(),
+            watchdog: WatchdogConfig::default(),
+            alert_rules: HashMap::new(),
+            cloud: CloudConfig::default(),
+            kubernetes: KubernetesConfig::default(),
+            docker: DockerConfig::default(),
+            digest: DigestConfig::default(),
+            cost: CostConfig::default(),
+            scaling: ScalingConfig::default(),
+            resource_audit: ResourceAuditConfig::default(),
+            security_audit: SecurityAuditConfig::default(),
+            dispatch: DispatchConfig::default(),
+            http: HttpConfig::default(),
+            grpc: GrpcConfig::default(),
+            chatops: ChatOpsConfig::default(),
+            email_commands: EmailCommandsConfig::default(),
+            dependencies: DependencyConfig::default(),
+            incidents: IncidentConfig::default(),
+            escalation: EscalationConfig::default(),
+            oncall: OnCallConfig::default(),
+            maintenance_calendar: MaintenanceCalendarConfig::default(),
+            tickets: TicketConfig::default(),
+            correlation: CorrelationConfig::default(),
+            inhibit_rules: Vec::new(),
+            redaction: RedactionConfig::default(),
+            audit: AuditConfig::default(),
+            inventory: InventoryConfig::default(),
+            disk_latency: DiskLatencyConfig::default(),
+            conntrack: ConntrackConfig::default(),
+            cpu_frequency: CpuFrequencyConfig::default(),
+            ups: Vec::new(),
+            libvirt: LibvirtConfig::default(),
+            lxd: LxdConfig::default(),
+            zfs: ZfsConfig::default(),
+            storage_pools: StoragePoolConfig::default(),
+            command_checks: Vec::new(),
+            cron: CronConfig::default(),
+            ping: PingConfig::default(),
+            fingerprints: FingerprintConfig::default(),
+            outbound: OutboundConfig::default(),
+            image_pins: ImagePinConfig::default(),
+            container_groups: Vec::new(),
+            filesystems: FilesystemConfig::default(),
+            interfaces: InterfaceConfig::default(),
+            cpu_cores: CpuCoreConfig::default(),
+            storage: StorageConfig::default(),
+            ha: HaConfig::default(),
+            history: HistoryConfig::default(),
+            trend_rules: Vec::new(),
+            memory_leak: MemoryLeakConfig::default(),
+            reports: Vec::new(),
+            statuspage: StatusPageConfig::default(),
+            webhooks: Vec::new(),
+            chat: Vec::new(),
+            apprise: Vec::new(),
+            webpush: WebPushConfig::default(),
+            push_monitors: Vec::new(),
+            network: NetworkConfig::default(),
+            self_update: SelfUpdateConfig::default(),
         }
     }
 }
 
+fn default_cpu_critical_threshold() -> f64 {
+    95.0
+}
+
+fn default_memory_threshold() -> f64 {
+    90.0
+}
+
+fn default_memory_critical_threshold() -> f64 {
+    95.0
+}
+
+fn default_swap_critical_threshold() -> f64 {
+    80.0
+}
+
+fn default_disk_threshold() -> f64 {
+    90.0
+}
+
+fn default_disk_critical_threshold() -> f64 {
+    95.0
+}
+
+fn default_top_processes() -> usize {
+    5
+}
+
+fn default_deploy_grace_label() -> String {
+    "monitor.deploy_grace".to_string()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+fn default_log_format() -> String {
+    "text".to_string()
+}
+
+fn default_timezone() -> String {
+    "UTC".to_string()
+}
+
+fn default_unit_system() -> String {
+    "binary".to_string()
+}
+
+fn default_otlp_endpoint() -> String {
+    "http://localhost:4318/v1/traces".to_string()
+}
+
+fn default_otlp_service_name() -> String {
+    "performance-monitor".to_string()
+}
+
+fn default_otlp_sample_ratio() -> f64 {
+    1.0
+}
+
+fn default_metadata_timeout_ms() -> u64 {
+    1000
+}
+
+/// Host CPU absorbs the container CPU alerts of the same cycle.
+fn default_correlation_rules() -> Vec<CorrelationRuleConfig> {
+    vec![CorrelationRuleConfig {
+        primary: "cpu".to_string(),
+        merge: vec!["container_cpu".to_string(), "unlimited_container".to_string()],
+        top_contributors: default_top_contributors(),
+    }]
+}
+
+fn default_top_contributors() -> usize {
+    3
+}
+
+fn default_node_name_env() -> String {
+    "NODE_NAME".to_string()
+}
+
+fn default_host_root() -> String {
+    "/host".to_string()
+}
+
+fn default_missed_tick_behavior() -> String {
+    "skip".to_string()
+}
+
+fn default_memory_budget_mb() -> u64 {
+    64
+}
+
+fn default_state_file() -> String {
+    "monitor_state.json".to_string()
+}
+
+fn default_storage_backend() -> String {
+    "sqlite".to_string()
+}
+
+fn default_ha_backend() -> String {
+    "file".to_string()
+}
+
+fn default_ha_lock_file() -> String {
+    "monitor_leader.lock".to_string()
+}
+
+fn default_newcomer_max_age() -> u64 {
+    900
+}
+
+fn default_digest_interval_hours() -> u64 {
+    24
+}
+
+fn default_cost_report_interval_days() -> u64 {
+    30
+}
+
+fn default_downsize_p95() -> f64 {
+    20.0
+}
+
+fn default_upsize_p95() -> f64 {
+    85.0
+}
+
+fn default_sustained_checks() -> u32 {
+    3
+}
+
+fn default_min_frequency_percent() -> f64 {
+    60.0
+}
+
+fn default_frequency_usage_threshold() -> f64 {
+    80.0
+}
+
+fn default_vm_cpu_threshold() -> f64 {
+    80.0
+}
+
+fn default_vm_memory_threshold() -> f64 {
+    90.0
+}
+
+fn default_zfs_capacity_threshold() -> f64 {
+    80.0
+}
+
+fn default_audit_file() -> String {
+    "audit.jsonl".to_string()
+}
+
+fn default_redaction_labels() -> Vec<String> {
+    ["*password*", "*passwd*", "*secret*", "*token*", "*api_key*", "*apikey*", "*credential*"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect()
+}
+
+fn default_redaction_replacement() -> String {
+    "[REDACTED]".to_string()
+}
+
+fn default_filesystem_exclude() -> Vec<String> {
+    ["/snap/*", "/run/*", "/var/lib/docker/*", "/var/lib/kubelet/*"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect()
+}
+
+fn default_interface_exclude() -> Vec<String> {
+    ["lo", "veth*", "docker*", "br-*", "virbr*", "cali*", "flannel*", "cni*"]
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect()
+}
+
+fn default_sensitive_paths() -> Vec<String> {
+    ["/etc", "/root", "/boot", "/proc", "/sys", "/dev", "/var/lib/docker", "/var/run/docker.sock", "/run/docker.sock"]
+        .iter()
+        .map(|path| path.to_string())
+        .collect()
+}
+
+fn default_cron_state_dir() -> String {
+    "cron_jobs".to_string()
+}
+
+fn default_cron_grace() -> String {
+    "10m".to_string()
+}
+
+fn default_command_timeout() -> String {
+    "30s".to_string()
+}
+
+fn default_storage_pool_threshold() -> f64 {
+    80.0
+}
+
+fn default_storage_pool_critical_threshold() -> f64 {
+    95.0
+}
+
+fn default_ups_kind() -> String {
+    "nut".to_string()
+}
+
+fn default_ups_host() -> String {
+    "127.0.0.1".to_string()
+}
+
+fn default_ups_name() -> String {
+    "ups".to_string()
+}
+
+fn default_low_battery_percent() -> f64 {
+    30.0
+}
+
+fn default_low_runtime_minutes() -> f64 {
+    10.0
+}
+
+fn default_scaling_cooldown() -> String {
+    "30m".to_string()
+}
+
+fn default_queue_file() -> String {
+    "email_queue.json".to_string()
+}
+
+fn default_prune_min_age() -> String {
+    "24h".to_string()
+}
+
+fn default_queue_max_size() -> usize {
+    100
+}
+
+fn default_dispatch_queue_size() -> usize {
+    50
+}
+
+fn default_dispatch_concurrency() -> usize {
+    2
+}
+
+fn default_overflow_policy() -> String {
+    "drop_oldest".to_string()
+}
+
+fn default_dispatch_drain_timeout() -> u64 {
+    30
+}
+
+fn default_dispatch_max_rows() -> usize {
+    20
+}
+
+fn default_dispatch_attachment() -> String {
+    "csv".to_string()
+}
+
+fn default_http_listen() -> String {
+    "127.0.0.1:9273".to_string()
+}
+
+fn default_http_role() -> String {
+    "viewer".to_string()
+}
+
+fn default_grpc_listen() -> String {
+    "127.0.0.1:50051".to_string()
+}
+
+fn default_imap_port() -> u16 {
+    993
+}
+
+fn default_imap_mailbox() -> String {
+    "INBOX".to_string()
+}
+
+fn default_email_command_poll() -> u64 {
+    60
+}
+
+fn default_email_command_max_age() -> String {
+    "1h".to_string()
+}
+
+fn default_email_command_method() -> String {
+    "smime".to_string()
+}
+
+fn default_rotation_start() -> String {
+    "2024-01-01T09:00:00Z".to_string()
+}
+
+fn default_rotation_days() -> u64 {
+    7
+}
+
+fn default_ical_refresh() -> u64 {
+    900
+}
+
+fn default_ticket_kind() -> String {
+    "github".to_string()
+}
+
+fn default_ticket_after_minutes() -> u64 {
+    60
+}
+
+fn default_github_api_url() -> String {
+    "https://api.github.com".to_string()
+}
+
+fn default_self_update_repository() -> String {
+    "denisetiya/rust-server-monitoring".to_string()
+}
+
+fn default_ticket_issue_type() -> String {
+    "Task".to_string()
+}
+
+fn default_ticket_resolve_transition() -> String {
+    "Done".to_string()
+}
+
+fn default_watchlist_refresh_hours() -> u64 {
+    24
+}
+
+fn default_push_monitor_checks() -> String {
+    "*".to_string()
+}
+
+fn default_depends_on_label() -> String {
+    "monitor.depends_on".to_string()
+}
+
+fn default_pending_checks() -> u32 {
+    1
+}
+
+fn default_flap_history() -> usize {
+    21
+}
+
+fn default_flap_high_threshold() -> f64 {
+    50.0
+}
+
+fn default_flap_low_threshold() -> f64 {
+    25.0
+}
+
+fn default_inventory_max_changes() -> usize {
+    200
+}
+
+fn default_read_latency_ms() -> f64 {
+    50.0
+}
+
+fn default_write_latency_ms() -> f64 {
+    100.0
+}
+
+fn default_conntrack_threshold() -> f64 {
+    80.0
+}
+
+fn default_conntrack_critical_threshold() -> f64 {
+    95.0
+}
+
+fn default_ping_latency_threshold_ms() -> f64 {
+    100.0
+}
+
+fn default_ping_loss_threshold() -> f64 {
+    20.0
+}
+
+fn default_ip_preference() -> String {
+    "auto".to_string()
+}
+
+fn default_happy_eyeballs_delay_ms() -> u64 {
+    250
+}
+
+fn default_ping_count() -> u32 {
+    3
+}
+
+fn default_history_retention_days() -> u64 {
+    35
+}
+
+fn default_history_top_containers() -> usize {
+    5
+}
+
+fn default_report_schedule() -> String {
+    "weekly".to_string()
+}
+
+fn default_report_time() -> String {
+    "08:00".to_string()
+}
+
+fn default_report_weekday() -> String {
+    "mon".to_string()
+}
+
+fn default_statuspage_title() -> String {
+    "System Status".to_string()
+}
+
+fn default_statuspage_history_days() -> u64 {
+    30
+}
+
+fn default_webhook_method() -> String {
+    "POST".to_string()
+}
+
+fn default_webhook_content_type() -> String {
+    "application/json".to_string()
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    5000
+}
+
+fn default_chat_username() -> String {
+    "performance-monitor".to_string()
+}
+
+fn default_vapid_key_file() -> String {
+    "vapid_private_key.pem".to_string()
+}
+
+fn default_push_subscriptions_file() -> String {
+    "push_subscriptions.json".to_string()
+}
+
+fn default_vapid_subject() -> String {
+    "mailto:admin@localhost".to_string()
+}
+
+fn default_push_ttl() -> u64 {
+    3600
+}
+
+fn default_max_push_subscriptions() -> usize {
+    20
+}
+
+/// Chrome, Firefox, Edge and Safari.
+fn default_push_services() -> Vec<String> {
+    ["fcm.googleapis.com", "*.push.services.mozilla.com", "*.notify.windows.com", "web.push.apple.com"]
+        .iter()
+        .map(|host| host.to_string())
+        .collect()
+}
+
+fn default_trend_condition() -> String {
+    "rate".to_string()
+}
+
+fn default_trend_window() -> String {
+    "1h".to_string()
+}
+
+fn default_trend_severity() -> String {
+    "warning".to_string()
+}
+
+fn default_memory_leak_window() -> String {
+    "6h".to_string()
+}
+
+fn default_memory_leak_slope_mb() -> f64 {
+    10.0
+}
+
+/// A config that parses but cannot work. Unlike a missing or unreadable
+/// file, it is not replaced by the defaults.
+#[derive(Debug)]
+pub struct InvalidConfig(pub &'static str);
+
+impl std::fmt::Display for InvalidConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl std::error::Error for InvalidConfig {}
+
+/// Parses durations such as `300`, `300s`, `5m` or `1h 30m`. Bare numbers are seconds.
+pub fn parse_duration(value: &str) -> Result<Duration> {
+    let value = value.trim();
+    if let Ok(secs) = value.parse::<u64>() {
+        return Ok(Duration::from_secs(secs));
+    }
+    humantime::parse_duration(value).map_err(|e| anyhow!("Invalid duration '{}': {}", value, e))
+}
+
 impl Config {
     pub fn load_from_file(path: &str) -> Result<Self> {
         let content = fs::read_to_string(path)?;
         let config: Config = serde_json::from_str(&content)?;
+        config.validate()?;
         Ok(config)
     }
     
+    /// Combinations that deserialize but cannot work.
+    pub fn validate(&self) -> Result<()> {
+        if self.ha.enabled && self.storage.namespace.is_empty() {
+            // The default namespace is the hostname, so each replica would lead its own
+            return Err(InvalidConfig("ha.enabled needs storage.namespace, set to the same value on every replica").into());
+        }
+        if self.resource_audit.auto_prune && parse_duration(&self.resource_audit.prune_min_age).is_err() {
+            // A typo must not make freshly stopped containers prunable
+            return Err(InvalidConfig("resource_audit.prune_min_age is not a valid duration").into());
+        }
+        if self.email_commands.enabled && parse_duration(&self.email_commands.max_age).is_err() {
+            return Err(InvalidConfig("email_commands.max_age is not a valid duration").into());
+        }
+        Ok(())
+    }
+    
     #[allow(dead_code)]
     pub fn save_to_file(&self, path: &str) -> Result<()> {
         let content = serde_json::to_string_pretty(self)?;
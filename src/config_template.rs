@@ -0,0 +1,65 @@
+use serde_json::Value;
+use std::fs;
+use tera::{Context, Tera};
+use crate::config::Config;
+use crate::webhook::describe_tera_error;
+use anyhow::{Result, anyhow};
+
+/// Renders a config from a Tera `template` with the variables of the
+/// `host_vars` JSON object, then validates it: the result must be valid
+/// JSON, load as a `Config` and only use keys the monitor knows, so a typo
+/// fails provisioning instead of silently falling back to a default. The
+/// output is pretty printed with sorted keys, so the same inputs always
+/// produce the same file.
+pub fn generate(template: &str, host_vars: &str) -> Result<String> {
+    let source = fs::read_to_string(template).map_err(|e| anyhow!("failed to read {}: {}", template, e))?;
+    let vars: Value = serde_json::from_str(
+        &fs::read_to_string(host_vars).map_err(|e| anyhow!("failed to read {}: {}", host_vars, e))?,
+    )
+    .map_err(|e| anyhow!("invalid JSON in {}: {}", host_vars, e))?;
+    if !vars.is_object() {
+        return Err(anyhow!("{} must contain a JSON object", host_vars));
+    }
+    
+    let context = Context::from_value(vars).map_err(|e| anyhow!("invalid variables in {}: {}", host_vars, e))?;
+    let rendered = Tera::one_off(&source, &context, false)
+        .map_err(|e| anyhow!("template {}: {}", template, describe_tera_error(&e)))?;
+    
+    let value: Value = serde_json::from_str(&rendered)
+        .map_err(|e| anyhow!("rendered config is not valid JSON: {} (near `{}`)", e, line(&rendered, e.line())))?;
+    let config: Config = serde_json::from_str(&rendered).map_err(|e| anyhow!("invalid config: {}", e))?;
+    
+    let mut unknown = Vec::new();
+    unknown_keys(&value, &serde_json::to_value(&config)?, "", &mut unknown);
+    if !unknown.is_empty() {
+        return Err(anyhow!("unknown config keys: {}", unknown.join(", ")));
+    }
+    
+    Ok(serde_json::to_string_pretty(&value)?)
+}
+
+fn line(text: &str, number: usize) -> &str {
+    text.lines().nth(number.saturating_sub(1)).map(str::trim).unwrap_or_default()
+}
+
+/// Collects the keys of `given` that do not appear in `known`, the same
+/// config serialized back with every field.
+fn unknown_keys(given: &Value, known: &Value, path: &str, unknown: &mut Vec<String>) {
+    match (given, known) {
+        (Value::Object(given), Value::Object(known)) => {
+            for (key, value) in given {
+                let path = if path.is_empty() { key.clone() } else { format!("{}.{}", path, key) };
+                match known.get(key) {
+                    Some(known) => unknown_keys(value, known, &path, unknown),
+                    None => unknown.push(path),
+                }
+            }
+        }
+        (Value::Array(given), Value::Array(known)) => {
+            for (index, (value, known)) in given.iter().zip(known).enumerate() {
+                unknown_keys(value, known, &format!("{}[{}]", path, index), unknown);
+            }
+        }
+        _ => {}
+    }
+}
\ No newline at end of file
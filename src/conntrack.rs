@@ -0,0 +1,45 @@
+use serde::Serialize;
+use std::fs;
+use log::warn;
+use crate::platform;
+
+const COUNT_PATH: &str = "/proc/sys/net/netfilter/nf_conntrack_count";
+const MAX_PATH: &str = "/proc/sys/net/netfilter/nf_conntrack_max";
+
+/// Connection tracking table usage. When the table is full the kernel drops
+/// new connections, including NATed container traffic.
+#[derive(Debug, Clone, Serialize)]
+pub struct ConntrackUsage {
+    pub count: u64,
+    pub max: u64,
+    pub percent: f64,
+}
+
+/// Reads the conntrack table usage, `None` when the nf_conntrack module is not
+/// loaded, the values are unreadable or the host has no procfs.
+pub fn read_usage() -> Option<ConntrackUsage> {
+    if !platform::has_procfs() {
+        return None;
+    }
+    let read = |path: &str| -> Option<u64> {
+        match fs::read_to_string(path) {
+            Ok(content) => content.trim().parse().ok(),
+            Err(e) => {
+                warn!("Failed to read {}: {}", path, e);
+                None
+            }
+        }
+    };
+    
+    let count = read(COUNT_PATH)?;
+    let max = read(MAX_PATH)?;
+    if max == 0 {
+        return None;
+    }
+    
+    Some(ConntrackUsage {
+        count,
+        max,
+        percent: count as f64 * 100.0 / max as f64,
+    })
+}
\ No newline at end of file
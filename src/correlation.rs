@@ -0,0 +1,66 @@
+use crate::alert::Alert;
+use crate::config::{CorrelationConfig, CorrelationRuleConfig};
+use crate::incident::IncidentState;
+
+/// An alert about to be sent, and whether it repeats a firing incident.
+pub struct Outgoing {
+    pub alert: Alert,
+    pub repeat: bool,
+}
+
+/// Folds the firing alerts of each rule's `merge` rules into its `primary`
+/// alert when they fire in the same cycle, so one notification says "host
+/// CPU 95%, top contributors: X, Y" instead of one per rule. Resolved and
+/// flapping notifications are never merged. Returns the alerts to send and
+/// the rules that were folded into another alert.
+pub fn correlate(config: &CorrelationConfig, mut outgoing: Vec<Outgoing>) -> (Vec<Outgoing>, Vec<String>) {
+    let mut merged_rules = Vec::new();
+    if !config.enabled {
+        return (outgoing, merged_rules);
+    }
+    
+    for rule in &config.rules {
+        let firing = |item: &Outgoing, name: &str| item.alert.rule == name && item.alert.state == IncidentState::Firing;
+        if !outgoing.iter().any(|item| firing(item, &rule.primary)) {
+            continue;
+        }
+        let (parts, rest): (Vec<Outgoing>, Vec<Outgoing>) = outgoing.into_iter()
+            .partition(|item| rule.merge.iter().any(|name| *name != rule.primary && firing(item, name)));
+        outgoing = rest;
+        if parts.is_empty() {
+            continue;
+        }
+        
+        merged_rules.extend(parts.iter().map(|part| part.alert.rule.clone()));
+        if let Some(primary) = outgoing.iter_mut().find(|item| firing(item, &rule.primary)) {
+            merge_into(rule, primary, parts);
+        }
+    }
+    
+    (outgoing, merged_rules)
+}
+
+fn merge_into(rule: &CorrelationRuleConfig, primary: &mut Outgoing, parts: Vec<Outgoing>) {
+    let alert = &mut primary.alert;
+    for part in parts {
+        primary.repeat &= part.repeat;
+        alert.severity = alert.severity.max(part.alert.severity);
+        alert.notes.push(format!("Correlated {} alert: {}", part.alert.rule, part.alert.summary));
+        alert.notes.extend(part.alert.notes);
+        alert.charts.extend(part.alert.charts);
+        for container in part.alert.containers {
+            if !alert.containers.iter().any(|known| known.id == container.id) {
+                alert.containers.push(container);
+            }
+        }
+    }
+    
+    alert.containers.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+    let contributors: Vec<String> = alert.containers.iter()
+        .take(rule.top_contributors)
+        .map(|container| format!("{} ({:.1}%)", container.name, container.cpu_usage))
+        .collect();
+    if !contributors.is_empty() {
+        alert.summary = format!("{}, top contributors: {}", alert.summary, contributors.join(", "));
+    }
+}
\ No newline at end of file
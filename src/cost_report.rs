@@ -0,0 +1,62 @@
+use chrono::{DateTime, Duration, Utc};
+use crate::cloud_metadata::CloudMetadata;
+use crate::config::CostConfig;
+use crate::digest::DigestSection;
+use crate::state::UtilizationHistogram;
+
+pub fn is_due(config: &CostConfig, last_sent: Option<DateTime<Utc>>, utilization: &UtilizationHistogram) -> bool {
+    if !config.enabled {
+        return false;
+    }
+    
+    let since = last_sent.unwrap_or(utilization.since);
+    Utc::now() - since >= Duration::days(config.report_interval_days as i64)
+}
+
+/// Builds the right-sizing digest section from the observed p95 utilization.
+pub fn right_sizing_section(
+    config: &CostConfig,
+    metadata: Option<&CloudMetadata>,
+    utilization: &UtilizationHistogram,
+) -> Option<DigestSection> {
+    if utilization.samples() == 0 {
+        return None;
+    }
+    
+    let instance_type = config.instance_type.clone()
+        .or_else(|| metadata.map(|m| m.instance_type.clone()))
+        .unwrap_or_else(|| "this host".to_string());
+    let price = config.prices.get(&instance_type)
+        .map(|price| format!(" (${:.2}/month)", price))
+        .unwrap_or_default();
+    
+    let cpu_p95 = utilization.cpu_percentile(95.0);
+    let memory_p95 = utilization.memory_percentile(95.0);
+    
+    let recommendation = if cpu_p95 < config.downsize_cpu_p95 && memory_p95 < config.downsize_memory_p95 {
+        "consider downsizing"
+    } else if cpu_p95 > config.upsize_cpu_p95 || memory_p95 > config.upsize_memory_p95 {
+        "consider upsizing"
+    } else {
+        "current size looks appropriate"
+    };
+    
+    let html = format!(
+        r#"
+                <p>CPU p95 was <strong>{:.0}%</strong> and memory p95 was <strong>{:.0}%</strong> on {}{} since {} — <strong>{}</strong>.</p>
+                <p><em>Based on {} samples.</em></p>
+        "#,
+        cpu_p95,
+        memory_p95,
+        instance_type,
+        price,
+        utilization.since.format("%Y-%m-%d"),
+        recommendation,
+        utilization.samples()
+    );
+    
+    Some(DigestSection {
+        title: "💰 Right-Sizing".to_string(),
+        html,
+    })
+}
\ No newline at end of file
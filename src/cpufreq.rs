@@ -0,0 +1,135 @@
+use serde::Serialize;
+use std::fs;
+use std::path::Path;
+use crate::config::{Config, CpuFrequencyConfig};
+use log::debug;
+
+const CPU_PATH: &str = "/sys/devices/system/cpu";
+
+/// Host CPU frequency averaged over all online cores, with the scaling
+/// governors and thermal throttle events since the last check.
+#[derive(Debug, Clone, Serialize)]
+pub struct CpuFrequency {
+    pub current_mhz: f64,
+    pub min_core_mhz: f64,
+    pub max_mhz: f64,
+    pub percent_of_max: f64,
+    pub governors: Vec<String>,
+    /// `energy_performance_preference` values, when the driver exposes them.
+    pub power_preferences: Vec<String>,
+    pub throttle_events: u64,
+    pub cpu_usage: f64,
+    /// Consecutive checks with low frequency under high usage, including this one.
+    pub low_checks: u32,
+}
+
+impl CpuFrequency {
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "{:.0} MHz average ({:.0}% of {:.0} MHz max, slowest core {:.0} MHz) at {:.1}% CPU, governor {}",
+            self.current_mhz, self.percent_of_max, self.max_mhz, self.min_core_mhz, self.cpu_usage,
+            if self.governors.is_empty() { "unknown".to_string() } else { self.governors.join("/") }
+        );
+        if !self.power_preferences.is_empty() {
+            description.push_str(&format!(", power preference {}", self.power_preferences.join("/")));
+        }
+        if self.throttle_events > 0 {
+            description.push_str(&format!(", {} thermal throttle events since last check", self.throttle_events));
+        }
+        description
+    }
+}
+
+/// Samples cpufreq from sysfs every check and tracks how long the CPU has been
+/// running slow while busy, which points to thermal throttling or a
+/// power-saving governor.
+pub struct CpuFrequencyMonitor {
+    config: CpuFrequencyConfig,
+    previous_throttles: Option<u64>,
+    streak: u32,
+}
+
+impl CpuFrequencyMonitor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.cpu_frequency.clone(),
+            previous_throttles: None,
+            streak: 0,
+        }
+    }
+    
+    /// Returns `None` when the check is disabled or the kernel exposes no
+    /// cpufreq data, e.g. in most VMs.
+    pub fn sample(&mut self, cpu_usage: f64) -> Option<CpuFrequency> {
+        if !self.config.enabled {
+            return None;
+        }
+        
+        let mut current = Vec::new();
+        let mut max = Vec::new();
+        let mut governors = Vec::new();
+        let mut power_preferences = Vec::new();
+        let mut throttles = 0;
+        for entry in fs::read_dir(CPU_PATH).ok()?.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !name.strip_prefix("cpu").is_some_and(|index| !index.is_empty() && index.chars().all(|c| c.is_ascii_digit())) {
+                continue;
+            }
+            let cpu = entry.path();
+            let cpufreq = cpu.join("cpufreq");
+            if let (Some(cur), Some(hardware_max)) = (read_number(&cpufreq.join("scaling_cur_freq")), read_number(&cpufreq.join("cpuinfo_max_freq"))) {
+                current.push(cur as f64 / 1000.0);
+                max.push(hardware_max as f64 / 1000.0);
+            }
+            for (file, values) in [("scaling_governor", &mut governors), ("energy_performance_preference", &mut power_preferences)] {
+                if let Ok(value) = fs::read_to_string(cpufreq.join(file)) {
+                    let value = value.trim().to_string();
+                    if !values.contains(&value) {
+                        values.push(value);
+                    }
+                }
+            }
+            for file in ["core_throttle_count", "package_throttle_count"] {
+                throttles += read_number(&cpu.join("thermal_throttle").join(file)).unwrap_or(0);
+            }
+        }
+        
+        if current.is_empty() {
+            debug!("No cpufreq data under {}", CPU_PATH);
+            return None;
+        }
+        
+        let average = |values: &[f64]| values.iter().sum::<f64>() / values.len() as f64;
+        let current_mhz = average(&current);
+        let max_mhz = average(&max);
+        let percent_of_max = if max_mhz > 0.0 { current_mhz * 100.0 / max_mhz } else { 100.0 };
+        let throttle_events = self.previous_throttles.map(|previous| throttles.saturating_sub(previous)).unwrap_or(0);
+        self.previous_throttles = Some(throttles);
+        
+        let low = cpu_usage >= self.config.usage_threshold && percent_of_max < self.config.min_frequency_percent;
+        self.streak = if low { self.streak + 1 } else { 0 };
+        governors.sort();
+        power_preferences.sort();
+        
+        Some(CpuFrequency {
+            current_mhz,
+            min_core_mhz: current.iter().copied().fold(f64::MAX, f64::min),
+            max_mhz,
+            percent_of_max,
+            governors,
+            power_preferences,
+            throttle_events,
+            cpu_usage,
+            low_checks: self.streak,
+        })
+    }
+    
+    /// True when the CPU stayed slow under load for `sustained_checks`.
+    pub fn is_stuck(&self, frequency: &CpuFrequency) -> bool {
+        frequency.low_checks >= self.config.sustained_checks.max(1)
+    }
+}
+
+fn read_number(path: &Path) -> Option<u64> {
+    fs::read_to_string(path).ok()?.trim().parse().ok()
+}
\ No newline at end of file
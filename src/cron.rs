@@ -0,0 +1,226 @@
+use chrono::{DateTime, Duration, Utc};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::os::unix::process::ExitStatusExt;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+use crate::alert::Severity;
+use crate::config::{parse_duration, CronConfig, CronJobConfig};
+use crate::timezone::Timezone;
+use anyhow::Result;
+
+/// One run of a wrapped job. `finished` is `None` while it is running, or
+/// when the wrapper was killed before the job ended.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CronRun {
+    pub started: DateTime<Utc>,
+    #[serde(default)]
+    pub finished: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub exit_code: Option<i32>,
+    #[serde(default)]
+    pub duration_secs: Option<f64>,
+}
+
+/// What `wrap` recorded about a job.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CronJobState {
+    pub name: String,
+    pub last_run: CronRun,
+    #[serde(default)]
+    pub last_success: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub runs_total: u64,
+    #[serde(default)]
+    pub failures_total: u64,
+}
+
+impl CronJobState {
+    pub fn failed(&self) -> bool {
+        self.last_run.exit_code.is_some_and(|code| code != 0)
+    }
+    
+    pub fn describe(&self, timezone: &Timezone) -> String {
+        let run = &self.last_run;
+        let outcome = match (run.finished, run.exit_code) {
+            (None, _) => "running".to_string(),
+            (Some(_), Some(0)) => "succeeded".to_string(),
+            (Some(_), Some(code)) => format!("failed with exit code {}", code),
+            (Some(_), None) => "ended without exit code".to_string(),
+        };
+        let mut description = format!(
+            "{}: last run {} {}",
+            self.name, timezone.format(run.started), outcome
+        );
+        if let Some(duration) = run.duration_secs {
+            description.push_str(&format!(" after {:.0}s", duration));
+        }
+        if let Some(success) = self.last_success {
+            description.push_str(&format!(", last success {}", timezone.format(success)));
+        }
+        description
+    }
+}
+
+/// Job states kept as one JSON file per job in `cron.state_dir`, so wrapped
+/// jobs running at the same time and the monitor never overwrite each other.
+pub struct CronStore {
+    dir: PathBuf,
+}
+
+impl CronStore {
+    pub fn new(config: &CronConfig) -> Self {
+        Self {
+            dir: PathBuf::from(&config.state_dir),
+        }
+    }
+    
+    fn path(&self, name: &str) -> PathBuf {
+        let file: String = name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' || c == '.' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.json", file))
+    }
+    
+    pub fn load(&self, name: &str) -> Option<CronJobState> {
+        read(&self.path(name))
+    }
+    
+    /// All recorded jobs, skipping unreadable files.
+    pub fn all(&self) -> Vec<CronJobState> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut jobs: Vec<CronJobState> = entries.flatten()
+            .filter(|entry| entry.path().extension().is_some_and(|extension| extension == "json"))
+            .filter_map(|entry| read(&entry.path()))
+            .collect();
+        jobs.sort_by(|a, b| a.name.cmp(&b.name));
+        jobs
+    }
+    
+    /// Writes the job atomically (temporary file + rename).
+    pub fn save(&self, job: &CronJobState) -> Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(&job.name);
+        let tmp_path = path.with_extension("json.tmp");
+        fs::write(&tmp_path, serde_json::to_string_pretty(job)?)?;
+        fs::rename(&tmp_path, &path)?;
+        Ok(())
+    }
+}
+
+fn read(path: &Path) -> Option<CronJobState> {
+    let content = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&content) {
+        Ok(job) => Some(job),
+        Err(e) => {
+            warn!("Failed to read cron job state {}: {}", path.display(), e);
+            None
+        }
+    }
+}
+
+/// Runs `command` with inherited stdio for `wrap`, records the run and
+/// returns the exit code to pass on to cron. A job killed by a signal
+/// reports `128 + signal` like the shell does.
+pub async fn wrap(config: &CronConfig, name: &str, command: &[String]) -> i32 {
+    let store = CronStore::new(config);
+    let previous = store.load(name);
+    let started = Utc::now();
+    let mut job = CronJobState {
+        name: name.to_string(),
+        last_run: CronRun { started, finished: None, exit_code: None, duration_secs: None },
+        last_success: previous.as_ref().and_then(|job| job.last_success),
+        runs_total: previous.as_ref().map(|job| job.runs_total).unwrap_or(0) + 1,
+        failures_total: previous.as_ref().map(|job| job.failures_total).unwrap_or(0),
+    };
+    if let Err(e) = store.save(&job) {
+        warn!("Failed to record start of cron job {}: {}", name, e);
+    }
+    
+    let code = match Command::new(&command[0]).args(&command[1..]).status().await {
+        Ok(status) => status.code().unwrap_or_else(|| 128 + status.signal().unwrap_or(0)),
+        Err(e) => {
+            eprintln!("Failed to run {}: {}", command[0], e);
+            127
+        }
+    };
+    
+    let finished = Utc::now();
+    job.last_run.finished = Some(finished);
+    job.last_run.exit_code = Some(code);
+    job.last_run.duration_secs = Some((finished - started).num_milliseconds() as f64 / 1000.0);
+    if code == 0 {
+        job.last_success = Some(finished);
+    } else {
+        job.failures_total += 1;
+    }
+    if let Err(e) = store.save(&job) {
+        warn!("Failed to record end of cron job {}: {}", name, e);
+    }
+    code
+}
+
+/// A wrapped job that failed, missed its schedule or runs too long.
+#[derive(Debug, Clone, Serialize)]
+pub struct CronProblem {
+    pub name: String,
+    pub severity: Severity,
+    pub description: String,
+}
+
+/// Checks the recorded jobs against `cron.jobs`. Jobs without a config entry
+/// are only checked for failures. A configured job that never ran counts as
+/// missed once `every + grace` has passed since `since` (the monitor start).
+pub fn evaluate(config: &CronConfig, jobs: &[CronJobState], now: DateTime<Utc>, since: DateTime<Utc>, timezone: &Timezone) -> Vec<CronProblem> {
+    let mut problems = Vec::new();
+    
+    for job in jobs {
+        if job.failed() {
+            let severity = config.jobs.iter()
+                .find(|expected| expected.name == job.name)
+                .map(job_severity)
+                .unwrap_or(Severity::Warning);
+            problems.push(CronProblem { name: job.name.clone(), severity, description: job.describe(timezone) });
+        }
+    }
+    
+    for expected in &config.jobs {
+        let duration = |value: &str| parse_duration(value).ok().and_then(|duration| Duration::from_std(duration).ok());
+        let (Some(every), Some(grace)) = (duration(&expected.every), duration(&expected.grace)) else {
+            warn!("Cron job {} has an invalid every/grace duration. Skipping schedule check.", expected.name);
+            continue;
+        };
+        let job = jobs.iter().find(|job| job.name == expected.name);
+        let severity = job_severity(expected);
+        
+        let last_start = job.map(|job| job.last_run.started).unwrap_or(since);
+        if now - last_start > every + grace {
+            let description = match job {
+                Some(job) => format!("{} (expected every {})", job.describe(timezone), expected.every),
+                None => format!("{}: no run recorded (expected every {})", expected.name, expected.every),
+            };
+            problems.push(CronProblem { name: expected.name.clone(), severity, description });
+            continue;
+        }
+        
+        let max_duration = expected.max_duration.as_deref().and_then(duration);
+        if let (Some(job), Some(max_duration)) = (job, max_duration) {
+            if job.last_run.finished.is_none() && now - job.last_run.started > max_duration {
+                problems.push(CronProblem {
+                    name: expected.name.clone(),
+                    severity,
+                    description: format!("{} for longer than {}", job.describe(timezone), expected.max_duration.as_deref().unwrap_or_default()),
+                });
+            }
+        }
+    }
+    
+    problems
+}
+
+fn job_severity(config: &CronJobConfig) -> Severity {
+    if config.severity == "critical" { Severity::Critical } else { Severity::Warning }
+}
\ No newline at end of file
@@ -0,0 +1,285 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Duration, Local, Utc};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use crate::chart::{self, Series};
+use crate::history::{ContainerBucket, ContainerUsage, History};
+use crate::query::{self, QueryRequest, QueryResponse};
+use crate::html::escape;
+use crate::units;
+use anyhow::Result;
+
+const PALETTE: [[u8; 3]; 8] = [
+    [52, 152, 219],
+    [230, 126, 34],
+    [46, 204, 113],
+    [155, 89, 182],
+    [231, 76, 60],
+    [26, 188, 156],
+    [241, 196, 15],
+    [52, 73, 94],
+];
+const OTHER_COLOR: [u8; 3] = [189, 195, 199];
+
+/// Time range shown by the dashboard.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Range {
+    Day,
+    Week,
+}
+
+impl Range {
+    pub fn parse(value: Option<&str>) -> Self {
+        match value {
+            Some("7d") => Range::Week,
+            _ => Range::Day,
+        }
+    }
+    
+    fn key(&self) -> &'static str {
+        match self {
+            Range::Day => "24h",
+            Range::Week => "7d",
+        }
+    }
+    
+    fn duration(&self) -> Duration {
+        match self {
+            Range::Day => Duration::hours(24),
+            Range::Week => Duration::days(7),
+        }
+    }
+}
+
+/// One stacked layer: a container, or everything outside the top ones.
+struct Layer {
+    name: String,
+    color: [u8; 3],
+    cpu: Vec<f64>,
+    memory: Vec<f64>,
+}
+
+impl Layer {
+    fn new(name: String, color: [u8; 3], hours: usize) -> Self {
+        Self { name, color, cpu: vec![0.0; hours], memory: vec![0.0; hours] }
+    }
+    
+    fn add(&mut self, hour: usize, usage: &ContainerUsage) {
+        self.cpu[hour] += usage.cpu_avg();
+        self.memory[hour] += usage.memory_avg();
+    }
+}
+
+/// Renders the container history page: stacked hourly CPU and memory per
+/// container over `range`. With `container` set only that container is shown,
+/// otherwise the `top` busiest containers are stacked and the rest grouped.
+pub fn render(history: &History, range: Range, container: Option<&str>, top: usize, now: DateTime<Utc>) -> Result<String> {
+    let buckets = history.containers_since(now - range.duration());
+    let names = container_names(&buckets);
+    let container = container.filter(|name| !name.is_empty());
+    let layers = layers(&buckets, container, top);
+    
+    let charts = if layers.is_empty() {
+        "<p>No container history recorded in this range yet.</p>".to_string()
+    } else {
+        render_charts(&buckets, &layers)?
+    };
+    
+    let range_links: String = [Range::Day, Range::Week].iter()
+        .map(|option| {
+            let mut href = format!("?range={}", option.key());
+            if let Some(name) = container {
+                href.push_str(&format!("&amp;container={}", escape(&url_encode(name))));
+            }
+            if *option == range {
+                format!(" <strong>{}</strong>", option.key())
+            } else {
+                format!(r#" <a href="{}">{}</a>"#, href, option.key())
+            }
+        })
+        .collect();
+    let options: String = names.iter()
+        .map(|name| {
+            let selected = if Some(name.as_str()) == container { " selected" } else { "" };
+            format!(r#"<option value="{0}"{1}>{0}</option>"#, escape(name), selected)
+        })
+        .collect();
+    
+    Ok(format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta http-equiv="refresh" content="300">
+    <title>Container history</title>
+    <style>
+        body {{ font-family: sans-serif; max-width: 820px; margin: 2em auto; color: #333; }}
+        table {{ border-collapse: collapse; margin-top: 1em; }}
+        td, th {{ padding: 0.3em 0.8em; border-bottom: 1px solid #eee; text-align: left; }}
+        .detail {{ color: #777; font-size: 0.9em; }}
+    </style>
+</head>
+<body>
+    <h1>Container history</h1>
+    <form method="get">
+        Range:{range_links}
+        <input type="hidden" name="range" value="{range}">
+        <select name="container" onchange="this.form.submit()">
+            <option value="">Top {top} containers</option>
+            {options}
+        </select>
+        <noscript><button type="submit">Show</button></noscript>
+    </form>
+    {charts}
+    <p class="detail">Hourly averages, local time. Closed hours keep the top {top} containers by CPU and by memory.</p>
+</body>
+</html>"#,
+        range_links = range_links,
+        range = range.key(),
+        top = top,
+        options = options,
+        charts = charts,
+    ))
+}
+
+/// Every container recorded in the range, sorted by name.
+fn container_names(buckets: &[&ContainerBucket]) -> Vec<String> {
+    let mut names: Vec<String> = buckets.iter()
+        .flat_map(|bucket| bucket.containers.keys().cloned())
+        .collect();
+    names.sort();
+    names.dedup();
+    names
+}
+
+fn layers(buckets: &[&ContainerBucket], container: Option<&str>, top: usize) -> Vec<Layer> {
+    if let Some(name) = container {
+        let mut layer = Layer::new(name.to_string(), PALETTE[0], buckets.len());
+        let mut seen = false;
+        for (hour, bucket) in buckets.iter().enumerate() {
+            if let Some(usage) = bucket.containers.get(name) {
+                layer.add(hour, usage);
+                seen = true;
+            }
+        }
+        return if seen { vec![layer] } else { Vec::new() };
+    }
+    
+    // Rank by CPU over the whole range, weighting each hour by its samples
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for bucket in buckets {
+        for (name, usage) in &bucket.containers {
+            *totals.entry(name).or_default() += usage.cpu_sum;
+        }
+    }
+    let mut ranked: Vec<(&str, f64)> = totals.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    
+    let shown = top.clamp(1, PALETTE.len());
+    let mut layers: Vec<Layer> = ranked.iter()
+        .take(shown)
+        .enumerate()
+        .map(|(index, (name, _))| Layer::new(name.to_string(), PALETTE[index], buckets.len()))
+        .collect();
+    let mut other = Layer::new("other".to_string(), OTHER_COLOR, buckets.len());
+    let mut has_other = false;
+    
+    for (hour, bucket) in buckets.iter().enumerate() {
+        for (name, usage) in &bucket.containers {
+            match layers.iter_mut().find(|layer| &layer.name == name) {
+                Some(layer) => layer.add(hour, usage),
+                None => {
+                    other.add(hour, usage);
+                    has_other = true;
+                }
+            }
+        }
+    }
+    if has_other {
+        layers.push(other);
+    }
+    layers
+}
+
+fn render_charts(buckets: &[&ContainerBucket], layers: &[Layer]) -> Result<String> {
+    let total = |values: fn(&Layer) -> &[f64]| {
+        (0..buckets.len())
+            .map(|hour| layers.iter().map(|layer| values(layer)[hour]).sum::<f64>())
+            .fold(0.0, f64::max)
+    };
+    let cpu_peak = total(|layer| &layer.cpu);
+    let memory_peak = total(|layer| &layer.memory);
+    // Keep the 0-100% scale unless containers together used more than one core
+    let cpu_max = cpu_peak.max(100.0);
+    
+    let cpu_series: Vec<Series> = layers.iter().map(|layer| Series { color: layer.color, values: &layer.cpu }).collect();
+    let memory_series: Vec<Series> = layers.iter().map(|layer| Series { color: layer.color, values: &layer.memory }).collect();
+    let cpu_png = chart::stacked_chart(&cpu_series, cpu_max)?;
+    let memory_png = chart::stacked_chart(&memory_series, memory_peak)?;
+    
+    let rows: String = layers.iter()
+        .map(|layer| {
+            let hours = layer.cpu.len().max(1) as f64;
+            format!(
+                "<tr><td><span style='color: rgb({}, {}, {});'>■</span> {}</td><td>{:.1}%</td><td>{:.1}%</td><td>{}</td><td>{}</td></tr>",
+                layer.color[0], layer.color[1], layer.color[2], escape(&layer.name),
+                layer.cpu.iter().sum::<f64>() / hours,
+                layer.cpu.iter().copied().fold(0.0, f64::max),
+                units::bytes(layer.memory.iter().sum::<f64>() / hours),
+                units::bytes(layer.memory.iter().copied().fold(0.0, f64::max)),
+            )
+        })
+        .collect();
+    
+    Ok(format!(
+        r#"
+    <p>{from} to {to}</p>
+    <h2>CPU</h2>
+    <img src="data:image/png;base64,{cpu}" alt="Stacked container CPU" width="640" height="200">
+    <p class="detail">Scale 0 to {cpu_max:.0}%, grid lines every 25%. Peak total {cpu_peak:.1}%.</p>
+    <h2>Memory</h2>
+    <img src="data:image/png;base64,{memory}" alt="Stacked container memory" width="640" height="200">
+    <p class="detail">Scale 0 to {memory_peak}, grid lines every 25%.</p>
+    <table>
+        <tr><th>Container</th><th>Avg CPU</th><th>Peak CPU</th><th>Avg memory</th><th>Peak memory</th></tr>
+        {rows}
+    </table>"#,
+        from = buckets[0].hour.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+        to = buckets[buckets.len() - 1].hour.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+        cpu = STANDARD.encode(cpu_png),
+        cpu_max = cpu_max,
+        cpu_peak = cpu_peak,
+        memory = STANDARD.encode(memory_png),
+        memory_peak = units::bytes(memory_peak),
+        rows = rows,
+    ))
+}
+
+fn url_encode(value: &str) -> String {
+    value.bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => (byte as char).to_string(),
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
+
+/// Latest usage history, shared with the HTTP endpoint.
+#[derive(Default)]
+pub struct PublishedHistory {
+    history: Mutex<History>,
+}
+
+impl PublishedHistory {
+    pub fn publish(&self, history: &History) {
+        self.history.lock().unwrap().clone_from(history);
+    }
+    
+    pub fn render(&self, range: Range, container: Option<&str>, top: usize) -> Result<String> {
+        render(&self.history.lock().unwrap(), range, container, top, Utc::now())
+    }
+    
+    pub fn query(&self, request: &QueryRequest) -> Result<QueryResponse> {
+        query::run(&self.history.lock().unwrap(), request, Utc::now())
+    }
+}
\ No newline at end of file
@@ -0,0 +1,180 @@
+use std::collections::{HashMap, HashSet};
+use crate::config::DependencyConfig;
+use crate::docker_monitor::ContainerStats;
+
+/// Container dependency graph (`api` depends on `db`), declared in config and
+/// via the dependency label on running containers.
+pub struct DependencyGraph {
+    edges: HashMap<String, Vec<String>>,
+}
+
+impl DependencyGraph {
+    pub fn build(config: &DependencyConfig, containers: &[ContainerStats]) -> Self {
+        let mut edges = config.containers.clone();
+        
+        for container in containers {
+            if let Some(value) = container.labels.get(&config.label) {
+                let depends_on = edges.entry(container.name.clone()).or_default();
+                for dependency in value.split(',').map(str::trim).filter(|d| !d.is_empty()) {
+                    if !depends_on.iter().any(|d| d == dependency) {
+                        depends_on.push(dependency.to_string());
+                    }
+                }
+            }
+        }
+        
+        Self { edges }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.edges.is_empty()
+    }
+    
+    /// Dependencies that are not running, given container name to state. A
+    /// declared dependency that doesn't exist at all counts as down.
+    pub fn down_dependencies(&self, states: &HashMap<String, String>) -> HashSet<String> {
+        self.edges.values()
+            .flatten()
+            .filter(|dependency| states.get(*dependency).is_none_or(|state| state != "running"))
+            .cloned()
+            .collect()
+    }
+    
+    /// The down dependency a container transitively relies on, if any. Follows
+    /// the chain of down dependencies, so `nginx -> api -> db` reports `db` when
+    /// both `api` and `db` are down.
+    pub fn root_cause(&self, container: &str, down: &HashSet<String>) -> Option<String> {
+        let mut visited = HashSet::new();
+        let mut root = self.nearest_down(container, down, &mut visited)?;
+        while let Some(deeper) = self.nearest_down(&root, down, &mut visited) {
+            root = deeper;
+        }
+        Some(root)
+    }
+    
+    fn nearest_down(&self, start: &str, down: &HashSet<String>, visited: &mut HashSet<String>) -> Option<String> {
+        let mut frontier = vec![start.to_string()];
+        
+        while !frontier.is_empty() {
+            let mut next = Vec::new();
+            for name in frontier {
+                if !visited.insert(name.clone()) {
+                    continue;
+                }
+                for dependency in self.edges.get(&name).into_iter().flatten() {
+                    if down.contains(dependency) {
+                        return Some(dependency.clone());
+                    }
+                    next.push(dependency.clone());
+                }
+            }
+            frontier = next;
+        }
+        
+        None
+    }
+    
+    /// Splits alerting containers into those with a down dependency and the
+    /// rest. Returns the containers to alert on, one note per kept container
+    /// with a down dependency, and with `suppress` one note per container
+    /// dropped from the list, so suppressed containers never show up in the
+    /// alert.
+    pub fn correlate(
+        &self,
+        containers: Vec<ContainerStats>,
+        down: &HashSet<String>,
+        suppress: bool,
+    ) -> (Vec<ContainerStats>, Vec<String>, Vec<String>) {
+        let mut kept = Vec::new();
+        let mut notes = Vec::new();
+        let mut suppressed = Vec::new();
+        
+        for container in containers {
+            match self.root_cause(&container.name, down) {
+                Some(root) if suppress => suppressed.push(format!("{} is likely caused by {} down", container.name, root)),
+                Some(root) => {
+                    notes.push(format!("{} is likely caused by {} down", container.name, root));
+                    kept.push(container);
+                }
+                None => kept.push(container),
+            }
+        }
+        
+        (kept, notes, suppressed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn container(name: &str, depends_on: Option<&str>) -> ContainerStats {
+        ContainerStats {
+            cpu_usage: 95.0,
+            labels: depends_on.map(|value| HashMap::from([("monitor.depends_on".to_string(), value.to_string())])).unwrap_or_default(),
+            ..ContainerStats::for_test(name, "nginx:1.27")
+        }
+    }
+    
+    fn graph(edges: &[(&str, &[&str])]) -> DependencyGraph {
+        let config = DependencyConfig {
+            label: "monitor.depends_on".to_string(),
+            containers: edges.iter()
+                .map(|(name, depends_on)| (name.to_string(), depends_on.iter().map(|d| d.to_string()).collect()))
+                .collect(),
+            suppress_downstream: false,
+        };
+        DependencyGraph::build(&config, &[])
+    }
+    
+    fn down(names: &[&str]) -> HashSet<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+    
+    #[test]
+    fn follows_the_chain_to_the_deepest_down_dependency() {
+        let graph = graph(&[("nginx", &["api"]), ("api", &["db", "cache"])]);
+        assert_eq!(graph.root_cause("nginx", &down(&["api", "db"])), Some("db".to_string()));
+        assert_eq!(graph.root_cause("nginx", &down(&["api"])), Some("api".to_string()));
+        assert_eq!(graph.root_cause("api", &down(&["cache"])), Some("cache".to_string()));
+        assert_eq!(graph.root_cause("nginx", &down(&[])), None);
+        assert_eq!(graph.root_cause("db", &down(&["api", "db"])), None);
+    }
+    
+    #[test]
+    fn cycles_terminate() {
+        let graph = graph(&[("a", &["b"]), ("b", &["a"]), ("c", &["a"])]);
+        assert_eq!(graph.root_cause("a", &down(&[])), None);
+        assert_eq!(graph.root_cause("a", &down(&["a", "b"])), Some("a".to_string()));
+        assert_eq!(graph.root_cause("c", &down(&["a", "b"])), Some("a".to_string()));
+        assert_eq!(graph.root_cause("c", &down(&["b"])), Some("b".to_string()));
+        
+        let graph = DependencyGraph::build(&DependencyConfig::default(), &[container("self", Some("self"))]);
+        assert_eq!(graph.root_cause("self", &down(&["self"])), Some("self".to_string()));
+    }
+    
+    #[test]
+    fn reads_dependencies_from_labels() {
+        let containers = [container("api", Some("db, cache,")), container("db", None)];
+        let graph = DependencyGraph::build(&DependencyConfig::default(), &containers);
+        let states = HashMap::from([("db".to_string(), "running".to_string()), ("cache".to_string(), "exited".to_string())]);
+        assert_eq!(graph.down_dependencies(&states), down(&["cache"]));
+        assert_eq!(graph.down_dependencies(&HashMap::new()), down(&["db", "cache"]));
+    }
+    
+    #[test]
+    fn suppressed_containers_get_no_notes() {
+        let graph = graph(&[("api", &["db"])]);
+        let containers = || vec![container("api", None), container("worker", None)];
+        
+        let (kept, notes, suppressed) = graph.correlate(containers(), &down(&["db"]), false);
+        assert_eq!(kept.len(), 2);
+        assert_eq!(notes, ["api is likely caused by db down"]);
+        assert!(suppressed.is_empty());
+        
+        let (kept, notes, suppressed) = graph.correlate(containers(), &down(&["db"]), true);
+        assert_eq!(kept.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(), ["worker"]);
+        assert!(notes.is_empty());
+        assert_eq!(suppressed, ["api is likely caused by db down"]);
+    }
+}
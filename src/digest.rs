@@ -0,0 +1,20 @@
+use chrono::{DateTime, Duration, Utc};
+use crate::config::DigestConfig;
+
+/// One titled block of HTML in the periodic digest email.
+#[derive(Debug, Clone)]
+pub struct DigestSection {
+    pub title: String,
+    pub html: String,
+}
+
+pub fn is_due(config: &DigestConfig, last_sent: Option<DateTime<Utc>>) -> bool {
+    if !config.enabled {
+        return false;
+    }
+    
+    match last_sent {
+        Some(last_sent) => Utc::now() - last_sent >= Duration::hours(config.interval_hours as i64),
+        None => true,
+    }
+}
\ No newline at end of file
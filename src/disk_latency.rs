@@ -0,0 +1,224 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use crate::config::{Config, DiskLatencyConfig};
+use crate::platform;
+use log::{debug, warn};
+
+const DISKSTATS_PATH: &str = "/proc/diskstats";
+
+/// Cumulative counters of one block device from /proc/diskstats.
+#[derive(Debug, Clone, Copy)]
+struct DiskCounters {
+    reads: u64,
+    read_ms: u64,
+    writes: u64,
+    write_ms: u64,
+}
+
+/// Average latency of one device over the last check interval.
+#[derive(Debug, Clone, Serialize)]
+pub struct DiskLatency {
+    pub device: String,
+    pub read_ms: f64,
+    pub write_ms: f64,
+    pub reads: u64,
+    pub writes: u64,
+    /// Consecutive checks above a threshold, including this one.
+    pub breached_checks: u32,
+}
+
+impl DiskLatency {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: read {:.1} ms ({} ops), write {:.1} ms ({} ops), {} consecutive checks over threshold",
+            self.device, self.read_ms, self.reads, self.write_ms, self.writes, self.breached_checks
+        )
+    }
+}
+
+/// Measures per-device average I/O latency from the delta of time spent on
+/// reads/writes divided by completed operations between two checks.
+pub struct DiskLatencyMonitor {
+    config: DiskLatencyConfig,
+    previous: HashMap<String, DiskCounters>,
+    streaks: HashMap<String, u32>,
+}
+
+impl DiskLatencyMonitor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.disk_latency.clone(),
+            previous: HashMap::new(),
+            streaks: HashMap::new(),
+        }
+    }
+    
+    /// Samples all devices and returns their latency since the last sample.
+    /// The first call only records a baseline.
+    pub fn sample(&mut self) -> Vec<DiskLatency> {
+        if !self.config.enabled || !platform::has_procfs() {
+            return Vec::new();
+        }
+        
+        let current = match fs::read_to_string(DISKSTATS_PATH) {
+            Ok(content) => parse_diskstats(&content),
+            Err(e) => {
+                warn!("Failed to read {}: {}", DISKSTATS_PATH, e);
+                return Vec::new();
+            }
+        };
+        
+        let mut latencies = Vec::new();
+        for (device, counters) in &current {
+            if !self.is_monitored(device) {
+                continue;
+            }
+            let Some(previous) = self.previous.get(device) else {
+                continue;
+            };
+            
+            let reads = delta(counters.reads, previous.reads);
+            let writes = delta(counters.writes, previous.writes);
+            let read_ms = average(delta(counters.read_ms, previous.read_ms), reads);
+            let write_ms = average(delta(counters.write_ms, previous.write_ms), writes);
+            
+            let breached = read_ms > self.config.read_threshold_ms || write_ms > self.config.write_threshold_ms;
+            let streak = self.streaks.entry(device.clone()).or_insert(0);
+            *streak = if breached { *streak + 1 } else { 0 };
+            
+            debug!("Disk {} latency: read {:.1} ms, write {:.1} ms", device, read_ms, write_ms);
+            latencies.push(DiskLatency {
+                device: device.clone(),
+                read_ms,
+                write_ms,
+                reads,
+                writes,
+                breached_checks: *streak,
+            });
+        }
+        
+        self.previous = current;
+        latencies.sort_by(|a, b| a.device.cmp(&b.device));
+        latencies
+    }
+    
+    /// Devices whose latency stayed above a threshold for `sustained_checks`.
+    pub fn sustained(&self, latencies: &[DiskLatency]) -> Vec<DiskLatency> {
+        latencies.iter()
+            .filter(|latency| latency.breached_checks >= self.config.sustained_checks.max(1))
+            .cloned()
+            .collect()
+    }
+    
+    /// Configured devices, or every whole disk except loop and RAM devices.
+    fn is_monitored(&self, device: &str) -> bool {
+        if !self.config.devices.is_empty() {
+            return self.config.devices.iter().any(|d| d == device);
+        }
+        let virtual_device = ["loop", "ram", "zram"].iter().any(|prefix| device.starts_with(prefix));
+        !virtual_device && Path::new("/sys/block").join(device).exists()
+    }
+}
+
+/// Increase of a counter between two samples. The kernel keeps these as
+/// `unsigned long`, so on 32-bit hosts they wrap at 2^32; a drop from a larger
+/// value means the device was reset or re-attached and counts as no change.
+fn delta(current: u64, previous: u64) -> u64 {
+    if current >= previous {
+        current - previous
+    } else if previous <= u32::MAX as u64 {
+        current + (u32::MAX as u64 + 1 - previous)
+    } else {
+        0
+    }
+}
+
+fn average(total_ms: u64, operations: u64) -> f64 {
+    if operations == 0 {
+        0.0
+    } else {
+        total_ms as f64 / operations as f64
+    }
+}
+
+/// Reads the completed operations and milliseconds spent per direction
+/// (fields 1, 4, 5 and 8 after the device name). Dividing their deltas gives
+/// the same per-operation latency as iostat's `r_await`/`w_await`. The
+/// weighted I/O time (field 11) is not used: it grows with queue depth, so it
+/// measures how much I/O was waiting rather than how long one operation took.
+/// Lines with fewer fields, such as partitions on 2.4/2.6 kernels, are skipped.
+fn parse_diskstats(content: &str) -> HashMap<String, DiskCounters> {
+    content.lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if fields.len() < 11 {
+                return None;
+            }
+            let number = |index: usize| fields[index].parse::<u64>().ok();
+            Some((fields[2].to_string(), DiskCounters {
+                reads: number(3)?,
+                read_ms: number(6)?,
+                writes: number(7)?,
+                write_ms: number(10)?,
+            }))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    // Captured from a 6.1 kernel (20 columns) and a 2.6 kernel, whose partition
+    // lines only have four counters
+    const DISKSTATS: &str = "\
+   7       0 loop0 52 0 2104 9 0 0 0 0 0 24 9 0 0 0 0 0 0
+   8       0 sda 1201735 331455 91403730 1453620 3166404 2765215 141403856 4530396 0 2279020 6298164 0 0 0 0 347551 314147
+   8       1 sda1 1201562 331455 91398506 1453574 3166372 2765215 141403856 4530373 0 2279000 5983947 0 0 0 0 0 0
+ 259       0 nvme0n1 453829 28 27063262 87215 1379398 566573 49187674 1210364 0 1181228 1318472 92851 0 96113880 20892 0 0
+   8      16 sdb 35486 38030 38030 38030 2180 1204 27072 9420 0 12280 47450
+   8      17 sdb1 35486 38030 38030 38030
+";
+    
+    #[test]
+    fn parses_disks_and_partitions() {
+        let stats = parse_diskstats(DISKSTATS);
+        
+        let sda = stats["sda"];
+        assert_eq!((sda.reads, sda.read_ms, sda.writes, sda.write_ms), (1201735, 1453620, 3166404, 4530396));
+        let sda1 = stats["sda1"];
+        assert_eq!((sda1.reads, sda1.read_ms, sda1.writes, sda1.write_ms), (1201562, 1453574, 3166372, 4530373));
+        let nvme = stats["nvme0n1"];
+        assert_eq!((nvme.reads, nvme.read_ms, nvme.writes, nvme.write_ms), (453829, 87215, 1379398, 1210364));
+        // Exactly 11 columns on older kernels
+        let sdb = stats["sdb"];
+        assert_eq!((sdb.reads, sdb.read_ms, sdb.writes, sdb.write_ms), (35486, 38030, 2180, 9420));
+        
+        assert!(!stats.contains_key("sdb1"));
+        assert_eq!(stats.len(), 5);
+    }
+    
+    #[test]
+    fn skips_malformed_lines() {
+        let stats = parse_diskstats("   8       0 sda x 0 0 0 0 0 0 0 0 0 0\n\n   8 0\n");
+        assert!(stats.is_empty());
+    }
+    
+    #[test]
+    fn delta_handles_counter_wrap() {
+        assert_eq!(delta(1500, 1000), 500);
+        assert_eq!(delta(1000, 1000), 0);
+        // 32-bit counter wrapped past 2^32
+        assert_eq!(delta(100, u32::MAX as u64 - 99), 200);
+        // A 64-bit counter going backwards is a reset, not a wrap
+        assert_eq!(delta(100, u32::MAX as u64 + 1000), 0);
+    }
+    
+    #[test]
+    fn average_latency_per_operation() {
+        assert_eq!(average(delta(4530396 + 900, 4530396), delta(3166404 + 100, 3166404)), 9.0);
+        assert_eq!(average(50, 0), 0.0);
+    }
+}
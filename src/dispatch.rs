@@ -0,0 +1,532 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+use tokio::task::JoinHandle;
+use crate::alert::Alert;
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::config::{Config, ContainerGroupConfig, DispatchConfig, EscalationTierConfig, InhibitRuleConfig};
+use crate::apprise::AppriseNotifier;
+use crate::chat::ChatNotifier;
+use crate::digest::DigestSection;
+use crate::email_notifier::EmailNotifier;
+use crate::metrics::RuntimeMetrics;
+use crate::payload;
+use crate::redact::Redactor;
+use crate::report::Report;
+use crate::webhook::WebhookNotifier;
+use crate::webpush::WebPushNotifier;
+use log::{info, error, warn};
+use tracing::Instrument;
+
+/// Counters describing the dispatch queue, exposed in state dumps and logs.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DispatchStats {
+    pub depth: usize,
+    pub max_depth: usize,
+    pub in_flight: usize,
+    pub enqueued: u64,
+    pub sent: u64,
+    pub failed: u64,
+    pub dropped: u64,
+    pub merged: u64,
+    #[serde(default)]
+    pub inhibited: u64,
+}
+
+#[derive(Default)]
+struct Counters {
+    max_depth: AtomicUsize,
+    in_flight: AtomicUsize,
+    enqueued: AtomicU64,
+    sent: AtomicU64,
+    failed: AtomicU64,
+    dropped: AtomicU64,
+    merged: AtomicU64,
+    inhibited: AtomicU64,
+}
+
+/// An inhibit rule with its patterns compiled.
+struct InhibitRule {
+    source: Vec<glob::Pattern>,
+    target: Vec<glob::Pattern>,
+}
+
+impl InhibitRule {
+    /// `None` when a pattern is invalid, so a typo never inhibits everything.
+    fn compile(config: &InhibitRuleConfig) -> Option<Self> {
+        let compile = |patterns: &[String]| -> Option<Vec<glob::Pattern>> {
+            patterns.iter()
+                .map(|pattern| glob::Pattern::new(pattern)
+                    .map_err(|e| warn!("Ignoring inhibit rule with invalid pattern '{}': {}", pattern, e))
+                    .ok())
+                .collect()
+        };
+        Some(Self {
+            source: compile(&config.source)?,
+            target: compile(&config.target)?,
+        })
+    }
+    
+    /// The firing rule that inhibits `rule`, if any. A rule never inhibits itself.
+    fn source_of<'a>(&self, rule: &str, firing: &'a HashSet<String>) -> Option<&'a String> {
+        if !self.target.iter().any(|pattern| pattern.matches(rule)) {
+            return None;
+        }
+        firing.iter().find(|source| *source != rule && self.source.iter().any(|pattern| pattern.matches(source)))
+    }
+}
+
+/// Notification channels an alert is delivered to.
+pub struct Notifiers {
+    pub email: Arc<EmailNotifier>,
+    pub webhooks: WebhookNotifier,
+    pub chat: ChatNotifier,
+    pub apprise: AppriseNotifier,
+    pub webpush: Arc<WebPushNotifier>,
+}
+
+impl Notifiers {
+    /// Delivers the alert to every channel, returning the outcome per channel.
+    /// Email is skipped when disabled, unless it is the only channel. Each
+    /// channel runs in its own `notify_channel` span.
+    #[tracing::instrument(name = "notify", skip_all, fields(rule = %alert.rule, state = ?alert.state))]
+    async fn deliver(&self, alert: &Alert) -> Vec<(String, bool)> {
+        let span = |channel: &str| tracing::info_span!("notify_channel", channel);
+        let mut results = Vec::new();
+        let only_email = self.webhooks.is_empty() && self.chat.is_empty() && self.apprise.is_empty() && self.webpush.is_empty();
+        if self.email.is_enabled() || only_email {
+            results.push(("email".to_string(), self.email.send_rule_alert(alert).instrument(span("email")).await));
+        }
+        results.extend(self.webhooks.send(alert).instrument(span("webhook")).await);
+        results.extend(self.chat.send(alert).instrument(span("chat")).await);
+        results.extend(self.apprise.send(alert).instrument(span("apprise")).await);
+        results.extend(self.webpush.send(alert).instrument(span("webpush")).await);
+        results
+    }
+    
+    /// Delivers the alert to the given email addresses and named channels, those
+    /// of an escalation tier or of a container group's owners.
+    async fn deliver_to(&self, alert: &Alert, emails: &[String], channels: &[String]) -> Vec<(String, bool)> {
+        let mut results = Vec::new();
+        for recipient in emails {
+            results.push(("email".to_string(), self.email.send_escalation(alert, recipient).await));
+        }
+        results.extend(self.webhooks.send_to(alert, channels).await);
+        results.extend(self.chat.send_to(alert, channels).await);
+        results
+    }
+}
+
+struct Shared {
+    config: DispatchConfig,
+    queue: Mutex<VecDeque<Alert>>,
+    ready: Notify,
+    inhibit_rules: Vec<InhibitRule>,
+    /// Alerts arrive redacted, digests and reports are redacted here.
+    redactor: Arc<Redactor>,
+    /// Rules firing after the last evaluation, the sources of inhibitions.
+    firing: Mutex<HashSet<String>>,
+    /// Set on shutdown once the queue is drained; workers exit and new
+    /// alerts are dropped.
+    closed: AtomicBool,
+    counters: Counters,
+    metrics: Arc<RuntimeMetrics>,
+}
+
+/// Bounded queue between rule evaluation and notifiers. Submitting never blocks,
+/// so a slow SMTP server can't stall the monitoring loop; a fixed number of
+/// workers deliver alerts to email and webhooks in the background.
+pub struct Dispatcher {
+    shared: Arc<Shared>,
+    notifiers: Arc<Notifiers>,
+}
+
+impl Dispatcher {
+    /// Creates the queue and spawns the delivery workers on the current runtime.
+    pub fn start(config: &Config, notifiers: Arc<Notifiers>, metrics: Arc<RuntimeMetrics>, redactor: Arc<Redactor>) -> Self {
+        let dispatcher = Self::new(config, notifiers, metrics, redactor);
+        let shared = &dispatcher.shared;
+        for _ in 0..shared.config.concurrency {
+            tokio::spawn(worker(shared.clone(), dispatcher.notifiers.clone()));
+        }
+        
+        info!(
+            "Notification dispatcher started (queue size {}, {} workers, {})",
+            shared.config.queue_size, shared.config.concurrency, shared.config.overflow_policy
+        );
+        dispatcher
+    }
+    
+    /// Creates the queue without any workers, so alerts stay queued.
+    fn new(config: &Config, notifiers: Arc<Notifiers>, metrics: Arc<RuntimeMetrics>, redactor: Arc<Redactor>) -> Self {
+        let mut dispatch_config = config.dispatch.clone();
+        dispatch_config.queue_size = dispatch_config.queue_size.max(1);
+        dispatch_config.concurrency = dispatch_config.concurrency.max(1);
+        if dispatch_config.overflow_policy != "drop_oldest" && dispatch_config.overflow_policy != "drop_newest" {
+            warn!("Unknown dispatch overflow policy '{}', using drop_oldest", dispatch_config.overflow_policy);
+            dispatch_config.overflow_policy = "drop_oldest".to_string();
+        }
+        
+        let shared = Arc::new(Shared {
+            queue: Mutex::new(VecDeque::with_capacity(dispatch_config.queue_size)),
+            ready: Notify::new(),
+            inhibit_rules: config.inhibit_rules.iter().filter_map(InhibitRule::compile).collect(),
+            redactor,
+            firing: Mutex::new(HashSet::new()),
+            closed: AtomicBool::new(false),
+            counters: Counters::default(),
+            metrics,
+            config: dispatch_config,
+        });
+        
+        Self { shared, notifiers }
+    }
+    
+    /// Updates the firing rules that inhibit rules matching their `target`.
+    pub fn set_firing(&self, rules: HashSet<String>) {
+        *self.shared.firing.lock().unwrap() = rules;
+    }
+    
+    /// The firing rule that inhibits notifications about `rule`, if any.
+    fn inhibitor(&self, rule: &str) -> Option<String> {
+        let firing = self.shared.firing.lock().unwrap();
+        self.shared.inhibit_rules.iter().find_map(|inhibit| inhibit.source_of(rule, &firing)).cloned()
+    }
+    
+    /// Whether an inhibit rule drops notifications about `rule`.
+    fn inhibited(&self, rule: &str) -> bool {
+        let Some(source) = self.inhibitor(rule) else {
+            return false;
+        };
+        info!("Inhibited {} notification, {} is firing", rule, source);
+        self.shared.counters.inhibited.fetch_add(1, Ordering::Relaxed);
+        true
+    }
+    
+    /// Queues an alert for delivery, applying the inhibit, merge and overflow
+    /// policies.
+    pub fn submit(&self, alert: Alert) {
+        if self.inhibited(&alert.rule) {
+            return;
+        }
+        let shared = &self.shared;
+        if shared.closed.load(Ordering::Relaxed) {
+            warn!("Notification queue is shut down, dropping {} alert", alert.rule);
+            shared.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            return;
+        }
+        let mut queue = shared.queue.lock().unwrap();
+        
+        if shared.config.merge_duplicates {
+            if let Some(queued) = queue.iter_mut().find(|queued| queued.rule == alert.rule) {
+                info!("Merging {} alert into the one already queued", alert.rule);
+                *queued = alert;
+                shared.counters.merged.fetch_add(1, Ordering::Relaxed);
+                return;
+            }
+        }
+        
+        if queue.len() >= shared.config.queue_size {
+            shared.counters.dropped.fetch_add(1, Ordering::Relaxed);
+            if shared.config.overflow_policy == "drop_newest" {
+                warn!("Notification queue full, dropping new {} alert", alert.rule);
+                return;
+            }
+            if let Some(oldest) = queue.pop_front() {
+                warn!("Notification queue full, dropping oldest {} alert", oldest.rule);
+            }
+        }
+        
+        queue.push_back(alert);
+        shared.counters.enqueued.fetch_add(1, Ordering::Relaxed);
+        shared.counters.max_depth.fetch_max(queue.len(), Ordering::Relaxed);
+        shared.metrics.set_queue_depth(queue.len());
+        drop(queue);
+        
+        shared.ready.notify_one();
+    }
+    
+    /// Notifies an escalation tier in the background. Bypasses the queue so
+    /// that merging never swallows an escalation.
+    pub fn escalate(&self, alert: Alert, tier: EscalationTierConfig) {
+        if self.inhibited(&alert.rule) {
+            return;
+        }
+        let span = tracing::info_span!("escalate", rule = %alert.rule, tier = %tier.name);
+        self.deliver_directly(alert, tier.emails, tier.channels, format!("tier {}", tier.name), span);
+    }
+    
+    /// Notifies the team owning a container group in the background, on top
+    /// of the regular channels the alert was submitted to. Inhibitions were
+    /// already counted by [`submit`](Self::submit).
+    pub fn notify_owners(&self, alert: Alert, group: &ContainerGroupConfig) {
+        if self.inhibitor(&alert.rule).is_some() || (group.emails.is_empty() && group.channels.is_empty()) {
+            return;
+        }
+        let span = tracing::info_span!("notify_owners", rule = %alert.rule, group = %group.name);
+        let recipients = format!("the owners of group {}", group.name);
+        self.deliver_directly(alert, group.emails.clone(), group.channels.clone(), recipients, span);
+    }
+    
+    /// Counted as in flight so [`drain`](Self::drain) waits for it.
+    fn deliver_directly(&self, alert: Alert, emails: Vec<String>, channels: Vec<String>, recipients: String, span: tracing::Span) {
+        let shared = self.shared.clone();
+        let notifiers = self.notifiers.clone();
+        shared.counters.in_flight.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(async move {
+            let alert = payload::limit(alert, &shared.config);
+            let mut sent = false;
+            for (channel, delivered) in notifiers.deliver_to(&alert, &emails, &channels).await {
+                shared.metrics.record_notification(&channel, delivered);
+                audit_notification(&alert, &format!("{} to {}", channel, recipients), delivered);
+                sent |= delivered;
+            }
+            if sent {
+                info!("{} alert sent to {}", alert.rule, recipients);
+            } else {
+                error!("Failed to send {} alert to {}", alert.rule, recipients);
+            }
+            shared.counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+        }.instrument(span));
+    }
+    
+    /// Retries emails queued while SMTP was unreachable, in the background.
+    /// Counted as in flight so [`drain`](Self::drain) waits for it.
+    pub fn flush_email_queue(&self) {
+        let shared = self.shared.clone();
+        let notifiers = self.notifiers.clone();
+        shared.counters.in_flight.fetch_add(1, Ordering::Relaxed);
+        tokio::spawn(async move {
+            notifiers.email.flush_queue().await;
+            shared.counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+        });
+    }
+    
+    /// Emails the digest in the background. The task yields whether it was
+    /// delivered, so the caller can record it as sent once it finishes.
+    pub fn send_digest(&self, sections: Vec<DigestSection>) -> JoinHandle<bool> {
+        let sections: Vec<DigestSection> = sections.into_iter()
+            .map(|section| DigestSection { title: section.title, html: self.shared.redactor.text(&section.html) })
+            .collect();
+        let shared = self.shared.clone();
+        let notifiers = self.notifiers.clone();
+        tokio::spawn(async move {
+            let sent = notifiers.email.send_digest(&sections).await;
+            shared.metrics.record_notification("email", sent);
+            sent
+        }.instrument(tracing::info_span!("digest")))
+    }
+    
+    /// Emails a scheduled report in the background, like [`send_digest`](Self::send_digest).
+    pub fn send_report(&self, mut report: Report) -> JoinHandle<bool> {
+        report.html = self.shared.redactor.text(&report.html);
+        let shared = self.shared.clone();
+        let notifiers = self.notifiers.clone();
+        tokio::spawn(async move {
+            let sent = notifiers.email.send_report(&report).await;
+            shared.metrics.record_notification("email", sent);
+            sent
+        }.instrument(tracing::info_span!("report")))
+    }
+    
+    pub fn stats(&self) -> DispatchStats {
+        let counters = &self.shared.counters;
+        DispatchStats {
+            depth: self.shared.queue.lock().unwrap().len(),
+            max_depth: counters.max_depth.load(Ordering::Relaxed),
+            in_flight: counters.in_flight.load(Ordering::Relaxed),
+            enqueued: counters.enqueued.load(Ordering::Relaxed),
+            sent: counters.sent.load(Ordering::Relaxed),
+            failed: counters.failed.load(Ordering::Relaxed),
+            dropped: counters.dropped.load(Ordering::Relaxed),
+            merged: counters.merged.load(Ordering::Relaxed),
+            inhibited: counters.inhibited.load(Ordering::Relaxed),
+        }
+    }
+    
+    /// Waits until queued and in-flight alerts are delivered, up to `drain_timeout`.
+    /// Used before exiting in single check mode. Returns false on timeout.
+    pub async fn drain(&self) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(self.shared.config.drain_timeout);
+        
+        loop {
+            let stats = self.stats();
+            if stats.depth == 0 && stats.in_flight == 0 {
+                return true;
+            }
+            if Instant::now() >= deadline {
+                warn!("Gave up waiting for {} pending notifications", stats.depth + stats.in_flight);
+                return false;
+            }
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+    }
+    
+    /// Drains the queue like [`drain`](Self::drain), then stops the workers.
+    /// Alerts still queued after `drain_timeout` are abandoned.
+    pub async fn shutdown(&self) -> DispatchStats {
+        self.drain().await;
+        self.shared.closed.store(true, Ordering::Relaxed);
+        self.shared.ready.notify_waiters();
+        self.stats()
+    }
+}
+
+async fn worker(shared: Arc<Shared>, notifiers: Arc<Notifiers>) {
+    while !shared.closed.load(Ordering::Relaxed) {
+        let next = {
+            let mut queue = shared.queue.lock().unwrap();
+            let next = queue.pop_front();
+            shared.metrics.set_queue_depth(queue.len());
+            if next.is_some() {
+                shared.counters.in_flight.fetch_add(1, Ordering::Relaxed);
+            }
+            next
+        };
+        
+        let alert = match next {
+            Some(alert) => payload::limit(alert, &shared.config),
+            None => {
+                shared.ready.notified().await;
+                continue;
+            }
+        };
+        
+        // The alert counts as sent when any channel delivered it
+        let mut sent = false;
+        for (channel, delivered) in notifiers.deliver(&alert).await {
+            shared.metrics.record_notification(&channel, delivered);
+            audit_notification(&alert, &channel, delivered);
+            sent |= delivered;
+        }
+        
+        if sent {
+            info!("{} alert sent successfully", alert.rule);
+            shared.counters.sent.fetch_add(1, Ordering::Relaxed);
+        } else {
+            error!("Failed to send {} alert", alert.rule);
+            shared.counters.failed.fetch_add(1, Ordering::Relaxed);
+        }
+        shared.counters.in_flight.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+fn audit_notification(alert: &Alert, channel: &str, delivered: bool) {
+    audit::record(
+        AuditEntry::new(
+            AuditAction::Notification,
+            "monitor",
+            &alert.rule,
+            format!("{} {} alert via {}: {}", alert.severity.label(), alert.state.label(), channel, alert.summary),
+        )
+        .with_success(delivered)
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::Severity;
+    use crate::metrics::RuntimeMetricsSnapshot;
+    
+    fn dispatcher(queue_size: usize, overflow_policy: &str, merge_duplicates: bool) -> Dispatcher {
+        let config = Config {
+            dispatch: DispatchConfig {
+                queue_size,
+                overflow_policy: overflow_policy.to_string(),
+                merge_duplicates,
+                ..DispatchConfig::default()
+            },
+            ..Config::default()
+        };
+        let notifiers = Arc::new(Notifiers {
+            email: Arc::new(EmailNotifier::new(&config)),
+            webhooks: WebhookNotifier::new(&config),
+            chat: ChatNotifier::new(&config),
+            apprise: AppriseNotifier::new(&config),
+            webpush: Arc::new(WebPushNotifier::new(&config)),
+        });
+        let metrics = Arc::new(RuntimeMetrics::restore(RuntimeMetricsSnapshot::default()));
+        Dispatcher::new(&config, notifiers, metrics, Arc::new(Redactor::new(&config.redaction)))
+    }
+    
+    fn alert(rule: &str, summary: &str) -> Alert {
+        Alert::new(rule, Severity::Warning, summary.to_string(), String::new(), String::new())
+    }
+    
+    fn queued(dispatcher: &Dispatcher) -> Vec<(String, String)> {
+        dispatcher.shared.queue.lock().unwrap().iter()
+            .map(|alert| (alert.rule.clone(), alert.summary.clone()))
+            .collect()
+    }
+    
+    fn pairs(expected: &[(&str, &str)]) -> Vec<(String, String)> {
+        expected.iter().map(|(rule, summary)| (rule.to_string(), summary.to_string())).collect()
+    }
+    
+    #[test]
+    fn merges_alerts_for_a_queued_rule() {
+        let merging = dispatcher(10, "drop_oldest", true);
+        merging.submit(alert("cpu", "first"));
+        merging.submit(alert("memory", "only"));
+        merging.submit(alert("cpu", "second"));
+        
+        assert_eq!(queued(&merging), pairs(&[("cpu", "second"), ("memory", "only")]));
+        let stats = merging.stats();
+        assert_eq!((stats.enqueued, stats.merged, stats.dropped), (2, 1, 0));
+        
+        let unmerged = dispatcher(10, "drop_oldest", false);
+        unmerged.submit(alert("cpu", "first"));
+        unmerged.submit(alert("cpu", "second"));
+        assert_eq!(queued(&unmerged), pairs(&[("cpu", "first"), ("cpu", "second")]));
+        assert_eq!(unmerged.stats().merged, 0);
+    }
+    
+    #[test]
+    fn a_full_queue_still_merges() {
+        let dispatcher = dispatcher(2, "drop_newest", true);
+        dispatcher.submit(alert("cpu", "first"));
+        dispatcher.submit(alert("memory", "first"));
+        dispatcher.submit(alert("memory", "second"));
+        
+        assert_eq!(queued(&dispatcher), pairs(&[("cpu", "first"), ("memory", "second")]));
+        assert_eq!(dispatcher.stats().dropped, 0);
+    }
+    
+    #[test]
+    fn drop_oldest_makes_room_for_the_new_alert() {
+        let dispatcher = dispatcher(2, "drop_oldest", true);
+        for rule in ["cpu", "memory", "disk", "swap"] {
+            dispatcher.submit(alert(rule, "firing"));
+        }
+        
+        assert_eq!(queued(&dispatcher), pairs(&[("disk", "firing"), ("swap", "firing")]));
+        let stats = dispatcher.stats();
+        assert_eq!((stats.depth, stats.max_depth, stats.enqueued, stats.dropped), (2, 2, 4, 2));
+    }
+    
+    #[test]
+    fn drop_newest_keeps_the_queued_alerts() {
+        let dispatcher = dispatcher(2, "drop_newest", true);
+        for rule in ["cpu", "memory", "disk", "swap"] {
+            dispatcher.submit(alert(rule, "firing"));
+        }
+        
+        assert_eq!(queued(&dispatcher), pairs(&[("cpu", "firing"), ("memory", "firing")]));
+        let stats = dispatcher.stats();
+        assert_eq!((stats.depth, stats.max_depth, stats.enqueued, stats.dropped), (2, 2, 2, 2));
+    }
+    
+    #[test]
+    fn unknown_policies_drop_the_oldest() {
+        let dispatcher = dispatcher(1, "drop_random", true);
+        dispatcher.submit(alert("cpu", "firing"));
+        dispatcher.submit(alert("memory", "firing"));
+        
+        assert_eq!(queued(&dispatcher), pairs(&[("memory", "firing")]));
+        assert_eq!(dispatcher.stats().dropped, 1);
+    }
+}
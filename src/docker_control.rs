@@ -0,0 +1,190 @@
+#[cfg(feature = "docker")]
+use std::sync::atomic::{AtomicBool, Ordering};
+#[cfg(feature = "docker")]
+use bollard::Docker;
+#[cfg(feature = "docker")]
+use bollard::container::{KillContainerOptions, RemoveContainerOptions, RestartContainerOptions};
+#[cfg(feature = "docker")]
+use bollard::volume::RemoveVolumeOptions;
+#[cfg(feature = "docker")]
+use crate::config::DockerConfig;
+#[cfg(feature = "docker")]
+use crate::docker_monitor::{is_forbidden, AuditedResource, ResourceAudit};
+#[cfg(feature = "docker")]
+use log::{info, warn};
+#[cfg(feature = "docker")]
+use anyhow::{Result, anyhow};
+
+/// Seconds `restart` waits for a container to stop before killing it.
+#[cfg(feature = "docker")]
+const RESTART_TIMEOUT_SECS: isize = 10;
+/// Request timeout for `docker.control_host`, as bollard's default.
+#[cfg(feature = "docker")]
+const REQUEST_TIMEOUT_SECS: u64 = 120;
+
+/// Actions that change containers: restart and kill for remediation, and
+/// removal of pruned resources. Only built when `docker.allow_container_actions`
+/// is set and the socket is not read-only, so a monitor without it has no
+/// code path that mutates Docker. `docker.control_host` gives the actions
+/// their own endpoint, e.g. a socket proxy that allows POST, while
+/// observation stays on a read-only one.
+#[cfg(feature = "docker")]
+pub struct DockerControl {
+    docker: Docker,
+    /// Set once the API refused a write; no further actions are attempted.
+    refused: AtomicBool,
+}
+
+#[cfg(feature = "docker")]
+impl DockerControl {
+    /// `None` when container actions are not allowed.
+    pub fn new(config: &DockerConfig) -> Result<Option<Self>> {
+        if !config.allow_container_actions {
+            return Ok(None);
+        }
+        if config.read_only {
+            warn!("docker.allow_container_actions has no effect on a read-only Docker socket");
+            return Ok(None);
+        }
+        
+        let docker = match config.control_host.as_deref() {
+            Some(host) if host.starts_with("unix://") => {
+                Docker::connect_with_unix(host, REQUEST_TIMEOUT_SECS, bollard::API_DEFAULT_VERSION)?
+            }
+            Some(host) => Docker::connect_with_http(host, REQUEST_TIMEOUT_SECS, bollard::API_DEFAULT_VERSION)?,
+            None => Docker::connect_with_defaults()?,
+        };
+        info!("Container actions enabled{}",
+              config.control_host.as_deref().map(|host| format!(" via {}", host)).unwrap_or_default());
+        Ok(Some(Self {
+            docker,
+            refused: AtomicBool::new(false),
+        }))
+    }
+    
+    pub async fn restart_container(&self, name: &str) -> Result<()> {
+        self.ensure_allowed()?;
+        let result = self.docker.restart_container(name, Some(RestartContainerOptions { t: RESTART_TIMEOUT_SECS })).await;
+        self.check_refused(&result);
+        result?;
+        info!("Restarted container {}", name);
+        Ok(())
+    }
+    
+    /// Sends `signal` (e.g. `SIGKILL`, `SIGTERM`) to the container.
+    pub async fn kill_container(&self, name: &str, signal: &str) -> Result<()> {
+        self.ensure_allowed()?;
+        let result = self.docker.kill_container(name, Some(KillContainerOptions { signal })).await;
+        self.check_refused(&result);
+        result?;
+        info!("Sent {} to container {}", signal, name);
+        Ok(())
+    }
+    
+    /// Removes audited resources whose names match none of the exclusion patterns,
+    /// containers only once they have been stopped for `min_age`. Returns the
+    /// names of removed resources. A refused removal stops the prune.
+    pub async fn prune_resources(&self, audit: &ResourceAudit, exclude: &[glob::Pattern], min_age: chrono::Duration) -> Vec<String> {
+        let excluded = |name: &str| exclude.iter().any(|pattern| pattern.matches(name));
+        let mut removed = Vec::new();
+        if self.ensure_allowed().is_err() {
+            return removed;
+        }
+        
+        let stopped_before = chrono::Utc::now().checked_sub_signed(min_age);
+        let old_enough = |container: &&AuditedResource| match (container.stopped_at, stopped_before) {
+            (Some(stopped_at), Some(stopped_before)) => stopped_at <= stopped_before,
+            _ => false,
+        };
+        for container in audit.exited_containers.iter().filter(|c| !excluded(&c.name)).filter(old_enough) {
+            let result = self.docker.remove_container(&container.name, None::<RemoveContainerOptions>).await;
+            if self.check_refused(&result) {
+                return removed;
+            }
+            match result {
+                Ok(_) => removed.push(format!("container {}", container.name)),
+                Err(e) => warn!("Failed to remove container {}: {}", container.name, e),
+            }
+        }
+        
+        for volume in audit.orphaned_volumes.iter().filter(|v| !excluded(&v.name)) {
+            let result = self.docker.remove_volume(&volume.name, None::<RemoveVolumeOptions>).await;
+            if self.check_refused(&result) {
+                return removed;
+            }
+            match result {
+                Ok(_) => removed.push(format!("volume {}", volume.name)),
+                Err(e) => warn!("Failed to remove volume {}: {}", volume.name, e),
+            }
+        }
+        
+        for network in audit.unused_networks.iter().filter(|n| !excluded(n)) {
+            let result = self.docker.remove_network(network).await;
+            if self.check_refused(&result) {
+                return removed;
+            }
+            match result {
+                Ok(_) => removed.push(format!("network {}", network)),
+                Err(e) => warn!("Failed to remove network {}: {}", network, e),
+            }
+        }
+        
+        info!("Pruned {} unused Docker resources", removed.len());
+        removed
+    }
+    
+    fn ensure_allowed(&self) -> Result<()> {
+        if self.refused.load(Ordering::Relaxed) {
+            return Err(anyhow!("the Docker API refused container actions earlier, check the socket proxy's POST setting"));
+        }
+        Ok(())
+    }
+    
+    /// Remembers that the API refused a write.
+    fn check_refused<T>(&self, result: &Result<T, bollard::errors::Error>) -> bool {
+        if !is_forbidden(result) {
+            return false;
+        }
+        warn!("Docker API refused a container action, disabling further actions");
+        self.refused.store(true, Ordering::Relaxed);
+        true
+    }
+}
+
+#[cfg(not(feature = "docker"))]
+pub use disabled::DockerControl;
+
+/// Stand-in for builds without the `docker` feature, never created.
+#[cfg(not(feature = "docker"))]
+mod disabled {
+    use std::convert::Infallible;
+    use crate::config::DockerConfig;
+    use crate::docker_monitor::ResourceAudit;
+    use anyhow::Result;
+    use log::warn;
+    
+    pub struct DockerControl {
+        never: Infallible,
+    }
+    
+    impl DockerControl {
+        pub fn new(config: &DockerConfig) -> Result<Option<Self>> {
+            if config.allow_container_actions {
+                warn!("docker.allow_container_actions has no effect, this build has no Docker support (cargo feature \"docker\")");
+            }
+            Ok(None)
+        }
+        
+        pub async fn restart_container(&self, _name: &str) -> Result<()> {
+            match self.never {}
+        }
+        
+        pub async fn kill_container(&self, _name: &str, _signal: &str) -> Result<()> {
+            match self.never {}
+        }
+        
+        pub async fn prune_resources(&self, _audit: &ResourceAudit, _exclude: &[glob::Pattern], _min_age: chrono::Duration) -> Vec<String> {
+            match self.never {}
+        }
+    }
+}
\ No newline at end of file
@@ -1,11 +1,22 @@
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+#[cfg(feature = "docker")]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "docker")]
 use bollard::Docker;
-use bollard::container::{StatsOptions};
+#[cfg(feature = "docker")]
+use bollard::container::{ListContainersOptions, StatsOptions};
+#[cfg(feature = "docker")]
 use bollard::models::{ContainerSummary, ContainerInspectResponse};
 use chrono::{DateTime, Utc};
-use crate::config::Config;
+#[cfg(feature = "docker")]
+use crate::config::{Config, parse_duration};
+use crate::units;
+#[cfg(feature = "docker")]
 use log::{info, error, warn};
+#[cfg(feature = "docker")]
 use anyhow::{Result, anyhow};
+#[cfg(feature = "docker")]
 use futures_util::StreamExt;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -13,15 +24,71 @@ pub struct ContainerStats {
     pub id: String,
     pub name: String,
     pub image: String,
+    /// Content addressable image ID (`sha256:...`), changes when the image is rebuilt.
+    #[serde(default)]
+    pub image_id: String,
     pub status: String,
     pub cpu_usage: f64,
     pub memory_usage: u64,
     pub memory_limit: u64,
     pub memory_percent: f64,
+    /// Configured CPU limit in CPUs, `None` when the container is unlimited.
+    #[serde(default)]
+    pub cpu_limit: Option<f64>,
+    /// Configured memory limit in bytes, `None` when the container is unlimited.
+    #[serde(default)]
+    pub configured_memory_limit: Option<u64>,
     pub ports: Vec<String>,
+    #[serde(default)]
+    pub labels: HashMap<String, String>,
+    pub created: DateTime<Utc>,
     pub timestamp: DateTime<Utc>,
 }
 
+impl ContainerStats {
+    pub fn has_limits(&self) -> bool {
+        self.cpu_limit.is_some() || self.configured_memory_limit.is_some()
+    }
+    
+    /// Short human readable description of the configured limits, e.g. `1.50 CPUs / 512 MiB`.
+    pub fn limits_summary(&self) -> String {
+        if !self.has_limits() {
+            return "no limits".to_string();
+        }
+        
+        let cpu = self.cpu_limit
+            .map(|cpus| format!("{:.2} CPUs", cpus))
+            .unwrap_or_else(|| "unlimited CPU".to_string());
+        let memory = self.configured_memory_limit
+            .map(|bytes| units::bytes(bytes as f64))
+            .unwrap_or_else(|| "unlimited memory".to_string());
+        format!("{} / {}", cpu, memory)
+    }
+    
+    /// A running container without limits, ports or labels, for tests to
+    /// adjust with struct update syntax.
+    #[cfg(test)]
+    pub fn for_test(name: &str, image: &str) -> Self {
+        Self {
+            id: name.to_string(),
+            name: name.to_string(),
+            image: image.to_string(),
+            image_id: String::new(),
+            status: "running".to_string(),
+            cpu_usage: 0.0,
+            memory_usage: 0,
+            memory_limit: 0,
+            memory_percent: 0.0,
+            cpu_limit: None,
+            configured_memory_limit: None,
+            ports: Vec::new(),
+            labels: HashMap::new(),
+            created: Utc::now(),
+            timestamp: Utc::now(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct DockerSystemInfo {
     pub version: String,
@@ -36,15 +103,114 @@ pub struct DockerSystemInfo {
     pub cpu_count: u64,
 }
 
+/// Last seen container id per container name, used to detect redeploys.
+#[cfg(feature = "docker")]
+#[derive(Debug, Clone)]
+struct DeployRecord {
+    id: String,
+    recreated_at: Option<DateTime<Utc>>,
+}
+
+/// Docker resources that no running workload uses and could be reclaimed.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct ResourceAudit {
+    pub unused_networks: Vec<String>,
+    pub orphaned_volumes: Vec<AuditedResource>,
+    pub exited_containers: Vec<AuditedResource>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuditedResource {
+    pub name: String,
+    pub size: u64,
+    /// When an exited container stopped, or was created if it never ran.
+    #[serde(default)]
+    pub stopped_at: Option<DateTime<Utc>>,
+}
+
+impl ResourceAudit {
+    pub fn reclaimable_bytes(&self) -> u64 {
+        self.orphaned_volumes.iter()
+            .chain(self.exited_containers.iter())
+            .map(|resource| resource.size)
+            .sum()
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.unused_networks.is_empty() && self.orphaned_volumes.is_empty() && self.exited_containers.is_empty()
+    }
+}
+
+/// Name looked up by the capability probes. It never exists, so an allowed
+/// section answers 404 and a blocked one 403.
+#[cfg(feature = "docker")]
+const PROBE_NAME: &str = "performance-monitor-capability-probe";
+
+/// The Docker API sections the socket grants. A socket proxy such as
+/// tecnativa/docker-socket-proxy forwards only the sections it is configured
+/// for and answers 403 to the rest; features needing a blocked section are
+/// skipped instead of failing every cycle.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct DockerCapabilities {
+    pub containers: bool,
+    pub images: bool,
+    pub networks: bool,
+    pub info: bool,
+    /// `/system/df`, only probed when the resource audit is enabled.
+    pub system: bool,
+}
+
+impl DockerCapabilities {
+    /// Proxy sections that answered 403, e.g. `["IMAGES", "NETWORKS"]`.
+    pub fn denied(&self) -> Vec<&'static str> {
+        [
+            (self.containers, "CONTAINERS"),
+            (self.images, "IMAGES"),
+            (self.networks, "NETWORKS"),
+            (self.info, "INFO"),
+            (self.system, "SYSTEM"),
+        ]
+        .into_iter()
+        .filter(|(allowed, _)| !allowed)
+        .map(|(_, section)| section)
+        .collect()
+    }
+    
+    /// The resource audit reads disk usage and networks.
+    pub fn can_audit(&self) -> bool {
+        self.containers && self.networks && self.system
+    }
+}
+
+/// True when the API refused the request rather than failing it.
+#[cfg(feature = "docker")]
+pub(crate) fn is_forbidden<T>(result: &Result<T, bollard::errors::Error>) -> bool {
+    matches!(result, Err(bollard::errors::Error::DockerResponseServerError { status_code: 403, .. }))
+}
+
+/// Read-only observation of containers. Everything that changes Docker state
+/// lives in `DockerControl`, which only exists when container actions are
+/// allowed.
+/// Configured CPU limit in CPUs and memory limit in bytes.
+#[cfg(feature = "docker")]
+type Limits = (Option<f64>, Option<u64>);
+
+#[cfg(feature = "docker")]
 pub struct DockerMonitor {
     docker: Docker,
-    #[allow(dead_code)]
-    config: Config,
+    config: Arc<Config>,
+    deploys: Mutex<HashMap<String, DeployRecord>>,
+    /// Configured limits by container id. A recreated container has a new
+    /// id, so each container is inspected once rather than every cycle.
+    limits: Mutex<HashMap<String, Limits>>,
+    capabilities: DockerCapabilities,
 }
 
+#[cfg(feature = "docker")]
 impl DockerMonitor {
-    pub async fn new(config: Config) -> Result<Self> {
-        let docker = Docker::connect_with_local_defaults()?;
+    pub async fn new(config: Arc<Config>) -> Result<Self> {
+        // Honors DOCKER_HOST, e.g. tcp://docker-proxy:2375 for a socket proxy
+        let docker = Docker::connect_with_defaults()?;
         
         // Test connection
         match docker.ping().await {
@@ -55,21 +221,61 @@ impl DockerMonitor {
             }
         }
         
+        let capabilities = Self::probe_capabilities(&docker, &config).await;
+        let denied = capabilities.denied();
+        if !denied.is_empty() {
+            warn!("Docker API denies {}; features needing them are disabled", denied.join(", "));
+        }
+        
         Ok(Self {
             docker,
             config,
+            deploys: Mutex::new(HashMap::new()),
+            limits: Mutex::new(HashMap::new()),
+            capabilities,
         })
     }
     
+    /// Probes each API section with a read-only request.
+    async fn probe_capabilities(docker: &Docker, config: &Config) -> DockerCapabilities {
+        let containers = docker.list_containers(Some(ListContainersOptions::<String> {
+            limit: Some(1),
+            ..Default::default()
+        })).await;
+        let images = docker.inspect_image(PROBE_NAME).await;
+        let networks = docker.inspect_network::<String>(PROBE_NAME, None).await;
+        let info = docker.info().await;
+        let system = if config.resource_audit.enabled {
+            !is_forbidden(&docker.df().await)
+        } else {
+            true
+        };
+        
+        DockerCapabilities {
+            containers: !is_forbidden(&containers),
+            images: !is_forbidden(&images),
+            networks: !is_forbidden(&networks),
+            info: !is_forbidden(&info),
+            system,
+        }
+    }
+    
+    pub fn capabilities(&self) -> DockerCapabilities {
+        self.capabilities
+    }
+    
+    #[tracing::instrument(name = "container_stats", skip_all)]
     pub async fn get_container_stats(&self) -> Result<Vec<ContainerStats>> {
         let containers = self.docker.list_containers::<String>(None).await?;
-        let mut container_stats = Vec::new();
+        self.limits.lock().unwrap().retain(|id, _| containers.iter().any(|container| container.id.as_ref() == Some(id)));
+        let mut container_stats = Vec::with_capacity(containers.len());
         
         for container in containers {
-            match self.get_single_container_stats(&container).await {
+            let id = container.id.clone();
+            match self.get_single_container_stats(container).await {
                 Ok(stats) => container_stats.push(stats),
                 Err(e) => {
-                    error!("Error getting stats for container {:?}: {}", container.id, e);
+                    error!("Error getting stats for container {:?}: {}", id, e);
                     continue;
                 }
             }
@@ -77,40 +283,61 @@ impl DockerMonitor {
         
         // Sort by CPU usage (highest first)
         container_stats.sort_by(|a, b| b.cpu_usage.partial_cmp(&a.cpu_usage).unwrap());
+        self.track_deploys(&container_stats);
         
         Ok(container_stats)
     }
     
-    async fn get_single_container_stats(&self, container: &ContainerSummary) -> Result<ContainerStats> {
-        let id = container.id.as_deref().unwrap_or("unknown");
-        let name = container.names.as_ref()
-            .and_then(|names| names.first())
-            .map(|s| s.strip_prefix('/').unwrap_or(s))
-            .unwrap_or("unknown")
-            .to_string();
+    #[tracing::instrument(
+        name = "single_container_stats",
+        skip_all,
+        fields(container = container.names.as_ref().and_then(|names| names.first()).map(|name| name.trim_start_matches('/')))
+    )]
+    async fn get_single_container_stats(&self, container: ContainerSummary) -> Result<ContainerStats> {
+        // Get CPU and memory stats
+        let (cpu_usage, memory_usage, memory_limit, memory_percent) = 
+            self.calculate_resource_usage(&container).await?;
+        let (cpu_limit, configured_memory_limit) = self.get_resource_limits(&container).await;
+        
+        // Move the strings out of the summary rather than copying them
+        let mut id = container.id.unwrap_or_else(|| "unknown".to_string());
+        id.truncate(12);
+        let name = container.names
+            .and_then(|names| names.into_iter().next())
+            .map(|name| match name.strip_prefix('/') {
+                Some(stripped) => stripped.to_string(),
+                None => name,
+            })
+            .unwrap_or_else(|| "unknown".to_string());
         
-        let image = container.image.as_deref().unwrap_or("unknown").to_string();
-        let status = container.status.as_deref().unwrap_or("unknown").to_string();
+        let image = container.image.unwrap_or_else(|| "unknown".to_string());
+        let image_id = container.image_id.unwrap_or_default();
+        let status = container.status.unwrap_or_else(|| "unknown".to_string());
+        let labels = container.labels.unwrap_or_default();
+        let created = container.created
+            .and_then(|ts| DateTime::from_timestamp(ts, 0))
+            .unwrap_or_else(Utc::now);
         
         // Get ports - simplified implementation
         let ports = Vec::new();
         // For now, skip port parsing to avoid type issues
         // In production, you would implement proper port parsing
         
-        // Get CPU and memory stats
-        let (cpu_usage, memory_usage, memory_limit, memory_percent) = 
-            self.calculate_resource_usage(container).await?;
-        
         Ok(ContainerStats {
-            id: id.chars().take(12).collect(),
+            id,
             name,
             image,
+            image_id,
             status,
             cpu_usage,
             memory_usage,
             memory_limit,
             memory_percent,
+            cpu_limit,
+            configured_memory_limit,
             ports,
+            labels,
+            created,
             timestamp: Utc::now(),
         })
     }
@@ -151,6 +378,81 @@ impl DockerMonitor {
         Ok(0.0)
     }
     
+    /// Reads the configured CPU (in CPUs) and memory (in bytes) limits of a
+    /// container, inspecting it only the first time its id is seen.
+    async fn get_resource_limits(&self, container: &ContainerSummary) -> Limits {
+        let Some(id) = container.id.as_deref() else {
+            return (None, None);
+        };
+        if let Some(limits) = self.limits.lock().unwrap().get(id) {
+            return *limits;
+        }
+        
+        // A failed inspect is retried next cycle rather than cached as unlimited
+        let host_config = match self.docker.inspect_container(id, None).await {
+            Ok(info) => info.host_config,
+            Err(e) => {
+                warn!("Error inspecting container {}: {}", id, e);
+                return (None, None);
+            }
+        };
+        let limits = host_config.map(|host_config| Self::limits(&host_config)).unwrap_or((None, None));
+        self.limits.lock().unwrap().insert(id.to_string(), limits);
+        limits
+    }
+    
+    fn limits(host_config: &bollard::models::HostConfig) -> Limits {
+        let cpu_limit = match (host_config.nano_cpus, host_config.cpu_quota, host_config.cpu_period) {
+            (Some(nano_cpus), _, _) if nano_cpus > 0 => Some(nano_cpus as f64 / 1_000_000_000.0),
+            (_, Some(quota), Some(period)) if quota > 0 && period > 0 => Some(quota as f64 / period as f64),
+            _ => None,
+        };
+        let memory_limit = host_config.memory.filter(|memory| *memory > 0).map(|memory| memory as u64);
+        
+        (cpu_limit, memory_limit)
+    }
+    
+    /// Returns running containers without CPU or memory limits whose usage exceeds
+    /// the configured absolute thresholds for unlimited containers.
+    pub fn unlimited_over_threshold(&self, containers: &[ContainerStats]) -> Vec<ContainerStats> {
+        let cpu_threshold = self.config.monitoring.unlimited_container_cpu_threshold;
+        let memory_threshold = self.config.monitoring.unlimited_container_memory_mb * 1024 * 1024;
+        
+        containers.iter()
+            .filter(|container| {
+                let cpu_over = cpu_threshold > 0.0
+                    && container.cpu_limit.is_none()
+                    && container.cpu_usage > cpu_threshold;
+                let memory_over = memory_threshold > 0
+                    && container.configured_memory_limit.is_none()
+                    && container.memory_usage > memory_threshold;
+                cpu_over || memory_over
+            })
+            .filter(|container| !self.in_deploy_grace(container))
+            .cloned()
+            .collect()
+    }
+    
+    /// Containers created within `newcomer_max_age` that already exceed the
+    /// newcomer CPU or memory threshold. Deploy grace does not apply here.
+    pub fn newcomers_over_threshold(&self, containers: &[ContainerStats]) -> Vec<ContainerStats> {
+        let monitoring = &self.config.monitoring;
+        let max_age = chrono::Duration::seconds(monitoring.newcomer_max_age as i64);
+        let now = Utc::now();
+        
+        containers.iter()
+            .filter(|container| now - container.created <= max_age)
+            .filter(|container| {
+                let cpu_over = monitoring.newcomer_cpu_threshold > 0.0
+                    && container.cpu_usage > monitoring.newcomer_cpu_threshold;
+                let memory_over = monitoring.newcomer_memory_threshold > 0.0
+                    && container.memory_percent > monitoring.newcomer_memory_threshold;
+                cpu_over || memory_over
+            })
+            .cloned()
+            .collect()
+    }
+    
     #[allow(dead_code)]
     pub async fn get_top_cpu_containers(&self, limit: usize) -> Result<Vec<ContainerStats>> {
         let mut all_stats = self.get_container_stats().await?;
@@ -158,11 +460,71 @@ impl DockerMonitor {
         Ok(all_stats)
     }
     
-    pub async fn check_container_cpu_threshold(&self, threshold: f64) -> Result<(bool, Vec<ContainerStats>)> {
-        let container_stats = self.get_container_stats().await?;
+    /// Records the current container ids and notes containers whose id changed
+    /// since the previous cycle, i.e. containers that were recreated by a deploy.
+    fn track_deploys(&self, containers: &[ContainerStats]) {
+        let mut deploys = self.deploys.lock().unwrap();
+        let now = Utc::now();
+        
+        for container in containers {
+            match deploys.get_mut(&container.name) {
+                Some(record) if record.id != container.id => {
+                    info!("Container {} was recreated ({} -> {})", container.name, record.id, container.id);
+                    record.id = container.id.clone();
+                    record.recreated_at = Some(now);
+                }
+                Some(_) => {}
+                None => {
+                    deploys.insert(container.name.clone(), DeployRecord {
+                        id: container.id.clone(),
+                        recreated_at: None,
+                    });
+                }
+            }
+        }
+    }
+    
+    /// Returns true while a container is inside its deploy grace period.
+    ///
+    /// The grace period comes from the container's deploy grace label (measured from
+    /// the container's creation time) or, for recreated containers without the label,
+    /// from `deploy_grace_period` in the monitoring config.
+    pub fn in_deploy_grace(&self, container: &ContainerStats) -> bool {
+        let now = Utc::now();
+        
+        if let Some(value) = container.labels.get(&self.config.monitoring.deploy_grace_label) {
+            match parse_duration(value) {
+                Ok(grace) => {
+                    let grace = chrono::Duration::from_std(grace).unwrap_or_else(|_| chrono::Duration::zero());
+                    return now - container.created < grace;
+                }
+                Err(e) => warn!("Ignoring deploy grace label on container {}: {}", container.name, e),
+            }
+        }
+        
+        if self.config.monitoring.deploy_grace_period == 0 {
+            return false;
+        }
+        
+        let grace = chrono::Duration::seconds(self.config.monitoring.deploy_grace_period as i64);
+        self.deploys.lock().unwrap()
+            .get(&container.name)
+            .and_then(|record| record.recreated_at)
+            .is_some_and(|recreated_at| now - recreated_at < grace)
+    }
+    
+    pub fn filter_high_cpu(&self, container_stats: Vec<ContainerStats>, threshold: f64) -> (bool, Vec<ContainerStats>) {
         let high_cpu_containers: Vec<ContainerStats> = container_stats
             .into_iter()
             .filter(|container| container.cpu_usage > threshold)
+            .filter(|container| {
+                if self.in_deploy_grace(container) {
+                    info!("Suppressing CPU alert for container {} during deploy grace period", container.name);
+                    false
+                } else {
+                    true
+                }
+            })
             .collect();
         
         let has_high_cpu = !high_cpu_containers.is_empty();
@@ -176,10 +538,9 @@ impl DockerMonitor {
             info!("All containers have normal CPU usage");
         }
         
-        Ok((has_high_cpu, high_cpu_containers))
+        (has_high_cpu, high_cpu_containers)
     }
     
-    #[allow(dead_code)]
     pub async fn get_container_info(&self) -> Result<Vec<ContainerInspectResponse>> {
         let containers = self.docker.list_containers::<String>(None).await?;
         let mut container_info = Vec::new();
@@ -199,7 +560,135 @@ impl DockerMonitor {
         Ok(container_info)
     }
     
+    pub async fn audit_resources(&self) -> Result<ResourceAudit> {
+        let usage = self.docker.df().await?;
+        let all_containers = self.docker.list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            ..Default::default()
+        })).await?;
+        
+        // Networks referenced by any container, running or not
+        let used_networks: Vec<String> = all_containers.iter()
+            .filter_map(|container| container.network_settings.as_ref())
+            .filter_map(|settings| settings.networks.as_ref())
+            .flat_map(|networks| networks.keys().cloned())
+            .collect();
+        
+        let unused_networks = self.docker.list_networks::<String>(None).await?
+            .into_iter()
+            .filter_map(|network| network.name)
+            .filter(|name| !["bridge", "host", "none"].contains(&name.as_str()))
+            .filter(|name| !used_networks.contains(name))
+            .collect();
+        
+        let orphaned_volumes = usage.volumes.unwrap_or_default()
+            .into_iter()
+            .filter_map(|volume| {
+                let usage = volume.usage_data?;
+                (usage.ref_count == 0).then(|| AuditedResource {
+                    name: volume.name,
+                    size: usage.size.max(0) as u64,
+                    stopped_at: None,
+                })
+            })
+            .collect();
+        
+        // `created` containers are about to be started by compose or an
+        // orchestrator, they are not leftovers
+        let mut exited_containers = Vec::new();
+        for container in usage.containers.unwrap_or_default() {
+            if !matches!(container.state.as_deref(), Some("exited") | Some("dead")) {
+                continue;
+            }
+            let name = container.names.as_ref()
+                .and_then(|names| names.first())
+                .map(|name| name.trim_start_matches('/').to_string())
+                .or(container.id.clone())
+                .unwrap_or_else(|| "unknown".to_string());
+            exited_containers.push(AuditedResource {
+                stopped_at: self.stopped_at(&name, container.created).await,
+                name,
+                size: container.size_rw.unwrap_or(0).max(0) as u64,
+            });
+        }
+        
+        Ok(ResourceAudit {
+            unused_networks,
+            orphaned_volumes,
+            exited_containers,
+        })
+    }
+    
+    /// When the container last stopped, or its creation time when it never
+    /// ran. `None` when neither is known.
+    async fn stopped_at(&self, name: &str, created: Option<i64>) -> Option<DateTime<Utc>> {
+        let created = created.and_then(|created| DateTime::from_timestamp(created, 0));
+        let finished_at = match self.docker.inspect_container(name, None).await {
+            Ok(info) => info.state.and_then(|state| state.finished_at),
+            Err(e) => {
+                warn!("Error inspecting container {}: {}", name, e);
+                return None;
+            }
+        };
+        // Docker reports 0001-01-01T00:00:00Z for containers that never ran
+        finished_at
+            .and_then(|finished_at| DateTime::parse_from_rfc3339(&finished_at).ok())
+            .map(|finished_at| finished_at.with_timezone(&Utc))
+            .filter(|finished_at| created.is_none_or(|created| *finished_at >= created))
+            .or(created)
+    }
+    
+    /// State (`running`, `exited`, ...) of every container, keyed by name.
+    pub async fn container_states(&self) -> Result<HashMap<String, String>> {
+        let containers = self.docker.list_containers(Some(ListContainersOptions::<String> {
+            all: true,
+            ..Default::default()
+        })).await?;
+        
+        Ok(containers.into_iter()
+            .filter_map(|container| {
+                let name = container.names?.first()?.trim_start_matches('/').to_string();
+                Some((name, container.state.unwrap_or_default()))
+            })
+            .collect())
+    }
+    
+    /// Host PID of the main process of every running container, keyed by
+    /// name, for reading the container's network namespace.
+    pub async fn container_pids(&self) -> Result<Vec<(String, u32)>> {
+        let containers = self.docker.list_containers(None::<ListContainersOptions<String>>).await?;
+        
+        let mut pids = Vec::new();
+        for container in containers {
+            let (Some(id), Some(name)) = (container.id, container.names.and_then(|names| names.first().cloned())) else {
+                continue;
+            };
+            match self.docker.inspect_container(&id, None).await {
+                Ok(info) => {
+                    if let Some(pid) = info.state.and_then(|state| state.pid).filter(|pid| *pid > 0) {
+                        pids.push((name.trim_start_matches('/').to_string(), pid as u32));
+                    }
+                }
+                Err(e) => warn!("Error inspecting container {}: {}", id, e),
+            }
+        }
+        Ok(pids)
+    }
+    
+    /// Repo digests (`repository@sha256:...`) of a local image, empty for
+    /// locally built images or when the image cannot be inspected.
+    pub async fn image_repo_digests(&self, image_id: &str) -> Result<Vec<String>> {
+        if !self.capabilities.images {
+            return Ok(Vec::new());
+        }
+        let image = self.docker.inspect_image(image_id).await?;
+        Ok(image.repo_digests.unwrap_or_default())
+    }
+    
     pub async fn get_docker_system_info(&self) -> Result<DockerSystemInfo> {
+        if !self.capabilities.info {
+            return Err(anyhow!("Docker API denies INFO"));
+        }
         let info = self.docker.info().await?;
         let version = self.docker.version().await?;
         
@@ -216,4 +705,69 @@ impl DockerMonitor {
             cpu_count: info.ncpu.map_or(0, |n| n as u64),
         })
     }
+}
+
+#[cfg(not(feature = "docker"))]
+pub use disabled::DockerMonitor;
+
+/// Stand-in for builds without the `docker` feature. It cannot be created,
+/// so callers take the same path as when the Docker daemon is unreachable.
+#[cfg(not(feature = "docker"))]
+mod disabled {
+    use std::collections::HashMap;
+    use std::convert::Infallible;
+    use std::sync::Arc;
+    use crate::config::Config;
+    use anyhow::{Result, anyhow};
+    use super::{ContainerStats, DockerCapabilities, DockerSystemInfo, ResourceAudit};
+    
+    pub struct DockerMonitor {
+        never: Infallible,
+    }
+    
+    impl DockerMonitor {
+        pub async fn new(_config: Arc<Config>) -> Result<Self> {
+            Err(anyhow!("this build has no Docker support (cargo feature \"docker\")"))
+        }
+        
+        pub fn capabilities(&self) -> DockerCapabilities {
+            match self.never {}
+        }
+        
+        pub async fn get_container_stats(&self) -> Result<Vec<ContainerStats>> {
+            match self.never {}
+        }
+        
+        pub fn unlimited_over_threshold(&self, _containers: &[ContainerStats]) -> Vec<ContainerStats> {
+            match self.never {}
+        }
+        
+        pub fn newcomers_over_threshold(&self, _containers: &[ContainerStats]) -> Vec<ContainerStats> {
+            match self.never {}
+        }
+        
+        pub fn filter_high_cpu(&self, _container_stats: Vec<ContainerStats>, _threshold: f64) -> (bool, Vec<ContainerStats>) {
+            match self.never {}
+        }
+        
+        pub async fn audit_resources(&self) -> Result<ResourceAudit> {
+            match self.never {}
+        }
+        
+        pub async fn container_states(&self) -> Result<HashMap<String, String>> {
+            match self.never {}
+        }
+        
+        pub async fn container_pids(&self) -> Result<Vec<(String, u32)>> {
+            match self.never {}
+        }
+        
+        pub async fn image_repo_digests(&self, _image_id: &str) -> Result<Vec<String>> {
+            match self.never {}
+        }
+        
+        pub async fn get_docker_system_info(&self) -> Result<DockerSystemInfo> {
+            match self.never {}
+        }
+    }
 }
\ No newline at end of file
@@ -0,0 +1,297 @@
+use std::env;
+use std::fs;
+use std::os::unix::fs::{FileTypeExt, MetadataExt};
+use std::os::unix::net::UnixStream;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::config::Config;
+use crate::docker_monitor::DockerMonitor;
+use crate::kubernetes;
+use crate::leader;
+use crate::network;
+use crate::platform;
+use crate::redact;
+use crate::storage;
+
+const DEFAULT_DOCKER_SOCKET: &str = "/var/run/docker.sock";
+
+/// Outcome of a single environment diagnostic.
+pub struct Diagnostic {
+    pub name: &'static str,
+    pub ok: bool,
+    pub detail: String,
+    pub remediation: Option<String>,
+}
+
+impl Diagnostic {
+    fn ok(name: &'static str, detail: String) -> Self {
+        Self { name, ok: true, detail, remediation: None }
+    }
+    
+    fn fail(name: &'static str, detail: String, remediation: &str) -> Self {
+        Self { name, ok: false, detail, remediation: Some(remediation.to_string()) }
+    }
+}
+
+fn running_in_container() -> bool {
+    Path::new("/.dockerenv").exists()
+        || fs::read_to_string("/proc/1/cgroup")
+            .map(|cgroup| cgroup.contains("docker") || cgroup.contains("kubepods"))
+            .unwrap_or(false)
+}
+
+/// Supplementary group ids of this process, read from /proc/self/status.
+fn process_groups() -> Vec<u32> {
+    fs::read_to_string("/proc/self/status")
+        .ok()
+        .and_then(|status| {
+            status.lines()
+                .find(|line| line.starts_with("Groups:"))
+                .map(|line| line["Groups:".len()..].split_whitespace().filter_map(|g| g.parse().ok()).collect())
+        })
+        .unwrap_or_default()
+}
+
+/// Checks the local Docker socket and explains the most common connection failures.
+pub fn check_docker_socket() -> Diagnostic {
+    if let Ok(host) = env::var("DOCKER_HOST") {
+        if !host.starts_with("unix://") {
+            return Diagnostic::ok("docker socket", format!("DOCKER_HOST={} (remote daemon, socket not checked)", host));
+        }
+    }
+    
+    let socket = env::var("DOCKER_HOST")
+        .ok()
+        .and_then(|host| host.strip_prefix("unix://").map(|path| path.to_string()))
+        .unwrap_or_else(|| DEFAULT_DOCKER_SOCKET.to_string());
+    
+    let metadata = match fs::metadata(&socket) {
+        Ok(metadata) => metadata,
+        Err(_) if running_in_container() => {
+            return Diagnostic::fail(
+                "docker socket",
+                format!("{} not found inside this container", socket),
+                "Mount the host socket, e.g. `-v /var/run/docker.sock:/var/run/docker.sock:ro`, or set DOCKER_HOST to a socket proxy (tcp://docker-proxy:2375).",
+            );
+        }
+        Err(_) => {
+            return Diagnostic::fail(
+                "docker socket",
+                format!("{} not found", socket),
+                "Make sure Docker is installed and running (`sudo systemctl start docker`), or set DOCKER_HOST if the daemon listens elsewhere.",
+            );
+        }
+    };
+    
+    if !metadata.file_type().is_socket() {
+        return Diagnostic::fail(
+            "docker socket",
+            format!("{} exists but is not a socket", socket),
+            "Check the volume mount; mounting a non-existent host path creates an empty directory instead of the socket.",
+        );
+    }
+    
+    match UnixStream::connect(&socket) {
+        Ok(_) => Diagnostic::ok("docker socket", format!("{} is accessible", socket)),
+        Err(e) if e.kind() == std::io::ErrorKind::PermissionDenied => {
+            let in_group = process_groups().contains(&metadata.gid());
+            let remediation = if in_group {
+                "The socket group matches but access is still denied; check SELinux/AppArmor labels (`:z` mount option) or socket mode."
+            } else if running_in_container() {
+                "Run the container with `--group-add $(stat -c %g /var/run/docker.sock)` so the monitor user can read the socket."
+            } else {
+                "Add the user to the docker group (`sudo usermod -aG docker $USER`) and log in again."
+            };
+            Diagnostic::fail("docker socket", format!("permission denied on {} (socket gid {})", socket, metadata.gid()), remediation)
+        }
+        Err(e) => Diagnostic::fail(
+            "docker socket",
+            format!("cannot connect to {}: {}", socket, e),
+            "The socket exists but nothing is listening; restart the Docker daemon.",
+        ),
+    }
+}
+
+/// Remediation hints logged when the Docker monitor fails to start.
+pub fn docker_remediation() -> Option<String> {
+    let diagnostic = check_docker_socket();
+    diagnostic.remediation.map(|remediation| format!("{}. {}", diagnostic.detail, remediation))
+}
+
+async fn check_smtp(config: &Config) -> Diagnostic {
+    if !config.email.enabled {
+        return Diagnostic::ok("smtp", "email notifications disabled".to_string());
+    }
+    
+    let address = format!("{}:{}", config.email.smtp_server, config.email.smtp_port);
+    let connect = network::connect(&config.email.smtp_server, config.email.smtp_port, &config.network);
+    match tokio::time::timeout(Duration::from_secs(5), connect).await {
+        Ok(Ok(_)) => Diagnostic::ok("smtp", format!("{} reachable", address)),
+        Ok(Err(e)) => Diagnostic::fail("smtp", format!("cannot connect to {}: {}", address, e),
+            "Check smtp_server/smtp_port and outbound firewall rules (port 587 is often blocked on VPS providers)."),
+        Err(_) => Diagnostic::fail("smtp", format!("timeout connecting to {}", address),
+            "Check outbound firewall rules and proxy settings."),
+    }
+}
+
+/// Checks that the proxy used for outbound HTTP(S) accepts connections.
+async fn check_proxy(config: &Config) -> Diagnostic {
+    let Some((proxy, source)) = network::effective_proxy(&config.network) else {
+        return Diagnostic::ok("proxy", "none (direct connections)".to_string());
+    };
+    
+    let redacted = network::redact_proxy(&proxy);
+    let url = match reqwest::Url::parse(&proxy) {
+        Ok(url) => url,
+        Err(e) => return Diagnostic::fail("proxy", format!("invalid proxy {} from {}: {}", redacted, source, e),
+            "Use a full URL such as http://proxy.example.com:3128."),
+    };
+    let (Some(host), Some(port)) = (url.host_str(), url.port_or_known_default()) else {
+        return Diagnostic::fail("proxy", format!("proxy {} from {} has no host", redacted, source),
+            "Use a full URL such as http://proxy.example.com:3128.");
+    };
+    
+    let host = host.trim_start_matches('[').trim_end_matches(']');
+    match tokio::time::timeout(Duration::from_secs(5), network::connect(host, port, &config.network)).await {
+        Ok(Ok(_)) => Diagnostic::ok("proxy", format!("{} from {} reachable", redacted, source)),
+        Ok(Err(e)) => Diagnostic::fail("proxy", format!("cannot connect to {} from {}: {}", redacted, source, e),
+            "Check the proxy address, or unset it if this host has direct internet access."),
+        Err(_) => Diagnostic::fail("proxy", format!("timeout connecting to {} from {}", redacted, source),
+            "Check the proxy address and outbound firewall rules."),
+    }
+}
+
+fn check_writable(name: &'static str, path: &str) -> Diagnostic {
+    if path.is_empty() {
+        return Diagnostic::ok(name, "disabled".to_string());
+    }
+    
+    let dir = Path::new(path).parent().filter(|p| !p.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    let probe = dir.join(".performance-monitor-doctor");
+    match fs::write(&probe, b"") {
+        Ok(_) => {
+            let _ = fs::remove_file(&probe);
+            Diagnostic::ok(name, format!("{} is writable", dir.display()))
+        }
+        Err(e) => Diagnostic::fail(name, format!("cannot write to {}: {}", dir.display(), e),
+            "Point the path at a writable directory or fix its ownership."),
+    }
+}
+
+/// The file backends need a writable directory, PostgreSQL a connection
+/// that can read the state.
+fn check_storage(config: &Config) -> Diagnostic {
+    match config.storage.backend.as_str() {
+        "file" => check_writable("state storage", &config.monitoring.state_file),
+        "sqlite" => check_writable("state storage", &storage::sqlite_path(config)),
+        _ => match storage::open(config).and_then(|storage| match storage {
+            Some(mut storage) => storage.load().map(|_| storage.describe()),
+            None => Ok("disabled".to_string()),
+        }) {
+            Ok(detail) => Diagnostic::ok("state storage", detail),
+            Err(e) => Diagnostic::fail("state storage", format!("{} backend: {:#}", config.storage.backend, e),
+                "Check storage.backend and storage.url, and that the database accepts the credentials in the URL."),
+        },
+    }
+}
+
+/// The lease must be readable, and replicas must share a namespace or each
+/// leads its own and sends the same alerts.
+fn check_ha(config: &Config) -> Diagnostic {
+    if config.storage.namespace.is_empty() {
+        return Diagnostic::fail("leader election", "storage.namespace is empty, each replica would lead its own hostname".to_string(),
+            "Set storage.namespace to the same value on every replica, or every replica sends the same alerts.");
+    }
+    match leader::open(config).and_then(|mut store| Ok((store.current()?, store.describe()))) {
+        Ok((Some(lease), store)) => Diagnostic::ok("leader election", format!("{}, leader is {}", store, lease.describe())),
+        Ok((None, store)) => Diagnostic::ok("leader election", format!("{}, no leader at the moment", store)),
+        Err(e) => Diagnostic::fail("leader election", format!("{} backend: {:#}", config.ha.backend, e),
+            "Check ha.backend and ha.lock_file, or storage.url for postgres, and that every replica can reach them."),
+    }
+}
+
+/// Runs every environment diagnostic and prints a report. Returns true if all passed.
+pub async fn run(config_path: &str) -> bool {
+    let mut diagnostics = Vec::new();
+    
+    let config = match Config::load_from_file(config_path) {
+        Ok(config) => {
+            diagnostics.push(Diagnostic::ok("config", format!("{} loaded", config_path)));
+            config
+        }
+        Err(e) => {
+            diagnostics.push(Diagnostic::fail("config", format!("{}: {}", config_path, e),
+                "Create the file from the example in the README or pass --config."));
+            Config::default()
+        }
+    };
+    
+    let socket = check_docker_socket();
+    let socket_ok = socket.ok;
+    diagnostics.push(socket);
+    if socket_ok {
+        diagnostics.push(match DockerMonitor::new(Arc::new(config.clone())).await {
+            Ok(monitor) if !monitor.capabilities().containers => Diagnostic::fail("docker api", "access to containers is denied".to_string(),
+                "Allow CONTAINERS on the socket proxy (e.g. CONTAINERS=1 for tecnativa/docker-socket-proxy)."),
+            Ok(monitor) => {
+                let denied = monitor.capabilities().denied();
+                let mut detail = "daemon responded to ping".to_string();
+                if !denied.is_empty() {
+                    detail.push_str(&format!(", proxy denies {} (dependent features skipped)", denied.join(", ")));
+                }
+                let docker = &config.docker;
+                detail.push_str(if docker.allow_container_actions && !docker.read_only {
+                    ", container actions allowed"
+                } else {
+                    ", observe only"
+                });
+                Diagnostic::ok("docker api", detail)
+            }
+            Err(e) => Diagnostic::fail("docker api", e.to_string(),
+                "The socket is reachable but the API call failed; check the daemon logs or socket proxy permissions."),
+        });
+    }
+    
+    if let Some(cluster) = kubernetes::current() {
+        let host_root = match &cluster.host_root {
+            Some(root) => format!("node root at {}", root),
+            None => format!("node root not mounted at {}, disk usage is the pod's", config.kubernetes.host_root),
+        };
+        diagnostics.push(Diagnostic::ok("kubernetes", format!("node {}, pod {}/{}, {}", cluster.node, cluster.namespace, cluster.pod, host_root)));
+    }
+    
+    diagnostics.push(if platform::has_procfs() {
+        Diagnostic::ok("procfs", format!("/proc is available ({})", platform::target()))
+    } else {
+        Diagnostic::fail("procfs", format!("/proc not found ({})", platform::target()),
+            "Network traffic, disk latency and conntrack need procfs and are skipped; on Linux, mount /proc into the container.")
+    });
+    
+    let email = &config.email;
+    if email.enabled && (email.sender_email.is_empty() || email.sender_password.is_empty() || email.recipient_email.is_empty()) {
+        diagnostics.push(Diagnostic::fail("email config", "sender_email, sender_password or recipient_email is empty".to_string(),
+            "Fill in all email fields or set email.enabled to false."));
+    }
+    let invalid = redact::invalid_patterns(&config.redaction);
+    if !invalid.is_empty() {
+        diagnostics.push(Diagnostic::fail("redaction", format!("invalid patterns are ignored: {}", invalid.join("; ")),
+            "Fix the regexes in redaction.patterns and the globs in redaction.labels, or the values they should hide are sent as is."));
+    }
+    diagnostics.push(check_smtp(&config).await);
+    diagnostics.push(check_proxy(&config).await);
+    diagnostics.push(check_storage(&config));
+    if config.ha.enabled {
+        diagnostics.push(check_ha(&config));
+    }
+    diagnostics.push(check_writable("email queue", &config.email.queue_file));
+    
+    for diagnostic in &diagnostics {
+        println!("{} {}: {}", if diagnostic.ok { "✅" } else { "❌" }, diagnostic.name, diagnostic.detail);
+        if let Some(remediation) = &diagnostic.remediation {
+            println!("   → {}", remediation);
+        }
+    }
+    
+    diagnostics.iter().all(|diagnostic| diagnostic.ok)
+}
\ No newline at end of file
@@ -0,0 +1,390 @@
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use openssl::sha::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+use crate::api::{ApiState, Role};
+use crate::chatops;
+use crate::config::{parse_duration, Config, EmailCommandsConfig, NetworkConfig};
+use crate::email_notifier::EmailNotifier;
+use crate::imap::ImapSession;
+use crate::redact::Redactor;
+use crate::signing::{EmailVerifier, Signer};
+use anyhow::{Result, anyhow};
+use log::{error, info, warn};
+
+/// How far in the future a signing time may be, for clocks that are off.
+const CLOCK_SKEW_SECS: i64 = 300;
+
+/// Starts polling the command inbox in the background. Every unseen message
+/// is flagged as seen before it is handled, so a bad message is never retried.
+pub fn spawn(config: &Config, api: Arc<ApiState>, email: Arc<EmailNotifier>, redactor: Arc<Redactor>) {
+    let settings = config.email_commands.clone();
+    if !settings.enabled {
+        return;
+    }
+    
+    let verifier = match EmailVerifier::from_config(&settings) {
+        Ok(verifier) => verifier,
+        Err(e) => {
+            error!("Email commands disabled: {}", e);
+            return;
+        }
+    };
+    if !email.is_enabled() {
+        warn!("email_commands is enabled but email is not, command replies will not be sent");
+    }
+    let network = config.network.clone();
+    
+    tokio::spawn(async move {
+        info!("Polling {} on {} for command emails every {}s", settings.mailbox, settings.imap_server, settings.poll_interval);
+        let mut interval = tokio::time::interval(Duration::from_secs(settings.poll_interval.max(10)));
+        loop {
+            interval.tick().await;
+            // The leader handles the mailbox, a change made here would be lost
+            if api.refuse_changes().is_some() {
+                continue;
+            }
+            if let Err(e) = poll(&settings, &network, &verifier, &api, &email, &redactor).await {
+                warn!("Failed to poll command emails: {}", e);
+            }
+        }
+    });
+}
+
+async fn poll(
+    settings: &EmailCommandsConfig,
+    network: &NetworkConfig,
+    verifier: &EmailVerifier,
+    api: &ApiState,
+    email: &EmailNotifier,
+    redactor: &Redactor,
+) -> Result<()> {
+    let mut session = ImapSession::connect(&settings.imap_server, settings.imap_port, network).await?;
+    session.login(&settings.username, &settings.password).await?;
+    session.select(&settings.mailbox).await?;
+    
+    for uid in session.unseen().await? {
+        let raw = session.fetch(uid).await?;
+        session.mark_seen(uid).await?;
+        handle(settings, verifier, api, email, redactor, &raw).await;
+    }
+    session.logout().await;
+    Ok(())
+}
+
+async fn handle(
+    settings: &EmailCommandsConfig,
+    verifier: &EmailVerifier,
+    api: &ApiState,
+    email: &EmailNotifier,
+    redactor: &Redactor,
+    raw: &[u8],
+) {
+    let message = Entity::parse(raw);
+    let from = message.header("From").map(address).unwrap_or_default();
+    
+    // Unverified mail gets no reply, so forged senders cannot use us to send mail
+    let (signed, signer) = match verify(verifier, raw, &message).await {
+        Ok(verified) => verified,
+        Err(e) => {
+            warn!("Ignoring command email from {}: {}", from, e);
+            return;
+        }
+    };
+    // From: is not signed, only the signer's own addresses are trusted
+    let Some(sender) = sender(settings, &signer, &from) else {
+        warn!("Ignoring command email from {}: signer {} has no address in allowed_senders", from, signer.id);
+        return;
+    };
+    // The signing time makes a resent command differ from a replayed copy
+    let max_age = parse_duration(&settings.max_age).ok()
+        .and_then(|max_age| chrono::Duration::from_std(max_age).ok())
+        .unwrap_or_else(|| chrono::Duration::hours(1));
+    let signed_at = match check_signing_time(signer.signed_at, max_age, Utc::now()) {
+        Ok(signed_at) => signed_at,
+        Err(e) => {
+            warn!("Ignoring command email from {}: {}", sender, e);
+            return;
+        }
+    };
+    if !api.mark_command_handled(digest(&signed, signed_at), signed_at, max_age) {
+        warn!("Ignoring command email from {}: the signed content was already executed", sender);
+        return;
+    }
+    
+    // Only the signed body counts, the subject can be changed in transit
+    let Some(text) = Entity::parse(&signed).text() else {
+        warn!("Ignoring command email from {}: no text/plain part", sender);
+        return;
+    };
+    let command = text.lines().map(str::trim).find(|line| !line.is_empty()).unwrap_or("help");
+    info!("Running emailed command '{}' from {}", command, sender);
+    let reply = chatops::plain_text(&chatops::execute(api, redactor, command, &sender, Role::Admin));
+    
+    let subject = message.header("Subject").unwrap_or("Monitor command");
+    let subject = if subject.to_lowercase().starts_with("re:") { subject.to_string() } else { format!("Re: {}", subject) };
+    email.send_reply(&sender, &subject, &reply, message.header("Message-ID")).await;
+}
+
+/// The signer's address that commands are run as and answered to: one in
+/// `allowed_senders` when it is set, preferably the `From:` address.
+fn sender(settings: &EmailCommandsConfig, signer: &Signer, from: &str) -> Option<String> {
+    let allowed: Vec<&String> = signer.addresses.iter()
+        .filter(|address| settings.allowed_senders.is_empty()
+            || settings.allowed_senders.iter().any(|allowed| allowed.eq_ignore_ascii_case(address)))
+        .collect();
+    allowed.iter().find(|address| address.as_str() == from).or(allowed.first()).map(|address| address.to_string())
+}
+
+/// The signing time when it lies within `max_age` before `now`, allowing
+/// for some clock skew. Signatures without one are refused since their age,
+/// and so a replay, cannot be told.
+fn check_signing_time(signed_at: Option<DateTime<Utc>>, max_age: chrono::Duration, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let signed_at = signed_at.ok_or_else(|| anyhow!("the signature carries no signing time"))?;
+    if signed_at < now - max_age {
+        return Err(anyhow!("it was signed at {}, more than {} ago", signed_at.format("%Y-%m-%d %H:%M UTC"), humantime::format_duration(max_age.to_std().unwrap_or_default())));
+    }
+    if signed_at > now + chrono::Duration::seconds(CLOCK_SKEW_SECS) {
+        return Err(anyhow!("it was signed in the future, at {}", signed_at.format("%Y-%m-%d %H:%M UTC")));
+    }
+    Ok(signed_at)
+}
+
+/// SHA-256 of the signed content and its signing time, hex encoded.
+fn digest(signed: &[u8], signed_at: DateTime<Utc>) -> String {
+    let mut hasher = Sha256::new();
+    hasher.update(signed);
+    hasher.update(signed_at.to_rfc3339().as_bytes());
+    hasher.finish().iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Checks the message signature and returns the signed MIME entity and its
+/// signer.
+async fn verify(verifier: &EmailVerifier, raw: &[u8], message: &Entity<'_>) -> Result<(Vec<u8>, Signer)> {
+    match verifier {
+        EmailVerifier::Smime { .. } => verifier.verify_smime(raw),
+        EmailVerifier::Pgp { .. } => {
+            let (content_type, boundary) = (message.content_type(), message.parameter("Content-Type", "boundary"));
+            let (true, Some(boundary)) = (content_type == "multipart/signed", boundary) else {
+                return Err(anyhow!("message is not PGP/MIME signed"));
+            };
+            let parts = split_parts(message.body, &boundary);
+            let [signed, signature] = parts.as_slice() else {
+                return Err(anyhow!("PGP/MIME message needs exactly two parts"));
+            };
+            let signer = verifier.verify_pgp(signed, &Entity::parse(signature).decoded()).await?;
+            Ok((signed.to_vec(), signer))
+        }
+    }
+}
+
+/// A MIME entity: unfolded headers and the raw body.
+struct Entity<'a> {
+    headers: Vec<(String, String)>,
+    body: &'a [u8],
+}
+
+impl<'a> Entity<'a> {
+    fn parse(data: &'a [u8]) -> Self {
+        let (head, body) = match find(data, b"\r\n\r\n") {
+            Some(end) => (&data[..end], &data[end + 4..]),
+            None => match find(data, b"\n\n") {
+                Some(end) => (&data[..end], &data[end + 2..]),
+                None => (data, &data[data.len()..]),
+            },
+        };
+        
+        let mut headers: Vec<(String, String)> = Vec::new();
+        for line in String::from_utf8_lossy(head).lines() {
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+            } else if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+        Self { headers, body }
+    }
+    
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers.iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+    
+    /// The lowercase media type, `text/plain` when absent.
+    fn content_type(&self) -> String {
+        self.header("Content-Type")
+            .and_then(|value| value.split(';').next())
+            .map(|media| media.trim().to_lowercase())
+            .unwrap_or_else(|| "text/plain".to_string())
+    }
+    
+    /// A `name=value` parameter of a header, e.g. the multipart boundary.
+    fn parameter(&self, header: &str, name: &str) -> Option<String> {
+        self.header(header)?
+            .split(';')
+            .skip(1)
+            .filter_map(|parameter| parameter.split_once('='))
+            .find(|(key, _)| key.trim().eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.trim().trim_matches('"').to_string())
+    }
+    
+    /// The body with its `Content-Transfer-Encoding` undone.
+    fn decoded(&self) -> Vec<u8> {
+        let encoding = self.header("Content-Transfer-Encoding").unwrap_or_default().to_lowercase();
+        match encoding.as_str() {
+            "base64" => {
+                let compact: Vec<u8> = self.body.iter().copied().filter(|byte| !byte.is_ascii_whitespace()).collect();
+                STANDARD.decode(compact).unwrap_or_default()
+            }
+            "quoted-printable" => decode_quoted_printable(self.body),
+            _ => self.body.to_vec(),
+        }
+    }
+    
+    /// The first `text/plain` part, searching nested multiparts.
+    fn text(&self) -> Option<String> {
+        let content_type = self.content_type();
+        if content_type.starts_with("multipart/") {
+            let boundary = self.parameter("Content-Type", "boundary")?;
+            return split_parts(self.body, &boundary).into_iter().find_map(|part| Entity::parse(part).text());
+        }
+        (content_type == "text/plain").then(|| String::from_utf8_lossy(&self.decoded()).into_owned())
+    }
+}
+
+/// The parts of a multipart body, each without the line break that belongs
+/// to the following delimiter, as RFC 3156 signs them.
+fn split_parts<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start = None;
+    let mut offset = 0;
+    
+    while offset < body.len() {
+        let end = find(&body[offset..], b"\n").map_or(body.len(), |index| offset + index + 1);
+        let line = &body[offset..end];
+        if line.starts_with(delimiter.as_bytes()) {
+            if let Some(start) = start {
+                let mut part_end = offset;
+                if part_end > start && body[part_end - 1] == b'\n' {
+                    part_end -= 1;
+                    if part_end > start && body[part_end - 1] == b'\r' {
+                        part_end -= 1;
+                    }
+                }
+                parts.push(&body[start..part_end]);
+            }
+            if line[delimiter.len()..].starts_with(b"--") {
+                break;
+            }
+            start = Some(end);
+        }
+        offset = end;
+    }
+    parts
+}
+
+fn decode_quoted_printable(data: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(data.len());
+    let mut index = 0;
+    while index < data.len() {
+        match data[index] {
+            b'=' if data[index + 1..].starts_with(b"\r\n") => index += 3,
+            b'=' if data[index + 1..].starts_with(b"\n") => index += 2,
+            b'=' if index + 2 < data.len() => {
+                let digit = |byte: u8| (byte as char).to_digit(16);
+                match (digit(data[index + 1]), digit(data[index + 2])) {
+                    (Some(high), Some(low)) => decoded.push((high * 16 + low) as u8),
+                    _ => decoded.extend_from_slice(&data[index..index + 3]),
+                }
+                index += 3;
+            }
+            byte => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// The bare address of a `From` header, e.g. `ops@example.com` from
+/// `Ops <ops@example.com>`.
+fn address(from: &str) -> String {
+    let address = match (from.rfind('<'), from.rfind('>')) {
+        (Some(start), Some(end)) if start < end => &from[start + 1..end],
+        _ => from,
+    };
+    address.trim().to_lowercase()
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+    
+    #[test]
+    fn refuses_old_future_and_undated_signatures() {
+        let now = Utc::now();
+        let hour = Duration::hours(1);
+        assert_eq!(check_signing_time(Some(now - Duration::minutes(59)), hour, now).unwrap(), now - Duration::minutes(59));
+        assert_eq!(check_signing_time(Some(now + Duration::minutes(4)), hour, now).unwrap(), now + Duration::minutes(4));
+        assert!(check_signing_time(Some(now - Duration::minutes(61)), hour, now).is_err());
+        assert!(check_signing_time(Some(now + Duration::minutes(6)), hour, now).is_err());
+        assert!(check_signing_time(None, hour, now).is_err());
+    }
+    
+    #[test]
+    fn resigned_commands_get_a_new_digest() {
+        let now = Utc::now();
+        assert_eq!(digest(b"STATUS", now), digest(b"STATUS", now));
+        assert_ne!(digest(b"STATUS", now), digest(b"STATUS", now + Duration::seconds(1)));
+        assert_ne!(digest(b"STATUS", now), digest(b"SILENCES", now));
+    }
+    
+    #[test]
+    fn splits_multipart_bodies_as_signed() {
+        let body = b"preamble\r\n--b1\r\nContent-Type: text/plain\r\n\r\nSTATUS\r\n--b1\r\nContent-Type: application/pgp-signature\r\n\r\nsig\n\r\n--b1--\r\nepilogue\r\n";
+        assert_eq!(split_parts(body, "b1"), [
+            &b"Content-Type: text/plain\r\n\r\nSTATUS"[..],
+            &b"Content-Type: application/pgp-signature\r\n\r\nsig\n"[..],
+        ]);
+        assert_eq!(split_parts(b"--b1\n\n--b1\nA\n--b1--\n", "b1"), [&b""[..], &b"A"[..]]);
+        assert!(split_parts(b"--other\nA\n--other--\n", "b1").is_empty());
+    }
+    
+    #[test]
+    fn decodes_quoted_printable() {
+        assert_eq!(decode_quoted_printable(b"SILENCE=20web 2h=\r\n deploy=3D1"), b"SILENCE web 2h deploy=1");
+        assert_eq!(decode_quoted_printable(b"caf=C3=A9=\n"), "caf\u{e9}".as_bytes());
+        assert_eq!(decode_quoted_printable(b"=ZZ =+1 =4"), b"=ZZ =+1 =4");
+        assert_eq!(decode_quoted_printable(b"="), b"=");
+    }
+    
+    #[test]
+    fn prefers_the_from_address_among_allowed_signer_addresses() {
+        let signer = Signer {
+            id: "CN=Ops".to_string(),
+            addresses: vec!["ops@example.com".to_string(), "oncall@example.com".to_string()],
+            signed_at: None,
+        };
+        let mut settings = EmailCommandsConfig::default();
+        assert_eq!(sender(&settings, &signer, "oncall@example.com").as_deref(), Some("oncall@example.com"));
+        assert_eq!(sender(&settings, &signer, "someone@example.org").as_deref(), Some("ops@example.com"));
+        
+        settings.allowed_senders = vec!["OnCall@Example.com".to_string()];
+        assert_eq!(sender(&settings, &signer, "ops@example.com").as_deref(), Some("oncall@example.com"));
+        settings.allowed_senders = vec!["admin@example.com".to_string()];
+        assert_eq!(sender(&settings, &signer, "ops@example.com"), None);
+        assert_eq!(address("Ops Team <Ops@Example.com>"), "ops@example.com");
+    }
+}
@@ -1,156 +1,1475 @@
 use lettre::{
-    Message, SmtpTransport, Transport,
-    message::{header::ContentType, MultiPart, SinglePart},
-    transport::smtp::authentication::Credentials,
+    Message,
+    address::Envelope,
+    message::{header::{ContentTransferEncoding, ContentType}, Attachment, MultiPart, SinglePart},
 };
-use chrono::Utc;
+#[cfg(feature = "email")]
+use lettre::{AsyncSmtpTransport, AsyncTransport, Tokio1Executor, transport::smtp::authentication::Credentials};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+use std::fs;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use anyhow::{Result, anyhow};
+use crate::alert::{self, Alert, AlertChart};
+use crate::chart::{self, Series};
+use crate::cloud_metadata::CloudMetadata;
 use crate::config::{Config, EmailConfig};
+use crate::digest::DigestSection;
 use crate::docker_monitor::ContainerStats;
+use crate::incident::IncidentState;
+use crate::html::escape;
+use crate::kubernetes;
+use crate::oncall::OnCall;
+use crate::process_watchdog::ProcessStatus;
+use crate::server_monitor::ProcessUsage;
+use crate::report::{InlineImage, Report};
+use crate::signing::EmailSigner;
+use crate::timezone::{self, Timezone};
+use crate::webhook::describe_tera_error;
+use crate::units;
 use log::{info, error, warn};
+use tera::{Context, Tera};
+
+const SUBJECT_TEMPLATE: &str = "subject";
+/// How long an SMTP connection or command may take before the send fails
+/// and the alert is queued.
+#[cfg_attr(not(feature = "email"), allow(dead_code))]
+const SMTP_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// An alert that could not be delivered, kept on disk until SMTP is reachable again.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct QueuedEmail {
+    subject: String,
+    message: String,
+    created_at: DateTime<Utc>,
+}
 
 pub struct EmailNotifier {
     config: EmailConfig,
+    host_metadata: Option<CloudMetadata>,
+    oncall: Option<Arc<OnCall>>,
     enabled: bool,
+    signer: Option<EmailSigner>,
+    /// Serializes access to the on-disk queue between dispatch workers.
+    queue_lock: Mutex<()>,
+    subject_template: Option<Tera>,
+    emoji: bool,
+    hostname: String,
+    environment: Option<String>,
+    timezone: Timezone,
 }
 
 impl EmailNotifier {
-    pub fn new(config: Config) -> Self {
+    pub fn new(config: &Config) -> Self {
         let email_config = config.email.clone();
         let enabled = email_config.enabled;
         
-        if enabled {
-            if email_config.sender_email.is_empty() 
-                || email_config.sender_password.is_empty() 
-                || email_config.recipient_email.is_empty() {
-                warn!("Email configuration incomplete. Email notifications disabled.");
-                Self {
-                    config: email_config,
-                    enabled: false,
-                }
-            } else {
-                info!("Email notifier initialized");
-                Self {
-                    config: email_config,
-                    enabled: true,
-                }
-            }
-        } else {
-            info!("Email notifications disabled");
-            Self {
-                config: email_config,
-                enabled: false,
-            }
-        }
+        // Never fall back to unsigned mail when signing was asked for
+        let (enabled, signer) = match email_config.signing.as_ref().filter(|_| enabled).map(EmailSigner::from_config) {
+            Some(Ok(signer)) => {
+                info!("Outgoing emails will be signed with {}", signer.method());
+                (enabled, Some(signer))
+            }
+            Some(Err(e)) => {
+                error!("Failed to load email signing key: {}. Email notifications disabled.", e);
+                (false, None)
+            }
+            None => (enabled, None),
+        };
+        
+        let enabled = if !enabled {
+            info!("Email notifications disabled");
+            false
+        } else if email_config.sender_email.is_empty() 
+            || email_config.sender_password.is_empty() 
+            || email_config.recipient_email.is_empty() {
+            warn!("Email configuration incomplete. Email notifications disabled.");
+            false
+        } else if cfg!(not(feature = "email")) {
+            error!("email.enabled is set but this build has no SMTP support, rebuild with `--features email`");
+            false
+        } else {
+            info!("Email notifier initialized");
+            true
+        };
+        
+        let subject_template = email_config.subject_template.as_ref().and_then(|source| {
+            let mut tera = Tera::default();
+            match tera.add_raw_template(SUBJECT_TEMPLATE, source) {
+                Ok(()) => Some(tera),
+                Err(e) => {
+                    error!("Invalid email subject template: {}. Using default subjects.", describe_tera_error(&e));
+                    None
+                }
+            }
+        });
+        
+        let timezone = timezone::load(email_config.timezone.as_deref().unwrap_or(&config.timezone));
+        
+        Self {
+            config: email_config,
+            host_metadata: None,
+            oncall: None,
+            enabled,
+            signer,
+            queue_lock: Mutex::new(()),
+            subject_template,
+            emoji: config.emoji,
+            hostname: kubernetes::hostname(),
+            environment: config.monitoring.environment.clone(),
+            timezone,
+        }
+    }
+    
+    pub fn set_host_metadata(&mut self, metadata: Option<CloudMetadata>) {
+        self.host_metadata = metadata;
+    }
+    
+    /// Sends mail to whoever is on call instead of `recipient_email`.
+    pub fn set_oncall(&mut self, oncall: Option<Arc<OnCall>>) {
+        self.oncall = oncall;
+    }
+    
+    /// The on-call address, `recipient_email` when nobody is on call.
+    fn recipient(&self) -> String {
+        self.oncall.as_ref()
+            .and_then(|oncall| oncall.current(Utc::now()))
+            .unwrap_or_else(|| self.config.recipient_email.clone())
+    }
+    
+    /// Overrides the hostname shown in subjects, for `render`.
+    pub fn set_hostname(&mut self, hostname: &str) {
+        self.hostname = hostname.to_string();
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    pub async fn send_alert(&self, subject: &str, message: &str) -> bool {
+        if !self.enabled {
+            info!("Email notifications disabled. Skipping alert.");
+            return false;
+        }
+        
+        match self.deliver(subject, message).await {
+            Ok(_) => {
+                info!("Alert email sent successfully to {}", self.recipient());
+                true
+            }
+            Err(e) => {
+                error!("Failed to send email alert: {}", e);
+                self.enqueue(subject, message).await;
+                false
+            }
+        }
+    }
+    
+    /// Renders `subject_template` for a rule alert. `title` is the built-in
+    /// subject without its emoji and timestamp, e.g. `HIGH CPU USAGE ALERT`.
+    fn render_subject(&self, alert: &Alert, default_subject: &str) -> String {
+        let Some(tera) = &self.subject_template else {
+            return default_subject.to_string();
+        };
+        
+        let title = default_subject.rsplit_once(" - ").map(|(title, _)| title).unwrap_or(default_subject);
+        let mut context = Context::new();
+        context.insert("severity", alert.severity.label());
+        context.insert("state", alert.state.label());
+        context.insert("rule", &alert.rule);
+        context.insert("summary", &alert.summary);
+        context.insert("value", &alert.value);
+        context.insert("threshold", &alert.threshold);
+        context.insert("title", strip_emoji(title).trim());
+        context.insert("hostname", &self.hostname);
+        context.insert("environment", self.environment.as_deref().unwrap_or_default());
+        context.insert("time", &self.timezone.format(alert.timestamp));
+        
+        match tera.render(SUBJECT_TEMPLATE, &context) {
+            Ok(subject) => subject.trim().to_string(),
+            Err(e) => {
+                warn!("Failed to render email subject template: {}", describe_tera_error(&e));
+                default_subject.to_string()
+            }
+        }
+    }
+    
+    /// Renders the email for an alert as `(subject, html)` exactly as it is
+    /// sent, or `None` when its rule has no template. Resolved and flapping
+    /// notifications share a generic template.
+    pub fn render_rule_alert(&self, alert: &Alert) -> Option<(String, String)> {
+        let (subject, message) = if alert.state != IncidentState::Firing {
+            self.state_change_email(alert)
+        } else {
+            match alert.rule.as_str() {
+                alert::RULE_CPU => self.cpu_email(alert),
+                alert::RULE_HOT_CORE => self.hot_core_email(alert),
+                alert::RULE_MEMORY => self.memory_email(alert),
+                alert::RULE_SWAP => self.swap_email(alert),
+                alert::RULE_DISK => self.disk_email(alert),
+                alert::RULE_BANDWIDTH => self.bandwidth_email(alert),
+                alert::RULE_CONTAINER_CPU => self.container_cpu_email(alert),
+                alert::RULE_UNLIMITED_CONTAINER => self.unlimited_container_email(alert),
+                alert::RULE_NEWCOMER_CONTAINER => self.newcomer_container_email(alert),
+                alert::RULE_PROCESS => self.process_email(alert),
+                alert::RULE_DISK_LATENCY => self.disk_latency_email(alert),
+                alert::RULE_CONNTRACK => self.conntrack_email(alert),
+                alert::RULE_PING => self.ping_email(alert),
+                alert::RULE_FINGERPRINT => self.fingerprint_email(alert),
+                alert::RULE_IMAGE_DRIFT => self.image_drift_email(alert),
+                alert::RULE_GROUP_BUDGET => self.group_budget_email(alert),
+                alert::RULE_TREND => self.trend_email(alert),
+                alert::RULE_MEMORY_LEAK => self.memory_leak_email(alert),
+                alert::RULE_CPU_FREQUENCY => self.cpu_frequency_email(alert),
+                alert::RULE_UPS => self.ups_email(alert),
+                alert::RULE_VM => self.vm_email(alert),
+                alert::RULE_LXD_CONTAINER => self.lxd_container_email(alert),
+                alert::RULE_ZFS => self.zfs_email(alert),
+                alert::RULE_STORAGE_POOL => self.storage_pool_email(alert),
+                alert::RULE_COMMAND => self.command_email(alert),
+                alert::RULE_CRON => self.cron_email(alert),
+                alert::RULE_OUTBOUND => self.outbound_email(alert),
+                _ => return None,
+            }
+        };
+        
+        Some((self.present(&self.render_subject(alert, &subject)), self.present(&message)))
+    }
+    
+    /// Sends a rule alert with its charts as inline images and, when the
+    /// dispatcher truncated it, the full details as an attachment. If delivery
+    /// fails the alert is queued like any other, without either.
+    pub async fn send_rule_alert(&self, alert: &Alert) -> bool {
+        let Some((subject, message)) = self.render_rule_alert(alert) else {
+            warn!("No email template for rule {}", alert.rule);
+            return false;
+        };
+        if alert.charts.is_empty() && alert.attachment.is_none() {
+            return self.send_alert(&subject, &message).await;
+        }
+        if !self.enabled {
+            info!("Email notifications disabled. Skipping alert.");
+            return false;
+        }
+        
+        let images = match render_charts(&alert.charts) {
+            Ok(images) => images,
+            Err(e) => {
+                warn!("Failed to render alert charts: {}", e);
+                Vec::new()
+            }
+        };
+        let body = self.build_related_body(&message, &images)
+            .and_then(|body| match &alert.attachment {
+                Some(attachment) => Ok(MultiPart::mixed().multipart(body).singlepart(
+                    Attachment::new(attachment.filename.clone())
+                        .body(attachment.body.clone(), ContentType::parse(&attachment.content_type)?)
+                )),
+                None => Ok(body),
+            });
+        let result = match body {
+            Ok(body) => self.transmit(&subject, body).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => {
+                info!("Alert email sent successfully to {}", self.recipient());
+                true
+            }
+            Err(e) => {
+                error!("Failed to send email alert: {}", e);
+                self.enqueue(&subject, &message).await;
+                false
+            }
+        }
+    }
+    
+    async fn deliver(&self, subject: &str, message: &str) -> Result<()> {
+        self.transmit(subject, self.alternative_body(message)).await
+    }
+    
+    /// Sends a rule alert to an escalation tier address. Not queued on
+    /// failure, the next tier follows anyway.
+    pub async fn send_escalation(&self, alert: &Alert, recipient: &str) -> bool {
+        if !self.enabled {
+            info!("Email notifications disabled. Skipping escalation to {}.", recipient);
+            return false;
+        }
+        let Some((subject, message)) = self.render_rule_alert(alert) else {
+            warn!("No email template for rule {}", alert.rule);
+            return false;
+        };
+        
+        match self.transmit_to(recipient, &subject, None, self.alternative_body(&message)).await {
+            Ok(_) => {
+                info!("Escalation email for {} sent to {}", alert.rule, recipient);
+                true
+            }
+            Err(e) => {
+                error!("Failed to send escalation email for {} to {}: {}", alert.rule, recipient, e);
+                false
+            }
+        }
+    }
+    
+    fn alternative_body(&self, message: &str) -> MultiPart {
+        let message = &self.present(message);
+        MultiPart::alternative()
+            .singlepart(
+                SinglePart::builder()
+                    .header(ContentType::TEXT_PLAIN)
+                    .body(self.strip_html_tags(message))
+            )
+            .singlepart(
+                SinglePart::builder()
+                    .header(ContentType::TEXT_HTML)
+                    .body(message.to_string())
+            )
+    }
+    
+    /// Replies to a command email from `recipient`, threading the reply
+    /// under the original message when it carried a `Message-ID`.
+    pub async fn send_reply(&self, recipient: &str, subject: &str, text: &str, in_reply_to: Option<&str>) -> bool {
+        let body = MultiPart::mixed().singlepart(
+            SinglePart::builder()
+                .header(ContentType::TEXT_PLAIN)
+                .body(text.to_string())
+        );
+        
+        match self.transmit_to(recipient, subject, in_reply_to, body).await {
+            Ok(_) => {
+                info!("Command reply sent to {}", recipient);
+                true
+            }
+            Err(e) => {
+                error!("Failed to send command reply to {}: {}", recipient, e);
+                false
+            }
+        }
+    }
+    
+    async fn transmit(&self, subject: &str, body: MultiPart) -> Result<()> {
+        self.transmit_to(&self.recipient(), subject, None, body).await
+    }
+    
+    async fn transmit_to(&self, recipient: &str, subject: &str, in_reply_to: Option<&str>, body: MultiPart) -> Result<()> {
+        let mut builder = Message::builder()
+            .from(self.config.sender_email.parse()?)
+            .to(recipient.parse()?)
+            .subject(self.present(subject).trim());
+        if let Some(message_id) = in_reply_to {
+            builder = builder
+                .in_reply_to(message_id.to_string())
+                .references(message_id.to_string());
+        }
+        
+        let (envelope, raw) = match &self.signer {
+            None => {
+                let email = builder.multipart(body)
+                    .map_err(|e| anyhow!("Failed to build email message: {}", e))?;
+                (email.envelope().clone(), email.formatted())
+            }
+            Some(signer) => {
+                let signed = signer.sign(&body.formatted()).await
+                    .map_err(|e| anyhow!("Failed to sign email: {}", e))?;
+                
+                // Let lettre format the envelope headers, then replace the
+                // body with the signed entity, which carries its own MIME headers
+                let mut email = builder.body(String::new())
+                    .map_err(|e| anyhow!("Failed to build email message: {}", e))?;
+                email.headers_mut().remove::<ContentTransferEncoding>();
+                let mut raw = email.formatted();
+                raw.truncate(raw.len().saturating_sub(2));
+                raw.extend_from_slice(&signed);
+                (email.envelope().clone(), raw)
+            }
+        };
+        self.smtp_send(&envelope, &raw).await
+    }
+    
+    #[cfg(feature = "email")]
+    async fn smtp_send(&self, envelope: &Envelope, raw: &[u8]) -> Result<()> {
+        let creds = Credentials::new(
+            self.config.sender_email.clone(),
+            self.config.sender_password.clone()
+        );
+        
+        let mailer = AsyncSmtpTransport::<Tokio1Executor>::relay(&self.config.smtp_server)?
+            .port(self.config.smtp_port)
+            .credentials(creds)
+            .timeout(Some(SMTP_TIMEOUT))
+            .build();
+        tokio::time::timeout(SMTP_TIMEOUT, mailer.send_raw(envelope, raw)).await
+            .map_err(|_| anyhow!("SMTP server did not answer within {}s", SMTP_TIMEOUT.as_secs()))??;
+        Ok(())
+    }
+    
+    #[cfg(not(feature = "email"))]
+    async fn smtp_send(&self, _envelope: &Envelope, _raw: &[u8]) -> Result<()> {
+        Err(anyhow!("this build has no SMTP support, rebuild with `--features email`"))
+    }
+    
+    fn load_queue(&self) -> Vec<QueuedEmail> {
+        if self.config.queue_file.is_empty() || !Path::new(&self.config.queue_file).exists() {
+            return Vec::new();
+        }
+        
+        match fs::read_to_string(&self.config.queue_file)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+        {
+            Ok(queue) => queue,
+            Err(e) => {
+                error!("Failed to read email queue {}: {}", self.config.queue_file, e);
+                Vec::new()
+            }
+        }
+    }
+    
+    fn save_queue(&self, queue: &[QueuedEmail]) {
+        let result = if queue.is_empty() {
+            fs::remove_file(&self.config.queue_file).or_else(|e| {
+                if e.kind() == std::io::ErrorKind::NotFound { Ok(()) } else { Err(e) }
+            }).map_err(anyhow::Error::from)
+        } else {
+            serde_json::to_string_pretty(queue)
+                .map_err(anyhow::Error::from)
+                .and_then(|content| Ok(fs::write(&self.config.queue_file, content)?))
+        };
+        
+        if let Err(e) = result {
+            error!("Failed to write email queue {}: {}", self.config.queue_file, e);
+        }
+    }
+    
+    async fn enqueue(&self, subject: &str, message: &str) {
+        if self.config.queue_file.is_empty() || self.config.queue_max_size == 0 {
+            return;
+        }
+        
+        let _guard = self.queue_lock.lock().await;
+        let mut queue = self.load_queue();
+        queue.push(QueuedEmail {
+            subject: subject.to_string(),
+            message: message.to_string(),
+            created_at: Utc::now(),
+        });
+        
+        // Keep the newest alerts when the queue is full
+        if queue.len() > self.config.queue_max_size {
+            let dropped = queue.len() - self.config.queue_max_size;
+            warn!("Email queue full. Dropping {} oldest queued alerts.", dropped);
+            queue.drain(..dropped);
+        }
+        
+        info!("Queued alert for later delivery ({} pending)", queue.len());
+        self.save_queue(&queue);
+    }
+    
+    /// Tries to deliver queued alerts, marking them as delayed. Stops at the first
+    /// failure so the remaining alerts keep their order. Returns the number delivered.
+    pub async fn flush_queue(&self) -> usize {
+        if !self.enabled {
+            return 0;
+        }
+        
+        // Another worker is already flushing
+        let _guard = match self.queue_lock.try_lock() {
+            Ok(guard) => guard,
+            Err(_) => return 0,
+        };
+        
+        let mut queue = self.load_queue();
+        if queue.is_empty() {
+            return 0;
+        }
+        
+        info!("Flushing {} queued alerts", queue.len());
+        let mut delivered = 0;
+        
+        while let Some(queued) = queue.first() {
+            let subject = format!("[DELAYED] {}", queued.subject);
+            let message = self.mark_delayed(&queued.message, queued.created_at);
+            
+            match self.deliver(&subject, &message).await {
+                Ok(_) => {
+                    delivered += 1;
+                    queue.remove(0);
+                }
+                Err(e) => {
+                    warn!("Still unable to deliver queued alerts: {}", e);
+                    break;
+                }
+            }
+        }
+        
+        if delivered > 0 {
+            info!("Delivered {} queued alerts", delivered);
+        }
+        self.save_queue(&queue);
+        delivered
+    }
+    
+    fn mark_delayed(&self, message: &str, created_at: DateTime<Utc>) -> String {
+        let notice = format!(
+            "<p style='color: #b36b00;'><strong>Delayed delivery:</strong> this alert was generated at {} but could not be delivered until now.</p>",
+            self.timezone.format(created_at)
+        );
+        
+        match message.find("<body>") {
+            Some(pos) => {
+                let insert_at = pos + "<body>".len();
+                format!("{}{}{}", &message[..insert_at], notice, &message[insert_at..])
+            }
+            None => format!("{}{}", notice, message),
+        }
+    }
+    
+    fn cpu_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🚨 HIGH CPU USAGE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🚨 HIGH CPU USAGE ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                
+                {}
+                <h3>📊 Server CPU Usage</h3>
+                <p><strong>Current CPU Usage:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2}%</span></p>
+                {}
+                
+                <h3>🐳 High CPU Docker Containers</h3>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Please check your server and containers immediately.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            alert.value.unwrap_or_default(),
+            self.format_alert_details(alert),
+            self.format_container_table(&alert.containers),
+            self.format_top_processes(&alert.top_processes),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn memory_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🧠 HIGH MEMORY USAGE ALERT - {}", self.timezone.format(alert.timestamp));
+        let containers = if alert.containers.is_empty() {
+            String::new()
+        } else {
+            format!("<h3>🐳 Largest Docker Containers</h3>\n{}", self.format_container_table(&alert.containers))
+        };
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🧠 HIGH MEMORY USAGE ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                
+                {}
+                <h3>📊 Server Memory Usage</h3>
+                <p><strong>Current Memory Usage:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2}%</span></p>
+                {}
+                
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>When memory runs out the kernel OOM killer terminates processes. Check the largest containers and processes.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            alert.value.unwrap_or_default(),
+            self.format_alert_details(alert),
+            containers,
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn bandwidth_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🌐 HIGH BANDWIDTH ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🌐 HIGH BANDWIDTH ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                
+                {}
+                <h3>📊 Bandwidth</h3>
+                <p><strong>Busiest Interface:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2} Mbit/s</span></p>
+                <h3>🔌 Traffic per Interface</h3>
+                {}
+                
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>A saturated link delays every service on it. Check which containers or processes are transferring data (`iftop`, `nethogs`) and whether receive errors are climbing.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            alert.value.unwrap_or_default(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn swap_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("💤 HIGH SWAP USAGE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>💤 HIGH SWAP USAGE ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                
+                {}
+                <h3>📊 Server Swap Usage</h3>
+                <p><strong>Current Swap Usage:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2}%</span></p>
+                {}
+                {}
+                
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Heavy swapping slows every process and usually comes before CPU spikes. Check the processes using the most memory and whether swap is still growing (`vmstat 1`).</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            alert.value.unwrap_or_default(),
+            self.format_alert_details(alert),
+            self.format_top_processes(&alert.top_processes),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn hot_core_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🔥 HOT CPU CORE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🔥 HOT CPU CORE ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                
+                {}
+                <h3>📊 CPU Cores</h3>
+                <p><strong>Busiest Core:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2}%</span></p>
+                <h3>🧮 Hot Cores</h3>
+                {}
+                {}
+                
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>One saturated core under a low average usually means a single-threaded process spinning. Check which thread holds it (`top -H`, `pidstat -t -u 1`).</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            alert.value.unwrap_or_default(),
+            self.format_alert_details(alert),
+            self.format_top_processes(&alert.top_processes),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn disk_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("💾 HIGH DISK USAGE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>💾 HIGH DISK USAGE ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                
+                {}
+                <h3>📊 Disk Usage</h3>
+                <p><strong>Fullest Mount:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2}%</span></p>
+                <h3>🗂️ Usage per Mount</h3>
+                {}
+                
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Writes fail once a filesystem is full. Check logs, Docker images and volumes (`docker system df`) first.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            alert.value.unwrap_or_default(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn container_cpu_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🐳 HIGH CONTAINER CPU ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🐳 HIGH CONTAINER CPU USAGE ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                
+                {}
+                {}
+                <h3>🔥 High CPU Docker Containers</h3>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Please check the highlighted containers immediately.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_detailed_container_table(&alert.containers),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn unlimited_container_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("📦 UNLIMITED CONTAINER USAGE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>📦 CONTAINERS WITHOUT RESOURCE LIMITS</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                <h3>🚧 Unlimited Containers Over Usage Threshold</h3>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Consider setting --cpus and --memory limits for these containers.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_detailed_container_table(&alert.containers),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn newcomer_container_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🆕 NEW CONTAINER RESOURCE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🆕 NEWLY STARTED CONTAINERS WITH HIGH USAGE</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                <h3>🕵️ Recently Created Containers Over Usage Threshold</h3>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Verify that these containers were deployed intentionally and are configured correctly.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_detailed_container_table(&alert.containers),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn image_drift_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("📌 IMAGE DRIFT ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>📌 CONTAINERS NOT RUNNING THEIR PINNED IMAGE</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                <h3>🐳 Drifted Containers</h3>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Redeploy these containers from the pinned image or update the pin if the change was intended.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_detailed_container_table(&alert.containers),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn group_budget_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("💰 GROUP BUDGET ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>💰 CONTAINER GROUPS OVER BUDGET</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                <h3>🐳 Containers in These Groups</h3>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Scale down or set limits on the busiest containers, or raise the budget if the growth is expected.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_detailed_container_table(&alert.containers),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn disk_latency_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("💽 DISK LATENCY ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>💽 HIGH DISK I/O LATENCY</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Rising latency is often the first sign of a failing disk. Check SMART data and kernel logs.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn conntrack_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🔌 CONNTRACK TABLE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🔌 CONNECTION TRACKING TABLE NEARLY FULL</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                <p><strong>Usage:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2}%</span></p>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>When the table is full new connections are dropped silently. Consider raising net.netfilter.nf_conntrack_max.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            alert.value.unwrap_or_default(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn ping_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("📡 NETWORK PATH ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>📡 NETWORK LATENCY / PACKET LOSS</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Check the network path between this host and the listed targets.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn cpu_frequency_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🐢 CPU FREQUENCY ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🐢 CPU STUCK AT LOW FREQUENCY</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>The CPU is busy but not boosting. Check for thermal throttling, the scaling governor and BIOS power settings.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn lxd_container_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("📦 HIGH LXD CPU ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>📦 HIGH LXD INSTANCE CPU USAGE ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                <h3>🔥 High CPU LXD Instances</h3>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Please check the highlighted instances with `lxc info`.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_detailed_container_table(&alert.containers),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn cron_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("⏰ CRON JOB ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>⏰ CRON JOB FAILED OR MISSED ITS SCHEDULE</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Check the crontab entry and the job's own output (cron mail or log) for the cause.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn command_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🛠️ COMMAND CHECK ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🛠️ COMMAND CHECK FAILED</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Run the command by hand on the host to see its full output.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn storage_pool_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("💾 STORAGE POOL ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>💾 BTRFS / LVM THIN POOL RUNNING OUT OF SPACE</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>These numbers can be far above what `df` shows. Rebalance the Btrfs filesystem or extend the thin pool before writes fail.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn zfs_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🗄️ ZFS POOL ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🗄️ ZFS POOLS NEED ATTENTION</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Run `zpool status -v` on the host; replace failed devices and scrub after resilvering.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn vm_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("💻 VIRTUAL MACHINE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>💻 VIRTUAL MACHINES OVER THRESHOLD</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Check the listed guests with virsh; crashed guests need to be restarted.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
     }
     
-    pub async fn send_alert(&self, subject: &str, message: &str) -> bool {
-        if !self.enabled {
-            info!("Email notifications disabled. Skipping alert.");
-            return false;
-        }
+    fn ups_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🔋 UPS POWER ALERT - {}", self.timezone.format(alert.timestamp));
         
-        let email = Message::builder()
-            .from(self.config.sender_email.parse().unwrap())
-            .to(self.config.recipient_email.parse().unwrap())
-            .subject(subject)
-            .multipart(
-                MultiPart::alternative()
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(ContentType::TEXT_PLAIN)
-                            .body(self.strip_html_tags(message))
-                    )
-                    .singlepart(
-                        SinglePart::builder()
-                            .header(ContentType::TEXT_HTML)
-                            .body(message.to_string())
-                    )
-            );
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🔋 UPS RUNNING ON BATTERY</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Line power was lost. Plan a clean shutdown before the remaining runtime runs out.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
         
-        if let Ok(email) = email {
-            let creds = Credentials::new(
-                self.config.sender_email.clone(),
-                self.config.sender_password.clone()
-            );
-            
-            let mailer = SmtpTransport::relay(&self.config.smtp_server)
-                .unwrap()
-                .port(self.config.smtp_port)
-                .credentials(creds)
-                .build();
-            
-            match mailer.send(&email) {
-                Ok(_) => {
-                    info!("Alert email sent successfully to {}", self.config.recipient_email);
-                    true
-                }
-                Err(e) => {
-                    error!("Failed to send email alert: {}", e);
-                    false
-                }
-            }
-        } else {
-            error!("Failed to build email message");
-            false
-        }
+        (subject, message)
     }
     
-    pub async fn send_cpu_alert(&self, server_cpu: f64, high_cpu_containers: &[ContainerStats]) -> bool {
-        let subject = format!("🚨 HIGH CPU USAGE ALERT - {}", Utc::now().format("%Y-%m-%d %H:%M:%S"));
+    fn memory_leak_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🧠 MEMORY LEAK ALERT - {}", self.timezone.format(alert.timestamp));
         
         let message = format!(
             r#"
             <html>
             <body>
-                <h2>🚨 HIGH CPU USAGE ALERT</h2>
+                <h2>🧠 POSSIBLE MEMORY LEAK</h2>
                 <p><strong>Time:</strong> {}</p>
-                
-                <h3>📊 Server CPU Usage</h3>
-                <p><strong>Current CPU Usage:</strong> <span style="color: red; font-size: 18px; font-weight: bold;">{:.2}%</span></p>
-                <p><strong>Threshold:</strong> 80%</p>
-                
-                <h3>🐳 High CPU Docker Containers</h3>
                 {}
+                {}
+                <h3>📈 Memory Usage</h3>
+                {}
+                <h3>🐳 Affected Containers</h3>
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Memory of these containers only grew over the window. Check for leaks or unbounded caches before they hit their limit.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            format_chart_images(&alert.charts),
+            self.format_detailed_container_table(&alert.containers),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn trend_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("📈 TREND ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>📈 METRICS CHANGING FASTER THAN EXPECTED</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Steady growth usually points to a leak, runaway logs or a filling queue; check before the threshold alerts fire.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn fingerprint_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🔑 FINGERPRINT CHANGE ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🔑 TLS CERTIFICATE / SSH HOST KEY CHANGED</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Verify the change was planned (certificate renewal, server rebuild). An unexpected change may indicate a man-in-the-middle.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn outbound_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("🕵️ SUSPICIOUS OUTBOUND CONNECTION ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>🕵️ CONNECTION TO A WATCHLISTED ADDRESS</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                {}
+                <br>
+                <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
+                <p><em>Find the process behind the connection (ss -tnp) and check the container or host for compromise, e.g. a cryptominer or a TOR client.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    fn process_email(&self, alert: &Alert) -> (String, String) {
+        let subject = format!("⛔ PROCESS DOWN ALERT - {}", self.timezone.format(alert.timestamp));
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>⛔ PROCESS DOWN ALERT</h2>
+                <p><strong>Time:</strong> {}</p>
                 
+                {}
+                {}
+                <h3>🔍 Watched Processes Not Running</h3>
+                {}
+                {}
                 <br>
                 <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
-                <p><em>Please check your server and containers immediately.</em></p>
+                <p><em>Please check the listed services immediately.</em></p>
             </body>
             </html>
             "#,
-            Utc::now().format("%Y-%m-%d %H:%M:%S"),
-            server_cpu,
-            self.format_container_table(high_cpu_containers)
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            self.format_process_table(&alert.processes),
+            self.format_rule_info(alert)
         );
         
-        self.send_alert(&subject, &message).await
+        (subject, message)
     }
     
-    pub async fn send_container_cpu_alert(&self, high_cpu_containers: &[ContainerStats]) -> bool {
-        let subject = format!("🐳 HIGH CONTAINER CPU ALERT - {}", Utc::now().format("%Y-%m-%d %H:%M:%S"));
+    /// Notification for a rule that resolved or started flapping.
+    fn state_change_email(&self, alert: &Alert) -> (String, String) {
+        let (icon, color) = match alert.state {
+            IncidentState::Resolved => ("✅", "green"),
+            _ => ("🔁", "#b36b00"),
+        };
+        let subject = format!("{} {} {} - {}", icon, alert.rule.to_uppercase(), alert.state.label(), self.timezone.format(alert.timestamp));
         
         let message = format!(
             r#"
             <html>
             <body>
-                <h2>🐳 HIGH CONTAINER CPU USAGE ALERT</h2>
+                <h2 style="color: {};">{} {} {}</h2>
                 <p><strong>Time:</strong> {}</p>
                 
-                <h3>🔥 High CPU Docker Containers</h3>
+                {}
+                {}
+                {}
                 {}
                 <br>
                 <p><em>This is an automated alert from your Docker & Server Performance Monitoring System.</em></p>
-                <p><em>Please check the highlighted containers immediately.</em></p>
             </body>
             </html>
             "#,
-            Utc::now().format("%Y-%m-%d %H:%M:%S"),
-            self.format_detailed_container_table(high_cpu_containers)
+            color,
+            icon,
+            alert.rule.to_uppercase(),
+            alert.state.label(),
+            self.timezone.format(alert.timestamp),
+            self.format_host_info(),
+            self.format_alert_details(alert),
+            if alert.containers.is_empty() { String::new() } else { self.format_container_table(&alert.containers) },
+            self.format_rule_info(alert)
+        );
+        
+        (subject, message)
+    }
+    
+    pub async fn send_digest(&self, sections: &[DigestSection]) -> bool {
+        let subject = format!("📰 Monitoring Digest - {}", self.timezone.convert(Utc::now()).format("%Y-%m-%d"));
+        
+        let body: String = sections.iter()
+            .map(|section| format!("<h3>{}</h3>\n{}", section.title, section.html))
+            .collect();
+        
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>📰 Monitoring Digest</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                <br>
+                <p><em>This is a periodic digest from your Docker & Server Performance Monitoring System.</em></p>
+            </body>
+            </html>
+            "#,
+            self.timezone.format(Utc::now()),
+            self.format_host_info(),
+            body
         );
         
         self.send_alert(&subject, &message).await
     }
     
+    /// Sends a scheduled report with its charts as inline images. Reports are
+    /// not queued on failure; the caller retries on the next check instead.
+    pub async fn send_report(&self, report: &Report) -> bool {
+        if !self.enabled {
+            info!("Email notifications disabled. Skipping report.");
+            return false;
+        }
+        
+        let subject = format!("📈 {} - {}", report.title, self.timezone.convert(Utc::now()).format("%Y-%m-%d"));
+        let message = format!(
+            r#"
+            <html>
+            <body>
+                <h2>📈 {}</h2>
+                <p><strong>Time:</strong> {}</p>
+                {}
+                {}
+                <br>
+                <p><em>This is a scheduled report from your Docker & Server Performance Monitoring System.</em></p>
+            </body>
+            </html>
+            "#,
+            report.title,
+            self.timezone.format(Utc::now()),
+            self.format_host_info(),
+            report.html
+        );
+        
+        let result = match self.build_related_body(&message, &report.images) {
+            Ok(body) => self.transmit(&subject, body).await,
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(_) => {
+                info!("Report email sent successfully to {}", self.recipient());
+                true
+            }
+            Err(e) => {
+                error!("Failed to send report email: {}", e);
+                false
+            }
+        }
+    }
+    
+    fn build_related_body(&self, message: &str, images: &[InlineImage]) -> Result<MultiPart> {
+        let message = &self.present(message);
+        let mut related = MultiPart::related().singlepart(
+            SinglePart::builder()
+                .header(ContentType::TEXT_HTML)
+                .body(message.to_string())
+        );
+        for image in images {
+            related = related.singlepart(
+                Attachment::new_inline(image.cid.clone()).body(image.png.clone(), ContentType::parse("image/png")?)
+            );
+        }
+        
+        Ok(MultiPart::alternative()
+            .singlepart(
+                SinglePart::builder()
+                    .header(ContentType::TEXT_PLAIN)
+                    .body(self.strip_html_tags(message))
+            )
+            .multipart(related))
+    }
+    
     pub async fn send_test_email(&self) -> bool {
         let subject = "🧪 Test Email - Docker & Server Performance Monitoring".to_string();
         
@@ -167,12 +1486,63 @@ impl EmailNotifier {
             </body>
             </html>
             "#,
-            Utc::now().format("%Y-%m-%d %H:%M:%S")
+            self.timezone.format(Utc::now())
         );
         
         self.send_alert(&subject, &message).await
     }
     
+    /// Applies the global `emoji` setting to outgoing text.
+    fn present(&self, text: &str) -> String {
+        if self.emoji { text.to_string() } else { strip_emoji(text) }
+    }
+    
+    fn format_host_info(&self) -> String {
+        match &self.host_metadata {
+            Some(metadata) => format!(
+                "<p><strong>Instance:</strong> {} ({}, {}, {})</p>",
+                metadata.instance_id, metadata.provider, metadata.region, metadata.instance_type
+            ),
+            None => String::new(),
+        }
+    }
+    
+    fn format_alert_details(&self, alert: &Alert) -> String {
+        let mut details = format!(
+            "<p><strong>{}</strong><br><strong>Rule:</strong> {} ({})<br><strong>Threshold:</strong> {}<br><strong>Window:</strong> {}</p>",
+            escape(&alert.summary),
+            escape(&alert.rule),
+            alert.severity.label(),
+            escape(&alert.threshold),
+            escape(&alert.window)
+        );
+        
+        // Notes carry command output and container names, never markup
+        if !alert.notes.is_empty() {
+            details.push_str("<ul>");
+            for note in &alert.notes {
+                details.push_str(&format_note(note));
+            }
+            details.push_str("</ul>");
+        }
+        details
+    }
+    
+    fn format_rule_info(&self, alert: &Alert) -> String {
+        if alert.description.is_none() && alert.runbook_url.is_none() {
+            return String::new();
+        }
+        
+        let mut info = String::from("<h3>📘 Runbook</h3>");
+        if let Some(description) = &alert.description {
+            info.push_str(&format!("<p>{}</p>", escape(description)));
+        }
+        if let Some(url) = &alert.runbook_url {
+            info.push_str(&format!("<p><strong>Runbook:</strong> <a href=\"{0}\">{0}</a></p>", escape(url)));
+        }
+        info
+    }
+    
     fn format_container_table(&self, containers: &[ContainerStats]) -> String {
         if containers.is_empty() {
             return "<p>No specific containers with high CPU usage detected.</p>".to_string();
@@ -190,13 +1560,54 @@ impl EmailNotifier {
         
         for container in containers {
             table.push_str("<tr>");
-            table.push_str(&format!("<td style='padding: 8px;'>{}</td>", container.name));
-            table.push_str(&format!(
-                "<td style='padding: 8px; color: red; font-weight: bold;'>{:.2}%</td>", 
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", container.name);
+            let _ = write!(
+                table,
+                "<td style='padding: 8px; color: red; font-weight: bold;'>{:.2}%</td>",
                 container.cpu_usage
-            ));
-            table.push_str(&format!("<td style='padding: 8px;'>{:.2}%</td>", container.memory_percent));
-            table.push_str(&format!("<td style='padding: 8px;'>{}</td>", container.image));
+            );
+            let _ = write!(
+                table,
+                "<td style='padding: 8px;'>{:.2}% ({})</td>",
+                container.memory_percent, units::bytes(container.memory_usage as f64)
+            );
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", container.image);
+            table.push_str("</tr>");
+        }
+        
+        table.push_str("</table>");
+        table
+    }
+    
+    /// The top processes table, in the alert's order. Empty without processes.
+    fn format_top_processes(&self, processes: &[ProcessUsage]) -> String {
+        if processes.is_empty() {
+            return String::new();
+        }
+        
+        let mut table = String::from("<h3>⚙️ Top Processes by CPU and Memory</h3>\n");
+        table.push_str(
+            "<table border='1' style='border-collapse: collapse; width: 100%;'>"
+        );
+        table.push_str("<tr style='background-color: #f2f2f2;'>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>Process</th>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>PID</th>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>User</th>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>CPU</th>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>Memory</th>");
+        table.push_str("</tr>");
+        
+        for process in processes {
+            table.push_str("<tr>");
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", escape(&process.name));
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", process.pid);
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", escape(&process.user));
+            let _ = write!(table, "<td style='padding: 8px; color: red; font-weight: bold;'>{:.1}%</td>", process.cpu_usage);
+            let _ = write!(
+                table,
+                "<td style='padding: 8px;'>{:.1}% ({})</td>",
+                process.memory_percent, units::bytes(process.memory as f64)
+            );
             table.push_str("</tr>");
         }
         
@@ -214,18 +1625,48 @@ impl EmailNotifier {
         table.push_str("<th style='padding: 8px; text-align: left;'>Memory Usage</th>");
         table.push_str("<th style='padding: 8px; text-align: left;'>Image</th>");
         table.push_str("<th style='padding: 8px; text-align: left;'>Status</th>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>Limits</th>");
         table.push_str("</tr>");
         
         for container in containers {
             table.push_str("<tr>");
-            table.push_str(&format!("<td style='padding: 8px;'>{}</td>", container.name));
-            table.push_str(&format!(
-                "<td style='padding: 8px; color: red; font-weight: bold;'>{:.2}%</td>", 
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", container.name);
+            let _ = write!(
+                table,
+                "<td style='padding: 8px; color: red; font-weight: bold;'>{:.2}%</td>",
                 container.cpu_usage
+            );
+            let _ = write!(
+                table,
+                "<td style='padding: 8px;'>{:.2}% ({})</td>",
+                container.memory_percent, units::bytes(container.memory_usage as f64)
+            );
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", container.image);
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", container.status);
+            let _ = write!(table, "<td style='padding: 8px;'>{}</td>", container.limits_summary());
+            table.push_str("</tr>");
+        }
+        
+        table.push_str("</table>");
+        table
+    }
+    
+    fn format_process_table(&self, processes: &[ProcessStatus]) -> String {
+        let mut table = String::from(
+            "<table border='1' style='border-collapse: collapse; width: 100%;'>"
+        );
+        table.push_str("<tr style='background-color: #f2f2f2;'>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>Process</th>");
+        table.push_str("<th style='padding: 8px; text-align: left;'>Restart Attempted</th>");
+        table.push_str("</tr>");
+        
+        for process in processes {
+            table.push_str("<tr>");
+            table.push_str(&format!("<td style='padding: 8px; color: red; font-weight: bold;'>{}</td>", process.name));
+            table.push_str(&format!(
+                "<td style='padding: 8px;'>{}</td>",
+                if process.restarted { "Yes (succeeded)" } else { "No / failed" }
             ));
-            table.push_str(&format!("<td style='padding: 8px;'>{:.2}%</td>", container.memory_percent));
-            table.push_str(&format!("<td style='padding: 8px;'>{}</td>", container.image));
-            table.push_str(&format!("<td style='padding: 8px;'>{}</td>", container.status));
             table.push_str("</tr>");
         }
         
@@ -255,4 +1696,63 @@ impl EmailNotifier {
             .collect::<Vec<_>>()
             .join("\n")
     }
+}
+
+/// Color of the lines in alert charts.
+const CHART_COLOR: [u8; 3] = [214, 39, 40];
+
+/// Renders alert charts as inline PNGs referenced as `cid:chart-<index>`.
+fn render_charts(charts: &[AlertChart]) -> Result<Vec<InlineImage>> {
+    charts.iter()
+        .enumerate()
+        .map(|(index, alert_chart)| {
+            let png = chart::line_chart(&[Series { color: CHART_COLOR, values: &alert_chart.values }], alert_chart.max)?;
+            Ok(InlineImage { cid: format!("chart-{}", index), png })
+        })
+        .collect()
+}
+
+/// A note as a list item. A link in it is made clickable and marked with 🔗,
+/// the rest is plain text.
+fn format_note(note: &str) -> String {
+    let Some(start) = note.find("http://").into_iter().chain(note.find("https://")).min() else {
+        return format!("<li>{}</li>", escape(note));
+    };
+    let end = note[start..].find(char::is_whitespace).map_or(note.len(), |length| start + length);
+    format!(
+        "<li>🔗 {}<a href=\"{1}\">{1}</a>{2}</li>",
+        escape(&note[..start]), escape(&note[start..end]), escape(&note[end..])
+    )
+}
+
+fn format_chart_images(charts: &[AlertChart]) -> String {
+    charts.iter()
+        .enumerate()
+        .map(|(index, alert_chart)| format!(
+            r#"<p><strong>{0}</strong></p>
+                <img src="cid:chart-{1}" alt="{0}" width="640" height="200">
+                <p style='color: #666;'>Scale 0 to {2:.0} {3}, grid lines every 25%.</p>"#,
+            alert_chart.title, index, alert_chart.max, alert_chart.unit
+        ))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Removes emoji and the joiners and variation selectors that build them,
+/// along with the space that separated an emoji from the following word.
+fn strip_emoji(text: &str) -> String {
+    let is_emoji = |c: char| matches!(c as u32,
+        0x1F000..=0x1FAFF | 0x2600..=0x27BF | 0x2300..=0x23FF | 0x2B00..=0x2BFF | 0xFE0F | 0x200D);
+    let mut stripped = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_emoji(c) {
+            if chars.peek() == Some(&' ') && chars.clone().nth(1).is_some_and(|next| !is_emoji(next)) {
+                chars.next();
+            }
+            continue;
+        }
+        stripped.push(c);
+    }
+    stripped
 }
\ No newline at end of file
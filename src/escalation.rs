@@ -0,0 +1,93 @@
+use serde::{Deserialize, Serialize};
+use crate::alert::Alert;
+use chrono::{DateTime, Duration, Utc};
+use crate::config::{Config, EscalationTierConfig};
+use crate::incident::{IncidentState, RuleState};
+use log::warn;
+
+/// Someone taking ownership of a firing rule, which stops its escalation.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Acknowledgement {
+    pub by: String,
+    pub at: DateTime<Utc>,
+}
+
+/// One escalation tier notified about a rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EscalationStep {
+    pub tier: String,
+    pub at: DateTime<Utc>,
+    /// Email addresses and channel names the tier was sent to.
+    pub recipients: Vec<String>,
+}
+
+impl EscalationStep {
+    pub fn new(tier: &EscalationTierConfig, at: DateTime<Utc>) -> Self {
+        Self {
+            tier: tier.name.clone(),
+            at,
+            recipients: tier.emails.iter().chain(&tier.channels).cloned().collect(),
+        }
+    }
+}
+
+/// The next tier to notify about `rule`, once it has been firing for the
+/// tier's `after_minutes` without being acknowledged.
+pub fn due<'a>(config: &'a Config, rule: &str, state: &RuleState, now: DateTime<Utc>) -> Option<&'a EscalationTierConfig> {
+    let escalation = &config.escalation;
+    if !escalation.enabled || state.state != IncidentState::Firing || state.acknowledged.is_some() {
+        return None;
+    }
+    if !escalation.rules.is_empty() && !escalation.rules.iter().any(|escalated| escalated == rule) {
+        return None;
+    }
+    
+    let tier = escalation.tiers.get(state.escalations.len())?;
+    (now - state.since >= Duration::minutes(tier.after_minutes as i64)).then_some(tier)
+}
+
+/// Attaches the escalation history and acknowledgement of the rule to its
+/// alert, with notes for the email and chat text.
+pub fn annotate(mut alert: Alert, state: &RuleState) -> Alert {
+    alert.notes.extend(notes(state));
+    alert.escalations = state.escalations.clone();
+    alert.acknowledged = state.acknowledged.clone();
+    alert
+}
+
+fn notes(state: &RuleState) -> Vec<String> {
+    let mut notes = Vec::new();
+    if !state.escalations.is_empty() {
+        let steps: Vec<String> = state.escalations.iter()
+            .map(|step| format!("{} at {}", step.tier, step.at.format("%H:%M UTC")))
+            .collect();
+        notes.push(format!("Escalated to {}", steps.join(", ")));
+    }
+    if let Some(acknowledgement) = &state.acknowledged {
+        notes.push(format!(
+            "Acknowledged by {} at {}",
+            acknowledgement.by, acknowledgement.at.format("%Y-%m-%d %H:%M UTC")
+        ));
+    }
+    notes
+}
+
+/// Warns about tier channels no notifier is configured for, which would
+/// otherwise be skipped silently.
+pub fn check_channels(config: &Config) {
+    if !config.escalation.enabled {
+        return;
+    }
+    let known: Vec<String> = config.webhooks.iter()
+        .map(|hook| format!("webhook:{}", hook.name))
+        .chain(config.chat.iter().map(|chat| format!("{}:{}", chat.kind, chat.name)))
+        .collect();
+    for tier in &config.escalation.tiers {
+        for channel in tier.channels.iter().filter(|channel| !known.contains(channel)) {
+            warn!("Escalation tier {} names unknown channel {}", tier.name, channel);
+        }
+        if tier.emails.is_empty() && tier.channels.is_empty() {
+            warn!("Escalation tier {} has no emails or channels", tier.name);
+        }
+    }
+}
\ No newline at end of file
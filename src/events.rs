@@ -0,0 +1,47 @@
+use serde::Serialize;
+use std::io::Write;
+use log::error;
+
+/// JSON Lines event stream on stdout for piping into log shippers. Each line
+/// is one object with a `type` and `timestamp` next to the event's fields.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EventStream {
+    enabled: bool,
+}
+
+impl EventStream {
+    pub fn new(enabled: bool) -> Self {
+        Self { enabled }
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+    
+    pub fn emit<T: Serialize>(&self, kind: &str, payload: &T) {
+        if !self.enabled {
+            return;
+        }
+        
+        let mut event = match serde_json::to_value(payload) {
+            Ok(serde_json::Value::Object(fields)) => fields,
+            Ok(value) => {
+                let mut fields = serde_json::Map::new();
+                fields.insert("data".to_string(), value);
+                fields
+            }
+            Err(e) => {
+                error!("Failed to serialize {} event: {}", kind, e);
+                return;
+            }
+        };
+        event.insert("type".to_string(), kind.into());
+        event.entry("timestamp").or_insert_with(|| chrono::Utc::now().to_rfc3339().into());
+        
+        let mut stdout = std::io::stdout().lock();
+        let line = serde_json::Value::Object(event).to_string();
+        if let Err(e) = writeln!(stdout, "{}", line).and_then(|_| stdout.flush()) {
+            error!("Failed to write {} event: {}", kind, e);
+        }
+    }
+}
\ No newline at end of file
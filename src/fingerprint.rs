@@ -0,0 +1,154 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use sha2::{Digest, Sha256};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use crate::config::{NetworkConfig, ServiceFingerprintConfig};
+use crate::network;
+use anyhow::{Result, anyhow};
+
+/// Identity of a service as last observed: the TLS certificate or SSH host
+/// key fingerprint plus the banner the service announces.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct ServiceFingerprint {
+    pub fingerprint: String,
+    #[serde(default)]
+    pub banner: Option<String>,
+    pub first_seen: DateTime<Utc>,
+}
+
+impl ServiceFingerprint {
+    /// True when the certificate or host key differs. The banner is left
+    /// out since it changes with every server upgrade.
+    pub fn differs_from(&self, other: &ServiceFingerprint) -> bool {
+        self.fingerprint != other.fingerprint
+    }
+    
+    pub fn describe(&self) -> String {
+        match &self.banner {
+            Some(banner) => format!("{} ({})", self.fingerprint, banner),
+            None => self.fingerprint.clone(),
+        }
+    }
+}
+
+/// Records the current fingerprint of a service.
+pub async fn probe(service: &ServiceFingerprintConfig, timeout: Duration, network: &NetworkConfig) -> Result<ServiceFingerprint> {
+    let (fingerprint, banner) = match service.kind.as_str() {
+        "tls" => (tls_fingerprint(service, timeout, network).await?, None),
+        "ssh" => ssh_fingerprint(service, timeout, network).await?,
+        other => return Err(anyhow!("unknown fingerprint kind '{}' (expected tls or ssh)", other)),
+    };
+    
+    Ok(ServiceFingerprint {
+        fingerprint,
+        banner,
+        first_seen: Utc::now(),
+    })
+}
+
+fn sha256_hex(data: &[u8]) -> String {
+    let digest = Sha256::digest(data);
+    digest.iter().map(|byte| format!("{:02X}", byte)).collect::<Vec<_>>().join(":")
+}
+
+/// SHA-256 of the DER certificate the server presents. Certificates are not
+/// validated, self-signed ones are fingerprinted too.
+async fn tls_fingerprint(service: &ServiceFingerprintConfig, timeout: Duration, network: &NetworkConfig) -> Result<String> {
+    let connector = native_tls::TlsConnector::builder()
+        .danger_accept_invalid_certs(true)
+        .danger_accept_invalid_hostnames(true)
+        .build()?;
+    let connector = tokio_native_tls::TlsConnector::from(connector);
+    
+    let stream = tokio::time::timeout(timeout, network::connect(&service.host, service.port, network)).await??;
+    let tls = tokio::time::timeout(timeout, connector.connect(&service.host, stream)).await??;
+    let certificate = tls.get_ref()
+        .peer_certificate()?
+        .ok_or_else(|| anyhow!("server presented no certificate"))?;
+    
+    Ok(sha256_hex(&certificate.to_der()?))
+}
+
+/// SSH banner plus a SHA-256 over the host keys reported by `ssh-keyscan`.
+/// Fails when no host key was collected, e.g. without ssh-keyscan, so a
+/// keyscan hiccup is never mistaken for a new host key.
+async fn ssh_fingerprint(service: &ServiceFingerprintConfig, timeout: Duration, network: &NetworkConfig) -> Result<(String, Option<String>)> {
+    let stream = tokio::time::timeout(timeout, network::connect(&service.host, service.port, network)).await??;
+    let mut banner = String::new();
+    tokio::time::timeout(timeout, BufReader::new(stream).read_line(&mut banner)).await??;
+    let banner = banner.trim().to_string();
+    if !banner.starts_with("SSH-") {
+        return Err(anyhow!("unexpected SSH banner '{}'", banner));
+    }
+    
+    let keyscan = Command::new("ssh-keyscan")
+        .arg("-p").arg(service.port.to_string())
+        .arg("-T").arg(timeout.as_secs().max(1).to_string())
+        .arg(&service.host)
+        .output()
+        .await;
+    
+    let keys = match keyscan {
+        Ok(output) => host_keys(&String::from_utf8_lossy(&output.stdout)),
+        Err(e) => return Err(anyhow!("ssh-keyscan unavailable: {}", e)),
+    };
+    if keys.is_empty() {
+        return Err(anyhow!("ssh-keyscan returned no host keys"));
+    }
+    Ok((sha256_hex(keys.join("\n").as_bytes()), Some(banner)))
+}
+
+/// The `type key` pairs of `ssh-keyscan` output, sorted. The host column is
+/// dropped so the fingerprint only covers the keys.
+fn host_keys(output: &str) -> Vec<String> {
+    let mut keys: Vec<String> = output.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace().skip(1);
+            Some(format!("{} {}", fields.next()?, fields.next()?))
+        })
+        .collect();
+    keys.sort();
+    keys.dedup();
+    keys
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn fingerprint(fingerprint: &str, banner: &str) -> ServiceFingerprint {
+        ServiceFingerprint {
+            fingerprint: fingerprint.to_string(),
+            banner: Some(banner.to_string()),
+            first_seen: Utc::now(),
+        }
+    }
+    
+    #[test]
+    fn only_the_key_counts_as_a_change() {
+        let known = fingerprint("AB:CD", "SSH-2.0-OpenSSH_9.2p1 Debian-2+deb12u2");
+        assert!(!known.differs_from(&fingerprint("AB:CD", "SSH-2.0-OpenSSH_9.2p1 Debian-2+deb12u3")));
+        assert!(!known.differs_from(&ServiceFingerprint { banner: None, ..known.clone() }));
+        assert!(known.differs_from(&fingerprint("EF:01", "SSH-2.0-OpenSSH_9.2p1 Debian-2+deb12u2")));
+    }
+    
+    #[test]
+    fn parses_keyscan_output() {
+        let output = "# db.example.com:22 SSH-2.0-OpenSSH_9.2p1\n\
+            db.example.com ssh-rsa AAAAB3NzaC1yc2E\n\
+            [db.example.com]:2222 ssh-ed25519 AAAAC3NzaC1lZDI1NTE5 comment\n\
+            \n\
+            db.example.com ssh-rsa AAAAB3NzaC1yc2E\n\
+            truncated\n";
+        assert_eq!(host_keys(output), ["ssh-ed25519 AAAAC3NzaC1lZDI1NTE5", "ssh-rsa AAAAB3NzaC1yc2E"]);
+        
+        let other_host = output.replace("db.example.com ", "10.0.0.5 ").replace("[db.example.com]:2222", "10.0.0.5");
+        assert_eq!(host_keys(&other_host), host_keys(output));
+        assert!(host_keys("# db.example.com:22 SSH-2.0-OpenSSH_9.2p1\n").is_empty());
+        assert!(host_keys("").is_empty());
+    }
+}
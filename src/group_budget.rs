@@ -0,0 +1,73 @@
+use crate::config::ContainerGroupConfig;
+use crate::docker_monitor::ContainerStats;
+use crate::units;
+
+/// A container group using more than its budget.
+pub struct BudgetBreach<'a> {
+    pub group: &'a ContainerGroupConfig,
+    pub containers: Vec<ContainerStats>,
+    /// Combined CPU usage in CPUs.
+    pub cpus: f64,
+    pub memory_bytes: u64,
+}
+
+impl BudgetBreach<'_> {
+    /// Usage as a percentage of the budget, the larger of CPU and memory.
+    pub fn percent_of_budget(&self) -> f64 {
+        let group = self.group;
+        let cpu = if group.cpu_budget > 0.0 { self.cpus / group.cpu_budget * 100.0 } else { 0.0 };
+        let memory = if group.memory_budget_mb > 0 {
+            self.memory_bytes as f64 / (group.memory_budget_mb * 1024 * 1024) as f64 * 100.0
+        } else {
+            0.0
+        };
+        cpu.max(memory)
+    }
+    
+    pub fn describe(&self) -> String {
+        let group = self.group;
+        let mut usage = Vec::new();
+        if group.cpu_budget > 0.0 {
+            usage.push(format!("{:.2} of {} CPUs", self.cpus, group.cpu_budget));
+        }
+        if group.memory_budget_mb > 0 {
+            usage.push(format!(
+                "{} of {} memory",
+                units::bytes(self.memory_bytes as f64), units::mebibytes(group.memory_budget_mb as f64)
+            ));
+        }
+        format!("{} ({}): {} containers using {}", group.name, group.label, self.containers.len(), usage.join(" and "))
+    }
+}
+
+/// Whether the container carries the group's label, `key=value` or just `key`.
+pub fn matches(group: &ContainerGroupConfig, container: &ContainerStats) -> bool {
+    match group.label.split_once('=') {
+        Some((key, value)) => container.labels.get(key.trim()).is_some_and(|label| label == value.trim()),
+        None => container.labels.contains_key(group.label.trim()),
+    }
+}
+
+/// Groups whose containers together use more CPU or memory than budgeted.
+/// Container CPU usage is in percent of one CPU.
+pub fn over_budget<'a>(groups: &'a [ContainerGroupConfig], containers: &[ContainerStats]) -> Vec<BudgetBreach<'a>> {
+    groups.iter()
+        .filter_map(|group| {
+            let members: Vec<ContainerStats> = containers.iter()
+                .filter(|container| matches(group, container))
+                .cloned()
+                .collect();
+            let cpus = members.iter().map(|container| container.cpu_usage).sum::<f64>() / 100.0;
+            let memory_bytes = members.iter().map(|container| container.memory_usage).sum::<u64>();
+            
+            let over_cpu = group.cpu_budget > 0.0 && cpus > group.cpu_budget;
+            let over_memory = group.memory_budget_mb > 0 && memory_bytes > group.memory_budget_mb * 1024 * 1024;
+            (over_cpu || over_memory).then_some(BudgetBreach {
+                group,
+                containers: members,
+                cpus,
+                memory_bytes,
+            })
+        })
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,189 @@
+use std::sync::Arc;
+use crate::api::ApiState;
+use crate::config::GrpcConfig;
+use log::error;
+
+#[cfg(feature = "grpc")]
+pub mod proto {
+    tonic::include_proto!("perfmon.v1");
+}
+
+/// Starts the gRPC API from `proto/monitor.proto` in the background, serving
+/// the same data as the REST endpoints from the shared [`ApiState`].
+#[cfg(feature = "grpc")]
+pub fn spawn(config: &GrpcConfig, api: Arc<ApiState>) {
+    use log::info;
+    use proto::monitor_server::MonitorServer;
+    
+    if !config.enabled {
+        return;
+    }
+    
+    let listen = match config.listen.parse() {
+        Ok(listen) => listen,
+        Err(e) => {
+            error!("Invalid gRPC listen address {}: {}", config.listen, e);
+            return;
+        }
+    };
+    let service = MonitorServer::new(service::MonitorService { api });
+    
+    tokio::spawn(async move {
+        info!("Serving gRPC API on {}", listen);
+        if let Err(e) = tonic::transport::Server::builder().add_service(service).serve(listen).await {
+            error!("gRPC API on {} stopped: {}", listen, e);
+        }
+    });
+}
+
+#[cfg(not(feature = "grpc"))]
+pub fn spawn(config: &GrpcConfig, _api: Arc<ApiState>) {
+    if config.enabled {
+        error!("grpc.enabled is set but this build has no gRPC support, rebuild with `--features grpc`");
+    }
+}
+
+#[cfg(feature = "grpc")]
+mod service {
+    use chrono::SecondsFormat;
+    use std::pin::Pin;
+    use std::sync::Arc;
+    use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+    use tonic::{Request, Response, Status};
+    use crate::alert::Alert;
+    use crate::api::{ApiState, Denied, Identity, Role};
+    use crate::config::parse_duration;
+    use crate::docker_monitor::ContainerStats;
+    use crate::server_monitor::ServerStats;
+    use super::proto::{self, monitor_server::Monitor};
+    use log::info;
+    
+    pub struct MonitorService {
+        pub api: Arc<ApiState>,
+    }
+    
+    impl MonitorService {
+        /// Checks the `authorization` metadata, which takes the same tokens as the REST API.
+        fn authorize<T>(&self, request: &Request<T>, role: Role) -> Result<Identity, Status> {
+            let authorization = request.metadata().get("authorization").and_then(|value| value.to_str().ok());
+            self.api.authorize(authorization, role).map_err(|denied| match denied {
+                Denied::Disabled | Denied::Forbidden => Status::permission_denied(denied.message()),
+                Denied::InvalidToken => Status::unauthenticated(denied.message()),
+            })
+        }
+    }
+    
+    #[tonic::async_trait]
+    impl Monitor for MonitorService {
+        async fn get_server_stats(&self, request: Request<proto::GetServerStatsRequest>) -> Result<Response<proto::ServerStats>, Status> {
+            self.authorize(&request, Role::Viewer)?;
+            match self.api.server() {
+                Some(server) => Ok(Response::new(server.into())),
+                None => Err(Status::unavailable("No monitoring cycle has completed yet")),
+            }
+        }
+        
+        async fn list_containers(&self, request: Request<proto::ListContainersRequest>) -> Result<Response<proto::ListContainersResponse>, Status> {
+            self.authorize(&request, Role::Viewer)?;
+            let filter = request.into_inner().name_filter;
+            let containers = self.api.containers().into_iter()
+                .filter(|container| container.name.contains(&filter))
+                .map(Into::into)
+                .collect();
+            Ok(Response::new(proto::ListContainersResponse { containers }))
+        }
+        
+        type StreamAlertsStream = Pin<Box<dyn Stream<Item = Result<proto::Alert, Status>> + Send>>;
+        
+        async fn stream_alerts(&self, request: Request<proto::StreamAlertsRequest>) -> Result<Response<Self::StreamAlertsStream>, Status> {
+            self.authorize(&request, Role::Viewer)?;
+            let rules = request.into_inner().rules;
+            // Alerts missed by a lagging subscriber are skipped rather than ending the stream
+            let stream = BroadcastStream::new(self.api.subscribe()).filter_map(move |alert| match alert {
+                Ok(alert) if rules.is_empty() || rules.contains(&alert.rule) => Some(Ok(alert.into())),
+                _ => None,
+            });
+            Ok(Response::new(Box::pin(stream)))
+        }
+        
+        async fn create_silence(&self, request: Request<proto::CreateSilenceRequest>) -> Result<Response<proto::Silence>, Status> {
+            let identity = self.authorize(&request, Role::Admin)?;
+            if let Some(refused) = self.api.refuse_changes() {
+                return Err(Status::unavailable(refused));
+            }
+            
+            let request = request.into_inner();
+            let duration = parse_duration(&request.duration)
+                .ok()
+                .and_then(|duration| chrono::Duration::from_std(duration).ok())
+                .ok_or_else(|| Status::invalid_argument(format!("Invalid duration '{}'", request.duration)))?;
+            let container = Some(request.container).filter(|container| !container.is_empty());
+            let silence = self.api.silences.create(&request.rule, container, duration, request.comment, identity.name)
+                .map_err(|e| Status::invalid_argument(e.to_string()))?;
+            
+            info!("Created silence {} for rule {} until {}", silence.id, silence.rule, silence.ends_at);
+            Ok(Response::new(proto::Silence {
+                id: silence.id,
+                rule: silence.rule,
+                container: silence.container.unwrap_or_default(),
+                comment: silence.comment,
+                created_by: silence.created_by,
+                starts_at: silence.starts_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+                ends_at: silence.ends_at.to_rfc3339_opts(SecondsFormat::Secs, true),
+            }))
+        }
+    }
+    
+    impl From<ServerStats> for proto::ServerStats {
+        fn from(server: ServerStats) -> Self {
+            Self {
+                timestamp: server.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+                hostname: server.system_info.hostname,
+                cpu_percent: server.cpu_usage,
+                memory_total_bytes: server.memory_usage.total,
+                memory_used_bytes: server.memory_usage.used,
+                memory_percent: server.memory_usage.percent,
+                swap_total_bytes: server.swap_usage.total,
+                swap_used_bytes: server.swap_usage.used,
+                swap_percent: server.swap_usage.percent,
+                disk_total_bytes: server.disk_usage.total,
+                disk_used_bytes: server.disk_usage.used,
+                disk_percent: server.disk_usage.percent,
+                load_1: server.load_average.one_min,
+                load_5: server.load_average.five_min,
+                load_15: server.load_average.fifteen_min,
+            }
+        }
+    }
+    
+    impl From<ContainerStats> for proto::Container {
+        fn from(container: ContainerStats) -> Self {
+            Self {
+                id: container.id,
+                name: container.name,
+                image: container.image,
+                status: container.status,
+                cpu_percent: container.cpu_usage,
+                memory_usage_bytes: container.memory_usage,
+                memory_limit_bytes: container.memory_limit,
+                memory_percent: container.memory_percent,
+            }
+        }
+    }
+    
+    impl From<Alert> for proto::Alert {
+        fn from(alert: Alert) -> Self {
+            Self {
+                rule: alert.rule,
+                severity: alert.severity.label().to_lowercase(),
+                state: alert.state.label().to_lowercase(),
+                summary: alert.summary,
+                value: alert.value,
+                threshold: alert.threshold,
+                timestamp: alert.timestamp.to_rfc3339_opts(SecondsFormat::Secs, true),
+                containers: alert.containers.into_iter().map(|container| container.name).collect(),
+                notes: alert.notes,
+            }
+        }
+    }
+}
\ No newline at end of file
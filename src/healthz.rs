@@ -0,0 +1,56 @@
+use chrono::{Duration, Utc};
+use crate::config::Config;
+use crate::docker_monitor::DockerMonitor;
+use crate::kubernetes;
+use crate::state::StateStore;
+use std::sync::Arc;
+
+/// Self check for container HEALTHCHECKs. Returns true when the config loads,
+/// Docker is reachable and the last monitoring cycle finished within `max_age`
+/// (defaults to three check intervals). In a cluster Docker is optional, the
+/// node may run containerd.
+pub async fn run(config_path: &str, max_age: Option<std::time::Duration>) -> bool {
+    let mut healthy = true;
+    
+    let config = match Config::load_from_file(config_path) {
+        Ok(config) => {
+            println!("✅ config: {} loaded", config_path);
+            config
+        }
+        Err(e) => {
+            println!("❌ config: failed to load {}: {}", config_path, e);
+            return false;
+        }
+    };
+    
+    match DockerMonitor::new(Arc::new(config.clone())).await {
+        Ok(_) => println!("✅ docker: reachable"),
+        Err(e) if kubernetes::current().is_some() => println!("⚠️ docker: {} (in cluster, host only)", e),
+        Err(e) => {
+            println!("❌ docker: {}", e);
+            healthy = false;
+        }
+    }
+    
+    let max_age = max_age
+        .and_then(|age| Duration::from_std(age).ok())
+        .unwrap_or_else(|| Duration::seconds(config.monitoring.check_interval as i64 * 3));
+    let state = StateStore::open(&config);
+    
+    match state.state.last_cycle {
+        Some(last_cycle) if Utc::now() - last_cycle <= max_age => {
+            println!("✅ cycle: last completed {}s ago", (Utc::now() - last_cycle).num_seconds());
+        }
+        Some(last_cycle) => {
+            println!("❌ cycle: last completed {}s ago (max {}s)",
+                     (Utc::now() - last_cycle).num_seconds(), max_age.num_seconds());
+            healthy = false;
+        }
+        None => {
+            println!("❌ cycle: no completed cycle recorded in {}", state.describe());
+            healthy = false;
+        }
+    }
+    
+    healthy
+}
\ No newline at end of file
@@ -0,0 +1,213 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use std::collections::BTreeMap;
+use crate::alert::{self, Alert};
+use crate::docker_monitor::ContainerStats;
+
+/// How far back alerts are compared, with the label used in notes.
+const COMPARISONS: [(i64, &str); 3] = [(1, "1h ago"), (24, "24h ago"), (24 * 7, "same hour last week")];
+
+/// A metric's hourly value at an earlier time, so recipients can tell a
+/// novel spike from a routine one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Comparison {
+    pub label: String,
+    pub hour: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Host usage aggregated over one hour.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryBucket {
+    pub hour: DateTime<Utc>,
+    pub samples: u64,
+    pub cpu_sum: f64,
+    pub cpu_max: f64,
+    pub memory_sum: f64,
+    pub memory_max: f64,
+    pub disk_max: f64,
+}
+
+impl HistoryBucket {
+    fn new(hour: DateTime<Utc>) -> Self {
+        Self {
+            hour,
+            samples: 0,
+            cpu_sum: 0.0,
+            cpu_max: 0.0,
+            memory_sum: 0.0,
+            memory_max: 0.0,
+            disk_max: 0.0,
+        }
+    }
+    
+    pub fn cpu_avg(&self) -> f64 {
+        self.cpu_sum / self.samples.max(1) as f64
+    }
+    
+    pub fn memory_avg(&self) -> f64 {
+        self.memory_sum / self.samples.max(1) as f64
+    }
+}
+
+/// Hourly CPU/memory/disk history persisted in the state file, used for trend
+/// reports and the dashboard. Buckets older than the retention are dropped on
+/// every record.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct History {
+    #[serde(default)]
+    pub buckets: Vec<HistoryBucket>,
+    #[serde(default)]
+    pub containers: Vec<ContainerBucket>,
+}
+
+impl History {
+    pub fn record(&mut self, now: DateTime<Utc>, cpu: f64, memory: f64, disk: f64, retention_days: u64) {
+        let hour = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+        if self.buckets.last().is_none_or(|bucket| bucket.hour != hour) {
+            self.buckets.push(HistoryBucket::new(hour));
+        }
+        
+        if let Some(bucket) = self.buckets.last_mut() {
+            bucket.samples += 1;
+            bucket.cpu_sum += cpu;
+            bucket.cpu_max = bucket.cpu_max.max(cpu);
+            bucket.memory_sum += memory;
+            bucket.memory_max = bucket.memory_max.max(memory);
+            bucket.disk_max = bucket.disk_max.max(disk);
+        }
+        
+        let cutoff = now - Duration::days(retention_days as i64);
+        self.buckets.retain(|bucket| bucket.hour >= cutoff);
+    }
+    
+    /// Adds this cycle's container samples to the current hour. When a new
+    /// hour starts the previous one is trimmed to the `top` containers by CPU
+    /// and by memory.
+    pub fn record_containers(&mut self, now: DateTime<Utc>, containers: &[ContainerStats], top: usize, retention_days: u64) {
+        let hour = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+        if self.containers.last().is_none_or(|bucket| bucket.hour != hour) {
+            if let Some(previous) = self.containers.last_mut() {
+                previous.trim(top);
+            }
+            self.containers.push(ContainerBucket { hour, containers: BTreeMap::new() });
+        }
+        
+        if let Some(bucket) = self.containers.last_mut() {
+            for container in containers {
+                let usage = bucket.containers.entry(container.name.clone()).or_default();
+                usage.samples += 1;
+                usage.cpu_sum += container.cpu_usage;
+                usage.memory_sum += container.memory_usage as f64;
+            }
+        }
+        
+        let cutoff = now - Duration::days(retention_days as i64);
+        self.containers.retain(|bucket| bucket.hour >= cutoff);
+    }
+    
+    /// Buckets starting at or after `since`, oldest first.
+    pub fn since(&self, since: DateTime<Utc>) -> Vec<&HistoryBucket> {
+        self.buckets.iter().filter(|bucket| bucket.hour >= since).collect()
+    }
+    
+    /// Container buckets starting at or after `since`, oldest first.
+    pub fn containers_since(&self, since: DateTime<Utc>) -> Vec<&ContainerBucket> {
+        self.containers.iter().filter(|bucket| bucket.hour >= since).collect()
+    }
+    
+    /// The value 1h, 24h and a week before `now`, read from the hourly
+    /// bucket by `value`. Hours without samples are left out.
+    fn compare<B>(buckets: &[B], now: DateTime<Utc>, hour_of: fn(&B) -> DateTime<Utc>, value: impl Fn(&B) -> Option<f64>) -> Vec<Comparison> {
+        let current = now.duration_trunc(Duration::hours(1)).unwrap_or(now);
+        COMPARISONS.iter()
+            .filter_map(|(hours, label)| {
+                let hour = current - Duration::hours(*hours);
+                let bucket = buckets.iter().find(|bucket| hour_of(bucket) == hour)?;
+                Some(Comparison {
+                    label: label.to_string(),
+                    hour,
+                    value: value(bucket)?,
+                })
+            })
+            .collect()
+    }
+    
+    /// Adds the history of the alert's metric: average host CPU or memory,
+    /// peak disk usage, or the average CPU of the busiest container. Other
+    /// rules have no history and are returned unchanged.
+    pub fn annotate(&self, mut alert: Alert, now: DateTime<Utc>) -> Alert {
+        let host = |value: fn(&HistoryBucket) -> f64| {
+            Self::compare(&self.buckets, now, |bucket| bucket.hour, |bucket| Some(value(bucket)))
+        };
+        let (metric, comparisons) = match alert.rule.as_str() {
+            alert::RULE_CPU => ("CPU".to_string(), host(HistoryBucket::cpu_avg)),
+            alert::RULE_MEMORY => ("Memory".to_string(), host(HistoryBucket::memory_avg)),
+            alert::RULE_DISK => ("Root disk".to_string(), host(|bucket| bucket.disk_max)),
+            alert::RULE_CONTAINER_CPU => match alert.containers.first() {
+                Some(container) => (
+                    format!("{} CPU", container.name),
+                    Self::compare(&self.containers, now, |bucket| bucket.hour, |bucket| {
+                        bucket.containers.get(&container.name).map(ContainerUsage::cpu_avg)
+                    }),
+                ),
+                None => return alert,
+            },
+            _ => return alert,
+        };
+        if comparisons.is_empty() {
+            return alert;
+        }
+        
+        let values: Vec<String> = comparisons.iter()
+            .map(|comparison| format!("{:.2}% {}", comparison.value, comparison.label))
+            .collect();
+        alert.notes.push(format!("{} was {}", metric, values.join(", ")));
+        alert.history = comparisons;
+        alert
+    }
+}
+/// One container's usage aggregated over one hour.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContainerUsage {
+    pub samples: u64,
+    pub cpu_sum: f64,
+    pub memory_sum: f64,
+}
+
+impl ContainerUsage {
+    pub fn cpu_avg(&self) -> f64 {
+        self.cpu_sum / self.samples.max(1) as f64
+    }
+    
+    /// Average memory usage in bytes.
+    pub fn memory_avg(&self) -> f64 {
+        self.memory_sum / self.samples.max(1) as f64
+    }
+}
+
+/// Per-container usage for one hour. Closed hours keep only the top
+/// containers by CPU and by memory so the state file stays small.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ContainerBucket {
+    pub hour: DateTime<Utc>,
+    #[serde(default)]
+    pub containers: BTreeMap<String, ContainerUsage>,
+}
+
+impl ContainerBucket {
+    fn trim(&mut self, top: usize) {
+        if self.containers.len() <= top {
+            return;
+        }
+        
+        let ranked = |value: fn(&ContainerUsage) -> f64| {
+            let mut names: Vec<(&String, f64)> = self.containers.iter().map(|(name, usage)| (name, value(usage))).collect();
+            names.sort_by(|a, b| b.1.total_cmp(&a.1));
+            names.into_iter().take(top).map(|(name, _)| name.clone()).collect::<Vec<_>>()
+        };
+        let mut keep = ranked(ContainerUsage::cpu_avg);
+        keep.extend(ranked(ContainerUsage::memory_avg));
+        self.containers.retain(|name, _| keep.contains(name));
+    }
+}
\ No newline at end of file
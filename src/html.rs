@@ -0,0 +1,8 @@
+/// Escapes text for HTML element content and double-quoted attributes, for
+/// the email, dashboard and status page renderers.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
\ No newline at end of file
@@ -0,0 +1,403 @@
+use axum::{body::Bytes, extract::{Path, Query, State}, http::{header, HeaderMap, StatusCode}, response::{Html, IntoResponse, Response}, routing::{delete, get, post}, Json, Router};
+use serde::Deserialize;
+use std::sync::Arc;
+use crate::api::{ApiState, Denied, Identity, Role};
+use crate::chatops::{self, SlackCommand, TelegramUpdate};
+use crate::config::{parse_duration, ChatOpsConfig, Config};
+use crate::dashboard::{PublishedHistory, Range};
+use crate::metrics::RuntimeMetrics;
+use crate::query::QueryRequest;
+use crate::redact::Redactor;
+use crate::statuspage::PublishedPage;
+use crate::thresholds;
+use crate::webpush::{self, PushSubscription, WebPushNotifier};
+use log::{info, error, warn};
+
+#[derive(Clone)]
+struct AppState {
+    metrics: Arc<RuntimeMetrics>,
+    status_page: Arc<PublishedPage>,
+    webpush: Arc<WebPushNotifier>,
+    history: Arc<PublishedHistory>,
+    top_containers: usize,
+    api: Arc<ApiState>,
+    chatops: Arc<ChatOpsConfig>,
+    redactor: Arc<Redactor>,
+    /// Adjustable thresholds as configured, before runtime overrides.
+    thresholds: Arc<Vec<(&'static str, f64)>>,
+}
+
+#[derive(Deserialize)]
+struct HistoryQuery {
+    range: Option<String>,
+    container: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CreateSilence {
+    rule: String,
+    #[serde(default)]
+    container: Option<String>,
+    duration: String,
+    #[serde(default)]
+    comment: String,
+}
+
+#[derive(Deserialize)]
+struct SetThreshold {
+    name: String,
+    value: f64,
+    #[serde(default)]
+    duration: Option<String>,
+    #[serde(default)]
+    persist: bool,
+}
+
+#[derive(Deserialize)]
+struct Unsubscribe {
+    endpoint: String,
+}
+
+/// Starts the HTTP endpoint in the background. Serves Prometheus metrics on
+/// `/metrics`, the status page on `/status`, container history charts on
+/// `/history`, the REST API under `/api/v1`, web push subscriptions on `/push`
+/// and, with `chatops.enabled`, Slack and Telegram commands under `/chatops`.
+pub fn spawn(
+    config: &Config,
+    metrics: Arc<RuntimeMetrics>,
+    status_page: Arc<PublishedPage>,
+    webpush: Arc<WebPushNotifier>,
+    history: Arc<PublishedHistory>,
+    api: Arc<ApiState>,
+    redactor: Arc<Redactor>,
+) {
+    if !config.http.enabled {
+        return;
+    }
+    
+    let listen = config.http.listen.clone();
+    let mut app = Router::new()
+        .route("/metrics", get(prometheus_metrics))
+        .route("/status", get(status_page_html))
+        .route("/history", get(history_html))
+        .route("/api/v1/query", get(query_history))
+        .route("/api/v1/server", get(server_stats))
+        .route("/api/v1/containers", get(list_containers))
+        .route("/api/v1/silences", get(list_silences).post(create_silence))
+        .route("/api/v1/thresholds", get(list_thresholds).post(set_threshold))
+        .route("/api/v1/thresholds/:name", delete(reset_threshold))
+        .route("/api/v1/alerts/:rule/ack", post(acknowledge_alert))
+        .route("/push", get(push_page))
+        .route("/push/sw.js", get(push_service_worker))
+        .route("/push/subscribe", post(push_subscribe))
+        .route("/push/unsubscribe", post(push_unsubscribe));
+    if config.chatops.enabled {
+        app = app
+            .route("/chatops/slack", post(slack_command))
+            .route("/chatops/telegram", post(telegram_update));
+    }
+    let app = app.with_state(AppState {
+        metrics,
+        status_page,
+        webpush,
+        history,
+        top_containers: config.history.top_containers,
+        api,
+        chatops: Arc::new(config.chatops.clone()),
+        redactor,
+        thresholds: Arc::new(thresholds::configured(config)),
+    });
+    
+    tokio::spawn(async move {
+        let listener = match tokio::net::TcpListener::bind(&listen).await {
+            Ok(listener) => listener,
+            Err(e) => {
+                error!("Failed to bind HTTP endpoint on {}: {}", listen, e);
+                return;
+            }
+        };
+        
+        info!("Serving metrics on http://{}/metrics", listen);
+        if let Err(e) = axum::serve(listener, app).await {
+            error!("HTTP endpoint stopped: {}", e);
+        }
+    });
+}
+
+fn authorize(state: &AppState, headers: &HeaderMap, role: Role) -> Result<Identity, Denied> {
+    let authorization = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+    state.api.authorize(authorization, role)
+}
+
+/// A missing or wrong token is answered with a Basic challenge so browsers
+/// prompt for it on the dashboard pages.
+fn refuse(denied: Denied) -> Response {
+    let message = format!("{}\n", denied.message());
+    match denied {
+        Denied::InvalidToken => (
+            StatusCode::UNAUTHORIZED,
+            [(header::WWW_AUTHENTICATE, "Basic realm=\"performance-monitor\"")],
+            message,
+        ).into_response(),
+        Denied::Disabled | Denied::Forbidden => (StatusCode::FORBIDDEN, message).into_response(),
+    }
+}
+
+/// Changes made on a standby would be lost, the leader saves the state.
+fn refuse_on_standby(state: &AppState) -> Option<Response> {
+    state.api.refuse_changes().map(|message| (StatusCode::SERVICE_UNAVAILABLE, format!("{}\n", message)).into_response())
+}
+
+async fn prometheus_metrics(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    (
+        [(header::CONTENT_TYPE, "text/plain; version=0.0.4")],
+        state.metrics.snapshot().to_prometheus(),
+    )
+        .into_response()
+}
+
+async fn status_page_html(State(state): State<AppState>) -> Response {
+    match state.status_page.get() {
+        Some(html) => Html(html).into_response(),
+        None => (StatusCode::NOT_FOUND, "Status page is disabled or not rendered yet\n").into_response(),
+    }
+}
+
+async fn history_html(State(state): State<AppState>, headers: HeaderMap, Query(query): Query<HistoryQuery>) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    let range = Range::parse(query.range.as_deref());
+    match state.history.render(range, query.container.as_deref(), state.top_containers) {
+        Ok(html) => Html(html).into_response(),
+        Err(e) => (StatusCode::INTERNAL_SERVER_ERROR, format!("Failed to render history: {}\n", e)).into_response(),
+    }
+}
+
+async fn query_history(State(state): State<AppState>, headers: HeaderMap, Query(request): Query<QueryRequest>) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    match state.history.query(&request) {
+        Ok(response) => Json(response).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, Json(serde_json::json!({ "error": e.to_string() }))).into_response(),
+    }
+}
+
+async fn server_stats(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    match state.api.server() {
+        Some(server) => Json(server).into_response(),
+        None => (StatusCode::SERVICE_UNAVAILABLE, "No monitoring cycle has completed yet\n").into_response(),
+    }
+}
+
+async fn list_containers(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    Json(state.api.containers()).into_response()
+}
+
+async fn list_silences(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    Json(state.api.silences.active(chrono::Utc::now())).into_response()
+}
+
+async fn create_silence(State(state): State<AppState>, headers: HeaderMap, Json(request): Json<CreateSilence>) -> Response {
+    let identity = match authorize(&state, &headers, Role::Admin) {
+        Ok(identity) => identity,
+        Err(denied) => return refuse(denied),
+    };
+    if let Some(refused) = refuse_on_standby(&state) {
+        return refused;
+    }
+    
+    let duration = match parse_duration(&request.duration).map(chrono::Duration::from_std) {
+        Ok(Ok(duration)) => duration,
+        _ => return (StatusCode::BAD_REQUEST, format!("Invalid duration '{}'\n", request.duration)).into_response(),
+    };
+    match state.api.silences.create(&request.rule, request.container, duration, request.comment, identity.name) {
+        Ok(silence) => {
+            info!("Created silence {} for rule {} until {}", silence.id, silence.rule, silence.ends_at);
+            (StatusCode::CREATED, Json(silence)).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, format!("{}\n", e)).into_response(),
+    }
+}
+
+async fn list_thresholds(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    
+    let overrides = state.api.thresholds.active(chrono::Utc::now());
+    let thresholds: Vec<serde_json::Value> = state.thresholds.iter()
+        .map(|(name, configured)| {
+            let adjusted = overrides.iter().find(|adjusted| adjusted.name == *name);
+            serde_json::json!({
+                "name": name,
+                "configured": configured,
+                "value": adjusted.map_or(*configured, |adjusted| adjusted.value),
+                "override": adjusted,
+            })
+        })
+        .collect();
+    Json(thresholds).into_response()
+}
+
+async fn set_threshold(State(state): State<AppState>, headers: HeaderMap, Json(request): Json<SetThreshold>) -> Response {
+    let identity = match authorize(&state, &headers, Role::Admin) {
+        Ok(identity) => identity,
+        Err(denied) => return refuse(denied),
+    };
+    if let Some(refused) = refuse_on_standby(&state) {
+        return refused;
+    }
+    
+    let duration = match request.duration.as_deref().map(|duration| parse_duration(duration).map(chrono::Duration::from_std)) {
+        None => None,
+        Some(Ok(Ok(duration))) => Some(duration),
+        Some(_) => {
+            let duration = request.duration.unwrap_or_default();
+            return (StatusCode::BAD_REQUEST, format!("Invalid duration '{}'\n", duration)).into_response();
+        }
+    };
+    match state.api.thresholds.set(&request.name, request.value, duration, request.persist, identity.name) {
+        Ok(adjusted) => {
+            info!("Threshold {} set to {} by {}{}", adjusted.name, adjusted.value, adjusted.set_by,
+                  if adjusted.persisted { " and written to the config file" } else { "" });
+            (StatusCode::CREATED, Json(adjusted)).into_response()
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, format!("{}\n", e)).into_response(),
+    }
+}
+
+async fn reset_threshold(State(state): State<AppState>, headers: HeaderMap, Path(name): Path<String>) -> Response {
+    let identity = match authorize(&state, &headers, Role::Admin) {
+        Ok(identity) => identity,
+        Err(denied) => return refuse(denied),
+    };
+    if let Some(refused) = refuse_on_standby(&state) {
+        return refused;
+    }
+    if state.api.thresholds.reset(&name, &identity.name) {
+        info!("Threshold {} reset to its configured value", name);
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        (StatusCode::NOT_FOUND, format!("Threshold {} has no override\n", name)).into_response()
+    }
+}
+
+async fn acknowledge_alert(State(state): State<AppState>, headers: HeaderMap, Path(rule): Path<String>) -> Response {
+    let identity = match authorize(&state, &headers, Role::Admin) {
+        Ok(identity) => identity,
+        Err(denied) => return refuse(denied),
+    };
+    if let Some(refused) = refuse_on_standby(&state) {
+        return refused;
+    }
+    match state.api.acknowledge(&rule, &identity.name) {
+        Some(acknowledgement) => {
+            info!("Rule {} acknowledged by {}", rule, acknowledgement.by);
+            Json(acknowledgement).into_response()
+        }
+        None => (StatusCode::NOT_FOUND, format!("Rule {} is not firing\n", rule)).into_response(),
+    }
+}
+
+async fn slack_command(State(state): State<AppState>, headers: HeaderMap, body: Bytes) -> Response {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok());
+    let verified = chatops::verify_slack(
+        &state.chatops, header("x-slack-request-timestamp"), header("x-slack-signature"), &body, chrono::Utc::now()
+    );
+    if let Err(e) = verified {
+        warn!("Rejected Slack command: {}", e);
+        return (StatusCode::UNAUTHORIZED, format!("{}\n", e)).into_response();
+    }
+    let command: SlackCommand = match serde_urlencoded::from_bytes(&body) {
+        Ok(command) => command,
+        Err(e) => return (StatusCode::BAD_REQUEST, format!("{}\n", e)).into_response(),
+    };
+    
+    info!("Slack command from {} ({}): {}", command.user_name, command.user_id, command.text);
+    let reply = chatops::reply(&state.chatops, &state.api, &state.redactor, &command.text, &command.user_name, &command.user_id);
+    Json(serde_json::json!({ "response_type": "in_channel", "text": reply })).into_response()
+}
+
+/// Telegram webhook. The reply is returned as a `sendMessage` call in the
+/// response, so the bot token is never needed here.
+async fn telegram_update(State(state): State<AppState>, headers: HeaderMap, Json(update): Json<TelegramUpdate>) -> Response {
+    let secret = headers.get("x-telegram-bot-api-secret-token").and_then(|value| value.to_str().ok());
+    if let Err(e) = chatops::verify_telegram(&state.chatops, secret) {
+        warn!("Rejected Telegram update: {}", e);
+        return (StatusCode::UNAUTHORIZED, format!("{}\n", e)).into_response();
+    }
+    let Some(message) = update.message else {
+        return StatusCode::OK.into_response();
+    };
+    let Some(text) = message.command_text() else {
+        return StatusCode::OK.into_response();
+    };
+    // Acknowledge updates from other chats so Telegram does not retry them
+    if !chatops::telegram_chat_allowed(&state.chatops, message.chat.id) {
+        warn!("Ignored Telegram command from chat {} which is not in chatops.telegram_chats", message.chat.id);
+        return StatusCode::OK.into_response();
+    }
+    
+    let user = message.user();
+    let user_id = message.user_id().map(|id| id.to_string()).unwrap_or_default();
+    info!("Telegram command from {} ({}): {}", user, user_id, text);
+    let reply = chatops::reply(&state.chatops, &state.api, &state.redactor, text, &user, &user_id);
+    Json(serde_json::json!({
+        "method": "sendMessage",
+        "chat_id": message.chat.id,
+        "text": chatops::telegram_html(&reply),
+        "parse_mode": "HTML",
+    }))
+    .into_response()
+}
+
+/// The page needs a token too, so the browser's login covers the
+/// subscribe and unsubscribe calls it makes.
+async fn push_page(State(state): State<AppState>, headers: HeaderMap) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    match state.webpush.public_key() {
+        Some(public_key) => Html(webpush::subscribe_page(public_key)).into_response(),
+        None => (StatusCode::NOT_FOUND, "Web push is disabled\n").into_response(),
+    }
+}
+
+async fn push_service_worker() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, "application/javascript")], webpush::SERVICE_WORKER)
+}
+
+async fn push_subscribe(State(state): State<AppState>, headers: HeaderMap, Json(subscription): Json<PushSubscription>) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    match state.webpush.subscribe(subscription) {
+        Ok(_) => StatusCode::CREATED.into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, format!("{}\n", e)).into_response(),
+    }
+}
+
+async fn push_unsubscribe(State(state): State<AppState>, headers: HeaderMap, Json(request): Json<Unsubscribe>) -> Response {
+    if let Err(denied) = authorize(&state, &headers, Role::Viewer) {
+        return refuse(denied);
+    }
+    if state.webpush.unsubscribe(&request.endpoint) {
+        StatusCode::NO_CONTENT.into_response()
+    } else {
+        StatusCode::NOT_FOUND.into_response()
+    }
+}
\ No newline at end of file
@@ -0,0 +1,415 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, NaiveDateTime, TimeZone, Utc, Weekday};
+use reqwest::Client;
+use std::collections::HashMap;
+use crate::timezone::Timezone;
+use anyhow::{Result, anyhow};
+use log::warn;
+
+/// How far ahead of now recurring events are expanded.
+const RECURRENCE_HORIZON_DAYS: i64 = 90;
+/// Bounds the expansion of rules that started long ago or never match.
+const MAX_RECURRENCE_PERIODS: i64 = 100_000;
+
+/// An event of an iCalendar feed with its times resolved to UTC.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CalendarEvent {
+    pub summary: String,
+    pub description: String,
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    /// `mailto:` addresses of the attendees, in calendar order.
+    pub attendees: Vec<String>,
+}
+
+/// A content line, `NAME;PARAM=value:VALUE`.
+struct Property {
+    name: String,
+    parameters: Vec<(String, String)>,
+    value: String,
+}
+
+impl CalendarEvent {
+    pub fn covers(&self, time: DateTime<Utc>) -> bool {
+        self.start <= time && time < self.end
+    }
+}
+
+/// Downloads and parses an iCalendar feed.
+pub async fn fetch(client: &Client, url: &str, default_zone: &Timezone) -> Result<Vec<CalendarEvent>> {
+    let response = client.get(url).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("calendar returned {}", response.status()));
+    }
+    Ok(parse(&response.text().await?, default_zone, Utc::now()))
+}
+
+/// Parses the events of an iCalendar (RFC 5545) document. Times with a
+/// `TZID` are read in that zone, floating times in `default_zone`, and events
+/// without a usable start or end are skipped.
+///
+/// Recurring events are expanded into the occurrences that have not ended by
+/// `now` and start within `RECURRENCE_HORIZON_DAYS` of it. `RRULE` supports
+/// `FREQ` (daily to yearly), `INTERVAL`, `COUNT`, `UNTIL` and weekday `BYDAY`
+/// on weekly rules; `EXDATE` and `RECURRENCE-ID` remove occurrences, the
+/// latter in favour of the modified event. Other rules only keep their first
+/// occurrence, with a warning.
+pub fn parse(text: &str, default_zone: &Timezone, now: DateTime<Utc>) -> Vec<CalendarEvent> {
+    let mut lines: Vec<String> = Vec::new();
+    for line in text.split('\n').map(|line| line.trim_end_matches('\r')) {
+        match (line.strip_prefix([' ', '\t']), lines.last_mut()) {
+            (Some(continuation), Some(last)) => last.push_str(continuation),
+            _ => lines.push(line.to_string()),
+        }
+    }
+    
+    let mut components = Vec::new();
+    let mut current: Option<Vec<Property>> = None;
+    for line in &lines {
+        let Some(property) = property(line) else {
+            continue;
+        };
+        match (property.name.as_str(), property.value.as_str()) {
+            ("BEGIN", "VEVENT") => current = Some(Vec::new()),
+            ("END", "VEVENT") => components.extend(current.take()),
+            _ => {
+                if let Some(properties) = current.as_mut() {
+                    properties.push(property);
+                }
+            }
+        }
+    }
+    
+    let mut zones = HashMap::new();
+    // Occurrences replaced by a modified copy, by UID
+    let mut modified: HashMap<String, Vec<DateTime<Utc>>> = HashMap::new();
+    for properties in &components {
+        let find = |wanted: &str| properties.iter().find(|property| property.name == wanted);
+        if let (Some(uid), Some(recurrence_id)) = (find("UID"), find("RECURRENCE-ID")) {
+            if let Some((time, _)) = time(recurrence_id, default_zone, &mut zones) {
+                modified.entry(uid.value.trim().to_string()).or_default().push(time);
+            }
+        }
+    }
+    
+    let mut events = Vec::new();
+    for properties in &components {
+        let Some(event) = event(properties, default_zone, &mut zones) else {
+            continue;
+        };
+        let find = |wanted: &str| properties.iter().find(|property| property.name == wanted);
+        match find("RRULE") {
+            Some(rule) if find("RECURRENCE-ID").is_none() => {
+                let mut excluded: Vec<DateTime<Utc>> = find("UID")
+                    .and_then(|uid| modified.get(uid.value.trim()))
+                    .cloned()
+                    .unwrap_or_default();
+                for exdate in properties.iter().filter(|property| property.name == "EXDATE") {
+                    excluded.extend(exdate.value.split(',').filter_map(|value| {
+                        let single = Property {
+                            name: exdate.name.clone(),
+                            parameters: exdate.parameters.clone(),
+                            value: value.to_string(),
+                        };
+                        time(&single, default_zone, &mut zones).map(|(time, _)| time)
+                    }));
+                }
+                let start = find("DTSTART").and_then(|start| local_time(start, default_zone, &mut zones));
+                match start.and_then(|(start, zone, _)| occurrences(&event, &rule.value, start, &zone, &excluded, now)) {
+                    Some(occurrences) => events.extend(occurrences),
+                    None => {
+                        warn!(
+                            "Calendar event '{}' repeats with an unsupported RRULE '{}', only its first occurrence is used",
+                            event.summary, rule.value,
+                        );
+                        events.push(event);
+                    }
+                }
+            }
+            _ => events.push(event),
+        }
+    }
+    events
+}
+
+/// The occurrences of `event` under `rule` that end after `now` and start
+/// before the expansion horizon, `None` when the rule is not supported.
+/// Occurrences are generated in local time, so they keep their wall clock
+/// time across DST changes.
+fn occurrences(
+    event: &CalendarEvent,
+    rule: &str,
+    start: NaiveDateTime,
+    zone: &Timezone,
+    excluded: &[DateTime<Utc>],
+    now: DateTime<Utc>,
+) -> Option<Vec<CalendarEvent>> {
+    let mut frequency = None;
+    let mut interval = 1i64;
+    let mut count = None;
+    let mut until = None;
+    let mut weekdays = Vec::new();
+    for part in rule.split(';').filter(|part| !part.trim().is_empty()) {
+        let (key, value) = part.split_once('=')?;
+        let value = value.trim();
+        match key.trim().to_uppercase().as_str() {
+            "FREQ" => frequency = Some(value.to_uppercase()),
+            "INTERVAL" => interval = value.parse::<u32>().ok().filter(|interval| *interval > 0)?.into(),
+            "COUNT" => count = Some(value.parse::<usize>().ok()?),
+            "UNTIL" => {
+                let until_property = Property { name: "UNTIL".to_string(), parameters: Vec::new(), value: value.to_string() };
+                let (naive, until_zone, all_day) = local_time(&until_property, zone, &mut HashMap::new())?;
+                // A date UNTIL includes the whole day
+                let naive = if all_day { naive + Duration::days(1) - Duration::seconds(1) } else { naive };
+                until = Some(to_utc(naive, &until_zone));
+            }
+            "BYDAY" => {
+                for day in value.split(',') {
+                    weekdays.push(weekday(day.trim())?);
+                }
+                weekdays.sort_by_key(|day| day.num_days_from_monday());
+                weekdays.dedup();
+            }
+            "WKST" => {}
+            _ => return None,
+        }
+    }
+    
+    let frequency = frequency?;
+    if !weekdays.is_empty() && frequency != "WEEKLY" {
+        return None;
+    }
+    let months = match frequency.as_str() {
+        "MONTHLY" => 1,
+        "YEARLY" => 12,
+        "DAILY" | "WEEKLY" => 0,
+        _ => return None,
+    };
+    
+    // Local starts within the `step`th period, `None` past the calendar's range
+    let period = |step: i64| -> Option<Vec<NaiveDateTime>> {
+        let steps = step * interval;
+        match frequency.as_str() {
+            "DAILY" => Some(vec![start.checked_add_signed(Duration::try_days(steps)?)?]),
+            "WEEKLY" if weekdays.is_empty() => Some(vec![start.checked_add_signed(Duration::try_weeks(steps)?)?]),
+            "WEEKLY" => {
+                let monday = start.date() - Duration::days(start.weekday().num_days_from_monday() as i64);
+                let monday = monday.checked_add_signed(Duration::try_weeks(steps)?)?;
+                Some(weekdays.iter()
+                    .map(|day| (monday + Duration::days(day.num_days_from_monday() as i64)).and_time(start.time()))
+                    .filter(|occurrence| *occurrence >= start)
+                    .collect())
+            }
+            _ => {
+                let month = start.year() as i64 * 12 + start.month0() as i64 + steps * months;
+                let year = i32::try_from(month.div_euclid(12)).ok()?;
+                // Months without the start's day (the 31st, February 29th) are skipped
+                Some(NaiveDate::from_ymd_opt(year, month.rem_euclid(12) as u32 + 1, start.day())
+                    .map(|date| date.and_time(start.time()))
+                    .into_iter()
+                    .collect())
+            }
+        }
+    };
+    
+    let duration = event.end - event.start;
+    let horizon = now + Duration::days(RECURRENCE_HORIZON_DAYS);
+    let mut generated = 0;
+    let mut occurrences = Vec::new();
+    'periods: for step in 0..MAX_RECURRENCE_PERIODS {
+        let Some(starts) = period(step) else {
+            break;
+        };
+        for local in starts {
+            let start = to_utc(local, zone);
+            if start >= horizon || until.is_some_and(|until| start > until) || count.is_some_and(|count| generated >= count) {
+                break 'periods;
+            }
+            generated += 1;
+            if start + duration > now && !excluded.contains(&start) {
+                occurrences.push(CalendarEvent { start, end: start + duration, ..event.clone() });
+            }
+        }
+    }
+    Some(occurrences)
+}
+
+fn weekday(day: &str) -> Option<Weekday> {
+    match day.to_uppercase().as_str() {
+        "MO" => Some(Weekday::Mon),
+        "TU" => Some(Weekday::Tue),
+        "WE" => Some(Weekday::Wed),
+        "TH" => Some(Weekday::Thu),
+        "FR" => Some(Weekday::Fri),
+        "SA" => Some(Weekday::Sat),
+        "SU" => Some(Weekday::Sun),
+        // Ordinals such as `1MO` or `-1FR` are not supported
+        _ => None,
+    }
+}
+
+fn event(
+    properties: &[Property],
+    default_zone: &Timezone,
+    zones: &mut HashMap<String, Timezone>,
+) -> Option<CalendarEvent> {
+    let find = |wanted: &str| properties.iter().find(|property| property.name == wanted);
+    let text = |wanted: &str| find(wanted).map(|property| unescape(&property.value)).unwrap_or_default();
+    
+    let (start, all_day) = time(find("DTSTART")?, default_zone, zones)?;
+    let end = match find("DTEND") {
+        Some(end) => time(end, default_zone, zones)?.0,
+        None if all_day => start + Duration::days(1),
+        None => return None,
+    };
+    
+    Some(CalendarEvent {
+        summary: text("SUMMARY"),
+        description: text("DESCRIPTION"),
+        start,
+        end,
+        attendees: properties.iter()
+            .filter(|property| property.name == "ATTENDEE")
+            .filter_map(|property| {
+                let scheme = property.value.get(..7).filter(|scheme| scheme.eq_ignore_ascii_case("mailto:"))?;
+                Some(property.value[scheme.len()..].trim().to_string())
+            })
+            .collect(),
+    })
+}
+
+/// A `DTSTART`/`DTEND` value and whether it is a whole day.
+fn time(
+    property: &Property,
+    default_zone: &Timezone,
+    zones: &mut HashMap<String, Timezone>,
+) -> Option<(DateTime<Utc>, bool)> {
+    let (naive, zone, all_day) = local_time(property, default_zone, zones)?;
+    Some((to_utc(naive, &zone), all_day))
+}
+
+/// A date-time value as the wall clock time in its zone.
+fn local_time(
+    property: &Property,
+    default_zone: &Timezone,
+    zones: &mut HashMap<String, Timezone>,
+) -> Option<(NaiveDateTime, Timezone, bool)> {
+    let value = property.value.trim();
+    if let Some(utc) = value.strip_suffix('Z') {
+        let naive = NaiveDateTime::parse_from_str(utc, "%Y%m%dT%H%M%S").ok()?;
+        return Some((naive, Timezone::Utc, false));
+    }
+    
+    let zone = match property.parameters.iter().find(|(name, _)| name == "TZID") {
+        Some((_, tzid)) => zones.entry(tzid.clone())
+            .or_insert_with(|| Timezone::parse(tzid).unwrap_or_else(|_| default_zone.clone()))
+            .clone(),
+        None => default_zone.clone(),
+    };
+    let (naive, all_day) = match NaiveDateTime::parse_from_str(value, "%Y%m%dT%H%M%S") {
+        Ok(naive) => (naive, false),
+        Err(_) => (NaiveDate::parse_from_str(value, "%Y%m%d").ok()?.and_hms_opt(0, 0, 0)?, true),
+    };
+    Some((naive, zone, all_day))
+}
+
+fn to_utc(naive: NaiveDateTime, zone: &Timezone) -> DateTime<Utc> {
+    // The offset at the local time read as UTC is right except within
+    // hours of a DST change
+    let guess = Utc.from_utc_datetime(&naive);
+    guess - Duration::seconds(zone.offset_at(guess).local_minus_utc() as i64)
+}
+
+/// Splits `NAME;PARAM=value:VALUE`, ignoring colons in quoted parameters.
+fn property(line: &str) -> Option<Property> {
+    let mut quoted = false;
+    let colon = line.char_indices().find_map(|(index, c)| {
+        match c {
+            '"' => quoted = !quoted,
+            ':' if !quoted => return Some(index),
+            _ => {}
+        }
+        None
+    })?;
+    
+    let mut head = line[..colon].split(';');
+    let name = head.next()?.trim().to_uppercase();
+    let parameters = head
+        .filter_map(|parameter| parameter.split_once('='))
+        .map(|(key, value)| (key.trim().to_uppercase(), value.trim().trim_matches('"').to_string()))
+        .collect();
+    Some(Property { name, parameters, value: line[colon + 1..].to_string() })
+}
+
+fn unescape(value: &str) -> String {
+    let mut text = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            text.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('n' | 'N') => text.push('\n'),
+            Some(escaped) => text.push(escaped),
+            None => {}
+        }
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn calendar(events: &str) -> String {
+        format!("BEGIN:VCALENDAR\r\n{}END:VCALENDAR\r\n", events)
+    }
+    
+    fn at(text: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(text).unwrap().with_timezone(&Utc)
+    }
+    
+    fn starts(events: &[CalendarEvent]) -> Vec<String> {
+        events.iter().map(|event| event.start.format("%Y-%m-%d %H:%M").to_string()).collect()
+    }
+    
+    #[test]
+    fn expands_weekly_rules_from_now() {
+        let text = calendar("BEGIN:VEVENT\r\nUID:a\r\nSUMMARY:ops@example.com\r\nDTSTART:20240101T090000Z\r\nDTEND:20240101T170000Z\r\nRRULE:FREQ=WEEKLY;BYDAY=MO,WE\r\nEND:VEVENT\r\n");
+        let events = parse(&text, &Timezone::Utc, at("2024-03-06T10:00:00Z"));
+        assert_eq!(starts(&events[..3]), ["2024-03-06 09:00", "2024-03-11 09:00", "2024-03-13 09:00"]);
+        assert!(events.iter().all(|event| event.end - event.start == Duration::hours(8)));
+        assert!(events.last().unwrap().start < at("2024-06-04T10:00:00Z"));
+    }
+    
+    #[test]
+    fn honours_count_until_exdate_and_modified_occurrences() {
+        let text = calendar(concat!(
+            "BEGIN:VEVENT\r\nUID:a\r\nSUMMARY:db\r\nDTSTART:20240101T020000Z\r\nDTEND:20240101T030000Z\r\n",
+            "RRULE:FREQ=DAILY;COUNT=5\r\nEXDATE:20240102T020000Z\r\nEND:VEVENT\r\n",
+            "BEGIN:VEVENT\r\nUID:a\r\nSUMMARY:db moved\r\nRECURRENCE-ID:20240103T020000Z\r\n",
+            "DTSTART:20240103T040000Z\r\nDTEND:20240103T050000Z\r\nEND:VEVENT\r\n",
+        ));
+        let events = parse(&text, &Timezone::Utc, at("2023-12-01T00:00:00Z"));
+        assert_eq!(starts(&events), ["2024-01-01 02:00", "2024-01-04 02:00", "2024-01-05 02:00", "2024-01-03 04:00"]);
+        
+        let text = calendar("BEGIN:VEVENT\r\nSUMMARY:backup\r\nDTSTART;VALUE=DATE:20240131\r\nRRULE:FREQ=MONTHLY;UNTIL=20240331\r\nEND:VEVENT\r\n");
+        let events = parse(&text, &Timezone::Utc, at("2024-01-15T00:00:00Z"));
+        assert_eq!(starts(&events), ["2024-01-31 00:00", "2024-03-31 00:00"]);
+    }
+    
+    #[test]
+    fn expands_floating_times_in_the_default_zone() {
+        let zone = Timezone::parse("-05:00").unwrap();
+        let text = calendar("BEGIN:VEVENT\r\nSUMMARY:standup\r\nDTSTART:20240101T090000\r\nDTEND:20240101T091500\r\nRRULE:FREQ=DAILY;INTERVAL=2;COUNT=2\r\nEND:VEVENT\r\n");
+        let events = parse(&text, &zone, at("2023-12-01T00:00:00Z"));
+        assert_eq!(starts(&events), ["2024-01-01 14:00", "2024-01-03 14:00"]);
+    }
+    
+    #[test]
+    fn keeps_the_first_occurrence_of_unsupported_rules() {
+        let text = calendar("BEGIN:VEVENT\r\nSUMMARY:patch\r\nDTSTART:20240109T020000Z\r\nDTEND:20240109T030000Z\r\nRRULE:FREQ=MONTHLY;BYDAY=2TU\r\nEND:VEVENT\r\n");
+        let events = parse(&text, &Timezone::Utc, at("2024-06-01T00:00:00Z"));
+        assert_eq!(starts(&events), ["2024-01-09 02:00"]);
+    }
+}
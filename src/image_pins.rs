@@ -0,0 +1,189 @@
+use crate::config::ImagePinConfig;
+use crate::docker_monitor::{ContainerStats, DockerMonitor};
+use log::warn;
+
+/// A running container whose image does not match its pin.
+#[derive(Debug, Clone)]
+pub struct ImageDrift {
+    pub container: ContainerStats,
+    pub expected: String,
+}
+
+impl ImageDrift {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: running {} ({}), expected {}",
+            self.container.name, self.container.image, short_id(&self.container.image_id), self.expected
+        )
+    }
+}
+
+/// Pins compiled from the config. Keys are container names or glob patterns,
+/// an exact name takes precedence over a pattern.
+pub struct ImagePins {
+    pins: Vec<(glob::Pattern, String)>,
+}
+
+impl ImagePins {
+    pub fn new(config: &ImagePinConfig) -> Self {
+        let mut pins: Vec<(glob::Pattern, String)> = config.containers.iter()
+            .filter_map(|(name, expected)| match glob::Pattern::new(name) {
+                Ok(pattern) => Some((pattern, expected.clone())),
+                Err(e) => {
+                    warn!("Ignoring invalid image pin pattern '{}': {}", name, e);
+                    None
+                }
+            })
+            .collect();
+        // Patterns without wildcards first so exact names win
+        pins.sort_by_key(|(pattern, _)| (pattern.as_str().contains(['*', '?', '[']), pattern.as_str().to_string()));
+        
+        Self { pins }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.pins.is_empty()
+    }
+    
+    fn expected_for(&self, name: &str) -> Option<&str> {
+        self.pins.iter()
+            .find(|(pattern, _)| pattern.matches(name))
+            .map(|(_, expected)| expected.as_str())
+    }
+    
+    /// Pinned containers running a different image. Digest pins are compared
+    /// with the image ID and the repo digests of the local image.
+    pub async fn drift(&self, docker: &DockerMonitor, containers: &[ContainerStats]) -> Vec<ImageDrift> {
+        let mut drifted = Vec::new();
+        
+        for container in containers {
+            let Some(expected) = self.expected_for(&container.name) else {
+                continue;
+            };
+            
+            // Without image access repo digests are unknown and the pin cannot be judged
+            if expected.contains("@sha256:") && !docker.capabilities().images {
+                continue;
+            }
+            let matches = if expected.starts_with("sha256:") || expected.contains("@sha256:") {
+                // A failed inspect says nothing about drift, the next cycle retries
+                let digests = match docker.image_repo_digests(&container.image_id).await {
+                    Ok(digests) => digests,
+                    Err(e) => {
+                        warn!("Skipping image pin of {}, failed to inspect image {}: {}", container.name, container.image_id, e);
+                        continue;
+                    }
+                };
+                matches_digest(expected, container, &digests)
+            } else {
+                normalize_reference(&container.image) == normalize_reference(expected)
+            };
+            
+            if !matches {
+                drifted.push(ImageDrift {
+                    container: container.clone(),
+                    expected: expected.to_string(),
+                });
+            }
+        }
+        
+        drifted
+    }
+}
+
+fn matches_digest(expected: &str, container: &ContainerStats, repo_digests: &[String]) -> bool {
+    match expected.split_once('@') {
+        // repository@sha256:... must match a repo digest of the running image
+        Some(_) => {
+            let expected = normalize_reference(expected);
+            normalize_reference(&container.image) == expected
+                || repo_digests.iter().any(|digest| normalize_reference(digest) == expected)
+        }
+        // A bare digest matches either the image ID or any repo digest
+        None => {
+            container.image_id == expected
+                || repo_digests.iter().any(|digest| digest.rsplit('@').next() == Some(expected))
+        }
+    }
+}
+
+/// Normalizes `nginx` to `nginx:latest` and drops the implicit Docker Hub
+/// registry and `library/` namespace, so equivalent references compare equal.
+/// Digest references keep only `repository@digest`, since repo digests carry no tag.
+fn normalize_reference(reference: &str) -> String {
+    let reference = reference.trim_start_matches("docker.io/").trim_start_matches("library/");
+    let has_tag = |repository: &str| repository.rsplit('/').next().is_some_and(|name| name.contains(':'));
+    
+    if let Some((repository, digest)) = reference.split_once('@') {
+        let repository = if has_tag(repository) {
+            repository.rsplit_once(':').map_or(repository, |(name, _)| name)
+        } else {
+            repository
+        };
+        return format!("{}@{}", repository, digest);
+    }
+    
+    if has_tag(reference) {
+        reference.to_string()
+    } else {
+        format!("{}:latest", reference)
+    }
+}
+
+fn short_id(image_id: &str) -> String {
+    let id = image_id.trim_start_matches("sha256:");
+    if id.is_empty() {
+        "unknown id".to_string()
+    } else {
+        format!("sha256:{}", id.chars().take(12).collect::<String>())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn container(image: &str, image_id: &str) -> ContainerStats {
+        ContainerStats { image_id: image_id.to_string(), ..ContainerStats::for_test("web", image) }
+    }
+    
+    #[test]
+    fn normalizes_implicit_registry_namespace_and_tag() {
+        assert_eq!(normalize_reference("nginx"), "nginx:latest");
+        assert_eq!(normalize_reference("library/nginx"), "nginx:latest");
+        assert_eq!(normalize_reference("docker.io/library/nginx:1.25"), "nginx:1.25");
+        assert_eq!(normalize_reference("docker.io/grafana/grafana"), "grafana/grafana:latest");
+        // A port belongs to the registry, not the tag
+        assert_eq!(normalize_reference("registry:5000/app"), "registry:5000/app:latest");
+        assert_eq!(normalize_reference("registry:5000/app:2"), "registry:5000/app:2");
+        // Other registries keep their library namespace
+        assert_eq!(normalize_reference("ghcr.io/library/app"), "ghcr.io/library/app:latest");
+    }
+    
+    #[test]
+    fn digest_references_drop_the_tag() {
+        assert_eq!(normalize_reference("nginx:1.25@sha256:abc"), "nginx@sha256:abc");
+        assert_eq!(normalize_reference("docker.io/library/nginx@sha256:abc"), "nginx@sha256:abc");
+        assert_eq!(normalize_reference("registry:5000/app@sha256:abc"), "registry:5000/app@sha256:abc");
+    }
+    
+    #[test]
+    fn bare_digests_match_the_image_id_or_a_repo_digest() {
+        let running = container("nginx:1.25", "sha256:aaa");
+        assert!(matches_digest("sha256:aaa", &running, &[]));
+        assert!(matches_digest("sha256:bbb", &running, &["nginx@sha256:bbb".to_string()]));
+        assert!(!matches_digest("sha256:ccc", &running, &["nginx@sha256:bbb".to_string()]));
+    }
+    
+    #[test]
+    fn repository_digests_must_name_the_same_repository() {
+        let running = container("nginx:1.25", "sha256:aaa");
+        let digests = ["docker.io/library/nginx@sha256:bbb".to_string()];
+        assert!(matches_digest("nginx@sha256:bbb", &running, &digests));
+        assert!(matches_digest("library/nginx:1.25@sha256:bbb", &running, &digests));
+        assert!(!matches_digest("httpd@sha256:bbb", &running, &digests));
+        assert!(!matches_digest("nginx@sha256:ccc", &running, &digests));
+        // A container started by digest matches without inspecting the image
+        assert!(matches_digest("nginx@sha256:ddd", &container("nginx@sha256:ddd", "sha256:aaa"), &[]));
+    }
+}
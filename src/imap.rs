@@ -0,0 +1,123 @@
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio_native_tls::TlsStream;
+use crate::config::NetworkConfig;
+use crate::network;
+use anyhow::{Result, anyhow};
+
+/// One untagged response line, with the literal that followed it, if any.
+struct ResponseLine {
+    line: String,
+    literal: Option<Vec<u8>>,
+}
+
+/// Just enough IMAP4rev1 over implicit TLS to read new messages: login,
+/// search unseen, fetch and flag them as seen.
+pub struct ImapSession {
+    stream: BufReader<TlsStream<TcpStream>>,
+    tag: u32,
+}
+
+impl ImapSession {
+    pub async fn connect(host: &str, port: u16, network: &NetworkConfig) -> Result<Self> {
+        let connector = tokio_native_tls::TlsConnector::from(native_tls::TlsConnector::new()?);
+        let stream = network::connect(host, port, network).await?;
+        let tls = connector.connect(host, stream).await?;
+        
+        let mut session = Self { stream: BufReader::new(tls), tag: 0 };
+        let greeting = session.read_line().await?;
+        if !greeting.starts_with("* OK") && !greeting.starts_with("* PREAUTH") {
+            return Err(anyhow!("unexpected IMAP greeting '{}'", greeting.trim()));
+        }
+        Ok(session)
+    }
+    
+    pub async fn login(&mut self, username: &str, password: &str) -> Result<()> {
+        self.command(&format!("LOGIN {} {}", quote(username), quote(password))).await?;
+        Ok(())
+    }
+    
+    pub async fn select(&mut self, mailbox: &str) -> Result<()> {
+        self.command(&format!("SELECT {}", quote(mailbox))).await?;
+        Ok(())
+    }
+    
+    /// UIDs of the messages not flagged `\Seen`.
+    pub async fn unseen(&mut self) -> Result<Vec<u32>> {
+        let responses = self.command("UID SEARCH UNSEEN").await?;
+        Ok(responses.iter()
+            .filter_map(|response| response.line.strip_prefix("* SEARCH"))
+            .flat_map(|uids| uids.split_whitespace().filter_map(|uid| uid.parse().ok()))
+            .collect())
+    }
+    
+    /// The raw message, without setting `\Seen`.
+    pub async fn fetch(&mut self, uid: u32) -> Result<Vec<u8>> {
+        let responses = self.command(&format!("UID FETCH {} BODY.PEEK[]", uid)).await?;
+        responses.into_iter()
+            .find_map(|response| response.literal)
+            .ok_or_else(|| anyhow!("message {} has no body", uid))
+    }
+    
+    pub async fn mark_seen(&mut self, uid: u32) -> Result<()> {
+        self.command(&format!("UID STORE {} +FLAGS.SILENT (\\Seen)", uid)).await?;
+        Ok(())
+    }
+    
+    pub async fn logout(mut self) {
+        let _ = self.command("LOGOUT").await;
+    }
+    
+    /// Sends a tagged command and collects the untagged responses up to its
+    /// completion, failing unless it completes with `OK`.
+    async fn command(&mut self, command: &str) -> Result<Vec<ResponseLine>> {
+        self.tag += 1;
+        let tag = format!("A{}", self.tag);
+        self.stream.get_mut().write_all(format!("{} {}\r\n", tag, command).as_bytes()).await?;
+        self.stream.get_mut().flush().await?;
+        
+        let mut responses = Vec::new();
+        loop {
+            let line = self.read_line().await?;
+            if let Some(status) = line.strip_prefix(&format!("{} ", tag)) {
+                if status.starts_with("OK") {
+                    return Ok(responses);
+                }
+                // Never echo the LOGIN arguments back into logs
+                let verb = command.split_whitespace().next().unwrap_or_default();
+                return Err(anyhow!("IMAP {} failed: {}", verb, status.trim()));
+            }
+            
+            let literal = match literal_length(&line) {
+                Some(length) => {
+                    let mut literal = vec![0; length];
+                    self.stream.read_exact(&mut literal).await?;
+                    // The rest of the response after the literal, e.g. ")"
+                    self.read_line().await?;
+                    Some(literal)
+                }
+                None => None,
+            };
+            responses.push(ResponseLine { line, literal });
+        }
+    }
+    
+    async fn read_line(&mut self) -> Result<String> {
+        let mut line = String::new();
+        if self.stream.read_line(&mut line).await? == 0 {
+            return Err(anyhow!("IMAP server closed the connection"));
+        }
+        Ok(line)
+    }
+}
+
+/// Length of the literal announced at the end of a line, e.g. `{1234}`.
+fn literal_length(line: &str) -> Option<usize> {
+    let line = line.trim_end().strip_suffix('}')?;
+    let start = line.rfind('{')?;
+    line[start + 1..].parse().ok()
+}
+
+fn quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('\\', "\\\\").replace('"', "\\\""))
+}
\ No newline at end of file
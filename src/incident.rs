@@ -0,0 +1,220 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::VecDeque;
+use crate::config::IncidentConfig;
+use crate::escalation::{Acknowledgement, EscalationStep};
+use crate::ticket::Ticket;
+
+/// Lifecycle of a rule: OK → PENDING → FIRING → RESOLVED, or FLAPPING while
+/// the rule toggles too often to be trusted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum IncidentState {
+    Ok,
+    Pending,
+    #[default]
+    Firing,
+    Resolved,
+    Flapping,
+}
+
+impl IncidentState {
+    pub fn label(&self) -> &'static str {
+        match self {
+            IncidentState::Ok => "OK",
+            IncidentState::Pending => "PENDING",
+            IncidentState::Firing => "FIRING",
+            IncidentState::Resolved => "RESOLVED",
+            IncidentState::Flapping => "FLAPPING",
+        }
+    }
+}
+
+/// What happened to a rule in one check, deciding what gets notified.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Transition {
+    /// Nothing to notify.
+    None,
+    /// Alerting, waiting for `pending_checks` consecutive checks.
+    Pending,
+    /// Just started firing.
+    Fired,
+    /// Still firing.
+    Repeat,
+    Resolved,
+    FlappingStarted,
+    FlappingStopped,
+}
+
+/// Persisted state of one rule.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleState {
+    pub state: IncidentState,
+    pub since: DateTime<Utc>,
+    #[serde(default)]
+    pub consecutive: u32,
+    /// Alerting flag of the most recent checks, oldest first.
+    #[serde(default)]
+    pub history: VecDeque<bool>,
+    /// Escalation tiers notified since the rule started firing.
+    #[serde(default)]
+    pub escalations: Vec<EscalationStep>,
+    #[serde(default)]
+    pub acknowledged: Option<Acknowledgement>,
+    /// Issue opened about the incident, kept until it is resolved there.
+    #[serde(default)]
+    pub ticket: Option<Ticket>,
+}
+
+impl Default for RuleState {
+    fn default() -> Self {
+        Self {
+            state: IncidentState::Ok,
+            since: Utc::now(),
+            consecutive: 0,
+            history: VecDeque::new(),
+            escalations: Vec::new(),
+            acknowledged: None,
+            ticket: None,
+        }
+    }
+}
+
+impl RuleState {
+    /// Percentage of state changes across the recorded history.
+    pub fn percent_state_change(&self) -> f64 {
+        if self.history.len() < 2 {
+            return 0.0;
+        }
+        
+        let changes = self.history.iter()
+            .zip(self.history.iter().skip(1))
+            .filter(|(a, b)| a != b)
+            .count();
+        changes as f64 * 100.0 / (self.history.len() - 1) as f64
+    }
+    
+    /// Feeds the result of one check and returns the resulting transition.
+    pub fn observe(&mut self, alerting: bool, config: &IncidentConfig) -> Transition {
+        self.history.push_back(alerting);
+        while self.history.len() > config.flap_history.max(2) {
+            self.history.pop_front();
+        }
+        self.consecutive = if alerting { self.consecutive + 1 } else { 0 };
+        
+        let change = self.percent_state_change();
+        let flap_detection = config.flap_high_threshold > 0.0 && self.history.len() >= config.flap_history.max(2);
+        
+        if self.state == IncidentState::Flapping {
+            if change < config.flap_low_threshold {
+                self.set(if alerting { IncidentState::Firing } else { IncidentState::Ok });
+                return Transition::FlappingStopped;
+            }
+            return Transition::None;
+        }
+        
+        if flap_detection && change >= config.flap_high_threshold {
+            self.set(IncidentState::Flapping);
+            return Transition::FlappingStarted;
+        }
+        
+        match (self.state, alerting) {
+            (IncidentState::Firing, true) => Transition::Repeat,
+            (IncidentState::Firing, false) => {
+                self.set(IncidentState::Resolved);
+                Transition::Resolved
+            }
+            (_, true) if self.consecutive >= config.pending_checks.max(1) => {
+                self.set(IncidentState::Firing);
+                Transition::Fired
+            }
+            (_, true) => {
+                self.set(IncidentState::Pending);
+                Transition::Pending
+            }
+            (IncidentState::Ok, false) => Transition::None,
+            (_, false) => {
+                self.set(IncidentState::Ok);
+                Transition::None
+            }
+        }
+    }
+    
+    fn set(&mut self, state: IncidentState) {
+        if self.state != state {
+            self.state = state;
+            self.since = Utc::now();
+            // Flapping holds the incident open, any other change ends it
+            if state != IncidentState::Flapping {
+                self.escalations.clear();
+                self.acknowledged = None;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn config() -> IncidentConfig {
+        IncidentConfig {
+            pending_checks: 2,
+            flap_history: 6,
+            flap_high_threshold: 50.0,
+            flap_low_threshold: 25.0,
+            notify_resolved: true,
+        }
+    }
+    
+    fn observe_all(rule: &mut RuleState, checks: &[bool], config: &IncidentConfig) -> Vec<Transition> {
+        checks.iter().map(|alerting| rule.observe(*alerting, config)).collect()
+    }
+    
+    #[test]
+    fn fires_after_pending_checks_and_resolves() {
+        let mut rule = RuleState::default();
+        let transitions = observe_all(&mut rule, &[true, true, true, false, false], &config());
+        assert_eq!(transitions, [Transition::Pending, Transition::Fired, Transition::Repeat, Transition::Resolved, Transition::None]);
+        assert_eq!(rule.state, IncidentState::Ok);
+        assert_eq!(rule.consecutive, 0);
+    }
+    
+    #[test]
+    fn a_single_alerting_check_stays_pending() {
+        let mut rule = RuleState::default();
+        assert_eq!(observe_all(&mut rule, &[true, false], &config()), [Transition::Pending, Transition::None]);
+        assert_eq!(rule.state, IncidentState::Ok);
+        
+        let immediate = IncidentConfig { pending_checks: 0, ..config() };
+        assert_eq!(RuleState::default().observe(true, &immediate), Transition::Fired);
+    }
+    
+    #[test]
+    fn flaps_until_the_history_settles() {
+        let mut rule = RuleState::default();
+        let transitions = observe_all(&mut rule, &[true, false, true, false, true, false], &config());
+        assert_eq!(transitions.last(), Some(&Transition::FlappingStarted));
+        assert_eq!(rule.state, IncidentState::Flapping);
+        assert_eq!(rule.percent_state_change(), 100.0);
+        
+        // 80%, 60%, 40%, then 20% drops below flap_low_threshold
+        let transitions = observe_all(&mut rule, &[true, true, true, true, true], &config());
+        assert_eq!(transitions, [Transition::None, Transition::None, Transition::None, Transition::None, Transition::FlappingStopped]);
+        assert_eq!(rule.state, IncidentState::Firing);
+        assert_eq!(rule.observe(true, &config()), Transition::Repeat);
+    }
+    
+    #[test]
+    fn flap_detection_needs_a_full_history_and_a_threshold() {
+        let mut rule = RuleState::default();
+        let transitions = observe_all(&mut rule, &[true, false, true, false, true], &config());
+        assert!(!transitions.contains(&Transition::FlappingStarted));
+        
+        let disabled = IncidentConfig { flap_high_threshold: 0.0, ..config() };
+        let mut rule = RuleState::default();
+        let transitions = observe_all(&mut rule, &[true, false, true, false, true, false, true, false], &disabled);
+        assert!(!transitions.contains(&Transition::FlappingStarted));
+        assert_eq!(rule.history.len(), 6);
+    }
+}
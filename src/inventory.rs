@@ -0,0 +1,214 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::{HashMap, HashSet};
+use crate::digest::DigestSection;
+use crate::docker_monitor::ContainerStats;
+use crate::timezone::Timezone;
+
+/// A running container as last seen by the monitor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryEntry {
+    pub image: String,
+    pub image_id: String,
+    pub first_seen: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ChangeKind {
+    Started,
+    Stopped,
+    ImageChanged,
+}
+
+/// A difference in the set of running containers between two cycles.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InventoryChange {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ChangeKind,
+    pub name: String,
+    pub image: String,
+    #[serde(default)]
+    pub previous_image: Option<String>,
+}
+
+impl InventoryChange {
+    pub fn describe(&self, timezone: &Timezone) -> String {
+        let when = timezone.format(self.timestamp);
+        match self.kind {
+            ChangeKind::Started => format!("{} started at {} (image {})", self.name, when, self.image),
+            ChangeKind::Stopped => format!("{} stopped at {} (image {})", self.name, when, self.image),
+            ChangeKind::ImageChanged => format!(
+                "{} changed image at {} ({} → {})",
+                self.name, when, self.previous_image.as_deref().unwrap_or("unknown"), self.image
+            ),
+        }
+    }
+}
+
+/// Compares the running containers with the previous inventory, updating it in
+/// place. The first cycle only records the inventory without reporting changes.
+pub fn update(inventory: &mut Option<HashMap<String, InventoryEntry>>, containers: &[ContainerStats]) -> Vec<InventoryChange> {
+    let now = Utc::now();
+    let Some(entries) = inventory.as_mut() else {
+        let current = containers.iter()
+            .map(|container| {
+                (container.name.clone(), InventoryEntry {
+                    image: container.image.clone(),
+                    image_id: container.image_id.clone(),
+                    first_seen: now,
+                })
+            })
+            .collect();
+        *inventory = Some(current);
+        return Vec::new();
+    };
+    
+    // Entries are updated where they are, so an unchanged fleet allocates
+    // nothing beyond the set of running names.
+    let mut changes = Vec::new();
+    for container in containers {
+        match entries.get_mut(&container.name) {
+            None => {
+                changes.push(InventoryChange {
+                    timestamp: now,
+                    kind: ChangeKind::Started,
+                    name: container.name.clone(),
+                    image: container.image.clone(),
+                    previous_image: None,
+                });
+                entries.insert(container.name.clone(), InventoryEntry {
+                    image: container.image.clone(),
+                    image_id: container.image_id.clone(),
+                    first_seen: now,
+                });
+            }
+            Some(entry) if entry.image_id != container.image_id || entry.image != container.image => {
+                let previous_image = std::mem::replace(&mut entry.image, container.image.clone());
+                entry.image_id.clone_from(&container.image_id);
+                entry.first_seen = now;
+                changes.push(InventoryChange {
+                    timestamp: now,
+                    kind: ChangeKind::ImageChanged,
+                    name: container.name.clone(),
+                    image: container.image.clone(),
+                    previous_image: Some(previous_image),
+                });
+            }
+            Some(_) => {}
+        }
+    }
+    
+    // Every running container has an entry by now, so any extra one stopped
+    if entries.len() > containers.len() {
+        let running: HashSet<&str> = containers.iter().map(|container| container.name.as_str()).collect();
+        entries.retain(|name, entry| {
+            if running.contains(name.as_str()) {
+                return true;
+            }
+            changes.push(InventoryChange {
+                timestamp: now,
+                kind: ChangeKind::Stopped,
+                name: name.clone(),
+                image: std::mem::take(&mut entry.image),
+                previous_image: None,
+            });
+            false
+        });
+    }
+    
+    changes.sort_by(|a, b| a.name.cmp(&b.name));
+    changes
+}
+
+/// Builds the inventory changes digest section.
+pub fn changes_section(changes: &[InventoryChange], timezone: &Timezone) -> DigestSection {
+    let mut html = String::new();
+    
+    if changes.is_empty() {
+        html.push_str("<p>No containers were started, stopped or changed image.</p>");
+    }
+    for (kind, label) in [
+        (ChangeKind::Started, "Newly started"),
+        (ChangeKind::Stopped, "Stopped"),
+        (ChangeKind::ImageChanged, "Image changed"),
+    ] {
+        let items: String = changes.iter()
+            .filter(|change| change.kind == kind)
+            .map(|change| format!("<li>{}</li>", change.describe(timezone)))
+            .collect();
+        if !items.is_empty() {
+            html.push_str(&format!("<p><strong>{}:</strong></p><ul>{}</ul>", label, items));
+        }
+    }
+    
+    DigestSection {
+        title: "📦 Container Inventory Changes".to_string(),
+        html,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn container(name: &str, image: &str, image_id: &str) -> ContainerStats {
+        ContainerStats { image_id: image_id.to_string(), ..ContainerStats::for_test(name, image) }
+    }
+    
+    fn summary(changes: &[InventoryChange]) -> Vec<(ChangeKind, &str, &str, Option<&str>)> {
+        changes.iter()
+            .map(|change| (change.kind, change.name.as_str(), change.image.as_str(), change.previous_image.as_deref()))
+            .collect()
+    }
+    
+    #[test]
+    fn the_first_cycle_only_records() {
+        let mut inventory = None;
+        assert!(update(&mut inventory, &[container("api", "api:1", "sha256:a")]).is_empty());
+        assert_eq!(inventory.as_ref().unwrap()["api"].image, "api:1");
+        assert!(update(&mut inventory, &[container("api", "api:1", "sha256:a")]).is_empty());
+    }
+    
+    #[test]
+    fn reports_started_stopped_and_changed_containers() {
+        let mut inventory = None;
+        update(&mut inventory, &[
+            container("api", "api:1", "sha256:a"),
+            container("db", "postgres:16", "sha256:b"),
+            container("web", "nginx:1.27", "sha256:c"),
+            container("worker", "api:1", "sha256:a"),
+        ]);
+        let first_seen = inventory.as_ref().unwrap()["web"].first_seen;
+        
+        let changes = update(&mut inventory, &[
+            container("api", "api:2", "sha256:d"),
+            container("cache", "redis:7", "sha256:e"),
+            container("web", "nginx:1.27", "sha256:f"),
+            container("worker", "api:1", "sha256:a"),
+        ]);
+        assert_eq!(summary(&changes), [
+            (ChangeKind::ImageChanged, "api", "api:2", Some("api:1")),
+            (ChangeKind::Started, "cache", "redis:7", None),
+            (ChangeKind::Stopped, "db", "postgres:16", None),
+            (ChangeKind::ImageChanged, "web", "nginx:1.27", Some("nginx:1.27")),
+        ]);
+        
+        let entries = inventory.as_ref().unwrap();
+        assert_eq!(entries.len(), 4);
+        assert!(!entries.contains_key("db"));
+        assert_eq!(entries["web"].image_id, "sha256:f");
+        assert!(entries["web"].first_seen >= first_seen);
+    }
+    
+    #[test]
+    fn a_stopped_container_starts_again_as_new() {
+        let mut inventory = None;
+        update(&mut inventory, &[container("api", "api:1", "sha256:a")]);
+        assert_eq!(summary(&update(&mut inventory, &[])), [(ChangeKind::Stopped, "api", "api:1", None)]);
+        assert_eq!(
+            summary(&update(&mut inventory, &[container("api", "api:1", "sha256:a")])),
+            [(ChangeKind::Started, "api", "api:1", None)],
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use serde::Serialize;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::sync::OnceLock;
+use sysinfo::{System, SystemExt};
+use crate::config::KubernetesConfig;
+
+const SERVICE_ACCOUNT_DIR: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// The pod the monitor runs in, e.g. as a DaemonSet. Taken from the downward
+/// API variables the Helm chart sets (`NODE_NAME`, `POD_NAME`,
+/// `POD_NAMESPACE`, `POD_IP`).
+#[derive(Debug, Clone, Serialize)]
+pub struct ClusterMetadata {
+    pub node: String,
+    pub namespace: String,
+    pub pod: String,
+    pub pod_ip: Option<String>,
+    /// Where the node's root filesystem is mounted, if it is.
+    pub host_root: Option<String>,
+}
+
+static CLUSTER: OnceLock<Option<ClusterMetadata>> = OnceLock::new();
+
+/// Detects in-cluster mode from the `kubernetes` config. Only the first call
+/// has an effect; call it before anything reads `hostname()`.
+pub fn configure(config: &KubernetesConfig) {
+    let _ = CLUSTER.set(detect(config));
+}
+
+/// The pod metadata when running in a cluster.
+pub fn current() -> Option<&'static ClusterMetadata> {
+    CLUSTER.get().and_then(Option::as_ref)
+}
+
+/// The name alerts and metrics are labelled with: the node in a cluster,
+/// where the pod's own hostname is a generated name, the host name otherwise.
+pub fn hostname() -> String {
+    match current() {
+        Some(cluster) => cluster.node.clone(),
+        None => System::new().host_name().unwrap_or_else(|| "unknown".to_string()),
+    }
+}
+
+/// In a pod when the API server variables or the service account are there.
+/// Without the node name from the downward API there is nothing to label
+/// with, so that counts as not in a cluster.
+fn detect(config: &KubernetesConfig) -> Option<ClusterMetadata> {
+    if !config.detect {
+        return None;
+    }
+    let in_pod = env::var_os("KUBERNETES_SERVICE_HOST").is_some() || Path::new(SERVICE_ACCOUNT_DIR).exists();
+    if !in_pod {
+        return None;
+    }
+    let var = |name: &str| env::var(name).ok().filter(|value| !value.is_empty());
+    
+    let node = var(&config.node_name_env)?;
+    let namespace = var("POD_NAMESPACE")
+        .or_else(|| fs::read_to_string(Path::new(SERVICE_ACCOUNT_DIR).join("namespace")).ok().map(|namespace| namespace.trim().to_string()))
+        .unwrap_or_else(|| "default".to_string());
+    let pod = var("POD_NAME")
+        .or_else(|| var("HOSTNAME"))
+        .unwrap_or_else(|| "unknown".to_string());
+    let host_root = Some(config.host_root.clone()).filter(|root| !root.is_empty() && Path::new(root).is_dir());
+    
+    Some(ClusterMetadata {
+        node,
+        namespace,
+        pod,
+        pod_ip: var("POD_IP"),
+        host_root,
+    })
+}
\ No newline at end of file
@@ -0,0 +1,442 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+use crate::config::Config;
+use crate::kubernetes;
+use anyhow::{Result, anyhow};
+use log::{debug, error, info, warn};
+
+/// Who leads a namespace and until when, unless renewed.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Lease {
+    pub holder: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+impl Lease {
+    pub fn describe(&self) -> String {
+        format!("{} until {}", self.holder, self.expires_at.format("%Y-%m-%d %H:%M:%S UTC"))
+    }
+}
+
+/// Where the lease of a namespace is kept. Taking it must be atomic, so two
+/// replicas never both get it.
+pub trait LeaseStore: Send {
+    /// Takes or renews the lease for `holder` unless another holder's lease
+    /// is still valid. Returns the lease in force afterwards.
+    fn acquire(&mut self, holder: &str, duration: Duration) -> Result<Lease>;
+    /// Gives the lease up if `holder` has it, so a standby takes over at its
+    /// next attempt instead of after the lease runs out.
+    fn release(&mut self, holder: &str) -> Result<()>;
+    /// The lease in force, `None` when nobody holds one.
+    fn current(&mut self) -> Result<Option<Lease>>;
+    fn describe(&self) -> String;
+}
+
+/// The backend selected by `ha.backend`.
+pub fn open(config: &Config) -> Result<Box<dyn LeaseStore>> {
+    match config.ha.backend.as_str() {
+        "file" => Ok(Box::new(FileLease::new(&config.ha.lock_file)?)),
+        "postgres" => open_postgres(config),
+        other => Err(anyhow!("Unknown ha backend '{}', expected postgres or file", other)),
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn open_postgres(config: &Config) -> Result<Box<dyn LeaseStore>> {
+    Ok(Box::new(postgres::PostgresLease::connect(&config.storage.url, &config.storage.namespace)?))
+}
+
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(_config: &Config) -> Result<Box<dyn LeaseStore>> {
+    Err(anyhow!("this build has no PostgreSQL support, rebuild with `--features postgres`"))
+}
+
+/// `ha.instance`, or else the pod name in a cluster and `<hostname>-<pid>`
+/// elsewhere, so two replicas on one host do not share a name.
+pub fn instance(config: &Config) -> String {
+    if !config.ha.instance.is_empty() {
+        return config.ha.instance.clone();
+    }
+    match kubernetes::current() {
+        Some(cluster) => cluster.pod.clone(),
+        None => format!("{}-{}", kubernetes::hostname(), std::process::id()),
+    }
+}
+
+/// `ha.lease_secs`, three check intervals when 0.
+pub fn lease_duration(config: &Config) -> Duration {
+    match config.ha.lease_secs {
+        0 => Duration::from_secs(config.monitoring.check_interval.max(1) * 3),
+        secs => Duration::from_secs(secs),
+    }
+}
+
+/// A lease kept as JSON in a file on storage the replicas share. Each
+/// attempt holds an exclusive lock on the file while it reads and writes.
+pub struct FileLease {
+    path: String,
+}
+
+impl FileLease {
+    pub fn new(path: &str) -> Result<Self> {
+        if path.is_empty() {
+            return Err(anyhow!("ha.lock_file is empty"));
+        }
+        Ok(Self { path: path.to_string() })
+    }
+    
+    fn open(&self) -> Result<File> {
+        if let Some(parent) = Path::new(&self.path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let file = OpenOptions::new().read(true).write(true).create(true).truncate(false).open(&self.path)?;
+        file.lock()?;
+        Ok(file)
+    }
+    
+    fn read(file: &mut File) -> Result<Option<Lease>> {
+        let mut content = String::new();
+        file.read_to_string(&mut content)?;
+        if content.trim().is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+    
+    fn write(file: &mut File, content: &[u8]) -> Result<()> {
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        file.write_all(content)?;
+        file.sync_data()?;
+        Ok(())
+    }
+}
+
+impl LeaseStore for FileLease {
+    fn acquire(&mut self, holder: &str, duration: Duration) -> Result<Lease> {
+        let mut file = self.open()?;
+        let now = Utc::now();
+        match Self::read(&mut file)? {
+            Some(lease) if lease.holder != holder && lease.expires_at > now => Ok(lease),
+            _ => {
+                let lease = Lease { holder: holder.to_string(), expires_at: now + chrono::Duration::from_std(duration)? };
+                Self::write(&mut file, serde_json::to_string(&lease)?.as_bytes())?;
+                Ok(lease)
+            }
+        }
+    }
+    
+    fn release(&mut self, holder: &str) -> Result<()> {
+        let mut file = self.open()?;
+        if Self::read(&mut file)?.is_some_and(|lease| lease.holder == holder) {
+            Self::write(&mut file, b"")?;
+        }
+        Ok(())
+    }
+    
+    fn current(&mut self) -> Result<Option<Lease>> {
+        if !Path::new(&self.path).exists() {
+            return Ok(None);
+        }
+        let mut file = self.open()?;
+        Ok(Self::read(&mut file)?.filter(|lease| lease.expires_at > Utc::now()))
+    }
+    
+    fn describe(&self) -> String {
+        format!("file {}", self.path)
+    }
+}
+
+#[cfg(feature = "postgres")]
+mod postgres {
+    use std::time::Duration;
+    use tokio_postgres::{Client, Row};
+    use crate::storage::postgres::{block_on, connect};
+    use super::{Lease, LeaseStore};
+    use anyhow::{Result, anyhow};
+    
+    /// A lease row per namespace in the `storage.url` database. Expiry is
+    /// compared with the database clock, so replicas need not agree on time.
+    pub struct PostgresLease {
+        url: String,
+        namespace: String,
+        client: Client,
+    }
+    
+    impl PostgresLease {
+        pub fn connect(url: &str, namespace: &str) -> Result<Self> {
+            if url.is_empty() {
+                return Err(anyhow!("storage.url is empty, expected e.g. postgres://monitor@db/monitor"));
+            }
+            Ok(Self {
+                url: url.to_string(),
+                namespace: namespace.to_string(),
+                client: block_on(connect_lease(url))?,
+            })
+        }
+        
+        fn client(&mut self) -> Result<&Client> {
+            if self.client.is_closed() {
+                self.client = block_on(connect_lease(&self.url))?;
+            }
+            Ok(&self.client)
+        }
+    }
+    
+    async fn connect_lease(url: &str) -> Result<Client> {
+        let client = connect(url).await?;
+        client.batch_execute(
+            "CREATE TABLE IF NOT EXISTS monitor_leader (
+                namespace TEXT PRIMARY KEY,
+                holder TEXT NOT NULL,
+                expires_at TIMESTAMPTZ NOT NULL
+            )"
+        ).await?;
+        Ok(client)
+    }
+    
+    fn lease(row: &Row) -> Lease {
+        Lease { holder: row.get(0), expires_at: row.get(1) }
+    }
+    
+    impl LeaseStore for PostgresLease {
+        fn acquire(&mut self, holder: &str, duration: Duration) -> Result<Lease> {
+            let namespace = self.namespace.clone();
+            let client = self.client()?;
+            let row = block_on(async {
+                // The upsert only overwrites our own or an expired lease
+                client.execute(
+                    "INSERT INTO monitor_leader (namespace, holder, expires_at) VALUES ($1, $2, now() + make_interval(secs => $3))
+                     ON CONFLICT (namespace) DO UPDATE SET holder = excluded.holder, expires_at = excluded.expires_at
+                     WHERE monitor_leader.holder = excluded.holder OR monitor_leader.expires_at < now()",
+                    &[&namespace, &holder, &duration.as_secs_f64()],
+                ).await?;
+                Ok(client.query_one("SELECT holder, expires_at FROM monitor_leader WHERE namespace = $1", &[&namespace]).await?)
+            })?;
+            Ok(lease(&row))
+        }
+        
+        fn release(&mut self, holder: &str) -> Result<()> {
+            let namespace = self.namespace.clone();
+            let client = self.client()?;
+            block_on(async {
+                client.execute("DELETE FROM monitor_leader WHERE namespace = $1 AND holder = $2", &[&namespace, &holder]).await?;
+                Ok(())
+            })
+        }
+        
+        fn current(&mut self) -> Result<Option<Lease>> {
+            let namespace = self.namespace.clone();
+            let client = self.client()?;
+            let row = block_on(async {
+                Ok(client.query_opt(
+                    "SELECT holder, expires_at FROM monitor_leader WHERE namespace = $1 AND expires_at > now()",
+                    &[&namespace],
+                ).await?)
+            })?;
+            Ok(row.as_ref().map(lease))
+        }
+        
+        fn describe(&self) -> String {
+            format!("postgres (namespace {})", self.namespace)
+        }
+    }
+}
+
+/// What the last attempt to lead changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Leadership {
+    /// This replica took the lease, also on its first attempt.
+    Gained,
+    Held,
+    /// Another replica has the lease now, or it could not be renewed in time.
+    Lost,
+    Standby,
+}
+
+impl Leadership {
+    pub fn is_leader(&self) -> bool {
+        matches!(self, Leadership::Gained | Leadership::Held)
+    }
+}
+
+/// This replica's side of the `ha` election.
+pub struct Election {
+    store: Box<dyn LeaseStore>,
+    instance: String,
+    lease: Duration,
+    /// When our lease runs out by the local clock, `None` while standing by.
+    leading_until: Option<Instant>,
+    /// The other replica last seen leading, logged again only when it changes.
+    other_leader: Option<String>,
+}
+
+impl Election {
+    /// `None` unless `ha.enabled` is set.
+    pub fn new(config: &Config) -> Result<Option<Self>> {
+        if !config.ha.enabled {
+            return Ok(None);
+        }
+        config.validate()?;
+        Ok(Some(Self {
+            store: open(config)?,
+            instance: instance(config),
+            lease: lease_duration(config),
+            leading_until: None,
+            other_leader: None,
+        }))
+    }
+    
+    pub fn instance(&self) -> &str {
+        &self.instance
+    }
+    
+    /// The other replica last seen leading.
+    pub fn leader(&self) -> Option<&str> {
+        self.other_leader.as_deref()
+    }
+    
+    pub fn is_leader(&self) -> bool {
+        self.leading_until.is_some_and(|until| until > Instant::now())
+    }
+    
+    /// Takes or renews the lease. When the store cannot be reached, a leader
+    /// keeps leading until its lease would have run out and a standby keeps
+    /// standing by, so an outage never makes two leaders.
+    pub fn poll(&mut self) -> Leadership {
+        let was_leader = self.is_leader();
+        let attempted = Instant::now();
+        match self.store.acquire(&self.instance, self.lease) {
+            Ok(lease) if lease.holder == self.instance => {
+                self.leading_until = Some(attempted + self.lease);
+                self.other_leader = None;
+                if was_leader {
+                    Leadership::Held
+                } else {
+                    info!("Elected leader through {}, lease {}", self.store.describe(), lease.describe());
+                    Leadership::Gained
+                }
+            }
+            Ok(lease) => {
+                self.leading_until = None;
+                let known = self.other_leader.replace(lease.holder.clone()).as_ref() == Some(&lease.holder);
+                if was_leader {
+                    warn!("Lost leadership to {}, standing by", lease.describe());
+                    Leadership::Lost
+                } else {
+                    if known {
+                        debug!("Standing by, leader is {}", lease.describe());
+                    } else {
+                        info!("Standing by, leader is {}", lease.describe());
+                    }
+                    Leadership::Standby
+                }
+            }
+            Err(e) if self.is_leader() => {
+                warn!("Failed to renew the leader lease in {}: {:#}. Still leading until the lease runs out.", self.store.describe(), e);
+                Leadership::Held
+            }
+            Err(e) => {
+                self.leading_until = None;
+                error!("Failed to take the leader lease in {}: {:#}. Standing by.", self.store.describe(), e);
+                if was_leader { Leadership::Lost } else { Leadership::Standby }
+            }
+        }
+    }
+    
+    /// Gives up the lease on shutdown.
+    pub fn resign(&mut self) {
+        if self.leading_until.take().is_none() {
+            return;
+        }
+        match self.store.release(&self.instance) {
+            Ok(()) => info!("Released the leader lease in {}", self.store.describe()),
+            Err(e) => warn!("Failed to release the leader lease in {}: {:#}", self.store.describe(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::thread;
+    
+    /// A lock file in a directory of its own, removed when the test ends.
+    struct LockDir(PathBuf);
+    
+    impl LockDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("perfmon-lease-{:016x}", rand::random::<u64>())))
+        }
+        
+        fn lease(&self) -> FileLease {
+            FileLease::new(self.0.join("ha/leader.json").to_str().unwrap()).unwrap()
+        }
+    }
+    
+    impl Drop for LockDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+    
+    const MINUTE: Duration = Duration::from_secs(60);
+    
+    #[test]
+    fn a_valid_lease_keeps_its_holder() {
+        let dir = LockDir::new();
+        let (mut a, mut b) = (dir.lease(), dir.lease());
+        assert!(a.current().unwrap().is_none());
+        
+        let first = a.acquire("a", MINUTE).unwrap();
+        assert_eq!(first.holder, "a");
+        assert_eq!(b.acquire("b", MINUTE).unwrap().holder, "a");
+        assert_eq!(b.current().unwrap().unwrap().holder, "a");
+        
+        let renewed = a.acquire("a", MINUTE * 2).unwrap();
+        assert_eq!(renewed.holder, "a");
+        assert!(renewed.expires_at > first.expires_at);
+    }
+    
+    #[test]
+    fn released_and_expired_leases_are_taken_over() {
+        let dir = LockDir::new();
+        let (mut a, mut b) = (dir.lease(), dir.lease());
+        a.acquire("a", MINUTE).unwrap();
+        b.release("b").unwrap();
+        assert_eq!(b.acquire("b", MINUTE).unwrap().holder, "a");
+        
+        a.release("a").unwrap();
+        assert!(b.current().unwrap().is_none());
+        assert_eq!(b.acquire("b", MINUTE).unwrap().holder, "b");
+        
+        b.acquire("b", Duration::ZERO).unwrap();
+        assert!(a.current().unwrap().is_none());
+        assert_eq!(a.acquire("a", MINUTE).unwrap().holder, "a");
+    }
+    
+    #[test]
+    fn concurrent_attempts_elect_one_holder() {
+        let dir = LockDir::new();
+        let holders: Vec<String> = (0..8)
+            .map(|replica| {
+                let mut lease = dir.lease();
+                thread::spawn(move || lease.acquire(&format!("replica-{}", replica), MINUTE).unwrap().holder)
+            })
+            .collect::<Vec<_>>()
+            .into_iter()
+            .map(|attempt| attempt.join().unwrap())
+            .collect();
+        assert!(holders.iter().all(|holder| *holder == holders[0]), "{:?}", holders);
+    }
+    
+    #[test]
+    fn an_empty_path_is_refused() {
+        assert!(FileLease::new("").is_err());
+    }
+}
@@ -0,0 +1,135 @@
+use chrono::{DateTime, Duration, Utc};
+use glob::Pattern;
+use crate::alert::AlertChart;
+use crate::config::{parse_duration, MemoryLeakConfig};
+use crate::docker_monitor::ContainerStats;
+use crate::trend::{self, SeriesPoint, TrendSamples};
+use crate::units;
+use log::warn;
+
+/// Most charts attached to one alert, so a host-wide regression doesn't
+/// produce a huge email.
+const MAX_CHARTS: usize = 4;
+
+/// Flags containers whose memory only grew over the window, at least
+/// `slope_threshold_mb` per hour by least-squares fit.
+pub struct LeakDetector {
+    config: MemoryLeakConfig,
+    pub window: Duration,
+    containers: Vec<Pattern>,
+}
+
+/// A container that looks like it is leaking memory.
+pub struct LeakSuspect {
+    pub container: ContainerStats,
+    pub slope_mb_per_hour: f64,
+    pub growth_mb: f64,
+    pub points: Vec<SeriesPoint>,
+}
+
+impl LeakDetector {
+    /// Returns `None` when the detector is disabled or misconfigured.
+    pub fn new(config: &MemoryLeakConfig) -> Option<Self> {
+        if !config.enabled {
+            return None;
+        }
+        
+        let window = match parse_duration(&config.window).map(Duration::from_std) {
+            Ok(Ok(window)) if window > Duration::zero() => window,
+            _ => {
+                warn!("Invalid memory leak window '{}'. Memory leak detection disabled.", config.window);
+                return None;
+            }
+        };
+        let containers = config.containers.iter()
+            .filter_map(|pattern| match Pattern::new(pattern) {
+                Ok(pattern) => Some(pattern),
+                Err(e) => {
+                    warn!("Invalid container pattern '{}' in memory_leak: {}", pattern, e);
+                    None
+                }
+            })
+            .collect();
+        
+        Some(Self { config: config.clone(), window, containers })
+    }
+    
+    pub fn watches(&self, name: &str) -> bool {
+        self.containers.is_empty() || self.containers.iter().any(|pattern| pattern.matches(name))
+    }
+    
+    pub fn threshold(&self) -> f64 {
+        self.config.slope_threshold_mb
+    }
+    
+    /// Checks the running containers against their recorded memory samples.
+    pub fn detect(&self, samples: &TrendSamples, containers: &[ContainerStats], now: DateTime<Utc>) -> Vec<LeakSuspect> {
+        let mut key = String::from("container_memory:");
+        let prefix = key.len();
+        let mut suspects: Vec<LeakSuspect> = containers.iter()
+            .filter(|container| self.watches(&container.name))
+            .filter_map(|container| {
+                key.truncate(prefix);
+                key.push_str(&container.name);
+                let points = samples.window(&key, now - self.window);
+                if !trend::covers(points, self.window, now) || !trend::is_monotonic(points) {
+                    return None;
+                }
+                let slope = trend::slope_per_hour(points)?;
+                let growth = points[points.len() - 1].value - points[0].value;
+                if slope < self.config.slope_threshold_mb || growth <= 0.0 {
+                    return None;
+                }
+                
+                Some(LeakSuspect {
+                    container: container.clone(),
+                    slope_mb_per_hour: slope,
+                    growth_mb: growth,
+                    points: points.to_vec(),
+                })
+            })
+            .collect();
+        
+        suspects.sort_by(|a, b| b.slope_mb_per_hour.total_cmp(&a.slope_mb_per_hour));
+        suspects
+    }
+}
+
+impl LeakSuspect {
+    fn limit_mb(&self) -> Option<f64> {
+        self.container.configured_memory_limit.map(|limit| limit as f64 / (1024.0 * 1024.0))
+    }
+    
+    pub fn describe(&self, window: &str) -> String {
+        let current = self.points.last().map(|point| point.value).unwrap_or_default();
+        let mut description = format!(
+            "{}: memory grew {} over {} to {} ({}{}/h)",
+            self.container.name, units::mebibytes(self.growth_mb), window, units::mebibytes(current),
+            if self.slope_mb_per_hour >= 0.0 { "+" } else { "" }, units::mebibytes(self.slope_mb_per_hour)
+        );
+        if let Some(limit) = self.limit_mb() {
+            let hours = (limit - current).max(0.0) / self.slope_mb_per_hour;
+            description.push_str(&format!(", reaches its {} limit in about {:.1}h", units::mebibytes(limit), hours));
+        }
+        description
+    }
+    
+    /// Usage chart scaled to the memory limit, or a little above the peak for
+    /// containers without one.
+    pub fn chart(&self) -> AlertChart {
+        let values: Vec<f64> = self.points.iter().map(|point| point.value).collect();
+        let peak = values.iter().copied().fold(0.0, f64::max);
+        let max = self.limit_mb().filter(|limit| *limit >= peak).unwrap_or(peak * 1.25);
+        AlertChart {
+            title: format!("{} memory", self.container.name),
+            unit: "MB".to_string(),
+            max,
+            values,
+        }
+    }
+}
+
+/// Charts for the alert, fastest growing containers first.
+pub fn charts(suspects: &[LeakSuspect]) -> Vec<AlertChart> {
+    suspects.iter().take(MAX_CHARTS).map(LeakSuspect::chart).collect()
+}
\ No newline at end of file
@@ -0,0 +1,94 @@
+//! Server and Docker performance monitoring. The `performance-monitor` binary
+//! wires these modules together; they live in a library so the benchmarks in
+//! `benches/` can exercise them directly.
+
+pub mod config;
+pub mod config_template;
+pub mod self_update;
+pub mod shutdown;
+pub mod snapshot;
+pub mod motd;
+pub mod alert;
+pub mod cloud_metadata;
+pub mod kubernetes;
+pub mod server_monitor;
+pub mod docker_monitor;
+pub mod docker_control;
+pub mod email_notifier;
+pub mod process_watchdog;
+pub mod state;
+pub mod storage;
+pub mod leader;
+pub mod digest;
+pub mod cost_report;
+pub mod scaling_hooks;
+pub mod resource_audit;
+#[cfg(feature = "docker")]
+pub mod security_audit;
+pub mod status;
+pub mod healthz;
+pub mod doctor;
+pub mod schedule;
+pub mod dispatch;
+pub mod payload;
+pub mod redact;
+pub mod render;
+pub mod metrics;
+#[cfg(feature = "web")]
+pub mod http_server;
+pub mod dependencies;
+pub mod incident;
+pub mod correlation;
+pub mod inventory;
+pub mod events;
+pub mod disk_latency;
+pub mod cpufreq;
+pub mod ups;
+pub mod libvirt;
+pub mod lxd;
+pub mod zfs;
+pub mod storage_pools;
+pub mod command_check;
+pub mod cron;
+pub mod conntrack;
+pub mod ping;
+pub mod fingerprint;
+pub mod outbound;
+pub mod image_pins;
+pub mod group_budget;
+pub mod history;
+pub mod dashboard;
+pub mod query;
+pub mod silence;
+pub mod audit;
+pub mod thresholds;
+pub mod escalation;
+pub mod oncall;
+pub mod ical;
+pub mod maintenance;
+pub mod ticket;
+pub mod api;
+pub mod grpc;
+pub mod chatops;
+#[cfg(feature = "email")]
+pub mod imap;
+#[cfg(feature = "email")]
+pub mod email_commands;
+pub mod chart;
+pub mod report;
+pub mod statuspage;
+pub mod timezone;
+pub mod units;
+pub mod html;
+pub mod logging;
+pub mod telemetry;
+pub mod webhook;
+pub mod chat;
+pub mod apprise;
+pub mod signing;
+pub mod webpush;
+pub mod push_monitor;
+pub mod network;
+pub mod trend;
+pub mod leak;
+pub mod platform;
\ No newline at end of file
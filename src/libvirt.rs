@@ -0,0 +1,156 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Instant;
+use tokio::process::Command;
+use crate::config::{Config, LibvirtConfig};
+use crate::units;
+use anyhow::{Result, anyhow};
+
+/// One libvirt domain. CPU usage is relative to the domain's vCPUs and is
+/// only known from the second sample on.
+#[derive(Debug, Clone, Serialize)]
+pub struct VmStats {
+    pub name: String,
+    pub state: String,
+    pub vcpus: u32,
+    pub cpu_percent: Option<f64>,
+    /// Memory used by the guest in MB, from balloon statistics when the guest
+    /// reports them, otherwise the RSS of the QEMU process.
+    pub memory_used_mb: f64,
+    pub memory_assigned_mb: f64,
+    pub memory_percent: f64,
+}
+
+impl VmStats {
+    pub fn is_running(&self) -> bool {
+        self.state == "running"
+    }
+    
+    pub fn describe(&self) -> String {
+        format!(
+            "{} ({}): CPU {}, memory {}/{} ({:.1}%), {} vCPUs",
+            self.name,
+            self.state,
+            self.cpu_percent.map(|cpu| format!("{:.1}%", cpu)).unwrap_or_else(|| "n/a".to_string()),
+            units::mebibytes(self.memory_used_mb),
+            units::mebibytes(self.memory_assigned_mb),
+            self.memory_percent,
+            self.vcpus
+        )
+    }
+}
+
+/// Samples KVM/QEMU guests through `virsh domstats`, the libvirt analog of
+/// the Docker monitor.
+pub struct LibvirtMonitor {
+    config: LibvirtConfig,
+    previous: HashMap<String, (u64, Instant)>,
+}
+
+impl LibvirtMonitor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.libvirt.clone(),
+            previous: HashMap::new(),
+        }
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Lists all defined domains with their current usage.
+    pub async fn sample(&mut self) -> Result<Vec<VmStats>> {
+        let mut command = Command::new("virsh");
+        if let Some(uri) = &self.config.uri {
+            command.arg("--connect").arg(uri);
+        }
+        let output = command
+            .args(["domstats", "--raw", "--state", "--cpu-total", "--balloon", "--vcpu"])
+            .output()
+            .await
+            .map_err(|e| anyhow!("failed to run virsh: {}", e))?;
+        if !output.status.success() {
+            return Err(anyhow!("virsh domstats failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        
+        let now = Instant::now();
+        let domains = parse_domstats(&String::from_utf8_lossy(&output.stdout));
+        let mut previous = HashMap::new();
+        let mut vms: Vec<VmStats> = domains.into_iter()
+            .map(|(name, fields)| {
+                let number = |key: &str| fields.get(key).and_then(|value| value.parse::<u64>().ok());
+                let vcpus = number("vcpu.current").unwrap_or(0) as u32;
+                let cpu_time = number("cpu.time");
+                
+                let cpu_percent = cpu_time.zip(self.previous.get(&name)).and_then(|(time, (last_time, last_at))| {
+                    let elapsed = now.duration_since(*last_at).as_nanos() as f64;
+                    (elapsed > 0.0 && vcpus > 0)
+                        .then(|| time.saturating_sub(*last_time) as f64 * 100.0 / (elapsed * vcpus as f64))
+                });
+                if let Some(time) = cpu_time {
+                    previous.insert(name.clone(), (time, now));
+                }
+                
+                let assigned = number("balloon.current").unwrap_or(0) as f64 / 1024.0;
+                let used = match (number("balloon.available"), number("balloon.unused")) {
+                    (Some(available), Some(unused)) => available.saturating_sub(unused) as f64 / 1024.0,
+                    _ => number("balloon.rss").unwrap_or(0) as f64 / 1024.0,
+                };
+                
+                VmStats {
+                    state: state_name(number("state.state").unwrap_or(0)).to_string(),
+                    vcpus,
+                    cpu_percent,
+                    memory_used_mb: used,
+                    memory_assigned_mb: assigned,
+                    memory_percent: if assigned > 0.0 { (used / assigned * 100.0).min(100.0) } else { 0.0 },
+                    name,
+                }
+            })
+            .collect();
+        
+        self.previous = previous;
+        vms.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(vms)
+    }
+    
+    /// Running guests above the CPU or memory threshold.
+    pub fn over_threshold(&self, vms: &[VmStats]) -> Vec<VmStats> {
+        vms.iter()
+            .filter(|vm| vm.is_running())
+            .filter(|vm| {
+                vm.cpu_percent.is_some_and(|cpu| cpu > self.config.cpu_threshold)
+                    || vm.memory_percent > self.config.memory_threshold
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+fn state_name(state: u64) -> &'static str {
+    match state {
+        1 => "running",
+        2 => "blocked",
+        3 => "paused",
+        4 => "shutdown",
+        5 => "shut off",
+        6 => "crashed",
+        7 => "suspended",
+        _ => "unknown",
+    }
+}
+
+/// Parses `virsh domstats` output: a `Domain: 'name'` line followed by
+/// indented `key=value` lines per domain.
+fn parse_domstats(output: &str) -> Vec<(String, HashMap<String, String>)> {
+    let mut domains: Vec<(String, HashMap<String, String>)> = Vec::new();
+    for line in output.lines() {
+        if let Some(name) = line.strip_prefix("Domain: ") {
+            domains.push((name.trim().trim_matches('\'').to_string(), HashMap::new()));
+        } else if let (Some((_, fields)), Some((key, value))) = (domains.last_mut(), line.trim().split_once('=')) {
+            fields.insert(key.to_string(), value.to_string());
+        }
+    }
+    domains
+}
\ No newline at end of file
@@ -0,0 +1,67 @@
+use crate::cloud_metadata::CloudMetadata;
+use crate::config::{LoggingConfig, OtlpConfig};
+use crate::telemetry::{self, Telemetry};
+use log::warn;
+use std::io::IsTerminal;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, EnvFilter};
+
+/// Installs the global logger. `RUST_LOG` wins over `logging.level` so a
+/// single run can be debugged without editing the config. Records from the
+/// `log` macros are forwarded to tracing, so they carry the span of the
+/// monitoring cycle they were written in. The returned guard keeps the OTLP
+/// exporter (if any) alive and must be held until exit.
+pub fn init(config: &LoggingConfig, otlp: &OtlpConfig, instance: Option<&CloudMetadata>) -> Telemetry {
+    let (filter, invalid) = match EnvFilter::try_from_default_env() {
+        Ok(filter) => (filter, None),
+        Err(_) => match EnvFilter::try_new(directives(&config.level)) {
+            Ok(filter) => (filter, None),
+            Err(e) => (EnvFilter::new("info"), Some(e)),
+        },
+    };
+    
+    let (exporter, telemetry, otlp_error) = match telemetry::layer(otlp, instance) {
+        Ok((exporter, telemetry)) => (exporter, telemetry, None),
+        Err(e) => (None, Telemetry::default(), Some(e)),
+    };
+    
+    let ansi = std::io::stderr().is_terminal();
+    let (text, json) = if config.format == "json" {
+        (None, Some(fmt::layer().with_writer(std::io::stderr).json().flatten_event(true)))
+    } else {
+        (Some(fmt::layer().with_writer(std::io::stderr).with_ansi(ansi)), None)
+    };
+    let result = tracing_subscriber::registry()
+        .with(exporter)
+        .with(filter)
+        .with(text)
+        .with(json)
+        .try_init();
+    if let Err(e) = result {
+        eprintln!("Failed to initialize logging: {}", e);
+    }
+    
+    if !matches!(config.format.as_str(), "text" | "json") {
+        warn!("Unknown logging format '{}', using text", config.format);
+    }
+    if let Some(e) = invalid {
+        warn!("Invalid logging level '{}' ({}), using INFO", config.level, e);
+    }
+    if let Some(e) = otlp_error {
+        warn!("OTLP trace export disabled: {}", e);
+    }
+    telemetry
+}
+
+/// Maps the Python style level names of older configs (`WARNING`,
+/// `CRITICAL`) to tracing levels. Anything else is passed on as filter
+/// directives, e.g. `info,bollard=warn`.
+fn directives(level: &str) -> String {
+    match level.trim().to_ascii_lowercase().as_str() {
+        "" => "info".to_string(),
+        "warning" => "warn".to_string(),
+        "critical" | "fatal" => "error".to_string(),
+        other => other.to_string(),
+    }
+}
\ No newline at end of file
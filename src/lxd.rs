@@ -0,0 +1,174 @@
+use chrono::{DateTime, Utc};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Instant;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::UnixStream;
+use crate::config::{Config, LxdConfig};
+use crate::docker_monitor::ContainerStats;
+use anyhow::{Result, anyhow};
+
+/// Default LXD socket locations, snap first.
+const SOCKETS: [&str; 2] = ["/var/snap/lxd/common/lxd/unix.socket", "/var/lib/lxd/unix.socket"];
+
+/// Reads LXD instances through the REST API on the local unix socket and
+/// reports them as `ContainerStats`, so thresholds work the same as for
+/// Docker containers. CPU usage is percent of one CPU, like `docker stats`.
+pub struct LxdMonitor {
+    config: LxdConfig,
+    previous: HashMap<String, (u64, Instant)>,
+}
+
+impl LxdMonitor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.lxd.clone(),
+            previous: HashMap::new(),
+        }
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    /// Running instances with their usage. CPU usage is 0 on the first sample
+    /// of an instance since it is computed from the delta between checks.
+    pub async fn get_container_stats(&mut self) -> Result<Vec<ContainerStats>> {
+        let socket = match &self.config.socket {
+            Some(socket) => socket.clone(),
+            None => SOCKETS.iter()
+                .find(|socket| Path::new(socket).exists())
+                .map(|socket| socket.to_string())
+                .ok_or_else(|| anyhow!("no LXD socket found at {}", SOCKETS.join(" or ")))?,
+        };
+        let mut path = "/1.0/instances?recursion=2".to_string();
+        if let Some(project) = &self.config.project {
+            path.push_str(&format!("&project={}", project));
+        }
+        
+        let response = get(&socket, &path).await?;
+        let instances = response["metadata"].as_array()
+            .ok_or_else(|| anyhow!("unexpected LXD response: {}", response["error"]))?;
+        
+        let now = Instant::now();
+        let mut previous = HashMap::new();
+        let mut containers = Vec::new();
+        for instance in instances {
+            let name = instance["name"].as_str().unwrap_or_default().to_string();
+            let status = instance["status"].as_str().unwrap_or_default().to_string();
+            if status != "Running" {
+                continue;
+            }
+            
+            let state = &instance["state"];
+            let cpu_time = state["cpu"]["usage"].as_u64().unwrap_or(0);
+            let cpu_usage = self.previous.get(&name)
+                .map(|(last_time, last_at)| {
+                    let elapsed = now.duration_since(*last_at).as_nanos().max(1) as f64;
+                    cpu_time.saturating_sub(*last_time) as f64 * 100.0 / elapsed
+                })
+                .unwrap_or(0.0);
+            previous.insert(name.clone(), (cpu_time, now));
+            
+            let config = &instance["config"];
+            let setting = |key: &str| config[key].as_str().filter(|value| !value.is_empty());
+            let configured_memory_limit = setting("limits.memory").and_then(parse_bytes);
+            let memory_usage = state["memory"]["usage"].as_u64().unwrap_or(0);
+            let memory_limit = configured_memory_limit
+                .or_else(|| state["memory"]["total"].as_u64())
+                .unwrap_or(0);
+            let image = setting("image.description")
+                .map(str::to_string)
+                .or_else(|| setting("image.os").map(|os| format!("{} {}", os, setting("image.release").unwrap_or_default())))
+                .unwrap_or_else(|| "unknown".to_string());
+            let labels = config.as_object()
+                .map(|config| config.iter()
+                    .filter_map(|(key, value)| Some((key.strip_prefix("user.")?.to_string(), value.as_str()?.to_string())))
+                    .collect())
+                .unwrap_or_default();
+            
+            containers.push(ContainerStats {
+                id: name.clone(),
+                image,
+                image_id: String::new(),
+                status: format!("{} ({})", status, instance["type"].as_str().unwrap_or("container")),
+                cpu_usage,
+                memory_usage,
+                memory_limit,
+                memory_percent: if memory_limit > 0 { memory_usage as f64 * 100.0 / memory_limit as f64 } else { 0.0 },
+                cpu_limit: setting("limits.cpu").and_then(parse_cpus),
+                configured_memory_limit,
+                ports: Vec::new(),
+                labels,
+                created: instance["created_at"].as_str()
+                    .and_then(|created| DateTime::parse_from_rfc3339(created).ok())
+                    .map(|created| created.with_timezone(&Utc))
+                    .unwrap_or_else(Utc::now),
+                timestamp: Utc::now(),
+                name,
+            });
+        }
+        
+        self.previous = previous;
+        Ok(containers)
+    }
+}
+
+/// Sends a GET request over the LXD unix socket and returns the JSON body.
+/// HTTP/1.0 keeps the response unchunked and closes the connection after it.
+async fn get(socket: &str, path: &str) -> Result<Value> {
+    let mut stream = UnixStream::connect(socket).await
+        .map_err(|e| anyhow!("failed to connect to {}: {}", socket, e))?;
+    stream.write_all(format!("GET {} HTTP/1.0\r\nHost: lxd\r\n\r\n", path).as_bytes()).await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+    
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n")
+        .ok_or_else(|| anyhow!("malformed HTTP response from LXD"))?;
+    let status = head.lines().next().unwrap_or_default();
+    if status.split_whitespace().nth(1).is_none_or(|code| code != "200") {
+        return Err(anyhow!("LXD returned {}: {}", status, body.trim()));
+    }
+    Ok(serde_json::from_str(body)?)
+}
+
+/// Parses LXD byte sizes such as `512MiB`, `2GB` or `1073741824`. Percent
+/// limits are relative to the host and return `None`.
+fn parse_bytes(value: &str) -> Option<u64> {
+    let value = value.trim();
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let multiplier: f64 = match unit.trim() {
+        "" | "B" => 1.0,
+        "kB" => 1e3,
+        "MB" => 1e6,
+        "GB" => 1e9,
+        "TB" => 1e12,
+        "KiB" => 1024.0,
+        "MiB" => 1024.0 * 1024.0,
+        "GiB" => 1024.0 * 1024.0 * 1024.0,
+        "TiB" => 1024.0 * 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    number.parse::<f64>().ok().map(|number| (number * multiplier) as u64)
+}
+
+/// Parses `limits.cpu`: a CPU count (`2`) or pinned CPUs (`0-3`, `1,3`).
+fn parse_cpus(value: &str) -> Option<f64> {
+    if let Ok(count) = value.trim().parse::<f64>() {
+        return Some(count);
+    }
+    let mut count = 0;
+    for part in value.split(',') {
+        match part.trim().split_once('-') {
+            Some((start, end)) => count += end.parse::<u32>().ok()?.checked_sub(start.parse::<u32>().ok()?)? + 1,
+            None => {
+                part.trim().parse::<u32>().ok()?;
+                count += 1;
+            }
+        }
+    }
+    (count > 0).then_some(count as f64)
+}
\ No newline at end of file
@@ -1,190 +1,2719 @@
 use clap::{Arg, Command};
 use log::{info, error, warn};
-use env_logger::Env;
+use tracing::Instrument;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
 use std::time::Duration;
-use anyhow::Result;
+use serde::Serialize;
+use tokio::signal::unix::{signal, SignalKind};
+use anyhow::{Context, Result};
 
-mod config;
-mod server_monitor;
-mod docker_monitor;
-mod email_notifier;
+use performance_monitor::{
+    config, alert, cloud_metadata, server_monitor, docker_monitor, docker_control, email_notifier, correlation,
+    process_watchdog, state, digest, cost_report, scaling_hooks, resource_audit,
+    status, healthz, doctor, schedule, dispatch, render, metrics, dependencies,
+    incident, inventory, events, disk_latency, cpufreq, ups, libvirt, lxd, zfs, storage_pools,
+    command_check, cron, conntrack, ping, fingerprint, image_pins, group_budget, report, statuspage, dashboard, timezone,
+    units, logging, webhook, chat, apprise, webpush, network, trend, leak, platform, kubernetes, api, grpc,
+    thresholds, escalation, oncall, maintenance, outbound, push_monitor, ticket, config_template,
+    self_update, shutdown, snapshot, motd, audit, leader, redact,
+};
+#[cfg(feature = "docker")]
+use performance_monitor::security_audit;
+#[cfg(feature = "web")]
+use performance_monitor::http_server;
+#[cfg(feature = "email")]
+use performance_monitor::email_commands;
 
 use config::Config;
-use server_monitor::ServerMonitor;
+use alert::{Alert, CheckOutcome, Severity};
+use audit::{AuditAction, AuditEntry};
+use cloud_metadata::CloudMetadata;
+use server_monitor::{CpuCoreStats, DiskStats, MemoryStats, NetworkStats, ProcessUsage, ServerMonitor, ServerStats, SwapStats};
 use docker_monitor::DockerMonitor;
+use docker_control::DockerControl;
+use correlation::Outgoing;
 use email_notifier::EmailNotifier;
+use dispatch::{Dispatcher, Notifiers};
+use metrics::RuntimeMetrics;
+use dependencies::DependencyGraph;
+use incident::{IncidentState, Transition};
+use events::EventStream;
+use disk_latency::DiskLatencyMonitor;
+use cpufreq::CpuFrequencyMonitor;
+use libvirt::LibvirtMonitor;
+use lxd::LxdMonitor;
+use zfs::ZfsMonitor;
+use network::TrafficMonitor;
+use image_pins::ImagePins;
+use statuspage::{ComponentCheck, PublishedPage};
+use dashboard::PublishedHistory;
+use api::ApiState;
+use thresholds::ThresholdOverride;
+use escalation::EscalationStep;
+use ticket::{TicketTracker, TicketUpdate};
+use oncall::OnCall;
+use timezone::Timezone;
+use webhook::WebhookNotifier;
+use chat::ChatNotifier;
+use apprise::AppriseNotifier;
+use webpush::WebPushNotifier;
+use push_monitor::PushMonitors;
+use trend::TrendRule;
+use command_check::CommandCheck;
+use cron::CronStore;
+use leak::LeakDetector;
+use outbound::OutboundMonitor;
+use process_watchdog::ProcessWatchdog;
+use redact::Redactor;
+use state::StateStore;
+use leader::{Election, Leadership};
+use scaling_hooks::ScalingHooks;
+use status::{StatusOptions, StatusSnapshot};
+use shutdown::ShutdownToken;
+
+/// Samples from the most recent monitoring cycle, kept for state dumps.
+#[derive(Debug, Clone, Serialize)]
+struct CycleSummary {
+    timestamp: chrono::DateTime<chrono::Utc>,
+    server_cpu: f64,
+    memory_percent: f64,
+    high_cpu_containers: Vec<docker_monitor::ContainerStats>,
+    outcome: CheckOutcome,
+}
+
+/// A digest handed to the dispatcher, recorded as sent once delivered.
+struct PendingDigest {
+    task: tokio::task::JoinHandle<bool>,
+    built_at: chrono::DateTime<chrono::Utc>,
+    cost_due: bool,
+}
+
+/// What a continuous run did, logged and emitted when it shuts down.
+#[derive(Debug, Serialize)]
+struct ShutdownSummary {
+    cycles: u64,
+    uptime_secs: i64,
+    notifications_sent: u64,
+    notifications_failed: u64,
+    notifications_dropped: u64,
+    /// Alerts still queued or in flight when `drain_timeout` ran out.
+    notifications_undelivered: usize,
+    firing: Vec<String>,
+}
 
 struct PerformanceMonitor {
-    config: Config,
+    /// The loaded config with runtime threshold overrides applied.
+    config: Arc<Config>,
+    /// The config as loaded from the file.
+    base_config: Arc<Config>,
+    applied_thresholds: Vec<ThresholdOverride>,
     server_monitor: ServerMonitor,
-    docker_monitor: DockerMonitor,
-    email_notifier: EmailNotifier,
+    /// `None` on a cluster node without Docker, where only the host is watched.
+    docker_monitor: Option<DockerMonitor>,
+    /// `None` unless `docker.allow_container_actions` is set.
+    docker_control: Option<DockerControl>,
+    email_notifier: Arc<EmailNotifier>,
+    dispatcher: Dispatcher,
+    /// Applied to every alert before it leaves the monitor; the state keeps
+    /// the original.
+    redactor: Arc<Redactor>,
+    process_watchdog: ProcessWatchdog,
+    disk_latency: DiskLatencyMonitor,
+    cpu_frequency: CpuFrequencyMonitor,
+    libvirt: LibvirtMonitor,
+    lxd: LxdMonitor,
+    zfs: ZfsMonitor,
+    traffic: TrafficMonitor,
+    cloud_metadata: Option<CloudMetadata>,
+    state: StateStore,
+    /// `None` unless `ha.enabled` is set and the monitor runs continuously.
+    election: Option<Election>,
+    scaling_hooks: ScalingHooks,
+    /// Emailed in the background so a slow SMTP server never stalls a cycle.
+    pending_digest: Option<PendingDigest>,
+    pending_reports: HashMap<String, (tokio::task::JoinHandle<bool>, chrono::DateTime<chrono::Utc>)>,
+    last_cycle: Option<CycleSummary>,
+    cycle_id: u64,
+    over_memory_budget: bool,
+    metrics: Arc<RuntimeMetrics>,
+    last_notified: HashMap<String, chrono::DateTime<chrono::Utc>>,
+    /// Container groups last found over budget, whose owners hear about the
+    /// `group_budget` rule until it fires for other groups.
+    budget_owners: Vec<String>,
+    cycle_alerts: HashMap<String, Alert>,
+    /// The top processes sampled for this cycle's alerts, taken at most once.
+    cycle_processes: Option<Vec<ProcessUsage>>,
+    /// Docker container stats for this cycle, fetched at most once.
+    cycle_containers: Option<Vec<docker_monitor::ContainerStats>>,
+    skipped_rules: HashSet<String>,
+    status_checks: Vec<ComponentCheck>,
+    status_page: Arc<PublishedPage>,
+    history_page: Arc<PublishedHistory>,
+    api: Arc<ApiState>,
+    /// Subscriptions arrive through the HTTP server.
+    #[cfg_attr(not(feature = "web"), allow(dead_code))]
+    webpush: Arc<WebPushNotifier>,
+    push_monitors: PushMonitors,
+    tickets: Option<TicketTracker>,
+    trend_rules: Vec<TrendRule>,
+    leak_detector: Option<LeakDetector>,
+    outbound: Option<OutboundMonitor>,
+    command_checks: Vec<CommandCheck>,
+    cron_store: CronStore,
+    image_pins: ImagePins,
+    started_at: chrono::DateTime<chrono::Utc>,
+    timezone: Timezone,
+    incident_active: bool,
+    events: EventStream,
 }
 
 impl PerformanceMonitor {
-    async fn new(config_path: &str) -> Result<Self> {
+    async fn new(config_path: &str, docker_read_only: bool) -> Result<Self> {
         // Load configuration
-        let config = match Config::load_from_file(config_path) {
+        let mut config = match Config::load_from_file(config_path) {
             Ok(config) => {
                 info!("Configuration loaded from {}", config_path);
                 config
             }
+            Err(e) if e.is::<config::InvalidConfig>() => return Err(anyhow::anyhow!("Invalid config {}: {}", config_path, e)),
             Err(e) => {
                 warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
                 Config::default()
             }
         };
+        units::configure(&config.units);
+        audit::configure(&config.audit);
+        config.docker.read_only |= docker_read_only;
+        if !platform::has_procfs() {
+            warn!("/proc is not available on {}: load average comes from sysinfo, network traffic, disk latency and conntrack are not collected",
+                  platform::target());
+        }
+        // Shared by the components that keep it, instead of a deep copy each
+        let config = Arc::new(config);
+        
+        // Initialize monitors
+        let server_monitor = ServerMonitor::new(config.clone());
+        if let Some(cluster) = kubernetes::current() {
+            info!("Running in cluster on node {} as pod {}/{}", cluster.node, cluster.namespace, cluster.pod);
+        }
+        let docker_monitor = match DockerMonitor::new(config.clone()).await {
+            Ok(monitor) if !monitor.capabilities().containers => {
+                warn!("Docker API denies access to containers, monitoring the host only. Allow CONTAINERS on the socket proxy.");
+                None
+            }
+            Ok(monitor) => {
+                info!("Docker monitor initialized successfully");
+                if config.resource_audit.enabled && !monitor.capabilities().can_audit() {
+                    warn!("Docker resource audit disabled: the API denies NETWORKS or SYSTEM");
+                }
+                Some(monitor)
+            }
+            Err(_) if cfg!(not(feature = "docker")) => {
+                info!("Built without Docker support, monitoring the host only");
+                None
+            }
+            Err(e) if kubernetes::current().is_some() => {
+                // containerd and CRI-O nodes have no Docker socket
+                warn!("Docker is not available on this node ({}), monitoring the host only", e);
+                None
+            }
+            Err(e) => {
+                error!("Failed to initialize Docker monitor: {}", e);
+                if let Some(remediation) = doctor::docker_remediation() {
+                    error!("{}", remediation);
+                }
+                return Err(e);
+            }
+        };
+        let docker_control = match DockerControl::new(&config.docker) {
+            Ok(control) => control,
+            Err(e) => {
+                warn!("Container actions disabled: {}", e);
+                None
+            }
+        };
+        if config.resource_audit.auto_prune && docker_control.is_none() {
+            warn!("resource_audit.auto_prune needs docker.allow_container_actions, unused resources are only reported");
+        }
+        let cloud_metadata = cloud_metadata::detect(&config.cloud).await;
+        if let Some(metadata) = &cloud_metadata {
+            info!("Detected {} instance {} ({}, {})",
+                  metadata.provider, metadata.instance_id, metadata.region, metadata.instance_type);
+        }
+        let oncall = OnCall::new(&config);
+        if let Some(oncall) = &oncall {
+            oncall.start(&config).await;
+        }
+        let mut email_notifier = EmailNotifier::new(&config);
+        email_notifier.set_host_metadata(cloud_metadata.clone());
+        email_notifier.set_oncall(oncall);
+        let email_notifier = Arc::new(email_notifier);
+        let process_watchdog = ProcessWatchdog::new(&config);
+        let disk_latency = DiskLatencyMonitor::new(&config);
+        let cpu_frequency = CpuFrequencyMonitor::new(&config);
+        let libvirt = LibvirtMonitor::new(&config);
+        let lxd = LxdMonitor::new(&config);
+        let zfs = ZfsMonitor::new(&config);
+        let state = StateStore::open(&config);
+        let metrics = Arc::new(RuntimeMetrics::restore(state.state.runtime_metrics.clone()));
+        metrics.set_instance(cloud_metadata.clone());
+        let webpush = Arc::new(WebPushNotifier::new(&config));
+        let notifiers = Arc::new(Notifiers {
+            email: email_notifier.clone(),
+            webhooks: WebhookNotifier::new(&config),
+            chat: ChatNotifier::new(&config),
+            apprise: AppriseNotifier::new(&config),
+            webpush: webpush.clone(),
+        });
+        let redactor = Arc::new(Redactor::new(&config.redaction));
+        let dispatcher = Dispatcher::start(&config, notifiers, metrics.clone(), redactor.clone());
+        let scaling_hooks = ScalingHooks::new(&config);
+        let push_monitors = PushMonitors::new(&config);
+        let tickets = TicketTracker::new(&config);
+        let trend_rules = trend::load_rules(&config.trend_rules);
+        let leak_detector = LeakDetector::new(&config.memory_leak);
+        let outbound = OutboundMonitor::new(&config);
+        let command_checks = command_check::load_checks(&config.command_checks);
+        let cron_store = CronStore::new(&config.cron);
+        let image_pins = ImagePins::new(&config.image_pins);
+        let timezone = timezone::load(&config.timezone);
+        
+        info!("Performance Monitor initialized");
+        info!("CPU Threshold: {}%", config.monitoring.cpu_threshold);
+        let api = Arc::new(ApiState::new(&config.http));
+        api.silences.load(state.state.silences.clone());
+        api.thresholds.load(state.state.thresholds.clone(), config_path);
+        api.load_handled_commands(state.state.recent_commands.clone());
+        api.load_acknowledgements(
+            state.state.incidents.iter()
+                .filter_map(|(rule, rule_state)| Some((rule.clone(), rule_state.acknowledged.clone()?)))
+                .collect()
+        );
+        escalation::check_channels(&config);
+        maintenance::start(&config, api.clone()).await;
+        
+        Ok(Self {
+            base_config: config.clone(),
+            applied_thresholds: Vec::new(),
+            config,
+            server_monitor,
+            docker_monitor,
+            docker_control,
+            email_notifier,
+            dispatcher,
+            redactor,
+            process_watchdog,
+            disk_latency,
+            cpu_frequency,
+            libvirt,
+            lxd,
+            zfs,
+            traffic: TrafficMonitor::default(),
+            cloud_metadata,
+            state,
+            election: None,
+            scaling_hooks,
+            pending_digest: None,
+            pending_reports: HashMap::new(),
+            last_cycle: None,
+            cycle_id: 0,
+            over_memory_budget: false,
+            metrics,
+            last_notified: HashMap::new(),
+            budget_owners: Vec::new(),
+            cycle_alerts: HashMap::new(),
+            cycle_processes: None,
+            cycle_containers: None,
+            skipped_rules: HashSet::new(),
+            status_checks: Vec::new(),
+            status_page: Arc::new(PublishedPage::default()),
+            history_page: Arc::new(PublishedHistory::default()),
+            api,
+            webpush,
+            push_monitors,
+            tickets,
+            trend_rules,
+            leak_detector,
+            outbound,
+            command_checks,
+            cron_store,
+            image_pins,
+            started_at: chrono::Utc::now(),
+            timezone,
+            incident_active: false,
+            events: EventStream::default(),
+        })
+    }
+    
+    /// Records an alert raised by a rule in this cycle. `evaluate_incidents`
+    /// decides whether it is sent.
+    fn raise(&mut self, alert: Alert) {
+        let rule = self.config.alert_rules.get(&alert.rule);
+        let alert = alert.with_rule_info(rule);
+        self.cycle_alerts.insert(alert.rule.clone(), alert);
+    }
+    
+    /// Hands an alert to the dispatch queue. While incident mode shortens the
+    /// check interval, repeats of the same rule are held back so notifications
+    /// keep the normal cadence.
+    fn send(&mut self, alert: Alert, repeat: bool) {
+        let now = chrono::Utc::now();
+        let min_gap = chrono::Duration::milliseconds((self.config.monitoring.check_interval * 900) as i64);
+        if let Some(last) = self.last_notified.get(&alert.rule) {
+            if repeat && now - *last < min_gap {
+                info!("Skipping repeat {} notification (last sent {}s ago)", alert.rule, (now - *last).num_seconds());
+                return;
+            }
+        }
+        
+        if let Some(silence) = self.api.silences.matching(&alert, now) {
+            info!("Silenced {} notification by silence {} until {}", alert.rule, silence.id, silence.ends_at);
+            return;
+        }
+        
+        self.last_notified.insert(alert.rule.clone(), now);
+        let alert = self.redactor.apply(alert);
+        self.events.emit("alert", &alert);
+        self.api.publish_alert(&alert);
+        if alert.rule == alert::RULE_GROUP_BUDGET {
+            for group in self.config.container_groups.iter().filter(|group| self.budget_owners.contains(&group.name)) {
+                self.dispatcher.notify_owners(alert.clone(), group);
+            }
+        }
+        self.dispatcher.submit(alert);
+    }
+    
+    /// Runs every known rule through its incident state machine and sends the
+    /// notifications the transitions call for, merged per `correlation` rules.
+    /// With `focus`, only those rules are evaluated and the others keep their
+    /// state. Returns the tickets to open or resolve.
+    fn evaluate_incidents(&mut self, focus: Option<&HashSet<&str>>) -> Vec<TicketUpdate> {
+        let mut rules: Vec<String> = self.state.state.incidents.keys()
+            .chain(self.cycle_alerts.keys())
+            .filter(|rule| !self.skipped_rules.contains(*rule))
+            .filter(|rule| focus.is_none_or(|focus| focus.contains(rule.as_str())))
+            .cloned()
+            .collect();
+        rules.sort();
+        rules.dedup();
+        
+        let now = chrono::Utc::now();
+        let mut outgoing = Vec::new();
+        let mut escalations = Vec::new();
+        let mut tickets = Vec::new();
+        for rule in rules {
+            let alert = self.cycle_alerts.remove(&rule).map(|alert| self.state.state.history.annotate(alert, now));
+            let rule_state = self.state.state.incidents.entry(rule.clone()).or_default();
+            let transition = rule_state.observe(alert.is_some(), &self.config.incidents);
+            let change = rule_state.percent_state_change();
+            
+            if matches!(rule_state.state, IncidentState::Firing | IncidentState::Flapping) && rule_state.acknowledged.is_none() {
+                rule_state.acknowledged = self.api.acknowledgement(&rule);
+                if let Some(acknowledgement) = &rule_state.acknowledged {
+                    info!("Rule {} acknowledged by {}", rule, acknowledgement.by);
+                }
+            }
+            if let (Some(tier), Some(alert)) = (escalation::due(&self.config, &rule, rule_state, now), &alert) {
+                if self.api.silences.matching(alert, now).is_none() {
+                    warn!("Rule {} unacknowledged since {}, escalating to tier {}", rule, rule_state.since.format("%H:%M UTC"), tier.name);
+                    rule_state.escalations.push(EscalationStep::new(tier, now));
+                    escalations.push((self.redactor.apply(escalation::annotate(alert.clone(), rule_state)), tier.clone()));
+                }
+            }
+            let alert = alert.map(|alert| escalation::annotate(alert, rule_state));
+            
+            if self.tickets.is_some() {
+                if let Some(alert) = alert.as_ref().filter(|_| ticket::due(&self.config, &rule, rule_state, now)) {
+                    if self.api.silences.matching(alert, now).is_none() {
+                        let alert = Box::new(self.redactor.apply(alert.clone()));
+                        tickets.push(TicketUpdate::Open { rule: rule.clone(), alert, firing_since: rule_state.since });
+                    }
+                }
+            }
+            if !matches!(rule_state.state, IncidentState::Firing | IncidentState::Flapping) {
+                if let Some(ticket) = rule_state.ticket.take() {
+                    tickets.push(TicketUpdate::Resolve { rule: rule.clone(), ticket });
+                }
+            }
+            
+            match transition {
+                Transition::None => {}
+                Transition::Pending => {
+                    info!("Rule {} is pending ({} of {} checks)", rule, rule_state.consecutive, self.config.incidents.pending_checks);
+                }
+                Transition::Fired | Transition::Repeat => {
+                    if transition == Transition::Fired {
+                        warn!("Rule {} is firing", rule);
+                    }
+                    if let Some(alert) = alert {
+                        outgoing.push(Outgoing { alert, repeat: transition == Transition::Repeat });
+                    }
+                }
+                Transition::Resolved => {
+                    info!("Rule {} resolved", rule);
+                    if self.config.incidents.notify_resolved {
+                        let resolved = Alert::new(
+                            &rule,
+                            Severity::Ok,
+                            format!("{} is back to normal", rule),
+                            "-".to_string(),
+                            self.check_window(),
+                        )
+                        .with_state(IncidentState::Resolved)
+                        .with_rule_info(self.config.alert_rules.get(&rule));
+                        outgoing.push(Outgoing { alert: resolved, repeat: false });
+                    }
+                }
+                Transition::FlappingStarted => {
+                    warn!("Rule {} is flapping ({:.0}% state change)", rule, change);
+                    let summary = format!(
+                        "{} is flapping: {:.0}% state change over the last {} checks. Individual alerts are held until it settles.",
+                        rule, change, self.config.incidents.flap_history
+                    );
+                    let flapping = match alert {
+                        Some(alert) => Alert { summary, ..alert },
+                        None => Alert::new(&rule, Severity::Warning, summary, "-".to_string(), self.check_window())
+                            .with_rule_info(self.config.alert_rules.get(&rule)),
+                    }
+                    .with_state(IncidentState::Flapping);
+                    outgoing.push(Outgoing { alert: flapping, repeat: false });
+                }
+                Transition::FlappingStopped => {
+                    info!("Rule {} stopped flapping ({:.0}% state change)", rule, change);
+                    if let Some(alert) = alert {
+                        outgoing.push(Outgoing { alert, repeat: false });
+                    }
+                }
+            }
+        }
+        
+        self.dispatcher.set_firing(
+            self.state.state.incidents.iter()
+                .filter(|(_, rule)| matches!(rule.state, IncidentState::Firing | IncidentState::Flapping))
+                .map(|(name, _)| name.clone())
+                .collect()
+        );
+        let (outgoing, merged_rules) = correlation::correlate(&self.config.correlation, outgoing);
+        if !merged_rules.is_empty() {
+            info!("Correlated {} into one notification", merged_rules.join(", "));
+            // Their next repeats keep the cadence of the notification they were part of
+            let now = chrono::Utc::now();
+            for rule in merged_rules {
+                self.last_notified.insert(rule, now);
+            }
+        }
+        for item in outgoing {
+            self.send(item.alert, item.repeat);
+        }
+        for (alert, tier) in escalations {
+            self.events.emit("escalation", &alert);
+            self.dispatcher.escalate(alert, tier);
+        }
+        
+        // Forget rules that have been quiet for the whole history
+        self.state.state.incidents.retain(|_, rule_state| {
+            rule_state.state != IncidentState::Ok || rule_state.history.iter().any(|alerting| *alerting) || rule_state.ticket.is_some()
+        });
+        tickets
+    }
+    
+    /// Opens and resolves tickets. A ticket that could not be resolved is
+    /// put back and retried next cycle, a failed open is retried the same way.
+    async fn update_tickets(&mut self, updates: Vec<TicketUpdate>) {
+        let Some(tracker) = &self.tickets else {
+            return;
+        };
+        for update in updates {
+            match update {
+                TicketUpdate::Open { rule, alert, firing_since } => match tracker.open(&alert, firing_since).await {
+                    Ok(ticket) => {
+                        info!("Opened {} ticket {} for rule {}: {}", ticket.kind, ticket.key, rule, ticket.url);
+                        self.events.emit("ticket", &serde_json::json!({ "rule": rule, "action": "opened", "ticket": ticket }));
+                        if let Some(rule_state) = self.state.state.incidents.get_mut(&rule) {
+                            rule_state.ticket = Some(ticket);
+                        }
+                    }
+                    Err(e) => error!("Failed to open ticket for rule {}: {}", rule, e),
+                },
+                TicketUpdate::Resolve { rule, ticket } => match tracker.resolve(&ticket, &rule).await {
+                    Ok(()) => {
+                        info!("Resolved {} ticket {} for rule {}", ticket.kind, ticket.key, rule);
+                        self.events.emit("ticket", &serde_json::json!({ "rule": rule, "action": "resolved", "ticket": ticket }));
+                    }
+                    Err(e) => {
+                        error!("Failed to resolve ticket {} for rule {}: {}", ticket.key, rule, e);
+                        self.state.state.incidents.entry(rule).or_default().ticket = Some(ticket);
+                    }
+                },
+            }
+        }
+    }
+    
+    /// Describes the sampling window rules are evaluated over.
+    fn check_window(&self) -> String {
+        format!("single sample, evaluated every {}s", self.config.monitoring.check_interval)
+    }
+    
+    async fn check_server_cpu(&mut self) -> (bool, f64) {
+        info!("Checking server CPU usage...");
+        
+        let (is_high, cpu_usage) = self.server_monitor.check_cpu_threshold();
+        
+        if is_high {
+            warn!("High CPU usage detected: {:.2}%", cpu_usage);
+            
+            // Get high CPU containers
+            let container_stats = self.container_stats().await.unwrap_or_default();
+            let high_cpu_containers = match &self.docker_monitor {
+                Some(docker_monitor) => docker_monitor.filter_high_cpu(container_stats, 50.0).1,
+                None => Vec::new(),
+            };
+            
+            let top_processes = self.top_processes().await;
+            let monitoring = &self.config.monitoring;
+            let alert = Alert::new(
+                alert::RULE_CPU,
+                Severity::for_value(cpu_usage, monitoring.cpu_threshold, monitoring.cpu_critical_threshold),
+                format!("Host CPU usage {:.2}% exceeds {:.2}%", cpu_usage, monitoring.cpu_threshold),
+                format!("{:.2}% (critical {:.2}%)", monitoring.cpu_threshold, monitoring.cpu_critical_threshold),
+                self.check_window(),
+            )
+            .with_value(cpu_usage)
+            .with_containers(high_cpu_containers)
+            .with_top_processes(top_processes);
+            
+            // Send alert
+            self.raise(alert);
+        } else {
+            info!("Server CPU usage is normal: {:.2}%", cpu_usage);
+        }
+        self.check_component("Server CPU", !is_high, format!("{:.1}% CPU", cpu_usage));
+        
+        (is_high, cpu_usage)
+    }
+    
+    /// Returns the severity over the busiest hot core, the cores above
+    /// `cpu_cores.threshold` for `sustained_checks` and every core.
+    async fn check_hot_cores(&mut self, server_cpu: f64) -> (Severity, Vec<CpuCoreStats>, Vec<CpuCoreStats>) {
+        let (hot, cores) = self.server_monitor.check_hot_cores();
+        let config = &self.config.cpu_cores;
+        if config.threshold <= 0.0 {
+            return (Severity::Ok, hot, cores);
+        }
+        
+        let critical = if config.critical_threshold > 0.0 { config.critical_threshold } else { f64::INFINITY };
+        let peak = hot.iter().map(|core| core.usage).fold(0.0, f64::max);
+        let severity = if hot.is_empty() { Severity::Ok } else { Severity::for_value(peak, config.threshold, critical) };
+        if let [first, ..] = hot.as_slice() {
+            let summary = match hot.as_slice() {
+                [_] => format!(
+                    "CPU core {} at {:.2}% exceeds {:.2}% while the host average is {:.2}%",
+                    first.name, first.usage, config.threshold, server_cpu
+                ),
+                _ => format!(
+                    "{} CPU cores exceed {:.2}% while the host average is {:.2}%: {}",
+                    hot.len(), config.threshold, server_cpu,
+                    hot.iter().map(|core| core.name.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            };
+            let threshold = if config.critical_threshold > 0.0 {
+                format!("{:.2}% (critical {:.2}%)", config.threshold, config.critical_threshold)
+            } else {
+                format!("{:.2}%", config.threshold)
+            };
+            let window = format!("{} consecutive checks", config.sustained_checks.max(1));
+            let alert = Alert::new(alert::RULE_HOT_CORE, severity, summary, threshold, window)
+                .with_value(peak)
+                .with_notes(hot.iter().map(CpuCoreStats::describe).collect())
+                .with_top_processes(self.top_processes().await);
+            
+            self.raise(alert);
+        }
+        self.check_component("CPU cores", hot.is_empty(), format!("{} of {} cores hot", hot.len(), cores.len()));
+        
+        (severity, hot, cores)
+    }
+    
+    /// The top processes for CPU alerts, `monitoring.top_processes` of each.
+    /// Waits for a second process sample, since CPU usage needs two, on the
+    /// first call of a cycle only; the CPU, hot core and swap alerts share it.
+    async fn top_processes(&mut self) -> Vec<ProcessUsage> {
+        let count = self.config.monitoring.top_processes;
+        if count == 0 {
+            return Vec::new();
+        }
+        if let Some(processes) = &self.cycle_processes {
+            return processes.clone();
+        }
+        self.server_monitor.sample_processes();
+        tokio::time::sleep(server_monitor::PROCESS_SAMPLE_INTERVAL).await;
+        let processes = self.server_monitor.top_processes(count);
+        self.cycle_processes = Some(processes.clone());
+        processes
+    }
+    
+    /// Docker container stats, fetched on the first call of a cycle only;
+    /// the CPU, memory and container checks share them. Empty without Docker.
+    async fn container_stats(&mut self) -> Result<Vec<docker_monitor::ContainerStats>> {
+        let Some(docker_monitor) = &self.docker_monitor else {
+            return Ok(Vec::new());
+        };
+        if let Some(containers) = &self.cycle_containers {
+            return Ok(containers.clone());
+        }
+        let containers = docker_monitor.get_container_stats().await?;
+        self.cycle_containers = Some(containers.clone());
+        Ok(containers)
+    }
+    
+    async fn check_server_memory(&mut self) -> MemoryStats {
+        info!("Checking server memory usage...");
+        
+        let (is_high, memory) = self.server_monitor.check_memory_threshold();
+        
+        if is_high {
+            // The largest containers are the usual suspects
+            let mut largest = self.container_stats().await.unwrap_or_default();
+            largest.sort_by_key(|container| std::cmp::Reverse(container.memory_usage));
+            largest.truncate(5);
+            
+            let monitoring = &self.config.monitoring;
+            let alert = Alert::new(
+                alert::RULE_MEMORY,
+                Severity::for_value(memory.percent, monitoring.memory_threshold, monitoring.memory_critical_threshold),
+                format!("Host memory usage {:.2}% exceeds {:.2}%", memory.percent, monitoring.memory_threshold),
+                format!("{:.2}% (critical {:.2}%)", monitoring.memory_threshold, monitoring.memory_critical_threshold),
+                self.check_window(),
+            )
+            .with_value(memory.percent)
+            .with_containers(largest)
+            .with_notes(vec![format!(
+                "{} used of {}, {} available",
+                units::bytes(memory.used as f64), units::bytes(memory.total as f64), units::bytes(memory.available as f64)
+            )]);
+            
+            self.raise(alert);
+        }
+        self.check_component("Server memory", !is_high, format!("{:.1}% memory", memory.percent));
+        
+        memory
+    }
+    
+    /// Swapping usually comes before the CPU spikes, so the alert lists the
+    /// top processes and how full memory is.
+    async fn check_server_swap(&mut self, memory: &MemoryStats) -> SwapStats {
+        info!("Checking server swap usage...");
+        
+        let (is_high, swap) = self.server_monitor.check_swap_threshold();
+        let monitoring = &self.config.monitoring;
+        if monitoring.swap_threshold <= 0.0 {
+            return swap;
+        }
+        
+        if is_high {
+            let alert = Alert::new(
+                alert::RULE_SWAP,
+                Severity::for_value(swap.percent, monitoring.swap_threshold, monitoring.swap_critical_threshold),
+                format!("Host swap usage {:.2}% exceeds {:.2}%", swap.percent, monitoring.swap_threshold),
+                format!("{:.2}% (critical {:.2}%)", monitoring.swap_threshold, monitoring.swap_critical_threshold),
+                self.check_window(),
+            )
+            .with_value(swap.percent)
+            .with_notes(vec![
+                format!("{} of {} swap used", units::bytes(swap.used as f64), units::bytes(swap.total as f64)),
+                format!("Memory at {:.2}%, {} available", memory.percent, units::bytes(memory.available as f64)),
+            ])
+            .with_top_processes(self.top_processes().await);
+            
+            self.raise(alert);
+        }
+        self.check_component("Server swap", !is_high, format!("{:.1}% swap", swap.percent));
+        
+        swap
+    }
+    
+    /// Returns the severity over the mounts above their threshold, those
+    /// mounts, the root disk usage and every monitored mount.
+    async fn check_server_disk(&mut self) -> (Severity, Vec<String>, DiskStats, BTreeMap<String, DiskStats>) {
+        info!("Checking server disk usage...");
+        
+        let (full, disk, mounts) = self.server_monitor.check_disk_threshold();
+        let severity = full.iter()
+            .map(|mount_point| {
+                let (threshold, critical) = self.server_monitor.mount_thresholds(mount_point);
+                Severity::for_value(mounts[mount_point].percent, threshold, critical)
+            })
+            .max()
+            .unwrap_or(Severity::Ok);
+        let fullest = mounts.values().map(|mount| mount.percent).fold(disk.percent, f64::max);
+        
+        if let [first, ..] = full.as_slice() {
+            let monitoring = &self.config.monitoring;
+            let (threshold, _) = self.server_monitor.mount_thresholds(first);
+            let summary = match full.as_slice() {
+                [mount] => format!("Disk usage {:.2}% on {} exceeds {:.2}%", mounts[mount].percent, mount, threshold),
+                _ => format!("{} mounts exceed their disk threshold: {}", full.len(), full.join(", ")),
+            };
+            let overridden = !self.config.filesystems.thresholds.is_empty();
+            let mut listed: Vec<(&String, &DiskStats)> = mounts.iter().collect();
+            listed.sort_by(|a, b| b.1.percent.total_cmp(&a.1.percent));
+            let alert = Alert::new(
+                alert::RULE_DISK,
+                severity,
+                summary,
+                format!(
+                    "{:.2}% (critical {:.2}%){}",
+                    monitoring.disk_threshold, monitoring.disk_critical_threshold,
+                    if overridden { ", per-mount overrides in filesystems.thresholds" } else { "" }
+                ),
+                self.check_window(),
+            )
+            .with_value(full.iter().map(|mount| mounts[mount].percent).fold(0.0, f64::max))
+            .with_notes(listed.iter().map(|(mount_point, mount)| mount.describe(mount_point)).collect());
+            
+            self.raise(alert);
+        }
+        self.check_component("Server disk", full.is_empty(), format!("{:.1}% on the fullest mount", fullest));
+        
+        (severity, full, disk, mounts)
+    }
+    
+    /// Returns the severity over the busiest interface, the interfaces above
+    /// `interfaces.threshold_mbps` and every monitored interface.
+    fn check_server_network(&mut self) -> (Severity, Vec<NetworkStats>, Vec<NetworkStats>) {
+        info!("Checking network interfaces...");
+        
+        let (busy, interfaces) = self.server_monitor.check_network_threshold();
+        for stats in &interfaces {
+            self.events.emit("network_interface", stats);
+        }
+        let config = &self.config.interfaces;
+        if config.threshold_mbps <= 0.0 {
+            return (Severity::Ok, busy, interfaces);
+        }
+        
+        let critical = if config.critical_threshold_mbps > 0.0 { config.critical_threshold_mbps } else { f64::INFINITY };
+        let peak = busy.iter().filter_map(NetworkStats::peak_mbps).fold(0.0, f64::max);
+        let severity = if busy.is_empty() { Severity::Ok } else { Severity::for_value(peak, config.threshold_mbps, critical) };
+        if let [first, ..] = busy.as_slice() {
+            let summary = match busy.as_slice() {
+                [_] => format!("Bandwidth {:.2} Mbit/s on {} exceeds {:.2} Mbit/s", peak, first.interface, config.threshold_mbps),
+                _ => format!(
+                    "{} interfaces exceed {:.2} Mbit/s: {}",
+                    busy.len(), config.threshold_mbps,
+                    busy.iter().map(|stats| stats.interface.as_str()).collect::<Vec<_>>().join(", ")
+                ),
+            };
+            let threshold = if config.critical_threshold_mbps > 0.0 {
+                format!("{:.2} Mbit/s (critical {:.2} Mbit/s)", config.threshold_mbps, config.critical_threshold_mbps)
+            } else {
+                format!("{:.2} Mbit/s", config.threshold_mbps)
+            };
+            let alert = Alert::new(alert::RULE_BANDWIDTH, severity, summary, threshold, self.check_window())
+                .with_value(peak)
+                .with_notes(interfaces.iter().map(NetworkStats::describe).collect());
+            
+            self.raise(alert);
+        }
+        self.check_component("Network", busy.is_empty(), format!("{:.2} Mbit/s on the busiest interface", peak));
+        
+        (severity, busy, interfaces)
+    }
+    
+    /// Records a component result for the status page.
+    fn check_component(&mut self, name: impl Into<String>, healthy: bool, detail: String) {
+        self.status_checks.push(ComponentCheck {
+            name: name.into(),
+            healthy,
+            detail,
+        });
+    }
+    
+    /// Records started, stopped and image-changed containers since the last cycle.
+    fn track_inventory(&mut self, containers: &[docker_monitor::ContainerStats]) {
+        if !self.config.inventory.enabled {
+            return;
+        }
+        
+        let changes = inventory::update(&mut self.state.state.inventory, containers);
+        for change in &changes {
+            warn!("Inventory change: {}", change.describe(&self.timezone));
+        }
+        
+        let pending = &mut self.state.state.inventory_changes;
+        pending.extend(changes);
+        if pending.len() > self.config.inventory.max_changes {
+            let dropped = pending.len() - self.config.inventory.max_changes;
+            pending.drain(..dropped);
+        }
+    }
+    
+    /// Notes for alerting containers that were recently started or changed image.
+    fn inventory_notes(&self, containers: &[docker_monitor::ContainerStats]) -> Vec<String> {
+        self.state.state.inventory_changes.iter()
+            .filter(|change| change.kind != inventory::ChangeKind::Stopped)
+            .filter(|change| containers.iter().any(|container| container.name == change.name))
+            .map(|change| format!("Recent change: {}", change.describe(&self.timezone)))
+            .collect()
+    }
+    
+    /// Dependencies from the graph that are currently not running.
+    async fn down_dependencies(&self, graph: &DependencyGraph) -> HashSet<String> {
+        let Some(docker_monitor) = self.docker_monitor.as_ref().filter(|_| !graph.is_empty()) else {
+            return HashSet::new();
+        };
+        
+        match docker_monitor.container_states().await {
+            Ok(states) => {
+                let down = graph.down_dependencies(&states);
+                for dependency in &down {
+                    warn!("Dependency {} is down", dependency);
+                }
+                down
+            }
+            Err(e) => {
+                error!("Error listing container states: {}", e);
+                self.metrics.record_docker_error();
+                HashSet::new()
+            }
+        }
+    }
+    
+    async fn check_container_cpu(&mut self) -> (Vec<docker_monitor::ContainerStats>, usize, usize, usize, usize, usize) {
+        if self.docker_monitor.is_none() {
+            return (vec![], 0, 0, 0, 0, 0);
+        }
+        info!("Checking Docker container CPU usage...");
+        
+        match self.container_stats().await {
+            Ok(container_stats) => {
+                for container in &container_stats {
+                    self.events.emit("container_sample", container);
+                }
+                self.api.publish_containers(&self.redactor.containers(&container_stats));
+                self.track_inventory(&container_stats);
+                let (rules, leak_detector) = (&self.trend_rules, &self.leak_detector);
+                trend::record_containers(&mut self.state.state.trend_samples, &container_stats, chrono::Utc::now(), |name| {
+                    leak_detector.as_ref().is_some_and(|detector| detector.watches(name))
+                        || rules.iter().any(|rule| rule.watches_container(name))
+                });
+                self.state.state.history.record_containers(
+                    chrono::Utc::now(), &container_stats, self.config.history.top_containers, self.config.history.retention_days
+                );
+                let leaking = self.check_memory_leaks(&container_stats);
+                let graph = DependencyGraph::build(&self.config.dependencies, &container_stats);
+                let down = self.down_dependencies(&graph).await;
+                let suppress = self.config.dependencies.suppress_downstream;
+                
+                let running = container_stats.len();
+                let drifted = self.check_image_pins(&container_stats).await;
+                let over_budget = self.check_group_budgets(&container_stats);
+                let (unlimited, newcomers, high_cpu_containers) = match &self.docker_monitor {
+                    Some(docker_monitor) => (
+                        docker_monitor.unlimited_over_threshold(&container_stats),
+                        docker_monitor.newcomers_over_threshold(&container_stats),
+                        docker_monitor.filter_high_cpu(container_stats, self.config.monitoring.cpu_threshold).1,
+                    ),
+                    None => Default::default(),
+                };
+                
+                // Annotate or drop containers whose dependency is down
+                let (high_cpu_containers, high_cpu_notes, high_cpu_suppressed) = graph.correlate(high_cpu_containers, &down, suppress);
+                let (unlimited, unlimited_notes, unlimited_suppressed) = graph.correlate(unlimited, &down, suppress);
+                let suppressed = [high_cpu_suppressed, unlimited_suppressed].concat();
+                if !suppressed.is_empty() {
+                    info!("Suppressed alerts for {} containers with a down dependency: {}",
+                          suppressed.len(), suppressed.join("; "));
+                }
+                let is_high = !high_cpu_containers.is_empty();
+                let (unlimited_count, newcomer_count) = (unlimited.len(), newcomers.len());
+                self.check_component(
+                    "Docker containers",
+                    !is_high,
+                    format!("{} running, {} over CPU threshold", running, high_cpu_containers.len()),
+                );
+                let high_cpu_notes = [high_cpu_notes, self.inventory_notes(&high_cpu_containers)].concat();
+                
+                if is_high {
+                    warn!("High CPU usage detected in {} containers", high_cpu_containers.len());
+                    
+                    let monitoring = &self.config.monitoring;
+                    let top_cpu = high_cpu_containers.iter().map(|c| c.cpu_usage).fold(0.0, f64::max);
+                    let alert = Alert::new(
+                        alert::RULE_CONTAINER_CPU,
+                        Severity::for_value(top_cpu, monitoring.cpu_threshold, monitoring.cpu_critical_threshold),
+                        format!("{} containers exceed {:.2}% CPU", high_cpu_containers.len(), monitoring.cpu_threshold),
+                        format!("{:.2}% per container (critical {:.2}%)", monitoring.cpu_threshold, monitoring.cpu_critical_threshold),
+                        self.check_window(),
+                    )
+                    .with_value(top_cpu)
+                    .with_containers(high_cpu_containers.clone())
+                    .with_notes(high_cpu_notes);
+                    
+                    // Send alert
+                    self.raise(alert);
+                } else {
+                    info!("All containers have normal CPU usage");
+                }
+                
+                if !unlimited.is_empty() {
+                    warn!("{} containers without limits exceed the usage threshold", unlimited.len());
+                    
+                    let monitoring = &self.config.monitoring;
+                    let alert = Alert::new(
+                        alert::RULE_UNLIMITED_CONTAINER,
+                        Severity::Warning,
+                        format!("{} containers without limits exceed the usage threshold", unlimited.len()),
+                        format!("CPU > {:.2}% or memory > {} for containers without limits",
+                                monitoring.unlimited_container_cpu_threshold, units::mebibytes(monitoring.unlimited_container_memory_mb as f64)),
+                        self.check_window(),
+                    )
+                    .with_containers(unlimited)
+                    .with_notes(unlimited_notes);
+                    
+                    self.raise(alert);
+                }
+                
+                if !newcomers.is_empty() {
+                    warn!("{} recently created containers exceed the newcomer usage threshold", newcomers.len());
+                    
+                    let monitoring = &self.config.monitoring;
+                    let notes = self.inventory_notes(&newcomers);
+                    let alert = Alert::new(
+                        alert::RULE_NEWCOMER_CONTAINER,
+                        Severity::Critical,
+                        format!("{} containers created in the last {}s already exceed the usage threshold",
+                                newcomers.len(), monitoring.newcomer_max_age),
+                        format!("CPU > {:.2}% or memory > {:.2}% within {}s of creation",
+                                monitoring.newcomer_cpu_threshold, monitoring.newcomer_memory_threshold, monitoring.newcomer_max_age),
+                        self.check_window(),
+                    )
+                    .with_containers(newcomers)
+                    .with_notes(notes);
+                    
+                    self.raise(alert);
+                }
+                
+                (high_cpu_containers, unlimited_count, newcomer_count, drifted, leaking, over_budget)
+            }
+            Err(e) => {
+                error!("Error checking container CPU: {}", e);
+                self.metrics.record_docker_error();
+                self.check_component("Docker containers", false, "Docker API unavailable".to_string());
+                // Keep the incident state of rules that could not be evaluated
+                self.skipped_rules.insert(alert::RULE_CONTAINER_CPU.to_string());
+                self.skipped_rules.insert(alert::RULE_UNLIMITED_CONTAINER.to_string());
+                self.skipped_rules.insert(alert::RULE_NEWCOMER_CONTAINER.to_string());
+                self.skipped_rules.insert(alert::RULE_IMAGE_DRIFT.to_string());
+                self.skipped_rules.insert(alert::RULE_MEMORY_LEAK.to_string());
+                self.skipped_rules.insert(alert::RULE_GROUP_BUDGET.to_string());
+                (vec![], 0, 0, 0, 0, 0)
+            }
+        }
+    }
+    
+    /// Flags containers whose memory grew steadily over the leak window.
+    fn check_memory_leaks(&mut self, containers: &[docker_monitor::ContainerStats]) -> usize {
+        let Some(detector) = &self.leak_detector else {
+            return 0;
+        };
+        
+        let suspects = detector.detect(&self.state.state.trend_samples, containers, chrono::Utc::now());
+        if suspects.is_empty() {
+            return 0;
+        }
+        
+        warn!("{} containers show sustained memory growth", suspects.len());
+        let window = self.config.memory_leak.window.clone();
+        let alert = Alert::new(
+            alert::RULE_MEMORY_LEAK,
+            Severity::Warning,
+            format!("Possible memory leak in {} containers", suspects.len()),
+            format!("monotonic growth over {} at {:.1} MB/h or more", window, detector.threshold()),
+            window.clone(),
+        )
+        .with_value(suspects[0].slope_mb_per_hour)
+        .with_containers(suspects.iter().map(|suspect| suspect.container.clone()).collect())
+        .with_notes(suspects.iter().map(|suspect| suspect.describe(&window)).collect())
+        .with_charts(leak::charts(&suspects));
+        
+        self.raise(alert);
+        suspects.len()
+    }
+    
+    /// Alerts on label groups whose containers together exceed their budget.
+    fn check_group_budgets(&mut self, containers: &[docker_monitor::ContainerStats]) -> usize {
+        let config = self.config.clone();
+        let breaches = group_budget::over_budget(&config.container_groups, containers);
+        if breaches.is_empty() {
+            return 0;
+        }
+        
+        warn!("{} container groups exceed their budget", breaches.len());
+        self.budget_owners = breaches.iter().map(|breach| breach.group.name.clone()).collect();
+        let alert = Alert::new(
+            alert::RULE_GROUP_BUDGET,
+            Severity::Warning,
+            format!("{} container groups exceed their resource budget", breaches.len()),
+            "combined CPU and memory within the group budget".to_string(),
+            self.check_window(),
+        )
+        .with_value(breaches.iter().map(|breach| breach.percent_of_budget()).fold(0.0, f64::max))
+        .with_containers(breaches.iter().flat_map(|breach| breach.containers.clone()).collect())
+        .with_notes(breaches.iter().map(|breach| breach.describe()).collect());
+        
+        self.raise(alert);
+        breaches.len()
+    }
+    
+    /// Alerts on pinned containers that run a different image than expected.
+    async fn check_image_pins(&mut self, containers: &[docker_monitor::ContainerStats]) -> usize {
+        let Some(docker_monitor) = self.docker_monitor.as_ref().filter(|_| !self.image_pins.is_empty()) else {
+            return 0;
+        };
+        
+        let drifted = self.image_pins.drift(docker_monitor, containers).await;
+        if drifted.is_empty() {
+            info!("All pinned containers run their expected image");
+            return 0;
+        }
+        
+        warn!("{} containers drifted from their pinned image", drifted.len());
+        let alert = Alert::new(
+            alert::RULE_IMAGE_DRIFT,
+            Severity::Warning,
+            format!("{} containers are not running their pinned image", drifted.len()),
+            "image matches pin".to_string(),
+            self.check_window(),
+        )
+        .with_containers(drifted.iter().map(|drift| drift.container.clone()).collect())
+        .with_notes(drifted.iter().map(|drift| drift.describe()).collect());
+        
+        self.raise(alert);
+        drifted.len()
+    }
+    
+    async fn check_processes(&mut self) -> usize {
+        if !self.process_watchdog.is_enabled() {
+            return 0;
+        }
+        
+        info!("Checking watched processes...");
+        
+        let statuses = self.process_watchdog.check_processes().await;
+        for status in &statuses {
+            let detail = if status.running { "running" } else { "not running" };
+            self.check_component(format!("Process {}", status.name), status.running, detail.to_string());
+        }
+        let down: Vec<_> = statuses.into_iter()
+            .filter(|status| !status.running)
+            .collect();
+        
+        if down.is_empty() {
+            info!("All watched processes are running");
+            return 0;
+        }
+        
+        warn!("{} watched processes are not running", down.len());
+        let count = down.len();
+        let alert = Alert::new(
+            alert::RULE_PROCESS,
+            Severity::Critical,
+            format!("{} watched processes are not running", count),
+            "process must be running".to_string(),
+            self.check_window(),
+        )
+        .with_processes(down);
+        
+        self.raise(alert);
+        
+        count
+    }
+    
+    fn check_disk_latency(&mut self) -> usize {
+        let latencies = self.disk_latency.sample();
+        for latency in &latencies {
+            self.events.emit("disk_latency", latency);
+        }
+        
+        let slow = self.disk_latency.sustained(&latencies);
+        if slow.is_empty() {
+            return 0;
+        }
+        
+        warn!("{} disks with sustained high I/O latency", slow.len());
+        let config = &self.config.disk_latency;
+        let worst = slow.iter().map(|latency| latency.read_ms.max(latency.write_ms)).fold(0.0, f64::max);
+        let alert = Alert::new(
+            alert::RULE_DISK_LATENCY,
+            Severity::Warning,
+            format!("{} disks with high I/O latency", slow.len()),
+            format!("read > {:.0} ms or write > {:.0} ms", config.read_threshold_ms, config.write_threshold_ms),
+            format!("average per operation over {} consecutive checks", config.sustained_checks),
+        )
+        .with_value(worst)
+        .with_notes(slow.iter().map(|latency| latency.describe()).collect());
+        
+        self.raise(alert);
+        slow.len()
+    }
+    
+    /// Samples the CPU frequency and alerts when it stays low while the CPU
+    /// is busy.
+    fn check_cpu_frequency(&mut self, server_cpu: f64) -> Option<cpufreq::CpuFrequency> {
+        let Some(frequency) = self.cpu_frequency.sample(server_cpu) else {
+            if self.config.cpu_frequency.enabled {
+                self.skipped_rules.insert(alert::RULE_CPU_FREQUENCY.to_string());
+            }
+            return None;
+        };
+        self.events.emit("cpu_frequency", &frequency);
+        self.metrics.set_cpu_frequency(frequency.current_mhz);
+        
+        if !self.cpu_frequency.is_stuck(&frequency) {
+            info!("CPU frequency: {}", frequency.describe());
+            return Some(frequency);
+        }
+        
+        warn!("CPU stuck at low frequency: {}", frequency.describe());
+        let config = &self.config.cpu_frequency;
+        let mut notes = Vec::new();
+        if frequency.governors.iter().any(|governor| governor == "powersave") {
+            notes.push("The powersave governor is active; consider schedutil or performance on servers.".to_string());
+        }
+        if frequency.throttle_events > 0 {
+            notes.push(format!("{} thermal throttle events since the last check, check cooling and sensors.", frequency.throttle_events));
+        }
+        let alert = Alert::new(
+            alert::RULE_CPU_FREQUENCY,
+            Severity::Warning,
+            format!("CPU running at {:.0}% of its maximum frequency under {:.1}% load", frequency.percent_of_max, server_cpu),
+            format!("< {:.0}% of max frequency at >= {:.0}% CPU", config.min_frequency_percent, config.usage_threshold),
+            format!("{} consecutive checks", frequency.low_checks),
+        )
+        .with_value(frequency.current_mhz)
+        .with_notes([vec![frequency.describe()], notes].concat());
+        
+        self.raise(alert);
+        Some(frequency)
+    }
+    
+    /// Checks ZFS pool health and reports the ARC hit rate.
+    async fn check_zfs(&mut self) -> Severity {
+        if !self.zfs.is_enabled() {
+            return Severity::Ok;
+        }
+        
+        if let Some(arc) = self.zfs.arc() {
+            info!("ZFS {}", arc.describe());
+            self.events.emit("zfs_arc", &arc);
+        }
+        
+        info!("Checking ZFS pools...");
+        let pools = match self.zfs.pools().await {
+            Ok(pools) => pools,
+            Err(e) => {
+                error!("Error checking ZFS pools: {}", e);
+                self.skipped_rules.insert(alert::RULE_ZFS.to_string());
+                return Severity::Ok;
+            }
+        };
+        let unhealthy = self.zfs.unhealthy(&pools);
+        for pool in &pools {
+            self.events.emit("zfs_pool", pool);
+            let healthy = !unhealthy.iter().any(|other| other.name == pool.name);
+            self.check_component(format!("ZFS pool {}", pool.name), healthy, pool.describe());
+        }
+        if unhealthy.is_empty() {
+            info!("All {} ZFS pools are healthy", pools.len());
+            return Severity::Ok;
+        }
+        
+        let severity = if unhealthy.iter().any(|pool| pool.is_failed()) { Severity::Critical } else { Severity::Warning };
+        warn!("{} ZFS pools need attention", unhealthy.len());
+        let alert = Alert::new(
+            alert::RULE_ZFS,
+            severity,
+            format!("{} ZFS pools degraded, nearly full or reporting errors", unhealthy.len()),
+            format!("ONLINE, below {:.0}% capacity, no scrub or data errors", self.config.zfs.capacity_threshold),
+            self.check_window(),
+        )
+        .with_value(unhealthy.iter().map(|pool| pool.capacity_percent).fold(0.0, f64::max))
+        .with_notes(unhealthy.iter().map(|pool| pool.describe()).collect());
+        
+        self.raise(alert);
+        severity
+    }
+    
+    /// Checks Btrfs allocation and LVM thin pools, which fill up before the
+    /// filesystem percentage shows it.
+    async fn check_storage_pools(&mut self) -> Severity {
+        let config = &self.config.storage_pools;
+        if !config.btrfs && !config.lvm_thin {
+            return Severity::Ok;
+        }
+        
+        let pools = match storage_pools::sample(config).await {
+            Ok(pools) => pools,
+            Err(e) => {
+                error!("Error checking storage pools: {}", e);
+                self.skipped_rules.insert(alert::RULE_STORAGE_POOL.to_string());
+                return Severity::Ok;
+            }
+        };
+        
+        let config = &self.config.storage_pools;
+        let (threshold, critical_threshold) = (config.threshold, config.critical_threshold);
+        for pool in &pools {
+            self.events.emit("storage_pool", pool);
+            let healthy = pool.worst_percent() < threshold;
+            self.check_component(format!("{} {}", pool.kind, pool.name), healthy, pool.describe());
+        }
+        
+        let full: Vec<_> = pools.iter().filter(|pool| pool.worst_percent() >= threshold).collect();
+        if full.is_empty() {
+            info!("{} storage pools below {:.0}%", pools.len(), threshold);
+            return Severity::Ok;
+        }
+        
+        let worst = full.iter().map(|pool| pool.worst_percent()).fold(0.0, f64::max);
+        let severity = Severity::for_value(worst, threshold, critical_threshold);
+        warn!("{} storage pools nearly full", full.len());
+        let alert = Alert::new(
+            alert::RULE_STORAGE_POOL,
+            severity,
+            format!("{} Btrfs or LVM thin pools running out of data or metadata space", full.len()),
+            format!("{:.0}% (critical {:.0}%)", threshold, critical_threshold),
+            self.check_window(),
+        )
+        .with_value(worst)
+        .with_notes(full.iter().map(|pool| pool.describe()).collect());
+        
+        self.raise(alert);
+        severity
+    }
+    
+    /// Checks LXD instances against the container CPU thresholds.
+    async fn check_lxd_containers(&mut self) -> usize {
+        if !self.lxd.is_enabled() {
+            return 0;
+        }
+        
+        info!("Checking LXD instance CPU usage...");
+        let instances = match self.lxd.get_container_stats().await {
+            Ok(instances) => instances,
+            Err(e) => {
+                error!("Error checking LXD instances: {}", e);
+                self.check_component("LXD instances", false, "LXD API unavailable".to_string());
+                self.skipped_rules.insert(alert::RULE_LXD_CONTAINER.to_string());
+                return 0;
+            }
+        };
+        for instance in &instances {
+            self.events.emit("lxd_sample", instance);
+        }
+        
+        let monitoring = &self.config.monitoring;
+        let mut high: Vec<_> = instances.iter()
+            .filter(|instance| instance.cpu_usage > monitoring.cpu_threshold)
+            .cloned()
+            .collect();
+        high.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+        self.check_component(
+            "LXD instances",
+            high.is_empty(),
+            format!("{} running, {} over CPU threshold", instances.len(), high.len()),
+        );
+        if high.is_empty() {
+            info!("All LXD instances have normal CPU usage");
+            return 0;
+        }
+        
+        warn!("High CPU usage detected in {} LXD instances", high.len());
+        let monitoring = &self.config.monitoring;
+        let alert = Alert::new(
+            alert::RULE_LXD_CONTAINER,
+            Severity::for_value(high[0].cpu_usage, monitoring.cpu_threshold, monitoring.cpu_critical_threshold),
+            format!("{} LXD instances exceed {:.2}% CPU", high.len(), monitoring.cpu_threshold),
+            format!("{:.2}% per instance (critical {:.2}%)", monitoring.cpu_threshold, monitoring.cpu_critical_threshold),
+            self.check_window(),
+        )
+        .with_value(high[0].cpu_usage)
+        .with_containers(high.clone());
+        
+        self.raise(alert);
+        high.len()
+    }
+    
+    /// Samples libvirt guests and alerts on busy or crashed VMs.
+    async fn check_vms(&mut self) -> Severity {
+        if !self.libvirt.is_enabled() {
+            return Severity::Ok;
+        }
+        
+        info!("Checking virtual machines...");
+        let vms = match self.libvirt.sample().await {
+            Ok(vms) => vms,
+            Err(e) => {
+                error!("Error checking virtual machines: {}", e);
+                self.check_component("Virtual machines", false, "libvirt unavailable".to_string());
+                self.skipped_rules.insert(alert::RULE_VM.to_string());
+                return Severity::Ok;
+            }
+        };
+        for vm in &vms {
+            self.events.emit("vm_sample", vm);
+        }
+        
+        let busy = self.libvirt.over_threshold(&vms);
+        let crashed: Vec<_> = vms.iter().filter(|vm| vm.state == "crashed").cloned().collect();
+        let running = vms.iter().filter(|vm| vm.is_running()).count();
+        self.check_component(
+            "Virtual machines",
+            crashed.is_empty(),
+            format!("{} running, {} over threshold, {} crashed", running, busy.len(), crashed.len()),
+        );
+        if busy.is_empty() && crashed.is_empty() {
+            info!("All {} virtual machines are within thresholds", running);
+            return Severity::Ok;
+        }
+        
+        let severity = if crashed.is_empty() { Severity::Warning } else { Severity::Critical };
+        warn!("{} virtual machines over threshold, {} crashed", busy.len(), crashed.len());
+        let config = &self.config.libvirt;
+        let alert = Alert::new(
+            alert::RULE_VM,
+            severity,
+            format!("{} virtual machines over threshold, {} crashed", busy.len(), crashed.len()),
+            format!("CPU > {:.0}% of vCPUs or memory > {:.0}%", config.cpu_threshold, config.memory_threshold),
+            self.check_window(),
+        )
+        .with_value(busy.iter().filter_map(|vm| vm.cpu_percent).fold(0.0, f64::max))
+        .with_notes(crashed.iter().chain(&busy).map(|vm| vm.describe()).collect());
+        
+        self.raise(alert);
+        severity
+    }
+    
+    /// Reads the configured UPSes and alerts on power loss and low battery.
+    async fn check_ups(&mut self) -> Severity {
+        if self.config.ups.is_empty() {
+            return Severity::Ok;
+        }
+        
+        info!("Checking {} UPS...", self.config.ups.len());
+        let results = futures_util::future::join_all(
+            self.config.ups.iter().map(|ups| ups::query(ups, &self.config.network))
+        ).await;
+        
+        let names: Vec<String> = self.config.ups.iter().map(|ups| ups.name.clone()).collect();
+        let mut statuses = Vec::new();
+        for (name, result) in names.into_iter().zip(results) {
+            match result {
+                Ok(status) => {
+                    self.events.emit("ups", &status);
+                    self.check_component(format!("UPS {}", status.name), !status.on_battery, status.describe());
+                    statuses.push(status);
+                }
+                Err(e) => {
+                    warn!("Failed to read UPS {}: {}", name, e);
+                    self.check_component(format!("UPS {}", name), false, format!("unreachable: {}", e));
+                }
+            }
+        }
+        if statuses.is_empty() {
+            self.skipped_rules.insert(alert::RULE_UPS.to_string());
+            return Severity::Ok;
+        }
+        
+        let on_battery: Vec<_> = statuses.iter().filter(|status| status.on_battery || status.low_battery).collect();
+        if on_battery.is_empty() {
+            info!("All UPS on line power");
+            return Severity::Ok;
+        }
+        
+        let severity = if on_battery.iter().any(|status| status.low_battery) { Severity::Critical } else { Severity::Warning };
+        warn!("{} UPS on battery", on_battery.len());
+        let alert = Alert::new(
+            alert::RULE_UPS,
+            severity,
+            if severity == Severity::Critical {
+                format!("Low battery on {} of {} UPS running on battery",
+                        on_battery.iter().filter(|status| status.low_battery).count(), on_battery.len())
+            } else {
+                format!("Power loss: {} UPS running on battery", on_battery.len())
+            },
+            "line power (critical on low battery)".to_string(),
+            self.check_window(),
+        )
+        .with_notes(on_battery.iter().map(|status| status.describe()).collect());
+        
+        self.raise(alert);
+        severity
+    }
+    
+    /// Runs the command checks that are due. Checks that are not due keep
+    /// their last result.
+    async fn check_commands(&mut self) -> Severity {
+        if self.command_checks.is_empty() {
+            return Severity::Ok;
+        }
+        
+        let mut results = Vec::new();
+        for check in &mut self.command_checks {
+            let severity = check.severity();
+            results.push((severity, check.poll().await.clone()));
+        }
+        
+        for (_, result) in &results {
+            self.events.emit("command_check", result);
+            self.check_component(format!("command {}", result.name), !result.is_failure(), result.describe());
+        }
+        self.metrics.set_command_values(
+            results.iter().filter_map(|(_, result)| Some((result.name.clone(), result.value?))).collect()
+        );
+        
+        let failed: Vec<_> = results.iter().filter(|(_, result)| result.is_failure()).collect();
+        let Some(severity) = failed.iter().map(|(severity, _)| *severity).max() else {
+            info!("All {} command checks passed", results.len());
+            return Severity::Ok;
+        };
+        
+        warn!("{} command checks failed", failed.len());
+        let alert = Alert::new(
+            alert::RULE_COMMAND,
+            severity,
+            format!("{} of {} command checks failed", failed.len(), results.len()),
+            "exit code 0 and no alert pattern match".to_string(),
+            self.check_window(),
+        )
+        .with_notes(failed.iter().map(|(_, result)| result.describe()).collect());
+        
+        self.raise(alert);
+        severity
+    }
+    
+    /// Checks jobs recorded by `wrap` for failures and missed schedules.
+    fn check_cron_jobs(&mut self) -> Severity {
+        let jobs = self.cron_store.all();
+        if jobs.is_empty() && self.config.cron.jobs.is_empty() {
+            return Severity::Ok;
+        }
+        
+        let problems = cron::evaluate(&self.config.cron, &jobs, chrono::Utc::now(), self.started_at, &self.timezone);
+        for job in &jobs {
+            self.events.emit("cron_job", job);
+            let healthy = !problems.iter().any(|problem| problem.name == job.name);
+            self.check_component(format!("cron {}", job.name), healthy, job.describe(&self.timezone));
+        }
+        
+        let Some(severity) = problems.iter().map(|problem| problem.severity).max() else {
+            info!("All {} cron jobs on schedule", jobs.len());
+            return Severity::Ok;
+        };
+        
+        warn!("{} cron job problems", problems.len());
+        let alert = Alert::new(
+            alert::RULE_CRON,
+            severity,
+            format!("{} cron jobs failed or missed their schedule", problems.len()),
+            "exit code 0 within the expected schedule".to_string(),
+            self.check_window(),
+        )
+        .with_notes(problems.iter().map(|problem| problem.description.clone()).collect());
+        
+        self.raise(alert);
+        severity
+    }
+    
+    async fn check_ping(&mut self) -> (Severity, usize) {
+        if self.config.ping.targets.is_empty() {
+            return (Severity::Ok, 0);
+        }
+        
+        info!("Probing {} network targets...", self.config.ping.targets.len());
+        let results = futures_util::future::join_all(
+            self.config.ping.targets.iter().map(|target| ping::probe(target, &self.config.network))
+        ).await;
+        for result in &results {
+            self.events.emit("ping", result);
+            let healthy = !result.is_degraded(&self.config.ping);
+            self.check_component(format!("Network {}", result.name), healthy, result.describe());
+        }
+        
+        let degraded: Vec<_> = results.iter()
+            .filter(|result| result.is_degraded(&self.config.ping))
+            .collect();
+        if degraded.is_empty() {
+            info!("All network targets are reachable");
+            return (Severity::Ok, 0);
+        }
+        
+        warn!("{} network targets degraded", degraded.len());
+        let config = &self.config.ping;
+        let unreachable = degraded.iter().any(|result| result.received == 0);
+        let severity = if unreachable { Severity::Critical } else { Severity::Warning };
+        let alert = Alert::new(
+            alert::RULE_PING,
+            severity,
+            format!("{} network targets with high latency or packet loss", degraded.len()),
+            format!("latency > {:.0} ms or loss > {:.0}%", config.latency_threshold_ms, config.loss_threshold),
+            self.check_window(),
+        )
+        .with_notes(degraded.iter().map(|result| result.describe()).collect());
+        
+        self.raise(alert);
+        (severity, degraded.len())
+    }
+    
+    /// Compares service fingerprints with the recorded ones. The first
+    /// observation of a service is recorded without alerting.
+    async fn check_fingerprints(&mut self) -> usize {
+        let config = &self.config.fingerprints;
+        if config.services.is_empty() {
+            return 0;
+        }
+        
+        info!("Checking fingerprints of {} services...", config.services.len());
+        let timeout = Duration::from_millis(config.timeout_ms);
+        let results = futures_util::future::join_all(
+            config.services.iter().map(|service| fingerprint::probe(service, timeout, &self.config.network))
+        ).await;
+        
+        let mut changes = Vec::new();
+        for (service, result) in config.services.iter().zip(results) {
+            let current = match result {
+                Ok(current) => current,
+                Err(e) => {
+                    warn!("Failed to fingerprint {} ({}:{}): {}", service.name, service.host, service.port, e);
+                    continue;
+                }
+            };
+            self.events.emit("fingerprint", &serde_json::json!({
+                "name": service.name,
+                "kind": service.kind,
+                "fingerprint": current.fingerprint,
+                "banner": current.banner,
+            }));
+            
+            match self.state.state.fingerprints.get(&service.name) {
+                None => info!("Recorded {} fingerprint of {}: {}", service.kind, service.name, current.describe()),
+                Some(previous) if previous.differs_from(&current) => {
+                    warn!("{} fingerprint of {} changed", service.kind, service.name);
+                    changes.push(format!(
+                        "{} ({} {}:{}): {} → {} (known since {})",
+                        service.name, service.kind, service.host, service.port,
+                        previous.describe(), current.describe(), self.timezone.format(previous.first_seen)
+                    ));
+                }
+                Some(previous) => {
+                    // Same key behind a new banner, e.g. after an upgrade
+                    if previous.banner != current.banner {
+                        info!("{} banner of {} is now {}", service.kind, service.name, current.banner.as_deref().unwrap_or("empty"));
+                        let first_seen = previous.first_seen;
+                        self.state.state.fingerprints.insert(service.name.clone(), fingerprint::ServiceFingerprint { first_seen, ..current });
+                    }
+                    continue;
+                }
+            }
+            self.state.state.fingerprints.insert(service.name.clone(), current);
+        }
+        
+        if changes.is_empty() {
+            return 0;
+        }
+        
+        let count = changes.len();
+        let alert = Alert::new(
+            alert::RULE_FINGERPRINT,
+            Severity::Critical,
+            format!("{} services presented a new certificate or host key", count),
+            "unchanged fingerprint".to_string(),
+            self.check_window(),
+        )
+        .with_notes(changes);
+        
+        self.raise(alert);
+        count
+    }
+    
+    async fn check_outbound(&mut self) -> usize {
+        let Some(outbound) = self.outbound.as_mut() else {
+            return 0;
+        };
+        
+        info!("Checking outbound connections...");
+        let containers = match (&self.docker_monitor, outbound.wants_containers()) {
+            (Some(docker_monitor), true) => docker_monitor.container_pids().await.unwrap_or_else(|e| {
+                warn!("Failed to list containers for outbound connections: {}", e);
+                Vec::new()
+            }),
+            _ => Vec::new(),
+        };
+        let hits = outbound.check(&containers).await;
+        if hits.is_empty() {
+            info!("No connections to watchlisted addresses");
+            return 0;
+        }
+        
+        for hit in &hits {
+            self.events.emit("outbound_connection", &serde_json::json!({
+                "source": hit.connection.source,
+                "local": hit.connection.local,
+                "remote": hit.connection.remote,
+                "watchlist": hit.watchlist,
+            }));
+        }
+        let sources: HashSet<&str> = hits.iter().map(|hit| hit.connection.source.as_str()).collect();
+        let alert = Alert::new(
+            alert::RULE_OUTBOUND,
+            Severity::Critical,
+            format!("{} connections to watchlisted addresses from {}", hits.len(), sources.into_iter().collect::<Vec<_>>().join(", ")),
+            "no connections to watchlisted addresses".to_string(),
+            self.check_window(),
+        )
+        .with_notes(hits.iter().map(|hit| hit.describe(&self.timezone)).collect());
+        
+        self.raise(alert);
+        hits.len()
+    }
+    
+    /// Records this cycle's host samples and evaluates the rate-of-change
+    /// rules against the sample buffer.
+    fn check_trends(&mut self, server_cpu: f64) -> Vec<trend::TrendBreach> {
+        let now = chrono::Utc::now();
+        let windows = self.trend_rules.iter()
+            .map(|rule| rule.window)
+            .chain(self.leak_detector.as_ref().map(|detector| detector.window));
+        self.state.state.trend_samples.prune(now, trend::retention(windows));
+        if self.trend_rules.is_empty() {
+            return Vec::new();
+        }
+        
+        let watches = |metric: &str| self.trend_rules.iter().any(|rule| rule.config.metric == metric);
+        let (cpu, memory, disk) = (watches("cpu"), watches("memory"), watches("disk"));
+        let samples = &mut self.state.state.trend_samples;
+        if cpu {
+            samples.record("cpu", now, server_cpu);
+        }
+        if memory {
+            samples.record("memory", now, self.server_monitor.get_memory_usage().percent);
+        }
+        if disk {
+            samples.record("disk", now, self.server_monitor.get_disk_usage().percent);
+        }
+        
+        let breaches = trend::evaluate(&self.trend_rules, &self.state.state.trend_samples, now);
+        for breach in &breaches {
+            self.events.emit("trend", breach);
+        }
+        if breaches.is_empty() {
+            return breaches;
+        }
+        
+        warn!("{} trend rules matched", breaches.len());
+        let severity = breaches.iter().map(|breach| breach.severity).max().unwrap_or(Severity::Warning);
+        let mut rules: Vec<&str> = breaches.iter().map(|breach| breach.rule.as_str()).collect();
+        rules.dedup();
+        let alert = Alert::new(
+            alert::RULE_TREND,
+            severity,
+            format!("{} metrics changing faster than their trend rules allow", breaches.len()),
+            format!("trend rules: {}", rules.join(", ")),
+            self.check_window(),
+        )
+        .with_value(breaches.iter().map(|breach| breach.slope_per_hour).fold(f64::MIN, f64::max))
+        .with_notes(breaches.iter().map(|breach| breach.description.clone()).collect());
+        
+        self.raise(alert);
+        breaches
+    }
+    
+    /// Returns the conntrack severity, or `None` when the check is disabled or
+    /// the table is unavailable.
+    fn check_conntrack(&mut self) -> Option<(Severity, conntrack::ConntrackUsage)> {
+        if !self.config.conntrack.enabled {
+            return None;
+        }
+        
+        let Some(usage) = conntrack::read_usage() else {
+            self.skipped_rules.insert(alert::RULE_CONNTRACK.to_string());
+            return None;
+        };
+        self.events.emit("conntrack", &usage);
+        
+        let config = &self.config.conntrack;
+        let severity = Severity::for_value(usage.percent, config.threshold, config.critical_threshold);
+        if severity == Severity::Ok {
+            info!("Conntrack table usage is normal: {}/{} ({:.2}%)", usage.count, usage.max, usage.percent);
+            return Some((severity, usage));
+        }
+        
+        warn!("Conntrack table usage high: {}/{} ({:.2}%)", usage.count, usage.max, usage.percent);
+        let alert = Alert::new(
+            alert::RULE_CONNTRACK,
+            severity,
+            format!("Conntrack table at {:.2}% ({} of {} entries)", usage.percent, usage.count, usage.max),
+            format!("{:.2}% (critical {:.2}%)", config.threshold, config.critical_threshold),
+            self.check_window(),
+        )
+        .with_value(usage.percent);
+        
+        self.raise(alert);
+        Some((severity, usage))
+    }
+    
+    /// Records this cycle's component results and re-renders the status page.
+    fn update_status_page(&mut self) {
+        let config = &self.config.statuspage;
+        if !config.enabled {
+            return;
+        }
+        
+        let history = &mut self.state.state.status_page;
+        history.record(&self.status_checks, chrono::Utc::now(), config.history_days);
+        history.incidents = self.state.state.incidents.iter()
+            .filter(|(_, rule_state)| matches!(rule_state.state, IncidentState::Firing | IncidentState::Flapping))
+            .map(|(rule, rule_state)| {
+                let info = self.config.alert_rules.get(rule);
+                statuspage::StatusIncident {
+                    rule: rule.clone(),
+                    since: rule_state.since,
+                    description: info.and_then(|info| info.description.clone()),
+                    runbook_url: info.and_then(|info| info.runbook_url.clone()),
+                }
+            })
+            .collect();
+        history.incidents.sort_by(|a, b| a.rule.cmp(&b.rule));
+        let html = statuspage::render(config, history, &self.timezone);
+        if !config.output.is_empty() {
+            if let Err(e) = statuspage::write(&config.output, &html) {
+                error!("Failed to write status page to {}: {}", config.output, e);
+            }
+        }
+        self.status_page.publish(html);
+    }
+    
+    /// Sends scheduled trend reports whose time passed since they were last
+    /// sent. A newly configured report waits for its first scheduled time.
+    async fn send_reports_if_due(&mut self) {
+        self.collect_reports(false).await;
+        let now = chrono::Utc::now();
+        
+        for config in &self.config.reports {
+            if self.pending_reports.contains_key(&config.name) {
+                continue;
+            }
+            let last_sent = *self.state.state.reports_last_sent.entry(config.name.clone()).or_insert(now);
+            match report::is_due(config, last_sent, now) {
+                Ok(true) => {}
+                Ok(false) => continue,
+                Err(e) => {
+                    warn!("Skipping report {}: {}", config.name, e);
+                    continue;
+                }
+            }
+            
+            let built = match report::build(config, &self.state.state.history, now) {
+                Ok(built) => built,
+                Err(e) => {
+                    error!("Failed to build report {}: {}", config.name, e);
+                    continue;
+                }
+            };
+            
+            info!("Sending scheduled report {}", config.name);
+            self.pending_reports.insert(config.name.clone(), (self.dispatcher.send_report(built), now));
+        }
+    }
+    
+    /// Records the reports the dispatcher delivered as sent, waiting for the
+    /// ones still in flight with `wait`.
+    async fn collect_reports(&mut self, wait: bool) {
+        let done: Vec<String> = self.pending_reports.iter()
+            .filter(|(_, (task, _))| wait || task.is_finished())
+            .map(|(name, _)| name.clone())
+            .collect();
+        for name in done {
+            let Some((task, built_at)) = self.pending_reports.remove(&name) else {
+                continue;
+            };
+            if task.await.unwrap_or(false) {
+                self.state.state.reports_last_sent.insert(name, built_at);
+            }
+        }
+    }
+    
+    async fn send_digest_if_due(&mut self) {
+        self.collect_digest(false).await;
+        if self.pending_digest.is_some() || !digest::is_due(&self.config.digest, self.state.state.digest_last_sent) {
+            return;
+        }
+        
+        let mut sections = Vec::new();
+        let now = chrono::Utc::now();
+        
+        let cost_due = cost_report::is_due(
+            &self.config.cost,
+            self.state.state.cost_report_last_sent,
+            &self.state.state.utilization,
+        );
+        if cost_due {
+            if let Some(section) = cost_report::right_sizing_section(
+                &self.config.cost,
+                self.cloud_metadata.as_ref(),
+                &self.state.state.utilization,
+            ) {
+                sections.push(section);
+            }
+        }
+        
+        let audit = self.config.resource_audit.enabled;
+        if let Some(docker_monitor) = self.docker_monitor.as_ref().filter(|docker| audit && docker.capabilities().can_audit()) {
+            match docker_monitor.audit_resources().await {
+                Ok(audit) => {
+                    let pruned = match &self.docker_control {
+                        Some(control) if self.config.resource_audit.auto_prune => {
+                            let exclude = resource_audit::exclude_patterns(&self.config.resource_audit);
+                            let min_age = resource_audit::prune_min_age(&self.config.resource_audit).unwrap_or(chrono::Duration::MAX);
+                            let pruned = control.prune_resources(&audit, &exclude, min_age).await;
+                            for resource in &pruned {
+                                audit::record(AuditEntry::new(
+                                    AuditAction::Remediation, "monitor", resource, "removed by resource_audit.auto_prune"
+                                ));
+                            }
+                            pruned
+                        }
+                        _ => Vec::new(),
+                    };
+                    sections.push(resource_audit::audit_section(&audit, &pruned));
+                }
+                Err(e) => {
+                    error!("Error auditing Docker resources: {}", e);
+                    self.metrics.record_docker_error();
+                }
+            }
+        }
+        
+        #[cfg(feature = "docker")]
+        if let Some(docker_monitor) = self.docker_monitor.as_ref().filter(|_| self.config.security_audit.enabled) {
+            match docker_monitor.get_container_info().await {
+                Ok(containers) => {
+                    let findings = security_audit::audit(&self.config.security_audit, &containers);
+                    sections.push(security_audit::security_section(&findings, containers.len()));
+                }
+                Err(e) => {
+                    error!("Error auditing container security: {}", e);
+                    self.metrics.record_docker_error();
+                }
+            }
+        }
+        
+        if self.config.inventory.enabled {
+            sections.push(inventory::changes_section(&self.state.state.inventory_changes, &self.timezone));
+        }
+        
+        if sections.is_empty() {
+            return;
+        }
+        
+        info!("Sending monitoring digest with {} sections", sections.len());
+        self.pending_digest = Some(PendingDigest { task: self.dispatcher.send_digest(sections), built_at: now, cost_due });
+    }
+    
+    /// Records the digest as sent once the dispatcher delivered it, waiting
+    /// for it with `wait`. Inventory changes seen since it was built are kept
+    /// for the next one.
+    async fn collect_digest(&mut self, wait: bool) {
+        let Some(pending) = self.pending_digest.take_if(|pending| wait || pending.task.is_finished()) else {
+            return;
+        };
+        if !pending.task.await.unwrap_or(false) {
+            error!("Failed to send monitoring digest");
+            return;
+        }
+        self.state.state.digest_last_sent = Some(pending.built_at);
+        self.state.state.inventory_changes.retain(|change| change.timestamp > pending.built_at);
+        if pending.cost_due {
+            self.state.state.cost_report_last_sent = Some(pending.built_at);
+            self.state.state.utilization = Default::default();
+        }
+    }
+    
+    /// Records the monitor's own resident memory and warns once when it grows
+    /// past `monitoring.memory_budget_mb`.
+    fn check_memory_budget(&mut self) {
+        let Some(memory) = metrics::process_memory() else {
+            return;
+        };
+        self.metrics.set_process_memory(memory);
+        
+        let budget = self.config.monitoring.memory_budget_mb * 1024 * 1024;
+        let over = budget > 0 && memory.rss > budget;
+        if over && !self.over_memory_budget {
+            warn!(
+                "Monitor memory {} is over the {} budget (peak {})",
+                units::bytes(memory.rss as f64), units::bytes(budget as f64), units::bytes(memory.peak_rss as f64)
+            );
+        } else if !over && self.over_memory_budget {
+            info!("Monitor memory back under budget at {}", units::bytes(memory.rss as f64));
+        }
+        self.over_memory_budget = over;
+    }
+    
+    /// Rebuilds the effective config when threshold overrides were set, reset
+    /// or expired since the last cycle.
+    fn apply_thresholds(&mut self) {
+        let active = self.api.thresholds.active(chrono::Utc::now());
+        if active == self.applied_thresholds {
+            return;
+        }
+        match thresholds::apply(&self.base_config, &active) {
+            Ok(config) => {
+                let before = thresholds::configured(&self.config);
+                for (name, value) in thresholds::configured(&config) {
+                    if !before.contains(&(name, value)) {
+                        info!("Threshold {} is now {}", name, value);
+                    }
+                }
+                self.config = Arc::new(config);
+                self.server_monitor.set_config(self.config.clone());
+                self.applied_thresholds = active;
+            }
+            Err(e) => error!("Failed to apply threshold overrides: {}", e),
+        }
+    }
+    
+    /// Runs one monitoring cycle inside a `cycle` span, so every log record it
+    /// writes carries the cycle id and the final one its duration.
+    async fn run_monitoring(&mut self) -> Result<CheckOutcome> {
+        self.cycle_id += 1;
+        let span = tracing::info_span!("cycle", id = self.cycle_id, duration_ms = tracing::field::Empty);
+        let started = std::time::Instant::now();
+        let outcome = self.monitoring_cycle().instrument(span.clone()).await;
+        let elapsed = started.elapsed();
+        span.record("duration_ms", elapsed.as_millis() as u64);
+        span.in_scope(|| info!("Monitoring check finished in {:?}", elapsed));
+        outcome
+    }
+    
+    async fn monitoring_cycle(&mut self) -> Result<CheckOutcome> {
+        info!("Starting monitoring check...");
+        let started = std::time::Instant::now();
+        self.cycle_alerts.clear();
+        self.cycle_processes = None;
+        self.cycle_containers = None;
+        self.skipped_rules.clear();
+        self.status_checks.clear();
+        self.apply_thresholds();
+        
+        // Check server CPU
+        let (_, server_cpu) = self.check_server_cpu().await;
+        let (hot_core_severity, hot_cores, cpu_cores) = self.check_hot_cores(server_cpu).await;
+        
+        // Check server memory
+        let memory = self.check_server_memory().await;
+        let swap = self.check_server_swap(&memory).await;
+        
+        // Check server disks
+        let (disk_severity, full_mounts, disk, mounts) = self.check_server_disk().await;
+        
+        // Check network interfaces
+        let (network_severity, busy_interfaces, network) = self.check_server_network();
+        
+        // Check CPU frequency
+        let cpu_frequency = self.check_cpu_frequency(server_cpu);
+        
+        // Check container CPU
+        let (high_containers, unlimited_containers, newcomer_containers, drifted_containers, leaking_containers, groups_over_budget) =
+            self.check_container_cpu().await;
+        
+        // Check LXD instances
+        let high_lxd = self.check_lxd_containers().await;
+        
+        // Check virtual machines
+        let vm_severity = self.check_vms().await;
+        
+        // Check watched processes
+        let processes_down = self.check_processes().await;
+        
+        // Check ZFS pools
+        let zfs_severity = self.check_zfs().await;
+        
+        // Check Btrfs and LVM thin pools
+        let storage_pool_severity = self.check_storage_pools().await;
+        
+        // Check disk latency
+        let slow_disks = self.check_disk_latency();
+        
+        // Check conntrack table usage
+        let conntrack = self.check_conntrack();
+        
+        // Check UPS power state
+        let ups_severity = self.check_ups().await;
+        
+        // Run custom command checks
+        let command_severity = self.check_commands().await;
+        
+        // Check wrapped cron jobs
+        let cron_severity = self.check_cron_jobs();
+        
+        // Probe network targets
+        let (ping_severity, degraded_targets) = self.check_ping().await;
+        
+        // Compare service certificates and host keys
+        let changed_fingerprints = self.check_fingerprints().await;
+        
+        // Match outbound connections against watchlists
+        let watchlisted_connections = self.check_outbound().await;
+        
+        // Evaluate rate-of-change rules
+        let trend_breaches = self.check_trends(server_cpu);
+        
+        // Decide which alerts to send
+        let tickets = self.evaluate_incidents(None);
+        self.update_tickets(tickets).await;
+        self.api.publish_firing(self.firing_rules().into_iter().map(str::to_string).collect());
+        
+        // Log summary
+        info!("Monitoring check completed. Server CPU: {:.2}%, High CPU containers: {}", 
+              server_cpu, high_containers.len());
+        let dispatch = self.dispatcher.stats();
+        info!("Notification queue: {} queued, {} in flight, {} sent, {} failed, {} dropped, {} merged, {} inhibited",
+              dispatch.depth, dispatch.in_flight, dispatch.sent, dispatch.failed, dispatch.dropped, dispatch.merged, dispatch.inhibited);
+        
+        let memory_percent = memory.percent;
+        self.events.emit("host_sample", &serde_json::json!({
+            "cpu_percent": server_cpu,
+            "memory_percent": memory_percent,
+        }));
+        let traffic = self.traffic.sample();
+        for family in &traffic {
+            info!("Network {}", family.describe());
+            self.events.emit("network_traffic", family);
+        }
+        self.state.state.utilization.record(server_cpu, memory_percent);
+        let disk_percent = disk.percent;
+        self.state.state.history.record(
+            chrono::Utc::now(), server_cpu, memory_percent, disk_percent, self.config.history.retention_days
+        );
+        self.history_page.publish(&self.state.state.history);
+        self.api.publish_server(ServerStats {
+            timestamp: chrono::Utc::now(),
+            cpu_usage: server_cpu,
+            cpu_cores,
+            memory_usage: memory,
+            swap_usage: swap.clone(),
+            disk_usage: disk,
+            mounts,
+            network,
+            load_average: self.server_monitor.get_load_average(),
+            system_info: self.server_monitor.get_system_info(),
+        });
+        self.scaling_hooks.evaluate(&mut self.state.state, server_cpu, memory_percent).await;
+        self.state.state.last_cycle = Some(chrono::Utc::now());
+        
+        self.send_digest_if_due().await;
+        self.send_reports_if_due().await;
+        self.update_status_page();
+        self.push_monitors.push(&self.status_checks, started.elapsed()).await;
+        self.metrics.record_cycle(started.elapsed());
+        self.metrics.set_host_usage(server_cpu, memory_percent);
+        self.metrics.set_network_traffic(traffic);
+        self.check_memory_budget();
+        self.state.state.runtime_metrics = self.metrics.snapshot();
+        self.state.state.silences = self.api.silences.snapshot(chrono::Utc::now());
+        self.state.state.thresholds = self.api.thresholds.snapshot(chrono::Utc::now());
+        self.state.state.recent_commands = self.api.handled_commands();
+        self.state.save();
+        
+        // Summarize the cycle for exit codes and plugin output
+        let monitoring = &self.config.monitoring;
+        let mut outcome = CheckOutcome::default();
+        
+        let cpu_severity = Severity::for_value(server_cpu, monitoring.cpu_threshold, monitoring.cpu_critical_threshold);
+        if cpu_severity != Severity::Ok {
+            outcome.add_problem(cpu_severity, format!("CPU {:.2}% (threshold {:.0}%)", server_cpu, monitoring.cpu_threshold));
+        }
+        
+        if monitoring.memory_threshold > 0.0 {
+            let memory_severity = Severity::for_value(memory_percent, monitoring.memory_threshold, monitoring.memory_critical_threshold);
+            if memory_severity != Severity::Ok {
+                outcome.add_problem(memory_severity, format!("memory {:.2}% (threshold {:.0}%)", memory_percent, monitoring.memory_threshold));
+            }
+        }
+        
+        if monitoring.swap_threshold > 0.0 {
+            let swap_severity = Severity::for_value(swap.percent, monitoring.swap_threshold, monitoring.swap_critical_threshold);
+            if swap_severity != Severity::Ok {
+                outcome.add_problem(swap_severity, format!("swap {:.2}% (threshold {:.0}%)", swap.percent, monitoring.swap_threshold));
+            }
+        }
+        
+        if disk_severity != Severity::Ok {
+            outcome.add_problem(disk_severity, format!("disk usage above threshold on {}", full_mounts.join(", ")));
+        }
+        
+        if hot_core_severity != Severity::Ok {
+            let names: Vec<&str> = hot_cores.iter().map(|core| core.name.as_str()).collect();
+            outcome.add_problem(hot_core_severity, format!(
+                "CPU cores above {:.0}% on {}", self.config.cpu_cores.threshold, names.join(", ")
+            ));
+        }
+        
+        if network_severity != Severity::Ok {
+            let names: Vec<&str> = busy_interfaces.iter().map(|stats| stats.interface.as_str()).collect();
+            outcome.add_problem(network_severity, format!(
+                "bandwidth above {:.0} Mbit/s on {}", self.config.interfaces.threshold_mbps, names.join(", ")
+            ));
+        }
+        
+        if let Some(top) = high_containers.first() {
+            let severity = Severity::for_value(top.cpu_usage, monitoring.cpu_threshold, monitoring.cpu_critical_threshold);
+            outcome.add_problem(severity, format!(
+                "{} containers above CPU threshold (top: {} {:.2}%)",
+                high_containers.len(), top.name, top.cpu_usage
+            ));
+        }
+        
+        if unlimited_containers > 0 {
+            outcome.add_problem(Severity::Warning, format!("{} unlimited containers over usage threshold", unlimited_containers));
+        }
+        
+        if newcomer_containers > 0 {
+            outcome.add_problem(Severity::Critical, format!("{} new containers over usage threshold", newcomer_containers));
+        }
+        
+        if let Some(frequency) = cpu_frequency.as_ref().filter(|frequency| self.cpu_frequency.is_stuck(frequency)) {
+            outcome.add_problem(Severity::Warning, format!("CPU at {:.0}% of max frequency under load", frequency.percent_of_max));
+        }
+        
+        if leaking_containers > 0 {
+            outcome.add_problem(Severity::Warning, format!("{} containers with possible memory leaks", leaking_containers));
+        }
+        
+        if drifted_containers > 0 {
+            outcome.add_problem(Severity::Warning, format!("{} containers not running their pinned image", drifted_containers));
+        }
+        
+        if groups_over_budget > 0 {
+            outcome.add_problem(Severity::Warning, format!("{} container groups over budget", groups_over_budget));
+        }
+        
+        if let Some((severity, usage)) = &conntrack {
+            if *severity != Severity::Ok {
+                outcome.add_problem(*severity, format!("conntrack {:.2}% ({}/{})", usage.percent, usage.count, usage.max));
+            }
+        }
+        
+        if high_lxd > 0 {
+            outcome.add_problem(Severity::Warning, format!("{} LXD instances above CPU threshold", high_lxd));
+        }
+        
+        if vm_severity != Severity::Ok {
+            outcome.add_problem(vm_severity, "virtual machines over threshold or crashed".to_string());
+        }
+        
+        if ups_severity != Severity::Ok {
+            outcome.add_problem(ups_severity, "UPS running on battery".to_string());
+        }
+        
+        if command_severity != Severity::Ok {
+            outcome.add_problem(command_severity, "command checks failed".to_string());
+        }
+        
+        if cron_severity != Severity::Ok {
+            outcome.add_problem(cron_severity, "cron jobs failed or missed their schedule".to_string());
+        }
+        
+        if degraded_targets > 0 {
+            outcome.add_problem(ping_severity, format!("{} network targets degraded", degraded_targets));
+        }
+        
+        if changed_fingerprints > 0 {
+            outcome.add_problem(Severity::Critical, format!("{} service fingerprints changed", changed_fingerprints));
+        }
+        
+        if watchlisted_connections > 0 {
+            outcome.add_problem(Severity::Critical, format!("{} connections to watchlisted addresses", watchlisted_connections));
+        }
         
-        // Initialize monitors
-        let server_monitor = ServerMonitor::new(config.clone());
-        let docker_monitor = match DockerMonitor::new(config.clone()).await {
-            Ok(monitor) => {
-                info!("Docker monitor initialized successfully");
-                monitor
-            }
-            Err(e) => {
-                error!("Failed to initialize Docker monitor: {}", e);
-                return Err(e);
-            }
-        };
-        let email_notifier = EmailNotifier::new(config.clone());
+        if let Some(severity) = trend_breaches.iter().map(|breach| breach.severity).max() {
+            outcome.add_problem(severity, format!("{} trend rules matched", trend_breaches.len()));
+        }
         
-        info!("Performance Monitor initialized");
-        info!("CPU Threshold: {}%", config.monitoring.cpu_threshold);
+        if zfs_severity != Severity::Ok {
+            outcome.add_problem(zfs_severity, "ZFS pools need attention".to_string());
+        }
         
-        Ok(Self {
-            config,
-            server_monitor,
-            docker_monitor,
-            email_notifier,
-        })
-    }
-    
-    async fn check_server_cpu(&mut self) -> (bool, f64) {
-        info!("Checking server CPU usage...");
+        if storage_pool_severity != Severity::Ok {
+            outcome.add_problem(storage_pool_severity, "storage pools running out of space".to_string());
+        }
         
-        let (is_high, cpu_usage) = self.server_monitor.check_cpu_threshold();
+        if slow_disks > 0 {
+            outcome.add_problem(Severity::Warning, format!("{} disks with high I/O latency", slow_disks));
+        }
         
-        if is_high {
-            warn!("High CPU usage detected: {:.2}%", cpu_usage);
-            
-            // Get high CPU containers
-            let (_, high_cpu_containers) = self.docker_monitor
-                .check_container_cpu_threshold(50.0)
-                .await
-                .unwrap_or((false, vec![]));
-            
-            // Send alert
-            let alert_sent = self.email_notifier.send_cpu_alert(cpu_usage, &high_cpu_containers).await;
-            if alert_sent {
-                info!("CPU alert email sent successfully");
-            } else {
-                error!("Failed to send CPU alert email");
-            }
-        } else {
-            info!("Server CPU usage is normal: {:.2}%", cpu_usage);
+        if processes_down > 0 {
+            outcome.add_problem(Severity::Critical, format!("{} watched processes down", processes_down));
         }
         
-        (is_high, cpu_usage)
+        if !self.skipped_rules.is_empty() {
+            let mut skipped: Vec<&str> = self.skipped_rules.iter().map(String::as_str).collect();
+            skipped.sort_unstable();
+            outcome.add_unknown(format!("unable to evaluate {}", skipped.join(", ")));
+        }
+        
+        outcome.perfdata = vec![
+            format!("cpu={:.2}%;{};{}", server_cpu, monitoring.cpu_threshold, monitoring.cpu_critical_threshold),
+            format!("memory={:.2}%;{};{}", memory_percent, monitoring.memory_threshold, monitoring.memory_critical_threshold),
+            format!("disk={:.2}%;{};{}", disk_percent, monitoring.disk_threshold, monitoring.disk_critical_threshold),
+            format!("containers_high={}", high_containers.len()),
+        ];
+        if swap.total > 0 {
+            outcome.perfdata.push(format!("swap={:.2}%;{};{}", swap.percent, monitoring.swap_threshold, monitoring.swap_critical_threshold));
+        }
+        if let Some((_, usage)) = &conntrack {
+            outcome.perfdata.push(format!(
+                "conntrack={:.2}%;{};{}",
+                usage.percent, self.config.conntrack.threshold, self.config.conntrack.critical_threshold
+            ));
+        }
+        if let Some(frequency) = &cpu_frequency {
+            outcome.perfdata.push(format!("cpu_frequency={:.0}MHz", frequency.current_mhz));
+        }
+        
+        self.events.emit("cycle", &outcome);
+        self.incident_active = !self.firing_rules().is_empty();
+        self.last_cycle = Some(CycleSummary {
+            timestamp: chrono::Utc::now(),
+            server_cpu,
+            memory_percent,
+            high_cpu_containers: high_containers,
+            outcome: outcome.clone(),
+        });
+        self.write_snapshot(&outcome, started.elapsed());
+        
+        // Retry alerts that could not be delivered earlier, once per cycle
+        self.dispatcher.flush_email_queue();
+        
+        Ok(outcome)
     }
     
-    async fn check_container_cpu(&self) -> (bool, Vec<docker_monitor::ContainerStats>) {
-        info!("Checking Docker container CPU usage...");
+    /// Rules currently firing or flapping.
+    fn firing_rules(&self) -> HashSet<&str> {
+        self.state.state.incidents.iter()
+            .filter(|(_, rule)| matches!(rule.state, IncidentState::Firing | IncidentState::Flapping))
+            .map(|(name, _)| name.as_str())
+            .collect()
+    }
+    
+    /// Re-runs only the checks whose rules are firing, on the
+    /// `incident_check_interval` cadence between full cycles. Everything else,
+    /// including digests, the status page and history, waits for the next
+    /// full cycle.
+    async fn incident_cycle(&mut self) {
+        let firing: Vec<String> = self.firing_rules().into_iter().map(str::to_string).collect();
+        let affected = |rules: &[&str]| rules.iter().any(|rule| firing.iter().any(|firing| firing == rule));
+        info!("Re-checking firing rules: {}", firing.join(", "));
+        self.cycle_alerts.clear();
+        self.cycle_processes = None;
+        self.cycle_containers = None;
+        self.skipped_rules.clear();
+        self.status_checks.clear();
+        self.apply_thresholds();
         
-        match self.docker_monitor.check_container_cpu_threshold(self.config.monitoring.cpu_threshold).await {
-            Ok((is_high, high_cpu_containers)) => {
-                if is_high {
-                    warn!("High CPU usage detected in {} containers", high_cpu_containers.len());
-                    
-                    // Send alert
-                    let alert_sent = self.email_notifier.send_container_cpu_alert(&high_cpu_containers).await;
-                    if alert_sent {
-                        info!("Container CPU alert email sent successfully");
-                    } else {
-                        error!("Failed to send container CPU alert email");
-                    }
-                } else {
-                    info!("All containers have normal CPU usage");
-                }
-                
-                (is_high, high_cpu_containers)
+        let mut focus: HashSet<&str> = HashSet::new();
+        // Host checks run one by one, so the sustained counters and the
+        // bandwidth sample of rules that are not firing keep the full cycle
+        // cadence
+        let server_cpu = if affected(&[alert::RULE_CPU]) {
+            focus.insert(alert::RULE_CPU);
+            Some(self.check_server_cpu().await.1)
+        } else if affected(&[alert::RULE_HOT_CORE, alert::RULE_CPU_FREQUENCY, alert::RULE_TREND]) {
+            Some(self.server_monitor.get_cpu_usage())
+        } else {
+            None
+        };
+        if let Some(server_cpu) = server_cpu {
+            if affected(&[alert::RULE_HOT_CORE]) {
+                self.check_hot_cores(server_cpu).await;
+                focus.insert(alert::RULE_HOT_CORE);
             }
-            Err(e) => {
-                error!("Error checking container CPU: {}", e);
-                (false, vec![])
+            if affected(&[alert::RULE_CPU_FREQUENCY]) {
+                self.check_cpu_frequency(server_cpu);
+                focus.insert(alert::RULE_CPU_FREQUENCY);
             }
+            if affected(&[alert::RULE_TREND]) {
+                self.check_trends(server_cpu);
+                focus.insert(alert::RULE_TREND);
+            }
+        }
+        let memory = if affected(&[alert::RULE_MEMORY]) {
+            focus.insert(alert::RULE_MEMORY);
+            Some(self.check_server_memory().await)
+        } else if affected(&[alert::RULE_SWAP]) {
+            Some(self.server_monitor.get_memory_usage())
+        } else {
+            None
+        };
+        if let Some(memory) = memory.filter(|_| affected(&[alert::RULE_SWAP])) {
+            self.check_server_swap(&memory).await;
+            focus.insert(alert::RULE_SWAP);
+        }
+        if affected(&[alert::RULE_DISK]) {
+            self.check_server_disk().await;
+            focus.insert(alert::RULE_DISK);
+        }
+        if affected(&[alert::RULE_BANDWIDTH]) {
+            self.check_server_network();
+            focus.insert(alert::RULE_BANDWIDTH);
+        }
+        if affected(&[alert::RULE_DISK_LATENCY]) {
+            self.check_disk_latency();
+            focus.insert(alert::RULE_DISK_LATENCY);
+        }
+        if affected(&[alert::RULE_CONNTRACK]) {
+            self.check_conntrack();
+            focus.insert(alert::RULE_CONNTRACK);
+        }
+        let containers = [
+            alert::RULE_CONTAINER_CPU, alert::RULE_UNLIMITED_CONTAINER, alert::RULE_NEWCOMER_CONTAINER,
+            alert::RULE_IMAGE_DRIFT, alert::RULE_MEMORY_LEAK, alert::RULE_GROUP_BUDGET,
+        ];
+        if affected(&containers) {
+            self.check_container_cpu().await;
+            focus.extend(containers);
+        }
+        if affected(&[alert::RULE_LXD_CONTAINER]) {
+            self.check_lxd_containers().await;
+            focus.insert(alert::RULE_LXD_CONTAINER);
+        }
+        if affected(&[alert::RULE_VM]) {
+            self.check_vms().await;
+            focus.insert(alert::RULE_VM);
+        }
+        if affected(&[alert::RULE_PROCESS]) {
+            self.check_processes().await;
+            focus.insert(alert::RULE_PROCESS);
+        }
+        if affected(&[alert::RULE_ZFS]) {
+            self.check_zfs().await;
+            focus.insert(alert::RULE_ZFS);
+        }
+        if affected(&[alert::RULE_STORAGE_POOL]) {
+            self.check_storage_pools().await;
+            focus.insert(alert::RULE_STORAGE_POOL);
+        }
+        if affected(&[alert::RULE_UPS]) {
+            self.check_ups().await;
+            focus.insert(alert::RULE_UPS);
+        }
+        if affected(&[alert::RULE_COMMAND]) {
+            self.check_commands().await;
+            focus.insert(alert::RULE_COMMAND);
         }
+        if affected(&[alert::RULE_CRON]) {
+            self.check_cron_jobs();
+            focus.insert(alert::RULE_CRON);
+        }
+        if affected(&[alert::RULE_PING]) {
+            self.check_ping().await;
+            focus.insert(alert::RULE_PING);
+        }
+        if affected(&[alert::RULE_FINGERPRINT]) {
+            self.check_fingerprints().await;
+            focus.insert(alert::RULE_FINGERPRINT);
+        }
+        if affected(&[alert::RULE_OUTBOUND]) {
+            self.check_outbound().await;
+            focus.insert(alert::RULE_OUTBOUND);
+        }
+        
+        let tickets = self.evaluate_incidents(Some(&focus));
+        self.update_tickets(tickets).await;
+        let firing: Vec<String> = self.firing_rules().into_iter().map(str::to_string).collect();
+        self.incident_active = !firing.is_empty();
+        self.api.publish_firing(firing);
+        self.state.save();
     }
     
-    async fn run_monitoring(&mut self) -> Result<bool> {
-        info!("Starting monitoring check...");
-        
-        // Check server CPU
-        let (server_high, server_cpu) = self.check_server_cpu().await;
-        
-        // Check container CPU
-        let (container_high, high_containers) = self.check_container_cpu().await;
-        
-        // Log summary
-        info!("Monitoring check completed. Server CPU: {:.2}%, High CPU containers: {}", 
-              server_cpu, high_containers.len());
-        
-        Ok(server_high || container_high)
+    /// Rewrites `monitoring.snapshot_file` with the cycle that just finished.
+    fn write_snapshot(&self, outcome: &CheckOutcome, duration: Duration) {
+        let Some(path) = &self.config.monitoring.snapshot_file else {
+            return;
+        };
+        let snapshot = snapshot::Snapshot {
+            timestamp: chrono::Utc::now(),
+            hostname: kubernetes::hostname(),
+            version: self_update::VERSION.to_string(),
+            cycle: self.cycle_id,
+            duration_ms: duration.as_millis() as u64,
+            outcome: outcome.clone(),
+            server: self.api.server(),
+            cloud: self.cloud_metadata.clone(),
+            containers: self.api.containers(),
+            firing: self.api.firing(),
+            incidents: self.state.state.incidents.clone(),
+            dispatch: self.dispatcher.stats(),
+        };
+        if let Err(e) = snapshot::write(path, &snapshot) {
+            error!("Failed to write snapshot file {}: {}", path, e);
+        }
     }
     
-    async fn print_status_summary(&mut self) -> Result<()> {
+    async fn print_status_summary(&mut self, options: &StatusOptions) -> Result<()> {
         let server_stats = self.server_monitor.get_full_stats();
-        let docker_stats = self.docker_monitor.get_container_stats().await.unwrap_or_default();
-        let docker_info = self.docker_monitor.get_docker_system_info().await.unwrap_or_default();
-        
-        println!("\n{}", "=".repeat(60));
-        println!("SYSTEM STATUS - {}", server_stats.timestamp.format("%Y-%m-%d %H:%M:%S"));
-        println!("{}", "=".repeat(60));
-        
-        // Server status
-        println!("\n🖥️  SERVER:");
-        println!("   CPU Usage: {:.2}%", server_stats.cpu_usage);
-        println!("   Memory Usage: {:.2}%", server_stats.memory_usage.percent);
-        println!("   Disk Usage: {:.2}%", server_stats.disk_usage.percent);
-        
-        // Docker status
-        println!("\n🐳 DOCKER:");
-        println!("   Running Containers: {}", docker_stats.len());
-        println!("   Total Containers: {}", docker_info.containers);
+        let (docker_stats, docker_info) = match self.docker_monitor.as_ref().filter(|_| options.show_containers) {
+            Some(docker_monitor) => (
+                docker_monitor.get_container_stats().await.unwrap_or_default(),
+                docker_monitor.get_docker_system_info().await.unwrap_or_default(),
+            ),
+            None => Default::default(),
+        };
         
-        if !docker_stats.is_empty() {
-            println!("\n   Top CPU Containers:");
-            for (i, container) in docker_stats.iter().take(5).enumerate() {
-                println!("   {}. {}: {:.2}% CPU", i + 1, container.name, container.cpu_usage);
+        // CPU usage of LXD instances and guests needs two samples
+        let lxd_enabled = options.show_containers && self.lxd.is_enabled();
+        let libvirt_enabled = options.show_containers && self.libvirt.is_enabled();
+        if lxd_enabled || libvirt_enabled {
+            if lxd_enabled {
+                let _ = self.lxd.get_container_stats().await;
             }
+            if libvirt_enabled {
+                let _ = self.libvirt.sample().await;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
         }
+        let instances = if lxd_enabled { self.lxd.get_container_stats().await.unwrap_or_default() } else { Vec::new() };
+        let vms = if libvirt_enabled { self.libvirt.sample().await.unwrap_or_default() } else { Vec::new() };
         
-        println!("\n{}", "=".repeat(60));
+        let snapshot = StatusSnapshot {
+            server: &server_stats,
+            cloud: self.cloud_metadata.as_ref(),
+            containers: &docker_stats,
+            docker_info: &docker_info,
+            lxd: self.lxd.is_enabled().then_some(instances.as_slice()),
+            vms: self.libvirt.is_enabled().then_some(vms.as_slice()),
+        };
+        print!("{}", options.render(&snapshot));
         
         Ok(())
     }
     
+    async fn watch(&mut self, interval: Duration, options: &StatusOptions) -> Result<()> {
+        loop {
+            // Clear the screen and move the cursor home before redrawing
+            print!("\x1B[2J\x1B[H");
+            self.print_status_summary(options).await?;
+            println!("Refreshing every {:?}. Press Ctrl+C to exit.", interval);
+            
+            tokio::time::sleep(interval).await;
+        }
+    }
+    
+    /// Writes the in-memory state (last cycle samples and active problems) to the
+    /// configured dump file, or to the log when none is configured.
+    fn dump_state(&self) {
+        let dump = serde_json::json!({
+            "timestamp": chrono::Utc::now(),
+            "last_cycle": self.last_cycle,
+            "dispatch": self.dispatcher.stats(),
+            "persisted_state": self.state.state,
+        });
+        
+        let content = match serde_json::to_string_pretty(&dump) {
+            Ok(content) => content,
+            Err(e) => {
+                error!("Failed to serialize state dump: {}", e);
+                return;
+            }
+        };
+        
+        match &self.config.monitoring.state_dump_file {
+            Some(path) => match std::fs::write(path, &content) {
+                Ok(_) => info!("State dumped to {}", path),
+                Err(e) => error!("Failed to write state dump to {}: {}", path, e),
+            },
+            None => info!("State dump:\n{}", content),
+        }
+    }
+    
+    /// Whether this replica should run the cycle. Without `ha` it always
+    /// does; with it only the leader does, after reading the state the
+    /// previous leader saved so its incidents are not raised again.
+    fn lead(&mut self) -> bool {
+        let Some(election) = self.election.as_mut() else {
+            return true;
+        };
+        let leadership = election.poll();
+        let leader = election.leader().unwrap_or("unknown").to_string();
+        self.metrics.set_leader(leadership.is_leader());
+        if leadership == Leadership::Gained {
+            self.state.reload();
+            self.api.silences.load(self.state.state.silences.clone());
+            self.api.thresholds.replace(self.state.state.thresholds.clone());
+            self.api.load_handled_commands(self.state.state.recent_commands.clone());
+            self.api.load_acknowledgements(
+                self.state.state.incidents.iter()
+                    .filter_map(|(rule, rule_state)| Some((rule.clone(), rule_state.acknowledged.clone()?)))
+                    .collect()
+            );
+        }
+        // Only once the state is reloaded, which would drop earlier changes
+        self.api.set_standby((!leadership.is_leader()).then_some(leader));
+        leadership.is_leader()
+    }
+    
     async fn run_continuous(&mut self) -> Result<()> {
-        let interval = Duration::from_secs(self.config.monitoring.check_interval);
+        self.election = Election::new(&self.config).context("Failed to set up leader election")?;
+        if let Some(election) = &self.election {
+            info!("Leader election enabled, this replica is {}", election.instance());
+            // Before the APIs start taking changes a standby would not save
+            self.lead();
+        }
+        let mut schedule = schedule::Schedule::new(&self.config);
+        if self.config.statuspage.enabled {
+            // Serve the recorded history until the first cycle completes
+            self.status_page.publish(statuspage::render(&self.config.statuspage, &self.state.state.status_page, &self.timezone));
+        }
+        self.history_page.publish(&self.state.state.history);
+        #[cfg(feature = "web")]
+        http_server::spawn(
+            &self.config,
+            self.metrics.clone(),
+            self.status_page.clone(),
+            self.webpush.clone(),
+            self.history_page.clone(),
+            self.api.clone(),
+            self.redactor.clone(),
+        );
+        #[cfg(not(feature = "web"))]
+        if self.config.http.enabled {
+            error!("http.enabled is set but this build has no HTTP server, rebuild with `--features web`");
+        }
+        grpc::spawn(&self.config.grpc, self.api.clone());
+        #[cfg(feature = "email")]
+        email_commands::spawn(&self.config, self.api.clone(), self.email_notifier.clone(), self.redactor.clone());
+        #[cfg(not(feature = "email"))]
+        if self.config.email_commands.enabled {
+            error!("email_commands.enabled is set but this build has no email support, rebuild with `--features email`");
+        }
+        let mut check_signal = signal(SignalKind::user_defined1())?;
+        let mut dump_signal = signal(SignalKind::user_defined2())?;
+        let shutdown = ShutdownToken::default();
+        shutdown::listen(shutdown.clone());
         
-        info!("Starting continuous monitoring with {:?} interval...", interval);
+        info!("Starting continuous monitoring with {:?} interval...", schedule.interval());
+        info!("Send SIGUSR1 to run a check immediately, SIGUSR2 to dump the current state, Ctrl+C or SIGTERM to stop");
         
-        loop {
-            match self.run_monitoring().await {
-                Ok(alert_triggered) => {
-                    if alert_triggered {
-                        println!("⚠️  High CPU usage detected! Check your email for alerts.");
+        let initial_delay = schedule.initial_delay();
+        if !initial_delay.is_zero() {
+            info!("Delaying first check by {:?} (splay offset {:?})", initial_delay, schedule.offset());
+            tokio::select! {
+                _ = tokio::time::sleep(initial_delay) => {}
+                _ = shutdown.cancelled() => {}
+            }
+        }
+        schedule.start();
+        
+        while !shutdown.is_cancelled() {
+            let result = if self.lead() { Some(self.run_monitoring().await) } else { None };
+            match result {
+                None => {}
+                Some(Ok(_)) if self.events.is_enabled() => {}
+                Some(Ok(outcome)) => {
+                    if outcome.is_alerting() {
+                        println!("⚠️  {}. Check your email for alerts.", outcome.summary());
                     } else {
                         println!("✅ All systems normal.");
                     }
                 }
-                Err(e) => {
+                Some(Err(e)) => {
                     error!("Error during monitoring check: {}", e);
                 }
             }
             
-            tokio::time::sleep(interval).await;
+            loop {
+                tokio::select! {
+                    tick = schedule.tick(self.incident_active) => match tick {
+                        schedule::Tick::Full => break,
+                        schedule::Tick::Incident => {
+                            if self.lead() {
+                                self.incident_cycle().await;
+                            }
+                        }
+                    },
+                    _ = shutdown.cancelled() => break,
+                    _ = check_signal.recv() => {
+                        info!("SIGUSR1 received, running an immediate check");
+                        break;
+                    }
+                    _ = dump_signal.recv() => {
+                        info!("SIGUSR2 received, dumping state");
+                        self.dump_state();
+                    }
+                }
+            }
+        }
+        
+        self.shut_down().await;
+        Ok(())
+    }
+    
+    /// Waits for the digest and reports still being emailed after a single
+    /// check, and saves them as sent so the next run does not repeat them.
+    async fn finish_scheduled_emails(&mut self) {
+        if self.pending_digest.is_none() && self.pending_reports.is_empty() {
+            return;
+        }
+        self.collect_digest(true).await;
+        self.collect_reports(true).await;
+        self.state.save();
+    }
+    
+    /// Delivers pending notifications and saves the state once the loop has
+    /// stopped, then summarizes the run.
+    async fn shut_down(&mut self) {
+        info!("Shutting down, delivering pending notifications...");
+        let stats = self.dispatcher.shutdown().await;
+        self.collect_digest(true).await;
+        self.collect_reports(true).await;
+        // A standby's state is stale, the leader's copy must not be overwritten
+        if self.election.as_ref().is_none_or(Election::is_leader) {
+            self.state.state.runtime_metrics = self.metrics.snapshot();
+            self.state.state.recent_commands = self.api.handled_commands();
+            self.state.save();
         }
+        if let Some(election) = self.election.as_mut() {
+            election.resign();
+        }
+        
+        let mut firing: Vec<String> = self.state.state.incidents.iter()
+            .filter(|(_, rule)| matches!(rule.state, IncidentState::Firing | IncidentState::Flapping))
+            .map(|(name, _)| name.clone())
+            .collect();
+        firing.sort();
+        let summary = ShutdownSummary {
+            cycles: self.cycle_id,
+            uptime_secs: (chrono::Utc::now() - self.started_at).num_seconds(),
+            notifications_sent: stats.sent,
+            notifications_failed: stats.failed,
+            notifications_dropped: stats.dropped,
+            notifications_undelivered: stats.depth + stats.in_flight,
+            firing,
+        };
+        self.events.emit("shutdown", &summary);
+        info!(
+            "Stopped after {} cycles in {}: {} notifications sent, {} failed, {} dropped, {} undelivered; firing: {}",
+            summary.cycles,
+            humantime::format_duration(Duration::from_secs(summary.uptime_secs.max(0) as u64)),
+            summary.notifications_sent,
+            summary.notifications_failed,
+            summary.notifications_dropped,
+            summary.notifications_undelivered,
+            if summary.firing.is_empty() { "none".to_string() } else { summary.firing.join(", ") },
+        );
     }
     
     async fn test_email(&self) -> Result<()> {
@@ -204,7 +2733,7 @@ impl PerformanceMonitor {
 #[tokio::main]
 async fn main() -> Result<()> {
     let matches = Command::new("performance-monitor")
-        .version("0.1.0")
+        .version(self_update::VERSION)
         .author("Performance Monitor")
         .about("Docker & Server Performance Monitor written in Rust")
         .arg(
@@ -214,6 +2743,7 @@ async fn main() -> Result<()> {
                 .value_name("FILE")
                 .help("Configuration file path")
                 .default_value("config.json")
+                .global(true)
         )
         .arg(
             Arg::new("status")
@@ -236,34 +2766,659 @@ async fn main() -> Result<()> {
                 .help("Run continuous monitoring")
                 .action(clap::ArgAction::SetTrue)
         )
+        .arg(
+            Arg::new("nagios")
+                .long("nagios")
+                .help("Print Nagios/Icinga plugin output for the single check")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("events-stdout")
+                .long("events-stdout")
+                .help("Emit every sample and alert as JSON Lines on stdout")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("docker-socket-ro")
+                .long("docker-socket-ro")
+                .help("The Docker socket is read-only (e.g. a socket proxy), never change Docker resources")
+                .action(clap::ArgAction::SetTrue)
+        )
+        .arg(
+            Arg::new("sort")
+                .long("sort")
+                .value_name("KEY")
+                .help("Sort containers in the status view by cpu, memory or name")
+                .value_parser(["cpu", "memory", "name"])
+                .default_value("cpu")
+                .global(true)
+        )
+        .arg(
+            Arg::new("filter")
+                .long("filter")
+                .value_name("EXPR")
+                .help("Filter containers, e.g. 'name~web', 'image=nginx,!label.team=ops', 'name~web|name~api' (repeatable)")
+                .action(clap::ArgAction::Append)
+                .global(true)
+        )
+        .arg(
+            Arg::new("top")
+                .long("top")
+                .value_name("N")
+                .help("Number of containers to show in the status view")
+                .value_parser(clap::value_parser!(usize))
+                .default_value("5")
+                .global(true)
+        )
+        .arg(
+            Arg::new("columns")
+                .long("columns")
+                .value_name("LIST")
+                .help("Comma separated container columns: name,id,image,status,cpu,memory,memory_percent,limits")
+                .global(true)
+        )
+        .arg(
+            Arg::new("ascii")
+                .long("ascii")
+                .help("Draw tables with ASCII instead of unicode box characters")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+        )
+        .arg(
+            Arg::new("no-emoji")
+                .long("no-emoji")
+                .help("Omit emoji from terminal output")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+        )
+        .arg(
+            Arg::new("timezone")
+                .long("timezone")
+                .value_name("ZONE")
+                .help("Show times in ZONE (UTC, local, +07:00 or Asia/Jakarta) [default: config timezone]")
+                .global(true)
+        )
+        .arg(
+            Arg::new("containers-only")
+                .long("containers-only")
+                .help("Only show containers in the status view")
+                .action(clap::ArgAction::SetTrue)
+                .conflicts_with("host-only")
+                .global(true)
+        )
+        .arg(
+            Arg::new("host-only")
+                .long("host-only")
+                .help("Only show host metrics in the status view")
+                .action(clap::ArgAction::SetTrue)
+                .global(true)
+        )
+        .subcommand(
+            Command::new("healthz")
+                .about("Check config, Docker connectivity and the last cycle time (for HEALTHCHECK)")
+                .arg(
+                    Arg::new("max-age")
+                        .long("max-age")
+                        .value_name("DURATION")
+                        .help("Maximum age of the last completed cycle [default: 3x check_interval]")
+                )
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Diagnose the environment (config, Docker socket, SMTP, file permissions)")
+        )
+        .subcommand(
+            Command::new("stats")
+                .about("Show statistics")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("self")
+                        .about("Show the monitor's own runtime metrics (cycles, durations, errors, notifications)")
+                )
+        )
+        .subcommand(
+            Command::new("audit")
+                .about("Read the audit log of notifications, remediations, silences and threshold changes")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("list")
+                        .about("List audit entries, oldest first")
+                        .arg(
+                            Arg::new("action")
+                                .short('a')
+                                .long("action")
+                                .value_name("ACTION")
+                                .value_parser(["notification", "remediation", "silence", "acknowledgement", "config"])
+                                .help("Only entries of this kind")
+                        )
+                        .arg(
+                            Arg::new("actor")
+                                .long("actor")
+                                .value_name("TEXT")
+                                .help("Only entries whose actor contains TEXT, e.g. cli:alice or monitor")
+                        )
+                        .arg(
+                            Arg::new("target")
+                                .long("target")
+                                .value_name("TEXT")
+                                .help("Only entries whose target (rule, container, threshold) contains TEXT")
+                        )
+                        .arg(
+                            Arg::new("since")
+                                .long("since")
+                                .value_name("DURATION")
+                                .help("Only entries from the last DURATION, e.g. 24h or 7d")
+                        )
+                        .arg(
+                            Arg::new("limit")
+                                .short('n')
+                                .long("limit")
+                                .value_name("N")
+                                .value_parser(clap::value_parser!(usize))
+                                .default_value("50")
+                                .help("Show only the N most recent matching entries, 0 for all")
+                        )
+                        .arg(
+                            Arg::new("json")
+                                .long("json")
+                                .help("Print the entries as JSON lines")
+                                .action(clap::ArgAction::SetTrue)
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("statuspage")
+                .about("Render the status page from the recorded history")
+                .arg(
+                    Arg::new("output")
+                        .short('o')
+                        .long("output")
+                        .value_name("FILE")
+                        .help("Write the page to FILE [default: statuspage.output, or stdout]")
+                )
+        )
+        .subcommand(
+            Command::new("wrap")
+                .about("Run a cron job and record its start, end, exit code and duration")
+                .arg(
+                    Arg::new("name")
+                        .short('n')
+                        .long("name")
+                        .value_name("NAME")
+                        .help("Job name [default: the command's file name]")
+                )
+                .arg(
+                    Arg::new("command")
+                        .value_name("COMMAND")
+                        .num_args(1..)
+                        .required(true)
+                        .last(true)
+                        .help("Command to run, after --")
+                )
+        )
+        .subcommand(
+            Command::new("container")
+                .about("Restart or kill a container (needs docker.allow_container_actions)")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("restart")
+                        .about("Restart a container")
+                        .arg(Arg::new("name").value_name("CONTAINER").required(true))
+                )
+                .subcommand(
+                    Command::new("kill")
+                        .about("Send a signal to a container")
+                        .arg(Arg::new("name").value_name("CONTAINER").required(true))
+                        .arg(
+                            Arg::new("signal")
+                                .short('s')
+                                .long("signal")
+                                .value_name("SIGNAL")
+                                .default_value("SIGKILL")
+                                .help("Signal to send, e.g. SIGTERM")
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("set-threshold")
+                .about("Change a threshold of the running monitor, e.g. `set-threshold cpu 90 --duration 4h`")
+                .arg(
+                    Arg::new("name")
+                        .value_name("THRESHOLD")
+                        .required(true)
+                        .value_parser(thresholds::ADJUSTABLE.iter().map(|(name, _, _)| *name).collect::<Vec<_>>())
+                )
+                .arg(
+                    Arg::new("value")
+                        .value_name("VALUE")
+                        .value_parser(clap::value_parser!(f64))
+                        .required_unless_present("reset")
+                )
+                .arg(
+                    Arg::new("duration")
+                        .short('d')
+                        .long("duration")
+                        .value_name("DURATION")
+                        .help("Revert after DURATION, e.g. 4h [default: until reset]")
+                        .conflicts_with("persist")
+                )
+                .arg(
+                    Arg::new("persist")
+                        .long("persist")
+                        .help("Also write the new value to the config file")
+                        .action(clap::ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("reset")
+                        .long("reset")
+                        .help("Drop the runtime change and go back to the configured value")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["value", "duration", "persist"])
+                )
+        )
+        .subcommand(
+            Command::new("render")
+                .about("Render a sample alert for a channel without sending it, to check templates")
+                .arg(
+                    Arg::new("alert")
+                        .value_name("ALERT_JSON")
+                        .required(true)
+                        .help("Alert JSON file, in the form webhooks receive by default")
+                )
+                .arg(
+                    Arg::new("channel")
+                        .long("channel")
+                        .value_name("CHANNEL")
+                        .value_parser(render::CHANNELS)
+                        .default_value("email")
+                        .help("Channel to render for")
+                )
+                .arg(
+                    Arg::new("name")
+                        .short('n')
+                        .long("name")
+                        .value_name("NAME")
+                        .help("Only render the webhook, chat or apprise channel with this name")
+                )
+                .arg(
+                    Arg::new("hostname")
+                        .long("hostname")
+                        .value_name("HOST")
+                        .help("Hostname shown in the output [default: this host]")
+                )
+        )
+        .subcommand(
+            Command::new("config")
+                .about("Work with configuration files")
+                .subcommand_required(true)
+                .subcommand(
+                    Command::new("generate")
+                        .about("Render a config from a Tera template and a host variables file, then validate it")
+                        .arg(
+                            Arg::new("template")
+                                .value_name("TEMPLATE")
+                                .required(true)
+                                .help("Config template, JSON with Tera expressions")
+                        )
+                        .arg(
+                            Arg::new("host-vars")
+                                .long("host-vars")
+                                .value_name("FILE")
+                                .required(true)
+                                .help("JSON object with the variables of this host")
+                        )
+                        .arg(
+                            Arg::new("output")
+                                .short('o')
+                                .long("output")
+                                .value_name("FILE")
+                                .help("Write the config to FILE instead of stdout")
+                        )
+                )
+        )
+        .subcommand(
+            Command::new("self-update")
+                .about("Install the latest GitHub release after verifying its checksum and signature")
+                .arg(
+                    Arg::new("check")
+                        .long("check")
+                        .help("Only report whether a newer release exists")
+                        .action(clap::ArgAction::SetTrue)
+                )
+                .arg(
+                    Arg::new("tag")
+                        .long("tag")
+                        .value_name("TAG")
+                        .help("Install this release, e.g. v0.3.0, even if it is older")
+                )
+                .arg(
+                    Arg::new("rollback")
+                        .long("rollback")
+                        .help("Restore the binary replaced by the last update")
+                        .action(clap::ArgAction::SetTrue)
+                        .conflicts_with_all(["check", "tag"])
+                )
+        )
+        .subcommand(
+            Command::new("motd")
+                .about("Print a compact health summary from the snapshot file, for login banners")
+                .arg(
+                    Arg::new("file")
+                        .long("file")
+                        .value_name("FILE")
+                        .help("Snapshot to read instead of monitoring.snapshot_file")
+                )
+                .arg(
+                    Arg::new("no-color")
+                        .long("no-color")
+                        .help("Print without ANSI colors (also when NO_COLOR is set)")
+                        .action(clap::ArgAction::SetTrue)
+                )
+        )
+        .subcommand(
+            Command::new("watch")
+                .about("Show a live, periodically refreshed status summary")
+                .arg(
+                    Arg::new("interval")
+                        .short('i')
+                        .long("interval")
+                        .value_name("DURATION")
+                        .help("Refresh interval, e.g. 2s or 1m")
+                        .default_value("2s")
+                )
+        )
         .get_matches();
     
-    // Initialize logger
-    env_logger::init_from_env(Env::default().default_filter_or("info"));
-    
     let config_path = matches.get_one::<String>("config").unwrap();
     
+    // Initialize logger from the config's logging section
+    let (logging, otlp, cluster, cloud) = Config::load_from_file(config_path)
+        .map(|config| (config.logging, config.otlp, config.kubernetes, config.cloud))
+        .unwrap_or_else(|_| (Config::default().logging, Default::default(), Default::default(), Default::default()));
+    // Before anything labels itself with the hostname
+    kubernetes::configure(&cluster);
+    // Exported spans carry the instance as resource attributes, so it is
+    // detected before the exporter starts
+    let instance = if otlp.enabled { cloud_metadata::detect(&cloud).await } else { None };
+    let _telemetry = logging::init(&logging, &otlp, instance.as_ref());
+    
+    let nagios = matches.get_flag("nagios");
+    
+    if matches.subcommand_matches("doctor").is_some() {
+        let ok = doctor::run(config_path).await;
+        std::process::exit(if ok { 0 } else { 1 });
+    }
+    
+    if let Some(stats_matches) = matches.subcommand_matches("stats") {
+        if stats_matches.subcommand_matches("self").is_some() {
+            let config = Config::load_from_file(config_path).unwrap_or_else(|e| {
+                warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
+                Config::default()
+            });
+            units::configure(&config.units);
+            metrics::print_self_stats(&config);
+        }
+        return Ok(());
+    }
+    
+    if let Some(list_matches) = matches.subcommand_matches("audit").and_then(|audit| audit.subcommand_matches("list")) {
+        let config = Config::load_from_file(config_path).unwrap_or_else(|e| {
+            warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
+            Config::default()
+        });
+        let since = match list_matches.get_one::<String>("since").map(|since| config::parse_duration(since)) {
+            Some(Ok(since)) => Some(chrono::Utc::now() - chrono::Duration::from_std(since)?),
+            Some(Err(e)) => {
+                error!("Invalid --since: {}", e);
+                std::process::exit(1);
+            }
+            None => None,
+        };
+        let query = audit::AuditQuery {
+            action: list_matches.get_one::<String>("action").and_then(|action| AuditAction::parse(action)),
+            actor: list_matches.get_one::<String>("actor").cloned(),
+            target: list_matches.get_one::<String>("target").cloned(),
+            since,
+            limit: *list_matches.get_one::<usize>("limit").unwrap(),
+        };
+        match audit::list(&config, &query, list_matches.get_flag("json")) {
+            Ok(output) => println!("{}", output),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    
+    if let Some(statuspage_matches) = matches.subcommand_matches("statuspage") {
+        let config = Config::load_from_file(config_path).unwrap_or_else(|e| {
+            warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
+            Config::default()
+        });
+        let state = StateStore::open(&config);
+        let html = statuspage::render(&config.statuspage, &state.state.status_page, &timezone::load(&config.timezone));
+        let output = statuspage_matches.get_one::<String>("output")
+            .cloned()
+            .unwrap_or_else(|| config.statuspage.output.clone());
+        if output.is_empty() {
+            println!("{}", html);
+        } else {
+            statuspage::write(&output, &html)?;
+            info!("Status page written to {}", output);
+        }
+        return Ok(());
+    }
+    
+    if let Some(wrap_matches) = matches.subcommand_matches("wrap") {
+        let config = Config::load_from_file(config_path).unwrap_or_else(|e| {
+            warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
+            Config::default()
+        });
+        let command: Vec<String> = wrap_matches.get_many::<String>("command").unwrap().cloned().collect();
+        let name = wrap_matches.get_one::<String>("name").cloned().unwrap_or_else(|| {
+            std::path::Path::new(&command[0]).file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_else(|| command[0].clone())
+        });
+        let code = cron::wrap(&config.cron, &name, &command).await;
+        std::process::exit(code);
+    }
+    
+    if let Some(container_matches) = matches.subcommand_matches("container") {
+        let mut config = Config::load_from_file(config_path).unwrap_or_else(|e| {
+            warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
+            Config::default()
+        });
+        config.docker.read_only |= matches.get_flag("docker-socket-ro");
+        let Some(control) = DockerControl::new(&config.docker)? else {
+            error!("Container actions are disabled, set docker.allow_container_actions in {}", config_path);
+            std::process::exit(1);
+        };
+        let (name, detail, result) = match container_matches.subcommand() {
+            Some(("restart", action)) => {
+                let name = action.get_one::<String>("name").unwrap();
+                (name, "restarted".to_string(), control.restart_container(name).await)
+            }
+            Some(("kill", action)) => {
+                let name = action.get_one::<String>("name").unwrap();
+                let signal = action.get_one::<String>("signal").unwrap();
+                (name, format!("sent {}", signal), control.kill_container(name, signal).await)
+            }
+            _ => unreachable!("subcommand_required"),
+        };
+        audit::configure(&config.audit);
+        audit::record(
+            AuditEntry::new(AuditAction::Remediation, &audit::cli_actor(), name, detail).with_success(result.is_ok())
+        );
+        if let Err(e) = result {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+    
+    if let Some(threshold_matches) = matches.subcommand_matches("set-threshold") {
+        let result = thresholds::set_from_cli(
+            config_path,
+            threshold_matches.get_one::<String>("name").unwrap(),
+            threshold_matches.get_one::<f64>("value").copied(),
+            threshold_matches.get_one::<String>("duration").map(String::as_str),
+            threshold_matches.get_flag("persist"),
+        ).await;
+        match result {
+            Ok(message) => println!("{}", message),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    
+    if let Some(render_matches) = matches.subcommand_matches("render") {
+        let mut config = Config::load_from_file(config_path).unwrap_or_else(|e| {
+            warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
+            Config::default()
+        });
+        if let Some(timezone) = render_matches.get_one::<String>("timezone") {
+            config.email.timezone = Some(timezone.clone());
+        }
+        units::configure(&config.units);
+        let alert = render::load_alert(render_matches.get_one::<String>("alert").unwrap())?;
+        let output = render::render(
+            &config,
+            alert,
+            render_matches.get_one::<String>("channel").unwrap(),
+            render_matches.get_one::<String>("name").map(String::as_str),
+            render_matches.get_one::<String>("hostname").map(String::as_str),
+        )?;
+        println!("{}", output);
+        return Ok(());
+    }
+    
+    if let Some(("generate", generate_matches)) = matches.subcommand_matches("config").and_then(|config| config.subcommand()) {
+        let generated = config_template::generate(
+            generate_matches.get_one::<String>("template").unwrap(),
+            generate_matches.get_one::<String>("host-vars").unwrap(),
+        );
+        match (generated, generate_matches.get_one::<String>("output")) {
+            (Ok(config), Some(output)) => {
+                std::fs::write(output, config + "\n")?;
+                info!("Config written to {}", output);
+            }
+            (Ok(config), None) => println!("{}", config),
+            (Err(e), _) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    
+    if let Some(update_matches) = matches.subcommand_matches("self-update") {
+        let result = if update_matches.get_flag("rollback") {
+            self_update::rollback()
+        } else {
+            let config = Config::load_from_file(config_path).unwrap_or_else(|e| {
+                warn!("Failed to load config from {}: {}. Using default configuration.", config_path, e);
+                Config::default()
+            });
+            self_update::run(
+                &config,
+                update_matches.get_one::<String>("tag").map(String::as_str),
+                update_matches.get_flag("check"),
+            ).await
+        };
+        match result {
+            Ok(message) => println!("{}", message),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    
+    if let Some(motd_matches) = matches.subcommand_matches("motd") {
+        // Runs at every login, possibly without access to the config
+        let config = Config::load_from_file(config_path).unwrap_or_default();
+        units::configure(&config.units);
+        let color = !motd_matches.get_flag("no-color") && std::env::var_os("NO_COLOR").is_none();
+        match motd::run(&config, motd_matches.get_one::<String>("file").map(String::as_str), color) {
+            Ok(banner) => print!("{}", banner),
+            Err(e) => {
+                println!("performance-monitor: {}", e);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
+    
+    if let Some(healthz_matches) = matches.subcommand_matches("healthz") {
+        let max_age = healthz_matches.get_one::<String>("max-age")
+            .map(|value| config::parse_duration(value))
+            .transpose()?;
+        let healthy = healthz::run(config_path, max_age).await;
+        std::process::exit(if healthy { 0 } else { 1 });
+    }
+    
     // Initialize monitor
-    let mut monitor = PerformanceMonitor::new(config_path).await?;
+    let mut monitor = match PerformanceMonitor::new(config_path, matches.get_flag("docker-socket-ro")).await {
+        Ok(monitor) => monitor,
+        Err(e) => {
+            if nagios {
+                println!("PERFMON UNKNOWN - {}", e);
+            } else {
+                error!("Failed to initialize monitor: {}", e);
+            }
+            std::process::exit(alert::EXIT_UNKNOWN);
+        }
+    };
     
-    if matches.get_flag("test-email") {
+    monitor.events = EventStream::new(matches.get_flag("events-stdout"));
+    
+    if let Some(watch_matches) = matches.subcommand_matches("watch") {
+        let interval = config::parse_duration(watch_matches.get_one::<String>("interval").unwrap())?;
+        let mut options = StatusOptions::from_matches(watch_matches)?;
+        options.emoji &= monitor.config.emoji;
+        options.timezone.get_or_insert_with(|| monitor.timezone.clone());
+        monitor.watch(interval, &options).await?;
+    } else if matches.get_flag("test-email") {
         monitor.test_email().await?;
     } else if matches.get_flag("status") {
-        monitor.print_status_summary().await?;
+        let mut options = StatusOptions::from_matches(&matches)?;
+        options.emoji &= monitor.config.emoji;
+        options.timezone.get_or_insert_with(|| monitor.timezone.clone());
+        monitor.print_status_summary(&options).await?;
     } else if matches.get_flag("continuous") {
         monitor.run_continuous().await?;
     } else {
-        // Run single monitoring check
-        match monitor.run_monitoring().await {
-            Ok(alert_triggered) => {
-                if alert_triggered {
-                    println!("⚠️  High CPU usage detected! Check your email for alerts.");
+        // Run single monitoring check, then wait for queued alerts before exiting
+        let result = monitor.run_monitoring().await;
+        monitor.dispatcher.drain().await;
+        monitor.finish_scheduled_emails().await;
+        match result {
+            Ok(outcome) => {
+                if nagios {
+                    println!("{}", outcome.nagios_line());
+                } else if monitor.events.is_enabled() {
+                    // Keep stdout pure JSON Lines
+                } else if outcome.is_alerting() {
+                    println!("⚠️  {}. Check your email for alerts.", outcome.summary());
+                } else if !outcome.unknown.is_empty() {
+                    println!("❓ {}.", outcome.unknown.join(", "));
                 } else {
                     println!("✅ All systems normal.");
                 }
+                std::process::exit(outcome.exit_code());
             }
             Err(e) => {
                 error!("Error during monitoring: {}", e);
+                if nagios {
+                    println!("PERFMON UNKNOWN - {}", e);
+                }
+                std::process::exit(alert::EXIT_UNKNOWN);
             }
         }
     }
@@ -0,0 +1,99 @@
+use chrono::Utc;
+use regex::{Regex, RegexBuilder};
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+use crate::api::ApiState;
+use crate::config::{Config, MaintenanceCalendarConfig};
+use crate::ical::{self, CalendarEvent};
+use crate::network;
+use crate::silence::Silence;
+use crate::timezone::{self, Timezone};
+use log::{error, info, warn};
+
+/// Who maintenance silences are attributed to in the API and ChatOps.
+const CREATED_BY: &str = "maintenance calendar";
+
+/// Loads the maintenance calendar once, then keeps refreshing it in the
+/// background. A failed download keeps the windows already known.
+pub async fn start(config: &Config, api: Arc<ApiState>) {
+    let settings = config.maintenance_calendar.clone();
+    if !settings.enabled {
+        return;
+    }
+    if settings.url.is_empty() {
+        warn!("maintenance_calendar is enabled without a url");
+        return;
+    }
+    let patterns: Vec<Regex> = match settings.patterns.iter()
+        .map(|pattern| RegexBuilder::new(pattern).case_insensitive(true).build())
+        .collect()
+    {
+        Ok(patterns) => patterns,
+        Err(e) => {
+            error!("Maintenance calendar disabled, invalid pattern: {}", e);
+            return;
+        }
+    };
+    
+    let client = network::http_client(&config.network);
+    let timezone = timezone::load(&config.timezone);
+    refresh(&settings, &patterns, &client, &timezone, &api).await;
+    
+    let period = Duration::from_secs(settings.refresh.max(60));
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+        loop {
+            interval.tick().await;
+            refresh(&settings, &patterns, &client, &timezone, &api).await;
+        }
+    });
+}
+
+async fn refresh(
+    settings: &MaintenanceCalendarConfig,
+    patterns: &[Regex],
+    client: &reqwest::Client,
+    timezone: &Timezone,
+    api: &ApiState,
+) {
+    let events = match ical::fetch(client, &settings.url, timezone).await {
+        Ok(events) => events,
+        Err(e) => {
+            warn!("Failed to load maintenance calendar: {}", e);
+            return;
+        }
+    };
+    
+    let now = Utc::now();
+    let windows: Vec<&CalendarEvent> = events.iter()
+        .filter(|event| event.end > now)
+        .filter(|event| patterns.is_empty() || patterns.iter().any(|pattern| pattern.is_match(&event.summary)))
+        .collect();
+    info!("Loaded {} upcoming maintenance windows from {} calendar events", windows.len(), events.len());
+    api.silences.load_calendar(windows.into_iter().flat_map(|event| silences(settings, event)).collect());
+}
+
+/// One silence per configured rule for the duration of the event. The start
+/// is part of the id, so each occurrence of a recurring event gets its own.
+fn silences(settings: &MaintenanceCalendarConfig, event: &CalendarEvent) -> Vec<Silence> {
+    let every_rule = ["*".to_string()];
+    let rules = if settings.rules.is_empty() { &every_rule[..] } else { &settings.rules[..] };
+    
+    rules.iter()
+        .map(|rule| {
+            let mut hasher = DefaultHasher::new();
+            (&event.summary, event.start, rule).hash(&mut hasher);
+            Silence {
+                id: format!("calendar-{:016x}", hasher.finish()),
+                rule: rule.clone(),
+                container: settings.container.clone(),
+                comment: event.summary.clone(),
+                created_by: CREATED_BY.to_string(),
+                starts_at: event.start,
+                ends_at: event.end,
+            }
+        })
+        .collect()
+}
\ No newline at end of file
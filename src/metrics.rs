@@ -0,0 +1,367 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::Mutex;
+use std::time::Duration;
+use crate::cloud_metadata::CloudMetadata;
+use crate::config::Config;
+use crate::kubernetes;
+use crate::network::FamilyTraffic;
+use crate::state::StateStore;
+use crate::timezone;
+use crate::units;
+
+/// Upper bounds (seconds) of the cycle duration histogram buckets.
+pub const CYCLE_DURATION_BUCKETS: [f64; 9] = [0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0, 300.0];
+
+/// Internal counters of the monitor itself, persisted in the state file so they
+/// survive restarts.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuntimeMetricsSnapshot {
+    #[serde(default = "Utc::now")]
+    pub since: DateTime<Utc>,
+    #[serde(default)]
+    pub cycles_total: u64,
+    #[serde(default)]
+    pub cycle_duration_buckets: Vec<u64>,
+    #[serde(default)]
+    pub cycle_duration_sum: f64,
+    #[serde(default)]
+    pub last_cycle_duration: f64,
+    #[serde(default)]
+    pub docker_api_errors_total: u64,
+    /// Resident and peak resident memory of the monitor process in bytes.
+    #[serde(default)]
+    pub rss_bytes: u64,
+    #[serde(default)]
+    pub peak_rss_bytes: u64,
+    #[serde(default)]
+    pub notifications_sent: HashMap<String, u64>,
+    #[serde(default)]
+    pub notifications_failed: HashMap<String, u64>,
+    #[serde(skip)]
+    pub queue_depth: usize,
+    #[serde(skip)]
+    pub host_cpu: Option<f64>,
+    #[serde(skip)]
+    pub host_memory: Option<f64>,
+    #[serde(skip)]
+    pub network_traffic: Vec<FamilyTraffic>,
+    #[serde(skip)]
+    pub cpu_frequency_mhz: Option<f64>,
+    #[serde(skip)]
+    pub command_values: BTreeMap<String, f64>,
+    /// Whether this replica leads, `None` without leader election.
+    #[serde(skip)]
+    pub leader: Option<bool>,
+    /// Cloud instance whose labels are added to every series.
+    #[serde(skip)]
+    pub instance: Option<CloudMetadata>,
+}
+
+impl Default for RuntimeMetricsSnapshot {
+    fn default() -> Self {
+        Self {
+            since: Utc::now(),
+            cycles_total: 0,
+            cycle_duration_buckets: vec![0; CYCLE_DURATION_BUCKETS.len()],
+            cycle_duration_sum: 0.0,
+            last_cycle_duration: 0.0,
+            docker_api_errors_total: 0,
+            rss_bytes: 0,
+            peak_rss_bytes: 0,
+            notifications_sent: HashMap::new(),
+            notifications_failed: HashMap::new(),
+            queue_depth: 0,
+            host_cpu: None,
+            host_memory: None,
+            network_traffic: Vec::new(),
+            cpu_frequency_mhz: None,
+            command_values: BTreeMap::new(),
+            leader: None,
+            instance: None,
+        }
+    }
+}
+
+impl RuntimeMetricsSnapshot {
+    /// Renders the counters in the Prometheus text exposition format.
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::new();
+        let instance = self.instance.as_ref().map(CloudMetadata::prometheus_labels).unwrap_or_default();
+        
+        let _ = writeln!(out, "# HELP perfmon_cycles_total Monitoring cycles completed.");
+        let _ = writeln!(out, "# TYPE perfmon_cycles_total counter");
+        let _ = writeln!(out, "perfmon_cycles_total{} {}", series(&instance, ""), self.cycles_total);
+        
+        let _ = writeln!(out, "# HELP perfmon_cycle_duration_seconds Duration of monitoring cycles.");
+        let _ = writeln!(out, "# TYPE perfmon_cycle_duration_seconds histogram");
+        let mut cumulative = 0;
+        for (bound, count) in CYCLE_DURATION_BUCKETS.iter().zip(&self.cycle_duration_buckets) {
+            cumulative += count;
+            let _ = writeln!(out, "perfmon_cycle_duration_seconds_bucket{} {}", series(&instance, &format!("le=\"{}\"", bound)), cumulative);
+        }
+        let _ = writeln!(out, "perfmon_cycle_duration_seconds_bucket{} {}", series(&instance, "le=\"+Inf\""), self.cycles_total);
+        let _ = writeln!(out, "perfmon_cycle_duration_seconds_sum{} {:.3}", series(&instance, ""), self.cycle_duration_sum);
+        let _ = writeln!(out, "perfmon_cycle_duration_seconds_count{} {}", series(&instance, ""), self.cycles_total);
+        
+        let _ = writeln!(out, "# HELP perfmon_docker_api_errors_total Failed Docker API requests.");
+        let _ = writeln!(out, "# TYPE perfmon_docker_api_errors_total counter");
+        let _ = writeln!(out, "perfmon_docker_api_errors_total{} {}", series(&instance, ""), self.docker_api_errors_total);
+        
+        let _ = writeln!(out, "# HELP perfmon_process_resident_memory_bytes Resident memory of the monitor process.");
+        let _ = writeln!(out, "# TYPE perfmon_process_resident_memory_bytes gauge");
+        let _ = writeln!(out, "perfmon_process_resident_memory_bytes{} {}", series(&instance, ""), self.rss_bytes);
+        let _ = writeln!(out, "# HELP perfmon_process_peak_resident_memory_bytes Peak resident memory of the monitor process.");
+        let _ = writeln!(out, "# TYPE perfmon_process_peak_resident_memory_bytes gauge");
+        let _ = writeln!(out, "perfmon_process_peak_resident_memory_bytes{} {}", series(&instance, ""), self.peak_rss_bytes);
+        
+        let _ = writeln!(out, "# HELP perfmon_notifications_total Notifications by channel and result.");
+        let _ = writeln!(out, "# TYPE perfmon_notifications_total counter");
+        let sent: BTreeMap<_, _> = self.notifications_sent.iter().collect();
+        for (channel, count) in sent {
+            let _ = writeln!(out, "perfmon_notifications_total{} {}", series(&instance, &format!("channel=\"{}\",result=\"sent\"", channel)), count);
+        }
+        let failed: BTreeMap<_, _> = self.notifications_failed.iter().collect();
+        for (channel, count) in failed {
+            let _ = writeln!(out, "perfmon_notifications_total{} {}", series(&instance, &format!("channel=\"{}\",result=\"failed\"", channel)), count);
+        }
+        
+        let _ = writeln!(out, "# HELP perfmon_notification_queue_depth Alerts waiting in the dispatch queue.");
+        let _ = writeln!(out, "# TYPE perfmon_notification_queue_depth gauge");
+        let _ = writeln!(out, "perfmon_notification_queue_depth{} {}", series(&instance, ""), self.queue_depth);
+        
+        if let Some(leader) = self.leader {
+            let _ = writeln!(out, "# HELP perfmon_leader Whether this replica leads its namespace and sends alerts.");
+            let _ = writeln!(out, "# TYPE perfmon_leader gauge");
+            let _ = writeln!(out, "perfmon_leader{} {}", series(&instance, ""), u8::from(leader));
+        }
+        if let Some(cluster) = kubernetes::current() {
+            let _ = writeln!(out, "# HELP perfmon_node_info Node and pod the monitor runs in, for joining per node.");
+            let _ = writeln!(out, "# TYPE perfmon_node_info gauge");
+            let _ = writeln!(out, "perfmon_node_info{} 1", series(&instance, &format!("node=\"{}\",namespace=\"{}\",pod=\"{}\"", cluster.node, cluster.namespace, cluster.pod)));
+        }
+        if let Some(cpu) = self.host_cpu {
+            let _ = writeln!(out, "# HELP perfmon_host_cpu_percent Host CPU usage in the last cycle.");
+            let _ = writeln!(out, "# TYPE perfmon_host_cpu_percent gauge");
+            let _ = writeln!(out, "perfmon_host_cpu_percent{} {:.2}", series(&instance, ""), cpu);
+        }
+        if let Some(memory) = self.host_memory {
+            let _ = writeln!(out, "# HELP perfmon_host_memory_percent Host memory usage in the last cycle.");
+            let _ = writeln!(out, "# TYPE perfmon_host_memory_percent gauge");
+            let _ = writeln!(out, "perfmon_host_memory_percent{} {:.2}", series(&instance, ""), memory);
+        }
+        if let Some(frequency) = self.cpu_frequency_mhz {
+            let _ = writeln!(out, "# HELP perfmon_host_cpu_frequency_mhz Average CPU frequency across cores in the last cycle.");
+            let _ = writeln!(out, "# TYPE perfmon_host_cpu_frequency_mhz gauge");
+            let _ = writeln!(out, "perfmon_host_cpu_frequency_mhz{} {:.0}", series(&instance, ""), frequency);
+        }
+        if !self.command_values.is_empty() {
+            let _ = writeln!(out, "# HELP perfmon_command_check_value Value captured from the output of a command check.");
+            let _ = writeln!(out, "# TYPE perfmon_command_check_value gauge");
+            for (check, value) in &self.command_values {
+                let _ = writeln!(out, "perfmon_command_check_value{} {}", series(&instance, &format!("check=\"{}\"", check)), value);
+            }
+        }
+        if !self.network_traffic.is_empty() {
+            let _ = writeln!(out, "# HELP perfmon_host_network_bytes_per_second Host IP traffic by address family over the last cycle.");
+            let _ = writeln!(out, "# TYPE perfmon_host_network_bytes_per_second gauge");
+            for family in &self.network_traffic {
+                let _ = writeln!(out, "perfmon_host_network_bytes_per_second{} {:.1}", series(&instance, &format!("family=\"{}\",direction=\"in\"", family.family)), family.in_bytes_per_sec);
+                let _ = writeln!(out, "perfmon_host_network_bytes_per_second{} {:.1}", series(&instance, &format!("family=\"{}\",direction=\"out\"", family.family)), family.out_bytes_per_sec);
+            }
+            let _ = writeln!(out, "# HELP perfmon_host_network_packets_per_second Host IP packets by address family over the last cycle.");
+            let _ = writeln!(out, "# TYPE perfmon_host_network_packets_per_second gauge");
+            for family in &self.network_traffic {
+                let _ = writeln!(out, "perfmon_host_network_packets_per_second{} {:.1}", series(&instance, &format!("family=\"{}\",direction=\"in\"", family.family)), family.in_packets_per_sec);
+                let _ = writeln!(out, "perfmon_host_network_packets_per_second{} {:.1}", series(&instance, &format!("family=\"{}\",direction=\"out\"", family.family)), family.out_packets_per_sec);
+            }
+        }
+        
+        out
+    }
+}
+
+/// Label set of a series with the instance labels in front, e.g.
+/// `{provider="aws",...,channel="email"}`. Empty when there are no labels.
+fn series(instance: &str, labels: &str) -> String {
+    match (instance.is_empty(), labels.is_empty()) {
+        (true, true) => String::new(),
+        (false, true) => format!("{{{}}}", instance),
+        (true, false) => format!("{{{}}}", labels),
+        (false, false) => format!("{{{},{}}}", instance, labels),
+    }
+}
+
+/// Thread-safe runtime counters shared by the monitor loop, the dispatch
+/// workers and the HTTP endpoint.
+#[derive(Default)]
+pub struct RuntimeMetrics {
+    inner: Mutex<RuntimeMetricsSnapshot>,
+}
+
+impl RuntimeMetrics {
+    /// Continues counting from previously persisted values.
+    pub fn restore(mut snapshot: RuntimeMetricsSnapshot) -> Self {
+        snapshot.cycle_duration_buckets.resize(CYCLE_DURATION_BUCKETS.len(), 0);
+        Self {
+            inner: Mutex::new(snapshot),
+        }
+    }
+    
+    pub fn snapshot(&self) -> RuntimeMetricsSnapshot {
+        self.inner.lock().unwrap().clone()
+    }
+    
+    pub fn record_cycle(&self, duration: Duration) {
+        let seconds = duration.as_secs_f64();
+        let mut metrics = self.inner.lock().unwrap();
+        metrics.cycles_total += 1;
+        metrics.cycle_duration_sum += seconds;
+        metrics.last_cycle_duration = seconds;
+        if let Some(bucket) = CYCLE_DURATION_BUCKETS.iter().position(|bound| seconds <= *bound) {
+            metrics.cycle_duration_buckets[bucket] += 1;
+        }
+    }
+    
+    pub fn record_docker_error(&self) {
+        self.inner.lock().unwrap().docker_api_errors_total += 1;
+    }
+    
+    pub fn record_notification(&self, channel: &str, sent: bool) {
+        let mut metrics = self.inner.lock().unwrap();
+        let counters = if sent { &mut metrics.notifications_sent } else { &mut metrics.notifications_failed };
+        *counters.entry(channel.to_string()).or_insert(0) += 1;
+    }
+    
+    pub fn set_process_memory(&self, memory: ProcessMemory) {
+        let mut metrics = self.inner.lock().unwrap();
+        metrics.rss_bytes = memory.rss;
+        metrics.peak_rss_bytes = metrics.peak_rss_bytes.max(memory.peak_rss);
+    }
+    
+    pub fn set_queue_depth(&self, depth: usize) {
+        self.inner.lock().unwrap().queue_depth = depth;
+    }
+    
+    pub fn set_host_usage(&self, cpu: f64, memory: f64) {
+        let mut metrics = self.inner.lock().unwrap();
+        metrics.host_cpu = Some(cpu);
+        metrics.host_memory = Some(memory);
+    }
+    
+    pub fn set_cpu_frequency(&self, mhz: f64) {
+        self.inner.lock().unwrap().cpu_frequency_mhz = Some(mhz);
+    }
+    
+    pub fn set_command_values(&self, values: BTreeMap<String, f64>) {
+        self.inner.lock().unwrap().command_values = values;
+    }
+    
+    pub fn set_network_traffic(&self, traffic: Vec<FamilyTraffic>) {
+        self.inner.lock().unwrap().network_traffic = traffic;
+    }
+    
+    pub fn set_leader(&self, leader: bool) {
+        self.inner.lock().unwrap().leader = Some(leader);
+    }
+    
+    pub fn set_instance(&self, instance: Option<CloudMetadata>) {
+        self.inner.lock().unwrap().instance = instance;
+    }
+}
+
+/// Resident memory of this process from `/proc/self/status`, in bytes.
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessMemory {
+    pub rss: u64,
+    pub peak_rss: u64,
+}
+
+/// Reads `VmRSS` and `VmHWM`, `None` where `/proc` is not available.
+pub fn process_memory() -> Option<ProcessMemory> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    let field = |name: &str| {
+        status.lines()
+            .find_map(|line| line.strip_prefix(name))
+            .and_then(|value| value.trim().trim_end_matches("kB").trim().parse::<u64>().ok())
+            .map(|kilobytes| kilobytes * 1024)
+    };
+    Some(ProcessMemory {
+        rss: field("VmRSS:")?,
+        peak_rss: field("VmHWM:")?,
+    })
+}
+
+/// Prints the persisted runtime metrics for `stats self`.
+pub fn print_self_stats(config: &Config) {
+    let state = StateStore::open(config);
+    let metrics = &state.state.runtime_metrics;
+    
+    println!("MONITOR RUNTIME STATS (since {})", timezone::load(&config.timezone).format(metrics.since));
+    println!("State storage:       {}", state.describe());
+    println!("Cycles run:          {}", metrics.cycles_total);
+    if metrics.cycles_total > 0 {
+        println!("Avg cycle duration:  {:.2}s", metrics.cycle_duration_sum / metrics.cycles_total as f64);
+        println!("Last cycle duration: {:.2}s", metrics.last_cycle_duration);
+    }
+    println!("Docker API errors:   {}", metrics.docker_api_errors_total);
+    if metrics.rss_bytes > 0 {
+        let budget = match config.monitoring.memory_budget_mb {
+            0 => String::new(),
+            budget => format!(", budget {}", units::mebibytes(budget as f64)),
+        };
+        println!(
+            "Memory (RSS):        {} (peak {}{})",
+            units::bytes(metrics.rss_bytes as f64), units::bytes(metrics.peak_rss_bytes as f64), budget
+        );
+    }
+    
+    let mut channels: Vec<_> = metrics.notifications_sent.keys()
+        .chain(metrics.notifications_failed.keys())
+        .collect();
+    channels.sort();
+    channels.dedup();
+    if channels.is_empty() {
+        println!("Notifications:       none");
+    }
+    for channel in channels {
+        println!(
+            "Notifications ({}): {} sent, {} failed",
+            channel,
+            metrics.notifications_sent.get(channel).unwrap_or(&0),
+            metrics.notifications_failed.get(channel).unwrap_or(&0)
+        );
+    }
+    
+    println!("Cycle duration histogram:");
+    for (bound, count) in CYCLE_DURATION_BUCKETS.iter().zip(&metrics.cycle_duration_buckets) {
+        println!("  <= {:>5}s  {}", bound, count);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn every_series_carries_the_instance_labels() {
+        let mut metrics = RuntimeMetricsSnapshot::default();
+        metrics.notifications_sent.insert("email".to_string(), 2);
+        metrics.host_cpu = Some(41.2);
+        assert!(metrics.to_prometheus().contains("\nperfmon_host_cpu_percent 41.20\n"));
+        
+        metrics.instance = Some(CloudMetadata {
+            provider: "aws".to_string(),
+            instance_id: "i-0abc123".to_string(),
+            region: "eu-west-1".to_string(),
+            instance_type: "c5.large".to_string(),
+        });
+        let text = metrics.to_prometheus();
+        let instance = r#"provider="aws",instance_id="i-0abc123",region="eu-west-1",instance_type="c5.large""#;
+        assert!(text.contains(&format!("perfmon_host_cpu_percent{{{}}} 41.20\n", instance)));
+        assert!(text.contains(&format!("perfmon_notifications_total{{{},channel=\"email\",result=\"sent\"}} 2\n", instance)));
+        assert!(text.lines().filter(|line| !line.starts_with('#')).all(|line| line.contains(instance)));
+    }
+}
@@ -0,0 +1,95 @@
+use chrono::{DateTime, Duration, Utc};
+use std::fmt::Write;
+use crate::alert::Severity;
+use crate::config::Config;
+use crate::snapshot::{self, Snapshot};
+use crate::units;
+use anyhow::{Result, anyhow};
+
+const RED: &str = "31";
+const GREEN: &str = "32";
+const YELLOW: &str = "33";
+const DIM: &str = "2";
+
+/// Number of containers listed by CPU usage.
+const TOP_CONTAINERS: usize = 3;
+
+/// Reads the snapshot the monitor wrote after its last cycle, `file` or
+/// `monitoring.snapshot_file`, and renders the login banner. Nothing is
+/// collected here, so it returns instantly.
+pub fn run(config: &Config, file: Option<&str>, color: bool) -> Result<String> {
+    let path = file.or(config.monitoring.snapshot_file.as_deref())
+        .ok_or_else(|| anyhow!("no snapshot to read, set monitoring.snapshot_file or pass --file"))?;
+    let snapshot = snapshot::read(path)?;
+    let max_age = Duration::seconds(config.monitoring.check_interval as i64 * 3);
+    Ok(render(&snapshot, Utc::now(), max_age, color))
+}
+
+/// A compact health summary. A snapshot older than `max_age` is marked
+/// stale, as the monitor has probably stopped.
+pub fn render(snapshot: &Snapshot, now: DateTime<Utc>, max_age: Duration, color: bool) -> String {
+    let paint = |code: &str, text: &str| {
+        if color {
+            format!("\x1B[{}m{}\x1B[0m", code, text)
+        } else {
+            text.to_string()
+        }
+    };
+    let severity_color = match snapshot.outcome.severity {
+        Severity::Ok => GREEN,
+        Severity::Warning => YELLOW,
+        Severity::Critical => RED,
+    };
+    
+    let age = now - snapshot.timestamp;
+    let mut out = String::new();
+    let _ = write!(out, "{}: {}", snapshot.hostname, paint(severity_color, snapshot.outcome.severity.label()));
+    if age > max_age {
+        let _ = writeln!(out, " {}", paint(YELLOW, &format!("(stale, last check {} ago)", ago(age))));
+    } else {
+        let _ = writeln!(out, " {}", paint(DIM, &format!("(checked {} ago)", ago(age))));
+    }
+    
+    if let Some(server) = &snapshot.server {
+        let _ = writeln!(
+            out,
+            "  CPU {:.0}% · Memory {:.0}% ({}/{}) · Disk {:.0}% · Load {:.2} {:.2} {:.2}",
+            server.cpu_usage,
+            server.memory_usage.percent,
+            units::bytes(server.memory_usage.used as f64),
+            units::bytes(server.memory_usage.total as f64),
+            server.disk_usage.percent,
+            server.load_average.one_min,
+            server.load_average.five_min,
+            server.load_average.fifteen_min,
+        );
+    }
+    
+    if !snapshot.containers.is_empty() {
+        let mut busiest: Vec<_> = snapshot.containers.iter().collect();
+        busiest.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+        let top: Vec<String> = busiest.iter()
+            .take(TOP_CONTAINERS)
+            .map(|container| format!("{} {:.1}%", container.name, container.cpu_usage))
+            .collect();
+        let _ = writeln!(out, "  Containers: {} (top CPU: {})", snapshot.containers.len(), top.join(", "));
+    }
+    
+    for problem in &snapshot.outcome.problems {
+        let _ = writeln!(out, "  {}", paint(severity_color, &format!("! {}", problem)));
+    }
+    if !snapshot.firing.is_empty() {
+        let _ = writeln!(out, "  Firing: {}", snapshot.firing.join(", "));
+    }
+    out
+}
+
+fn ago(age: Duration) -> String {
+    let seconds = age.num_seconds().max(0);
+    match seconds {
+        0..=59 => format!("{}s", seconds),
+        60..=3599 => format!("{}m", seconds / 60),
+        3600..=86399 => format!("{}h", seconds / 3600),
+        _ => format!("{}d", seconds / 86400),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,331 @@
+use futures_util::stream::{FuturesUnordered, StreamExt};
+use hyper::client::connect::dns::Name;
+use reqwest::dns::{Addrs, Resolve, Resolving};
+use reqwest::{Client, NoProxy, Proxy, Url};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::net::TcpStream;
+use crate::config::NetworkConfig;
+use crate::platform;
+use crate::units;
+use log::{debug, warn};
+
+const SNMP_PATH: &str = "/proc/net/snmp";
+const NETSTAT_PATH: &str = "/proc/net/netstat";
+const SNMP6_PATH: &str = "/proc/net/snmp6";
+
+/// Cumulative IP counters of one address family.
+#[derive(Debug, Clone, Copy)]
+struct FamilyCounters {
+    in_packets: u64,
+    out_packets: u64,
+    in_bytes: u64,
+    out_bytes: u64,
+}
+
+/// Host traffic of one address family over the last check interval.
+#[derive(Debug, Clone, Serialize)]
+pub struct FamilyTraffic {
+    /// `ipv4` or `ipv6`.
+    pub family: &'static str,
+    pub in_packets_per_sec: f64,
+    pub out_packets_per_sec: f64,
+    pub in_bytes_per_sec: f64,
+    pub out_bytes_per_sec: f64,
+}
+
+impl FamilyTraffic {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: in {:.0} pkt/s ({}/s), out {:.0} pkt/s ({}/s)",
+            self.family,
+            self.in_packets_per_sec,
+            units::bytes(self.in_bytes_per_sec),
+            self.out_packets_per_sec,
+            units::bytes(self.out_bytes_per_sec)
+        )
+    }
+}
+
+/// Host IP traffic per address family, from the kernel's SNMP counters. A
+/// family is only reported when the kernel exposes it (no IPv6 counters when
+/// IPv6 is disabled).
+#[derive(Default)]
+pub struct TrafficMonitor {
+    previous: HashMap<&'static str, FamilyCounters>,
+    sampled_at: Option<Instant>,
+}
+
+impl TrafficMonitor {
+    /// Returns the traffic since the last sample. The first call only records
+    /// a baseline, and a family whose counters went backwards (counter reset,
+    /// network namespace change) is skipped for one sample.
+    pub fn sample(&mut self) -> Vec<FamilyTraffic> {
+        let now = Instant::now();
+        let current = read_counters();
+        let elapsed = self.sampled_at.map(|at| now.duration_since(at).as_secs_f64());
+        
+        let mut traffic = Vec::new();
+        if let Some(elapsed) = elapsed.filter(|elapsed| *elapsed > 0.0) {
+            for (family, counters) in &current {
+                let Some(previous) = self.previous.get(family) else {
+                    continue;
+                };
+                let delta = |current: u64, previous: u64| current.checked_sub(previous);
+                let (Some(in_packets), Some(out_packets), Some(in_bytes), Some(out_bytes)) = (
+                    delta(counters.in_packets, previous.in_packets),
+                    delta(counters.out_packets, previous.out_packets),
+                    delta(counters.in_bytes, previous.in_bytes),
+                    delta(counters.out_bytes, previous.out_bytes),
+                ) else {
+                    debug!("{} counters went backwards, skipping this sample", family);
+                    continue;
+                };
+                
+                traffic.push(FamilyTraffic {
+                    family,
+                    in_packets_per_sec: in_packets as f64 / elapsed,
+                    out_packets_per_sec: out_packets as f64 / elapsed,
+                    in_bytes_per_sec: in_bytes as f64 / elapsed,
+                    out_bytes_per_sec: out_bytes as f64 / elapsed,
+                });
+            }
+        }
+        
+        traffic.sort_by_key(|family| family.family);
+        self.previous = current;
+        self.sampled_at = Some(now);
+        traffic
+    }
+}
+
+fn read_counters() -> HashMap<&'static str, FamilyCounters> {
+    let mut counters = HashMap::new();
+    if !platform::has_procfs() {
+        return counters;
+    }
+    
+    // IPv4 packets are in /proc/net/snmp, octets in the IpExt section of /proc/net/netstat
+    match (fs::read_to_string(SNMP_PATH), fs::read_to_string(NETSTAT_PATH)) {
+        (Ok(snmp), Ok(netstat)) => {
+            let ip = parse_sections(&snmp);
+            let ip_ext = parse_sections(&netstat);
+            let value = |sections: &HashMap<String, HashMap<String, u64>>, section: &str, field: &str| {
+                sections.get(section).and_then(|fields| fields.get(field)).copied()
+            };
+            if let (Some(in_packets), Some(out_packets), Some(in_bytes), Some(out_bytes)) = (
+                value(&ip, "Ip", "InReceives"),
+                value(&ip, "Ip", "OutRequests"),
+                value(&ip_ext, "IpExt", "InOctets"),
+                value(&ip_ext, "IpExt", "OutOctets"),
+            ) {
+                counters.insert("ipv4", FamilyCounters { in_packets, out_packets, in_bytes, out_bytes });
+            }
+        }
+        (Err(e), _) | (_, Err(e)) => warn!("Failed to read IPv4 counters: {}", e),
+    }
+    
+    // Missing when IPv6 is disabled
+    if let Ok(snmp6) = fs::read_to_string(SNMP6_PATH) {
+        let fields = parse_snmp6(&snmp6);
+        if let (Some(in_packets), Some(out_packets), Some(in_bytes), Some(out_bytes)) = (
+            fields.get("Ip6InReceives"),
+            fields.get("Ip6OutRequests"),
+            fields.get("Ip6InOctets"),
+            fields.get("Ip6OutOctets"),
+        ) {
+            counters.insert("ipv6", FamilyCounters {
+                in_packets: *in_packets,
+                out_packets: *out_packets,
+                in_bytes: *in_bytes,
+                out_bytes: *out_bytes,
+            });
+        }
+    }
+    
+    counters
+}
+
+/// Parses the `Section: names...` / `Section: values...` line pairs of
+/// /proc/net/snmp and /proc/net/netstat. Sections are paired by prefix rather
+/// than position, so a section spread over several frames or printed out of
+/// order is still matched correctly.
+fn parse_sections(content: &str) -> HashMap<String, HashMap<String, u64>> {
+    let mut headers: HashMap<&str, Vec<&str>> = HashMap::new();
+    let mut sections: HashMap<String, HashMap<String, u64>> = HashMap::new();
+    
+    for line in content.lines() {
+        let Some((section, rest)) = line.split_once(':') else {
+            continue;
+        };
+        let columns: Vec<&str> = rest.split_whitespace().collect();
+        let is_header = columns.first().is_some_and(|column| column.parse::<i64>().is_err());
+        
+        if is_header {
+            headers.insert(section, columns);
+        } else if let Some(names) = headers.remove(section) {
+            let fields = sections.entry(section.to_string()).or_default();
+            for (name, value) in names.iter().zip(&columns) {
+                // Some fields (e.g. Ip Forwarding, Tcp MaxConn) can be negative
+                if let Ok(value) = value.parse::<u64>() {
+                    fields.insert(name.to_string(), value);
+                }
+            }
+        }
+    }
+    
+    sections
+}
+
+fn parse_snmp6(content: &str) -> HashMap<String, u64> {
+    content.lines()
+        .filter_map(|line| {
+            let mut columns = line.split_whitespace();
+            Some((columns.next()?.to_string(), columns.next()?.parse().ok()?))
+        })
+        .collect()
+}
+
+/// Resolves a host and orders its addresses for connecting. With `ipv4` or
+/// `ipv6` the preferred family comes first; `auto` keeps the resolver's first
+/// family and interleaves the rest as recommended by RFC 8305.
+pub async fn resolve(host: &str, port: u16, config: &NetworkConfig) -> io::Result<Vec<SocketAddr>> {
+    let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host, port)).await?.collect();
+    if addresses.is_empty() {
+        return Err(io::Error::new(io::ErrorKind::NotFound, format!("{} has no addresses", host)));
+    }
+    Ok(order_addresses(addresses, &config.ip_preference))
+}
+
+fn order_addresses(addresses: Vec<SocketAddr>, preference: &str) -> Vec<SocketAddr> {
+    let prefer_v6 = match preference {
+        "ipv4" => false,
+        "ipv6" => true,
+        _ => addresses[0].is_ipv6(),
+    };
+    let (preferred, fallback): (Vec<SocketAddr>, Vec<SocketAddr>) = addresses.into_iter()
+        .partition(|address| address.is_ipv6() == prefer_v6);
+    
+    if preference == "ipv4" || preference == "ipv6" {
+        return preferred.into_iter().chain(fallback).collect();
+    }
+    
+    let mut ordered = Vec::with_capacity(preferred.len() + fallback.len());
+    let mut preferred = preferred.into_iter();
+    let mut fallback = fallback.into_iter();
+    loop {
+        match (preferred.next(), fallback.next()) {
+            (None, None) => break,
+            (first, second) => ordered.extend(first.into_iter().chain(second)),
+        }
+    }
+    ordered
+}
+
+/// Connects to a host with Happy Eyeballs (RFC 8305): addresses are tried in
+/// preference order, starting the next attempt when the previous one fails
+/// or has not connected within `happy_eyeballs_delay_ms`. The first
+/// established connection wins, so a broken IPv6 (or IPv4) path only costs
+/// the delay instead of a full connect timeout.
+pub async fn connect(host: &str, port: u16, config: &NetworkConfig) -> io::Result<TcpStream> {
+    let delay = Duration::from_millis(config.happy_eyeballs_delay_ms);
+    let mut pending = resolve(host, port, config).await?.into_iter().peekable();
+    let mut attempts = FuturesUnordered::new();
+    let mut last_error = None;
+    
+    while pending.peek().is_some() || !attempts.is_empty() {
+        if let Some(address) = pending.next() {
+            attempts.push(TcpStream::connect(address));
+        }
+        
+        let result = if pending.peek().is_some() {
+            match tokio::time::timeout(delay, attempts.next()).await {
+                Ok(result) => result,
+                // Still connecting, race the next address
+                Err(_) => continue,
+            }
+        } else {
+            attempts.next().await
+        };
+        
+        match result {
+            Some(Ok(stream)) => return Ok(stream),
+            Some(Err(e)) => last_error = Some(e),
+            None => {}
+        }
+    }
+    
+    Err(last_error.unwrap_or_else(|| io::Error::new(io::ErrorKind::NotFound, format!("{} has no addresses", host))))
+}
+
+/// Resolver for outbound HTTP clients that orders addresses by the configured
+/// preference. The HTTP connector already races the address families.
+struct PreferenceResolver {
+    preference: String,
+}
+
+impl Resolve for PreferenceResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_string();
+        let preference = self.preference.clone();
+        Box::pin(async move {
+            let addresses: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if addresses.is_empty() {
+                return Err(format!("{} has no addresses", host).into());
+            }
+            let addresses: Addrs = Box::new(order_addresses(addresses, &preference).into_iter());
+            Ok(addresses)
+        })
+    }
+}
+
+/// HTTP client for notification channels and hooks, honoring `ip_preference`
+/// and the proxy settings. Without a configured proxy, reqwest picks up
+/// HTTP_PROXY, HTTPS_PROXY, ALL_PROXY and NO_PROXY from the environment.
+pub fn http_client(config: &NetworkConfig) -> Client {
+    let mut builder = Client::builder();
+    if config.ip_preference == "ipv4" || config.ip_preference == "ipv6" {
+        builder = builder.dns_resolver(Arc::new(PreferenceResolver {
+            preference: config.ip_preference.clone(),
+        }));
+    }
+    if let Some(url) = &config.proxy {
+        match Proxy::all(url) {
+            Ok(proxy) => builder = builder.proxy(proxy.no_proxy(NoProxy::from_string(&config.no_proxy.join(",")))),
+            Err(e) => warn!("Invalid proxy {}: {}. Using the environment proxy settings.", redact_proxy(url), e),
+        }
+    }
+    builder.build().unwrap_or_else(|e| {
+        warn!("Failed to build HTTP client with ip_preference {}: {}", config.ip_preference, e);
+        Client::new()
+    })
+}
+
+/// Proxy HTTPS requests go through, and where it was configured.
+pub fn effective_proxy(config: &NetworkConfig) -> Option<(String, &'static str)> {
+    if let Some(url) = &config.proxy {
+        return Some((url.clone(), "network.proxy"));
+    }
+    ["HTTPS_PROXY", "https_proxy", "ALL_PROXY", "all_proxy", "HTTP_PROXY", "http_proxy"]
+        .into_iter()
+        .find_map(|name| env::var(name).ok().filter(|value| !value.is_empty()).map(|value| (value, name)))
+}
+
+/// Proxy URL without its credentials, for logs and diagnostics.
+pub fn redact_proxy(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) if !parsed.username().is_empty() || parsed.password().is_some() => {
+            let _ = parsed.set_username("***");
+            let _ = parsed.set_password(None);
+            parsed.to_string()
+        }
+        Ok(parsed) => parsed.to_string(),
+        Err(_) => url.to_string(),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+use chrono::{DateTime, Duration, Utc};
+use std::sync::{Arc, Mutex};
+use crate::config::{Config, OnCallConfig};
+use crate::ical::{self, CalendarEvent};
+use crate::network;
+use crate::timezone::{self, Timezone};
+use log::{info, warn};
+
+/// Works out who is on call from the rotation and calendar of `oncall`.
+pub struct OnCall {
+    config: OnCallConfig,
+    rotation_start: Option<DateTime<Utc>>,
+    timezone: Timezone,
+    calendar: Mutex<Vec<CalendarEvent>>,
+    /// The last recipient handed out, to log handoffs once.
+    last: Mutex<Option<String>>,
+}
+
+impl OnCall {
+    /// `None` when on-call routing is disabled.
+    pub fn new(config: &Config) -> Option<Arc<Self>> {
+        let settings = config.oncall.clone();
+        if !settings.enabled {
+            return None;
+        }
+        
+        let rotation_start = match DateTime::parse_from_rfc3339(&settings.rotation_start) {
+            Ok(start) => Some(start.with_timezone(&Utc)),
+            Err(_) if settings.rotation.is_empty() => None,
+            Err(e) => {
+                warn!("Invalid oncall.rotation_start '{}': {}, the rotation is ignored", settings.rotation_start, e);
+                None
+            }
+        };
+        if settings.rotation.is_empty() && settings.ical_url.is_none() {
+            warn!("oncall is enabled without a rotation or ical_url, alerts go to email.recipient_email");
+        }
+        
+        Some(Arc::new(Self {
+            config: settings,
+            rotation_start,
+            timezone: timezone::load(&config.timezone),
+            calendar: Mutex::new(Vec::new()),
+            last: Mutex::new(None),
+        }))
+    }
+    
+    /// Downloads the calendar once, then keeps refreshing it in the
+    /// background. A failed download keeps the previous events.
+    pub async fn start(self: &Arc<Self>, config: &Config) {
+        let Some(url) = self.config.ical_url.clone() else {
+            return;
+        };
+        let client = network::http_client(&config.network);
+        self.refresh(&client, &url).await;
+        
+        let oncall = self.clone();
+        let period = std::time::Duration::from_secs(self.config.ical_refresh.max(60));
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval_at(tokio::time::Instant::now() + period, period);
+            loop {
+                interval.tick().await;
+                oncall.refresh(&client, &url).await;
+            }
+        });
+    }
+    
+    async fn refresh(&self, client: &reqwest::Client, url: &str) {
+        match ical::fetch(client, url, &self.timezone).await {
+            Ok(events) => {
+                info!("Loaded {} on-call calendar events", events.len());
+                *self.calendar.lock().unwrap() = events;
+            }
+            Err(e) => warn!("Failed to load on-call calendar: {}", e),
+        }
+    }
+    
+    /// The on-call address at `now`, `None` when nobody is scheduled.
+    pub fn current(&self, now: DateTime<Utc>) -> Option<String> {
+        let current = self.calendar_shift(now).or_else(|| self.rotation_shift(now));
+        let mut last = self.last.lock().unwrap();
+        if *last != current {
+            match &current {
+                Some(recipient) => info!("{} is now on call", recipient),
+                None => info!("Nobody is on call, alerts go to email.recipient_email"),
+            }
+            last.clone_from(&current);
+        }
+        current
+    }
+    
+    /// The first attendee of an event covering `now`, or its summary when
+    /// that is an address.
+    fn calendar_shift(&self, now: DateTime<Utc>) -> Option<String> {
+        let calendar = self.calendar.lock().unwrap();
+        calendar.iter()
+            .filter(|event| event.covers(now))
+            .find_map(|event| event.attendees.first().cloned().or_else(|| {
+                let summary = event.summary.trim();
+                summary.contains('@').then(|| summary.to_string())
+            }))
+    }
+    
+    fn rotation_shift(&self, now: DateTime<Utc>) -> Option<String> {
+        let start = self.rotation_start?;
+        if self.config.rotation.is_empty() || now < start {
+            return None;
+        }
+        let shifts = (now - start).num_seconds() / Duration::days(self.config.rotation_days.max(1) as i64).num_seconds();
+        self.config.rotation.get(shifts as usize % self.config.rotation.len()).cloned()
+    }
+}
\ No newline at end of file
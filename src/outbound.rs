@@ -0,0 +1,291 @@
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use std::collections::HashMap;
+use std::fs;
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::time::{Duration, Instant};
+use crate::config::{Config, OutboundConfig, WatchlistConfig};
+use crate::network;
+use crate::timezone::Timezone;
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+
+/// Source name of connections opened in the host's own network namespace.
+pub const HOST: &str = "host";
+
+const RESOLVE_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Socket states of `/proc/net/tcp`.
+const TCP_ESTABLISHED: u8 = 0x01;
+const TCP_SYN_SENT: u8 = 0x02;
+const TCP_LISTEN: u8 = 0x0A;
+
+/// An outbound TCP connection, established or still connecting.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Connection {
+    /// `host` or the container name.
+    pub source: String,
+    pub local: SocketAddr,
+    pub remote: SocketAddr,
+}
+
+/// A connection to an address on a watchlist.
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub connection: Connection,
+    pub watchlist: String,
+    /// When this source was first seen talking to this address, counted
+    /// from the cycles it was seen without a break.
+    pub first_seen: DateTime<Utc>,
+}
+
+impl Hit {
+    pub fn describe(&self, timezone: &Timezone) -> String {
+        format!(
+            "{} → {} on watchlist {} (since {})",
+            self.connection.source, self.connection.remote, self.watchlist, timezone.format(self.first_seen)
+        )
+    }
+}
+
+/// An address range, IPv4 kept as IPv4-mapped IPv6.
+#[derive(Debug, Clone, Copy)]
+struct Range {
+    network: u128,
+    prefix: u32,
+}
+
+impl Range {
+    fn new(ip: IpAddr, prefix: Option<u32>) -> Option<Self> {
+        let (network, prefix) = match ip {
+            IpAddr::V4(v4) => (u128::from(v4.to_ipv6_mapped()), 96 + prefix.unwrap_or(32)),
+            IpAddr::V6(v6) => (u128::from(v6), prefix.unwrap_or(128)),
+        };
+        (prefix <= 128).then(|| Self { network: network & mask(prefix), prefix })
+    }
+    
+    fn contains(&self, ip: IpAddr) -> bool {
+        (address(ip) & mask(self.prefix)) == self.network
+    }
+}
+
+struct Watchlist {
+    name: String,
+    ranges: Vec<Range>,
+}
+
+/// Samples outbound connections and matches them against the configured
+/// watchlists, which are reloaded every `refresh_hours`.
+pub struct OutboundMonitor {
+    config: OutboundConfig,
+    client: Client,
+    watchlists: Vec<Watchlist>,
+    loaded_at: Option<Instant>,
+    /// First sighting of each (source, address, watchlist) hit of the last cycle.
+    first_seen: HashMap<(String, IpAddr, String), DateTime<Utc>>,
+}
+
+impl OutboundMonitor {
+    /// `None` when disabled or without watchlists.
+    pub fn new(config: &Config) -> Option<Self> {
+        let settings = config.outbound.clone();
+        if !settings.enabled {
+            return None;
+        }
+        if settings.watchlists.is_empty() {
+            warn!("outbound is enabled without watchlists, connections are not checked");
+            return None;
+        }
+        
+        Some(Self {
+            client: network::http_client(&config.network),
+            watchlists: settings.watchlists.iter()
+                .map(|watchlist| Watchlist { name: watchlist.name.clone(), ranges: Vec::new() })
+                .collect(),
+            config: settings,
+            loaded_at: None,
+            first_seen: HashMap::new(),
+        })
+    }
+    
+    /// Whether container network namespaces should be sampled too.
+    pub fn wants_containers(&self) -> bool {
+        self.config.containers
+    }
+    
+    /// Samples the host and the given `(name, pid)` containers and returns
+    /// the connections to watchlisted addresses.
+    pub async fn check(&mut self, containers: &[(String, u32)]) -> Vec<Hit> {
+        let refresh = Duration::from_secs(self.config.refresh_hours.max(1) * 3600);
+        if self.loaded_at.is_none_or(|loaded_at| loaded_at.elapsed() >= refresh) {
+            self.reload().await;
+        }
+        
+        let now = Utc::now();
+        let mut hits = Vec::new();
+        let mut first_seen = HashMap::new();
+        for connection in sample(containers) {
+            let ip = connection.remote.ip();
+            for watchlist in self.watchlists.iter().filter(|watchlist| watchlist.ranges.iter().any(|range| range.contains(ip))) {
+                let key = (connection.source.clone(), ip, watchlist.name.clone());
+                let since = match self.first_seen.get(&key).or_else(|| first_seen.get(&key)) {
+                    Some(since) => *since,
+                    None => {
+                        warn!("{} connected to {}, which is on watchlist {}", connection.source, connection.remote, watchlist.name);
+                        now
+                    }
+                };
+                first_seen.insert(key, since);
+                hits.push(Hit { connection: connection.clone(), watchlist: watchlist.name.clone(), first_seen: since });
+            }
+        }
+        self.first_seen = first_seen;
+        hits
+    }
+    
+    /// Reloads every watchlist, keeping the previous ranges of a list whose
+    /// source cannot be read.
+    async fn reload(&mut self) {
+        self.loaded_at = Some(Instant::now());
+        for (config, watchlist) in self.config.watchlists.iter().zip(self.watchlists.iter_mut()) {
+            match load(config, &self.client).await {
+                Ok(ranges) => {
+                    info!("Loaded watchlist {} with {} address ranges", config.name, ranges.len());
+                    watchlist.ranges = ranges;
+                }
+                Err(e) => warn!("Failed to load watchlist {}: {}", config.name, e),
+            }
+        }
+    }
+}
+
+async fn load(config: &WatchlistConfig, client: &Client) -> Result<Vec<Range>> {
+    let mut lines = config.entries.clone();
+    if let Some(path) = &config.path {
+        let text = fs::read_to_string(path).map_err(|e| anyhow!("cannot read {}: {}", path, e))?;
+        lines.extend(text.lines().map(str::to_string));
+    }
+    if let Some(url) = &config.url {
+        let response = client.get(url).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("{} returned {}", url, response.status()));
+        }
+        lines.extend(response.text().await?.lines().map(str::to_string));
+    }
+    
+    let mut ranges = Vec::new();
+    for line in &lines {
+        let Some(entry) = line.split('#').next().and_then(|entry| entry.split_whitespace().next()) else {
+            continue;
+        };
+        let (ip, prefix) = match entry.split_once('/') {
+            Some((ip, prefix)) => match prefix.parse() {
+                Ok(prefix) => (ip, Some(prefix)),
+                Err(_) => {
+                    warn!("Ignoring invalid range {} in watchlist {}", entry, config.name);
+                    continue;
+                }
+            },
+            None => (entry, None),
+        };
+        if let Ok(ip) = ip.parse::<IpAddr>() {
+            match Range::new(ip, prefix) {
+                Some(range) => ranges.push(range),
+                None => warn!("Ignoring invalid range {} in watchlist {}", entry, config.name),
+            }
+            continue;
+        }
+        
+        // Anything else is a host name, e.g. a mining pool
+        match tokio::time::timeout(RESOLVE_TIMEOUT, tokio::net::lookup_host((entry, 0))).await {
+            Ok(Ok(addresses)) => ranges.extend(addresses.filter_map(|address| Range::new(address.ip(), None))),
+            _ => warn!("Could not resolve {} in watchlist {}", entry, config.name),
+        }
+    }
+    Ok(ranges)
+}
+
+/// Outbound connections of the host and of every container with its own
+/// network namespace. Containers on the host network are covered by the
+/// host table.
+fn sample(containers: &[(String, u32)]) -> Vec<Connection> {
+    let mut connections = read_tables("/proc/net", HOST);
+    let host_namespace = fs::read_link("/proc/self/ns/net").ok();
+    for (name, pid) in containers {
+        let namespace = fs::read_link(format!("/proc/{}/ns/net", pid)).ok();
+        if namespace.is_none() || namespace == host_namespace {
+            continue;
+        }
+        connections.extend(read_tables(&format!("/proc/{}/net", pid), name));
+    }
+    connections
+}
+
+/// Established and connecting sockets of `tcp` and `tcp6` in `directory`
+/// whose local port is not a listening one, so accepted connections are
+/// left out.
+fn read_tables(directory: &str, source: &str) -> Vec<Connection> {
+    let mut sockets = Vec::new();
+    for table in ["tcp", "tcp6"] {
+        let Ok(text) = fs::read_to_string(format!("{}/{}", directory, table)) else {
+            continue;
+        };
+        for line in text.lines().skip(1) {
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            if let (Some(local), Some(remote), Some(state)) = (
+                fields.get(1).and_then(|field| socket_address(field)),
+                fields.get(2).and_then(|field| socket_address(field)),
+                fields.get(3).and_then(|field| u8::from_str_radix(field, 16).ok()),
+            ) {
+                sockets.push((local, remote, state));
+            }
+        }
+    }
+    
+    let listening: Vec<u16> = sockets.iter()
+        .filter(|(_, _, state)| *state == TCP_LISTEN)
+        .map(|(local, _, _)| local.port())
+        .collect();
+    sockets.into_iter()
+        .filter(|(local, remote, state)| {
+            matches!(*state, TCP_ESTABLISHED | TCP_SYN_SENT)
+                && !listening.contains(&local.port())
+                && !remote.ip().is_loopback()
+                && !remote.ip().is_unspecified()
+        })
+        .map(|(local, remote, _)| Connection { source: source.to_string(), local, remote })
+        .collect()
+}
+
+/// Decodes `0100007F:0016`, the address printed as native-endian 32-bit
+/// words, port in hex.
+fn socket_address(field: &str) -> Option<SocketAddr> {
+    let (ip, port) = field.split_once(':')?;
+    let port = u16::from_str_radix(port, 16).ok()?;
+    let mut bytes = Vec::with_capacity(16);
+    for word in ip.as_bytes().chunks(8) {
+        let word = u32::from_str_radix(std::str::from_utf8(word).ok()?, 16).ok()?;
+        bytes.extend_from_slice(&word.to_ne_bytes());
+    }
+    
+    let ip = match bytes.len() {
+        4 => IpAddr::V4(Ipv4Addr::new(bytes[0], bytes[1], bytes[2], bytes[3])),
+        16 => {
+            let v6 = Ipv6Addr::from(<[u8; 16]>::try_from(bytes).ok()?);
+            v6.to_ipv4_mapped().map_or(IpAddr::V6(v6), IpAddr::V4)
+        }
+        _ => return None,
+    };
+    Some(SocketAddr::new(ip, port))
+}
+
+fn address(ip: IpAddr) -> u128 {
+    match ip {
+        IpAddr::V4(v4) => u128::from(v4.to_ipv6_mapped()),
+        IpAddr::V6(v6) => u128::from(v6),
+    }
+}
+
+fn mask(prefix: u32) -> u128 {
+    u128::MAX.checked_shl(128 - prefix).unwrap_or(0)
+}
\ No newline at end of file
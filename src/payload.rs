@@ -0,0 +1,207 @@
+use crate::alert::{Alert, AlertAttachment};
+use crate::config::DispatchConfig;
+use log::{info, warn};
+
+/// Caps the containers, processes, top processes and notes an alert carries to
+/// `max_rows` each before it reaches any channel, so every notifier renders
+/// the same top rows. The rows come ordered by the rule (worst first). The
+/// full alert is kept as a JSON or CSV attachment for the email, and a note
+/// tells readers of other channels what was left out.
+pub fn limit(mut alert: Alert, config: &DispatchConfig) -> Alert {
+    let max_rows = config.max_rows;
+    let omitted_containers = alert.containers.len().saturating_sub(max_rows);
+    let omitted_processes = alert.processes.len().saturating_sub(max_rows);
+    let omitted_top_processes = alert.top_processes.len().saturating_sub(max_rows);
+    let omitted_notes = alert.notes.len().saturating_sub(max_rows);
+    if max_rows == 0 || omitted_containers + omitted_processes + omitted_top_processes + omitted_notes == 0 {
+        return alert;
+    }
+    
+    let attachment = match config.attachment.as_str() {
+        "json" => Some(json_attachment(&alert)),
+        "csv" => Some(csv_attachment(&alert)),
+        "none" => None,
+        other => {
+            warn!("Unknown dispatch attachment format '{}', sending truncated {} alert without it", other, alert.rule);
+            None
+        }
+    };
+    
+    let omitted: Vec<String> = [
+        (omitted_containers, "containers"),
+        (omitted_processes, "processes"),
+        (omitted_top_processes, "top processes"),
+        (omitted_notes, "notes"),
+    ]
+    .iter()
+    .filter(|(count, _)| *count > 0)
+    .map(|(count, kind)| format!("{} more {}", count, kind))
+    .collect();
+    info!("Truncating {} alert to {} rows per list ({})", alert.rule, max_rows, omitted.join(", "));
+    
+    alert.containers.truncate(max_rows);
+    alert.processes.truncate(max_rows);
+    alert.top_processes.truncate(max_rows);
+    alert.notes.truncate(max_rows);
+    alert.notes.push(match &attachment {
+        Some(attachment) => format!("{} not shown; the full list is attached to the email as {}", omitted.join(", "), attachment.filename),
+        None => format!("{} not shown", omitted.join(", ")),
+    });
+    alert.attachment = attachment;
+    alert
+}
+
+fn json_attachment(alert: &Alert) -> AlertAttachment {
+    AlertAttachment {
+        filename: format!("{}-{}.json", alert.rule, alert.timestamp.format("%Y%m%d-%H%M%S")),
+        content_type: "application/json".to_string(),
+        body: serde_json::to_vec_pretty(alert).unwrap_or_default(),
+    }
+}
+
+/// One CSV table per non-empty list, separated by a blank line.
+fn csv_attachment(alert: &Alert) -> AlertAttachment {
+    let mut tables = Vec::new();
+    if !alert.containers.is_empty() {
+        let mut table = String::from("container,image,cpu_percent,memory_bytes,memory_limit_bytes,memory_percent\n");
+        for container in &alert.containers {
+            table.push_str(&format!(
+                "{},{},{:.2},{},{},{:.2}\n",
+                csv_field(&container.name), csv_field(&container.image), container.cpu_usage,
+                container.memory_usage, container.memory_limit, container.memory_percent
+            ));
+        }
+        tables.push(table);
+    }
+    if !alert.processes.is_empty() {
+        let mut table = String::from("process,running,restarted\n");
+        for process in &alert.processes {
+            table.push_str(&format!("{},{},{}\n", csv_field(&process.name), process.running, process.restarted));
+        }
+        tables.push(table);
+    }
+    if !alert.top_processes.is_empty() {
+        let mut table = String::from("pid,process,user,cpu_percent,memory_bytes,memory_percent\n");
+        for process in &alert.top_processes {
+            table.push_str(&format!(
+                "{},{},{},{:.2},{},{:.2}\n",
+                process.pid, csv_field(&process.name), csv_field(&process.user),
+                process.cpu_usage, process.memory, process.memory_percent
+            ));
+        }
+        tables.push(table);
+    }
+    if !alert.notes.is_empty() {
+        let mut table = String::from("note\n");
+        for note in &alert.notes {
+            table.push_str(&format!("{}\n", csv_field(note)));
+        }
+        tables.push(table);
+    }
+    
+    AlertAttachment {
+        filename: format!("{}-{}.csv", alert.rule, alert.timestamp.format("%Y%m%d-%H%M%S")),
+        content_type: "text/csv".to_string(),
+        body: tables.join("\n").into_bytes(),
+    }
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alert::Severity;
+    use crate::docker_monitor::ContainerStats;
+    use crate::process_watchdog::ProcessStatus;
+    use chrono::{TimeZone, Utc};
+    
+    fn config(max_rows: usize, attachment: &str) -> DispatchConfig {
+        DispatchConfig { max_rows, attachment: attachment.to_string(), ..DispatchConfig::default() }
+    }
+    
+    fn alert(containers: usize) -> Alert {
+        let mut alert = Alert::new("container_cpu", Severity::Warning, "busy".to_string(), "80%".to_string(), "5m".to_string())
+            .with_containers((0..containers).map(|i| ContainerStats::for_test(&format!("web-{}", i), "nginx")).collect());
+        alert.timestamp = Utc.with_ymd_and_hms(2026, 3, 4, 5, 6, 7).unwrap();
+        alert
+    }
+    
+    fn body(attachment: &AlertAttachment) -> String {
+        String::from_utf8(attachment.body.clone()).unwrap()
+    }
+    
+    #[test]
+    fn zero_max_rows_disables_the_limit() {
+        let limited = limit(alert(50), &config(0, "csv"));
+        assert_eq!(limited.containers.len(), 50);
+        assert!(limited.notes.is_empty());
+        assert!(limited.attachment.is_none());
+    }
+    
+    #[test]
+    fn rows_at_the_limit_are_kept_as_is() {
+        let limited = limit(alert(3), &config(3, "csv"));
+        assert_eq!(limited.containers.len(), 3);
+        assert!(limited.notes.is_empty());
+        assert!(limited.attachment.is_none());
+    }
+    
+    #[test]
+    fn one_row_over_the_limit_is_truncated_with_a_note() {
+        let limited = limit(alert(4), &config(3, "json"));
+        let names: Vec<&str> = limited.containers.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(names, ["web-0", "web-1", "web-2"]);
+        assert_eq!(
+            limited.notes,
+            ["1 more containers not shown; the full list is attached to the email as container_cpu-20260304-050607.json"]
+        );
+        
+        let attachment = limited.attachment.unwrap();
+        assert_eq!(attachment.filename, "container_cpu-20260304-050607.json");
+        assert_eq!(attachment.content_type, "application/json");
+        let full: Alert = serde_json::from_slice(&attachment.body).unwrap();
+        assert_eq!(full.containers.len(), 4);
+        
+        let limited = limit(alert(4), &config(3, "none"));
+        assert_eq!(limited.notes, ["1 more containers not shown"]);
+        assert!(limited.attachment.is_none());
+    }
+    
+    #[test]
+    fn csv_attachment_escapes_fields() {
+        let mut alert = alert(0).with_processes(vec![
+            ProcessStatus { name: "nginx".to_string(), running: true, restarted: false },
+            ProcessStatus { name: "worker, \"blue\"\nqueue".to_string(), running: false, restarted: true },
+        ]);
+        alert.containers.push(ContainerStats { cpu_usage: 12.345, ..ContainerStats::for_test("api,v2", "registry/api:\"1\"") });
+        alert.notes = vec!["first".to_string(), "second".to_string()];
+        
+        let limited = limit(alert, &config(1, "csv"));
+        let attachment = limited.attachment.unwrap();
+        assert_eq!(attachment.filename, "container_cpu-20260304-050607.csv");
+        assert_eq!(attachment.content_type, "text/csv");
+        assert_eq!(body(&attachment), concat!(
+            "container,image,cpu_percent,memory_bytes,memory_limit_bytes,memory_percent\n",
+            "\"api,v2\",\"registry/api:\"\"1\"\"\",12.35,0,0,0.00\n",
+            "\n",
+            "process,running,restarted\n",
+            "nginx,true,false\n",
+            "\"worker, \"\"blue\"\"\nqueue\",false,true\n",
+            "\n",
+            "note\n",
+            "first\n",
+            "second\n",
+        ));
+        assert_eq!(limited.notes, [
+            "first",
+            "1 more processes, 1 more notes not shown; the full list is attached to the email as container_cpu-20260304-050607.csv",
+        ]);
+    }
+}
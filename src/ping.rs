@@ -0,0 +1,205 @@
+use serde::Serialize;
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+use crate::config::{NetworkConfig, PingConfig, PingTargetConfig};
+use crate::network;
+use log::{debug, warn};
+
+/// Result of probing one target.
+#[derive(Debug, Clone, Serialize)]
+pub struct PingResult {
+    pub name: String,
+    pub host: String,
+    /// `icmp` or `tcp`.
+    pub method: String,
+    pub sent: u32,
+    pub received: u32,
+    pub loss_percent: f64,
+    /// Average round trip of the successful probes.
+    pub avg_latency_ms: Option<f64>,
+}
+
+impl PingResult {
+    pub fn describe(&self) -> String {
+        let latency = self.avg_latency_ms
+            .map(|ms| format!("{:.1} ms", ms))
+            .unwrap_or_else(|| "no replies".to_string());
+        format!(
+            "{} ({}, {}): {}, {:.0}% loss ({}/{} replies)",
+            self.name, self.host, self.method, latency, self.loss_percent, self.received, self.sent
+        )
+    }
+    
+    pub fn is_degraded(&self, config: &PingConfig) -> bool {
+        self.loss_percent > config.loss_threshold
+            || self.avg_latency_ms.is_some_and(|ms| ms > config.latency_threshold_ms)
+    }
+}
+
+/// Probes a target with the system `ping`, falling back to TCP connects on
+/// `port` when ICMP is unavailable (no ping binary or no CAP_NET_RAW).
+pub async fn probe(target: &PingTargetConfig, network: &NetworkConfig) -> PingResult {
+    if let Some(result) = probe_icmp(target, network).await {
+        return result;
+    }
+    
+    match target.port {
+        Some(port) => probe_tcp(target, port, network).await,
+        None => {
+            warn!("ICMP probe of {} failed and no TCP fallback port is configured", target.name);
+            summarize(target, "icmp", &[])
+        }
+    }
+}
+
+async fn probe_icmp(target: &PingTargetConfig, network: &NetworkConfig) -> Option<PingResult> {
+    // Resolve here so the address family follows ip_preference; ping picks
+    // IPv4 or IPv6 from the literal address
+    let address = match network::resolve(&target.host, 0, network).await {
+        Ok(addresses) => addresses[0].ip().to_string(),
+        Err(e) => {
+            debug!("Unable to resolve {} for ping: {}", target.host, e);
+            target.host.clone()
+        }
+    };
+    
+    let timeout_secs = target.timeout_ms.div_ceil(1000).max(1);
+    let output = Command::new("ping")
+        .arg("-n")
+        .arg("-c").arg(target.count.to_string())
+        .arg("-W").arg(timeout_secs.to_string())
+        .arg(&address)
+        .output()
+        .await;
+    
+    let output = match output {
+        Ok(output) => output,
+        Err(e) => {
+            debug!("Unable to run ping for {}: {}", target.name, e);
+            return None;
+        }
+    };
+    
+    let times = reply_times(&String::from_utf8_lossy(&output.stdout));
+    
+    // Exit code 1 means no replies, anything else without replies is a ping error
+    if times.is_empty() && output.status.code() != Some(1) {
+        debug!("ping for {} failed: {}", target.name, String::from_utf8_lossy(&output.stderr).trim());
+        return None;
+    }
+    
+    Some(summarize(target, "icmp", &times))
+}
+
+/// Round trip times in milliseconds from the `time=` field of each reply
+/// line. iputils prints `time=12.3 ms`, busybox `time=12.345 ms`.
+fn reply_times(stdout: &str) -> Vec<f64> {
+    stdout.lines()
+        .filter_map(|line| line.split("time=").nth(1))
+        .filter_map(|rest| rest.split_whitespace().next())
+        .filter_map(|value| value.trim_end_matches("ms").parse().ok())
+        .collect()
+}
+
+async fn probe_tcp(target: &PingTargetConfig, port: u16, network: &NetworkConfig) -> PingResult {
+    let timeout = Duration::from_millis(target.timeout_ms);
+    let mut times = Vec::new();
+    
+    for _ in 0..target.count {
+        let started = Instant::now();
+        match tokio::time::timeout(timeout, network::connect(&target.host, port, network)).await {
+            Ok(Ok(_)) => times.push(started.elapsed().as_secs_f64() * 1000.0),
+            Ok(Err(e)) => debug!("TCP probe of {}:{} failed: {}", target.host, port, e),
+            Err(_) => debug!("TCP probe of {}:{} timed out", target.host, port),
+        }
+    }
+    
+    summarize(target, "tcp", &times)
+}
+
+fn summarize(target: &PingTargetConfig, method: &str, times: &[f64]) -> PingResult {
+    let sent = target.count.max(1);
+    let received = (times.len() as u32).min(sent);
+    
+    PingResult {
+        name: target.name.clone(),
+        host: target.host.clone(),
+        method: method.to_string(),
+        sent,
+        received,
+        loss_percent: (sent - received) as f64 * 100.0 / sent as f64,
+        avg_latency_ms: if times.is_empty() {
+            None
+        } else {
+            Some(times.iter().sum::<f64>() / times.len() as f64)
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn target(count: u32) -> PingTargetConfig {
+        serde_json::from_value(serde_json::json!({
+            "name": "gateway",
+            "host": "192.0.2.1",
+            "count": count,
+        })).unwrap()
+    }
+    
+    #[test]
+    fn parses_iputils_output() {
+        let stdout = "PING 192.0.2.1 (192.0.2.1) 56(84) bytes of data.
+64 bytes from 192.0.2.1: icmp_seq=1 ttl=57 time=11.8 ms
+64 bytes from 192.0.2.1: icmp_seq=2 ttl=57 time=12.2 ms
+64 bytes from 192.0.2.1: icmp_seq=3 ttl=57 time=0.045ms
+
+--- 192.0.2.1 ping statistics ---
+3 packets transmitted, 3 received, 0% packet loss, time 2003ms
+rtt min/avg/max/mdev = 0.045/8.015/12.200/5.639 ms
+";
+        assert_eq!(reply_times(stdout), vec![11.8, 12.2, 0.045]);
+    }
+    
+    #[test]
+    fn parses_busybox_output() {
+        let stdout = "PING 192.0.2.1 (192.0.2.1): 56 data bytes
+64 bytes from 192.0.2.1: seq=0 ttl=57 time=12.345 ms
+64 bytes from 192.0.2.1: seq=1 ttl=57 time=13.655 ms
+
+--- 192.0.2.1 ping statistics ---
+2 packets transmitted, 2 packets received, 0% packet loss
+round-trip min/avg/max = 12.345/13.000/13.655 ms
+";
+        let result = summarize(&target(2), "icmp", &reply_times(stdout));
+        assert_eq!((result.sent, result.received), (2, 2));
+        assert_eq!(result.loss_percent, 0.0);
+        assert!((result.avg_latency_ms.unwrap() - 13.0).abs() < 1e-9);
+    }
+    
+    #[test]
+    fn total_loss_has_no_latency() {
+        let stdout = "PING 192.0.2.1 (192.0.2.1) 56(84) bytes of data.
+
+--- 192.0.2.1 ping statistics ---
+4 packets transmitted, 0 received, 100% packet loss, time 3062ms
+";
+        let result = summarize(&target(4), "icmp", &reply_times(stdout));
+        assert_eq!(result.received, 0);
+        assert_eq!(result.loss_percent, 100.0);
+        assert_eq!(result.avg_latency_ms, None);
+    }
+    
+    #[test]
+    fn lines_without_a_time_are_not_replies() {
+        let stdout = "From 192.0.2.254 icmp_seq=1 Destination Host Unreachable
+64 bytes from 192.0.2.1: icmp_seq=2 ttl=57
+64 bytes from 192.0.2.1: icmp_seq=3 ttl=57 time=9.5 ms
+";
+        let result = summarize(&target(3), "icmp", &reply_times(stdout));
+        assert_eq!(reply_times(stdout), vec![9.5]);
+        assert_eq!(result.received, 1);
+        assert!((result.loss_percent - 200.0 / 3.0).abs() < 1e-9);
+    }
+}
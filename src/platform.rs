@@ -0,0 +1,29 @@
+use std::path::Path;
+use std::sync::OnceLock;
+
+static PROCFS: OnceLock<bool> = OnceLock::new();
+
+/// Whether the Linux `/proc` interface is readable. It is missing on macOS and
+/// the BSDs, and in containers started without it mounted. Collectors that
+/// only read procfs return nothing without it instead of warning every cycle;
+/// host stats fall back to what sysinfo reports for the platform.
+pub fn has_procfs() -> bool {
+    *PROCFS.get_or_init(|| cfg!(target_os = "linux") && Path::new("/proc/self/stat").exists())
+}
+
+/// The target the binary was built for, e.g. `aarch64-linux-musl`.
+pub fn target() -> String {
+    let env = if cfg!(target_env = "musl") {
+        "musl"
+    } else if cfg!(target_env = "gnu") {
+        "gnu"
+    } else {
+        ""
+    };
+    let mut target = format!("{}-{}", std::env::consts::ARCH, std::env::consts::OS);
+    if !env.is_empty() {
+        target.push('-');
+        target.push_str(env);
+    }
+    target
+}
\ No newline at end of file
@@ -0,0 +1,112 @@
+use serde::{Deserialize, Serialize};
+use sysinfo::{System, SystemExt, ProcessExt, ProcessRefreshKind, Pid, PidExt};
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::config::{Config, ProcessCheckConfig};
+use log::{info, error, warn};
+use std::fs;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessStatus {
+    pub name: String,
+    pub running: bool,
+    pub restarted: bool,
+}
+
+/// Init-system agnostic "process must be running" checks.
+///
+/// Processes are matched by executable name, command line substring or pidfile,
+/// so the watchdog works the same under openrc, runit or inside containers.
+pub struct ProcessWatchdog {
+    system: System,
+    checks: Vec<ProcessCheckConfig>,
+}
+
+impl ProcessWatchdog {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            system: System::new(),
+            checks: config.watchdog.processes.clone(),
+        }
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        !self.checks.is_empty()
+    }
+    
+    fn is_running(&self, check: &ProcessCheckConfig) -> bool {
+        if let Some(pidfile) = &check.pidfile {
+            let pid = fs::read_to_string(pidfile)
+                .ok()
+                .and_then(|content| content.trim().parse::<u32>().ok());
+            return match pid {
+                Some(pid) => self.system.process(Pid::from_u32(pid)).is_some(),
+                None => false,
+            };
+        }
+        
+        self.system.processes().values().any(|process| {
+            let name_matches = check.process_name.as_deref()
+                .is_none_or(|name| process.name() == name);
+            let cmdline_matches = check.cmdline.as_deref()
+                .is_none_or(|pattern| process.cmd().join(" ").contains(pattern));
+            name_matches && cmdline_matches
+        })
+    }
+    
+    async fn restart(check: &ProcessCheckConfig, command: &str) -> bool {
+        info!("Restarting process {} with: {}", check.name, command);
+        
+        let restarted = match tokio::process::Command::new("sh").arg("-c").arg(command).status().await {
+            Ok(status) if status.success() => {
+                info!("Restart command for {} completed", check.name);
+                true
+            }
+            Ok(status) => {
+                error!("Restart command for {} exited with {}", check.name, status);
+                false
+            }
+            Err(e) => {
+                error!("Failed to run restart command for {}: {}", check.name, e);
+                false
+            }
+        };
+        audit::record(
+            AuditEntry::new(AuditAction::Remediation, "monitor", &check.name, format!("process down, ran restart command: {}", command))
+                .with_success(restarted)
+        );
+        restarted
+    }
+    
+    pub async fn check_processes(&mut self) -> Vec<ProcessStatus> {
+        // Names, command lines and PIDs are all the checks need, skip CPU and disk usage
+        self.system.refresh_processes_specifics(ProcessRefreshKind::new());
+        let mut statuses = Vec::new();
+        
+        for check in &self.checks {
+            if check.process_name.is_none() && check.cmdline.is_none() && check.pidfile.is_none() {
+                warn!("Process check {} has no process_name, cmdline or pidfile. Skipping.", check.name);
+                continue;
+            }
+            
+            let running = self.is_running(check);
+            let mut restarted = false;
+            
+            if running {
+                info!("Process {} is running", check.name);
+            } else {
+                warn!("Process {} is not running", check.name);
+                if let Some(command) = &check.restart_command {
+                    restarted = Self::restart(check, command).await;
+                }
+            }
+            
+            statuses.push(ProcessStatus {
+                name: check.name.clone(),
+                running,
+                restarted,
+            });
+        }
+        
+        statuses
+    }
+}
\ No newline at end of file
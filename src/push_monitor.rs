@@ -0,0 +1,104 @@
+use glob::Pattern;
+use reqwest::{Client, RequestBuilder};
+use std::time::Duration;
+use crate::config::{Config, PushMonitorConfig};
+use crate::network;
+use crate::statuspage::ComponentCheck;
+use anyhow::{Result, anyhow};
+use log::{debug, error, info, warn};
+
+/// Pushes check results to Uptime Kuma and Healthchecks.io after every cycle,
+/// so they show up next to the other monitors of those dashboards.
+pub struct PushMonitors {
+    client: Client,
+    monitors: Vec<(PushMonitorConfig, Pattern)>,
+}
+
+impl PushMonitors {
+    pub fn new(config: &Config) -> Self {
+        let monitors = config.push_monitors.iter()
+            .filter_map(|monitor| {
+                if monitor.kind != "uptime_kuma" && monitor.kind != "healthchecks" {
+                    warn!("Unknown push monitor kind '{}' for {}, expected uptime_kuma or healthchecks", monitor.kind, monitor.name);
+                    return None;
+                }
+                match Pattern::new(&monitor.checks) {
+                    Ok(pattern) => Some((monitor.clone(), pattern)),
+                    Err(e) => {
+                        warn!("Invalid checks pattern '{}' for push monitor {}: {}", monitor.checks, monitor.name, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        
+        Self {
+            client: network::http_client(&config.network),
+            monitors,
+        }
+    }
+    
+    /// Reports the checks of a cycle to every push monitor. `latency` is how
+    /// long the cycle took.
+    pub async fn push(&self, checks: &[ComponentCheck], latency: Duration) {
+        let pushes = self.monitors.iter().filter_map(|(monitor, pattern)| {
+            let matching: Vec<&ComponentCheck> = checks.iter().filter(|check| pattern.matches(&check.name)).collect();
+            if matching.is_empty() {
+                debug!("No checks match push monitor {} ({})", monitor.name, monitor.checks);
+                return None;
+            }
+            Some(async move {
+                let healthy = matching.iter().all(|check| check.healthy);
+                match self.send(monitor, healthy, &message(&matching), latency).await {
+                    Ok(_) => info!("Pushed {} to {} monitor {}", if healthy { "up" } else { "down" }, monitor.kind, monitor.name),
+                    Err(e) => error!("Failed to push to {} monitor {}: {}", monitor.kind, monitor.name, e),
+                }
+            })
+        });
+        futures_util::future::join_all(pushes).await;
+    }
+    
+    async fn send(&self, monitor: &PushMonitorConfig, healthy: bool, message: &str, latency: Duration) -> Result<()> {
+        let request = match monitor.kind.as_str() {
+            "uptime_kuma" => self.uptime_kuma(monitor, healthy, message, latency),
+            _ => self.healthchecks(monitor, healthy, message, latency),
+        };
+        let response = request.timeout(Duration::from_millis(monitor.timeout_ms)).send().await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("{} returned {}", monitor.kind, response.status()));
+        }
+        Ok(())
+    }
+    
+    /// `GET <push url>?status=up|down&msg=...&ping=<ms>`. A push URL copied
+    /// with Uptime Kuma's example query keeps only the path.
+    fn uptime_kuma(&self, monitor: &PushMonitorConfig, healthy: bool, message: &str, latency: Duration) -> RequestBuilder {
+        let url = monitor.url.split('?').next().unwrap_or_default();
+        self.client.get(url).query(&[
+            ("status", if healthy { "up" } else { "down" }),
+            ("msg", message),
+            ("ping", &latency.as_millis().to_string()),
+        ])
+    }
+    
+    /// `POST <ping url>` on success, `<ping url>/fail` otherwise, with the
+    /// check details as the body Healthchecks.io shows in its log.
+    fn healthchecks(&self, monitor: &PushMonitorConfig, healthy: bool, message: &str, latency: Duration) -> RequestBuilder {
+        let url = monitor.url.trim_end_matches('/');
+        let url = if healthy { url.to_string() } else { format!("{}/fail", url) };
+        self.client.post(url).body(format!("{}\ncycle took {} ms", message, latency.as_millis()))
+    }
+}
+
+/// The unhealthy checks, or a count of the healthy ones when all are fine.
+fn message(checks: &[&ComponentCheck]) -> String {
+    let unhealthy: Vec<String> = checks.iter()
+        .filter(|check| !check.healthy)
+        .map(|check| format!("{}: {}", check.name, check.detail))
+        .collect();
+    match (unhealthy.is_empty(), checks) {
+        (true, [check]) => format!("{}: {}", check.name, check.detail),
+        (true, _) => format!("{} checks healthy", checks.len()),
+        (false, _) => unhealthy.join("; "),
+    }
+}
\ No newline at end of file
@@ -0,0 +1,132 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use crate::config::parse_duration;
+use crate::history::History;
+use crate::trend::SeriesPoint;
+use anyhow::{Result, anyhow};
+
+/// Metrics the query API can return. Host metrics and container CPU are
+/// percentages, container memory is in bytes.
+pub const QUERY_METRICS: [&str; 5] = ["cpu", "memory", "disk", "container_cpu", "container_memory"];
+
+/// History is stored per hour, so no step can be finer than this.
+const RESOLUTION: i64 = 3600;
+
+/// Parameters of `/api/v1/query`. `from` and `to` take RFC 3339 timestamps or
+/// a duration back from now (`24h`); `step` is a duration rounded up to whole hours.
+#[derive(Debug, Clone, Deserialize)]
+pub struct QueryRequest {
+    pub metric: String,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub from: Option<String>,
+    #[serde(default)]
+    pub to: Option<String>,
+    #[serde(default)]
+    pub step: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct QueryResponse {
+    pub metric: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub unit: &'static str,
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    /// Step actually used, which may be coarser than the one requested.
+    pub step_seconds: i64,
+    pub points: Vec<SeriesPoint>,
+}
+
+/// Downsamples the recorded history of one metric into `step` wide points.
+/// Each point is the sample-weighted average of the hours it covers and is
+/// stamped with the start of its step; steps without samples are omitted.
+pub fn run(history: &History, request: &QueryRequest, now: DateTime<Utc>) -> Result<QueryResponse> {
+    let metric = request.metric.as_str();
+    if !QUERY_METRICS.contains(&metric) {
+        return Err(anyhow!("Unknown metric '{}'. Available: {}", metric, QUERY_METRICS.join(", ")));
+    }
+    let container = metric.starts_with("container_");
+    let name = match (&request.name, container) {
+        (Some(name), true) => Some(name.clone()),
+        (None, true) => return Err(anyhow!("Metric '{}' requires a container name", metric)),
+        (_, false) => None,
+    };
+    
+    let from = parse_time(request.from.as_deref(), now)?.unwrap_or(now - Duration::hours(24));
+    let to = parse_time(request.to.as_deref(), now)?.unwrap_or(now);
+    if from > to {
+        return Err(anyhow!("'from' must be before 'to'"));
+    }
+    let requested = match &request.step {
+        Some(step) => parse_duration(step)?.as_secs() as i64,
+        None => RESOLUTION,
+    };
+    let step = (requested.max(1) + RESOLUTION - 1) / RESOLUTION * RESOLUTION;
+    let start = from.duration_trunc(Duration::hours(1)).unwrap_or(from);
+    
+    // (hour, samples, value) of every recorded hour in range
+    let hours: Vec<(DateTime<Utc>, u64, f64)> = match (metric, &name) {
+        (_, Some(name)) => history.containers_since(start).into_iter()
+            .filter(|bucket| bucket.hour <= to)
+            .filter_map(|bucket| bucket.containers.get(name).map(|usage| (bucket.hour, usage)))
+            .map(|(hour, usage)| {
+                let value = if metric == "container_cpu" { usage.cpu_avg() } else { usage.memory_avg() };
+                (hour, usage.samples, value)
+            })
+            .collect(),
+        _ => history.since(start).into_iter()
+            .filter(|bucket| bucket.hour <= to)
+            .map(|bucket| {
+                let value = match metric {
+                    "cpu" => bucket.cpu_avg(),
+                    "memory" => bucket.memory_avg(),
+                    _ => bucket.disk_max,
+                };
+                (bucket.hour, bucket.samples, value)
+            })
+            .collect(),
+    };
+    
+    let mut points: Vec<SeriesPoint> = Vec::new();
+    let mut weights: Vec<u64> = Vec::new();
+    for (hour, samples, value) in hours {
+        let at = start + Duration::seconds((hour - start).num_seconds() / step * step);
+        let samples = samples.max(1);
+        match points.last_mut() {
+            Some(point) if point.at == at => {
+                let weight = weights.last_mut().expect("one weight per point");
+                point.value = (point.value * *weight as f64 + value * samples as f64) / (*weight + samples) as f64;
+                *weight += samples;
+            }
+            _ => {
+                points.push(SeriesPoint { at, value });
+                weights.push(samples);
+            }
+        }
+    }
+    
+    Ok(QueryResponse {
+        metric: metric.to_string(),
+        name,
+        unit: if metric == "container_memory" { "bytes" } else { "percent" },
+        from,
+        to,
+        step_seconds: step,
+        points,
+    })
+}
+
+fn parse_time(value: Option<&str>, now: DateTime<Utc>) -> Result<Option<DateTime<Utc>>> {
+    let Some(value) = value.map(str::trim).filter(|value| !value.is_empty()) else {
+        return Ok(None);
+    };
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Ok(Some(time.with_timezone(&Utc)));
+    }
+    let ago = parse_duration(value)
+        .map_err(|_| anyhow!("Invalid time '{}': expected RFC 3339 or a duration like 24h", value))?;
+    Ok(Some(now - Duration::from_std(ago)?))
+}
\ No newline at end of file
@@ -0,0 +1,101 @@
+use std::fs;
+use crate::alert::Alert;
+use crate::apprise::AppriseNotifier;
+use crate::chat::ChatNotifier;
+use crate::config::Config;
+use crate::email_notifier::EmailNotifier;
+use crate::payload;
+use crate::redact::Redactor;
+use crate::webhook::WebhookNotifier;
+use crate::webpush::WebPushNotifier;
+use anyhow::{Result, anyhow};
+
+/// Channels the `render` subcommand can show.
+pub const CHANNELS: [&str; 5] = ["email", "webhook", "chat", "apprise", "webpush"];
+
+/// Reads a sample alert, in the JSON form webhooks receive by default.
+pub fn load_alert(path: &str) -> Result<Alert> {
+    let content = fs::read_to_string(path).map_err(|e| anyhow!("failed to read {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow!("invalid alert in {}: {}", path, e))
+}
+
+/// Renders an alert for one channel exactly as the dispatcher would send it,
+/// redaction and truncation included, without sending anything. Webhook, chat and apprise
+/// render every configured channel whatever rules it is subscribed to, or
+/// only the one called `name`. `hostname` replaces this host's name so the
+/// output is the same on every machine.
+pub fn render(config: &Config, alert: Alert, channel: &str, name: Option<&str>, hostname: Option<&str>) -> Result<String> {
+    let rule = config.alert_rules.get(&alert.rule);
+    let alert = payload::limit(Redactor::new(&config.redaction).apply(alert.with_rule_info(rule)), &config.dispatch);
+    
+    let outputs: Vec<(String, String)> = match channel {
+        "email" => {
+            let mut notifier = EmailNotifier::new(config);
+            if let Some(hostname) = hostname {
+                notifier.set_hostname(hostname);
+            }
+            let (subject, message) = notifier.render_rule_alert(&alert)
+                .ok_or_else(|| anyhow!("no email template for rule {}", alert.rule))?;
+            let mut output = format!("Subject: {}\n{}", subject, message);
+            if let Some(attachment) = &alert.attachment {
+                output.push_str(&format!(
+                    "\n--- attachment {} ({}) ---\n{}",
+                    attachment.filename, attachment.content_type, String::from_utf8_lossy(&attachment.body)
+                ));
+            }
+            return Ok(output);
+        }
+        "webpush" => {
+            let mut notifier = WebPushNotifier::new(config);
+            if let Some(hostname) = hostname {
+                notifier.set_hostname(hostname);
+            }
+            return Ok(notifier.payload(&alert));
+        }
+        "webhook" => {
+            let mut notifier = WebhookNotifier::new(config);
+            if let Some(hostname) = hostname {
+                notifier.set_hostname(hostname);
+            }
+            notifier.preview(&alert).into_iter()
+                .map(|(channel, body)| body.map(|body| (channel.clone(), body)).map_err(|e| anyhow!("{}: {}", channel, e)))
+                .collect::<Result<_>>()?
+        }
+        "chat" => {
+            let mut notifier = ChatNotifier::new(config);
+            if let Some(hostname) = hostname {
+                notifier.set_hostname(hostname);
+            }
+            pretty(notifier.preview(&alert))
+        }
+        "apprise" => {
+            let mut notifier = AppriseNotifier::new(config);
+            if let Some(hostname) = hostname {
+                notifier.set_hostname(hostname);
+            }
+            pretty(notifier.preview(&alert))
+        }
+        other => return Err(anyhow!("unknown channel '{}', expected one of {}", other, CHANNELS.join(", "))),
+    };
+    
+    let outputs: Vec<(String, String)> = outputs.into_iter()
+        .filter(|(channel, _)| name.is_none_or(|name| channel.split_once(':').is_some_and(|(_, channel)| channel == name)))
+        .collect();
+    if outputs.is_empty() {
+        return Err(match name {
+            Some(name) => anyhow!("no {} channel named {}", channel, name),
+            None => anyhow!("no {} channels configured", channel),
+        });
+    }
+    
+    Ok(outputs.iter()
+        .map(|(channel, body)| format!("--- {} ---\n{}", channel, body))
+        .collect::<Vec<_>>()
+        .join("\n"))
+}
+
+fn pretty(payloads: Vec<(String, serde_json::Value)>) -> Vec<(String, String)> {
+    payloads.into_iter()
+        .map(|(channel, payload)| (channel, serde_json::to_string_pretty(&payload).unwrap_or_default()))
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,128 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveTime, TimeZone, Utc, Weekday};
+use crate::chart::{self, Series};
+use crate::config::ReportConfig;
+use crate::history::{History, HistoryBucket};
+use anyhow::{Result, anyhow};
+
+const CPU_COLOR: [u8; 3] = [31, 119, 180];
+const MEMORY_COLOR: [u8; 3] = [44, 160, 44];
+const DISK_COLOR: [u8; 3] = [255, 127, 14];
+
+/// PNG attached to the report and referenced from the HTML as `cid:<cid>`.
+pub struct InlineImage {
+    pub cid: String,
+    pub png: Vec<u8>,
+}
+
+pub struct Report {
+    pub title: String,
+    pub html: String,
+    pub images: Vec<InlineImage>,
+}
+
+/// Length of the period a report covers.
+pub fn period(config: &ReportConfig) -> Duration {
+    match config.schedule.as_str() {
+        "daily" => Duration::days(1),
+        _ => Duration::weeks(1),
+    }
+}
+
+/// Most recent scheduled time at or before `now`, in the host's local time.
+pub fn last_occurrence(config: &ReportConfig, now: DateTime<Utc>) -> Result<DateTime<Utc>> {
+    let time = NaiveTime::parse_from_str(&config.time, "%H:%M")
+        .map_err(|e| anyhow!("invalid report time '{}': {}", config.time, e))?;
+    let weekday = match config.schedule.as_str() {
+        "daily" => None,
+        "weekly" => Some(config.weekday.parse::<Weekday>()
+            .map_err(|_| anyhow!("invalid report weekday '{}'", config.weekday))?),
+        other => return Err(anyhow!("invalid report schedule '{}' (expected daily or weekly)", other)),
+    };
+    
+    let now = now.with_timezone(&Local);
+    let mut date = now.date_naive();
+    for _ in 0..8 {
+        let candidate = Local.from_local_datetime(&date.and_time(time)).earliest();
+        let weekday_matches = weekday.is_none_or(|weekday| date.weekday() == weekday);
+        if let Some(candidate) = candidate.filter(|candidate| weekday_matches && *candidate <= now) {
+            return Ok(candidate.with_timezone(&Utc));
+        }
+        date = date.pred_opt().ok_or_else(|| anyhow!("report date out of range"))?;
+    }
+    
+    Err(anyhow!("no scheduled time found for report {}", config.name))
+}
+
+/// True when a scheduled time passed since the report was last sent.
+pub fn is_due(config: &ReportConfig, last_sent: DateTime<Utc>, now: DateTime<Utc>) -> Result<bool> {
+    Ok(last_sent < last_occurrence(config, now)?)
+}
+
+pub fn build(config: &ReportConfig, history: &History, now: DateTime<Utc>) -> Result<Report> {
+    let since = now - period(config);
+    let buckets = history.since(since);
+    let title = format!("{} health report", capitalize(&config.schedule));
+    
+    if buckets.is_empty() {
+        return Ok(Report {
+            title,
+            html: format!("<p>No history recorded since {}.</p>", since.format("%Y-%m-%d %H:%M")),
+            images: Vec::new(),
+        });
+    }
+    
+    let cpu: Vec<f64> = buckets.iter().map(|bucket| bucket.cpu_avg()).collect();
+    let memory: Vec<f64> = buckets.iter().map(|bucket| bucket.memory_avg()).collect();
+    let disk: Vec<f64> = buckets.iter().map(|bucket| bucket.disk_max).collect();
+    let png = chart::percent_chart(&[
+        Series { color: CPU_COLOR, values: &cpu },
+        Series { color: MEMORY_COLOR, values: &memory },
+        Series { color: DISK_COLOR, values: &disk },
+    ])?;
+    
+    let summary_row = |label: &str, color: [u8; 3], average: f64, peak: f64| {
+        format!(
+            "<tr><td><span style='color: rgb({}, {}, {});'>■</span> {}</td><td>{:.1}%</td><td>{:.1}%</td></tr>",
+            color[0], color[1], color[2], label, average, peak
+        )
+    };
+    let samples: u64 = buckets.iter().map(|bucket| bucket.samples).sum();
+    let weighted = |value: fn(&HistoryBucket) -> f64| {
+        buckets.iter().map(|bucket| value(bucket) * bucket.samples as f64).sum::<f64>() / samples.max(1) as f64
+    };
+    let peak = |value: fn(&HistoryBucket) -> f64| buckets.iter().map(|bucket| value(bucket)).fold(0.0, f64::max);
+    
+    let html = format!(
+        r#"
+                <p>{} to {} ({} checks)</p>
+                <img src="cid:trend" alt="CPU, memory and disk trend" width="640" height="200">
+                <p style='color: #666;'>Hourly averages for CPU and memory, hourly peak for disk. Grid lines at 25%, 50% and 75%.</p>
+                <table border="1" style="border-collapse: collapse;">
+                    <tr><th>Metric</th><th>Average</th><th>Peak</th></tr>
+                    {}
+                    {}
+                    {}
+                </table>
+        "#,
+        buckets[0].hour.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+        now.with_timezone(&Local).format("%Y-%m-%d %H:%M"),
+        samples,
+        summary_row("CPU", CPU_COLOR, weighted(HistoryBucket::cpu_avg), peak(|bucket| bucket.cpu_max)),
+        summary_row("Memory", MEMORY_COLOR, weighted(HistoryBucket::memory_avg), peak(|bucket| bucket.memory_max)),
+        summary_row("Disk", DISK_COLOR, disk.iter().sum::<f64>() / disk.len() as f64, peak(|bucket| bucket.disk_max)),
+    );
+    
+    Ok(Report {
+        title,
+        html,
+        images: vec![InlineImage { cid: "trend".to_string(), png }],
+    })
+}
+
+fn capitalize(value: &str) -> String {
+    let mut chars = value.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
\ No newline at end of file
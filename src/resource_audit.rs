@@ -0,0 +1,68 @@
+use crate::config::{parse_duration, ResourceAuditConfig};
+use crate::digest::DigestSection;
+use crate::docker_monitor::{AuditedResource, ResourceAudit};
+use crate::units;
+use log::warn;
+
+pub fn exclude_patterns(config: &ResourceAuditConfig) -> Vec<glob::Pattern> {
+    config.exclude.iter()
+        .filter_map(|pattern| match glob::Pattern::new(pattern) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                warn!("Ignoring invalid audit exclude pattern '{}': {}", pattern, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// How long a container must have been stopped before it is pruned. `None`
+/// for an invalid `prune_min_age`, which `Config::validate` refuses anyway.
+pub fn prune_min_age(config: &ResourceAuditConfig) -> Option<chrono::Duration> {
+    parse_duration(&config.prune_min_age).ok().and_then(|age| chrono::Duration::from_std(age).ok())
+}
+
+fn format_resource_list(resources: &[AuditedResource]) -> String {
+    resources.iter()
+        .map(|resource| format!("<li>{} ({})</li>", resource.name, units::bytes(resource.size as f64)))
+        .collect()
+}
+
+/// Builds the orphaned resources digest section, including anything pruned this run.
+pub fn audit_section(audit: &ResourceAudit, pruned: &[String]) -> DigestSection {
+    let mut html = format!(
+        "<p><strong>Total reclaimable space:</strong> {}</p>",
+        units::bytes(audit.reclaimable_bytes() as f64)
+    );
+    
+    if audit.is_empty() {
+        html.push_str("<p>No unused networks, orphaned volumes or exited containers found.</p>");
+    }
+    if !audit.unused_networks.is_empty() {
+        let networks: String = audit.unused_networks.iter()
+            .map(|network| format!("<li>{}</li>", network))
+            .collect();
+        html.push_str(&format!("<p><strong>Networks without containers:</strong></p><ul>{}</ul>", networks));
+    }
+    if !audit.orphaned_volumes.is_empty() {
+        html.push_str(&format!(
+            "<p><strong>Orphaned volumes:</strong></p><ul>{}</ul>",
+            format_resource_list(&audit.orphaned_volumes)
+        ));
+    }
+    if !audit.exited_containers.is_empty() {
+        html.push_str(&format!(
+            "<p><strong>Exited containers:</strong></p><ul>{}</ul>",
+            format_resource_list(&audit.exited_containers)
+        ));
+    }
+    if !pruned.is_empty() {
+        let removed: String = pruned.iter().map(|name| format!("<li>{}</li>", name)).collect();
+        html.push_str(&format!("<p><strong>Automatically pruned:</strong></p><ul>{}</ul>", removed));
+    }
+    
+    DigestSection {
+        title: "🧹 Unused Docker Resources".to_string(),
+        html,
+    }
+}
\ No newline at end of file
@@ -0,0 +1,161 @@
+use serde::Serialize;
+use chrono::{DateTime, Utc};
+use reqwest::Client;
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::config::{Config, ScalingHookConfig, parse_duration};
+use crate::network;
+use crate::state::MonitorState;
+use log::{info, error, warn};
+
+#[derive(Debug, Clone, Serialize)]
+struct ScalingEvent<'a> {
+    hook: &'a str,
+    metric: &'a str,
+    direction: &'a str,
+    value: f64,
+    threshold: f64,
+    timestamp: DateTime<Utc>,
+}
+
+/// Runs user-configured scale-up/scale-down actions once a utilization condition
+/// has held for `sustained_checks` consecutive cycles, honouring per-hook cooldowns.
+pub struct ScalingHooks {
+    hooks: Vec<ScalingHook>,
+    client: Client,
+}
+
+struct ScalingHook {
+    config: ScalingHookConfig,
+    cooldown: chrono::Duration,
+}
+
+impl ScalingHooks {
+    /// Hooks with an invalid cooldown are skipped with a warning rather than
+    /// run without one.
+    pub fn new(config: &Config) -> Self {
+        let hooks = config.scaling.hooks.iter()
+            .filter_map(|hook| {
+                let cooldown = parse_duration(&hook.cooldown)
+                    .and_then(|cooldown| Ok(chrono::Duration::from_std(cooldown)?));
+                match cooldown {
+                    Ok(cooldown) => Some(ScalingHook { config: hook.clone(), cooldown }),
+                    Err(e) => {
+                        warn!("Scaling hook {} has invalid cooldown: {}. Skipping.", hook.name, e);
+                        None
+                    }
+                }
+            })
+            .collect();
+        Self {
+            client: network::http_client(&config.network),
+            hooks,
+        }
+    }
+    
+    pub async fn evaluate(&self, state: &mut MonitorState, cpu_percent: f64, memory_percent: f64) {
+        for ScalingHook { config: hook, cooldown } in &self.hooks {
+            let value = match hook.metric.as_str() {
+                "cpu" => cpu_percent,
+                "memory" => memory_percent,
+                other => {
+                    warn!("Scaling hook {} has unknown metric '{}'. Skipping.", hook.name, other);
+                    continue;
+                }
+            };
+            
+            let breached = match hook.direction.as_str() {
+                "above" => value > hook.threshold,
+                "below" => value < hook.threshold,
+                other => {
+                    warn!("Scaling hook {} has unknown direction '{}'. Skipping.", hook.name, other);
+                    continue;
+                }
+            };
+            
+            let streak = state.scaling_streaks.entry(hook.name.clone()).or_insert(0);
+            if !breached {
+                *streak = 0;
+                continue;
+            }
+            *streak += 1;
+            
+            if *streak < hook.sustained_checks.max(1) {
+                info!("Scaling hook {}: {} {} {:.2} for {} checks", hook.name, hook.metric, hook.direction, hook.threshold, streak);
+                continue;
+            }
+            
+            if let Some(last_triggered) = state.scaling_last_triggered.get(&hook.name) {
+                if Utc::now() - *last_triggered < *cooldown {
+                    info!("Scaling hook {} is in cooldown", hook.name);
+                    continue;
+                }
+            }
+            
+            let event = ScalingEvent {
+                hook: &hook.name,
+                metric: &hook.metric,
+                direction: &hook.direction,
+                value,
+                threshold: hook.threshold,
+                timestamp: Utc::now(),
+            };
+            
+            if self.trigger(hook, &event).await {
+                state.scaling_last_triggered.insert(hook.name.clone(), Utc::now());
+                state.scaling_streaks.insert(hook.name.clone(), 0);
+            }
+        }
+    }
+    
+    async fn trigger(&self, hook: &ScalingHookConfig, event: &ScalingEvent<'_>) -> bool {
+        info!("Triggering scaling hook {} ({} {:.2}% {} {:.2}%)",
+              hook.name, hook.metric, event.value, hook.direction, hook.threshold);
+        let mut success = true;
+        
+        if let Some(url) = &hook.webhook_url {
+            match self.client.post(url).json(event).send().await.and_then(|r| r.error_for_status()) {
+                Ok(_) => info!("Scaling webhook for {} delivered", hook.name),
+                Err(e) => {
+                    error!("Scaling webhook for {} failed: {}", hook.name, e);
+                    success = false;
+                }
+            }
+        }
+        
+        if let Some(command) = &hook.command {
+            let status = tokio::process::Command::new("sh")
+                .arg("-c")
+                .arg(command)
+                .env("SCALE_HOOK", &hook.name)
+                .env("SCALE_METRIC", &hook.metric)
+                .env("SCALE_DIRECTION", &hook.direction)
+                .env("SCALE_VALUE", format!("{:.2}", event.value))
+                .env("SCALE_THRESHOLD", format!("{:.2}", hook.threshold))
+                .status()
+                .await;
+            
+            match status {
+                Ok(status) if status.success() => info!("Scaling command for {} completed", hook.name),
+                Ok(status) => {
+                    error!("Scaling command for {} exited with {}", hook.name, status);
+                    success = false;
+                }
+                Err(e) => {
+                    error!("Failed to run scaling command for {}: {}", hook.name, e);
+                    success = false;
+                }
+            }
+        }
+        
+        audit::record(
+            AuditEntry::new(
+                AuditAction::Remediation,
+                "monitor",
+                &hook.name,
+                format!("scaling hook triggered, {} {:.2}% {} {:.2}%", hook.metric, event.value, hook.direction, hook.threshold),
+            )
+            .with_success(success)
+        );
+        success
+    }
+}
\ No newline at end of file
@@ -0,0 +1,188 @@
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use rand::Rng;
+use tokio::time::{Instant, Interval, MissedTickBehavior};
+use crate::config::Config;
+use log::{info, warn};
+
+/// What a [`Schedule`] tick asks for.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tick {
+    /// Every check, on the regular interval.
+    Full,
+    /// Only the checks of firing rules, on `incident_check_interval`.
+    Incident,
+}
+
+/// Continuous-mode schedule with optional random splay and wall-clock alignment.
+///
+/// The splay offset is chosen once per process, so a fleet started at the same
+/// moment spreads its checks across the splay window while each instance keeps
+/// a stable cadence. Checks are ticks of a [`tokio::time::Interval`] anchored
+/// at the first check, so a slow cycle does not push later samples back.
+pub struct Schedule {
+    interval: Duration,
+    incident_interval: Option<Duration>,
+    align: bool,
+    offset: Duration,
+    missed_tick: MissedTickBehavior,
+    ticker: Option<Interval>,
+    /// Runs between the regular ticks while an incident is active.
+    incident_ticker: Option<Interval>,
+}
+
+impl Schedule {
+    pub fn new(config: &Config) -> Self {
+        let splay = config.monitoring.splay;
+        let offset = if splay > 0 {
+            Duration::from_millis(rand::thread_rng().gen_range(0..=splay * 1000))
+        } else {
+            Duration::ZERO
+        };
+        
+        let missed_tick = match config.monitoring.missed_tick_behavior.as_str() {
+            "skip" => MissedTickBehavior::Skip,
+            "delay" => MissedTickBehavior::Delay,
+            "burst" => MissedTickBehavior::Burst,
+            other => {
+                warn!("Unknown missed_tick_behavior '{}' (expected skip, delay or burst), using skip", other);
+                MissedTickBehavior::Skip
+            }
+        };
+        
+        Self {
+            interval: Duration::from_secs(config.monitoring.check_interval.max(1)),
+            incident_interval: Some(config.monitoring.incident_check_interval)
+                .filter(|seconds| *seconds > 0)
+                .map(Duration::from_secs),
+            align: config.monitoring.align_to_interval,
+            offset,
+            missed_tick,
+            ticker: None,
+            incident_ticker: None,
+        }
+    }
+    
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+    
+    pub fn offset(&self) -> Duration {
+        self.offset
+    }
+    
+    /// Time until the next wall-clock multiple of the interval, shifted by the splay offset.
+    fn until_next_boundary(&self) -> Duration {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default();
+        let interval_ms = self.interval.as_millis();
+        let phase = (now.as_millis() + interval_ms - self.offset.as_millis() % interval_ms) % interval_ms;
+        Duration::from_millis((interval_ms - phase) as u64)
+    }
+    
+    /// Delay before the first check.
+    pub fn initial_delay(&self) -> Duration {
+        if self.align {
+            self.until_next_boundary()
+        } else {
+            self.offset
+        }
+    }
+    
+    /// Anchors the cadence at the check that is about to run.
+    pub fn start(&mut self) {
+        self.ticker = Some(self.regular());
+    }
+    
+    /// Waits for the next check. The regular cadence is kept at all times;
+    /// while `incident` is set and `incident_check_interval` is configured,
+    /// [`Tick::Incident`] fires in between, anchored where the incident
+    /// started. Cancel safe.
+    pub async fn tick(&mut self, incident: bool) -> Tick {
+        let incident = self.incident_interval.filter(|_| incident);
+        match (incident, self.incident_ticker.is_some()) {
+            (Some(period), false) => {
+                info!("Incident active, re-checking firing rules every {:?}", period);
+                self.incident_ticker = Some(self.interval_at(Instant::now() + period, period));
+            }
+            (None, true) => {
+                info!("Incident over, checking every {:?} only", self.interval);
+                self.incident_ticker = None;
+            }
+            _ => {}
+        }
+        if self.ticker.is_none() {
+            self.start();
+        }
+        
+        let Some(ticker) = self.ticker.as_mut() else {
+            return Tick::Full;
+        };
+        match self.incident_ticker.as_mut() {
+            Some(incident_ticker) => tokio::select! {
+                biased;
+                _ = ticker.tick() => {
+                    // The full check covers the firing rules too
+                    incident_ticker.reset();
+                    Tick::Full
+                }
+                _ = incident_ticker.tick() => Tick::Incident,
+            },
+            None => {
+                ticker.tick().await;
+                Tick::Full
+            }
+        }
+    }
+    
+    /// Ticks every `interval` from now, or on the wall-clock boundaries when aligned.
+    fn regular(&self) -> Interval {
+        let first = if self.align { self.until_next_boundary() } else { self.interval };
+        self.interval_at(Instant::now() + first, self.interval)
+    }
+    
+    fn interval_at(&self, start: Instant, period: Duration) -> Interval {
+        let mut ticker = tokio::time::interval_at(start, period);
+        ticker.set_missed_tick_behavior(self.missed_tick);
+        ticker
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[tokio::test(start_paused = true)]
+    async fn incidents_add_ticks_between_the_regular_ones() {
+        let mut config = Config::default();
+        config.monitoring.check_interval = 300;
+        config.monitoring.incident_check_interval = 30;
+        let mut schedule = Schedule::new(&config);
+        let started = Instant::now();
+        schedule.start();
+        
+        let mut ticks = Vec::new();
+        for _ in 0..11 {
+            let tick = schedule.tick(true).await;
+            ticks.push((tick, started.elapsed().as_secs()));
+        }
+        let expected: Vec<(Tick, u64)> = (1..=9).map(|n| (Tick::Incident, n * 30))
+            .chain([(Tick::Full, 300), (Tick::Incident, 330)])
+            .collect();
+        assert_eq!(ticks, expected);
+        
+        // Back to the regular cadence once the incident is over
+        assert_eq!(schedule.tick(false).await, Tick::Full);
+        assert_eq!(started.elapsed().as_secs(), 600);
+    }
+    
+    #[tokio::test(start_paused = true)]
+    async fn incidents_keep_the_regular_cadence_without_an_incident_interval() {
+        let mut config = Config::default();
+        config.monitoring.check_interval = 300;
+        let mut schedule = Schedule::new(&config);
+        let started = Instant::now();
+        schedule.start();
+        
+        assert_eq!(schedule.tick(true).await, Tick::Full);
+        assert_eq!(started.elapsed().as_secs(), 300);
+    }
+}
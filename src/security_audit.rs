@@ -0,0 +1,125 @@
+use bollard::models::{ContainerInspectResponse, MountPointTypeEnum};
+use crate::config::SecurityAuditConfig;
+use crate::digest::DigestSection;
+use log::warn;
+
+/// Environment variable names that usually hold credentials.
+const SECRET_ENV_MARKERS: [&str; 6] = ["PASSWORD", "PASSWD", "SECRET", "TOKEN", "API_KEY", "PRIVATE_KEY"];
+
+/// Risky settings found on one running container.
+#[derive(Debug, Clone)]
+pub struct SecurityFinding {
+    pub container: String,
+    pub issues: Vec<String>,
+}
+
+/// Checks running containers for root users, privileged mode, host
+/// namespaces, sensitive host mounts and plaintext secrets in the environment.
+pub fn audit(config: &SecurityAuditConfig, containers: &[ContainerInspectResponse]) -> Vec<SecurityFinding> {
+    let exclude: Vec<glob::Pattern> = config.exclude.iter()
+        .filter_map(|pattern| match glob::Pattern::new(pattern) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                warn!("Ignoring invalid security audit exclude pattern '{}': {}", pattern, e);
+                None
+            }
+        })
+        .collect();
+    
+    let mut findings: Vec<SecurityFinding> = containers.iter()
+        .filter_map(|container| {
+            let name = container.name.as_deref().unwrap_or_default().trim_start_matches('/').to_string();
+            if exclude.iter().any(|pattern| pattern.matches(&name)) {
+                return None;
+            }
+            
+            let mut issues = Vec::new();
+            let container_config = container.config.as_ref();
+            let user = container_config.and_then(|config| config.user.as_deref()).unwrap_or_default();
+            if matches!(user.split(':').next().unwrap_or_default(), "" | "root" | "0") {
+                issues.push("runs as root".to_string());
+            }
+            
+            if let Some(host_config) = &container.host_config {
+                if host_config.privileged == Some(true) {
+                    issues.push("privileged mode".to_string());
+                }
+                if host_config.network_mode.as_deref() == Some("host") {
+                    issues.push("host network".to_string());
+                }
+                if host_config.pid_mode.as_deref() == Some("host") {
+                    issues.push("host PID namespace".to_string());
+                }
+            }
+            
+            for mount in container.mounts.iter().flatten() {
+                let Some(source) = mount.source.as_deref().filter(|_| mount.typ == Some(MountPointTypeEnum::BIND)) else {
+                    continue;
+                };
+                if is_sensitive(source, &config.sensitive_paths) {
+                    let mode = if mount.rw == Some(false) { "read-only" } else { "read-write" };
+                    issues.push(format!("host path {} mounted {}", source, mode));
+                }
+            }
+            
+            if config.check_env {
+                let secrets: Vec<&str> = container_config
+                    .and_then(|config| config.env.as_ref())
+                    .into_iter()
+                    .flatten()
+                    .filter_map(|variable| variable.split_once('='))
+                    .filter(|(name, value)| !value.is_empty() && looks_secret(name))
+                    .map(|(name, _)| name)
+                    .collect();
+                if !secrets.is_empty() {
+                    issues.push(format!("secrets in plain environment variables: {}", secrets.join(", ")));
+                }
+            }
+            
+            (!issues.is_empty()).then_some(SecurityFinding { container: name, issues })
+        })
+        .collect();
+    
+    findings.sort_by(|a, b| a.container.cmp(&b.container));
+    findings
+}
+
+/// A mount is sensitive when its source is one of `paths` or a parent of one,
+/// so mounting `/` or `/var/run` counts as mounting the Docker socket.
+fn is_sensitive(source: &str, paths: &[String]) -> bool {
+    let source = source.trim_end_matches('/');
+    paths.iter().any(|path| {
+        let path = path.trim_end_matches('/');
+        source == path || source.is_empty() || path.starts_with(&format!("{}/", source))
+    })
+}
+
+/// Names like `DB_PASSWORD` or `GITHUB_TOKEN`. `*_FILE` variables point to
+/// mounted secrets and are fine.
+fn looks_secret(name: &str) -> bool {
+    let name = name.to_ascii_uppercase();
+    !name.ends_with("_FILE") && SECRET_ENV_MARKERS.iter().any(|marker| name.contains(marker))
+}
+
+/// Builds the security report digest section. Variable values are never
+/// included, only their names.
+pub fn security_section(findings: &[SecurityFinding], audited: usize) -> DigestSection {
+    let mut html = format!(
+        "<p><strong>{} of {} running containers</strong> have risky settings.</p>",
+        findings.len(), audited
+    );
+    
+    if findings.is_empty() {
+        html.push_str("<p>No containers running as root, privileged, on host namespaces, with sensitive host mounts or plaintext secrets.</p>");
+    } else {
+        let items: String = findings.iter()
+            .map(|finding| format!("<li><strong>{}</strong>: {}</li>", finding.container, finding.issues.join("; ")))
+            .collect();
+        html.push_str(&format!("<ul>{}</ul>", items));
+    }
+    
+    DigestSection {
+        title: "🔐 Container Security Audit".to_string(),
+        html,
+    }
+}
\ No newline at end of file
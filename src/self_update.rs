@@ -0,0 +1,291 @@
+use openssl::hash::MessageDigest;
+use openssl::pkey::{Id, PKey, Public};
+use openssl::sign::Verifier;
+use reqwest::{Client, RequestBuilder};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use crate::config::{Config, SelfUpdateConfig};
+use crate::network;
+use anyhow::{Result, anyhow};
+use log::{info, warn};
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Target triple this binary was built for, naming its release archive.
+const TARGET: &str = env!("BUILD_TARGET");
+
+/// File name of the binary inside release archives.
+const BINARY: &str = "performance-monitor";
+
+#[derive(Deserialize)]
+struct Release {
+    tag_name: String,
+    assets: Vec<Asset>,
+}
+
+#[derive(Deserialize)]
+struct Asset {
+    name: String,
+    browser_download_url: String,
+}
+
+/// Removes the download directory however the update ends.
+struct WorkDir(PathBuf);
+
+impl Drop for WorkDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Checks GitHub releases for a newer version, or the release `tag`, and
+/// installs it in place of the running binary. With `check_only` nothing is
+/// downloaded. Returns what happened, for the user.
+pub async fn run(config: &Config, tag: Option<&str>, check_only: bool) -> Result<String> {
+    let settings = &config.self_update;
+    let key = if check_only { None } else { signing_key(settings)? };
+    let client = network::http_client(&config.network);
+    let release = fetch_release(&client, settings, tag).await?;
+    let version = release.tag_name.trim_start_matches('v');
+    
+    if tag.is_none() && !is_newer(version, VERSION) {
+        return Ok(format!("Already up to date ({}, latest release {})", VERSION, release.tag_name));
+    }
+    if check_only {
+        return Ok(format!("Release {} is available, running {}", release.tag_name, VERSION));
+    }
+    
+    let archive = archive_asset(&release)?;
+    let executable = std::env::current_exe()?.canonicalize()?;
+    let directory = executable.parent().ok_or_else(|| anyhow!("{} has no parent directory", executable.display()))?;
+    let work = WorkDir(directory.join(format!(".{}-update-{}", BINARY, std::process::id())));
+    fs::create_dir(&work.0).map_err(|e| anyhow!("cannot write to {}: {}", directory.display(), e))?;
+    
+    info!("Downloading {}", archive.name);
+    let bytes = download(&client, &archive.browser_download_url).await?;
+    verify_checksum(&client, &release, archive, &bytes).await?;
+    match &key {
+        Some(key) => verify_signature(&client, key, &release, archive, &bytes).await?,
+        None => warn!("self_update.require_signature is off, {} is only verified by its checksum", archive.name),
+    }
+    
+    let archive_path = work.0.join(&archive.name);
+    fs::write(&archive_path, &bytes)?;
+    let status = Command::new("tar")
+        .arg("-xzf").arg(&archive_path)
+        .arg("-C").arg(&work.0)
+        .arg(BINARY)
+        .status()
+        .map_err(|e| anyhow!("cannot run tar: {}", e))?;
+    if !status.success() {
+        return Err(anyhow!("tar could not extract {} from {}", BINARY, archive.name));
+    }
+    let staged = work.0.join(BINARY);
+    fs::set_permissions(&staged, fs::Permissions::from_mode(0o755))?;
+    smoke_test(&staged).map_err(|e| anyhow!("downloaded binary does not run, nothing was replaced: {}", e))?;
+    
+    // Keep the running binary for a rollback, then swap in the new one with a
+    // rename, which is atomic within the directory
+    let backup = backup_path(&executable);
+    fs::copy(&executable, &backup).map_err(|e| anyhow!("cannot back up {}: {}", executable.display(), e))?;
+    fs::rename(&staged, &executable).map_err(|e| anyhow!("cannot replace {}: {}", executable.display(), e))?;
+    if let Err(e) = smoke_test(&executable) {
+        fs::rename(&backup, &executable)?;
+        return Err(anyhow!("installed binary does not run, rolled back to {}: {}", VERSION, e));
+    }
+    
+    Ok(format!(
+        "Updated {} from {} to {}, the previous binary is kept as {}. Restart running monitors to use it.",
+        executable.display(), VERSION, release.tag_name, backup.display()
+    ))
+}
+
+/// Puts back the binary the last update replaced.
+pub fn rollback() -> Result<String> {
+    let executable = std::env::current_exe()?.canonicalize()?;
+    let backup = backup_path(&executable);
+    if !backup.exists() {
+        return Err(anyhow!("no previous binary at {}", backup.display()));
+    }
+    smoke_test(&backup).map_err(|e| anyhow!("previous binary {} does not run: {}", backup.display(), e))?;
+    fs::rename(&backup, &executable).map_err(|e| anyhow!("cannot restore {}: {}", executable.display(), e))?;
+    Ok(format!("Restored the previous binary to {}", executable.display()))
+}
+
+async fn fetch_release(client: &Client, settings: &SelfUpdateConfig, tag: Option<&str>) -> Result<Release> {
+    let url = match tag {
+        Some(tag) => format!("{}/repos/{}/releases/tags/{}", settings.api_url.trim_end_matches('/'), settings.repository, tag),
+        None => format!("{}/repos/{}/releases/latest", settings.api_url.trim_end_matches('/'), settings.repository),
+    };
+    let response = github(client.get(&url)).header("Accept", "application/vnd.github+json").send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("{} returned {}", url, response.status()));
+    }
+    Ok(response.json().await?)
+}
+
+/// The archive built for this target, or the static musl build of the same
+/// architecture, which runs on glibc hosts too.
+fn archive_asset(release: &Release) -> Result<&Asset> {
+    let targets = [TARGET.to_string(), TARGET.replace("-gnu", "-musl")];
+    targets.iter()
+        .find_map(|target| {
+            let name = format!("{}-{}-{}.tar.gz", BINARY, release.tag_name, target);
+            release.assets.iter().find(|asset| asset.name == name)
+        })
+        .ok_or_else(|| anyhow!("release {} has no archive for {}", release.tag_name, TARGET))
+}
+
+async fn download(client: &Client, url: &str) -> Result<Vec<u8>> {
+    let response = github(client.get(url)).send().await?;
+    if !response.status().is_success() {
+        return Err(anyhow!("{} returned {}", url, response.status()));
+    }
+    Ok(response.bytes().await?.to_vec())
+}
+
+async fn companion(client: &Client, release: &Release, archive: &Asset, extension: &str) -> Result<Option<Vec<u8>>> {
+    let name = format!("{}.{}", archive.name, extension);
+    match release.assets.iter().find(|asset| asset.name == name) {
+        Some(asset) => Ok(Some(download(client, &asset.browser_download_url).await?)),
+        None => Ok(None),
+    }
+}
+
+/// Compares the archive with its `sha256sum` output, which every release has.
+async fn verify_checksum(client: &Client, release: &Release, archive: &Asset, bytes: &[u8]) -> Result<()> {
+    let checksum = companion(client, release, archive, "sha256").await?
+        .ok_or_else(|| anyhow!("release {} has no {}.sha256", release.tag_name, archive.name))?;
+    let expected = String::from_utf8_lossy(&checksum).split_whitespace().next().unwrap_or_default().to_lowercase();
+    let actual: String = Sha256::digest(bytes).iter().map(|byte| format!("{:02x}", byte)).collect();
+    if expected != actual {
+        return Err(anyhow!("checksum mismatch for {}: expected {}, got {}", archive.name, expected, actual));
+    }
+    info!("Checksum of {} verified", archive.name);
+    Ok(())
+}
+
+/// The `public_key` releases are verified with, read before anything is
+/// downloaded. Without one nothing is installed unless `require_signature`
+/// was turned off.
+fn signing_key(settings: &SelfUpdateConfig) -> Result<Option<PKey<Public>>> {
+    match &settings.public_key {
+        Some(path) => {
+            let pem = fs::read(path).map_err(|e| anyhow!("cannot read {}: {}", path, e))?;
+            Ok(Some(PKey::public_key_from_pem(&pem).map_err(|e| anyhow!("{} is not a PEM public key: {}", path, e))?))
+        }
+        None if settings.require_signature => Err(anyhow!(
+            "self_update.public_key is not set, releases are only installed when their signature verifies \
+             (set self_update.require_signature to false to trust the checksum alone)"
+        )),
+        None => Ok(None),
+    }
+}
+
+/// Verifies the `openssl dgst -sha256 -sign` signature of the archive with
+/// `key` (RSA, EC or Ed25519).
+async fn verify_signature(
+    client: &Client,
+    key: &PKey<Public>,
+    release: &Release,
+    archive: &Asset,
+    bytes: &[u8],
+) -> Result<()> {
+    let signature = companion(client, release, archive, "sig").await?
+        .ok_or_else(|| anyhow!("release {} has no {}.sig", release.tag_name, archive.name))?;
+    
+    let valid = if key.id() == Id::ED25519 {
+        Verifier::new_without_digest(key)?.verify_oneshot(&signature, bytes)?
+    } else {
+        let mut verifier = Verifier::new(MessageDigest::sha256(), key)?;
+        verifier.update(bytes)?;
+        verifier.verify(&signature)?
+    };
+    if !valid {
+        return Err(anyhow!("signature of {} does not match self_update.public_key", archive.name));
+    }
+    info!("Signature of {} verified", archive.name);
+    Ok(())
+}
+
+fn smoke_test(binary: &Path) -> Result<()> {
+    let output = Command::new(binary).arg("--version").output()?;
+    if !output.status.success() {
+        return Err(anyhow!("--version exited with {}", output.status));
+    }
+    Ok(())
+}
+
+fn backup_path(executable: &Path) -> PathBuf {
+    let mut path = OsString::from(executable);
+    path.push(".old");
+    PathBuf::from(path)
+}
+
+fn github(request: RequestBuilder) -> RequestBuilder {
+    request.header("User-Agent", BINARY)
+}
+
+/// Whether version `candidate` is above `current`, comparing the numeric
+/// parts of `major.minor.patch` and ignoring pre-release suffixes. Missing
+/// parts count as zero, so `1.2` and `1.2.0` are the same version.
+fn is_newer(candidate: &str, current: &str) -> bool {
+    let parts = |version: &str| -> Vec<u64> {
+        let mut parts: Vec<u64> = version.split(['-', '+']).next().unwrap_or_default()
+            .split('.')
+            .map(|part| part.parse().unwrap_or(0))
+            .collect();
+        while parts.last() == Some(&0) {
+            parts.pop();
+        }
+        parts
+    };
+    parts(candidate) > parts(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn release(targets: &[&str]) -> Release {
+        Release {
+            tag_name: "v1.4.0".to_string(),
+            assets: targets.iter()
+                .flat_map(|target| {
+                    let archive = format!("{}-v1.4.0-{}.tar.gz", BINARY, target);
+                    [format!("{}.sha256", archive), archive]
+                })
+                .map(|name| Asset { browser_download_url: format!("https://example.com/{}", name), name })
+                .collect(),
+        }
+    }
+    
+    #[test]
+    fn compares_numeric_versions() {
+        assert!(is_newer("1.10.0", "1.9.3"));
+        assert!(is_newer("0.2.0", "0.1.9"));
+        assert!(is_newer("2", "1.99.99"));
+        assert!(!is_newer("1.2.0", "1.2"));
+        assert!(!is_newer("1.2", "1.2.0"));
+        assert!(!is_newer("1.2.1", "1.2.1"));
+        assert!(!is_newer("1.2.0-rc.1", "1.2.0"));
+        assert!(!is_newer("1.1.9", "1.2.0"));
+    }
+    
+    #[test]
+    fn picks_the_archive_for_this_target() {
+        let musl = TARGET.replace("-gnu", "-musl");
+        let name = |target: &str| format!("{}-v1.4.0-{}.tar.gz", BINARY, target);
+        
+        let both = release(&["armv7-unknown-linux-gnueabihf", &musl, TARGET]);
+        assert_eq!(archive_asset(&both).unwrap().name, name(TARGET));
+        let musl_only = release(&[&musl]);
+        assert_eq!(archive_asset(&musl_only).unwrap().name, name(&musl));
+        assert!(archive_asset(&release(&["armv7-unknown-linux-gnueabihf"])).is_err());
+    }
+}
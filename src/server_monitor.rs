@@ -1,19 +1,82 @@
 use serde::{Deserialize, Serialize};
-use sysinfo::{System, SystemExt, CpuExt, DiskExt};
+use sysinfo::{Disk, System, SystemExt, CpuExt, DiskExt, NetworkExt, NetworksExt, PidExt, ProcessExt, ProcessRefreshKind, UserExt};
 use chrono::{DateTime, Utc};
-use crate::config::Config;
+use glob::Pattern;
+use crate::config::{Config, FilesystemConfig, InterfaceConfig};
+use crate::kubernetes;
+use crate::units;
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use log::{info, warn};
 
+/// Time between the two process samples CPU usage is measured over.
+pub const PROCESS_SAMPLE_INTERVAL: Duration = System::MINIMUM_CPU_UPDATE_INTERVAL;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ServerStats {
     pub timestamp: DateTime<Utc>,
     pub cpu_usage: f64,
+    /// Usage of each logical CPU, in the kernel's order.
+    #[serde(default)]
+    pub cpu_cores: Vec<CpuCoreStats>,
     pub memory_usage: MemoryStats,
+    #[serde(default)]
+    pub swap_usage: SwapStats,
+    /// The root filesystem.
     pub disk_usage: DiskStats,
+    /// Every monitored filesystem by mount point, the root included.
+    #[serde(default)]
+    pub mounts: BTreeMap<String, DiskStats>,
+    /// Monitored network interfaces, sorted by name.
+    #[serde(default)]
+    pub network: Vec<NetworkStats>,
     pub load_average: LoadAverage,
     pub system_info: SystemInfo,
 }
 
+/// Usage of one logical CPU. `hot_checks` counts the consecutive checks it
+/// has been above `cpu_cores.threshold`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CpuCoreStats {
+    pub name: String,
+    pub usage: f64,
+    pub frequency_mhz: u64,
+    #[serde(default)]
+    pub hot_checks: u32,
+}
+
+impl CpuCoreStats {
+    pub fn describe(&self) -> String {
+        format!(
+            "{}: {:.1}% at {} MHz, {} consecutive checks over threshold",
+            self.name, self.usage, self.frequency_mhz, self.hot_checks
+        )
+    }
+}
+
+/// CPU and memory of one host process, for the top processes in CPU alerts.
+/// CPU is in percent of one core, so a multi-threaded process can exceed 100.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProcessUsage {
+    pub pid: u32,
+    pub name: String,
+    pub user: String,
+    pub cpu_usage: f64,
+    pub memory: u64,
+    pub memory_percent: f64,
+}
+
+impl ProcessUsage {
+    pub fn describe(&self) -> String {
+        format!(
+            "{} (PID {}, {}): {:.1}% CPU, {} ({:.1}% memory)",
+            self.name, self.pid, self.user, self.cpu_usage, units::bytes(self.memory as f64), self.memory_percent
+        )
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryStats {
     pub total: u64,
@@ -22,12 +85,151 @@ pub struct MemoryStats {
     pub percent: f64,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Swap space, all zero on a host without swap.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SwapStats {
+    pub total: u64,
+    pub used: u64,
+    pub percent: f64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DiskStats {
     pub total: u64,
     pub used: u64,
     pub available: u64,
     pub percent: f64,
+    #[serde(default)]
+    pub device: String,
+    #[serde(default)]
+    pub file_system: String,
+}
+
+impl DiskStats {
+    fn from_disk(disk: &Disk) -> Self {
+        let total = disk.total_space();
+        let available = disk.available_space();
+        let used = total - available;
+        
+        Self {
+            total,
+            used,
+            available,
+            percent: (used as f64 / total as f64) * 100.0,
+            device: disk.name().to_string_lossy().to_string(),
+            file_system: String::from_utf8_lossy(disk.file_system()).to_string(),
+        }
+    }
+    
+    pub fn describe(&self, mount_point: &str) -> String {
+        format!(
+            "{} ({} on {}): {:.1}% used, {} free of {}",
+            mount_point, self.file_system, self.device, self.percent,
+            units::bytes(self.available as f64), units::bytes(self.total as f64)
+        )
+    }
+}
+
+/// Counters and rates of one network interface. Rates cover the time since
+/// the previous sample and are missing for an interface seen for the first
+/// time or whose counters went backwards.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NetworkStats {
+    pub interface: String,
+    pub rx_bytes: u64,
+    pub tx_bytes: u64,
+    pub rx_packets: u64,
+    pub tx_packets: u64,
+    pub rx_errors: u64,
+    pub tx_errors: u64,
+    pub rx_bytes_per_sec: Option<f64>,
+    pub tx_bytes_per_sec: Option<f64>,
+}
+
+impl NetworkStats {
+    /// Received bandwidth in Mbit/s.
+    pub fn rx_mbps(&self) -> Option<f64> {
+        self.rx_bytes_per_sec.map(|rate| rate * 8.0 / 1_000_000.0)
+    }
+    
+    /// Transmitted bandwidth in Mbit/s.
+    pub fn tx_mbps(&self) -> Option<f64> {
+        self.tx_bytes_per_sec.map(|rate| rate * 8.0 / 1_000_000.0)
+    }
+    
+    /// The higher of the received and transmitted bandwidth.
+    pub fn peak_mbps(&self) -> Option<f64> {
+        [self.rx_mbps(), self.tx_mbps()].into_iter().flatten().reduce(f64::max)
+    }
+    
+    pub fn describe(&self) -> String {
+        let mbps = |rate: Option<f64>| rate.map_or("-".to_string(), |rate| format!("{:.2} Mbit/s", rate));
+        format!(
+            "{}: rx {}, tx {} ({} received, {} sent, {} rx errors, {} tx errors)",
+            self.interface, mbps(self.rx_mbps()), mbps(self.tx_mbps()),
+            units::bytes(self.rx_bytes as f64), units::bytes(self.tx_bytes as f64), self.rx_errors, self.tx_errors
+        )
+    }
+}
+
+/// Include and exclude globs, compiled once. An empty include list selects
+/// every name; excludes win.
+struct NameFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl NameFilter {
+    fn new(include: &[String], exclude: &[String], section: &str) -> Self {
+        Self {
+            include: compile_patterns(include, section),
+            exclude: compile_patterns(exclude, section),
+        }
+    }
+    
+    fn selects(&self, name: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|pattern| pattern.matches(name)))
+            && !self.exclude.iter().any(|pattern| pattern.matches(name))
+    }
+}
+
+fn compile_patterns(patterns: &[String], section: &str) -> Vec<Pattern> {
+    patterns.iter()
+        .filter_map(|pattern| match Pattern::new(pattern) {
+            Ok(pattern) => Some(pattern),
+            Err(e) => {
+                warn!("Invalid pattern '{}' in {}: {}", pattern, section, e);
+                None
+            }
+        })
+        .collect()
+}
+
+/// The `filesystems` mount point globs and threshold overrides.
+struct MountFilter {
+    names: NameFilter,
+    /// Warning and critical threshold overrides, first match wins.
+    thresholds: Vec<(Pattern, f64, Option<f64>)>,
+}
+
+impl MountFilter {
+    fn new(config: &FilesystemConfig) -> Self {
+        Self {
+            names: NameFilter::new(&config.include, &config.exclude, "filesystems"),
+            thresholds: config.thresholds.iter()
+                .filter_map(|threshold| {
+                    let pattern = compile_patterns(std::slice::from_ref(&threshold.mount), "filesystems").pop()?;
+                    Some((pattern, threshold.threshold, threshold.critical_threshold))
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Cumulative byte counters of an interface at the previous sample.
+struct NetworkSample {
+    sampled_at: Instant,
+    bytes: HashMap<String, (u64, u64)>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -50,31 +252,90 @@ pub struct SystemInfo {
 
 pub struct ServerMonitor {
     system: System,
-    config: Config,
+    config: Arc<Config>,
+    mounts: MountFilter,
+    interfaces: NameFilter,
+    network_sample: Option<NetworkSample>,
+    /// Consecutive checks each core has been above `cpu_cores.threshold`.
+    hot_checks: HashMap<String, u32>,
 }
 
 impl ServerMonitor {
-    pub fn new(config: Config) -> Self {
-        let mut system = System::new_all();
-        system.refresh_all();
+    /// Loads only what the host metrics need. Processes are listed only for
+    /// the top processes of a CPU alert; the process watchdog refreshes its
+    /// own list when checks are configured.
+    pub fn new(config: Arc<Config>) -> Self {
+        let mut system = System::new();
+        system.refresh_cpu();
+        system.refresh_memory();
+        system.refresh_disks_list();
+        system.refresh_networks_list();
         
-        Self {
+        let mut monitor = Self {
             system,
+            mounts: MountFilter::new(&config.filesystems),
+            interfaces: interface_filter(&config.interfaces),
+            network_sample: None,
+            hot_checks: HashMap::new(),
             config,
-        }
+        };
+        // Baseline for the first bandwidth rates
+        monitor.read_network();
+        monitor
+    }
+    
+    /// Swaps in a config with runtime threshold overrides applied.
+    pub fn set_config(&mut self, config: Arc<Config>) {
+        self.mounts = MountFilter::new(&config.filesystems);
+        self.interfaces = interface_filter(&config.interfaces);
+        self.config = config;
     }
     
+    /// Refreshes CPU, memory, disk and network usage, the metrics
+    /// `get_full_stats` reads.
     pub fn refresh(&mut self) {
-        self.system.refresh_all();
+        self.system.refresh_cpu();
+        self.system.refresh_memory();
+        self.system.refresh_disks();
+        self.system.refresh_networks_list();
     }
     
     pub fn get_cpu_usage(&mut self) -> f64 {
-        self.refresh();
-        self.system.global_cpu_info().cpu_usage().into()
+        self.system.refresh_cpu();
+        self.read_cpu_usage()
     }
     
     pub fn get_memory_usage(&mut self) -> MemoryStats {
-        self.refresh();
+        self.system.refresh_memory();
+        self.read_memory_usage()
+    }
+    
+    pub fn get_swap_usage(&mut self) -> SwapStats {
+        self.system.refresh_memory();
+        self.read_swap_usage()
+    }
+    
+    pub fn get_disk_usage(&mut self) -> DiskStats {
+        self.system.refresh_disks();
+        self.read_disk_usage()
+    }
+    
+    fn read_cpu_usage(&self) -> f64 {
+        self.system.global_cpu_info().cpu_usage().into()
+    }
+    
+    fn read_cpu_cores(&self) -> Vec<CpuCoreStats> {
+        self.system.cpus().iter()
+            .map(|cpu| CpuCoreStats {
+                name: cpu.name().to_string(),
+                usage: cpu.cpu_usage().into(),
+                frequency_mhz: cpu.frequency(),
+                hot_checks: self.hot_checks.get(cpu.name()).copied().unwrap_or_default(),
+            })
+            .collect()
+    }
+    
+    fn read_memory_usage(&self) -> MemoryStats {
         let memory = self.system.total_memory();
         let used = self.system.used_memory();
         let available = self.system.available_memory();
@@ -87,28 +348,129 @@ impl ServerMonitor {
         }
     }
     
-    pub fn get_disk_usage(&mut self) -> DiskStats {
-        self.refresh();
-        // Get root disk usage
-        if let Some(disk) = self.system.disks().first() {
-            let total = disk.total_space();
-            let available = disk.available_space();
-            let used = total - available;
-            
-            DiskStats {
-                total,
-                used,
-                available,
-                percent: (used as f64 / total as f64) * 100.0,
+    fn read_swap_usage(&self) -> SwapStats {
+        let total = self.system.total_swap();
+        let used = self.system.used_swap();
+        
+        SwapStats {
+            total,
+            used,
+            percent: if total == 0 { 0.0 } else { (used as f64 / total as f64) * 100.0 },
+        }
+    }
+    
+    /// Monitored filesystems by mount point, filtered by the `filesystems`
+    /// globs. A filesystem mounted at several points is listed once, at its
+    /// shortest mount point.
+    pub fn get_mounts(&mut self) -> BTreeMap<String, DiskStats> {
+        self.system.refresh_disks();
+        self.read_mounts()
+    }
+    
+    fn read_mounts(&self) -> BTreeMap<String, DiskStats> {
+        let mut disks: Vec<&Disk> = self.system.disks().iter()
+            .filter(|disk| disk.total_space() > 0)
+            .collect();
+        disks.sort_by_key(|disk| disk.mount_point().as_os_str().len());
+        
+        let mut mounts: BTreeMap<String, DiskStats> = BTreeMap::new();
+        for disk in disks {
+            let mount_point = disk.mount_point().display().to_string();
+            let stats = DiskStats::from_disk(disk);
+            let mounted = mounts.values().any(|mount| mount.device == stats.device && mount.total == stats.total);
+            if !mounted && self.mounts.names.selects(&mount_point) {
+                mounts.insert(mount_point, stats);
             }
-        } else {
-            DiskStats {
-                total: 0,
-                used: 0,
-                available: 0,
-                percent: 0.0,
+        }
+        mounts
+    }
+    
+    /// Warning and critical threshold of a mount point, from `filesystems.thresholds`
+    /// or else `disk_threshold` and `disk_critical_threshold`.
+    pub fn mount_thresholds(&self, mount_point: &str) -> (f64, f64) {
+        let monitoring = &self.config.monitoring;
+        self.mounts.thresholds.iter()
+            .find(|(pattern, _, _)| pattern.matches(mount_point))
+            .map(|(_, threshold, critical)| (*threshold, critical.unwrap_or(monitoring.disk_critical_threshold)))
+            .unwrap_or((monitoring.disk_threshold, monitoring.disk_critical_threshold))
+    }
+    
+    fn read_disk_usage(&self) -> DiskStats {
+        // The filesystem mounted at `/`. In a pod that is the container's
+        // overlay, the node's root is the one mounted at `host_root`.
+        let host_root = kubernetes::current().and_then(|cluster| cluster.host_root.as_deref()).unwrap_or("/");
+        self.system.disks().iter()
+            .find(|disk| disk.mount_point() == Path::new(host_root))
+            .or_else(|| self.system.disks().first())
+            .map(DiskStats::from_disk)
+            .unwrap_or_default()
+    }
+    
+    /// Counters and bandwidth of the monitored interfaces since the previous
+    /// call, or since startup for the first one.
+    pub fn get_network_stats(&mut self) -> Vec<NetworkStats> {
+        self.system.refresh_networks_list();
+        self.read_network()
+    }
+    
+    fn read_network(&mut self) -> Vec<NetworkStats> {
+        let now = Instant::now();
+        let elapsed = self.network_sample.as_ref()
+            .map(|sample| now.duration_since(sample.sampled_at).as_secs_f64())
+            .filter(|elapsed| *elapsed > 0.0);
+        
+        let mut interfaces = Vec::new();
+        let mut bytes = HashMap::new();
+        for (interface, data) in self.system.networks().iter() {
+            if !self.interfaces.selects(interface) {
+                continue;
             }
+            let (rx_bytes, tx_bytes) = (data.total_received(), data.total_transmitted());
+            let previous = self.network_sample.as_ref().and_then(|sample| sample.bytes.get(interface));
+            let rate = |current: u64, previous: u64| {
+                Some(current.checked_sub(previous)? as f64 / elapsed?)
+            };
+            
+            interfaces.push(NetworkStats {
+                interface: interface.clone(),
+                rx_bytes,
+                tx_bytes,
+                rx_packets: data.total_packets_received(),
+                tx_packets: data.total_packets_transmitted(),
+                rx_errors: data.total_errors_on_received(),
+                tx_errors: data.total_errors_on_transmitted(),
+                rx_bytes_per_sec: previous.and_then(|(previous, _)| rate(rx_bytes, *previous)),
+                tx_bytes_per_sec: previous.and_then(|(_, previous)| rate(tx_bytes, *previous)),
+            });
+            bytes.insert(interface.clone(), (rx_bytes, tx_bytes));
         }
+        
+        interfaces.sort_by(|a, b| a.interface.cmp(&b.interface));
+        self.network_sample = Some(NetworkSample { sampled_at: now, bytes });
+        interfaces
+    }
+    
+    /// Interfaces whose received or transmitted bandwidth is above
+    /// `interfaces.threshold_mbps`, never when it is 0, with every monitored
+    /// interface.
+    pub fn check_network_threshold(&mut self) -> (Vec<NetworkStats>, Vec<NetworkStats>) {
+        let interfaces = self.get_network_stats();
+        let threshold = self.config.interfaces.threshold_mbps;
+        if threshold <= 0.0 {
+            return (Vec::new(), interfaces);
+        }
+        
+        let busy: Vec<NetworkStats> = interfaces.iter()
+            .filter(|stats| stats.peak_mbps().is_some_and(|mbps| mbps > threshold))
+            .cloned()
+            .collect();
+        for stats in &busy {
+            warn!("High bandwidth detected: {} (threshold: {:.2} Mbit/s)", stats.describe(), threshold);
+        }
+        if busy.is_empty() {
+            info!("Bandwidth is normal on {} interfaces", interfaces.len());
+        }
+        (busy, interfaces)
     }
     
     pub fn get_load_average(&self) -> LoadAverage {
@@ -124,20 +486,21 @@ impl ServerMonitor {
             }
         }
         
-        // Fallback values
+        // macOS and the BSDs have no procfs, sysinfo asks the kernel instead
+        let load = self.system.load_average();
         LoadAverage {
-            one_min: 0.0,
-            five_min: 0.0,
-            fifteen_min: 0.0,
+            one_min: load.one,
+            five_min: load.five,
+            fifteen_min: load.fifteen,
         }
     }
     
     pub fn get_system_info(&self) -> SystemInfo {
         let boot_time = DateTime::from_timestamp(self.system.boot_time() as i64, 0)
-            .unwrap_or_else(|| Utc::now());
+            .unwrap_or_else(Utc::now);
         
         SystemInfo {
-            hostname: self.system.name().unwrap_or_else(|| "Unknown".to_string()),
+            hostname: kubernetes::hostname(),
             os: self.system.long_os_version().unwrap_or_else(|| "Unknown".to_string()),
             kernel: self.system.kernel_version().unwrap_or_else(|| "Unknown".to_string()),
             cpu_count: self.system.cpus().len(),
@@ -163,14 +526,143 @@ impl ServerMonitor {
         }
     }
     
+    /// Cores that stayed above `cpu_cores.threshold` for `sustained_checks`,
+    /// never when it is 0, with every core. Reads the sample taken by
+    /// `check_cpu_threshold`, so call it right after that.
+    pub fn check_hot_cores(&mut self) -> (Vec<CpuCoreStats>, Vec<CpuCoreStats>) {
+        let config = &self.config.cpu_cores;
+        if config.threshold <= 0.0 {
+            self.hot_checks.clear();
+            return (Vec::new(), self.read_cpu_cores());
+        }
+        
+        for cpu in self.system.cpus() {
+            if f64::from(cpu.cpu_usage()) > config.threshold {
+                *self.hot_checks.entry(cpu.name().to_string()).or_default() += 1;
+            } else {
+                self.hot_checks.remove(cpu.name());
+            }
+        }
+        let cores = self.read_cpu_cores();
+        let hot: Vec<CpuCoreStats> = cores.iter()
+            .filter(|core| core.hot_checks >= config.sustained_checks.max(1))
+            .cloned()
+            .collect();
+        for core in &hot {
+            warn!("Hot CPU core detected: {} (threshold: {:.2}%)", core.describe(), config.threshold);
+        }
+        if hot.is_empty() {
+            info!("No CPU core above {:.2}% for {} checks", config.threshold, config.sustained_checks);
+        }
+        (hot, cores)
+    }
+    
+    /// Takes the first process sample; call `top_processes` after
+    /// `PROCESS_SAMPLE_INTERVAL`.
+    pub fn sample_processes(&mut self) {
+        self.system.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_user());
+        self.system.refresh_users_list();
+    }
+    
+    /// The `count` busiest processes by CPU, followed by those among the
+    /// `count` largest by memory that are not already listed.
+    pub fn top_processes(&mut self, count: usize) -> Vec<ProcessUsage> {
+        self.system.refresh_processes_specifics(ProcessRefreshKind::new().with_cpu().with_user());
+        self.system.refresh_memory();
+        let total_memory = self.system.total_memory().max(1) as f64;
+        let mut processes: Vec<ProcessUsage> = self.system.processes().values()
+            .map(|process| ProcessUsage {
+                pid: process.pid().as_u32(),
+                name: process.name().to_string(),
+                user: process.user_id()
+                    .and_then(|uid| self.system.get_user_by_id(uid))
+                    .map(|user| user.name().to_string())
+                    .or_else(|| process.user_id().map(|uid| uid.to_string()))
+                    .unwrap_or_else(|| "?".to_string()),
+                cpu_usage: process.cpu_usage().into(),
+                memory: process.memory(),
+                memory_percent: process.memory() as f64 / total_memory * 100.0,
+            })
+            .collect();
+        
+        processes.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage));
+        let mut top: Vec<ProcessUsage> = processes.iter().take(count).cloned().collect();
+        processes.sort_by_key(|process| std::cmp::Reverse(process.memory));
+        for process in processes.into_iter().take(count) {
+            if !top.iter().any(|listed| listed.pid == process.pid) {
+                top.push(process);
+            }
+        }
+        top
+    }
+    
+    /// Whether host memory usage is above `memory_threshold`, never when it is 0.
+    pub fn check_memory_threshold(&mut self) -> (bool, MemoryStats) {
+        let memory = self.get_memory_usage();
+        let threshold = self.config.monitoring.memory_threshold;
+        
+        if threshold > 0.0 && memory.percent > threshold {
+            warn!("High memory usage detected: {:.2}% (threshold: {:.2}%)", memory.percent, threshold);
+            (true, memory)
+        } else {
+            info!("Memory usage is normal: {:.2}%", memory.percent);
+            (false, memory)
+        }
+    }
+    
+    /// Whether swap usage is above `swap_threshold`, never when it is 0 or
+    /// the host has no swap.
+    pub fn check_swap_threshold(&mut self) -> (bool, SwapStats) {
+        let swap = self.get_swap_usage();
+        let threshold = self.config.monitoring.swap_threshold;
+        
+        if threshold > 0.0 && swap.percent > threshold {
+            warn!("High swap usage detected: {:.2}% (threshold: {:.2}%)", swap.percent, threshold);
+            (true, swap)
+        } else {
+            info!("Swap usage is normal: {:.2}%", swap.percent);
+            (false, swap)
+        }
+    }
+    
+    /// Mount points above their threshold, never those whose threshold is 0,
+    /// along with the root disk and every monitored mount.
+    pub fn check_disk_threshold(&mut self) -> (Vec<String>, DiskStats, BTreeMap<String, DiskStats>) {
+        let mounts = self.get_mounts();
+        let disk = self.read_disk_usage();
+        
+        let mut full = Vec::new();
+        for (mount_point, stats) in &mounts {
+            let (threshold, _) = self.mount_thresholds(mount_point);
+            if threshold > 0.0 && stats.percent > threshold {
+                warn!("High disk usage detected on {}: {:.2}% (threshold: {:.2}%)", mount_point, stats.percent, threshold);
+                full.push(mount_point.clone());
+            }
+        }
+        if full.is_empty() {
+            info!("Disk usage is normal on {} mounts", mounts.len());
+        }
+        (full, disk, mounts)
+    }
+    
+    /// Refreshes once for all metrics rather than once per getter.
     pub fn get_full_stats(&mut self) -> ServerStats {
+        self.refresh();
         ServerStats {
             timestamp: Utc::now(),
-            cpu_usage: self.get_cpu_usage(),
-            memory_usage: self.get_memory_usage(),
-            disk_usage: self.get_disk_usage(),
+            cpu_usage: self.read_cpu_usage(),
+            cpu_cores: self.read_cpu_cores(),
+            memory_usage: self.read_memory_usage(),
+            swap_usage: self.read_swap_usage(),
+            disk_usage: self.read_disk_usage(),
+            mounts: self.read_mounts(),
+            network: self.read_network(),
             load_average: self.get_load_average(),
             system_info: self.get_system_info(),
         }
     }
+}
+
+fn interface_filter(config: &InterfaceConfig) -> NameFilter {
+    NameFilter::new(&config.include, &config.exclude, "interfaces")
 }
\ No newline at end of file
@@ -0,0 +1,69 @@
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+use log::{info, error, warn};
+
+/// Exit code after a second signal, as a shell reports an interrupted command.
+const FORCED_EXIT_CODE: i32 = 130;
+
+/// Cancellation of the continuous loop, shared by every clone.
+#[derive(Clone)]
+pub struct ShutdownToken {
+    sender: Arc<watch::Sender<bool>>,
+    receiver: watch::Receiver<bool>,
+}
+
+impl Default for ShutdownToken {
+    fn default() -> Self {
+        let (sender, receiver) = watch::channel(false);
+        Self {
+            sender: Arc::new(sender),
+            receiver,
+        }
+    }
+}
+
+impl ShutdownToken {
+    pub fn cancel(&self) {
+        self.sender.send_replace(true);
+    }
+    
+    pub fn is_cancelled(&self) -> bool {
+        *self.receiver.borrow()
+    }
+    
+    /// Resolves once the token is cancelled, at once when it already is.
+    pub async fn cancelled(&self) {
+        let mut receiver = self.receiver.clone();
+        // Every clone holds the sender, so the channel stays open while waiting
+        let _ = receiver.wait_for(|cancelled| *cancelled).await;
+    }
+}
+
+/// Cancels `token` on SIGINT (Ctrl+C) or SIGTERM, so the monitor finishes
+/// its cycle and flushes before exiting. A second signal exits immediately.
+pub fn listen(token: ShutdownToken) {
+    let (mut interrupt, mut terminate) = match (signal(SignalKind::interrupt()), signal(SignalKind::terminate())) {
+        (Ok(interrupt), Ok(terminate)) => (interrupt, terminate),
+        (Err(e), _) | (_, Err(e)) => {
+            error!("Failed to listen for shutdown signals, Ctrl+C exits without flushing: {}", e);
+            return;
+        }
+    };
+    
+    tokio::spawn(async move {
+        let name = tokio::select! {
+            _ = interrupt.recv() => "SIGINT",
+            _ = terminate.recv() => "SIGTERM",
+        };
+        info!("{} received, finishing the current cycle before exiting (send it again to exit immediately)", name);
+        token.cancel();
+        
+        tokio::select! {
+            _ = interrupt.recv() => {}
+            _ = terminate.recv() => {}
+        }
+        warn!("Second shutdown signal received, exiting without flushing");
+        std::process::exit(FORCED_EXIT_CODE);
+    });
+}
\ No newline at end of file
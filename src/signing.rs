@@ -0,0 +1,452 @@
+use chrono::{DateTime, NaiveDateTime, Utc};
+use openssl::nid::Nid;
+use openssl::pkcs7::{Pkcs7, Pkcs7Flags};
+use openssl::pkey::{PKey, Private};
+use openssl::stack::Stack;
+use openssl::x509::store::{X509Store, X509StoreBuilder};
+use openssl::x509::verify::X509VerifyFlags;
+use openssl::x509::{X509, X509Ref};
+use std::fs;
+use std::process::{Output, Stdio};
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use crate::config::{EmailCommandsConfig, EmailSigningConfig};
+use anyhow::{Result, anyhow};
+
+/// How long a `gpg` run may take, e.g. while its agent waits for a
+/// passphrase nobody will type, before it is killed.
+const GPG_TIMEOUT: Duration = Duration::from_secs(30);
+/// DER encoding of the PKCS#9 signingTime attribute OID, 1.2.840.113549.1.9.5.
+const SIGNING_TIME_OID: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x09, 0x05];
+
+/// Signs the MIME body of outgoing emails so recipients can verify they came
+/// from the monitoring host: S/MIME with a certificate and key, or PGP/MIME
+/// (RFC 3156) through the local `gpg`.
+pub enum EmailSigner {
+    Smime {
+        certificate: X509,
+        key: PKey<Private>,
+        chain: Stack<X509>,
+    },
+    Pgp {
+        key_id: String,
+        gpg_home: Option<String>,
+    },
+}
+
+impl EmailSigner {
+    pub fn from_config(config: &EmailSigningConfig) -> Result<Self> {
+        match config.method.as_str() {
+            "smime" => {
+                let certificate_path = config.certificate.as_deref()
+                    .ok_or_else(|| anyhow!("S/MIME signing needs a certificate"))?;
+                let key_path = config.private_key.as_deref()
+                    .ok_or_else(|| anyhow!("S/MIME signing needs a private_key"))?;
+                
+                let certificate = X509::from_pem(&fs::read(certificate_path)?)?;
+                let key_pem = fs::read(key_path)?;
+                let key = match &config.key_password {
+                    Some(password) => PKey::private_key_from_pem_passphrase(&key_pem, password.as_bytes())?,
+                    None => PKey::private_key_from_pem(&key_pem)?,
+                };
+                let mut chain = Stack::new()?;
+                if let Some(chain_path) = &config.chain {
+                    for certificate in X509::stack_from_pem(&fs::read(chain_path)?)? {
+                        chain.push(certificate)?;
+                    }
+                }
+                
+                Ok(EmailSigner::Smime { certificate, key, chain })
+            }
+            "pgp" => {
+                let key_id = config.key_id.clone()
+                    .ok_or_else(|| anyhow!("PGP signing needs a key_id"))?;
+                // Fail at startup rather than on the first alert
+                let output = gpg_command(config.gpg_home.as_deref()).as_std_mut()
+                    .args(["--batch", "--list-secret-keys"])
+                    .arg(&key_id)
+                    .output()
+                    .map_err(|e| anyhow!("failed to run gpg: {}", e))?;
+                if !output.status.success() {
+                    return Err(anyhow!("gpg has no secret key {}: {}", key_id, String::from_utf8_lossy(&output.stderr).trim()));
+                }
+                Ok(EmailSigner::Pgp { key_id, gpg_home: config.gpg_home.clone() })
+            }
+            other => Err(anyhow!("unknown signing method '{}' (expected smime or pgp)", other)),
+        }
+    }
+    
+    pub fn method(&self) -> &'static str {
+        match self {
+            EmailSigner::Smime { .. } => "S/MIME",
+            EmailSigner::Pgp { .. } => "PGP/MIME",
+        }
+    }
+    
+    /// Wraps a MIME entity (its headers and body, CRLF line endings) in a
+    /// `multipart/signed` entity, returned with its own MIME headers.
+    pub async fn sign(&self, entity: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            EmailSigner::Smime { certificate, key, chain } => {
+                let flags = Pkcs7Flags::DETACHED;
+                let pkcs7 = Pkcs7::sign(certificate, key, chain, entity, flags)?;
+                let signed = pkcs7.to_smime(entity, flags)?;
+                Ok(to_crlf(&signed))
+            }
+            EmailSigner::Pgp { .. } => {
+                let signature = self.pgp_signature(entity).await?;
+                let boundary = format!("pgp-signed-{:016x}", rand::random::<u64>());
+                let mut signed = format!(
+                    "MIME-Version: 1.0\r\n\
+                     Content-Type: multipart/signed; micalg=pgp-sha256; protocol=\"application/pgp-signature\"; boundary=\"{0}\"\r\n\
+                     \r\n\
+                     This is an OpenPGP/MIME signed message (RFC 4880 and 3156)\r\n\
+                     --{0}\r\n",
+                    boundary
+                ).into_bytes();
+                signed.extend_from_slice(entity);
+                signed.extend_from_slice(format!(
+                    "\r\n--{0}\r\n\
+                     Content-Type: application/pgp-signature; name=\"signature.asc\"\r\n\
+                     Content-Description: OpenPGP digital signature\r\n\
+                     Content-Disposition: attachment; filename=\"signature.asc\"\r\n\
+                     \r\n\
+                     {1}\r\n\
+                     --{0}--\r\n",
+                    boundary,
+                    String::from_utf8_lossy(&to_crlf(&signature)).trim_end()
+                ).as_bytes());
+                Ok(signed)
+            }
+        }
+    }
+    
+    async fn pgp_signature(&self, data: &[u8]) -> Result<Vec<u8>> {
+        let EmailSigner::Pgp { key_id, gpg_home } = self else {
+            return Err(anyhow!("not a PGP signer"));
+        };
+        
+        let mut command = gpg_command(gpg_home.as_deref());
+        command
+            .args(["--batch", "--yes", "--armor", "--detach-sign", "--digest-algo", "SHA256", "--local-user"])
+            .arg(key_id);
+        let output = run_gpg(command, data).await?;
+        if !output.status.success() {
+            return Err(anyhow!("gpg signing failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+        }
+        
+        Ok(output.stdout)
+    }
+}
+
+/// Normalizes line endings to CRLF as SMTP requires.
+fn to_crlf(data: &[u8]) -> Vec<u8> {
+    let mut normalized = Vec::with_capacity(data.len() + data.len() / 40);
+    for (index, byte) in data.iter().enumerate() {
+        if *byte == b'\n' && (index == 0 || data[index - 1] != b'\r') {
+            normalized.push(b'\r');
+        }
+        normalized.push(*byte);
+    }
+    normalized
+}
+
+/// Who signed a verified message. The `From:` header is not signed, so this
+/// is what a command email is authorized and answered by.
+pub struct Signer {
+    /// The certificate subject or the key fingerprint.
+    pub id: String,
+    /// The addresses in the certificate or the key's user IDs, lowercase.
+    pub addresses: Vec<String>,
+    /// When the message was signed, covered by the signature: the S/MIME
+    /// signingTime attribute or the PGP signature creation time.
+    pub signed_at: Option<DateTime<Utc>>,
+}
+
+/// Checks the signature of incoming command emails: S/MIME against trusted
+/// certificates, or PGP/MIME through the local `gpg` keyring.
+pub enum EmailVerifier {
+    Smime {
+        store: X509Store,
+        certificates: Stack<X509>,
+    },
+    Pgp {
+        gpg_home: Option<String>,
+        /// Accepted key fingerprints, never empty: every key in the keyring
+        /// would otherwise be trusted to run commands.
+        fingerprints: Vec<String>,
+    },
+}
+
+impl EmailVerifier {
+    pub fn from_config(config: &EmailCommandsConfig) -> Result<Self> {
+        match config.method.as_str() {
+            "smime" => {
+                let path = config.trusted_certificates.as_deref()
+                    .ok_or_else(|| anyhow!("S/MIME verification needs trusted_certificates"))?;
+                let trusted = X509::stack_from_pem(&fs::read(path)?)?;
+                if trusted.is_empty() {
+                    return Err(anyhow!("no certificates in {}", path));
+                }
+                
+                // Trust the admin certificates themselves, not only their CA
+                let mut store = X509StoreBuilder::new()?;
+                store.set_flags(X509VerifyFlags::PARTIAL_CHAIN)?;
+                let mut certificates = Stack::new()?;
+                for certificate in trusted {
+                    store.add_cert(certificate.clone())?;
+                    certificates.push(certificate)?;
+                }
+                Ok(EmailVerifier::Smime { store: store.build(), certificates })
+            }
+            "pgp" => {
+                let fingerprints: Vec<String> = config.trusted_keys.iter()
+                    .map(|fingerprint| fingerprint.replace(' ', "").to_uppercase())
+                    .filter(|fingerprint| !fingerprint.is_empty())
+                    .collect();
+                if fingerprints.is_empty() {
+                    return Err(anyhow!("PGP verification needs trusted_keys, the fingerprints of the admin keys"));
+                }
+                Ok(EmailVerifier::Pgp { gpg_home: config.gpg_home.clone(), fingerprints })
+            }
+            other => Err(anyhow!("unknown verification method '{}' (expected smime or pgp)", other)),
+        }
+    }
+    
+    /// Verifies an S/MIME message (headers and body) and returns the signed
+    /// MIME entity with its signer.
+    pub fn verify_smime(&self, message: &[u8]) -> Result<(Vec<u8>, Signer)> {
+        let EmailVerifier::Smime { store, certificates } = self else {
+            return Err(anyhow!("not an S/MIME verifier"));
+        };
+        
+        let (pkcs7, content) = Pkcs7::from_smime(message).map_err(|_| anyhow!("message is not S/MIME signed"))?;
+        let mut entity = Vec::new();
+        pkcs7.verify(certificates, store, content.as_deref(), Some(&mut entity), Pkcs7Flags::empty())
+            .map_err(|e| anyhow!("S/MIME signature is not valid: {}", e))?;
+        let signers = pkcs7.signers(certificates, Pkcs7Flags::empty())?;
+        let certificate = signers.iter().next().ok_or_else(|| anyhow!("S/MIME signature has no signer"))?;
+        let mut signer = certificate_signer(certificate);
+        signer.signed_at = pkcs7.to_der().ok().and_then(|der| smime_signing_time(&der));
+        Ok((entity, signer))
+    }
+    
+    /// Verifies the detached PGP `signature` over `entity` (the first part of
+    /// a `multipart/signed` message) and returns its signer.
+    pub async fn verify_pgp(&self, entity: &[u8], signature: &[u8]) -> Result<Signer> {
+        let EmailVerifier::Pgp { gpg_home, fingerprints } = self else {
+            return Err(anyhow!("not a PGP verifier"));
+        };
+        
+        let signature_path = std::env::temp_dir().join(format!("perfmon-{:016x}.asc", rand::random::<u64>()));
+        fs::write(&signature_path, signature)?;
+        let mut command = gpg_command(gpg_home.as_deref());
+        command
+            .args(["--batch", "--status-fd", "1", "--verify"])
+            .arg(&signature_path)
+            .arg("-");
+        let output = run_gpg(command, &to_crlf(entity)).await;
+        let _ = fs::remove_file(&signature_path);
+        let output = output?;
+        
+        let status = String::from_utf8_lossy(&output.stdout);
+        // VALIDSIG <signing key> ... <primary key>, either may be trusted
+        let keys: Vec<&str> = status.lines()
+            .find_map(|line| line.strip_prefix("[GNUPG:] VALIDSIG "))
+            .map(|rest| rest.split_whitespace().collect())
+            .filter(|keys: &Vec<&str>| output.status.success() && !keys.is_empty())
+            .ok_or_else(|| anyhow!("PGP signature is not valid: {}", String::from_utf8_lossy(&output.stderr).trim()))?;
+        let fingerprint = keys[0];
+        if ![Some(&fingerprint), keys.get(9)].into_iter().flatten().any(|key| fingerprints.iter().any(|trusted| trusted == key)) {
+            return Err(anyhow!("PGP key {} is not in trusted_keys", fingerprint));
+        }
+        Ok(Signer {
+            id: fingerprint.to_string(),
+            addresses: pgp_addresses(gpg_home.as_deref(), fingerprint).await?,
+            signed_at: keys.get(2).and_then(|timestamp| pgp_timestamp(timestamp)),
+        })
+    }
+}
+
+fn certificate_signer(certificate: &X509Ref) -> Signer {
+    let subject = certificate.subject_name().entries()
+        .filter_map(|entry| Some(format!("{}={}", entry.object().nid().short_name().ok()?, entry.data().to_string().ok()?)))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut addresses: Vec<String> = certificate.subject_alt_names().into_iter().flatten()
+        .filter_map(|name| name.email().map(str::to_lowercase))
+        .collect();
+    addresses.extend(certificate.subject_name().entries_by_nid(Nid::PKCS9_EMAILADDRESS)
+        .filter_map(|entry| Some(entry.data().to_string().ok()?.to_lowercase())));
+    addresses.sort();
+    addresses.dedup();
+    Signer { id: subject, addresses, signed_at: None }
+}
+
+/// The signingTime among the signed attributes of the first SignerInfo of a
+/// DER encoded PKCS#7 SignedData. Unsigned attributes and the certificates,
+/// which anyone can change, are never looked at.
+fn smime_signing_time(der: &[u8]) -> Option<DateTime<Utc>> {
+    // ContentInfo { contentType, [0] EXPLICIT SignedData }
+    let (_, content_info, _) = der_element(der).filter(|(tag, _, _)| *tag == 0x30)?;
+    let (_, explicit) = der_children(content_info).into_iter().find(|(tag, _)| *tag == 0xa0)?;
+    let (_, signed_data, _) = der_element(explicit).filter(|(tag, _, _)| *tag == 0x30)?;
+    // SignedData ends with the signerInfos SET
+    let (_, signer_infos) = der_children(signed_data).pop().filter(|(tag, _)| *tag == 0x31)?;
+    let (_, signer_info) = der_children(signer_infos).into_iter().next().filter(|(tag, _)| *tag == 0x30)?;
+    // signedAttrs is [0] IMPLICIT, unsignedAttrs [1]
+    let (_, attributes) = der_children(signer_info).into_iter().find(|(tag, _)| *tag == 0xa0)?;
+    der_children(attributes).into_iter()
+        .filter_map(|(_, attribute)| match der_children(attribute).as_slice() {
+            [(0x06, oid), (0x31, values)] if *oid == SIGNING_TIME_OID => der_children(values).into_iter().next(),
+            _ => None,
+        })
+        .find_map(|(tag, time)| {
+            let time = std::str::from_utf8(time).ok()?;
+            let format = match tag {
+                0x17 => "%y%m%d%H%M%SZ",
+                0x18 => "%Y%m%d%H%M%SZ",
+                _ => return None,
+            };
+            Some(NaiveDateTime::parse_from_str(time, format).ok()?.and_utc())
+        })
+}
+
+/// The first DER element of `data`: its tag, contents and the bytes after it.
+fn der_element(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&first, rest) = rest.split_first()?;
+    let (length, rest) = if first < 0x80 {
+        (first as usize, rest)
+    } else {
+        let count = (first & 0x7f) as usize;
+        if count == 0 || count > 4 || rest.len() < count {
+            return None;
+        }
+        (rest[..count].iter().fold(0, |length, byte| length << 8 | *byte as usize), &rest[count..])
+    };
+    (rest.len() >= length).then(|| (tag, &rest[..length], &rest[length..]))
+}
+
+/// The tags and contents of the elements in a constructed DER value.
+fn der_children(mut data: &[u8]) -> Vec<(u8, &[u8])> {
+    let mut children = Vec::new();
+    while let Some((tag, contents, rest)) = der_element(data) {
+        children.push((tag, contents));
+        data = rest;
+    }
+    children
+}
+
+/// The signature timestamp of a `VALIDSIG` status line, seconds since the
+/// epoch or ISO 8601 (`20260102T030405`) depending on the gpg version.
+fn pgp_timestamp(timestamp: &str) -> Option<DateTime<Utc>> {
+    match timestamp.parse::<i64>() {
+        Ok(seconds) => DateTime::from_timestamp(seconds, 0),
+        Err(_) => NaiveDateTime::parse_from_str(timestamp, "%Y%m%dT%H%M%S").ok().map(|time| time.and_utc()),
+    }
+}
+
+/// The addresses in the valid user IDs of the key with `fingerprint`.
+async fn pgp_addresses(gpg_home: Option<&str>, fingerprint: &str) -> Result<Vec<String>> {
+    let mut command = gpg_command(gpg_home);
+    command
+        .args(["--batch", "--with-colons", "--fixed-list-mode", "--list-keys"])
+        .arg(fingerprint);
+    let output = run_gpg(command, &[]).await?;
+    if !output.status.success() {
+        return Err(anyhow!("gpg cannot list key {}: {}", fingerprint, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    
+    // uid:<validity>:...:<user id>, revoked, expired and invalid ones skipped
+    Ok(String::from_utf8_lossy(&output.stdout).lines()
+        .map(|line| line.split(':').collect::<Vec<_>>())
+        .filter(|fields| fields.first() == Some(&"uid") && !matches!(fields.get(1), Some(&("r" | "e" | "i"))))
+        .filter_map(|fields| {
+            let user_id = fields.get(9)?.replace("\\x3a", ":");
+            let (start, end) = (user_id.rfind('<')?, user_id.rfind('>')?);
+            (start < end).then(|| user_id[start + 1..end].trim().to_lowercase())
+        })
+        .collect())
+}
+fn gpg_command(gpg_home: Option<&str>) -> Command {
+    let mut command = Command::new("gpg");
+    if let Some(home) = gpg_home {
+        command.arg("--homedir").arg(home);
+    }
+    command
+}
+
+/// Runs `gpg` with `input` on stdin, killing it after [`GPG_TIMEOUT`] so a
+/// hung agent cannot hold up alert delivery or the command inbox.
+async fn run_gpg(mut command: Command, input: &[u8]) -> Result<Output> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| anyhow!("failed to run gpg: {}", e))?;
+    
+    let run = async {
+        if let Some(mut stdin) = child.stdin.take() {
+            stdin.write_all(input).await?;
+        }
+        Ok::<_, anyhow::Error>(child.wait_with_output().await?)
+    };
+    tokio::time::timeout(GPG_TIMEOUT, run).await
+        .map_err(|_| anyhow!("gpg did not finish within {}s", GPG_TIMEOUT.as_secs()))?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::asn1::Asn1Time;
+    use openssl::hash::MessageDigest;
+    use openssl::rsa::Rsa;
+    use openssl::x509::X509NameBuilder;
+    
+    fn self_signed() -> (X509, PKey<Private>) {
+        let key = PKey::from_rsa(Rsa::generate(2048).unwrap()).unwrap();
+        let mut name = X509NameBuilder::new().unwrap();
+        name.append_entry_by_nid(Nid::COMMONNAME, "ops").unwrap();
+        let name = name.build();
+        let mut builder = X509::builder().unwrap();
+        builder.set_version(2).unwrap();
+        builder.set_subject_name(&name).unwrap();
+        builder.set_issuer_name(&name).unwrap();
+        builder.set_pubkey(&key).unwrap();
+        builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+        builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+        builder.sign(&key, MessageDigest::sha256()).unwrap();
+        (builder.build(), key)
+    }
+    
+    #[test]
+    fn reads_the_smime_signing_time() {
+        let (certificate, key) = self_signed();
+        let before = Utc::now() - chrono::Duration::seconds(1);
+        let pkcs7 = Pkcs7::sign(&certificate, &key, &Stack::new().unwrap(), b"STATUS\r\n", Pkcs7Flags::DETACHED).unwrap();
+        let signed_at = smime_signing_time(&pkcs7.to_der().unwrap()).unwrap();
+        assert!(signed_at >= before && signed_at <= Utc::now());
+    }
+    
+    #[test]
+    fn no_signing_time_without_signed_attributes() {
+        let (certificate, key) = self_signed();
+        let flags = Pkcs7Flags::DETACHED | Pkcs7Flags::NOATTR;
+        let pkcs7 = Pkcs7::sign(&certificate, &key, &Stack::new().unwrap(), b"STATUS\r\n", flags).unwrap();
+        assert_eq!(smime_signing_time(&pkcs7.to_der().unwrap()), None);
+        assert_eq!(smime_signing_time(b"\x30\x84\xff\xff\xff\xff"), None);
+        assert_eq!(smime_signing_time(b""), None);
+    }
+    
+    #[test]
+    fn parses_both_gpg_timestamp_formats() {
+        let expected = DateTime::from_timestamp(1_767_323_045, 0);
+        assert_eq!(pgp_timestamp("1767323045"), expected);
+        assert_eq!(pgp_timestamp("20260102T030405"), expected);
+        assert_eq!(pgp_timestamp("yesterday"), None);
+    }
+}
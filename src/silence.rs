@@ -0,0 +1,120 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use glob::Pattern;
+use std::sync::Mutex;
+use crate::alert::Alert;
+use crate::audit::{self, AuditAction, AuditEntry};
+use anyhow::{Result, anyhow};
+
+/// Mutes notifications of one rule (or `*` for every rule) until it expires.
+/// With `container` set only alerts naming a matching container are muted.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Silence {
+    pub id: String,
+    pub rule: String,
+    /// Glob matched against the alert's container names.
+    #[serde(default)]
+    pub container: Option<String>,
+    #[serde(default)]
+    pub comment: String,
+    #[serde(default)]
+    pub created_by: String,
+    pub starts_at: DateTime<Utc>,
+    pub ends_at: DateTime<Utc>,
+}
+
+impl Silence {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.starts_at <= now && now < self.ends_at
+    }
+    
+    fn matches(&self, alert: &Alert) -> bool {
+        if self.rule != "*" && self.rule != alert.rule {
+            return false;
+        }
+        match self.container.as_deref().map(Pattern::new) {
+            None => true,
+            Some(Ok(pattern)) => alert.containers.iter().any(|container| pattern.matches(&container.name)),
+            Some(Err(_)) => false,
+        }
+    }
+}
+
+/// Silences shared between the monitor loop and the APIs that create them.
+/// The monitor copies them into the state file so they survive restarts.
+/// Silences from the maintenance calendar are kept apart and replaced on
+/// every calendar refresh instead.
+#[derive(Default)]
+pub struct Silences {
+    silences: Mutex<Vec<Silence>>,
+    calendar: Mutex<Vec<Silence>>,
+}
+
+impl Silences {
+    pub fn load(&self, silences: Vec<Silence>) {
+        *self.silences.lock().unwrap() = silences;
+    }
+    
+    pub fn create(&self, rule: &str, container: Option<String>, duration: Duration, comment: String, created_by: String) -> Result<Silence> {
+        if rule.trim().is_empty() {
+            return Err(anyhow!("Silence needs a rule, or '*' for every rule"));
+        }
+        if duration <= Duration::zero() {
+            return Err(anyhow!("Silence duration must be positive"));
+        }
+        if let Some(container) = &container {
+            Pattern::new(container).map_err(|e| anyhow!("Invalid container pattern '{}': {}", container, e))?;
+        }
+        
+        let now = Utc::now();
+        let silence = Silence {
+            id: format!("{:016x}", rand::random::<u64>()),
+            rule: rule.trim().to_string(),
+            container,
+            comment,
+            created_by,
+            starts_at: now,
+            ends_at: now + duration,
+        };
+        self.silences.lock().unwrap().push(silence.clone());
+        audit::record(AuditEntry::new(
+            AuditAction::Silence,
+            &silence.created_by,
+            &silence.rule,
+            format!(
+                "silenced{} until {}{}",
+                silence.container.as_ref().map(|container| format!(" containers {}", container)).unwrap_or_default(),
+                silence.ends_at.to_rfc3339(),
+                if silence.comment.is_empty() { String::new() } else { format!(": {}", silence.comment) }
+            ),
+        ));
+        Ok(silence)
+    }
+    
+    /// Replaces the silences of maintenance calendar events.
+    pub fn load_calendar(&self, silences: Vec<Silence>) {
+        *self.calendar.lock().unwrap() = silences;
+    }
+    
+    pub fn active(&self, now: DateTime<Utc>) -> Vec<Silence> {
+        let silences = self.silences.lock().unwrap();
+        let calendar = self.calendar.lock().unwrap();
+        silences.iter().chain(calendar.iter()).filter(|silence| silence.is_active(now)).cloned().collect()
+    }
+    
+    /// Active silence muting `alert`, if any.
+    pub fn matching(&self, alert: &Alert, now: DateTime<Utc>) -> Option<Silence> {
+        let silences = self.silences.lock().unwrap();
+        let calendar = self.calendar.lock().unwrap();
+        silences.iter().chain(calendar.iter())
+            .find(|silence| silence.is_active(now) && silence.matches(alert))
+            .cloned()
+    }
+    
+    /// Drops expired silences and returns the rest for the state file.
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Vec<Silence> {
+        let mut silences = self.silences.lock().unwrap();
+        silences.retain(|silence| silence.ends_at > now);
+        silences.clone()
+    }
+}
\ No newline at end of file
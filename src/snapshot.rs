@@ -0,0 +1,48 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use crate::alert::CheckOutcome;
+use crate::cloud_metadata::CloudMetadata;
+use crate::dispatch::DispatchStats;
+use crate::docker_monitor::ContainerStats;
+use crate::incident::RuleState;
+use crate::server_monitor::ServerStats;
+use anyhow::{Result, anyhow};
+
+/// The last completed cycle, written to `monitoring.snapshot_file` for
+/// scripts and MOTD generators that should not need the API.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub timestamp: DateTime<Utc>,
+    pub hostname: String,
+    pub version: String,
+    pub cycle: u64,
+    pub duration_ms: u64,
+    pub outcome: CheckOutcome,
+    pub server: Option<ServerStats>,
+    pub cloud: Option<CloudMetadata>,
+    pub containers: Vec<ContainerStats>,
+    /// Rules firing or flapping, sorted.
+    pub firing: Vec<String>,
+    pub incidents: HashMap<String, RuleState>,
+    pub dispatch: DispatchStats,
+}
+
+/// Writes the snapshot to a temporary file next to `path` and renames it
+/// into place, so readers never see a partial file.
+pub fn write(path: &str, snapshot: &Snapshot) -> Result<()> {
+    if let Some(parent) = Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        fs::create_dir_all(parent)?;
+    }
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, serde_json::to_string_pretty(snapshot)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+pub fn read(path: &str) -> Result<Snapshot> {
+    let content = fs::read_to_string(path).map_err(|e| anyhow!("cannot read {}: {}", path, e))?;
+    serde_json::from_str(&content).map_err(|e| anyhow!("invalid snapshot {}: {}", path, e))
+}
\ No newline at end of file
@@ -0,0 +1,208 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use std::collections::HashMap;
+use std::path::Path;
+use log::{error, info, warn};
+use crate::api::HandledCommand;
+use crate::config::Config;
+use crate::fingerprint::ServiceFingerprint;
+use crate::history::History;
+use crate::incident::RuleState;
+use crate::inventory::{InventoryChange, InventoryEntry};
+use crate::metrics::RuntimeMetricsSnapshot;
+use crate::silence::Silence;
+use crate::statuspage::StatusHistory;
+use crate::storage::{self, FileStorage, Storage};
+use crate::thresholds::ThresholdOverride;
+use crate::trend::TrendSamples;
+
+/// Percent histogram with one bucket per whole percent (0..=100).
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UtilizationHistogram {
+    pub since: DateTime<Utc>,
+    pub cpu: Vec<u64>,
+    pub memory: Vec<u64>,
+}
+
+impl Default for UtilizationHistogram {
+    fn default() -> Self {
+        Self {
+            since: Utc::now(),
+            cpu: vec![0; 101],
+            memory: vec![0; 101],
+        }
+    }
+}
+
+impl UtilizationHistogram {
+    pub fn record(&mut self, cpu_percent: f64, memory_percent: f64) {
+        let bucket = |value: f64| value.round().clamp(0.0, 100.0) as usize;
+        self.cpu[bucket(cpu_percent)] += 1;
+        self.memory[bucket(memory_percent)] += 1;
+    }
+    
+    pub fn samples(&self) -> u64 {
+        self.cpu.iter().sum()
+    }
+    
+    pub fn cpu_percentile(&self, percentile: f64) -> f64 {
+        Self::percentile(&self.cpu, percentile)
+    }
+    
+    pub fn memory_percentile(&self, percentile: f64) -> f64 {
+        Self::percentile(&self.memory, percentile)
+    }
+    
+    fn percentile(buckets: &[u64], percentile: f64) -> f64 {
+        let total: u64 = buckets.iter().sum();
+        if total == 0 {
+            return 0.0;
+        }
+        
+        let rank = (total as f64 * percentile / 100.0).ceil() as u64;
+        let mut seen = 0;
+        for (value, count) in buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return value as f64;
+            }
+        }
+        100.0
+    }
+}
+
+/// Monitor state persisted between runs.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct MonitorState {
+    #[serde(default)]
+    pub last_cycle: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub digest_last_sent: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub cost_report_last_sent: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub utilization: UtilizationHistogram,
+    #[serde(default)]
+    pub scaling_streaks: HashMap<String, u32>,
+    #[serde(default)]
+    pub scaling_last_triggered: HashMap<String, DateTime<Utc>>,
+    #[serde(default)]
+    pub runtime_metrics: RuntimeMetricsSnapshot,
+    #[serde(default)]
+    pub incidents: HashMap<String, RuleState>,
+    /// Running containers seen in the last cycle, `None` before the first one.
+    #[serde(default)]
+    pub inventory: Option<HashMap<String, InventoryEntry>>,
+    /// Inventory changes not yet reported in a digest.
+    #[serde(default)]
+    pub inventory_changes: Vec<InventoryChange>,
+    /// Last known fingerprint per configured service name.
+    #[serde(default)]
+    pub fingerprints: HashMap<String, ServiceFingerprint>,
+    #[serde(default)]
+    pub history: History,
+    /// Last time each scheduled report was sent (or first scheduled), by name.
+    #[serde(default)]
+    pub reports_last_sent: HashMap<String, DateTime<Utc>>,
+    #[serde(default)]
+    pub status_page: StatusHistory,
+    /// Samples of the metrics watched by trend rules.
+    #[serde(default)]
+    pub trend_samples: TrendSamples,
+    /// Silences created through the APIs that have not expired yet.
+    #[serde(default)]
+    pub silences: Vec<Silence>,
+    /// Runtime threshold changes that are not in the config file.
+    #[serde(default)]
+    pub thresholds: Vec<ThresholdOverride>,
+    /// The signed command emails run within `email_commands.max_age`, so
+    /// none runs twice.
+    #[serde(default)]
+    pub recent_commands: Vec<HandledCommand>,
+}
+
+/// The monitor state and the `storage` backend it is saved to.
+pub struct StateStore {
+    storage: Option<Box<dyn Storage>>,
+    pub state: MonitorState,
+}
+
+impl StateStore {
+    /// Loads the state from the configured backend. A database backend that
+    /// is still empty imports `monitoring.state_file` once, so upgrading
+    /// keeps incidents and history.
+    pub fn open(config: &Config) -> Self {
+        let mut storage = match storage::open(config) {
+            Ok(storage) => storage,
+            Err(e) => {
+                error!("Failed to open {} storage: {:#}. Starting with empty state that is not saved.", config.storage.backend, e);
+                None
+            }
+        };
+        let Some(backend) = storage.as_mut() else {
+            return Self { storage, state: MonitorState::default() };
+        };
+        
+        let state = match backend.load() {
+            Ok(Some(state)) => state,
+            Ok(None) => Self::import(config, backend.as_mut()).unwrap_or_default(),
+            Err(e) if config.storage.backend == "file" => {
+                warn!("Failed to read state from {}: {}. Starting with empty state.", backend.describe(), e);
+                MonitorState::default()
+            }
+            Err(e) => {
+                // Saving over a state that could not be read would lose it for every replica
+                error!("Failed to read state from {}: {:#}. Starting with empty state that is not saved.", backend.describe(), e);
+                return Self { storage: None, state: MonitorState::default() };
+            }
+        };
+        Self { storage, state }
+    }
+    
+    /// The JSON state file written before `storage` had a database backend.
+    fn import(config: &Config, backend: &mut dyn Storage) -> Option<MonitorState> {
+        let path = &config.monitoring.state_file;
+        if config.storage.backend == "file" || path.is_empty() || !Path::new(path).exists() {
+            return None;
+        }
+        let state = match FileStorage::new(path).load() {
+            Ok(state) => state?,
+            Err(e) => {
+                warn!("Failed to import state file {}: {}", path, e);
+                return None;
+            }
+        };
+        match backend.save(&state) {
+            Ok(()) => info!("Imported state file {} into {}", path, backend.describe()),
+            Err(e) => error!("Failed to import state file {} into {}: {}", path, backend.describe(), e),
+        }
+        Some(state)
+    }
+    
+    /// Reads the state again, for a replica taking over from the leader that
+    /// saved it. The current state stays when the read fails.
+    pub fn reload(&mut self) {
+        let Some(storage) = self.storage.as_mut() else {
+            return;
+        };
+        match storage.load() {
+            Ok(Some(state)) => self.state = state,
+            Ok(None) => {}
+            Err(e) => error!("Failed to reload state from {}: {:#}", storage.describe(), e),
+        }
+    }
+    
+    /// Where the state is saved, or why it is not.
+    pub fn describe(&self) -> String {
+        self.storage.as_ref().map_or_else(|| "nowhere (persistence off)".to_string(), |storage| storage.describe())
+    }
+    
+    pub fn save(&mut self) {
+        let Some(storage) = self.storage.as_mut() else {
+            return;
+        };
+        if let Err(e) = storage.save(&self.state) {
+            error!("Failed to save state to {}: {:#}", storage.describe(), e);
+        }
+    }
+}
\ No newline at end of file
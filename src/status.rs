@@ -0,0 +1,493 @@
+use clap::ArgMatches;
+use comfy_table::{presets, Cell, CellAlignment, ContentArrangement, Table};
+use crate::cloud_metadata::CloudMetadata;
+use crate::docker_monitor::{ContainerStats, DockerSystemInfo};
+use crate::libvirt::VmStats;
+use crate::server_monitor::ServerStats;
+use crate::timezone::Timezone;
+use crate::units;
+use anyhow::{Result, anyhow};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SortKey {
+    Cpu,
+    Memory,
+    Name,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum FilterOp {
+    Contains,
+    NotContains,
+    Equals,
+    NotEquals,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Column {
+    Name,
+    Id,
+    Image,
+    Status,
+    Cpu,
+    Memory,
+    MemoryPercent,
+    Limits,
+}
+
+impl Column {
+    pub const DEFAULT: [Column; 4] = [Column::Name, Column::Cpu, Column::Memory, Column::Limits];
+    
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim() {
+            "name" => Ok(Column::Name),
+            "id" => Ok(Column::Id),
+            "image" => Ok(Column::Image),
+            "status" => Ok(Column::Status),
+            "cpu" => Ok(Column::Cpu),
+            "memory" => Ok(Column::Memory),
+            "memory_percent" => Ok(Column::MemoryPercent),
+            "limits" => Ok(Column::Limits),
+            other => Err(anyhow!(
+                "Unknown column '{}'. Available: name, id, image, status, cpu, memory, memory_percent, limits",
+                other
+            )),
+        }
+    }
+    
+    fn header(&self) -> &'static str {
+        match self {
+            Column::Name => "Name",
+            Column::Id => "ID",
+            Column::Image => "Image",
+            Column::Status => "Status",
+            Column::Cpu => "CPU",
+            Column::Memory => "Memory",
+            Column::MemoryPercent => "Memory %",
+            Column::Limits => "Limits",
+        }
+    }
+    
+    fn cell(&self, container: &ContainerStats) -> Cell {
+        match self {
+            Column::Name => Cell::new(&container.name),
+            Column::Id => Cell::new(&container.id),
+            Column::Image => Cell::new(&container.image),
+            Column::Status => Cell::new(&container.status),
+            Column::Cpu => Cell::new(format!("{:.2}%", container.cpu_usage)).set_alignment(CellAlignment::Right),
+            Column::Memory => Cell::new(units::bytes(container.memory_usage as f64))
+                .set_alignment(CellAlignment::Right),
+            Column::MemoryPercent => Cell::new(format!("{:.2}%", container.memory_percent))
+                .set_alignment(CellAlignment::Right),
+            Column::Limits => Cell::new(container.limits_summary()),
+        }
+    }
+}
+
+/// A container filter such as `name~web`, `image=nginx` or `label.team!=ops`.
+///
+/// Terms combine with `,` (and) and `|` (or), and a leading `!` negates a
+/// single term. `!` binds tightest and `,` binds tighter than `|`, so
+/// `!name~a,image=b|status=exited` reads as
+/// `((not name~a) and image=b) or status=exited`. Empty terms are rejected.
+#[derive(Debug, Clone)]
+pub struct ContainerFilter {
+    /// Alternatives joined by `|`, each a list of terms joined by `,`.
+    any: Vec<Vec<FilterTerm>>,
+}
+
+#[derive(Debug, Clone)]
+struct FilterTerm {
+    negated: bool,
+    field: String,
+    op: FilterOp,
+    value: String,
+}
+
+impl ContainerFilter {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let any = expression.split('|')
+            .map(|group| group.split(',').map(|term| FilterTerm::parse(term, expression)).collect::<Result<Vec<_>>>())
+            .collect::<Result<Vec<_>>>()?;
+        
+        Ok(Self { any })
+    }
+    
+    pub fn matches(&self, container: &ContainerStats) -> bool {
+        self.any.iter().any(|all| all.iter().all(|term| term.matches(container)))
+    }
+}
+
+impl FilterTerm {
+    fn parse(term: &str, expression: &str) -> Result<Self> {
+        let term = term.trim();
+        let (negated, term) = match term.strip_prefix('!') {
+            Some(rest) => (true, rest.trim_start()),
+            None => (false, term),
+        };
+        
+        // Longest operators first so `!~` is not read as `~`
+        for (token, op) in [("!~", FilterOp::NotContains), ("!=", FilterOp::NotEquals), ("~", FilterOp::Contains), ("=", FilterOp::Equals)] {
+            if let Some((field, value)) = term.split_once(token) {
+                let field = field.trim();
+                if field.is_empty() {
+                    break;
+                }
+                return Ok(Self {
+                    negated,
+                    field: field.to_string(),
+                    op,
+                    value: value.trim().to_string(),
+                });
+            }
+        }
+        
+        if term.is_empty() {
+            return Err(anyhow!("Invalid filter '{}'. Empty term around ',' or '|'", expression));
+        }
+        Err(anyhow!("Invalid filter '{}'. Expected FIELD~VALUE, FIELD=VALUE, FIELD!~VALUE or FIELD!=VALUE", expression))
+    }
+    
+    fn field_value<'a>(&self, container: &'a ContainerStats) -> Option<&'a str> {
+        match self.field.as_str() {
+            "name" => Some(&container.name),
+            "image" => Some(&container.image),
+            "status" => Some(&container.status),
+            "id" => Some(&container.id),
+            field => field.strip_prefix("label.")
+                .and_then(|label| container.labels.get(label))
+                .map(|value| value.as_str()),
+        }
+    }
+    
+    fn matches(&self, container: &ContainerStats) -> bool {
+        let value = self.field_value(container);
+        let matched = match self.op {
+            FilterOp::Contains => value.is_some_and(|v| v.contains(&self.value)),
+            FilterOp::NotContains => !value.is_some_and(|v| v.contains(&self.value)),
+            FilterOp::Equals => value == Some(self.value.as_str()),
+            FilterOp::NotEquals => value != Some(self.value.as_str()),
+        };
+        matched != self.negated
+    }
+}
+
+/// Options controlling what `--status` and `watch` print.
+#[derive(Debug, Clone)]
+pub struct StatusOptions {
+    pub sort: SortKey,
+    pub filters: Vec<ContainerFilter>,
+    pub top: usize,
+    pub show_host: bool,
+    pub show_containers: bool,
+    pub columns: Vec<Column>,
+    pub ascii: bool,
+    pub emoji: bool,
+    /// From `--timezone`, else the configured `timezone`.
+    pub timezone: Option<Timezone>,
+}
+
+impl Default for StatusOptions {
+    fn default() -> Self {
+        Self {
+            sort: SortKey::Cpu,
+            filters: Vec::new(),
+            top: 5,
+            show_host: true,
+            show_containers: true,
+            columns: Column::DEFAULT.to_vec(),
+            ascii: false,
+            emoji: true,
+            timezone: None,
+        }
+    }
+}
+
+impl StatusOptions {
+    pub fn from_matches(matches: &ArgMatches) -> Result<Self> {
+        let sort = match matches.get_one::<String>("sort").map(|s| s.as_str()) {
+            Some("memory") => SortKey::Memory,
+            Some("name") => SortKey::Name,
+            _ => SortKey::Cpu,
+        };
+        
+        let filters = matches.get_many::<String>("filter")
+            .map(|values| values.map(|value| ContainerFilter::parse(value)).collect::<Result<Vec<_>>>())
+            .transpose()?
+            .unwrap_or_default();
+        
+        let columns = match matches.get_one::<String>("columns") {
+            Some(columns) => columns.split(',').map(Column::parse).collect::<Result<Vec<_>>>()?,
+            None => Column::DEFAULT.to_vec(),
+        };
+        
+        Ok(Self {
+            sort,
+            filters,
+            top: *matches.get_one::<usize>("top").unwrap_or(&5),
+            show_host: !matches.get_flag("containers-only"),
+            show_containers: !matches.get_flag("host-only"),
+            columns,
+            ascii: matches.get_flag("ascii"),
+            emoji: !matches.get_flag("no-emoji"),
+            timezone: matches.get_one::<String>("timezone").map(|name| Timezone::parse(name)).transpose()?,
+        })
+    }
+    
+    /// Filters, sorts and truncates the container list for display.
+    pub fn apply(&self, containers: &[ContainerStats]) -> Vec<ContainerStats> {
+        let mut selected: Vec<ContainerStats> = containers.iter()
+            .filter(|container| self.filters.iter().all(|filter| filter.matches(container)))
+            .cloned()
+            .collect();
+        
+        match self.sort {
+            SortKey::Cpu => selected.sort_by(|a, b| b.cpu_usage.total_cmp(&a.cpu_usage)),
+            SortKey::Memory => selected.sort_by_key(|c| std::cmp::Reverse(c.memory_usage)),
+            SortKey::Name => selected.sort_by(|a, b| a.name.cmp(&b.name)),
+        }
+        
+        selected.truncate(self.top);
+        selected
+    }
+}
+
+/// Everything the status view shows, collected before rendering.
+pub struct StatusSnapshot<'a> {
+    pub server: &'a ServerStats,
+    pub cloud: Option<&'a CloudMetadata>,
+    pub containers: &'a [ContainerStats],
+    pub docker_info: &'a DockerSystemInfo,
+    /// LXD instances, `None` when the LXD collector is disabled.
+    pub lxd: Option<&'a [ContainerStats]>,
+    /// Libvirt guests, `None` when the libvirt collector is disabled.
+    pub vms: Option<&'a [VmStats]>,
+}
+
+impl StatusOptions {
+    fn new_table(&self) -> Table {
+        let mut table = Table::new();
+        table
+            .load_preset(if self.ascii { presets::ASCII_FULL } else { presets::UTF8_FULL })
+            .set_content_arrangement(ContentArrangement::Dynamic);
+        table
+    }
+    
+    fn heading(&self, emoji: &str, title: &str) -> String {
+        if self.emoji {
+            format!("{} {}", emoji, title)
+        } else {
+            title.to_string()
+        }
+    }
+    
+    pub fn render(&self, snapshot: &StatusSnapshot) -> String {
+        let mut output = format!(
+            "SYSTEM STATUS - {}\n",
+            self.timezone.clone().unwrap_or_default().format(snapshot.server.timestamp)
+        );
+        
+        if self.show_host {
+            let server = snapshot.server;
+            let mut table = self.new_table();
+            table.set_header(vec!["Metric", "Value"]);
+            table.add_row(vec!["Hostname".to_string(), server.system_info.hostname.clone()]);
+            if let Some(metadata) = snapshot.cloud {
+                table.add_row(vec![
+                    "Instance".to_string(),
+                    format!("{} ({}, {}, {})", metadata.instance_id, metadata.provider, metadata.region, metadata.instance_type),
+                ]);
+            }
+            table.add_row(vec!["CPU Usage".to_string(), format!("{:.2}%", server.cpu_usage)]);
+            if let Some(busiest) = server.cpu_cores.iter().max_by(|a, b| a.usage.total_cmp(&b.usage)) {
+                table.add_row(vec![
+                    "Busiest Core".to_string(),
+                    format!("{} {:.2}% ({} cores)", busiest.name, busiest.usage, server.cpu_cores.len()),
+                ]);
+            }
+            table.add_row(vec![
+                "Memory Usage".to_string(),
+                format!("{:.2}% ({} of {})", server.memory_usage.percent,
+                        units::bytes(server.memory_usage.used as f64), units::bytes(server.memory_usage.total as f64)),
+            ]);
+            table.add_row(vec![
+                "Swap Usage".to_string(),
+                if server.swap_usage.total == 0 {
+                    "no swap".to_string()
+                } else {
+                    format!("{:.2}% ({} of {})", server.swap_usage.percent,
+                            units::bytes(server.swap_usage.used as f64), units::bytes(server.swap_usage.total as f64))
+                },
+            ]);
+            table.add_row(vec![
+                "Disk Usage".to_string(),
+                format!("{:.2}% ({} of {})", server.disk_usage.percent,
+                        units::bytes(server.disk_usage.used as f64), units::bytes(server.disk_usage.total as f64)),
+            ]);
+            
+            output.push_str(&format!("\n{}\n{}\n", self.heading("🖥️ ", "SERVER"), table));
+            
+            if !server.mounts.is_empty() {
+                let mut table = self.new_table();
+                table.set_header(vec!["Mount", "Device", "Type", "Used", "Size", "Usage"]);
+                for (mount_point, mount) in &server.mounts {
+                    table.add_row(vec![
+                        Cell::new(mount_point),
+                        Cell::new(&mount.device),
+                        Cell::new(&mount.file_system),
+                        Cell::new(units::bytes(mount.used as f64)).set_alignment(CellAlignment::Right),
+                        Cell::new(units::bytes(mount.total as f64)).set_alignment(CellAlignment::Right),
+                        Cell::new(format!("{:.1}%", mount.percent)).set_alignment(CellAlignment::Right),
+                    ]);
+                }
+                output.push_str(&format!("\n{}\n{}\n", self.heading("💾", "FILESYSTEMS"), table));
+            }
+            
+            if !server.network.is_empty() {
+                let mbps = |rate: Option<f64>| rate.map_or("-".to_string(), |rate| format!("{:.2} Mbit/s", rate));
+                let mut table = self.new_table();
+                table.set_header(vec!["Interface", "RX", "TX", "RX Rate", "TX Rate", "Packets (RX/TX)", "Errors (RX/TX)"]);
+                for stats in &server.network {
+                    table.add_row(vec![
+                        Cell::new(&stats.interface),
+                        Cell::new(units::bytes(stats.rx_bytes as f64)).set_alignment(CellAlignment::Right),
+                        Cell::new(units::bytes(stats.tx_bytes as f64)).set_alignment(CellAlignment::Right),
+                        Cell::new(mbps(stats.rx_mbps())).set_alignment(CellAlignment::Right),
+                        Cell::new(mbps(stats.tx_mbps())).set_alignment(CellAlignment::Right),
+                        Cell::new(format!("{}/{}", stats.rx_packets, stats.tx_packets)).set_alignment(CellAlignment::Right),
+                        Cell::new(format!("{}/{}", stats.rx_errors, stats.tx_errors)).set_alignment(CellAlignment::Right),
+                    ]);
+                }
+                output.push_str(&format!("\n{}\n{}\n", self.heading("🌐", "NETWORK"), table));
+            }
+        }
+        
+        if self.show_containers {
+            let containers = snapshot.containers;
+            let unlimited = containers.iter().filter(|container| !container.has_limits()).count();
+            output.push_str(&format!(
+                "\n{}\nRunning: {}  Total: {}  Without limits: {}\n",
+                self.heading("🐳", "DOCKER"),
+                containers.len(),
+                snapshot.docker_info.containers,
+                unlimited
+            ));
+            
+            let selected = self.apply(containers);
+            if !selected.is_empty() {
+                let mut table = self.new_table();
+                table.set_header(self.columns.iter().map(|column| column.header()));
+                for container in &selected {
+                    table.add_row(self.columns.iter().map(|column| column.cell(container)));
+                }
+                output.push_str(&format!("{}\n", table));
+            } else if !self.filters.is_empty() {
+                output.push_str("No containers match the given filters.\n");
+            }
+        }
+        
+        if let (true, Some(instances)) = (self.show_containers, snapshot.lxd) {
+            output.push_str(&format!("\n{}\nRunning: {}\n", self.heading("📦", "LXD"), instances.len()));
+            
+            let selected = self.apply(instances);
+            if !selected.is_empty() {
+                let mut table = self.new_table();
+                table.set_header(self.columns.iter().map(|column| column.header()));
+                for instance in &selected {
+                    table.add_row(self.columns.iter().map(|column| column.cell(instance)));
+                }
+                output.push_str(&format!("{}\n", table));
+            }
+        }
+        
+        if let (true, Some(vms)) = (self.show_containers, snapshot.vms) {
+            let running = vms.iter().filter(|vm| vm.is_running()).count();
+            output.push_str(&format!(
+                "\n{}\nRunning: {}  Defined: {}\n",
+                self.heading("💻", "VIRTUAL MACHINES"),
+                running,
+                vms.len()
+            ));
+            
+            if !vms.is_empty() {
+                let mut table = self.new_table();
+                table.set_header(vec!["Name", "State", "vCPUs", "CPU", "Memory"]);
+                for vm in vms {
+                    table.add_row(vec![
+                        vm.name.clone(),
+                        vm.state.clone(),
+                        vm.vcpus.to_string(),
+                        vm.cpu_percent.map(|cpu| format!("{:.2}%", cpu)).unwrap_or_else(|| "-".to_string()),
+                        format!("{}/{} ({:.1}%)", units::mebibytes(vm.memory_used_mb), units::mebibytes(vm.memory_assigned_mb), vm.memory_percent),
+                    ]);
+                }
+                output.push_str(&format!("{}\n", table));
+            }
+        }
+        
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    
+    fn container(name: &str, image: &str, status: &str) -> ContainerStats {
+        ContainerStats {
+            status: status.to_string(),
+            labels: HashMap::from([("team".to_string(), "ops".to_string())]),
+            ..ContainerStats::for_test(name, image)
+        }
+    }
+    
+    fn matching(expression: &str, containers: &[ContainerStats]) -> Vec<String> {
+        let filter = ContainerFilter::parse(expression).unwrap();
+        containers.iter()
+            .filter(|container| filter.matches(container))
+            .map(|container| container.name.clone())
+            .collect()
+    }
+    
+    #[test]
+    fn negation_applies_to_a_single_term() {
+        let containers = [
+            container("web-1", "nginx", "running"),
+            container("web-2", "redis", "running"),
+            container("api", "nginx", "running"),
+        ];
+        
+        assert_eq!(matching("!name~web", &containers), ["api"]);
+        // `!` binds only to `name~web`, not to the whole `,` group
+        assert_eq!(matching("!name~web,image=nginx", &containers), ["api"]);
+        assert_eq!(matching("image=nginx,!name~web", &containers), ["api"]);
+        assert_eq!(matching("!label.team=ops", &containers), Vec::<String>::new());
+        assert_eq!(matching("!name!=api", &containers), ["api"]);
+    }
+    
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let containers = [
+            container("web-1", "nginx", "running"),
+            container("web-2", "nginx", "exited"),
+            container("db", "postgres", "exited"),
+            container("cache", "redis", "running"),
+        ];
+        
+        assert_eq!(matching("image=nginx,status=running|image=postgres", &containers), ["web-1", "db"]);
+        assert_eq!(matching("image=postgres|image=nginx,status=running", &containers), ["web-1", "db"]);
+        assert_eq!(matching("!name~web,status=running|name=db", &containers), ["db", "cache"]);
+        assert_eq!(matching(" name = cache | name = db ", &containers), ["db", "cache"]);
+    }
+    
+    #[test]
+    fn empty_terms_are_rejected() {
+        for expression in ["", "name~a,", ",name~a", "name~a,,image=b", "name~a|", "|name~a", "!", "name~a,!"] {
+            let error = ContainerFilter::parse(expression).unwrap_err().to_string();
+            assert!(error.contains("Empty term"), "{expression}: {error}");
+        }
+        assert!(ContainerFilter::parse("name").is_err());
+        assert!(ContainerFilter::parse("~web").is_err());
+    }
+}
@@ -0,0 +1,271 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::collections::BTreeMap;
+use std::fs;
+use std::sync::Mutex;
+use crate::config::StatusPageConfig;
+use crate::html::escape;
+use crate::timezone::Timezone;
+use anyhow::Result;
+
+/// Result of one component's health check in the current cycle.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ComponentCheck {
+    pub name: String,
+    pub healthy: bool,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DailyUptime {
+    pub date: NaiveDate,
+    pub checks: u64,
+    pub failures: u64,
+}
+
+impl DailyUptime {
+    fn percent(&self) -> f64 {
+        (self.checks - self.failures) as f64 * 100.0 / self.checks.max(1) as f64
+    }
+}
+
+/// A rule firing when the page was last updated.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusIncident {
+    pub rule: String,
+    pub since: DateTime<Utc>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub runbook_url: Option<String>,
+}
+
+/// Daily uptime per component plus the latest results, persisted in the state file.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct StatusHistory {
+    #[serde(default)]
+    pub components: BTreeMap<String, Vec<DailyUptime>>,
+    #[serde(default)]
+    pub current: Vec<ComponentCheck>,
+    #[serde(default)]
+    pub updated: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub incidents: Vec<StatusIncident>,
+}
+
+impl StatusHistory {
+    pub fn record(&mut self, checks: &[ComponentCheck], now: DateTime<Utc>, history_days: u64) {
+        let today = now.date_naive();
+        for check in checks {
+            let days = self.components.entry(check.name.clone()).or_default();
+            if days.last().is_none_or(|day| day.date != today) {
+                days.push(DailyUptime { date: today, checks: 0, failures: 0 });
+            }
+            if let Some(day) = days.last_mut() {
+                day.checks += 1;
+                if !check.healthy {
+                    day.failures += 1;
+                }
+            }
+        }
+        
+        let cutoff = today - Duration::days(history_days.max(1) as i64 - 1);
+        for days in self.components.values_mut() {
+            days.retain(|day| day.date >= cutoff);
+        }
+        self.components.retain(|_, days| !days.is_empty());
+        
+        self.current = checks.to_vec();
+        self.updated = Some(now);
+    }
+    
+    /// Uptime over all recorded days of a component.
+    fn uptime(&self, name: &str) -> Option<f64> {
+        let days = self.components.get(name)?;
+        let checks: u64 = days.iter().map(|day| day.checks).sum();
+        let failures: u64 = days.iter().map(|day| day.failures).sum();
+        (checks > 0).then(|| (checks - failures) as f64 * 100.0 / checks as f64)
+    }
+}
+
+/// Renders the status page as a self-contained HTML document, with times in
+/// the configured `timezone`.
+pub fn render(config: &StatusPageConfig, history: &StatusHistory, timezone: &Timezone) -> String {
+    let degraded = history.current.iter().filter(|check| !check.healthy).count();
+    let (banner_color, banner) = if history.current.is_empty() {
+        ("#95a5a6", "No checks recorded yet".to_string())
+    } else if degraded == 0 {
+        ("#2ecc71", "All systems operational".to_string())
+    } else {
+        ("#e74c3c", format!("{} of {} components degraded", degraded, history.current.len()))
+    };
+    
+    let today = history.updated.unwrap_or_else(Utc::now).date_naive();
+    let components: String = history.current.iter()
+        .map(|check| render_component(check, history, today, config.history_days))
+        .collect();
+    let incidents = render_incidents(&history.incidents, timezone);
+    let updated = history.updated
+        .map(|updated| timezone.format(updated))
+        .unwrap_or_else(|| "never".to_string());
+    
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <meta http-equiv="refresh" content="60">
+    <title>{title}</title>
+    <style>
+        body {{ font-family: sans-serif; max-width: 820px; margin: 2em auto; color: #333; }}
+        .banner {{ padding: 1em; color: #fff; border-radius: 4px; font-weight: bold; }}
+        .component {{ border-bottom: 1px solid #eee; padding: 1em 0; }}
+        .status {{ float: right; font-weight: bold; }}
+        .days span {{ display: inline-block; width: 8px; height: 24px; margin-right: 2px; border-radius: 2px; }}
+        .detail {{ color: #777; font-size: 0.9em; }}
+    </style>
+</head>
+<body>
+    <h1>{title}</h1>
+    <div class="banner" style="background: {banner_color};">{banner}</div>
+    {incidents}
+    {components}
+    <p class="detail">Last updated {updated}. Uptime covers the last {days} days.</p>
+</body>
+</html>"#,
+        title = escape(&config.title),
+        banner_color = banner_color,
+        banner = banner,
+        incidents = incidents,
+        components = components,
+        updated = updated,
+        days = config.history_days,
+    )
+}
+
+fn render_incidents(incidents: &[StatusIncident], timezone: &Timezone) -> String {
+    if incidents.is_empty() {
+        return String::new();
+    }
+    
+    let items: String = incidents.iter()
+        .map(|incident| {
+            let description = incident.description.as_deref()
+                .map(|description| format!(r#"<div class="detail">{}</div>"#, escape(description)))
+                .unwrap_or_default();
+            let runbook = incident.runbook_url.as_deref()
+                .map(|url| format!(r#"<div class="detail"><a href="{0}">Runbook</a></div>"#, escape(url)))
+                .unwrap_or_default();
+            format!(
+                r#"
+        <div class="component"><strong>{}</strong> <span class="detail">since {}</span>{}{}</div>"#,
+                escape(&incident.rule), timezone.convert(incident.since).format("%Y-%m-%d %H:%M %:z"), description, runbook
+            )
+        })
+        .collect();
+    format!("<h2>Active incidents</h2>{}", items)
+}
+
+fn render_component(check: &ComponentCheck, history: &StatusHistory, today: NaiveDate, history_days: u64) -> String {
+    let (color, status) = if check.healthy {
+        ("#2ecc71", "Operational")
+    } else {
+        ("#e74c3c", "Degraded")
+    };
+    let uptime = history.uptime(&check.name)
+        .map(|uptime| format!("{:.2}% uptime", uptime))
+        .unwrap_or_default();
+    
+    let recorded = history.components.get(&check.name);
+    let bars: String = (0..history_days.max(1))
+        .rev()
+        .map(|offset| today - Duration::days(offset as i64))
+        .map(|date| {
+            let day = recorded.and_then(|days| days.iter().find(|day| day.date == date));
+            let (color, label) = match day {
+                None => ("#ddd", "no data".to_string()),
+                Some(day) if day.failures == 0 => ("#2ecc71", format!("{:.2}%", day.percent())),
+                Some(day) if day.percent() >= 95.0 => ("#f1c40f", format!("{:.2}%", day.percent())),
+                Some(day) => ("#e74c3c", format!("{:.2}%", day.percent())),
+            };
+            format!(r#"<span style="background: {};" title="{}: {}"></span>"#, color, date, label)
+        })
+        .collect();
+    
+    format!(
+        r#"
+    <div class="component">
+        <span class="status" style="color: {};">{}</span>
+        <strong>{}</strong> <span class="detail">{}</span>
+        <div class="days">{}</div>
+        <div class="detail">{}</div>
+    </div>"#,
+        color, status, escape(&check.name), uptime, bars, escape(&check.detail)
+    )
+}
+
+/// Writes the page atomically so a web server never serves a partial file.
+pub fn write(path: &str, html: &str) -> Result<()> {
+    let tmp_path = format!("{}.tmp", path);
+    fs::write(&tmp_path, html)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+/// Last rendered page, shared with the HTTP endpoint.
+#[derive(Default)]
+pub struct PublishedPage {
+    html: Mutex<Option<String>>,
+}
+
+impl PublishedPage {
+    pub fn publish(&self, html: String) {
+        *self.html.lock().unwrap() = Some(html);
+    }
+    
+    pub fn get(&self) -> Option<String> {
+        self.html.lock().unwrap().clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn firing_rules_link_their_runbook() {
+        let config = StatusPageConfig::default();
+        let mut history = StatusHistory::default();
+        assert!(!render(&config, &history, &Timezone::Utc).contains("Active incidents"));
+        
+        history.incidents = vec![StatusIncident {
+            rule: "cpu".to_string(),
+            since: "2024-05-14T08:30:00Z".parse().unwrap(),
+            description: Some("Host CPU <above> threshold".to_string()),
+            runbook_url: Some("https://wiki.example.com/runbooks/cpu".to_string()),
+        }];
+        let html = render(&config, &history, &Timezone::Utc);
+        assert!(html.contains("<strong>cpu</strong> <span class=\"detail\">since 2024-05-14 08:30 +00:00</span>"));
+        assert!(html.contains("Host CPU &lt;above&gt; threshold"));
+        assert!(html.contains("<a href=\"https://wiki.example.com/runbooks/cpu\">Runbook</a>"));
+    }
+    
+    #[test]
+    fn times_follow_the_configured_timezone() {
+        let config = StatusPageConfig::default();
+        let mut history = StatusHistory::default();
+        let timezone = Timezone::parse("+07:00").unwrap();
+        assert!(render(&config, &history, &timezone).contains("Last updated never."));
+        
+        history.updated = Some("2024-05-14T20:15:00Z".parse().unwrap());
+        history.incidents = vec![StatusIncident {
+            rule: "disk".to_string(),
+            since: "2024-05-14T18:30:00Z".parse().unwrap(),
+            description: None,
+            runbook_url: None,
+        }];
+        let html = render(&config, &history, &timezone);
+        assert!(html.contains("Last updated 2024-05-15 03:15:00 +07:00."));
+        assert!(html.contains("since 2024-05-15 01:30 +07:00"));
+    }
+}
@@ -0,0 +1,325 @@
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use chrono::Utc;
+use rusqlite::{params, Connection};
+use crate::config::Config;
+use crate::kubernetes;
+use crate::state::MonitorState;
+use anyhow::{Result, anyhow};
+
+/// Where the monitor state is loaded from and saved to. Each backend keeps
+/// history, incidents, silences and the rest of the state.
+pub trait Storage: Send {
+    /// The saved state, `None` when nothing was saved yet.
+    fn load(&mut self) -> Result<Option<MonitorState>>;
+    fn save(&mut self, state: &MonitorState) -> Result<()>;
+    /// Where the state lives, for logs and `doctor`.
+    fn describe(&self) -> String;
+}
+
+/// The backend selected by `storage.backend`, `None` when persistence is
+/// turned off with an empty path.
+pub fn open(config: &Config) -> Result<Option<Box<dyn Storage>>> {
+    let storage = &config.storage;
+    match storage.backend.as_str() {
+        "file" => Ok((!config.monitoring.state_file.is_empty())
+            .then(|| Box::new(FileStorage::new(&config.monitoring.state_file)) as Box<dyn Storage>)),
+        "sqlite" => {
+            let path = sqlite_path(config);
+            if path.is_empty() {
+                return Ok(None);
+            }
+            Ok(Some(Box::new(SqliteStorage::open(&path, &namespace(config))?)))
+        }
+        "postgres" => open_postgres(config),
+        other => Err(anyhow!("Unknown storage backend '{}', expected sqlite, postgres or file", other)),
+    }
+}
+
+/// `storage.path`, or else `monitoring.state_file` with a `.db` extension.
+pub fn sqlite_path(config: &Config) -> String {
+    if !config.storage.path.is_empty() || config.monitoring.state_file.is_empty() {
+        return config.storage.path.clone();
+    }
+    Path::new(&config.monitoring.state_file).with_extension("db").display().to_string()
+}
+
+/// Rows of the state in the database, `storage.namespace` or the hostname.
+fn namespace(config: &Config) -> String {
+    if config.storage.namespace.is_empty() {
+        kubernetes::hostname()
+    } else {
+        config.storage.namespace.clone()
+    }
+}
+
+#[cfg(feature = "postgres")]
+fn open_postgres(config: &Config) -> Result<Option<Box<dyn Storage>>> {
+    Ok(Some(Box::new(postgres::PostgresStorage::connect(&config.storage.url, &namespace(config))?)))
+}
+
+#[cfg(not(feature = "postgres"))]
+fn open_postgres(_config: &Config) -> Result<Option<Box<dyn Storage>>> {
+    Err(anyhow!("this build has no PostgreSQL support, rebuild with `--features postgres`"))
+}
+
+/// The state as one JSON document, the format used before the database
+/// backends and still the one the `file` backend writes.
+pub struct FileStorage {
+    path: String,
+}
+
+impl FileStorage {
+    pub fn new(path: &str) -> Self {
+        Self { path: path.to_string() }
+    }
+}
+
+impl Storage for FileStorage {
+    fn load(&mut self) -> Result<Option<MonitorState>> {
+        if !Path::new(&self.path).exists() {
+            return Ok(None);
+        }
+        let content = fs::read_to_string(&self.path)?;
+        Ok(Some(serde_json::from_str(&content)?))
+    }
+    
+    /// Writes the state atomically (temporary file + rename).
+    fn save(&mut self, state: &MonitorState) -> Result<()> {
+        let tmp_path = format!("{}.tmp", self.path);
+        fs::write(&tmp_path, serde_json::to_string_pretty(state)?)?;
+        fs::rename(&tmp_path, &self.path)?;
+        Ok(())
+    }
+    
+    fn describe(&self) -> String {
+        format!("file {}", self.path)
+    }
+}
+
+/// One row per top-level field of the state, so history, incidents and
+/// silences are written only when they change.
+fn sections(state: &MonitorState) -> Result<Vec<(String, String)>> {
+    let Value::Object(fields) = serde_json::to_value(state)? else {
+        return Err(anyhow!("monitor state is not a JSON object"));
+    };
+    fields.into_iter()
+        .map(|(section, value)| Ok((section, serde_json::to_string(&value)?)))
+        .collect()
+}
+
+fn assemble(rows: Vec<(String, String)>) -> Result<Option<MonitorState>> {
+    if rows.is_empty() {
+        return Ok(None);
+    }
+    let fields = rows.into_iter()
+        .map(|(section, content)| Ok((section, serde_json::from_str(&content)?)))
+        .collect::<Result<Map<String, Value>>>()?;
+    Ok(Some(serde_json::from_value(Value::Object(fields))?))
+}
+
+/// Sections whose content differs from what was last read or written.
+fn changed(written: &HashMap<String, String>, sections: Vec<(String, String)>) -> Vec<(String, String)> {
+    sections.into_iter()
+        .filter(|(section, content)| written.get(section) != Some(content))
+        .collect()
+}
+
+/// The default backend, a local SQLite database.
+pub struct SqliteStorage {
+    connection: Connection,
+    path: String,
+    namespace: String,
+    written: HashMap<String, String>,
+}
+
+impl SqliteStorage {
+    pub fn open(path: &str, namespace: &str) -> Result<Self> {
+        if let Some(parent) = Path::new(path).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+        let connection = Connection::open(path)?;
+        // Lets `healthz` and `stats self` read while the monitor writes
+        connection.pragma_update(None, "journal_mode", "WAL")?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS monitor_state (
+                namespace TEXT NOT NULL,
+                section TEXT NOT NULL,
+                content TEXT NOT NULL,
+                updated_at TEXT NOT NULL,
+                PRIMARY KEY (namespace, section)
+            )"
+        )?;
+        Ok(Self {
+            connection,
+            path: path.to_string(),
+            namespace: namespace.to_string(),
+            written: HashMap::new(),
+        })
+    }
+}
+
+impl Storage for SqliteStorage {
+    fn load(&mut self) -> Result<Option<MonitorState>> {
+        let mut statement = self.connection.prepare("SELECT section, content FROM monitor_state WHERE namespace = ?1")?;
+        let rows = statement
+            .query_map(params![self.namespace], |row| Ok((row.get(0)?, row.get(1)?)))?
+            .collect::<rusqlite::Result<Vec<(String, String)>>>()?;
+        self.written = rows.iter().cloned().collect();
+        assemble(rows)
+    }
+    
+    fn save(&mut self, state: &MonitorState) -> Result<()> {
+        let changed = changed(&self.written, sections(state)?);
+        if changed.is_empty() {
+            return Ok(());
+        }
+        
+        let now = Utc::now().to_rfc3339();
+        let transaction = self.connection.transaction()?;
+        for (section, content) in &changed {
+            transaction.execute(
+                "INSERT INTO monitor_state (namespace, section, content, updated_at) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (namespace, section) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at",
+                params![self.namespace, section, content, now],
+            )?;
+        }
+        transaction.commit()?;
+        self.written.extend(changed);
+        Ok(())
+    }
+    
+    fn describe(&self) -> String {
+        format!("sqlite {} (namespace {})", self.path, self.namespace)
+    }
+}
+
+#[cfg(feature = "postgres")]
+pub(crate) mod postgres {
+    use std::collections::HashMap;
+    use chrono::Utc;
+    use postgres_native_tls::MakeTlsConnector;
+    use tokio::runtime::Handle;
+    use tokio_postgres::Client;
+    use crate::state::MonitorState;
+    use super::{assemble, changed, sections, Storage};
+    use anyhow::{Result, anyhow};
+    
+    /// A PostgreSQL database shared by replicas. The client is async; the
+    /// monitor loop saves synchronously, so calls block the current worker
+    /// thread like the file and SQLite backends do.
+    pub struct PostgresStorage {
+        url: String,
+        namespace: String,
+        client: Client,
+        written: HashMap<String, String>,
+    }
+    
+    impl PostgresStorage {
+        pub fn connect(url: &str, namespace: &str) -> Result<Self> {
+            if url.is_empty() {
+                return Err(anyhow!("storage.url is empty, expected e.g. postgres://monitor@db/monitor"));
+            }
+            let client = block_on(connect(url))?;
+            Ok(Self {
+                url: url.to_string(),
+                namespace: namespace.to_string(),
+                client,
+                written: HashMap::new(),
+            })
+        }
+        
+        /// The client, reconnected if the connection dropped since the last call.
+        fn client(&mut self) -> Result<&mut Client> {
+            if self.client.is_closed() {
+                self.client = block_on(connect(&self.url))?;
+            }
+            Ok(&mut self.client)
+        }
+    }
+    
+    pub(crate) async fn connect(url: &str) -> Result<Client> {
+        // TLS is used as the URL's sslmode asks, `prefer` by default
+        let tls = MakeTlsConnector::new(native_tls::TlsConnector::new()?);
+        let (client, connection) = tokio_postgres::connect(url, tls).await?;
+        tokio::spawn(async move {
+            if let Err(e) = connection.await {
+                log::error!("PostgreSQL storage connection closed: {}", e);
+            }
+        });
+        // Without the first statement every start logs that the table exists
+        client.batch_execute(
+            "SET client_min_messages = warning;
+            CREATE TABLE IF NOT EXISTS monitor_state (
+                namespace TEXT NOT NULL,
+                section TEXT NOT NULL,
+                content JSONB NOT NULL,
+                updated_at TIMESTAMPTZ NOT NULL,
+                PRIMARY KEY (namespace, section)
+            )"
+        ).await?;
+        Ok(client)
+    }
+    
+    pub(crate) fn block_on<T>(future: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        let handle = Handle::try_current().map_err(|_| anyhow!("PostgreSQL storage needs the Tokio runtime"))?;
+        tokio::task::block_in_place(|| handle.block_on(future))
+    }
+    
+    impl Storage for PostgresStorage {
+        fn load(&mut self) -> Result<Option<MonitorState>> {
+            let namespace = self.namespace.clone();
+            let client = self.client()?;
+            let rows = block_on(async {
+                Ok(client.query("SELECT section, content::text FROM monitor_state WHERE namespace = $1", &[&namespace]).await?)
+            })?;
+            let rows: Vec<(String, String)> = rows.iter().map(|row| (row.get(0), row.get(1))).collect();
+            self.written = rows.iter().cloned().collect();
+            assemble(rows)
+        }
+        
+        fn save(&mut self, state: &MonitorState) -> Result<()> {
+            let changed = changed(&self.written, sections(state)?);
+            if changed.is_empty() {
+                return Ok(());
+            }
+            
+            let namespace = self.namespace.clone();
+            let client = self.client()?;
+            block_on(async {
+                let now = Utc::now();
+                let statement = client.prepare(
+                    "INSERT INTO monitor_state (namespace, section, content, updated_at) VALUES ($1, $2, $3::text::jsonb, $4)
+                     ON CONFLICT (namespace, section) DO UPDATE SET content = excluded.content, updated_at = excluded.updated_at"
+                ).await?;
+                let transaction = client.transaction().await?;
+                for (section, content) in &changed {
+                    transaction.execute(&statement, &[&namespace, section, content, &now]).await?;
+                }
+                transaction.commit().await?;
+                Ok(())
+            })?;
+            self.written.extend(changed);
+            Ok(())
+        }
+        
+        fn describe(&self) -> String {
+            let target = self.url.parse::<tokio_postgres::Config>()
+                .map(|config| format!(
+                    "{}/{}",
+                    config.get_hosts().iter()
+                        .map(|host| match host {
+                            tokio_postgres::config::Host::Tcp(host) => host.clone(),
+                            tokio_postgres::config::Host::Unix(path) => path.display().to_string(),
+                        })
+                        .collect::<Vec<_>>()
+                        .join(","),
+                    config.get_dbname().unwrap_or_default()
+                ))
+                .unwrap_or_else(|_| "database".to_string());
+            format!("postgres {} (namespace {})", target, self.namespace)
+        }
+    }
+}
\ No newline at end of file
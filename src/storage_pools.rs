@@ -0,0 +1,139 @@
+use serde::Serialize;
+use std::fs;
+use std::io::ErrorKind;
+use tokio::process::Command;
+use crate::config::StoragePoolConfig;
+use crate::units;
+use anyhow::{Result, anyhow};
+use log::debug;
+
+const BTRFS_PATH: &str = "/sys/fs/btrfs";
+
+/// Usage of a pool that can run out before `df` shows it: a Btrfs
+/// filesystem's data or metadata space, or an LVM thin pool.
+#[derive(Debug, Clone, Serialize)]
+pub struct PoolUsage {
+    /// `btrfs` or `lvm-thin`.
+    pub kind: String,
+    pub name: String,
+    pub data_percent: f64,
+    pub metadata_percent: f64,
+    pub detail: String,
+}
+
+impl PoolUsage {
+    pub fn worst_percent(&self) -> f64 {
+        self.data_percent.max(self.metadata_percent)
+    }
+    
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {}: data {:.1}%, metadata {:.1}% ({})",
+            self.kind, self.name, self.data_percent, self.metadata_percent, self.detail
+        )
+    }
+}
+
+/// Samples every configured pool type. Missing tooling or filesystems are
+/// skipped; only unexpected failures of `lvs` are returned as errors.
+pub async fn sample(config: &StoragePoolConfig) -> Result<Vec<PoolUsage>> {
+    let mut pools = Vec::new();
+    if config.btrfs {
+        pools.extend(btrfs_usage());
+    }
+    if config.lvm_thin {
+        pools.extend(lvm_thin_usage().await?);
+    }
+    Ok(pools)
+}
+
+/// Btrfs allocates space in chunks, so `df` can show free space while the
+/// metadata chunks are full and nothing is left to allocate. Usage here is
+/// the used bytes against allocated chunks plus what is still unallocated.
+fn btrfs_usage() -> Vec<PoolUsage> {
+    let Ok(entries) = fs::read_dir(BTRFS_PATH) else {
+        debug!("No Btrfs filesystems under {}", BTRFS_PATH);
+        return Vec::new();
+    };
+    
+    entries.flatten()
+        .filter(|entry| entry.path().join("allocation").is_dir())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let read = |relative: &str| -> Option<u64> {
+                fs::read_to_string(path.join(relative)).ok()?.trim().parse().ok()
+            };
+            
+            // Device sizes are in 512-byte sectors
+            let device_size: u64 = fs::read_dir(path.join("devices")).ok()?
+                .flatten()
+                .filter_map(|device| fs::read_to_string(device.path().join("size")).ok()?.trim().parse::<u64>().ok())
+                .sum::<u64>() * 512;
+            let allocated: u64 = ["data", "metadata", "system"].iter()
+                .filter_map(|kind| read(&format!("allocation/{}/disk_total", kind)))
+                .sum();
+            let unallocated = device_size.saturating_sub(allocated);
+            
+            let percent = |kind: &str| -> Option<f64> {
+                let total = read(&format!("allocation/{}/total_bytes", kind))?;
+                let used = read(&format!("allocation/{}/bytes_used", kind))?;
+                let disk_total = read(&format!("allocation/{}/disk_total", kind)).unwrap_or(total);
+                // RAID1/DUP profiles store each byte more than once
+                let ratio = if total > 0 { disk_total as f64 / total as f64 } else { 1.0 }.max(1.0);
+                let capacity = total as f64 + unallocated as f64 / ratio;
+                Some(if capacity > 0.0 { used as f64 * 100.0 / capacity } else { 0.0 })
+            };
+            
+            let label = fs::read_to_string(path.join("label")).unwrap_or_default().trim().to_string();
+            let uuid = entry.file_name().to_string_lossy().to_string();
+            Some(PoolUsage {
+                kind: "btrfs".to_string(),
+                name: if label.is_empty() { uuid } else { label },
+                data_percent: percent("data")?,
+                metadata_percent: percent("metadata")?,
+                detail: format!(
+                    "{} of {} allocated, {} unallocated",
+                    units::bytes(allocated as f64), units::bytes(device_size as f64), units::bytes(unallocated as f64)
+                ),
+            })
+        })
+        .collect()
+}
+
+/// Thin pools overcommit their volumes; writes fail once the pool data or
+/// metadata is full, whatever the filesystems on the thin volumes report.
+async fn lvm_thin_usage() -> Result<Vec<PoolUsage>> {
+    let output = match Command::new("lvs")
+        .args(["--noheadings", "--separator", "|", "--units", "b", "--nosuffix",
+               "-o", "vg_name,lv_name,lv_attr,lv_size,data_percent,metadata_percent"])
+        .output()
+        .await
+    {
+        Ok(output) => output,
+        Err(e) if e.kind() == ErrorKind::NotFound => {
+            debug!("lvs not found, skipping LVM thin pools");
+            return Ok(Vec::new());
+        }
+        Err(e) => return Err(anyhow!("failed to run lvs: {}", e)),
+    };
+    if !output.status.success() {
+        return Err(anyhow!("lvs failed: {}", String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    
+    Ok(String::from_utf8_lossy(&output.stdout).lines()
+        .filter_map(|line| {
+            let fields: Vec<&str> = line.trim().split('|').map(str::trim).collect();
+            // Thin pools have volume type 't' as the first lv_attr character
+            if fields.len() < 6 || !fields[2].starts_with('t') {
+                return None;
+            }
+            Some(PoolUsage {
+                kind: "lvm-thin".to_string(),
+                name: format!("{}/{}", fields[0], fields[1]),
+                data_percent: fields[4].parse().unwrap_or(0.0),
+                metadata_percent: fields[5].parse().unwrap_or(0.0),
+                detail: format!("{} pool", units::bytes(fields[3].parse().unwrap_or(0.0))),
+            })
+        })
+        .collect())
+}
\ No newline at end of file
@@ -0,0 +1,84 @@
+use crate::cloud_metadata::CloudMetadata;
+use crate::config::OtlpConfig;
+use anyhow::Result;
+use tracing_subscriber::{Layer, Registry};
+
+/// A layer added to the logging subscriber, e.g. the OTLP span exporter.
+pub type BoxedLayer = Box<dyn Layer<Registry> + Send + Sync>;
+
+/// Keeps the OTLP exporter running for the lifetime of the process.
+/// Dropping it flushes the spans still buffered.
+#[derive(Default)]
+pub struct Telemetry {
+    #[cfg(feature = "otlp")]
+    provider: Option<opentelemetry_sdk::trace::SdkTracerProvider>,
+}
+
+#[cfg(feature = "otlp")]
+impl Drop for Telemetry {
+    fn drop(&mut self) {
+        if let Some(provider) = self.provider.take() {
+            if let Err(e) = provider.shutdown() {
+                eprintln!("Failed to flush OTLP spans: {}", e);
+            }
+        }
+    }
+}
+
+/// Builds the layer that exports spans to `otlp.endpoint` over OTLP/HTTP.
+/// Spans are batched and sent from a background thread, so a slow or
+/// unreachable collector never delays a cycle.
+#[cfg(feature = "otlp")]
+pub fn layer(config: &OtlpConfig, instance: Option<&CloudMetadata>) -> Result<(Option<BoxedLayer>, Telemetry)> {
+    use opentelemetry::trace::TracerProvider;
+    use opentelemetry::KeyValue;
+    use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+    use opentelemetry_sdk::trace::{Sampler, SdkTracerProvider};
+    use opentelemetry_sdk::Resource;
+    use crate::kubernetes;
+    
+    if !config.enabled {
+        return Ok((None, Telemetry::default()));
+    }
+    
+    let exporter = SpanExporter::builder()
+        .with_http()
+        .with_endpoint(&config.endpoint)
+        .build()?;
+    let mut resource = Resource::builder()
+        .with_service_name(config.service_name.clone())
+        .with_attribute(KeyValue::new("host.name", kubernetes::hostname()));
+    if let Some(cluster) = kubernetes::current() {
+        resource = resource.with_attributes([
+            KeyValue::new("k8s.node.name", cluster.node.clone()),
+            KeyValue::new("k8s.namespace.name", cluster.namespace.clone()),
+            KeyValue::new("k8s.pod.name", cluster.pod.clone()),
+        ]);
+    }
+    if let Some(instance) = instance {
+        resource = resource.with_attributes([
+            KeyValue::new("cloud.provider", instance.provider.clone()),
+            KeyValue::new("cloud.region", instance.region.clone()),
+            KeyValue::new("host.id", instance.instance_id.clone()),
+            KeyValue::new("host.type", instance.instance_type.clone()),
+        ]);
+    }
+    let resource = resource.build();
+    let provider = SdkTracerProvider::builder()
+        .with_batch_exporter(exporter)
+        .with_resource(resource)
+        .with_sampler(Sampler::ParentBased(Box::new(Sampler::TraceIdRatioBased(config.sample_ratio))))
+        .build();
+    
+    let tracer = provider.tracer("performance-monitor");
+    let layer = tracing_opentelemetry::layer().with_tracer(tracer).boxed();
+    Ok((Some(layer), Telemetry { provider: Some(provider) }))
+}
+
+#[cfg(not(feature = "otlp"))]
+pub fn layer(config: &OtlpConfig, _instance: Option<&CloudMetadata>) -> Result<(Option<BoxedLayer>, Telemetry)> {
+    if config.enabled {
+        anyhow::bail!("this build has no OTLP support, rebuild with `--features otlp`");
+    }
+    Ok((None, Telemetry::default()))
+}
\ No newline at end of file
@@ -0,0 +1,551 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use chrono::{DateTime, Duration, Utc};
+use std::fs;
+use std::ops::Range;
+use std::sync::Mutex;
+use crate::api::Role;
+use crate::audit::{self, AuditAction, AuditEntry};
+use crate::config::Config;
+use anyhow::{Result, anyhow};
+
+/// Thresholds that can be changed at runtime, by `set-threshold` name, where
+/// each lives in the config file and what it measures.
+pub const ADJUSTABLE: &[(&str, &[&str], Unit)] = &[
+    ("cpu", &["monitoring", "cpu_threshold"], Unit::Percent),
+    ("cpu_critical", &["monitoring", "cpu_critical_threshold"], Unit::Percent),
+    ("memory", &["monitoring", "memory_threshold"], Unit::Percent),
+    ("memory_critical", &["monitoring", "memory_critical_threshold"], Unit::Percent),
+    ("swap", &["monitoring", "swap_threshold"], Unit::Percent),
+    ("swap_critical", &["monitoring", "swap_critical_threshold"], Unit::Percent),
+    ("disk", &["monitoring", "disk_threshold"], Unit::Percent),
+    ("disk_critical", &["monitoring", "disk_critical_threshold"], Unit::Percent),
+    ("conntrack", &["conntrack", "threshold"], Unit::Percent),
+    ("conntrack_critical", &["conntrack", "critical_threshold"], Unit::Percent),
+    ("storage_pool", &["storage_pools", "threshold"], Unit::Percent),
+    ("storage_pool_critical", &["storage_pools", "critical_threshold"], Unit::Percent),
+    ("bandwidth", &["interfaces", "threshold_mbps"], Unit::Mbps),
+    ("bandwidth_critical", &["interfaces", "critical_threshold_mbps"], Unit::Mbps),
+    ("hot_core", &["cpu_cores", "threshold"], Unit::Percent),
+    ("hot_core_critical", &["cpu_cores", "critical_threshold"], Unit::Percent),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Unit {
+    Percent,
+    Mbps,
+}
+
+impl Unit {
+    /// A runtime value must be in range: 0 would disable the rule, and a
+    /// percentage above 100 would never be reached.
+    fn check(self, name: &str, value: f64) -> Result<()> {
+        match self {
+            Unit::Percent if value > 0.0 && value <= 100.0 => Ok(()),
+            Unit::Mbps if value > 0.0 && value.is_finite() => Ok(()),
+            Unit::Percent => Err(anyhow!("Threshold {} is a percentage, expected above 0 and at most 100, got {}", name, value)),
+            Unit::Mbps => Err(anyhow!("Threshold {} is in Mbit/s, expected a positive number, got {}", name, value)),
+        }
+    }
+}
+
+/// A runtime change of one threshold, until `expires_at` or, without it,
+/// until it is reset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdOverride {
+    pub name: String,
+    pub value: f64,
+    #[serde(default)]
+    pub set_by: String,
+    pub set_at: DateTime<Utc>,
+    #[serde(default)]
+    pub expires_at: Option<DateTime<Utc>>,
+    /// Also written to the config file, so it is not kept in the state file.
+    #[serde(default)]
+    pub persisted: bool,
+}
+
+impl ThresholdOverride {
+    pub fn is_active(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_none_or(|expires_at| now < expires_at)
+    }
+}
+
+/// Threshold overrides shared between the monitor loop, which applies them
+/// at the start of each cycle, and the API that sets them.
+#[derive(Default)]
+pub struct Thresholds {
+    overrides: Mutex<Vec<ThresholdOverride>>,
+    config_path: Mutex<Option<String>>,
+}
+
+impl Thresholds {
+    /// Restores overrides from the state file; `config_path` is where
+    /// permanent changes are written.
+    pub fn load(&self, overrides: Vec<ThresholdOverride>, config_path: &str) {
+        *self.overrides.lock().unwrap() = overrides;
+        *self.config_path.lock().unwrap() = Some(config_path.to_string());
+    }
+    
+    /// Replaces the overrides with the ones another replica saved.
+    pub fn replace(&self, overrides: Vec<ThresholdOverride>) {
+        *self.overrides.lock().unwrap() = overrides;
+    }
+    
+    /// Overrides `name` with `value`, for `duration` or until reset. With
+    /// `persist` the change is also written to the config file.
+    pub fn set(&self, name: &str, value: f64, duration: Option<Duration>, persist: bool, set_by: String) -> Result<ThresholdOverride> {
+        let path = checked_path(name, value)?;
+        if duration.is_some_and(|duration| duration <= Duration::zero()) {
+            return Err(anyhow!("Threshold duration must be positive"));
+        }
+        if persist && duration.is_some() {
+            return Err(anyhow!("A persisted threshold is permanent, drop the duration"));
+        }
+        if persist {
+            let config_path = self.config_path.lock().unwrap().clone()
+                .ok_or_else(|| anyhow!("No config file to persist to"))?;
+            persist_threshold(&config_path, path, value)?;
+        }
+        
+        let now = Utc::now();
+        let adjusted = ThresholdOverride {
+            name: name.to_string(),
+            value,
+            set_by,
+            set_at: now,
+            expires_at: duration.map(|duration| now + duration),
+            persisted: persist,
+        };
+        let mut overrides = self.overrides.lock().unwrap();
+        overrides.retain(|existing| existing.name != name);
+        overrides.push(adjusted.clone());
+        audit::record(AuditEntry::new(
+            AuditAction::Config,
+            &adjusted.set_by,
+            name,
+            match (adjusted.expires_at, persist) {
+                (Some(expires_at), _) => format!("set to {} until {}", value, expires_at.to_rfc3339()),
+                (None, true) => format!("set to {} and written to the config file", value),
+                (None, false) => format!("set to {} until reset", value),
+            },
+        ));
+        Ok(adjusted)
+    }
+    
+    /// Drops the override of `name`, returning whether there was one.
+    pub fn reset(&self, name: &str, reset_by: &str) -> bool {
+        let mut overrides = self.overrides.lock().unwrap();
+        let before = overrides.len();
+        overrides.retain(|existing| existing.name != name);
+        let reset = overrides.len() != before;
+        if reset {
+            audit::record(AuditEntry::new(AuditAction::Config, reset_by, name, "reset to the configured value"));
+        }
+        reset
+    }
+    
+    pub fn active(&self, now: DateTime<Utc>) -> Vec<ThresholdOverride> {
+        self.overrides.lock().unwrap().iter().filter(|adjusted| adjusted.is_active(now)).cloned().collect()
+    }
+    
+    /// Drops expired overrides and returns the ones the state file keeps.
+    pub fn snapshot(&self, now: DateTime<Utc>) -> Vec<ThresholdOverride> {
+        let mut overrides = self.overrides.lock().unwrap();
+        overrides.retain(|adjusted| adjusted.is_active(now));
+        overrides.iter().filter(|adjusted| !adjusted.persisted).cloned().collect()
+    }
+}
+
+/// `set-threshold` from the command line: sent to the running monitor's API
+/// with an admin token from the config. Without a reachable monitor a
+/// `persist` change is written to the config file for the next start.
+pub async fn set_from_cli(config_path: &str, name: &str, value: Option<f64>, duration: Option<&str>, persist: bool) -> Result<String> {
+    let config = Config::load_from_file(config_path)?;
+    match value {
+        Some(value) => checked_path(name, value)?,
+        None => path_of(name)?,
+    };
+    let token = config.http.api_token.clone().filter(|token| !token.is_empty()).or_else(|| {
+        config.http.users.iter()
+            .find(|user| Role::parse(&user.role) == Some(Role::Admin))
+            .map(|user| user.token.clone())
+    });
+    
+    if config.http.enabled {
+        let base = format!("http://{}/api/v1/thresholds", loopback(&config.http.listen));
+        let client = reqwest::Client::builder().no_proxy().build()?;
+        let request = match value {
+            Some(value) => client.post(&base).json(&serde_json::json!({
+                "name": name,
+                "value": value,
+                "duration": duration,
+                "persist": persist,
+            })),
+            None => client.delete(format!("{}/{}", base, name)),
+        };
+        let request = match &token {
+            Some(token) => request.bearer_auth(token),
+            None => request,
+        };
+        
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {
+                return Ok(match value {
+                    Some(value) => describe(name, value, duration, persist),
+                    None => format!("Threshold {} reset to its configured value", name),
+                });
+            }
+            Ok(response) => {
+                let status = response.status();
+                return Err(anyhow!("Monitor refused the change ({}): {}", status, response.text().await.unwrap_or_default().trim()));
+            }
+            Err(e) if !(persist && value.is_some()) => {
+                return Err(anyhow!("Monitor is not reachable at {}: {}", base, e));
+            }
+            Err(_) => {}
+        }
+    } else if !persist {
+        return Err(anyhow!("Changing a running monitor needs http.enabled, or use --persist to only change {}", config_path));
+    }
+    
+    let Some(value) = value else {
+        return Err(anyhow!("Nothing to reset, the monitor is not running"));
+    };
+    if duration.is_some() {
+        return Err(anyhow!("A persisted threshold is permanent, drop the duration"));
+    }
+    persist_threshold(config_path, checked_path(name, value)?, value)?;
+    audit::configure(&config.audit);
+    audit::record(AuditEntry::new(
+        AuditAction::Config, &audit::cli_actor(), name, format!("set to {} in {}", value, config_path)
+    ));
+    Ok(format!("Threshold {} set to {} in {}, effective when the monitor starts", name, value, config_path))
+}
+
+fn describe(name: &str, value: f64, duration: Option<&str>, persist: bool) -> String {
+    match (duration, persist) {
+        (Some(duration), _) => format!("Threshold {} set to {} for {} from the next check", name, value, duration),
+        (None, true) => format!("Threshold {} set to {} from the next check and written to the config file", name, value),
+        (None, false) => format!("Threshold {} set to {} from the next check until reset", name, value),
+    }
+}
+
+/// The address to reach a listener on this host, e.g. `127.0.0.1:9273` for `0.0.0.0:9273`.
+fn loopback(listen: &str) -> String {
+    if let Some(port) = listen.strip_prefix("0.0.0.0:") {
+        format!("127.0.0.1:{}", port)
+    } else if let Some(port) = listen.strip_prefix("[::]:") {
+        format!("[::1]:{}", port)
+    } else {
+        listen.to_string()
+    }
+}
+
+fn adjustable(name: &str) -> Result<(&'static [&'static str], Unit)> {
+    ADJUSTABLE.iter()
+        .find(|(adjustable, _, _)| *adjustable == name)
+        .map(|(_, path, unit)| (*path, *unit))
+        .ok_or_else(|| anyhow!(
+            "Unknown threshold '{}', expected one of: {}",
+            name,
+            ADJUSTABLE.iter().map(|(adjustable, _, _)| *adjustable).collect::<Vec<_>>().join(", ")
+        ))
+}
+
+fn path_of(name: &str) -> Result<&'static [&'static str]> {
+    Ok(adjustable(name)?.0)
+}
+
+/// The config path of `name`, once `value` is in range for it.
+fn checked_path(name: &str, value: f64) -> Result<&'static [&'static str]> {
+    let (path, unit) = adjustable(name)?;
+    unit.check(name, value)?;
+    Ok(path)
+}
+
+/// The value of every adjustable threshold in `config`.
+pub fn configured(config: &Config) -> Vec<(&'static str, f64)> {
+    let value = serde_json::to_value(config).unwrap_or(Value::Null);
+    ADJUSTABLE.iter()
+        .filter_map(|(name, path, _)| {
+            let threshold = path.iter().try_fold(&value, |value, key| value.get(key))?;
+            threshold.as_f64().map(|threshold| (*name, threshold))
+        })
+        .collect()
+}
+
+/// `config` with the overrides applied.
+pub fn apply(config: &Config, overrides: &[ThresholdOverride]) -> Result<Config> {
+    let mut value = serde_json::to_value(config)?;
+    for adjusted in overrides {
+        set_path(&mut value, path_of(&adjusted.name)?, adjusted.value);
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// Writes one threshold into the config file. A key already in the file is
+/// replaced in place, keeping the rest as written; a missing one means
+/// rewriting the whole file. The file is replaced atomically so the monitor
+/// never reads half of it, which needs its directory to be writable: a
+/// symlinked config is followed to its target, but a file bind-mounted on its
+/// own (Docker `-v ./config.json:/app/config.json`) cannot be replaced.
+fn persist_threshold(config_path: &str, path: &[&str], threshold: f64) -> Result<()> {
+    let config_path = fs::canonicalize(config_path)?;
+    let content = replace_value(&fs::read_to_string(&config_path)?, path, threshold)?;
+    
+    // Refuse to write a file the monitor could no longer load
+    serde_json::from_str::<Config>(&content)?;
+    
+    let temporary = config_path.with_extension("json.tmp");
+    let replace = || -> std::io::Result<()> {
+        fs::write(&temporary, &content)?;
+        fs::set_permissions(&temporary, fs::metadata(&config_path)?.permissions())?;
+        fs::rename(&temporary, &config_path)
+    };
+    if let Err(e) = replace() {
+        let _ = fs::remove_file(&temporary);
+        return Err(anyhow!(
+            "Failed to replace {}: {}. Its directory must be writable, mount the config directory rather than the file",
+            config_path.display(), e
+        ));
+    }
+    Ok(())
+}
+
+/// `content` with the value at `path` set to `threshold`.
+fn replace_value(content: &str, path: &[&str], threshold: f64) -> Result<String> {
+    match (Scanner { bytes: content.as_bytes(), position: 0 }).find(path) {
+        Some(span) => {
+            let mut content = content.to_string();
+            content.replace_range(span, &Value::from(threshold).to_string());
+            // The scanner only looks at the path, the rest may still be broken
+            serde_json::from_str::<Value>(&content)?;
+            Ok(content)
+        }
+        None => {
+            let mut value: Value = serde_json::from_str(content)?;
+            if !value.is_object() {
+                return Err(anyhow!("expected a JSON object"));
+            }
+            set_path(&mut value, path, threshold);
+            Ok(serde_json::to_string_pretty(&value)?)
+        }
+    }
+}
+
+/// Finds where a value sits in JSON text, so it can be replaced without
+/// reformatting the rest.
+struct Scanner<'a> {
+    bytes: &'a [u8],
+    position: usize,
+}
+
+impl Scanner<'_> {
+    /// The byte range of the value at `path`, `None` when it is missing.
+    fn find(&mut self, path: &[&str]) -> Option<Range<usize>> {
+        self.whitespace();
+        let Some((key, rest)) = path.split_first() else {
+            let start = self.position;
+            self.skip_value()?;
+            return Some(start..self.position);
+        };
+        
+        self.expect(b'{')?;
+        loop {
+            self.whitespace();
+            if self.peek()? == b'}' {
+                return None;
+            }
+            let name = self.string()?;
+            self.whitespace();
+            self.expect(b':')?;
+            if name == *key {
+                return self.find(rest);
+            }
+            self.whitespace();
+            self.skip_value()?;
+            self.whitespace();
+            if self.peek()? != b',' {
+                return None;
+            }
+            self.position += 1;
+        }
+    }
+    
+    fn skip_value(&mut self) -> Option<()> {
+        match self.peek()? {
+            b'"' => self.string().map(|_| ()),
+            b'{' | b'[' => {
+                let mut depth = 0;
+                loop {
+                    match self.peek()? {
+                        b'"' => {
+                            self.string()?;
+                            continue;
+                        }
+                        b'{' | b'[' => depth += 1,
+                        b'}' | b']' => depth -= 1,
+                        _ => {}
+                    }
+                    self.position += 1;
+                    if depth == 0 {
+                        return Some(());
+                    }
+                }
+            }
+            _ => {
+                let start = self.position;
+                while self.peek().is_some_and(|byte| !matches!(byte, b',' | b'}' | b']') && !byte.is_ascii_whitespace()) {
+                    self.position += 1;
+                }
+                (self.position > start).then_some(())
+            }
+        }
+    }
+    
+    fn string(&mut self) -> Option<String> {
+        let start = self.position;
+        self.expect(b'"')?;
+        loop {
+            match self.peek()? {
+                b'\\' => self.position += 2,
+                b'"' => break,
+                _ => self.position += 1,
+            }
+        }
+        self.position += 1;
+        serde_json::from_slice(&self.bytes[start..self.position]).ok()
+    }
+    
+    fn whitespace(&mut self) {
+        while self.peek().is_some_and(|byte| byte.is_ascii_whitespace()) {
+            self.position += 1;
+        }
+    }
+    
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        (self.peek()? == byte).then(|| self.position += 1)
+    }
+    
+    fn peek(&self) -> Option<u8> {
+        self.bytes.get(self.position).copied()
+    }
+}
+
+fn set_path(value: &mut Value, path: &[&str], threshold: f64) {
+    let mut current = value;
+    for key in path {
+        if !current.is_object() {
+            *current = Value::Object(Default::default());
+        }
+        current = current.as_object_mut().unwrap().entry(key.to_string()).or_insert(Value::Null);
+    }
+    *current = Value::from(threshold);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const CPU: &[&str] = &["monitoring", "cpu_threshold"];
+    
+    fn find<'a>(content: &'a str, path: &[&str]) -> Option<&'a str> {
+        let span = Scanner { bytes: content.as_bytes(), position: 0 }.find(path)?;
+        Some(&content[span])
+    }
+    
+    #[test]
+    fn finds_the_nested_key_not_a_namesake() {
+        let content = r#"{
+            "cpu_threshold": 1,
+            "alerts": {"monitoring": {"cpu_threshold": 2}, "list": [{"cpu_threshold": 3}, [4, {"x": 5}]]},
+            "monitoring": {"check_interval": 60, "nested": {"cpu_threshold": 6}, "cpu_threshold": 80.5}
+        }"#;
+        assert_eq!(find(content, CPU), Some("80.5"));
+        assert_eq!(find(content, &["cpu_threshold"]), Some("1"));
+        assert_eq!(find(content, &["alerts", "list"]), Some(r#"[{"cpu_threshold": 3}, [4, {"x": 5}]]"#));
+    }
+    
+    #[test]
+    fn skips_escaped_strings() {
+        let content = r#"{"note": "a \"} {\" b \\", "monitoring": {"name": "x\\\"y}", "cpu_threshold": 70}}"#;
+        assert_eq!(find(content, CPU), Some("70"));
+        assert_eq!(find(content, &["note"]), Some(r#""a \"} {\" b \\""#));
+    }
+    
+    #[test]
+    fn keeps_the_rest_of_the_file_as_written() {
+        let content = "{\n  \"_comment\": \"thresholds tuned for the db host, see ops#12\",\n\t\"monitoring\" :  {\n    \"cpu_threshold\"  :   80 ,   \"check_interval\": 60\n  }\n}\n";
+        let replaced = replace_value(content, CPU, 92.5).unwrap();
+        assert_eq!(replaced, content.replace("80 ,", "92.5 ,"));
+    }
+    
+    #[test]
+    fn adds_a_missing_key() {
+        assert_eq!(find(r#"{"monitoring": {"check_interval": 60}}"#, CPU), None);
+        assert_eq!(find(r#"{"email": {}}"#, CPU), None);
+        
+        let replaced: Value = serde_json::from_str(&replace_value(r#"{"monitoring": {"check_interval": 60}}"#, CPU, 75.0).unwrap()).unwrap();
+        assert_eq!(replaced, serde_json::json!({"monitoring": {"check_interval": 60, "cpu_threshold": 75.0}}));
+        let replaced: Value = serde_json::from_str(&replace_value(r#"{"email": {}}"#, CPU, 75.0).unwrap()).unwrap();
+        assert_eq!(replaced, serde_json::json!({"email": {}, "monitoring": {"cpu_threshold": 75.0}}));
+    }
+    
+    #[test]
+    fn malformed_json_is_an_error() {
+        for content in ["", "{", r#"{"monitoring": {"cpu_threshold": }"#, r#"{"monitoring": {"cpu_threshold": }}"#, r#"{"monitoring": {"cpu_threshold": 80}} x"#, r#"{"monitoring": "unterminated}"#, "[80]"] {
+            assert!(replace_value(content, CPU, 75.0).is_err(), "{}", content);
+        }
+    }
+    
+    #[test]
+    fn checks_the_range_of_each_threshold() {
+        for value in [0.0, -1.0, 100.5, f64::NAN, f64::INFINITY] {
+            assert!(checked_path("cpu", value).is_err(), "cpu {}", value);
+            assert!(checked_path("disk_critical", value).is_err(), "disk_critical {}", value);
+        }
+        for value in [0.5, 80.0, 100.0] {
+            assert_eq!(checked_path("cpu", value).unwrap(), CPU);
+        }
+        assert!(checked_path("bandwidth", 2500.0).is_ok());
+        for value in [0.0, -5.0, f64::NAN, f64::INFINITY] {
+            assert!(checked_path("bandwidth", value).is_err(), "bandwidth {}", value);
+        }
+        assert!(checked_path("load", 1.0).is_err());
+        
+        let thresholds = Thresholds::default();
+        assert!(thresholds.set("memory", 120.0, None, false, String::new()).is_err());
+        assert!(thresholds.active(Utc::now()).is_empty());
+    }
+    
+    #[test]
+    fn persists_into_a_config_file() {
+        let path = std::env::temp_dir().join(format!("perfmon-thresholds-{:016x}.json", rand::random::<u64>()));
+        let original = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/render/config.json")).unwrap();
+        fs::write(&path, &original).unwrap();
+        
+        let result = persist_threshold(path.to_str().unwrap(), CPU, 91.0);
+        let written = fs::read_to_string(&path);
+        let _ = fs::remove_file(&path);
+        result.unwrap();
+        assert_eq!(written.unwrap(), original.replacen("\"cpu_threshold\": 80.0", "\"cpu_threshold\": 91.0", 1));
+    }
+    
+    #[test]
+    fn persists_through_a_symlink() {
+        let directory = std::env::temp_dir().join(format!("perfmon-thresholds-{:016x}", rand::random::<u64>()));
+        fs::create_dir_all(directory.join("config")).unwrap();
+        let original = fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/render/config.json")).unwrap();
+        fs::write(directory.join("config/config.json"), &original).unwrap();
+        std::os::unix::fs::symlink("config/config.json", directory.join("config.json")).unwrap();
+        
+        let result = persist_threshold(directory.join("config.json").to_str().unwrap(), CPU, 91.0);
+        let link = fs::read_link(directory.join("config.json"));
+        let written = fs::read_to_string(directory.join("config/config.json"));
+        let _ = fs::remove_dir_all(&directory);
+        result.unwrap();
+        assert_eq!(link.unwrap(), std::path::Path::new("config/config.json"));
+        assert!(written.unwrap().contains("\"cpu_threshold\": 91.0"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,287 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use reqwest::{Client, RequestBuilder};
+use serde_json::{json, Value};
+use crate::alert::Alert;
+use crate::config::{Config, TicketConfig};
+use crate::incident::{IncidentState, RuleState};
+use crate::kubernetes;
+use crate::network;
+use crate::timezone::{self, Timezone};
+use crate::units;
+use anyhow::{Result, anyhow};
+use log::warn;
+
+/// Jira limits summaries to 255 characters.
+const MAX_TITLE: usize = 250;
+
+/// An issue opened about a rule, kept in its incident state until the rule
+/// resolves and the issue is updated.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Ticket {
+    /// `github` or `jira`.
+    pub kind: String,
+    /// Issue number or Jira key, e.g. `42` or `OPS-17`.
+    pub key: String,
+    pub url: String,
+    pub opened_at: DateTime<Utc>,
+    /// When the rule started firing.
+    pub firing_since: DateTime<Utc>,
+}
+
+/// A ticket to open or resolve once the incidents of a cycle are evaluated.
+pub enum TicketUpdate {
+    Open { rule: String, alert: Box<Alert>, firing_since: DateTime<Utc> },
+    Resolve { rule: String, ticket: Ticket },
+}
+
+/// Whether a ticket should be opened about `rule`, firing for `after_minutes`
+/// without one.
+pub fn due(config: &Config, rule: &str, state: &RuleState, now: DateTime<Utc>) -> bool {
+    let tickets = &config.tickets;
+    tickets.enabled
+        && state.state == IncidentState::Firing
+        && state.ticket.is_none()
+        && (tickets.rules.is_empty() || tickets.rules.iter().any(|ticketed| ticketed == rule))
+        && now - state.since >= Duration::minutes(tickets.after_minutes as i64)
+}
+
+/// How issue text is marked up: Markdown on GitHub, wiki markup on Jira.
+#[derive(Clone, Copy, PartialEq)]
+enum Markup {
+    Markdown,
+    Jira,
+}
+
+/// Opens and updates issues in GitHub Issues or Jira Cloud.
+pub struct TicketTracker {
+    config: TicketConfig,
+    client: Client,
+    hostname: String,
+    timezone: Timezone,
+}
+
+impl TicketTracker {
+    /// `None` when disabled or missing what the tracker needs.
+    pub fn new(config: &Config) -> Option<Self> {
+        let settings = config.tickets.clone();
+        if !settings.enabled {
+            return None;
+        }
+        let missing = match settings.kind.as_str() {
+            "github" => settings.repository.is_empty().then_some("repository"),
+            "jira" => settings.project.is_empty().then_some("project")
+                .or_else(|| settings.user.is_empty().then_some("user")),
+            other => {
+                warn!("Unknown tickets kind '{}' (expected github or jira), no tickets are opened", other);
+                return None;
+            }
+        };
+        if let Some(field) = missing.or_else(|| settings.token.is_empty().then_some("token")) {
+            warn!("tickets is enabled without a {}, no tickets are opened", field);
+            return None;
+        }
+        
+        Some(Self {
+            client: network::http_client(&config.network),
+            hostname: kubernetes::hostname(),
+            timezone: timezone::load(&config.timezone),
+            config: settings,
+        })
+    }
+    
+    /// Opens an issue with a snapshot of the alert.
+    pub async fn open(&self, alert: &Alert, firing_since: DateTime<Utc>) -> Result<Ticket> {
+        let mut title = format!("[{}] {}", self.hostname, alert.summary);
+        if let Some((index, _)) = title.char_indices().nth(MAX_TITLE) {
+            title.truncate(index);
+        }
+        
+        let (key, url) = match self.config.kind.as_str() {
+            "jira" => {
+                let body = json!({
+                    "fields": {
+                        "project": { "key": self.config.project },
+                        "issuetype": { "name": self.config.issue_type },
+                        "summary": title,
+                        "description": self.snapshot(alert, firing_since, Markup::Jira),
+                        "labels": self.config.labels,
+                    }
+                });
+                let response = send(self.jira(self.client.post(self.api("rest/api/2/issue"))).json(&body)).await?;
+                let key = response["key"].as_str().ok_or_else(|| anyhow!("Jira returned no issue key"))?;
+                (key.to_string(), format!("{}/browse/{}", self.config.url.trim_end_matches('/'), key))
+            }
+            _ => {
+                let body = json!({
+                    "title": title,
+                    "body": self.snapshot(alert, firing_since, Markup::Markdown),
+                    "labels": self.config.labels,
+                });
+                let path = format!("repos/{}/issues", self.config.repository);
+                let response = send(self.github(self.client.post(self.api(&path))).json(&body)).await?;
+                let number = response["number"].as_u64().ok_or_else(|| anyhow!("GitHub returned no issue number"))?;
+                (number.to_string(), response["html_url"].as_str().unwrap_or_default().to_string())
+            }
+        };
+        
+        Ok(Ticket {
+            kind: self.config.kind.clone(),
+            key,
+            url,
+            opened_at: Utc::now(),
+            firing_since,
+        })
+    }
+    
+    /// Comments that `rule` resolved and, with `close_on_resolve`, closes
+    /// the issue.
+    pub async fn resolve(&self, ticket: &Ticket, rule: &str) -> Result<()> {
+        let now = Utc::now();
+        let comment = format!(
+            "{} on {} resolved at {} after firing for {} minutes.",
+            rule, self.hostname, self.timezone.format(now), (now - ticket.firing_since).num_minutes()
+        );
+        
+        match ticket.kind.as_str() {
+            "jira" => {
+                let issue = format!("rest/api/2/issue/{}", ticket.key);
+                send(self.jira(self.client.post(self.api(&format!("{}/comment", issue)))).json(&json!({ "body": comment }))).await?;
+                if self.config.close_on_resolve {
+                    let transitions = send(self.jira(self.client.get(self.api(&format!("{}/transitions", issue))))).await?;
+                    let id = transitions["transitions"].as_array()
+                        .and_then(|transitions| transitions.iter().find(|transition| {
+                            transition["name"].as_str().is_some_and(|name| name.eq_ignore_ascii_case(&self.config.resolve_transition))
+                        }))
+                        .and_then(|transition| transition["id"].as_str())
+                        .ok_or_else(|| anyhow!("{} has no transition named {}", ticket.key, self.config.resolve_transition))?;
+                    let body = json!({ "transition": { "id": id } });
+                    send(self.jira(self.client.post(self.api(&format!("{}/transitions", issue)))).json(&body)).await?;
+                }
+            }
+            _ => {
+                let issue = format!("repos/{}/issues/{}", self.config.repository, ticket.key);
+                send(self.github(self.client.post(self.api(&format!("{}/comments", issue)))).json(&json!({ "body": comment }))).await?;
+                if self.config.close_on_resolve {
+                    let body = json!({ "state": "closed", "state_reason": "completed" });
+                    send(self.github(self.client.patch(self.api(&issue))).json(&body)).await?;
+                }
+            }
+        }
+        Ok(())
+    }
+    
+    fn api(&self, path: &str) -> String {
+        format!("{}/{}", self.config.url.trim_end_matches('/'), path)
+    }
+    
+    fn github(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .bearer_auth(&self.config.token)
+            .header("Accept", "application/vnd.github+json")
+            .header("User-Agent", "performance-monitor")
+            .timeout(std::time::Duration::from_millis(self.config.timeout_ms))
+    }
+    
+    fn jira(&self, request: RequestBuilder) -> RequestBuilder {
+        request
+            .basic_auth(&self.config.user, Some(&self.config.token))
+            .header("Accept", "application/json")
+            .timeout(std::time::Duration::from_millis(self.config.timeout_ms))
+    }
+    
+    /// The alert as it was when the ticket was opened: its values, the
+    /// containers and processes involved and its notes.
+    fn snapshot(&self, alert: &Alert, firing_since: DateTime<Utc>, markup: Markup) -> String {
+        let mut lines = vec![
+            bold(&alert.summary, markup),
+            String::new(),
+            header(&["Field", "Value"], markup),
+        ];
+        let mut fields = vec![
+            ("Host", self.hostname.clone()),
+            ("Rule", alert.rule.clone()),
+            ("Severity", alert.severity.label().to_string()),
+        ];
+        if let Some(value) = alert.value {
+            fields.push(("Value", format!("{:.2}", value)));
+        }
+        fields.extend([
+            ("Threshold", alert.threshold.clone()),
+            ("Window", alert.window.clone()),
+            ("Firing since", self.timezone.format(firing_since)),
+        ]);
+        lines.extend(fields.iter().map(|(name, value)| row(&[name, value])));
+        
+        if !alert.containers.is_empty() {
+            lines.extend([String::new(), heading("Containers", markup), header(&["Name", "Image", "Status", "CPU", "Memory"], markup)]);
+            lines.extend(alert.containers.iter().map(|container| row(&[
+                &container.name,
+                &container.image,
+                &container.status,
+                &format!("{:.2}%", container.cpu_usage),
+                &format!("{} ({:.1}%)", units::bytes(container.memory_usage as f64), container.memory_percent),
+            ])));
+        }
+        if !alert.processes.is_empty() {
+            lines.extend([String::new(), heading("Processes", markup)]);
+            lines.extend(alert.processes.iter().map(|process| {
+                let status = match (process.running, process.restarted) {
+                    (true, true) => "restarted",
+                    (true, false) => "running",
+                    (false, _) => "not running",
+                };
+                bullet(&format!("{}: {}", process.name, status), markup)
+            }));
+        }
+        if !alert.notes.is_empty() {
+            lines.extend([String::new(), heading("Notes", markup)]);
+            lines.extend(alert.notes.iter().map(|note| bullet(note, markup)));
+        }
+        lines.join("\n")
+    }
+}
+
+/// Sends a tracker request and returns its JSON response, `null` when empty.
+async fn send(request: RequestBuilder) -> Result<Value> {
+    let response = request.send().await?;
+    let status = response.status();
+    let text = response.text().await.unwrap_or_default();
+    if !status.is_success() {
+        return Err(anyhow!("HTTP {}: {}", status, text.chars().take(200).collect::<String>()));
+    }
+    Ok(serde_json::from_str(&text).unwrap_or(Value::Null))
+}
+
+fn bold(text: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Markdown => format!("**{}**", text),
+        Markup::Jira => format!("*{}*", text),
+    }
+}
+
+fn heading(text: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Markdown => format!("### {}", text),
+        Markup::Jira => format!("h3. {}", text),
+    }
+}
+
+fn bullet(text: &str, markup: Markup) -> String {
+    match markup {
+        Markup::Markdown => format!("- {}", text),
+        Markup::Jira => format!("* {}", text),
+    }
+}
+
+fn header(cells: &[&str], markup: Markup) -> String {
+    match markup {
+        Markup::Markdown => format!("{}\n|{}", row(cells), "---|".repeat(cells.len())),
+        Markup::Jira => format!("||{}||", cells.join("||")),
+    }
+}
+
+fn row(cells: &[&str]) -> String {
+    let cells: Vec<String> = cells.iter().map(|cell| cell.replace('|', "\\|").replace('\n', " ")).collect();
+    format!("| {} |", cells.join(" | "))
+}
\ No newline at end of file
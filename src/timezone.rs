@@ -0,0 +1,199 @@
+use chrono::{DateTime, FixedOffset, Local, Offset, TimeZone, Utc};
+use std::fs;
+use std::sync::Arc;
+use anyhow::{Result, anyhow};
+use log::warn;
+
+const ZONEINFO: &str = "/usr/share/zoneinfo";
+
+/// Timestamps shown to people, always with their UTC offset.
+pub const DATETIME_FORMAT: &str = "%Y-%m-%d %H:%M:%S %:z";
+
+/// Zone timestamps are shown in: UTC, the host's local time, a fixed offset
+/// such as `+07:00`, or an IANA zone such as `Asia/Jakarta` read from the
+/// system tz database.
+#[derive(Debug, Clone, Default)]
+pub enum Timezone {
+    #[default]
+    Utc,
+    /// The host's zone, from `TZ` or `/etc/localtime`.
+    Local,
+    Fixed(FixedOffset),
+    Zone(Arc<Zone>),
+}
+
+impl Timezone {
+    pub fn parse(name: &str) -> Result<Self> {
+        let name = name.trim();
+        match name {
+            "" | "UTC" | "utc" | "Z" => Ok(Timezone::Utc),
+            "local" => Ok(Timezone::Local),
+            _ if name.starts_with(['+', '-']) => {
+                let seconds = parse_offset(name).ok_or_else(|| anyhow!("invalid UTC offset '{}'", name))?;
+                FixedOffset::east_opt(seconds)
+                    .map(Timezone::Fixed)
+                    .ok_or_else(|| anyhow!("UTC offset '{}' out of range", name))
+            }
+            _ => Ok(Timezone::Zone(Arc::new(Zone::load(name)?))),
+        }
+    }
+    
+    pub fn offset_at(&self, time: DateTime<Utc>) -> FixedOffset {
+        match self {
+            Timezone::Utc => Utc.fix(),
+            Timezone::Local => Local.offset_from_utc_datetime(&time.naive_utc()).fix(),
+            Timezone::Fixed(offset) => *offset,
+            Timezone::Zone(zone) => FixedOffset::east_opt(zone.offset_at(time.timestamp())).unwrap_or(Utc.fix()),
+        }
+    }
+    
+    pub fn convert(&self, time: DateTime<Utc>) -> DateTime<FixedOffset> {
+        time.with_timezone(&self.offset_at(time))
+    }
+    
+    /// `2024-05-14 15:30:00 +07:00`
+    pub fn format(&self, time: DateTime<Utc>) -> String {
+        self.convert(time).format(DATETIME_FORMAT).to_string()
+    }
+}
+
+/// Parses a configured zone, falling back to UTC with a warning.
+pub fn load(name: &str) -> Timezone {
+    Timezone::parse(name).unwrap_or_else(|e| {
+        warn!("Invalid timezone '{}': {}. Using UTC.", name, e);
+        Timezone::Utc
+    })
+}
+
+/// An IANA zone read from the system tz database. Offsets past its last
+/// recorded transition follow the POSIX rule in the file's footer.
+#[derive(Debug)]
+pub struct Zone(tz::TimeZone);
+
+impl Zone {
+    fn load(name: &str) -> Result<Self> {
+        if name.split('/').any(|part| part.is_empty() || part == "..") {
+            return Err(anyhow!("invalid zone name '{}'", name));
+        }
+        let path = format!("{}/{}", ZONEINFO, name);
+        let data = fs::read(&path).map_err(|e| anyhow!("unknown zone '{}' ({}: {})", name, path, e))?;
+        Self::from_tzif(&data).map_err(|e| anyhow!("failed to read {}: {}", path, e))
+    }
+    
+    /// Parses the content of a TZif file (RFC 8536).
+    fn from_tzif(data: &[u8]) -> Result<Self> {
+        Ok(Zone(tz::TimeZone::from_tz_data(data)?))
+    }
+    
+    /// Seconds east of UTC at `time`, 0 where the zone has no offset for it.
+    fn offset_at(&self, time: i64) -> i32 {
+        self.0.find_local_time_type(time).map_or(0, |local| local.ut_offset())
+    }
+}
+/// `+07:00`, `+0700`, `+07` or `-03:30` as seconds east of UTC.
+fn parse_offset(text: &str) -> Option<i32> {
+    let (sign, digits) = match text.split_at(1) {
+        ("+", digits) => (1, digits),
+        ("-", digits) => (-1, digits),
+        _ => return None,
+    };
+    let digits: String = digits.chars().filter(|c| *c != ':').collect();
+    if !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let (hours, minutes) = match digits.len() {
+        1 | 2 => (digits.parse::<i32>().ok()?, 0),
+        4 => (digits[..2].parse::<i32>().ok()?, digits[2..].parse::<i32>().ok()?),
+        _ => return None,
+    };
+    if minutes >= 60 {
+        return None;
+    }
+    Some(sign * (hours * 3600 + minutes * 60))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    /// A version 2 TZif file with no transitions, only the POSIX `footer`.
+    fn tzif(footer: &str) -> Vec<u8> {
+        let mut data = Vec::new();
+        for _ in 0..2 {
+            data.extend_from_slice(b"TZif2");
+            data.extend_from_slice(&[0; 15]);
+            // isutcnt, isstdcnt, leapcnt, timecnt, typecnt, charcnt
+            for count in [0u32, 0, 0, 0, 1, 4] {
+                data.extend_from_slice(&count.to_be_bytes());
+            }
+            data.extend_from_slice(&[0, 0, 0, 0, 0, 0]);
+            data.extend_from_slice(b"UTC\0");
+        }
+        data.extend_from_slice(format!("\n{}\n", footer).as_bytes());
+        data
+    }
+    
+    fn offset(footer: &str, time: &str) -> i32 {
+        let zone = Zone::from_tzif(&tzif(footer)).unwrap();
+        zone.offset_at(time.parse::<DateTime<Utc>>().unwrap().timestamp())
+    }
+    
+    #[test]
+    fn fixed_offsets() {
+        for (text, seconds) in [("+07:00", 25200), ("+0530", 19800), ("-03:30", -12600), ("+7", 25200)] {
+            assert_eq!(parse_offset(text), Some(seconds), "{}", text);
+        }
+        for text in ["+07:60", "+123", "+07:3x", "07:00", "+"] {
+            assert_eq!(parse_offset(text), None, "{}", text);
+        }
+        assert!(Timezone::parse("+25:00").is_err());
+    }
+    
+    #[test]
+    fn northern_dst_switches_at_local_time() {
+        let cet = "CET-1CEST,M3.5.0,M10.5.0/3";
+        assert_eq!(offset(cet, "2024-01-15T12:00:00Z"), 3600);
+        assert_eq!(offset(cet, "2024-07-15T12:00:00Z"), 7200);
+        // Last Sunday of March 02:00 CET and of October 03:00 CEST
+        assert_eq!(offset(cet, "2024-03-31T00:59:59Z"), 3600);
+        assert_eq!(offset(cet, "2024-03-31T01:00:00Z"), 7200);
+        assert_eq!(offset(cet, "2024-10-27T00:59:59Z"), 7200);
+        assert_eq!(offset(cet, "2024-10-27T01:00:00Z"), 3600);
+    }
+    
+    #[test]
+    fn southern_dst_spans_the_new_year() {
+        let sydney = "AEST-10AEDT,M10.1.0,M4.1.0/3";
+        assert_eq!(offset(sydney, "2024-01-15T00:00:00Z"), 39600);
+        assert_eq!(offset(sydney, "2024-07-15T00:00:00Z"), 36000);
+        assert_eq!(offset(sydney, "2024-12-31T23:00:00Z"), 39600);
+    }
+    
+    #[test]
+    fn julian_days_skip_february_29_and_ordinal_days_count_it() {
+        // Day 59 from 0 is February 29 in a leap year, J60 is always March 1
+        assert_eq!(offset("XXX0YYY,59/0,300", "2024-02-29T12:00:00Z"), 3600);
+        assert_eq!(offset("XXX0YYY,J60/0,J300", "2024-02-29T12:00:00Z"), 0);
+        assert_eq!(offset("XXX0YYY,J60/0,J300", "2024-03-01T12:00:00Z"), 3600);
+        assert_eq!(offset("XXX0YYY,59/0,300", "2023-03-01T12:00:00Z"), 3600);
+    }
+    
+    #[test]
+    fn quoted_names_and_no_dst() {
+        assert_eq!(offset("<+0330>-3:30", "2024-07-15T12:00:00Z"), 12600);
+        assert_eq!(offset("<-03>3", "2024-07-15T12:00:00Z"), -10800);
+    }
+    
+    #[test]
+    fn malformed_input_is_an_error() {
+        assert!(Zone::from_tzif(b"").is_err());
+        assert!(Zone::from_tzif(b"not a tzif file at all, but long enough to hold a header").is_err());
+        let data = tzif("CET-1CEST,M3.5.0,M10.5.0/3");
+        assert!(Zone::from_tzif(&data[..50]).is_err());
+        for footer in ["CET-1CEST,M13.5.0,M10.5.0", "CET-1CEST,M3.6.0,M10.5.0", "XXX99999999999999", "XXX-2147483648:00"] {
+            assert!(Zone::from_tzif(&tzif(footer)).is_err(), "{}", footer);
+        }
+        assert!(Timezone::parse("../../etc/passwd").is_err());
+        assert!(Timezone::parse("Nowhere/Atlantis").is_err());
+    }
+}
\ No newline at end of file
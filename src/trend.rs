@@ -0,0 +1,363 @@
+use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Duration, Utc};
+use glob::Pattern;
+use std::collections::BTreeMap;
+use crate::alert::Severity;
+use crate::config::{parse_duration, TrendRuleConfig};
+use crate::docker_monitor::ContainerStats;
+use log::warn;
+
+/// Metrics trend rules can watch. Host metrics are percentages, container
+/// memory is in MB.
+pub const TREND_METRICS: [&str; 4] = ["cpu", "memory", "disk", "container_memory"];
+
+/// Dips smaller than this fraction of the previous value still count as
+/// monotonic growth, so allocator noise doesn't hide a leak.
+const MONOTONIC_TOLERANCE: f64 = 0.005;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SeriesPoint {
+    pub at: DateTime<Utc>,
+    pub value: f64,
+}
+
+/// Per-cycle samples of the metrics watched by trend rules, persisted in the
+/// state file so windows of several hours survive restarts.
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+pub struct TrendSamples {
+    #[serde(default)]
+    pub series: BTreeMap<String, Vec<SeriesPoint>>,
+}
+
+impl TrendSamples {
+    pub fn record(&mut self, key: &str, at: DateTime<Utc>, value: f64) {
+        let point = SeriesPoint { at, value };
+        // Only allocate the key for a new series
+        match self.series.get_mut(key) {
+            Some(points) => points.push(point),
+            None => {
+                self.series.insert(key.to_string(), vec![point]);
+            }
+        }
+    }
+    
+    /// Drops samples older than `keep` and series left empty, e.g. containers
+    /// that were removed.
+    pub fn prune(&mut self, now: DateTime<Utc>, keep: Duration) {
+        let cutoff = now - keep;
+        for points in self.series.values_mut() {
+            points.retain(|point| point.at >= cutoff);
+        }
+        self.series.retain(|_, points| !points.is_empty());
+    }
+    
+    /// Samples of a series taken at or after `since`, oldest first.
+    pub fn window(&self, key: &str, since: DateTime<Utc>) -> &[SeriesPoint] {
+        let Some(points) = self.series.get(key) else {
+            return &[];
+        };
+        let start = points.partition_point(|point| point.at < since);
+        &points[start..]
+    }
+}
+
+/// Series key of a container metric.
+pub fn container_key(metric: &str, container: &str) -> String {
+    format!("{}:{}", metric, container)
+}
+
+/// Least-squares slope of the samples in units per hour, `None` with fewer
+/// than two samples or when they were all taken at the same time.
+pub fn slope_per_hour(points: &[SeriesPoint]) -> Option<f64> {
+    if points.len() < 2 {
+        return None;
+    }
+    
+    // One pass over the points without collecting the x values
+    let origin = points[0].at.timestamp_millis();
+    let (mut sum_x, mut sum_y, mut sum_xy, mut sum_xx) = (0.0, 0.0, 0.0, 0.0);
+    for point in points {
+        let x = (point.at.timestamp_millis() - origin) as f64 / 3_600_000.0;
+        sum_x += x;
+        sum_y += point.value;
+        sum_xy += x * point.value;
+        sum_xx += x * x;
+    }
+    let n = points.len() as f64;
+    let covariance = sum_xy - sum_x * sum_y / n;
+    let variance = sum_xx - sum_x * sum_x / n;
+    
+    (variance > 0.0).then(|| covariance / variance)
+}
+
+/// True when every sample is at least the previous one, allowing dips within
+/// `MONOTONIC_TOLERANCE`.
+pub fn is_monotonic(points: &[SeriesPoint]) -> bool {
+    points.windows(2).all(|pair| pair[1].value >= pair[0].value - pair[0].value.abs() * MONOTONIC_TOLERANCE)
+}
+
+/// A series that matched a trend rule in this cycle.
+#[derive(Debug, Clone, Serialize)]
+pub struct TrendBreach {
+    pub rule: String,
+    pub series: String,
+    pub severity: Severity,
+    pub slope_per_hour: f64,
+    pub growth: f64,
+    pub description: String,
+}
+
+/// Validated trend rule.
+pub struct TrendRule {
+    pub config: TrendRuleConfig,
+    pub window: Duration,
+    containers: Vec<Pattern>,
+}
+
+impl TrendRule {
+    pub fn severity(&self) -> Severity {
+        if self.config.severity == "critical" { Severity::Critical } else { Severity::Warning }
+    }
+    
+    pub fn watches_container(&self, name: &str) -> bool {
+        self.config.metric == "container_memory"
+            && (self.containers.is_empty() || self.containers.iter().any(|pattern| pattern.matches(name)))
+    }
+    
+    fn unit(&self) -> &'static str {
+        if self.config.metric == "container_memory" { "MB" } else { "%" }
+    }
+}
+
+/// Parses the configured rules, skipping invalid ones with a warning.
+pub fn load_rules(configs: &[TrendRuleConfig]) -> Vec<TrendRule> {
+    configs.iter()
+        .filter_map(|config| {
+            if !TREND_METRICS.contains(&config.metric.as_str()) {
+                warn!("Trend rule {} has unknown metric '{}' (expected one of {}). Skipping.",
+                      config.name, config.metric, TREND_METRICS.join(", "));
+                return None;
+            }
+            if config.condition != "rate" && config.condition != "monotonic" {
+                warn!("Trend rule {} has unknown condition '{}' (expected rate or monotonic). Skipping.", config.name, config.condition);
+                return None;
+            }
+            let window = match parse_duration(&config.window).map(Duration::from_std) {
+                Ok(Ok(window)) if window > Duration::zero() => window,
+                _ => {
+                    warn!("Trend rule {} has invalid window '{}'. Skipping.", config.name, config.window);
+                    return None;
+                }
+            };
+            let containers = config.containers.iter()
+                .filter_map(|pattern| match Pattern::new(pattern) {
+                    Ok(pattern) => Some(pattern),
+                    Err(e) => {
+                        warn!("Invalid container pattern '{}' in trend rule {}: {}", pattern, config.name, e);
+                        None
+                    }
+                })
+                .collect();
+            
+            Some(TrendRule { config: config.clone(), window, containers })
+        })
+        .collect()
+}
+
+/// Records the memory of the containers `watches` selects, in MB.
+pub fn record_containers(samples: &mut TrendSamples, containers: &[ContainerStats], now: DateTime<Utc>, watches: impl Fn(&str) -> bool) {
+    // One key buffer for all containers instead of a format! per container
+    let mut key = String::from("container_memory:");
+    let prefix = key.len();
+    for container in containers {
+        if watches(&container.name) {
+            let megabytes = container.memory_usage as f64 / (1024.0 * 1024.0);
+            key.truncate(prefix);
+            key.push_str(&container.name);
+            samples.record(&key, now, megabytes);
+        }
+    }
+}
+
+/// True when the samples are enough to judge a window: three or more of them,
+/// spanning at least 80% of it.
+pub fn covers(points: &[SeriesPoint], window: Duration, now: DateTime<Utc>) -> bool {
+    points.len() >= 3 && points.first().is_some_and(|first| (now - first.at) * 5 >= window * 4)
+}
+
+/// Evaluates every rule against its series.
+pub fn evaluate(rules: &[TrendRule], samples: &TrendSamples, now: DateTime<Utc>) -> Vec<TrendBreach> {
+    let mut breaches = Vec::new();
+    
+    for rule in rules {
+        let keys: Vec<&String> = if rule.config.metric == "container_memory" {
+            samples.series.keys()
+                .filter(|key| key.strip_prefix("container_memory:").is_some_and(|name| rule.watches_container(name)))
+                .collect()
+        } else {
+            samples.series.keys().filter(|key| **key == rule.config.metric).collect()
+        };
+        
+        for key in keys {
+            let points = samples.window(key, now - rule.window);
+            if !covers(points, rule.window, now) {
+                continue;
+            }
+            let Some(slope) = slope_per_hour(points) else {
+                continue;
+            };
+            let growth = points[points.len() - 1].value - points[0].value;
+            
+            let breached = match rule.config.condition.as_str() {
+                "monotonic" => growth > 0.0 && growth >= rule.config.threshold && is_monotonic(points),
+                _ => slope >= rule.config.threshold,
+            };
+            if !breached {
+                continue;
+            }
+            
+            let unit = rule.unit();
+            let description = match rule.config.condition.as_str() {
+                "monotonic" => format!(
+                    "{} ({}): {} grew monotonically by {:.1}{} over {} ({:+.2}{}/h)",
+                    rule.config.name, key, rule.config.metric, growth, unit, rule.config.window, slope, unit
+                ),
+                _ => format!(
+                    "{} ({}): {} changing {:+.2}{}/h over {} (threshold {:+.2}{}/h)",
+                    rule.config.name, key, rule.config.metric, slope, unit, rule.config.window, rule.config.threshold, unit
+                ),
+            };
+            breaches.push(TrendBreach {
+                rule: rule.config.name.clone(),
+                series: key.clone(),
+                severity: rule.severity(),
+                slope_per_hour: slope,
+                growth,
+                description,
+            });
+        }
+    }
+    
+    breaches
+}
+
+/// How long samples must be kept to cover the longest of `windows`.
+pub fn retention(windows: impl IntoIterator<Item = Duration>) -> Duration {
+    windows.into_iter()
+        .max()
+        .unwrap_or_else(Duration::zero)
+        + Duration::minutes(10)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    fn start() -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339("2024-05-14T00:00:00Z").unwrap().with_timezone(&Utc)
+    }
+    
+    /// One sample every 10 minutes starting at `start()`.
+    fn series(values: &[f64]) -> Vec<SeriesPoint> {
+        values.iter()
+            .enumerate()
+            .map(|(i, value)| SeriesPoint { at: start() + Duration::minutes(10 * i as i64), value: *value })
+            .collect()
+    }
+    
+    fn rule(condition: &str, threshold: f64, window: &str) -> TrendRule {
+        let config: TrendRuleConfig = serde_json::from_value(serde_json::json!({
+            "name": "disk_fill",
+            "metric": "disk",
+            "condition": condition,
+            "threshold": threshold,
+            "window": window,
+        })).unwrap();
+        load_rules(&[config]).pop().unwrap()
+    }
+    
+    fn samples(values: &[f64]) -> TrendSamples {
+        let mut samples = TrendSamples::default();
+        for point in series(values) {
+            samples.record("disk", point.at, point.value);
+        }
+        samples
+    }
+    
+    #[test]
+    fn slope_of_flat_rising_and_noisy_series() {
+        assert_eq!(slope_per_hour(&series(&[50.0, 50.0, 50.0, 50.0])), Some(0.0));
+        // +1 every 10 minutes
+        let rising = slope_per_hour(&series(&[10.0, 11.0, 12.0, 13.0])).unwrap();
+        assert!((rising - 6.0).abs() < 1e-9, "{rising}");
+        let noisy = slope_per_hour(&series(&[10.0, 12.0, 11.0, 13.0, 12.0, 14.0])).unwrap();
+        assert!(noisy > 3.0 && noisy < 6.0, "{noisy}");
+    }
+    
+    #[test]
+    fn slope_needs_two_distinct_times() {
+        assert_eq!(slope_per_hour(&[]), None);
+        assert_eq!(slope_per_hour(&series(&[10.0])), None);
+        let same_time = [SeriesPoint { at: start(), value: 1.0 }, SeriesPoint { at: start(), value: 2.0 }];
+        assert_eq!(slope_per_hour(&same_time), None);
+    }
+    
+    #[test]
+    fn monotonic_allows_small_dips_only() {
+        assert!(is_monotonic(&series(&[100.0, 100.0, 101.0, 102.0])));
+        assert!(is_monotonic(&series(&[100.0, 99.6, 101.0])));
+        assert!(!is_monotonic(&series(&[100.0, 99.0, 101.0])));
+        assert!(is_monotonic(&series(&[100.0])));
+    }
+    
+    #[test]
+    fn covers_needs_three_samples_over_80_percent_of_the_window() {
+        let window = Duration::hours(1);
+        let points = series(&[1.0, 2.0, 3.0]);
+        // First sample 48 minutes before now is exactly 80% of the window
+        assert!(covers(&points, window, start() + Duration::minutes(48)));
+        assert!(!covers(&points, window, start() + Duration::minutes(47)));
+        assert!(!covers(&points[..2], window, start() + Duration::hours(2)));
+    }
+    
+    #[test]
+    fn evaluate_rate_rule() {
+        let rule = rule("rate", 5.0, "1h");
+        let now = start() + Duration::minutes(60);
+        
+        let breaches = evaluate(std::slice::from_ref(&rule), &samples(&[10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0]), now);
+        assert_eq!(breaches.len(), 1);
+        assert_eq!(breaches[0].series, "disk");
+        assert!((breaches[0].slope_per_hour - 6.0).abs() < 1e-9);
+        assert!((breaches[0].growth - 6.0).abs() < 1e-9);
+        
+        assert!(evaluate(std::slice::from_ref(&rule), &samples(&[50.0; 7]), now).is_empty());
+        // Too short: the only samples are from the last 20 minutes
+        assert!(evaluate(std::slice::from_ref(&rule), &samples(&[10.0, 20.0, 30.0]), start() + Duration::minutes(20)).is_empty());
+    }
+    
+    #[test]
+    fn evaluate_monotonic_rule() {
+        let rule = rule("monotonic", 3.0, "1h");
+        let now = start() + Duration::minutes(60);
+        
+        assert_eq!(evaluate(std::slice::from_ref(&rule), &samples(&[10.0, 11.0, 12.0, 13.0, 14.0, 15.0, 16.0]), now).len(), 1);
+        // Rising overall, but with a real drop in between
+        assert!(evaluate(std::slice::from_ref(&rule), &samples(&[10.0, 12.0, 11.0, 13.0, 12.0, 14.0, 16.0]), now).is_empty());
+        // Monotonic, but below the growth threshold
+        assert!(evaluate(std::slice::from_ref(&rule), &samples(&[10.0, 10.0, 10.5, 11.0, 11.0, 12.0, 12.0]), now).is_empty());
+    }
+    
+    #[test]
+    fn invalid_rules_are_skipped() {
+        let configs: Vec<TrendRuleConfig> = serde_json::from_value(serde_json::json!([
+            { "name": "a", "metric": "load", "window": "1h" },
+            { "name": "b", "metric": "disk", "condition": "spike", "window": "1h" },
+            { "name": "c", "metric": "disk", "window": "0s" },
+            { "name": "d", "metric": "disk", "window": "1h" },
+        ])).unwrap();
+        let rules = load_rules(&configs);
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules[0].config.name, "d");
+    }
+}
@@ -0,0 +1,158 @@
+use std::env;
+use std::sync::OnceLock;
+use crate::config::UnitsConfig;
+use log::warn;
+
+const BINARY_UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+const DECIMAL_UNITS: [&str; 6] = ["B", "kB", "MB", "GB", "TB", "PB"];
+
+/// Languages that write `1,5` rather than `1.5`.
+const COMMA_LANGUAGES: [&str; 30] = [
+    "id", "de", "fr", "es", "it", "pt", "nl", "ru", "uk", "be", "pl", "cs", "sk", "sl", "hr",
+    "sr", "bg", "ro", "hu", "tr", "el", "sv", "da", "nb", "nn", "fi", "et", "lv", "lt", "vi",
+];
+/// Regions that use a point although their language uses a comma.
+const POINT_LOCALES: [&str; 6] = ["de_CH", "de_LI", "it_CH", "es_MX", "es_US", "es_PR"];
+
+/// How sizes are written for people, set once from `units` at startup.
+/// Prometheus metrics and JSON payloads keep raw bytes.
+#[derive(Debug, Clone, Copy)]
+struct UnitFormat {
+    decimal: bool,
+    separator: char,
+}
+
+static FORMAT: OnceLock<UnitFormat> = OnceLock::new();
+
+/// Applies the `units` config. Only the first call has an effect.
+pub fn configure(config: &UnitsConfig) {
+    let decimal = match config.system.as_str() {
+        "binary" => false,
+        "decimal" => true,
+        other => {
+            warn!("Unknown unit system '{}', using binary units", other);
+            false
+        }
+    };
+    let locale = config.locale.clone()
+        .or_else(|| ["LC_ALL", "LC_NUMERIC", "LANG"].iter().find_map(|name| env::var(name).ok().filter(|value| !value.is_empty())))
+        .unwrap_or_default();
+    let _ = FORMAT.set(UnitFormat {
+        decimal,
+        separator: decimal_separator(&locale),
+    });
+}
+
+fn format() -> UnitFormat {
+    FORMAT.get().copied().unwrap_or(UnitFormat { decimal: false, separator: '.' })
+}
+
+/// `en_US.UTF-8` writes a point, `id_ID` a comma. `C` and unknown locales
+/// write a point.
+fn decimal_separator(locale: &str) -> char {
+    let locale = locale.split(['.', '@']).next().unwrap_or_default().replace('-', "_");
+    let language = locale.split('_').next().unwrap_or_default().to_ascii_lowercase();
+    if COMMA_LANGUAGES.contains(&language.as_str()) && !POINT_LOCALES.contains(&locale.as_str()) {
+        ','
+    } else {
+        '.'
+    }
+}
+
+/// A byte count in the largest unit it reaches, e.g. `1.50 GiB`, `512 MiB` or
+/// `23.4 GB` with decimal units. Precision drops as the number grows.
+pub fn bytes(bytes: f64) -> String {
+    format_bytes(bytes, format())
+}
+
+fn format_bytes(bytes: f64, format: UnitFormat) -> String {
+    let (base, units) = if format.decimal { (1000.0, DECIMAL_UNITS) } else { (1024.0, BINARY_UNITS) };
+    
+    let mut value = bytes.abs();
+    let mut unit = 0;
+    while value >= base && unit < units.len() - 1 {
+        value /= base;
+        unit += 1;
+    }
+    
+    // Rounding can carry into the next unit (1023.7 KiB is `1.00 MiB`, not
+    // `1024 KiB`) or into fewer decimals (9.996 MiB is `10.0 MiB`)
+    let rounded = round(value, precision(unit, value));
+    if rounded >= base && unit < units.len() - 1 {
+        value = rounded / base;
+        unit += 1;
+    } else {
+        value = rounded;
+    }
+    
+    let sign = if bytes < 0.0 && value > 0.0 { "-" } else { "" };
+    let number = format!("{:.*}", precision(unit, value), value).replace('.', &format.separator.to_string());
+    format!("{}{} {}", sign, number, units[unit])
+}
+
+fn precision(unit: usize, value: f64) -> usize {
+    if unit == 0 || value >= 100.0 { 0 } else if value >= 10.0 { 1 } else { 2 }
+}
+
+fn round(value: f64, precision: usize) -> f64 {
+    let factor = 10f64.powi(precision as i32);
+    (value * factor).round() / factor
+}
+
+/// A size given in MiB, as rules and libvirt report memory.
+pub fn mebibytes(mebibytes: f64) -> String {
+    bytes(mebibytes * 1024.0 * 1024.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    const BINARY: UnitFormat = UnitFormat { decimal: false, separator: '.' };
+    const DECIMAL: UnitFormat = UnitFormat { decimal: true, separator: '.' };
+    
+    #[test]
+    fn picks_the_largest_unit() {
+        assert_eq!(format_bytes(0.0, BINARY), "0 B");
+        assert_eq!(format_bytes(512.0, BINARY), "512 B");
+        assert_eq!(format_bytes(1536.0, BINARY), "1.50 KiB");
+        assert_eq!(format_bytes(512.0 * 1024.0 * 1024.0, BINARY), "512 MiB");
+        assert_eq!(format_bytes(23.4e9, DECIMAL), "23.4 GB");
+        assert_eq!(format_bytes(-1536.0, BINARY), "-1.50 KiB");
+    }
+    
+    #[test]
+    fn rounding_promotes_to_the_next_unit() {
+        assert_eq!(format_bytes(1023.0, BINARY), "1023 B");
+        assert_eq!(format_bytes(1023.6, BINARY), "1.00 KiB");
+        assert_eq!(format_bytes(1023.4 * 1024.0, BINARY), "1023 KiB");
+        assert_eq!(format_bytes(1023.7 * 1024.0, BINARY), "1.00 MiB");
+        assert_eq!(format_bytes(1024.0 * 1024.0 - 1.0, BINARY), "1.00 MiB");
+        assert_eq!(format_bytes(999_999.0, DECIMAL), "1.00 MB");
+        assert_eq!(format_bytes(999.6e15, DECIMAL), "1000 PB");
+        assert_eq!(format_bytes(-999.7, DECIMAL), "-1.00 kB");
+    }
+    
+    #[test]
+    fn rounding_drops_precision_at_10_and_100() {
+        assert_eq!(format_bytes(9.994 * 1024.0, BINARY), "9.99 KiB");
+        assert_eq!(format_bytes(9.996 * 1024.0, BINARY), "10.0 KiB");
+        assert_eq!(format_bytes(99.94 * 1024.0, BINARY), "99.9 KiB");
+        assert_eq!(format_bytes(99.96 * 1024.0, BINARY), "100 KiB");
+    }
+    
+    #[test]
+    fn decimal_separator_follows_the_locale() {
+        assert_eq!(decimal_separator("id_ID.UTF-8"), ',');
+        assert_eq!(decimal_separator("de-DE"), ',');
+        assert_eq!(decimal_separator("de_CH.UTF-8"), '.');
+        assert_eq!(decimal_separator("en_US.UTF-8"), '.');
+        assert_eq!(decimal_separator("fr_FR@euro"), ',');
+        assert_eq!(decimal_separator("C"), '.');
+        assert_eq!(decimal_separator(""), '.');
+        
+        let format = UnitFormat { decimal: false, separator: decimal_separator("id_ID") };
+        assert_eq!(format_bytes(1023.7 * 1024.0, format), "1,00 MiB");
+        assert_eq!(format_bytes(1536.0 * 1024.0 * 1024.0, format), "1,50 GiB");
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tokio::time::timeout;
+use crate::config::{NetworkConfig, UpsConfig};
+use crate::network;
+use anyhow::{Result, anyhow};
+
+/// Power state of one UPS.
+#[derive(Debug, Clone, Serialize)]
+pub struct UpsStatus {
+    pub name: String,
+    /// Raw status reported by the daemon, e.g. `OL CHRG` or `ONBATT`.
+    pub status: String,
+    pub on_battery: bool,
+    pub charge_percent: Option<f64>,
+    pub runtime_minutes: Option<f64>,
+    pub load_percent: Option<f64>,
+    /// Low battery as flagged by the UPS itself or by the configured limits.
+    pub low_battery: bool,
+}
+
+impl UpsStatus {
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "{}: {} ({})",
+            self.name, if self.on_battery { "on battery" } else { "on line power" }, self.status
+        );
+        if let Some(charge) = self.charge_percent {
+            description.push_str(&format!(", charge {:.0}%", charge));
+        }
+        if let Some(runtime) = self.runtime_minutes {
+            description.push_str(&format!(", {:.0} min runtime left", runtime));
+        }
+        if let Some(load) = self.load_percent {
+            description.push_str(&format!(", load {:.0}%", load));
+        }
+        if self.low_battery {
+            description.push_str(", LOW BATTERY");
+        }
+        description
+    }
+}
+
+/// Queries a UPS through its daemon: NUT `upsd` (`kind: "nut"`, port 3493)
+/// or the apcupsd network information server (`kind: "apcupsd"`, port 3551).
+pub async fn query(config: &UpsConfig, network: &NetworkConfig) -> Result<UpsStatus> {
+    let duration = Duration::from_millis(config.timeout_ms);
+    let status = match config.kind.as_str() {
+        "nut" => timeout(duration, query_nut(config, network)).await,
+        "apcupsd" => timeout(duration, query_apcupsd(config, network)).await,
+        other => return Err(anyhow!("unknown UPS kind '{}' (expected nut or apcupsd)", other)),
+    };
+    let mut status = status.map_err(|_| anyhow!("timed out after {} ms", config.timeout_ms))??;
+    
+    status.low_battery |= status.on_battery
+        && (status.charge_percent.is_some_and(|charge| charge <= config.low_battery_percent)
+            || status.runtime_minutes.is_some_and(|runtime| runtime <= config.low_runtime_minutes));
+    Ok(status)
+}
+
+async fn query_nut(config: &UpsConfig, network: &NetworkConfig) -> Result<UpsStatus> {
+    let stream = network::connect(&config.host, config.port.unwrap_or(3493), network).await?;
+    let mut stream = BufReader::new(stream);
+    stream.get_mut().write_all(format!("LIST VAR {}\n", config.ups).as_bytes()).await?;
+    
+    let mut variables = HashMap::new();
+    let mut line = String::new();
+    loop {
+        line.clear();
+        if stream.read_line(&mut line).await? == 0 {
+            return Err(anyhow!("upsd closed the connection"));
+        }
+        let line = line.trim_end();
+        if let Some(error) = line.strip_prefix("ERR ") {
+            return Err(anyhow!("upsd error for {}: {}", config.ups, error));
+        }
+        if line.starts_with("END LIST VAR") {
+            break;
+        }
+        // VAR <ups> <name> "<value>"
+        let mut parts = line.splitn(4, ' ');
+        if let (Some("VAR"), Some(_), Some(name), Some(value)) = (parts.next(), parts.next(), parts.next(), parts.next()) {
+            variables.insert(name.to_string(), value.trim_matches('"').to_string());
+        }
+    }
+    let _ = stream.get_mut().write_all(b"LOGOUT\n").await;
+    
+    let status = variables.get("ups.status").cloned().unwrap_or_default();
+    let flags: Vec<&str> = status.split_whitespace().collect();
+    let number = |name: &str| variables.get(name).and_then(|value| value.parse::<f64>().ok());
+    Ok(UpsStatus {
+        name: config.name.clone(),
+        on_battery: flags.contains(&"OB"),
+        low_battery: flags.contains(&"LB"),
+        charge_percent: number("battery.charge"),
+        runtime_minutes: number("battery.runtime").map(|seconds| seconds / 60.0),
+        load_percent: number("ups.load"),
+        status,
+    })
+}
+
+async fn query_apcupsd(config: &UpsConfig, network: &NetworkConfig) -> Result<UpsStatus> {
+    let mut stream = network::connect(&config.host, config.port.unwrap_or(3551), network).await?;
+    // NIS messages are prefixed with their length as a 16-bit big-endian number
+    stream.write_all(&[0, 6]).await?;
+    stream.write_all(b"status").await?;
+    
+    let mut variables = HashMap::new();
+    loop {
+        let length = read_length(&mut stream).await?;
+        if length == 0 {
+            break;
+        }
+        let mut record = vec![0; length];
+        stream.read_exact(&mut record).await?;
+        // "BCHARGE  : 100.0 Percent"
+        if let Some((key, value)) = String::from_utf8_lossy(&record).split_once(':') {
+            variables.insert(key.trim().to_string(), value.trim().to_string());
+        }
+    }
+    
+    let status = variables.get("STATUS").cloned().unwrap_or_default();
+    let number = |name: &str| variables.get(name)
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|value| value.parse::<f64>().ok());
+    Ok(UpsStatus {
+        name: config.name.clone(),
+        on_battery: status.contains("ONBATT"),
+        low_battery: status.contains("LOWBATT"),
+        charge_percent: number("BCHARGE"),
+        runtime_minutes: number("TIMELEFT"),
+        load_percent: number("LOADPCT"),
+        status,
+    })
+}
+
+async fn read_length(stream: &mut TcpStream) -> Result<usize> {
+    let mut length = [0; 2];
+    stream.read_exact(&mut length).await?;
+    Ok(u16::from_be_bytes(length) as usize)
+}
\ No newline at end of file
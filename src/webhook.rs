@@ -0,0 +1,165 @@
+use reqwest::{Client, Method};
+use std::fs;
+use std::time::Duration;
+use tera::{Context, Tera};
+use crate::alert::Alert;
+use crate::config::{Config, WebhookConfig};
+use crate::kubernetes;
+use crate::network;
+use anyhow::{Result, anyhow};
+use log::{info, error, warn};
+
+/// Generic HTTP webhook channels. Each hook posts the alert as JSON, or the
+/// body rendered from its Tera template with the full alert in the context.
+pub struct WebhookNotifier {
+    hooks: Vec<WebhookConfig>,
+    templates: Tera,
+    client: Client,
+    hostname: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(config: &Config) -> Self {
+        let mut templates = Tera::default();
+        let mut hooks = Vec::new();
+        
+        for hook in &config.webhooks {
+            match load_template(hook) {
+                Ok(Some(source)) => {
+                    if let Err(e) = templates.add_raw_template(&hook.name, &source) {
+                        error!("Invalid template for webhook {}: {}. Webhook disabled.", hook.name, describe_tera_error(&e));
+                        continue;
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    error!("Failed to load template for webhook {}: {}. Webhook disabled.", hook.name, e);
+                    continue;
+                }
+            }
+            hooks.push(hook.clone());
+        }
+        
+        if !hooks.is_empty() {
+            info!("Webhook notifier initialized with {} channels", hooks.len());
+        }
+        
+        Self {
+            hooks,
+            templates,
+            client: network::http_client(&config.network),
+            hostname: kubernetes::hostname(),
+        }
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.hooks.is_empty()
+    }
+    
+    /// Overrides the hostname shown in notifications, for `render`.
+    pub fn set_hostname(&mut self, hostname: &str) {
+        self.hostname = hostname.to_string();
+    }
+    
+    /// Renders the request body of every hook, whatever rules it is
+    /// subscribed to, named like the channels `send` reports.
+    pub fn preview(&self, alert: &Alert) -> Vec<(String, Result<String>)> {
+        self.hooks.iter()
+            .map(|hook| (format!("webhook:{}", hook.name), self.render(hook, alert)))
+            .collect()
+    }
+    
+    /// Delivers the alert to every hook subscribed to its rule. Returns the
+    /// outcome per channel, named `webhook:<name>`.
+    pub async fn send(&self, alert: &Alert) -> Vec<(String, bool)> {
+        let hooks = self.hooks.iter().filter(|hook| hook.rules.is_empty() || hook.rules.contains(&alert.rule));
+        self.deliver(hooks.collect(), alert).await
+    }
+    
+    /// Delivers the alert to the hooks named in `channels` as `webhook:<name>`,
+    /// whatever rules they are subscribed to. Used by escalation tiers.
+    pub async fn send_to(&self, alert: &Alert, channels: &[String]) -> Vec<(String, bool)> {
+        let hooks = self.hooks.iter().filter(|hook| channels.contains(&format!("webhook:{}", hook.name)));
+        self.deliver(hooks.collect(), alert).await
+    }
+    
+    async fn deliver(&self, hooks: Vec<&WebhookConfig>, alert: &Alert) -> Vec<(String, bool)> {
+        let mut results = Vec::new();
+        
+        for hook in hooks {
+            let sent = match self.post(hook, alert).await {
+                Ok(_) => {
+                    info!("{} alert delivered to webhook {}", alert.rule, hook.name);
+                    true
+                }
+                Err(e) => {
+                    error!("Failed to deliver {} alert to webhook {}: {}", alert.rule, hook.name, e);
+                    false
+                }
+            };
+            results.push((format!("webhook:{}", hook.name), sent));
+        }
+        
+        results
+    }
+    
+    /// Renders the request body of a hook for an alert.
+    fn render(&self, hook: &WebhookConfig, alert: &Alert) -> Result<String> {
+        if !self.templates.get_template_names().any(|name| name == hook.name) {
+            return Ok(serde_json::to_string(alert)?);
+        }
+        
+        let mut context = Context::new();
+        context.insert("alert", alert);
+        context.insert("hostname", &self.hostname);
+        context.insert("channel", &hook.name);
+        self.templates.render(&hook.name, &context)
+            .map_err(|e| anyhow!("template error: {}", describe_tera_error(&e)))
+    }
+    
+    async fn post(&self, hook: &WebhookConfig, alert: &Alert) -> Result<()> {
+        let body = self.render(hook, alert)?;
+        let method = Method::from_bytes(hook.method.to_uppercase().as_bytes())
+            .map_err(|_| anyhow!("invalid method '{}'", hook.method))?;
+        
+        let mut request = self.client.request(method, &hook.url)
+            .timeout(Duration::from_millis(hook.timeout_ms))
+            .header(reqwest::header::CONTENT_TYPE, &hook.content_type)
+            .body(body);
+        for (name, value) in &hook.headers {
+            request = request.header(name, value);
+        }
+        
+        let response = request.send().await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("HTTP {}: {}", status, text.chars().take(200).collect::<String>()));
+        }
+        
+        Ok(())
+    }
+}
+
+fn load_template(hook: &WebhookConfig) -> Result<Option<String>> {
+    match (&hook.template, &hook.template_file) {
+        (Some(_), Some(_)) => {
+            warn!("Webhook {} sets both template and template_file, using template", hook.name);
+            Ok(hook.template.clone())
+        }
+        (Some(template), None) => Ok(Some(template.clone())),
+        (None, Some(path)) => Ok(Some(fs::read_to_string(path)?)),
+        (None, None) => Ok(None),
+    }
+}
+
+/// Tera wraps the useful message in the error source chain.
+pub fn describe_tera_error(error: &tera::Error) -> String {
+    let mut message = error.to_string();
+    let mut source = std::error::Error::source(error);
+    while let Some(cause) = source {
+        message = format!("{}: {}", message, cause);
+        source = cause.source();
+    }
+    message
+}
\ No newline at end of file
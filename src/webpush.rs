@@ -0,0 +1,514 @@
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use openssl::bn::BigNumContext;
+use openssl::derive::Deriver;
+use openssl::ec::{EcGroup, EcKey, EcPoint, PointConversionForm};
+use openssl::ecdsa::EcdsaSig;
+use openssl::hash::MessageDigest;
+use openssl::nid::Nid;
+use openssl::pkey::{PKey, Private};
+use openssl::sha::sha256;
+use openssl::sign::Signer;
+use openssl::symm::{encrypt_aead, Cipher};
+use reqwest::{Client, StatusCode, Url};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Duration;
+use sysinfo::{System, SystemExt};
+use crate::alert::{Alert, Severity};
+use crate::config::{Config, WebPushConfig};
+use crate::incident::IncidentState;
+use crate::network;
+use anyhow::{Result, anyhow};
+use log::{info, error, warn};
+
+/// Push services reject payloads over 4 KB; keep the alert body well below.
+const MAX_BODY_CHARS: usize = 1000;
+const RECORD_SIZE: u32 = 4096;
+
+/// A browser's push subscription, as produced by `PushSubscription.toJSON()`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PushSubscription {
+    pub endpoint: String,
+    pub keys: SubscriptionKeys,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SubscriptionKeys {
+    pub p256dh: String,
+    pub auth: String,
+}
+
+/// Browser push notifications (RFC 8030) with VAPID authentication (RFC 8292)
+/// and aes128gcm payload encryption (RFC 8291). Browsers subscribe through
+/// the HTTP endpoint; subscriptions are kept in `subscriptions_file`.
+pub struct WebPushNotifier {
+    config: WebPushConfig,
+    vapid: Option<EcKey<Private>>,
+    public_key: String,
+    subscriptions: Mutex<Vec<PushSubscription>>,
+    click_url: String,
+    client: Client,
+    hostname: String,
+}
+
+impl WebPushNotifier {
+    pub fn new(config: &Config) -> Self {
+        let webpush = config.webpush.clone();
+        let mut notifier = Self {
+            vapid: None,
+            public_key: String::new(),
+            subscriptions: Mutex::new(Vec::new()),
+            click_url: if config.statuspage.enabled { "/status" } else { "/push" }.to_string(),
+            client: network::http_client(&config.network),
+            hostname: System::new().host_name().unwrap_or_else(|| "unknown".to_string()),
+            config: webpush,
+        };
+        
+        if !notifier.config.enabled {
+            return notifier;
+        }
+        if !config.http.enabled {
+            warn!("Web push needs http.enabled so browsers can subscribe. Web push disabled.");
+            return notifier;
+        }
+        
+        match load_or_generate_key(&notifier.config.vapid_key_file).and_then(|key| Ok((public_key(&key)?, key))) {
+            Ok((public_key, key)) => {
+                notifier.vapid = Some(key);
+                notifier.public_key = public_key;
+            }
+            Err(e) => {
+                error!("Failed to load VAPID key {}: {}. Web push disabled.", notifier.config.vapid_key_file, e);
+                return notifier;
+            }
+        }
+        
+        let subscriptions = notifier.load_subscriptions();
+        info!("Web push notifier initialized with {} subscriptions", subscriptions.len());
+        *notifier.subscriptions.lock().unwrap() = subscriptions;
+        notifier
+    }
+    
+    pub fn is_empty(&self) -> bool {
+        self.vapid.is_none()
+    }
+    
+    /// VAPID public key (uncompressed P-256 point, base64url) browsers pass
+    /// as `applicationServerKey` when subscribing.
+    pub fn public_key(&self) -> Option<&str> {
+        self.vapid.as_ref().map(|_| self.public_key.as_str())
+    }
+    
+    pub fn subscribe(&self, subscription: PushSubscription) -> Result<()> {
+        if self.is_empty() {
+            return Err(anyhow!("web push is disabled"));
+        }
+        
+        let endpoint = Url::parse(&subscription.endpoint)?;
+        if endpoint.scheme() != "https" || endpoint.port().is_some() {
+            return Err(anyhow!("push endpoint must use https on the default port"));
+        }
+        let host = endpoint.host_str().unwrap_or_default();
+        if !self.config.push_services.iter().any(|pattern| {
+            glob::Pattern::new(pattern).map(|p| p.matches(host)).unwrap_or(false)
+        }) {
+            return Err(anyhow!("{} is not a known push service", host));
+        }
+        if URL_SAFE_NO_PAD.decode(subscription.keys.p256dh.trim_end_matches('='))?.len() != 65 {
+            return Err(anyhow!("invalid p256dh key"));
+        }
+        if URL_SAFE_NO_PAD.decode(subscription.keys.auth.trim_end_matches('='))?.len() != 16 {
+            return Err(anyhow!("invalid auth secret"));
+        }
+        
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        subscriptions.retain(|existing| existing.endpoint != subscription.endpoint);
+        if subscriptions.len() >= self.config.max_subscriptions {
+            return Err(anyhow!("subscription limit of {} reached", self.config.max_subscriptions));
+        }
+        info!("New web push subscription on {}", endpoint.host_str().unwrap_or_default());
+        subscriptions.push(subscription);
+        self.save_subscriptions(&subscriptions);
+        Ok(())
+    }
+    
+    pub fn unsubscribe(&self, endpoint: &str) -> bool {
+        let mut subscriptions = self.subscriptions.lock().unwrap();
+        let before = subscriptions.len();
+        subscriptions.retain(|existing| existing.endpoint != endpoint);
+        let removed = subscriptions.len() != before;
+        if removed {
+            self.save_subscriptions(&subscriptions);
+        }
+        removed
+    }
+    
+    /// Pushes the alert to every subscribed browser. Subscriptions the push
+    /// service reports as gone are removed. Returns a single `webpush` outcome,
+    /// failed if any subscription could not be reached.
+    pub async fn send(&self, alert: &Alert) -> Vec<(String, bool)> {
+        if self.is_empty() || (!self.config.rules.is_empty() && !self.config.rules.contains(&alert.rule)) {
+            return Vec::new();
+        }
+        
+        let subscriptions = self.subscriptions.lock().unwrap().clone();
+        if subscriptions.is_empty() {
+            return Vec::new();
+        }
+        
+        let payload = self.payload(alert);
+        let mut failed = 0;
+        let mut expired = Vec::new();
+        for subscription in &subscriptions {
+            match self.push(subscription, &payload, alert).await {
+                Ok(true) => {}
+                Ok(false) => expired.push(subscription.endpoint.clone()),
+                Err(e) => {
+                    error!("Failed to push {} alert to {}: {}", alert.rule, subscription.endpoint, e);
+                    failed += 1;
+                }
+            }
+        }
+        
+        if !expired.is_empty() {
+            info!("Removing {} expired web push subscriptions", expired.len());
+            let mut current = self.subscriptions.lock().unwrap();
+            current.retain(|subscription| !expired.contains(&subscription.endpoint));
+            self.save_subscriptions(&current);
+        }
+        
+        let delivered = subscriptions.len() - failed - expired.len();
+        if delivered > 0 {
+            info!("{} alert pushed to {} browsers", alert.rule, delivered);
+        }
+        vec![("webpush".to_string(), failed == 0)]
+    }
+    
+    /// Overrides the hostname shown in notifications, for `render`.
+    pub fn set_hostname(&mut self, hostname: &str) {
+        self.hostname = hostname.to_string();
+    }
+    
+    /// The notification payload before encryption, as the service worker
+    /// receives it.
+    pub fn payload(&self, alert: &Alert) -> String {
+        let label = if alert.state == IncidentState::Firing { alert.severity.label() } else { alert.state.label() };
+        let mut body = alert.notes.first().cloned().unwrap_or_else(|| format!("Threshold {} over {}", alert.threshold, alert.window));
+        if let Some(value) = alert.value {
+            body = format!("Value {:.2}. {}", value, body);
+        }
+        
+        json!({
+            "title": format!("[{}] {} on {}", label, alert.summary, self.hostname),
+            "body": body.chars().take(MAX_BODY_CHARS).collect::<String>(),
+            "tag": alert.rule,
+            "url": self.click_url,
+            "critical": alert.state == IncidentState::Firing && alert.severity == Severity::Critical,
+        }).to_string()
+    }
+    
+    /// Returns false when the push service reports the subscription as gone.
+    async fn push(&self, subscription: &PushSubscription, payload: &str, alert: &Alert) -> Result<bool> {
+        let ua_public = URL_SAFE_NO_PAD.decode(subscription.keys.p256dh.trim_end_matches('='))?;
+        let auth = URL_SAFE_NO_PAD.decode(subscription.keys.auth.trim_end_matches('='))?;
+        let body = encrypt(&ua_public, &auth, payload.as_bytes())?;
+        let token = self.vapid_token(&subscription.endpoint)?;
+        let urgency = match (alert.state, alert.severity) {
+            (IncidentState::Firing, Severity::Critical) => "high",
+            (IncidentState::Firing, _) => "normal",
+            _ => "low",
+        };
+        
+        let response = self.client.post(&subscription.endpoint)
+            .timeout(Duration::from_millis(self.config.timeout_ms))
+            .header("TTL", self.config.ttl.to_string())
+            .header("Urgency", urgency)
+            .header("Content-Encoding", "aes128gcm")
+            .header(reqwest::header::CONTENT_TYPE, "application/octet-stream")
+            .header(reqwest::header::AUTHORIZATION, format!("vapid t={}, k={}", token, self.public_key))
+            .body(body)
+            .send()
+            .await?;
+        
+        let status = response.status();
+        if status == StatusCode::NOT_FOUND || status == StatusCode::GONE {
+            return Ok(false);
+        }
+        if !status.is_success() {
+            let text = response.text().await.unwrap_or_default();
+            return Err(anyhow!("HTTP {}: {}", status, text.chars().take(200).collect::<String>()));
+        }
+        Ok(true)
+    }
+    
+    /// ES256 JWT for the push service's origin, valid for 12 hours.
+    fn vapid_token(&self, endpoint: &str) -> Result<String> {
+        let key = self.vapid.as_ref().ok_or_else(|| anyhow!("web push is disabled"))?;
+        let audience = Url::parse(endpoint)?.origin().ascii_serialization();
+        let claims = json!({
+            "aud": audience,
+            "exp": chrono::Utc::now().timestamp() + 12 * 3600,
+            "sub": self.config.subject,
+        });
+        
+        let signing_input = format!(
+            "{}.{}",
+            URL_SAFE_NO_PAD.encode(r#"{"typ":"JWT","alg":"ES256"}"#),
+            URL_SAFE_NO_PAD.encode(claims.to_string())
+        );
+        let signature = EcdsaSig::sign(&sha256(signing_input.as_bytes()), key)?;
+        let mut raw = signature.r().to_vec_padded(32)?;
+        raw.extend(signature.s().to_vec_padded(32)?);
+        
+        Ok(format!("{}.{}", signing_input, URL_SAFE_NO_PAD.encode(raw)))
+    }
+    
+    fn load_subscriptions(&self) -> Vec<PushSubscription> {
+        let path = &self.config.subscriptions_file;
+        if !Path::new(path).exists() {
+            return Vec::new();
+        }
+        
+        match fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(serde_json::from_str(&content)?))
+        {
+            Ok(subscriptions) => subscriptions,
+            Err(e) => {
+                error!("Failed to read web push subscriptions {}: {}", path, e);
+                Vec::new()
+            }
+        }
+    }
+    
+    fn save_subscriptions(&self, subscriptions: &[PushSubscription]) {
+        let result = serde_json::to_string_pretty(subscriptions)
+            .map_err(anyhow::Error::from)
+            .and_then(|content| Ok(fs::write(&self.config.subscriptions_file, content)?));
+        
+        if let Err(e) = result {
+            error!("Failed to write web push subscriptions {}: {}", self.config.subscriptions_file, e);
+        }
+    }
+}
+
+/// Loads the VAPID private key, generating a new P-256 key on first start.
+/// Changing the key invalidates every existing subscription.
+fn load_or_generate_key(path: &str) -> Result<EcKey<Private>> {
+    if Path::new(path).exists() {
+        return Ok(EcKey::private_key_from_pem(&fs::read(path)?)?);
+    }
+    
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let key = EcKey::generate(&group)?;
+    let mut file = OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)?;
+    file.write_all(&key.private_key_to_pem()?)?;
+    info!("Generated VAPID key in {}", path);
+    Ok(key)
+}
+
+fn public_key(key: &EcKey<Private>) -> Result<String> {
+    let mut context = BigNumContext::new()?;
+    let bytes = key.public_key().to_bytes(key.group(), PointConversionForm::UNCOMPRESSED, &mut context)?;
+    Ok(URL_SAFE_NO_PAD.encode(bytes))
+}
+
+/// Encrypts a payload for one subscription as a single aes128gcm record (RFC 8291).
+fn encrypt(ua_public: &[u8], auth: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    encrypt_with(EcKey::generate(&group)?, rand::random(), ua_public, auth, plaintext)
+}
+
+/// [`encrypt`] with a fixed ephemeral key and salt, so it can be checked against
+/// the RFC 8291 test vector.
+fn encrypt_with(ephemeral: EcKey<Private>, salt: [u8; 16], ua_public: &[u8], auth: &[u8], plaintext: &[u8]) -> Result<Vec<u8>> {
+    let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1)?;
+    let mut context = BigNumContext::new()?;
+    let as_public = ephemeral.public_key().to_bytes(&group, PointConversionForm::UNCOMPRESSED, &mut context)?;
+    let peer_point = EcPoint::from_bytes(&group, ua_public, &mut context)?;
+    let peer = EcKey::from_public_key(&group, &peer_point)?;
+    
+    let ephemeral = PKey::from_ec_key(ephemeral)?;
+    let peer = PKey::from_ec_key(peer)?;
+    let mut deriver = Deriver::new(&ephemeral)?;
+    deriver.set_peer(&peer)?;
+    let shared_secret = deriver.derive_to_vec()?;
+    
+    let mut key_info = b"WebPush: info\0".to_vec();
+    key_info.extend_from_slice(ua_public);
+    key_info.extend_from_slice(&as_public);
+    let ikm = hkdf_expand(&hmac_sha256(auth, &shared_secret)?, &key_info, 32)?;
+    
+    let prk = hmac_sha256(&salt, &ikm)?;
+    let cek = hkdf_expand(&prk, b"Content-Encoding: aes128gcm\0", 16)?;
+    let nonce = hkdf_expand(&prk, b"Content-Encoding: nonce\0", 12)?;
+    
+    // A single record, terminated by the last-record delimiter
+    let mut record = plaintext.to_vec();
+    record.push(2);
+    let mut tag = [0u8; 16];
+    let ciphertext = encrypt_aead(Cipher::aes_128_gcm(), &cek, Some(&nonce), &[], &record, &mut tag)?;
+    
+    let mut body = salt.to_vec();
+    body.extend_from_slice(&RECORD_SIZE.to_be_bytes());
+    body.push(as_public.len() as u8);
+    body.extend_from_slice(&as_public);
+    body.extend_from_slice(&ciphertext);
+    body.extend_from_slice(&tag);
+    Ok(body)
+}
+
+fn hmac_sha256(key: &[u8], data: &[u8]) -> Result<Vec<u8>> {
+    let key = PKey::hmac(key)?;
+    let mut signer = Signer::new(MessageDigest::sha256(), &key)?;
+    signer.update(data)?;
+    Ok(signer.sign_to_vec()?)
+}
+
+/// HKDF-Expand for outputs up to one SHA-256 block.
+fn hkdf_expand(prk: &[u8], info: &[u8], length: usize) -> Result<Vec<u8>> {
+    let mut input = info.to_vec();
+    input.push(1);
+    let mut output = hmac_sha256(prk, &input)?;
+    output.truncate(length);
+    Ok(output)
+}
+
+/// Page served on `/push` to subscribe the browser to alert notifications.
+pub fn subscribe_page(public_key: &str) -> String {
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+    <meta charset="utf-8">
+    <title>Alert notifications</title>
+    <style>
+        body {{ font-family: sans-serif; max-width: 620px; margin: 2em auto; color: #333; }}
+        button {{ padding: 0.6em 1.2em; margin-right: 0.5em; }}
+        #status {{ margin-top: 1em; color: #777; }}
+    </style>
+</head>
+<body>
+    <h1>Alert notifications</h1>
+    <p>Receive a browser notification whenever the monitor raises or resolves an alert, even when this page is closed.</p>
+    <button id="subscribe">Enable notifications</button>
+    <button id="unsubscribe">Disable</button>
+    <p id="status"></p>
+    <script>
+        const publicKey = "{public_key}";
+        const status = text => document.getElementById("status").textContent = text;
+        
+        function decodeKey(value) {{
+            const base64 = value.replace(/-/g, "+").replace(/_/g, "/") + "=".repeat((4 - value.length % 4) % 4);
+            return Uint8Array.from(atob(base64), c => c.charCodeAt(0));
+        }}
+        
+        async function registration() {{
+            return navigator.serviceWorker.register("/push/sw.js");
+        }}
+        
+        async function subscribe() {{
+            if (await Notification.requestPermission() !== "granted") {{
+                status("Notifications are blocked for this site.");
+                return;
+            }}
+            const reg = await registration();
+            const subscription = await reg.pushManager.subscribe({{ userVisibleOnly: true, applicationServerKey: decodeKey(publicKey) }});
+            const response = await fetch("/push/subscribe", {{
+                method: "POST",
+                headers: {{ "Content-Type": "application/json" }},
+                body: JSON.stringify(subscription),
+            }});
+            status(response.ok ? "Notifications enabled." : "Subscription failed: " + await response.text());
+        }}
+        
+        async function unsubscribe() {{
+            const subscription = await (await registration()).pushManager.getSubscription();
+            if (subscription) {{
+                await fetch("/push/unsubscribe", {{
+                    method: "POST",
+                    headers: {{ "Content-Type": "application/json" }},
+                    body: JSON.stringify({{ endpoint: subscription.endpoint }}),
+                }});
+                await subscription.unsubscribe();
+            }}
+            status("Notifications disabled.");
+        }}
+        
+        if (!window.isSecureContext || !("serviceWorker" in navigator) || !("PushManager" in window)) {{
+            status("Push notifications need a supported browser and HTTPS (or localhost).");
+        }} else {{
+            document.getElementById("subscribe").onclick = () => subscribe().catch(e => status(e.message));
+            document.getElementById("unsubscribe").onclick = () => unsubscribe().catch(e => status(e.message));
+            registration()
+                .then(reg => reg.pushManager.getSubscription())
+                .then(subscription => status(subscription ? "Notifications are enabled in this browser." : "Notifications are not enabled yet."));
+        }}
+    </script>
+</body>
+</html>"#,
+        public_key = public_key,
+    )
+}
+
+/// Service worker that turns pushed alerts into notifications.
+pub const SERVICE_WORKER: &str = r#"self.addEventListener("push", event => {
+    const alert = event.data ? event.data.json() : { title: "Monitoring alert" };
+    event.waitUntil(self.registration.showNotification(alert.title, {
+        body: alert.body,
+        tag: alert.tag,
+        renotify: true,
+        requireInteraction: alert.critical,
+        data: { url: alert.url || "/push" },
+    }));
+});
+
+self.addEventListener("notificationclick", event => {
+    event.notification.close();
+    event.waitUntil(clients.openWindow(event.notification.data.url));
+});
+"#;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use openssl::bn::BigNum;
+    
+    fn decode(value: &str) -> Vec<u8> {
+        URL_SAFE_NO_PAD.decode(value).unwrap()
+    }
+    
+    #[test]
+    fn encrypt_matches_rfc8291_test_vector() {
+        // RFC 8291 Appendix A
+        let group = EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap();
+        let private = BigNum::from_slice(&decode("yfWPiYE-n46HLnH0KqZOF1fJJU3MYrct3AELtAQ-oRw")).unwrap();
+        let mut context = BigNumContext::new().unwrap();
+        let mut public = EcPoint::new(&group).unwrap();
+        public.mul_generator2(&group, &private, &mut context).unwrap();
+        let ephemeral = EcKey::from_private_components(&group, &private, &public).unwrap();
+        assert_eq!(
+            public_key(&ephemeral).unwrap(),
+            "BP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A8"
+        );
+        
+        let salt: [u8; 16] = decode("DGv6ra1nlYgDCS1FRnbzlw").try_into().unwrap();
+        let body = encrypt_with(
+            ephemeral,
+            salt,
+            &decode("BCVxsr7N_eNgVRqvHtD0zTZsEc6-VV-JvLexhqUzORcxaOzi6-AYWXvTBHm4bjyPjs7Vd8pZGH6SRpkNtoIAiw4"),
+            &decode("BTBZMqHH6r4Tts7J_aSIgg"),
+            b"When I grow up, I want to be a watermelon",
+        ).unwrap();
+        
+        assert_eq!(
+            URL_SAFE_NO_PAD.encode(body),
+            "DGv6ra1nlYgDCS1FRnbzlwAAEABBBP4z9KsN6nGRTbVYI_c7VJSPQTBtkgcy27mlmlMoZIIgDll6e3vCYLocInmYWAmS6TlzAC8wEqKK6PBru3jl7A_yl95bQpu6cVPTpK4Mqgkf1CXztLVBSt2Ks3oZwbuwXPXLWyouBWLVWGNWQexSgSxsj_Qulcy4a-fN"
+        );
+    }
+}
@@ -0,0 +1,198 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs;
+use tokio::process::Command;
+use crate::config::{Config, ZfsConfig};
+use crate::units;
+use anyhow::{Result, anyhow};
+
+const ARCSTATS_PATH: &str = "/proc/spl/kstat/zfs/arcstats";
+
+/// State of one pool from `zpool list` and `zpool status`.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZfsPool {
+    pub name: String,
+    /// `ONLINE`, `DEGRADED`, `FAULTED`, `OFFLINE`, `UNAVAIL`, `REMOVED` or `SUSPENDED`.
+    pub health: String,
+    pub size_bytes: u64,
+    pub allocated_bytes: u64,
+    pub capacity_percent: f64,
+    /// Last scrub or resilver, e.g. `scrub repaired 0B in 00:01:02 with 0 errors on ...`.
+    pub scan: Option<String>,
+    /// The `errors:` line, `No known data errors` on a healthy pool.
+    pub errors: Option<String>,
+}
+
+impl ZfsPool {
+    /// Pool unusable or data inaccessible.
+    pub fn is_failed(&self) -> bool {
+        matches!(self.health.as_str(), "FAULTED" | "UNAVAIL" | "SUSPENDED")
+    }
+    
+    /// Last scrub or resilver found errors.
+    pub fn scrub_errors(&self) -> bool {
+        self.scan.as_deref()
+            .and_then(|scan| scan.split(" with ").nth(1))
+            .and_then(|rest| rest.split_whitespace().next())
+            .is_some_and(|count| count != "0")
+    }
+    
+    pub fn data_errors(&self) -> bool {
+        self.errors.as_deref().is_some_and(|errors| errors != "No known data errors")
+    }
+    
+    pub fn describe(&self) -> String {
+        let mut description = format!(
+            "{}: {}, {:.0}% used ({} of {})",
+            self.name, self.health, self.capacity_percent,
+            units::bytes(self.allocated_bytes as f64), units::bytes(self.size_bytes as f64)
+        );
+        if let Some(scan) = &self.scan {
+            description.push_str(&format!(", last scan: {}", scan));
+        }
+        if self.data_errors() {
+            description.push_str(&format!(", errors: {}", self.errors.as_deref().unwrap_or_default()));
+        }
+        description
+    }
+}
+
+/// ARC size and hit rate since the previous check.
+#[derive(Debug, Clone, Serialize)]
+pub struct ArcStats {
+    pub size_bytes: u64,
+    pub max_bytes: u64,
+    pub hit_percent: Option<f64>,
+}
+
+impl ArcStats {
+    pub fn describe(&self) -> String {
+        format!(
+            "ARC {} of {}, hit rate {}",
+            units::bytes(self.size_bytes as f64),
+            units::bytes(self.max_bytes as f64),
+            self.hit_percent.map(|hit| format!("{:.1}%", hit)).unwrap_or_else(|| "n/a".to_string())
+        )
+    }
+}
+
+/// Reads ZFS pool health through the `zpool` CLI and ARC statistics from the
+/// kernel module's kstats.
+pub struct ZfsMonitor {
+    config: ZfsConfig,
+    previous_arc: Option<(u64, u64)>,
+}
+
+impl ZfsMonitor {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            config: config.zfs.clone(),
+            previous_arc: None,
+        }
+    }
+    
+    pub fn is_enabled(&self) -> bool {
+        self.config.enabled
+    }
+    
+    pub async fn pools(&self) -> Result<Vec<ZfsPool>> {
+        let list = zpool(&["list", "-H", "-p", "-o", "name,size,alloc,cap,health"]).await?;
+        let status = zpool(&["status"]).await?;
+        let details = parse_status(&status);
+        
+        Ok(list.lines()
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split('\t').collect();
+                if fields.len() < 5 {
+                    return None;
+                }
+                let (scan, errors) = details.get(fields[0]).cloned().unwrap_or_default();
+                Some(ZfsPool {
+                    name: fields[0].to_string(),
+                    size_bytes: fields[1].parse().unwrap_or(0),
+                    allocated_bytes: fields[2].parse().unwrap_or(0),
+                    capacity_percent: fields[3].trim_end_matches('%').parse().unwrap_or(0.0),
+                    health: fields[4].to_string(),
+                    scan,
+                    errors,
+                })
+            })
+            .collect())
+    }
+    
+    /// Pools that need attention: not `ONLINE`, above the capacity threshold,
+    /// or with scrub or data errors.
+    pub fn unhealthy(&self, pools: &[ZfsPool]) -> Vec<ZfsPool> {
+        pools.iter()
+            .filter(|pool| {
+                pool.health != "ONLINE"
+                    || pool.capacity_percent >= self.config.capacity_threshold
+                    || pool.scrub_errors()
+                    || pool.data_errors()
+            })
+            .cloned()
+            .collect()
+    }
+    
+    /// `None` when the ZFS module is not loaded.
+    pub fn arc(&mut self) -> Option<ArcStats> {
+        let content = fs::read_to_string(ARCSTATS_PATH).ok()?;
+        // "name type data" rows after two header lines
+        let values: HashMap<&str, u64> = content.lines()
+            .skip(2)
+            .filter_map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                Some((*fields.first()?, fields.get(2)?.parse().ok()?))
+            })
+            .collect();
+        
+        let (hits, misses) = (*values.get("hits")?, *values.get("misses")?);
+        let hit_percent = self.previous_arc.and_then(|(last_hits, last_misses)| {
+            let hits = hits.saturating_sub(last_hits);
+            let total = hits + misses.saturating_sub(last_misses);
+            (total > 0).then(|| hits as f64 * 100.0 / total as f64)
+        });
+        self.previous_arc = Some((hits, misses));
+        
+        Some(ArcStats {
+            size_bytes: values.get("size").copied().unwrap_or(0),
+            max_bytes: values.get("c_max").copied().unwrap_or(0),
+            hit_percent,
+        })
+    }
+}
+
+async fn zpool(args: &[&str]) -> Result<String> {
+    let output = Command::new("zpool")
+        .args(args)
+        .output()
+        .await
+        .map_err(|e| anyhow!("failed to run zpool: {}", e))?;
+    if !output.status.success() {
+        return Err(anyhow!("zpool {} failed: {}", args[0], String::from_utf8_lossy(&output.stderr).trim()));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+/// Extracts the `scan:` and `errors:` lines of each pool from `zpool status`.
+fn parse_status(status: &str) -> HashMap<String, (Option<String>, Option<String>)> {
+    let mut pools = HashMap::new();
+    let mut current: Option<String> = None;
+    for line in status.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix("pool:") {
+            current = Some(name.trim().to_string());
+            continue;
+        }
+        let Some(name) = &current else {
+            continue;
+        };
+        let entry: &mut (Option<String>, Option<String>) = pools.entry(name.clone()).or_default();
+        if let Some(scan) = line.strip_prefix("scan:") {
+            entry.0 = Some(scan.trim().to_string());
+        } else if let Some(errors) = line.strip_prefix("errors:") {
+            entry.1 = Some(errors.trim().to_string());
+        }
+    }
+    pools
+}
\ No newline at end of file
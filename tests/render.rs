@@ -0,0 +1,93 @@
+//! Golden tests for the built-in notification templates. Each sample alert in
+//! `tests/fixtures/render/alerts` is rendered through the `render` subcommand
+//! and compared with `tests/fixtures/render/golden`. After an intended
+//! template change, regenerate the golden files with
+//! `UPDATE_GOLDEN=1 cargo test --test render` and review the diff.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const FIXTURES: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/render");
+
+fn render(alert: &Path, channel: &str) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_performance-monitor"))
+        .arg("--config")
+        .arg(Path::new(FIXTURES).join("config.json"))
+        .arg("render")
+        .arg(alert)
+        .args(["--channel", channel, "--hostname", "web-01"])
+        .env("RUST_LOG", "off")
+        .output()
+        .expect("failed to run performance-monitor");
+    assert!(
+        output.status.success(),
+        "render {} --channel {} failed: {}",
+        alert.display(), channel, String::from_utf8_lossy(&output.stderr)
+    );
+    String::from_utf8(output.stdout).expect("render output is not UTF-8")
+}
+
+fn alerts() -> Vec<PathBuf> {
+    let mut alerts: Vec<PathBuf> = fs::read_dir(Path::new(FIXTURES).join("alerts"))
+        .expect("missing alert fixtures")
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|extension| extension == "json"))
+        .collect();
+    alerts.sort();
+    alerts
+}
+
+/// Renders every sample alert for `channel` and compares it with its golden
+/// file, reporting all mismatches at once.
+fn check(channel: &str) {
+    let update = std::env::var_os("UPDATE_GOLDEN").is_some();
+    let mut mismatches = Vec::new();
+    
+    for alert in alerts() {
+        let name = alert.file_stem().unwrap().to_string_lossy().to_string();
+        let golden = Path::new(FIXTURES).join("golden").join(format!("{}.{}.txt", name, channel));
+        let actual = render(&alert, channel);
+        if update {
+            fs::write(&golden, &actual).unwrap();
+            continue;
+        }
+        
+        match fs::read_to_string(&golden) {
+            Ok(expected) if expected == actual => {}
+            Ok(expected) => mismatches.push(format!("{}:\n--- expected\n{}\n--- actual\n{}", golden.display(), expected, actual)),
+            Err(_) => mismatches.push(format!("{}: missing golden file", golden.display())),
+        }
+    }
+    
+    assert!(
+        mismatches.is_empty(),
+        "{} {} renderings differ from the golden files (rerun with UPDATE_GOLDEN=1 if intended):\n{}",
+        mismatches.len(), channel, mismatches.join("\n")
+    );
+}
+
+#[test]
+fn email_templates() {
+    check("email");
+}
+
+#[test]
+fn webhook_payloads() {
+    check("webhook");
+}
+
+#[test]
+fn chat_payloads() {
+    check("chat");
+}
+
+#[test]
+fn apprise_payloads() {
+    check("apprise");
+}
+
+#[test]
+fn webpush_payloads() {
+    check("webpush");
+}
\ No newline at end of file